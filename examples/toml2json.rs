@@ -43,5 +43,6 @@ fn convert(toml: Toml) -> Json {
             Json::Object(table.into_iter().map(|(k, v)| (k, convert(v))).collect())
         }
         Toml::Datetime(dt) => Json::String(dt.to_string()),
+        Toml::RawNumber(n) => Json::String(n.into_string()),
     }
 }
@@ -0,0 +1,321 @@
+//! Typed structures for Cargo manifests.
+//!
+//! Cargo manifests are one of the most common TOML documents to read, and
+//! most programs reading them need the same few sections. The types here
+//! cover the package metadata, dependencies in both their string and table
+//! forms, features and the workspace, and are read like any other
+//! deserializable type. Other sections, such as `[lib]` or `[profile]`, are
+//! ignored.
+//!
+//! ```rust
+//! use toml::cargo::{Dependency, Manifest};
+//!
+//! let manifest: Manifest = toml::from_str(r#"
+//!     [package]
+//!     name = "demo"
+//!     version = "0.1.0"
+//!
+//!     [dependencies]
+//!     serde = "1.0"
+//!     rand = { version = "0.8", default-features = false, features = ["std"] }
+//!     local = { path = "../local" }
+//!
+//!     [features]
+//!     default = ["rand/std"]
+//! "#).unwrap();
+//!
+//! let package = manifest.package.as_ref().unwrap();
+//! assert_eq!(package.name, "demo");
+//! assert_eq!(package.version.as_ref().and_then(|v| v.value()).map(String::as_str), Some("0.1.0"));
+//!
+//! assert_eq!(manifest.dependencies["serde"], Dependency::Simple("1.0".to_string()));
+//! assert_eq!(manifest.dependencies["rand"].version(), Some("0.8"));
+//! assert_eq!(manifest.dependencies["rand"].detail().default_features, Some(false));
+//! assert_eq!(manifest.dependencies["local"].detail().path.as_deref(), Some("../local"));
+//! assert_eq!(manifest.features["default"], ["rand/std"]);
+//! ```
+//!
+//! This module is only available with the `cargo` feature.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+
+use serde::de::{self, Deserializer, MapAccess, Visitor};
+use serde::Deserialize;
+
+use crate::string_or_table::Shorthand;
+use crate::value::Table;
+
+/// The sections of a Cargo manifest, `Cargo.toml`.
+#[derive(Debug, Clone, PartialEq, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct Manifest {
+    /// The `[package]` section, which a virtual workspace manifest lacks.
+    pub package: Option<Package>,
+    /// The `[dependencies]` section.
+    pub dependencies: BTreeMap<String, Dependency>,
+    /// The `[dev-dependencies]` section.
+    #[serde(alias = "dev_dependencies")]
+    pub dev_dependencies: BTreeMap<String, Dependency>,
+    /// The `[build-dependencies]` section.
+    #[serde(alias = "build_dependencies")]
+    pub build_dependencies: BTreeMap<String, Dependency>,
+    /// The dependencies of each `[target.'cfg(...)']` section, keyed by the
+    /// target name or `cfg` expression.
+    pub target: BTreeMap<String, Target>,
+    /// The `[features]` section, mapping each feature to the features and
+    /// dependencies it enables.
+    pub features: BTreeMap<String, Vec<String>>,
+    /// The `[workspace]` section.
+    pub workspace: Option<Workspace>,
+}
+
+impl FromStr for Manifest {
+    type Err = crate::de::Error;
+
+    fn from_str(s: &str) -> Result<Manifest, crate::de::Error> {
+        crate::from_str(s)
+    }
+}
+
+/// The `[package]` section of a manifest.
+#[derive(Debug, Clone, PartialEq, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct Package {
+    /// The name of the package.
+    pub name: String,
+    /// The version of the package, which may be left out since Cargo 1.75.
+    pub version: Option<Inheritable<String>>,
+    /// The Rust edition, such as `2021`.
+    pub edition: Option<Inheritable<String>>,
+    /// The minimum supported Rust version.
+    pub rust_version: Option<Inheritable<String>>,
+    /// The authors of the package.
+    pub authors: Option<Inheritable<Vec<String>>>,
+    /// A short description of the package.
+    pub description: Option<Inheritable<String>>,
+    /// The SPDX license expression of the package.
+    pub license: Option<Inheritable<String>>,
+    /// The URL of the source repository.
+    pub repository: Option<Inheritable<String>>,
+    /// The URL of the home page.
+    pub homepage: Option<Inheritable<String>>,
+    /// The URL of the documentation.
+    pub documentation: Option<Inheritable<String>>,
+    /// The keywords the package is found by.
+    pub keywords: Option<Inheritable<Vec<String>>>,
+    /// The crates.io categories of the package.
+    pub categories: Option<Inheritable<Vec<String>>>,
+    /// The `[package.metadata]` table, which Cargo ignores and leaves to
+    /// other tools.
+    pub metadata: Option<Table>,
+}
+
+/// A package field that is either set or inherited from the workspace with
+/// `field.workspace = true`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Inheritable<T> {
+    /// The value set in the package.
+    Value(T),
+    /// The value is inherited from `[workspace.package]`.
+    Workspace,
+}
+
+impl<T> Inheritable<T> {
+    /// Returns the value set in the package, or `None` if it is inherited.
+    pub fn value(&self) -> Option<&T> {
+        match self {
+            Inheritable::Value(value) => Some(value),
+            Inheritable::Workspace => None,
+        }
+    }
+
+    /// Returns whether the value is inherited from the workspace.
+    pub fn is_inherited(&self) -> bool {
+        matches!(self, Inheritable::Workspace)
+    }
+}
+
+impl<'de, T> Deserialize<'de> for Inheritable<T>
+where
+    T: Deserialize<'de>,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Repr<T> {
+            Inherit { workspace: bool },
+            Value(T),
+        }
+
+        match Repr::deserialize(deserializer)? {
+            Repr::Inherit { workspace: true } => Ok(Inheritable::Workspace),
+            Repr::Inherit { workspace: false } => Err(de::Error::custom(
+                "`workspace` cannot be false, leave the field out instead",
+            )),
+            Repr::Value(value) => Ok(Inheritable::Value(value)),
+        }
+    }
+}
+
+/// A dependency, written either as a version requirement or as a table.
+///
+/// ```toml
+/// serde = "1.0"
+/// rand = { version = "0.8", features = ["small_rng"] }
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub enum Dependency {
+    /// A version requirement on a crates.io package, such as `"1.0"`.
+    Simple(String),
+    /// A dependency written as a table.
+    Detailed(DependencyDetail),
+}
+
+impl Dependency {
+    /// Returns the version requirement, if any.
+    pub fn version(&self) -> Option<&str> {
+        match self {
+            Dependency::Simple(version) => Some(version),
+            Dependency::Detailed(detail) => detail.version.as_deref(),
+        }
+    }
+
+    /// Returns whether the dependency is only enabled through a feature.
+    pub fn is_optional(&self) -> bool {
+        match self {
+            Dependency::Simple(_) => false,
+            Dependency::Detailed(detail) => detail.optional,
+        }
+    }
+
+    /// Returns the dependency in its table form, with a version requirement
+    /// written as a string turned into the `version` entry.
+    pub fn detail(&self) -> DependencyDetail {
+        match self {
+            Dependency::Simple(version) => DependencyDetail::from_shorthand(version.clone()),
+            Dependency::Detailed(detail) => detail.clone(),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Dependency {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct DependencyVisitor;
+
+        impl<'de> Visitor<'de> for DependencyVisitor {
+            type Value = Dependency;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+                formatter.write_str("a version string like \"1.0\" or a dependency table")
+            }
+
+            fn visit_str<E>(self, s: &str) -> Result<Dependency, E>
+            where
+                E: de::Error,
+            {
+                Ok(Dependency::Simple(s.to_string()))
+            }
+
+            fn visit_map<A>(self, map: A) -> Result<Dependency, A::Error>
+            where
+                A: MapAccess<'de>,
+            {
+                let deserializer = de::value::MapAccessDeserializer::new(map);
+                DependencyDetail::deserialize(deserializer).map(Dependency::Detailed)
+            }
+        }
+
+        deserializer.deserialize_any(DependencyVisitor)
+    }
+}
+
+/// The entries of a dependency written as a table.
+#[derive(Debug, Clone, PartialEq, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct DependencyDetail {
+    /// The version requirement.
+    pub version: Option<String>,
+    /// The path of a local package.
+    pub path: Option<String>,
+    /// The URL of a git repository.
+    pub git: Option<String>,
+    /// The git branch to use.
+    pub branch: Option<String>,
+    /// The git tag to use.
+    pub tag: Option<String>,
+    /// The git revision to use.
+    pub rev: Option<String>,
+    /// The registry to use instead of crates.io.
+    pub registry: Option<String>,
+    /// The name of the package, when it differs from the dependency's key.
+    pub package: Option<String>,
+    /// The features of the dependency to enable.
+    pub features: Vec<String>,
+    /// Whether the dependency is only enabled through a feature.
+    pub optional: bool,
+    /// Whether to enable the default features of the dependency, if set.
+    #[serde(alias = "default_features")]
+    pub default_features: Option<bool>,
+    /// Whether the dependency is inherited from `[workspace.dependencies]`.
+    pub workspace: bool,
+}
+
+/// A version requirement is the shorthand of a dependency table, so a
+/// `StringOrTable<DependencyDetail>` reads either form of a dependency.
+impl Shorthand for DependencyDetail {
+    fn from_shorthand(version: String) -> DependencyDetail {
+        DependencyDetail {
+            version: Some(version),
+            ..DependencyDetail::default()
+        }
+    }
+
+    fn shorthand(&self) -> Option<&str> {
+        let version = self.version.as_ref()?;
+        if *self == DependencyDetail::from_shorthand(version.clone()) {
+            Some(version)
+        } else {
+            None
+        }
+    }
+}
+
+/// The dependencies of a `[target.'cfg(...)']` section.
+#[derive(Debug, Clone, PartialEq, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct Target {
+    /// The `dependencies` of the target.
+    pub dependencies: BTreeMap<String, Dependency>,
+    /// The `dev-dependencies` of the target.
+    #[serde(alias = "dev_dependencies")]
+    pub dev_dependencies: BTreeMap<String, Dependency>,
+    /// The `build-dependencies` of the target.
+    #[serde(alias = "build_dependencies")]
+    pub build_dependencies: BTreeMap<String, Dependency>,
+}
+
+/// The `[workspace]` section of a manifest.
+#[derive(Debug, Clone, PartialEq, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct Workspace {
+    /// The paths or globs of the member packages.
+    pub members: Vec<String>,
+    /// The paths left out of the workspace.
+    pub exclude: Vec<String>,
+    /// The members built when no package is selected.
+    pub default_members: Vec<String>,
+    /// The dependency resolver version, such as `"2"`.
+    pub resolver: Option<String>,
+    /// The `[workspace.package]` fields members can inherit.
+    pub package: Option<Table>,
+    /// The `[workspace.dependencies]` members can inherit.
+    pub dependencies: BTreeMap<String, Dependency>,
+}
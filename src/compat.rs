@@ -0,0 +1,575 @@
+//! Conversion between TOML values and configuration written in other
+//! formats.
+//!
+//! [`from_ini`] reads a simple INI file into a table, so that an application
+//! moving its configuration to TOML can keep reading the files its users
+//! already have:
+//!
+//! ```rust
+//! let ini = "\
+//! ; legacy settings
+//! name = demo
+//!
+//! [server]
+//! host = example.com
+//! port = 8080
+//! ";
+//!
+//! let table = toml::compat::from_ini(ini).unwrap();
+//! assert_eq!(table["server"]["port"].as_str(), Some("8080"));
+//!
+//! let table = toml::compat::from_ini_with(ini, toml::compat::IniValues::Sniffed).unwrap();
+//! assert_eq!(table["server"]["port"].as_integer(), Some(8080));
+//! assert_eq!(
+//!     toml::to_string(&table).unwrap(),
+//!     "name = \"demo\"\n\n[server]\nhost = \"example.com\"\nport = 8080\n",
+//! );
+//! ```
+//!
+//! The other way around, [`to_env`] and [`to_properties`] write a table as
+//! flat `KEY=value` lines, for programs configured through environment
+//! variables or Java properties files:
+//!
+//! ```rust
+//! let table: toml::value::Table =
+//!     toml::from_str("name = 'demo app'\n\n[server]\nport = 8080\n").unwrap();
+//!
+//! assert_eq!(
+//!     toml::compat::to_env(&table).unwrap(),
+//!     "NAME=\"demo app\"\nSERVER_PORT=8080\n",
+//! );
+//! assert_eq!(
+//!     toml::compat::to_properties(&table).unwrap(),
+//!     "name=demo app\nserver.port=8080\n",
+//! );
+//! ```
+//!
+//! [`from_env_prefix`] reads the environment variables whose names start
+//! with a prefix into a table, which can then be merged over the
+//! configuration read from files with `Map::merge`.
+//!
+//! [`from_args`] does the same for `key.path=value` arguments given on the
+//! command line, completing a configuration layered from files, the
+//! environment and the command line.
+//!
+//! [`from_ini`]: fn.from_ini.html
+//! [`from_args`]: fn.from_args.html
+//! [`from_env_prefix`]: fn.from_env_prefix.html
+//! [`to_env`]: fn.to_env.html
+//! [`to_properties`]: fn.to_properties.html
+
+use std::collections::HashMap;
+use std::env;
+use std::error;
+use std::fmt::{self, Write};
+
+use crate::datetime::Datetime;
+use crate::de;
+use crate::path::{KeyPath, PathSegment};
+use crate::value::{Table, Value};
+
+/// How the values of an INI file are typed, as passed to [`from_ini_with`].
+///
+/// [`from_ini_with`]: fn.from_ini_with.html
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum IniValues {
+    /// Every value is a string, as INI itself has no types. This is what
+    /// [`from_ini`] does.
+    ///
+    /// [`from_ini`]: fn.from_ini.html
+    #[default]
+    Strings,
+    /// A value that reads as a TOML boolean, integer, float or datetime
+    /// becomes one, such as `true`, `8080`, `0.5` or `1979-05-27`. Anything
+    /// else, and any value written in quotes, is a string. So is a number
+    /// with a leading zero, such as the file mode `0755` or the postal code
+    /// `02134`, whose zeros would be lost as an integer.
+    Sniffed,
+}
+
+/// Reads the INI file `src` into a table, with every value a string.
+///
+/// This is `from_ini_with(src, IniValues::Strings)`.
+pub fn from_ini(src: &str) -> Result<Table, de::Error> {
+    from_ini_with(src, IniValues::Strings)
+}
+
+/// Reads the INI file `src` into a table, typing values as set by `values`.
+///
+/// The file is read line by line:
+///
+/// - Blank lines, and lines starting with `;` or `#`, are skipped. Comments
+///   after a value are not recognized, and are part of the value.
+/// - `[section]` starts a table, in which the following keys are put. Keys
+///   before the first section are put in the root table. A name with dots,
+///   such as `[database.replica]`, starts a nested table.
+/// - `key = value` and `key: value` set a key of the current table. Both are
+///   trimmed, and a value enclosed in matching `"` or `'` quotes has them
+///   removed.
+///
+/// A line that is none of these, a key set twice in the same table, and a
+/// key that is also the name of a section are errors, with the position of
+/// the line.
+pub fn from_ini_with(src: &str, values: IniValues) -> Result<Table, de::Error> {
+    let mut root = Table::new();
+    let mut section: Vec<String> = Vec::new();
+    let mut at = 0;
+    for line in src.split_inclusive('\n') {
+        let start = at;
+        at += line.len();
+        let line = line.trim();
+        if line.is_empty() || line.starts_with(';') || line.starts_with('#') {
+            continue;
+        }
+        let error = |message: String| {
+            let mut e = de::Error::custom(Some(start), message);
+            e.relocate(src, 0);
+            e
+        };
+        let section_error = |section: &[String]| {
+            error(format!(
+                "section `{}` has the name of a key",
+                section.join(".")
+            ))
+        };
+        if let Some(name) = line.strip_prefix('[') {
+            let name = match name.strip_suffix(']') {
+                Some(name) => name,
+                None => return Err(error("expected `]` at the end of a section".to_string())),
+            };
+            section = name.split('.').map(|k| k.trim().to_string()).collect();
+            if section.iter().any(String::is_empty) {
+                return Err(error(format!("invalid section name `{}`", name)));
+            }
+            table_at(&mut root, &section).map_err(|len| section_error(&section[..len]))?;
+            continue;
+        }
+        let split = match line.find(&['=', ':'][..]) {
+            Some(split) => split,
+            None => return Err(error(format!("expected `key = value`, found `{}`", line))),
+        };
+        let key = line[..split].trim();
+        if key.is_empty() {
+            return Err(error("expected a key before the `=`".to_string()));
+        }
+        let value = ini_value(line[split + 1..].trim(), values);
+        let table = table_at(&mut root, &section).map_err(|len| section_error(&section[..len]))?;
+        if table.contains_key(key) {
+            let message = match section.len() {
+                0 => format!("duplicate key `{}`", key),
+                _ => format!("duplicate key `{}` in section `{}`", key, section.join(".")),
+            };
+            return Err(error(message));
+        }
+        table.insert(key.to_string(), value);
+    }
+    Ok(root)
+}
+
+/// Reads the environment variables whose names start with `prefix` into a
+/// table.
+///
+/// This is `from_vars(std::env::vars_os(), prefix)`; variables whose names
+/// or values are not valid Unicode are skipped.
+pub fn from_env_prefix(prefix: &str) -> Result<Table, de::Error> {
+    let vars = env::vars_os()
+        .filter_map(|(name, value)| Some((name.into_string().ok()?, value.into_string().ok()?)));
+    from_vars(vars, prefix)
+}
+
+/// Reads the variables of `vars`, pairs of names and values, whose names
+/// start with `prefix` into a table.
+///
+/// The rest of each name is split on `__` into the keys of the value's path,
+/// which are lower-cased, so with the prefix `MYAPP_` the variable
+/// `MYAPP_LOG__FILE_NAME` sets `log.file_name`. Values are typed as by
+/// `IniValues::Sniffed`: a value that reads as a boolean, integer, float or
+/// datetime becomes one, and a value enclosed in quotes is a string without
+/// them. A number with a leading zero, such as the mask `0022`, stays a
+/// string. In addition a value starting with `[` or `{` that reads as a TOML
+/// array or inline table becomes one, such as `[80, 443]`.
+///
+/// Variables are read in the order of their names. A name with an empty
+/// key, such as `MYAPP_LOG____FILE`, is an error, as are two variables that
+/// set the same value, such as `MYAPP_LOG` and `MYAPP_log`, or a value and a
+/// key inside it, such as `MYAPP_LOG=1` with `MYAPP_LOG__FILE=a`.
+pub fn from_vars<I, K, V>(vars: I, prefix: &str) -> Result<Table, de::Error>
+where
+    I: IntoIterator<Item = (K, V)>,
+    K: AsRef<str>,
+    V: AsRef<str>,
+{
+    let mut vars = vars
+        .into_iter()
+        .filter(|(name, _)| name.as_ref().starts_with(prefix))
+        .collect::<Vec<_>>();
+    vars.sort_by(|a, b| a.0.as_ref().cmp(b.0.as_ref()));
+    let mut root = Table::new();
+    for (name, value) in &vars {
+        let name = name.as_ref();
+        let path = name[prefix.len()..]
+            .split("__")
+            .map(str::to_lowercase)
+            .collect::<Vec<_>>();
+        if path.iter().any(String::is_empty) {
+            return Err(de::Error::custom(
+                None,
+                format!("environment variable `{}` has an empty key", name),
+            ));
+        }
+        let (key, parents) = path.split_last().unwrap();
+        let conflict = |path: &[String]| {
+            de::Error::custom(
+                None,
+                format!(
+                    "environment variable `{}` sets `{}`, which another variable set",
+                    name,
+                    path.join(".")
+                ),
+            )
+        };
+        let table = table_at(&mut root, parents).map_err(|len| conflict(&parents[..len]))?;
+        if table.contains_key(key) {
+            return Err(conflict(&path));
+        }
+        table.insert(key.clone(), env_value(value.as_ref()));
+    }
+    Ok(root)
+}
+
+/// Error returned by [`from_args`] for an argument that cannot be read.
+///
+/// [`from_args`]: fn.from_args.html
+#[derive(Debug, Clone)]
+pub struct ArgError {
+    index: usize,
+    argument: String,
+    error: de::Error,
+}
+
+impl ArgError {
+    /// Returns the position of the argument in the list given.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Returns the argument.
+    pub fn argument(&self) -> &str {
+        &self.argument
+    }
+
+    /// Returns the error found in the argument, whose line and column are
+    /// those within the argument.
+    pub fn error(&self) -> &de::Error {
+        &self.error
+    }
+}
+
+impl fmt::Display for ArgError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid argument `{}`: {}", self.argument, self.error)
+    }
+}
+
+impl error::Error for ArgError {}
+
+/// Reads `key.path=value` arguments, such as those given to a program with
+/// a `--set` option, into a table.
+///
+/// Each argument is a TOML key/value pair as read by
+/// `Deserializer::parse_key_value`: the key may be dotted and quoted, and
+/// the value is written in TOML syntax, so strings need quotes, as in
+/// `log.level="debug"` or `ports=[80, 443]`. A later argument setting the
+/// same key replaces the value of an earlier one.
+///
+/// Every argument is read before returning, so that all the errors are
+/// reported at once: an argument that is not a key/value pair, and one that
+/// sets a key inside a value that is not a table, such as `a.b=1` after
+/// `a=1`.
+///
+/// ```rust
+/// let args = ["server.port=8080", "server.host='example.com'", "tags=['a']"];
+/// let table = toml::compat::from_args(&args).unwrap();
+/// assert_eq!(table["server"]["port"].as_integer(), Some(8080));
+///
+/// let errors = toml::compat::from_args(&["port=8080", "host=example.com", "debug"]).unwrap_err();
+/// assert_eq!(errors.len(), 2);
+/// assert_eq!(errors[0].index(), 1);
+/// ```
+pub fn from_args<I>(args: I) -> Result<Table, Vec<ArgError>>
+where
+    I: IntoIterator,
+    I::Item: AsRef<str>,
+{
+    let mut root = Table::new();
+    let mut errors = Vec::new();
+    for (index, argument) in args.into_iter().enumerate() {
+        let argument = argument.as_ref();
+        let result = de::Deserializer::new(argument)
+            .parse_key_value::<Value>()
+            .and_then(|(path, value)| set_arg(&mut root, &path, value));
+        if let Err(error) = result {
+            errors.push(ArgError {
+                index,
+                argument: argument.to_string(),
+                error,
+            });
+        }
+    }
+    if errors.is_empty() {
+        Ok(root)
+    } else {
+        Err(errors)
+    }
+}
+
+fn set_arg(root: &mut Table, path: &KeyPath, value: Value) -> Result<(), de::Error> {
+    let keys = path
+        .segments()
+        .iter()
+        .map(|segment| match segment {
+            PathSegment::Key(key) => key.clone(),
+            PathSegment::Index(_) => unreachable!(),
+        })
+        .collect::<Vec<_>>();
+    let (key, parents) = keys.split_last().unwrap();
+    let table = table_at(root, parents).map_err(|len| {
+        de::Error::custom(
+            None,
+            format!(
+                "`{}` is set to a value that is not a table",
+                KeyPath::from(path.segments()[..len].to_vec())
+            ),
+        )
+    })?;
+    table.insert(key.clone(), value);
+    Ok(())
+}
+
+fn env_value(text: &str) -> Value {
+    let text = text.trim();
+    if text.starts_with('[') || text.starts_with('{') {
+        let parsed = format!("value = {}", text).parse::<Value>();
+        if let Ok(Value::Table(mut table)) = parsed {
+            return table.remove("value").unwrap();
+        }
+    }
+    ini_value(text, IniValues::Sniffed)
+}
+
+/// Returns the table at `path`, creating the tables leading to it, or the
+/// length of the prefix of `path` that holds a value that is not a table.
+fn table_at<'a>(root: &'a mut Table, path: &[String]) -> Result<&'a mut Table, usize> {
+    let mut table = root;
+    for (i, key) in path.iter().enumerate() {
+        let value = table
+            .entry(key.clone())
+            .or_insert_with(|| Value::Table(Table::new()));
+        table = match value {
+            Value::Table(table) => table,
+            _ => return Err(i + 1),
+        };
+    }
+    Ok(table)
+}
+
+fn ini_value(text: &str, values: IniValues) -> Value {
+    for quote in &['"', '\''] {
+        if text.len() >= 2 && text.starts_with(*quote) && text.ends_with(*quote) {
+            return Value::from(&text[1..text.len() - 1]);
+        }
+    }
+    if values == IniValues::Sniffed {
+        if text.eq_ignore_ascii_case("true") {
+            return Value::Boolean(true);
+        }
+        if text.eq_ignore_ascii_case("false") {
+            return Value::Boolean(false);
+        }
+        let numeric = !text.is_empty()
+            && text
+                .chars()
+                .all(|c| c.is_ascii_digit() || matches!(c, '+' | '-' | '.' | 'e' | 'E'));
+        if numeric && !has_leading_zero(text) {
+            if let Ok(i) = text.parse::<i64>() {
+                return Value::Integer(i);
+            }
+            if let Ok(f) = text.parse::<f64>() {
+                return Value::Float(f);
+            }
+        }
+        if let Ok(datetime) = text.parse::<Datetime>() {
+            return Value::Datetime(datetime);
+        }
+    }
+    Value::from(text)
+}
+
+/// Returns whether the number `text` starts with a zero followed by another
+/// digit, which TOML numbers cannot.
+fn has_leading_zero(text: &str) -> bool {
+    let digits = text.trim_start_matches(&['+', '-'][..]).as_bytes();
+    digits.len() > 1 && digits[0] == b'0' && digits[1].is_ascii_digit()
+}
+
+/// Writes `table` as lines of environment variable assignments, in the
+/// format read by shells and dotenv files.
+///
+/// This is `to_env_with(table, "", "_")`, so `server.port = 8080` is written
+/// as `SERVER_PORT=8080`.
+pub fn to_env(table: &Table) -> Result<String, de::Error> {
+    to_env_with(table, "", "_")
+}
+
+/// Writes `table` as lines of environment variable assignments, with each
+/// name starting with `prefix` and the keys of its path joined by
+/// `separator`.
+///
+/// Every value of `Table::flatten` becomes one line:
+///
+/// - The name is the path in upper case, with each character that is not an
+///   ASCII letter or digit replaced by `_`, and array indices written as
+///   numbers: `servers[0].max-conns` is `SERVERS_0_MAX_CONNS`.
+/// - Strings are written as they are if they consist of letters, digits and
+///   `_-.,:/@%+`, and are otherwise enclosed in `"`, with `\`, `"`, `$` and
+///   `` ` `` escaped by a backslash and newlines, carriage returns and tabs
+///   written as `\n`, `\r` and `\t`. Other values are written in TOML
+///   syntax, such as `8080`, `true` or `"[1, 2]"`, under the same rule.
+///
+/// An error is returned if two paths are written as the same name, such as
+/// `a.b` and `a-b`.
+pub fn to_env_with(table: &Table, prefix: &str, separator: &str) -> Result<String, de::Error> {
+    let mut ret = String::new();
+    let mut names = HashMap::new();
+    for (path, value) in table.flatten() {
+        let mut name = prefix.to_string();
+        for (i, segment) in path.segments().iter().enumerate() {
+            if i > 0 {
+                name.push_str(separator);
+            }
+            match segment {
+                PathSegment::Key(key) => name.extend(key.chars().map(|c| match c {
+                    'a'..='z' | 'A'..='Z' | '0'..='9' => c.to_ascii_uppercase(),
+                    _ => '_',
+                })),
+                PathSegment::Index(index) => write!(name, "{}", index).unwrap(),
+            }
+        }
+        check_collision(&mut names, &name, &path)?;
+        ret.push_str(&name);
+        ret.push('=');
+        let text = flat_text(&value);
+        let plain = !text.is_empty()
+            && text
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || "_-.,:/@%+".contains(c));
+        if plain {
+            ret.push_str(&text);
+        } else {
+            ret.push('"');
+            for c in text.chars() {
+                match c {
+                    '\\' | '"' | '$' | '`' => {
+                        ret.push('\\');
+                        ret.push(c);
+                    }
+                    '\n' => ret.push_str("\\n"),
+                    '\r' => ret.push_str("\\r"),
+                    '\t' => ret.push_str("\\t"),
+                    _ => ret.push(c),
+                }
+            }
+            ret.push('"');
+        }
+        ret.push('\n');
+    }
+    Ok(ret)
+}
+
+/// Writes `table` as lines of a Java properties file.
+///
+/// Every value of `Table::flatten` becomes one `key=value` line, whose key
+/// is the path with its keys and array indices joined by `.`, such as
+/// `servers.0.host`. Strings are written as they are and other values in
+/// TOML syntax. Both are escaped as `java.util.Properties` reads them: `\`
+/// is doubled, tabs, newlines, carriage returns and form feeds are written
+/// as `\t`, `\n`, `\r` and `\f`, and characters outside printable ASCII
+/// as `\uXXXX`. Spaces, `=`, `:`, `#` and `!` are escaped in keys, as is a
+/// leading space in values.
+///
+/// An error is returned if two paths are written as the same key, such as
+/// `a.b` and `"a.b"`.
+pub fn to_properties(table: &Table) -> Result<String, de::Error> {
+    let mut ret = String::new();
+    let mut keys = HashMap::new();
+    for (path, value) in table.flatten() {
+        let mut key = String::new();
+        for (i, segment) in path.segments().iter().enumerate() {
+            if i > 0 {
+                key.push('.');
+            }
+            match segment {
+                PathSegment::Key(k) => escape_property(&mut key, k, true),
+                PathSegment::Index(index) => write!(key, "{}", index).unwrap(),
+            }
+        }
+        check_collision(&mut keys, &key, &path)?;
+        ret.push_str(&key);
+        ret.push('=');
+        let text = flat_text(&value);
+        if text.starts_with(' ') {
+            ret.push('\\');
+        }
+        escape_property(&mut ret, &text, false);
+        ret.push('\n');
+    }
+    Ok(ret)
+}
+
+/// Returns the text of a flattened value: a string as it is and anything
+/// else in TOML syntax.
+fn flat_text(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        // The only tables `Table::flatten` returns are empty.
+        Value::Table(_) => "{}".to_string(),
+        _ => value.to_string(),
+    }
+}
+
+fn check_collision(
+    seen: &mut HashMap<String, KeyPath>,
+    name: &str,
+    path: &KeyPath,
+) -> Result<(), de::Error> {
+    match seen.insert(name.to_string(), path.clone()) {
+        Some(other) => Err(de::Error::custom(
+            None,
+            format!("`{}` and `{}` are both written as `{}`", other, path, name),
+        )),
+        None => Ok(()),
+    }
+}
+
+fn escape_property(out: &mut String, s: &str, key: bool) {
+    for c in s.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            '\t' => out.push_str("\\t"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\u{c}' => out.push_str("\\f"),
+            ' ' | '=' | ':' | '#' | '!' if key => {
+                out.push('\\');
+                out.push(c);
+            }
+            ' '..='~' => out.push(c),
+            _ => {
+                let mut units = [0; 2];
+                for unit in c.encode_utf16(&mut units) {
+                    write!(out, "\\u{:04X}", unit).unwrap();
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,224 @@
+//! Adapters declaring how a timestamp is held in TOML.
+//!
+//! A timestamp can be kept as a number of seconds in the program yet be
+//! written as a TOML datetime, or be kept as a datetime yet be written as a
+//! string for tools that do not understand TOML datetimes. Wrapping a field
+//! in one of these types states which, without glue code in every project:
+//!
+//! - [`EpochSeconds`] holds seconds since 1970-01-01T00:00:00Z and is
+//!   written as an offset date-time in UTC. It reads both offset date-times
+//!   and integers.
+//! - [`Iso8601String`] holds a datetime and is written as a string in the
+//!   RFC 3339 profile of ISO 8601. It reads both strings and datetimes.
+//!
+//! ```rust
+//! use serde_derive::{Deserialize, Serialize};
+//! use toml::conv::{EpochSeconds, Iso8601String};
+//!
+//! #[derive(Deserialize, Serialize)]
+//! struct Build {
+//!     started: EpochSeconds,
+//!     finished: Iso8601String,
+//! }
+//!
+//! let build: Build = toml::from_str(
+//!     "started = 1979-05-27T00:30:00-07:00\nfinished = 1979-05-27T07:32:00Z\n",
+//! )
+//! .unwrap();
+//! assert_eq!(build.started.0, 296_638_200);
+//!
+//! assert_eq!(
+//!     toml::to_string(&build).unwrap(),
+//!     "started = 1979-05-27T07:30:00Z\nfinished = \"1979-05-27T07:32:00Z\"\n",
+//! );
+//! ```
+//!
+//! [`EpochSeconds`]: struct.EpochSeconds.html
+//! [`Iso8601String`]: struct.Iso8601String.html
+
+use std::convert::TryFrom;
+use std::fmt;
+use std::ops::{Deref, DerefMut};
+
+use serde::de::{self, Deserialize, Deserializer, MapAccess, Visitor};
+use serde::ser::{self, Serialize, Serializer};
+
+use crate::datetime::Datetime;
+
+/// A number of seconds since 1970-01-01T00:00:00Z, written as an offset
+/// date-time in UTC.
+///
+/// It is read from an offset date-time, whose fractional seconds are
+/// dropped, or from an integer. Local date-times, dates and times are
+/// rejected, as they do not denote an instant.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct EpochSeconds(pub i64);
+
+impl EpochSeconds {
+    /// Consumes the wrapper, returning the number of seconds.
+    pub fn into_inner(self) -> i64 {
+        self.0
+    }
+}
+
+impl From<i64> for EpochSeconds {
+    fn from(seconds: i64) -> EpochSeconds {
+        EpochSeconds(seconds)
+    }
+}
+
+impl Deref for EpochSeconds {
+    type Target = i64;
+
+    fn deref(&self) -> &i64 {
+        &self.0
+    }
+}
+
+impl DerefMut for EpochSeconds {
+    fn deref_mut(&mut self) -> &mut i64 {
+        &mut self.0
+    }
+}
+
+impl<'de> Deserialize<'de> for EpochSeconds {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct EpochSecondsVisitor;
+
+        impl<'de> Visitor<'de> for EpochSecondsVisitor {
+            type Value = EpochSeconds;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+                formatter.write_str("an offset date-time or an integer")
+            }
+
+            fn visit_i64<E>(self, seconds: i64) -> Result<EpochSeconds, E>
+            where
+                E: de::Error,
+            {
+                Ok(EpochSeconds(seconds))
+            }
+
+            fn visit_u64<E>(self, seconds: u64) -> Result<EpochSeconds, E>
+            where
+                E: de::Error,
+            {
+                match i64::try_from(seconds) {
+                    Ok(seconds) => Ok(EpochSeconds(seconds)),
+                    Err(_) => Err(de::Error::invalid_value(
+                        de::Unexpected::Unsigned(seconds),
+                        &self,
+                    )),
+                }
+            }
+
+            fn visit_map<A>(self, map: A) -> Result<EpochSeconds, A::Error>
+            where
+                A: MapAccess<'de>,
+            {
+                let datetime = Datetime::deserialize(de::value::MapAccessDeserializer::new(map))?;
+                match datetime.unix_timestamp() {
+                    Some(seconds) => Ok(EpochSeconds(seconds)),
+                    None => Err(de::Error::custom(format!(
+                        "datetime `{}` has no offset, so does not denote an instant",
+                        datetime
+                    ))),
+                }
+            }
+        }
+
+        deserializer.deserialize_any(EpochSecondsVisitor)
+    }
+}
+
+impl Serialize for EpochSeconds {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match Datetime::from_unix_timestamp(self.0, 0) {
+            Some(datetime) => datetime.serialize(serializer),
+            None => Err(ser::Error::custom(
+                "time is out of range for a TOML datetime",
+            )),
+        }
+    }
+}
+
+/// A datetime written as a string, such as `"1979-05-27T07:32:00Z"`.
+///
+/// It is read from a string holding a TOML datetime or from a datetime.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Iso8601String(pub Datetime);
+
+impl Iso8601String {
+    /// Consumes the wrapper, returning the datetime.
+    pub fn into_inner(self) -> Datetime {
+        self.0
+    }
+}
+
+impl From<Datetime> for Iso8601String {
+    fn from(datetime: Datetime) -> Iso8601String {
+        Iso8601String(datetime)
+    }
+}
+
+impl Deref for Iso8601String {
+    type Target = Datetime;
+
+    fn deref(&self) -> &Datetime {
+        &self.0
+    }
+}
+
+impl DerefMut for Iso8601String {
+    fn deref_mut(&mut self) -> &mut Datetime {
+        &mut self.0
+    }
+}
+
+impl<'de> Deserialize<'de> for Iso8601String {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct Iso8601StringVisitor;
+
+        impl<'de> Visitor<'de> for Iso8601StringVisitor {
+            type Value = Iso8601String;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+                formatter.write_str("a string holding a datetime or a datetime")
+            }
+
+            fn visit_str<E>(self, s: &str) -> Result<Iso8601String, E>
+            where
+                E: de::Error,
+            {
+                s.parse().map(Iso8601String).map_err(de::Error::custom)
+            }
+
+            fn visit_map<A>(self, map: A) -> Result<Iso8601String, A::Error>
+            where
+                A: MapAccess<'de>,
+            {
+                Datetime::deserialize(de::value::MapAccessDeserializer::new(map)).map(Iso8601String)
+            }
+        }
+
+        deserializer.deserialize_any(Iso8601StringVisitor)
+    }
+}
+
+impl Serialize for Iso8601String {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_str(&self.0)
+    }
+}
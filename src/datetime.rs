@@ -1,6 +1,9 @@
+use std::cmp::Ordering;
+use std::convert::TryFrom;
 use std::error;
 use std::fmt;
 use std::str::{self, FromStr};
+use std::time::{SystemTime, UNIX_EPOCH};
 
 use serde::{de, ser};
 
@@ -76,7 +79,7 @@ use serde::{de, ser};
 /// [Local Date-Time]: https://toml.io/en/v1.0.0#local-date-time
 /// [Local Date]: https://toml.io/en/v1.0.0#local-date
 /// [Local Time]: https://toml.io/en/v1.0.0#local-time
-#[derive(PartialEq, Clone)]
+#[derive(PartialEq, Eq, Hash, Clone)]
 pub struct Datetime {
     /// Optional date.
     /// Required for: *Offset Date-Time*, *Local Date-Time*, *Local Date*.
@@ -94,7 +97,7 @@ pub struct Datetime {
 /// Error returned from parsing a `Datetime` in the `FromStr` implementation.
 #[derive(Debug, Clone)]
 pub struct DatetimeParseError {
-    _private: (),
+    reason: Option<String>,
 }
 
 // Currently serde itself doesn't have a datetime type, so we map our `Datetime`
@@ -120,7 +123,7 @@ pub const NAME: &str = "$__toml_private_Datetime";
 /// > ```
 ///
 /// [Local Date]: https://toml.io/en/v1.0.0#local-date
-#[derive(PartialEq, Clone)]
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Clone)]
 pub struct Date {
     /// Year: four digits
     pub year: u16,
@@ -150,7 +153,7 @@ pub struct Date {
 /// > must be truncated, not rounded.
 ///
 /// [Local Time]: https://toml.io/en/v1.0.0#local-time
-#[derive(PartialEq, Clone)]
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Clone)]
 pub struct Time {
     /// Hour: 0 to 23
     pub hour: u8,
@@ -164,7 +167,7 @@ pub struct Time {
 
 /// A parsed TOML time offset
 ///
-#[derive(PartialEq, Clone)]
+#[derive(PartialEq, Eq, Hash, Clone)]
 pub enum Offset {
     /// > A suffix which, when applied to a time, denotes a UTC offset of 00:00;
     /// > often spoken "Zulu" from the ICAO phonetic alphabet representation of
@@ -175,8 +178,14 @@ pub enum Offset {
 
     /// Offset between local time and UTC
     Custom {
-        /// Hours: -12 to +12
-        hours: i8,
+        /// Whether the offset is behind UTC, written with a `-`
+        ///
+        /// This is kept apart from `hours` so that offsets of less than an
+        /// hour, such as `-00:30`, keep their sign.
+        negative: bool,
+
+        /// Hours: 0 to 23
+        hours: u8,
 
         /// Minutes: 0 to 59
         minutes: u8,
@@ -224,11 +233,247 @@ impl fmt::Display for Time {
     }
 }
 
+impl fmt::Debug for Offset {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
 impl fmt::Display for Offset {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match *self {
             Offset::Z => write!(f, "Z"),
-            Offset::Custom { hours, minutes } => write!(f, "{:+03}:{:02}", hours, minutes),
+            Offset::Custom {
+                negative,
+                hours,
+                minutes,
+            } => {
+                let sign = if negative { '-' } else { '+' };
+                write!(f, "{}{:02}:{:02}", sign, hours, minutes)
+            }
+        }
+    }
+}
+
+impl Datetime {
+    /// Returns the number of whole seconds between this datetime and
+    /// 1970-01-01T00:00:00Z.
+    ///
+    /// Only an offset date-time denotes an instant, so `None` is returned for
+    /// local date-times, dates and times. Fractional seconds are left in
+    /// `time.nanosecond`.
+    ///
+    /// ```rust
+    /// let dt: toml::value::Datetime = "1979-05-27T00:32:00-07:00".parse().unwrap();
+    /// assert_eq!(dt.unix_timestamp(), Some(296_638_320));
+    /// ```
+    pub fn unix_timestamp(&self) -> Option<i64> {
+        match (&self.date, &self.time, &self.offset) {
+            (Some(date), Some(time), Some(offset)) => {
+                let seconds = date.days_since_epoch() * 86_400
+                    + i64::from(time.hour) * 3_600
+                    + i64::from(time.minute) * 60
+                    + i64::from(time.second);
+                Some(seconds - i64::from(offset.minutes()) * 60)
+            }
+            _ => None,
+        }
+    }
+
+    /// Creates an offset date-time in UTC from a number of seconds since
+    /// 1970-01-01T00:00:00Z and a number of nanoseconds within that second.
+    ///
+    /// Returns `None` if the result does not fit in a four-digit year or if
+    /// `nanosecond` is not below one billion.
+    ///
+    /// ```rust
+    /// use toml::value::Datetime;
+    ///
+    /// let dt = Datetime::from_unix_timestamp(296_638_320, 0).unwrap();
+    /// assert_eq!(dt.to_string(), "1979-05-27T07:32:00Z");
+    /// ```
+    pub fn from_unix_timestamp(seconds: i64, nanosecond: u32) -> Option<Datetime> {
+        if nanosecond >= 1_000_000_000 {
+            return None;
+        }
+        let days = seconds.div_euclid(86_400);
+        let rem = seconds.rem_euclid(86_400);
+        Some(Datetime {
+            date: Some(Date::from_days_since_epoch(days)?),
+            time: Some(Time {
+                hour: (rem / 3_600) as u8,
+                minute: (rem / 60 % 60) as u8,
+                second: (rem % 60) as u8,
+                nanosecond,
+            }),
+            offset: Some(Offset::Z),
+        })
+    }
+
+    /// Returns the same instant as this offset date-time expressed in UTC,
+    /// with a `Z` offset.
+    ///
+    /// Returns `None` for local date-times, dates and times, which cannot be
+    /// converted without knowing their time zone.
+    ///
+    /// ```rust
+    /// let dt: toml::value::Datetime = "1979-05-27T00:32:00.5-07:00".parse().unwrap();
+    /// assert_eq!(dt.to_utc().unwrap().to_string(), "1979-05-27T07:32:00.5Z");
+    /// ```
+    pub fn to_utc(&self) -> Option<Datetime> {
+        let nanosecond = self.time.as_ref()?.nanosecond;
+        Datetime::from_unix_timestamp(self.unix_timestamp()?, nanosecond)
+    }
+
+    /// Returns the same instant as this offset date-time expressed with the
+    /// given offset.
+    ///
+    /// Returns `None` for local date-times, dates and times, and if the
+    /// result does not fit in a four-digit year.
+    ///
+    /// ```rust
+    /// use toml::value::{Datetime, Offset};
+    ///
+    /// let dt: Datetime = "1979-05-27T07:32:00Z".parse().unwrap();
+    /// let offset = Offset::Custom { negative: true, hours: 7, minutes: 0 };
+    /// assert_eq!(dt.to_offset(offset).unwrap().to_string(), "1979-05-27T00:32:00-07:00");
+    /// ```
+    pub fn to_offset(&self, offset: Offset) -> Option<Datetime> {
+        let nanosecond = self.time.as_ref()?.nanosecond;
+        let local = self.unix_timestamp()? + i64::from(offset.minutes()) * 60;
+        let mut dt = Datetime::from_unix_timestamp(local, nanosecond)?;
+        dt.offset = Some(offset);
+        Some(dt)
+    }
+
+    /// Creates an offset date-time in UTC for the instant a `SystemTime`
+    /// denotes.
+    ///
+    /// Returns `None` if the result does not fit in a four-digit year.
+    ///
+    /// ```rust
+    /// use std::time::{Duration, UNIX_EPOCH};
+    /// use toml::value::Datetime;
+    ///
+    /// let time = UNIX_EPOCH + Duration::from_millis(296_638_320_250);
+    /// let dt = Datetime::from_system_time(time).unwrap();
+    /// assert_eq!(dt.to_string(), "1979-05-27T07:32:00.25Z");
+    /// ```
+    pub fn from_system_time(time: SystemTime) -> Option<Datetime> {
+        let (seconds, nanosecond) = match time.duration_since(UNIX_EPOCH) {
+            Ok(after) => (i64::try_from(after.as_secs()).ok()?, after.subsec_nanos()),
+            Err(before) => {
+                let before = before.duration();
+                let seconds = i64::try_from(before.as_secs()).ok()?;
+                match before.subsec_nanos() {
+                    0 => (-seconds, 0),
+                    n => (-seconds - 1, 1_000_000_000 - n),
+                }
+            }
+        };
+        Datetime::from_unix_timestamp(seconds, nanosecond)
+    }
+}
+
+/// Datetimes are ordered chronologically when they are of the same kind.
+///
+/// Offset date-times are compared by the instant they denote, so
+/// `1979-05-27T00:32:00-07:00` is after `1979-05-27T07:00:00Z`. Two different
+/// representations of the same instant are ordered by their offsets, to stay
+/// consistent with `==`, which compares the fields. Local date-times, dates
+/// and times are each compared with their own kind only; comparing datetimes
+/// of different kinds gives `None`.
+impl PartialOrd for Datetime {
+    fn partial_cmp(&self, other: &Datetime) -> Option<Ordering> {
+        match (&self.offset, &other.offset) {
+            (Some(a), Some(b)) => {
+                let instant = |dt: &Datetime| {
+                    let nanosecond = dt.time.as_ref().map(|t| t.nanosecond);
+                    (dt.unix_timestamp(), nanosecond)
+                };
+                let offset = |o: &Offset| (o.minutes(), matches!(*o, Offset::Custom { .. }));
+                Some(
+                    instant(self)
+                        .cmp(&instant(other))
+                        .then_with(|| offset(a).cmp(&offset(b))),
+                )
+            }
+            (None, None)
+                if self.date.is_some() == other.date.is_some()
+                    && self.time.is_some() == other.time.is_some() =>
+            {
+                Some((&self.date, &self.time).cmp(&(&other.date, &other.time)))
+            }
+            _ => None,
+        }
+    }
+}
+
+impl Datetime {
+    /// Orders datetimes of every kind: offset date-times first, then local
+    /// date-times, local dates and local times, each kind as by
+    /// `partial_cmp`.
+    pub(crate) fn total_cmp(&self, other: &Datetime) -> Ordering {
+        let rank = |dt: &Datetime| (dt.offset.is_none(), dt.date.is_none(), dt.time.is_none());
+        rank(self)
+            .cmp(&rank(other))
+            .then_with(|| self.partial_cmp(other).unwrap_or(Ordering::Equal))
+    }
+}
+
+impl Date {
+    /// Days from 1970-01-01 to this date in the proleptic Gregorian calendar.
+    fn days_since_epoch(&self) -> i64 {
+        // http://howardhinnant.github.io/date_algorithms.html#days_from_civil
+        let month = i64::from(self.month);
+        let year = i64::from(self.year) - if month <= 2 { 1 } else { 0 };
+        let era = year.div_euclid(400);
+        let yoe = year - era * 400;
+        let mp = (month + 9) % 12;
+        let doy = (153 * mp + 2) / 5 + i64::from(self.day) - 1;
+        let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+        era * 146_097 + doe - 719_468
+    }
+
+    fn from_days_since_epoch(days: i64) -> Option<Date> {
+        // http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+        let z = days + 719_468;
+        let era = z.div_euclid(146_097);
+        let doe = z - era * 146_097;
+        let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let day = doy - (153 * mp + 2) / 5 + 1;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 };
+        let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+        if !(0..=9999).contains(&year) {
+            return None;
+        }
+        Some(Date {
+            year: year as u16,
+            month: month as u8,
+            day: day as u8,
+        })
+    }
+}
+
+impl Offset {
+    /// Returns this offset from UTC in minutes, negative west of Greenwich.
+    pub fn minutes(&self) -> i16 {
+        match *self {
+            Offset::Z => 0,
+            Offset::Custom {
+                negative,
+                hours,
+                minutes,
+            } => {
+                let minutes = i16::from(hours) * 60 + i16::from(minutes);
+                if negative {
+                    -minutes
+                } else {
+                    minutes
+                }
+            }
         }
     }
 }
@@ -244,7 +489,7 @@ impl FromStr for Datetime {
         // 0000-00-00
         // 00:00:00.00
         if date.len() < 3 {
-            return Err(DatetimeParseError { _private: () });
+            return Err(DatetimeParseError::new());
         }
         let mut offset_allowed = true;
         let mut chars = date.chars();
@@ -261,7 +506,7 @@ impl FromStr for Datetime {
 
             match chars.next() {
                 Some('-') => {}
-                _ => return Err(DatetimeParseError { _private: () }),
+                _ => return Err(DatetimeParseError::new()),
             }
 
             let m1 = digit(&mut chars)?;
@@ -269,7 +514,7 @@ impl FromStr for Datetime {
 
             match chars.next() {
                 Some('-') => {}
-                _ => return Err(DatetimeParseError { _private: () }),
+                _ => return Err(DatetimeParseError::new()),
             }
 
             let d1 = digit(&mut chars)?;
@@ -282,10 +527,16 @@ impl FromStr for Datetime {
             };
 
             if date.month < 1 || date.month > 12 {
-                return Err(DatetimeParseError { _private: () });
+                return Err(DatetimeParseError::out_of_range(format!(
+                    "month {:02} is out of range",
+                    date.month
+                )));
             }
-            if date.day < 1 || date.day > 31 {
-                return Err(DatetimeParseError { _private: () });
+            if date.day < 1 || date.day > days_in_month(date.year, date.month) {
+                return Err(DatetimeParseError::out_of_range(format!(
+                    "day {:02} is out of range for {:04}-{:02}",
+                    date.day, date.year, date.month
+                )));
             }
 
             Some(date)
@@ -307,13 +558,13 @@ impl FromStr for Datetime {
             let h2 = digit(&mut chars)?;
             match chars.next() {
                 Some(':') => {}
-                _ => return Err(DatetimeParseError { _private: () }),
+                _ => return Err(DatetimeParseError::new()),
             }
             let m1 = digit(&mut chars)?;
             let m2 = digit(&mut chars)?;
             match chars.next() {
                 Some(':') => {}
-                _ => return Err(DatetimeParseError { _private: () }),
+                _ => return Err(DatetimeParseError::new()),
             }
             let s1 = digit(&mut chars)?;
             let s2 = digit(&mut chars)?;
@@ -339,7 +590,7 @@ impl FromStr for Datetime {
                     }
                 }
                 if end == 0 {
-                    return Err(DatetimeParseError { _private: () });
+                    return Err(DatetimeParseError::new());
                 }
                 chars = whole[end..].chars();
             }
@@ -351,17 +602,26 @@ impl FromStr for Datetime {
                 nanosecond,
             };
 
-            if time.hour > 24 {
-                return Err(DatetimeParseError { _private: () });
+            if time.hour > 23 {
+                return Err(DatetimeParseError::out_of_range(format!(
+                    "hour {:02} is out of range",
+                    time.hour
+                )));
             }
             if time.minute > 59 {
-                return Err(DatetimeParseError { _private: () });
+                return Err(DatetimeParseError::out_of_range(format!(
+                    "minute {:02} is out of range",
+                    time.minute
+                )));
             }
             if time.second > 59 {
-                return Err(DatetimeParseError { _private: () });
+                return Err(DatetimeParseError::out_of_range(format!(
+                    "second {:02} is out of range",
+                    time.second
+                )));
             }
             if time.nanosecond > 999_999_999 {
-                return Err(DatetimeParseError { _private: () });
+                return Err(DatetimeParseError::new());
             }
 
             Some(time)
@@ -379,24 +639,38 @@ impl FromStr for Datetime {
             } else if next.is_none() {
                 None
             } else {
-                let sign = match next {
-                    Some('+') => 1,
-                    Some('-') => -1,
-                    _ => return Err(DatetimeParseError { _private: () }),
+                let negative = match next {
+                    Some('+') => false,
+                    Some('-') => true,
+                    _ => return Err(DatetimeParseError::new()),
                 };
                 chars.next();
-                let h1 = digit(&mut chars)? as i8;
-                let h2 = digit(&mut chars)? as i8;
+                let h1 = digit(&mut chars)?;
+                let h2 = digit(&mut chars)?;
                 match chars.next() {
                     Some(':') => {}
-                    _ => return Err(DatetimeParseError { _private: () }),
+                    _ => return Err(DatetimeParseError::new()),
                 }
                 let m1 = digit(&mut chars)?;
                 let m2 = digit(&mut chars)?;
 
+                let (hours, minutes) = (h1 * 10 + h2, m1 * 10 + m2);
+                if hours > 23 {
+                    return Err(DatetimeParseError::out_of_range(format!(
+                        "offset hour {:02} is out of range",
+                        hours
+                    )));
+                }
+                if minutes > 59 {
+                    return Err(DatetimeParseError::out_of_range(format!(
+                        "offset minute {:02} is out of range",
+                        minutes
+                    )));
+                }
                 Some(Offset::Custom {
-                    hours: sign * (h1 * 10 + h2),
-                    minutes: m1 * 10 + m2,
+                    negative,
+                    hours,
+                    minutes,
                 })
             }
         } else {
@@ -406,7 +680,7 @@ impl FromStr for Datetime {
         // Return an error if we didn't hit eof, otherwise return our parsed
         // date
         if chars.next().is_some() {
-            return Err(DatetimeParseError { _private: () });
+            return Err(DatetimeParseError::new());
         }
 
         Ok(Datetime {
@@ -420,7 +694,7 @@ impl FromStr for Datetime {
 fn digit(chars: &mut str::Chars<'_>) -> Result<u8, DatetimeParseError> {
     match chars.next() {
         Some(c) if '0' <= c && c <= '9' => Ok(c as u8 - b'0'),
-        _ => Err(DatetimeParseError { _private: () }),
+        _ => Err(DatetimeParseError::new()),
     }
 }
 
@@ -535,9 +809,44 @@ impl<'de> de::Deserialize<'de> for DatetimeFromString {
     }
 }
 
+fn days_in_month(year: u16, month: u8) -> u8 {
+    match month {
+        2 => match (year % 4, year % 100, year % 400) {
+            (_, _, 0) => 29,
+            (_, 0, _) => 28,
+            (0, _, _) => 29,
+            _ => 28,
+        },
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+impl DatetimeParseError {
+    fn new() -> DatetimeParseError {
+        DatetimeParseError { reason: None }
+    }
+
+    fn out_of_range(reason: String) -> DatetimeParseError {
+        DatetimeParseError {
+            reason: Some(reason),
+        }
+    }
+
+    /// Describes the component that was out of range, if the input was a
+    /// well-formed datetime with an impossible value such as `2024-02-30`.
+    pub(crate) fn out_of_range_reason(&self) -> Option<&str> {
+        self.reason.as_deref()
+    }
+}
+
 impl fmt::Display for DatetimeParseError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        "failed to parse datetime".fmt(f)
+        "failed to parse datetime".fmt(f)?;
+        if let Some(ref reason) = self.reason {
+            write!(f, ": {}", reason)?;
+        }
+        Ok(())
     }
 }
 
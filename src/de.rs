@@ -5,13 +5,16 @@
 //! provided at the top of the crate.
 
 use std::borrow::Cow;
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap, HashSet};
 use std::error;
 use std::f64;
 use std::fmt;
+use std::hash::{BuildHasher, Hash};
 use std::iter;
 use std::marker::PhantomData;
-use std::str;
+use std::str::{self, FromStr};
+use std::sync::atomic::{self, AtomicBool};
+use std::sync::{Arc, Mutex};
 use std::vec;
 
 use serde::de;
@@ -19,11 +22,14 @@ use serde::de::value::BorrowedStrDeserializer;
 use serde::de::IntoDeserializer;
 
 use crate::datetime;
+use crate::fix::Fix;
+use crate::path::{KeyPath, PathSegment};
 use crate::spanned;
 use crate::tokens::{Error as TokenError, Span, Token, Tokenizer};
+use crate::value;
 
 /// Type Alias for a TOML Table pair
-type TablePair<'a> = ((Span, Cow<'a, str>), Value<'a>);
+pub(crate) type TablePair<'a> = ((Span, Cow<'a, str>), Value<'a>);
 
 /// Deserializes a byte slice into a type.
 ///
@@ -82,6 +88,100 @@ where
     Ok(ret)
 }
 
+/// What the `unique_set` visitor expects, by which `Error` recognizes the
+/// duplicates it reports.
+const UNIQUE_SET_EXPECTED: &str = "an array without duplicate elements";
+
+/// Convenience function to deserialize a set from an array, rejecting
+/// duplicate elements.
+///
+/// Deserializing an array into a `HashSet` or `BTreeSet` normally drops
+/// repeated elements silently, but a value listed twice in a configuration
+/// file is usually a mistake. This helper reports the first duplicate, with
+/// its value and its index in the array, instead:
+///
+/// ```rust
+/// # use serde_derive::Deserialize;
+/// # use std::collections::BTreeSet;
+/// #[derive(Debug, Deserialize)]
+/// struct Config {
+///     #[serde(deserialize_with = "toml::de::unique_set")]
+///     features: BTreeSet<String>,
+/// }
+///
+/// # fn main() {
+/// let err = toml::from_str::<Config>("features = ['a', 'b', 'a']").unwrap_err();
+/// assert!(err.to_string().contains("duplicate set element `\"a\"` at index 2"));
+/// # }
+/// ```
+pub fn unique_set<'de, D, S, T>(deserializer: D) -> Result<S, D::Error>
+where
+    D: de::Deserializer<'de>,
+    S: Set<T>,
+    T: de::Deserialize<'de> + fmt::Debug,
+{
+    struct UniqueSetVisitor<S, T>(PhantomData<(S, T)>);
+
+    impl<'de, S, T> de::Visitor<'de> for UniqueSetVisitor<S, T>
+    where
+        S: Set<T>,
+        T: de::Deserialize<'de> + fmt::Debug,
+    {
+        type Value = S;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+            formatter.write_str(UNIQUE_SET_EXPECTED)
+        }
+
+        fn visit_seq<A>(self, mut seq: A) -> Result<S, A::Error>
+        where
+            A: de::SeqAccess<'de>,
+        {
+            let mut set = S::default();
+            let mut index = 0;
+            while let Some(value) = seq.next_element::<T>()? {
+                let description = format!("{:?}", value);
+                if !set.add(value) {
+                    let duplicate =
+                        format!("duplicate set element `{}` at index {}", description, index);
+                    return Err(de::Error::invalid_value(
+                        de::Unexpected::Other(&duplicate),
+                        &self,
+                    ));
+                }
+                index += 1;
+            }
+            Ok(set)
+        }
+    }
+
+    deserializer.deserialize_seq(UniqueSetVisitor(PhantomData))
+}
+
+/// Sets that [`unique_set`] can deserialize into.
+///
+/// [`unique_set`]: fn.unique_set.html
+pub trait Set<T>: Default {
+    /// Adds `value` to the set, returning `false` if it was already present.
+    fn add(&mut self, value: T) -> bool;
+}
+
+impl<T, H> Set<T> for HashSet<T, H>
+where
+    T: Eq + Hash,
+    H: BuildHasher + Default,
+{
+    fn add(&mut self, value: T) -> bool {
+        self.insert(value)
+    }
+}
+
+impl<T: Ord> Set<T> for BTreeSet<T> {
+    fn add(&mut self, value: T) -> bool {
+        self.insert(value)
+    }
+}
+
 /// Errors that can occur when deserializing a type.
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct Error {
@@ -138,6 +238,16 @@ enum ErrorKind {
     /// A date or datetime was invalid
     DateInvalid,
 
+    /// A datetime was well-formed but one of its components was out of range
+    InvalidDateTime(String),
+
+    /// A decimal number had leading zeros; holds the number without them
+    LeadingZeros(String),
+
+    /// A float had no digits after its decimal point; holds the corrected
+    /// float
+    TrailingDecimalPoint(String),
+
     /// Wanted one sort of token, but found another.
     Wanted {
         /// Expected token type
@@ -152,6 +262,19 @@ enum ErrorKind {
     /// A previously defined table was redefined as an array.
     RedefineAsArray,
 
+    /// A table was defined again, or defined in another way, after an
+    /// earlier definition.
+    TableRedefined {
+        /// The path of the table.
+        name: String,
+        /// How the table was first defined.
+        first: Definition,
+        /// The line of the first definition, starting at 1.
+        first_line: usize,
+        /// How the table was defined again.
+        second: Definition,
+    },
+
     /// An empty table key was found.
     EmptyTableKey,
 
@@ -162,6 +285,10 @@ enum ErrorKind {
     /// type.
     Custom,
 
+    /// A value of the wrong type was found when deserializing a particular
+    /// type.
+    InvalidType,
+
     /// A tuple with a certain number of elements was expected but something
     /// else was found.
     ExpectedTuple(usize),
@@ -194,16 +321,466 @@ enum ErrorKind {
     /// Unquoted string was found when quoted one was expected
     UnquotedString,
 
+    /// An unquoted key contained a character only allowed in quoted keys.
+    InvalidBareKey {
+        /// The key, up to the next separator.
+        key: String,
+        /// The invalid character.
+        ch: char,
+    },
+
+    /// Parsing was cancelled.
+    Cancelled,
+
+    /// Two keys of a table differ only in case.
+    KeyCaseConflict {
+        /// The later key.
+        key: String,
+        /// The earlier key.
+        first: String,
+        /// The line of the earlier key, starting at 1.
+        first_line: usize,
+    },
+
+    /// A string names none of the variants of an enum under the
+    /// [`VariantMatching`] in use.
+    ///
+    /// [`VariantMatching`]: enum.VariantMatching.html
+    UnknownVariant {
+        /// The string read.
+        variant: String,
+        /// The names that would be accepted, one for each variant.
+        expected: Vec<String>,
+    },
+
+    /// An element of an array read with [`unique_set`] repeats an earlier
+    /// one; the message names the element and its index.
+    ///
+    /// [`unique_set`]: fn.unique_set.html
+    DuplicateSetElement(String),
+
     #[doc(hidden)]
     __Nonexhaustive,
 }
 
+/// How a table came to be defined, for describing redefinitions.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum Definition {
+    /// A `[table]` header, or a header of a table below it.
+    Table,
+    /// An `[[array]]` header.
+    ArrayOfTables,
+    /// Dotted keys such as `table.key = 1`.
+    DottedKeys,
+    /// An inline table.
+    InlineTable,
+}
+
+/// A table defined so far, while checking for redefinitions.
+#[derive(Clone, Copy)]
+struct Defined {
+    how: Definition,
+    /// Where the definition starts.
+    at: usize,
+    /// Whether the table was only defined by a header of a table below it,
+    /// so that a header of its own may still follow.
+    implicit: bool,
+    /// The index of the table the definition is in.
+    table: usize,
+}
+
+type Definitions<'a> = HashMap<Vec<Cow<'a, str>>, Defined>;
+
+/// The keys defined so far, with where they start, by the path of their
+/// table and their lowercase form.
+type KeyCases<'a> = HashMap<(Vec<Cow<'a, str>>, String), (String, usize)>;
+
+/// How many bytes of input are parsed between calls to a progress callback.
+const PROGRESS_INTERVAL: usize = 64 * 1024;
+
+/// A callback told how many bytes of how many have been parsed, which
+/// returns `false` to cancel parsing.
+///
+/// The callbacks are only called through `&mut Deserializer`; the mutex keeps
+/// the deserializer `Sync` without asking the same of them.
+type Progress<'a> = Mutex<Box<dyn FnMut(usize, usize) -> bool + Send + 'a>>;
+
+/// A callback given each value read, other than arrays and tables, which
+/// returns the value to read instead.
+type Rewrite<'a> = Mutex<Box<dyn FnMut(&KeyPath, crate::Value) -> crate::Value + Send + 'a>>;
+
 /// Deserialization implementation for TOML.
 pub struct Deserializer<'a> {
     require_newline_after_table: bool,
     allow_duplciate_after_longer_table: bool,
+    lenient_numbers: bool,
+    allow_trailing_commas: bool,
+    allow_bare_boolean_keys: bool,
+    allow_unquoted_strings: bool,
+    merge_reopened_tables: bool,
+    duplicate_keys: DuplicateKeys,
+    case_conflicts: CaseConflicts,
+    progress: Option<(Progress<'a>, usize)>,
+    cancel: Option<Arc<AtomicBool>>,
+    rewrite: Option<Rewrite<'a>>,
     input: &'a str,
     tokens: Tokenizer<'a>,
+    ctx: Arc<Context>,
+}
+
+/// State shared between the `Deserializer` and the value deserializers it
+/// hands out, which outlive any borrow of it.
+#[derive(Default)]
+struct Context {
+    lenient_coercion: AtomicBool,
+    legacy_booleans: AtomicBool,
+    big_integer_strings: AtomicBool,
+    raw_numbers: AtomicBool,
+    warnings: Mutex<Vec<(usize, String)>>,
+    fixes: Mutex<Vec<Fix>>,
+    usage: Mutex<Option<UsageTracker>>,
+    variant_matching: Mutex<VariantMatching>,
+}
+
+/// The keys being deserialized and those already accounted for, kept while
+/// key usage is tracked.
+#[derive(Default)]
+struct UsageTracker {
+    frames: Vec<UsageFrame>,
+    usage: KeyUsage,
+    /// The paths in each list of `usage`, to keep them free of duplicates.
+    consumed: HashSet<KeyPath>,
+    ignored: HashSet<KeyPath>,
+    defaulted: HashSet<KeyPath>,
+}
+
+/// A value being deserialized: the root, a value of a table key or an
+/// element of an array.
+struct UsageFrame {
+    segment: Option<PathSegment>,
+    ignored: bool,
+    /// The fields of the struct the value is read as, if any.
+    fields: &'static [&'static str],
+    /// The keys of the value deserialized so far.
+    seen: Vec<String>,
+}
+
+impl UsageFrame {
+    fn new(segment: Option<PathSegment>) -> UsageFrame {
+        UsageFrame {
+            segment,
+            ignored: false,
+            fields: &[],
+            seen: Vec::new(),
+        }
+    }
+}
+
+impl UsageTracker {
+    fn path(&self) -> KeyPath {
+        let segments = self.frames.iter().filter_map(|f| f.segment.clone());
+        KeyPath::from(segments.collect::<Vec<_>>())
+    }
+
+    /// Finishes the innermost value, recording its key as consumed or
+    /// ignored and the fields it did not have as defaulted. Nothing is
+    /// recorded inside a value that is ignored as a whole.
+    fn leave(&mut self) {
+        let path = self.path();
+        let frame = match self.frames.pop() {
+            Some(frame) => frame,
+            None => return,
+        };
+        if self.frames.iter().any(|f| f.ignored) {
+            return;
+        }
+        if let Some(PathSegment::Key(_)) = frame.segment {
+            let (list, recorded) = if frame.ignored {
+                (&mut self.usage.ignored, &mut self.ignored)
+            } else {
+                (&mut self.usage.consumed, &mut self.consumed)
+            };
+            if recorded.insert(path.clone()) {
+                list.push(path.clone());
+            }
+        }
+        if frame.ignored {
+            return;
+        }
+        for field in frame.fields {
+            if !frame.seen.iter().any(|k| k == field) {
+                let path = path.join_key(*field);
+                if self.defaulted.insert(path.clone()) {
+                    self.usage.defaulted.push(path);
+                }
+            }
+        }
+    }
+}
+
+impl Context {
+    /// Returns the variant of an enum a string names, under the
+    /// `VariantMatching` in use.
+    fn match_variant<'de>(
+        &self,
+        name: Cow<'de, str>,
+        variants: &'static [&'static str],
+        at: usize,
+    ) -> Result<Cow<'de, str>, Error> {
+        let matching = *self.variant_matching.lock().unwrap();
+        if matching == VariantMatching::Exact || variants.contains(&&*name) {
+            return Ok(name);
+        }
+        let found = variants.iter().find(|v| matching.matches(v, &name));
+        match found {
+            Some(variant) => Ok(Cow::Borrowed(*variant)),
+            None => Err(Error::from_kind(
+                Some(at),
+                ErrorKind::UnknownVariant {
+                    variant: name.into_owned(),
+                    expected: variants.iter().map(|v| matching.display(v)).collect(),
+                },
+            )),
+        }
+    }
+
+    /// Starts tracking the key usage of a document, if enabled, unless it is
+    /// being tracked already.
+    fn start_usage(&self, fields: &'static [&'static str]) {
+        if let Some(ref mut tracker) = *self.usage.lock().unwrap() {
+            if tracker.frames.is_empty() {
+                tracker.usage = KeyUsage::default();
+                tracker.frames.push(UsageFrame::new(None));
+                tracker.frames[0].fields = fields;
+            }
+        }
+    }
+
+    /// Finishes tracking the key usage of a document.
+    fn finish_usage(&self) {
+        if let Some(ref mut tracker) = *self.usage.lock().unwrap() {
+            while !tracker.frames.is_empty() {
+                tracker.leave();
+            }
+        }
+    }
+
+    fn enter_key(&self, key: &str) {
+        if let Some(ref mut tracker) = *self.usage.lock().unwrap() {
+            if let Some(parent) = tracker.frames.last_mut() {
+                parent.seen.push(key.to_string());
+            }
+            let frame = UsageFrame::new(Some(PathSegment::Key(key.to_string())));
+            tracker.frames.push(frame);
+        }
+    }
+
+    fn enter_index(&self, index: usize) {
+        if let Some(ref mut tracker) = *self.usage.lock().unwrap() {
+            tracker
+                .frames
+                .push(UsageFrame::new(Some(PathSegment::Index(index))));
+        }
+    }
+
+    fn leave(&self) {
+        if let Some(ref mut tracker) = *self.usage.lock().unwrap() {
+            tracker.leave();
+        }
+    }
+
+    /// Marks the value being deserialized as ignored.
+    fn ignore(&self) {
+        if let Some(ref mut tracker) = *self.usage.lock().unwrap() {
+            if let Some(frame) = tracker.frames.last_mut() {
+                frame.ignored = true;
+            }
+        }
+    }
+
+    /// Records the fields of the struct the value being deserialized is
+    /// read as.
+    fn expect_fields(&self, fields: &'static [&'static str]) {
+        if let Some(ref mut tracker) = *self.usage.lock().unwrap() {
+            if let Some(frame) = tracker.frames.last_mut() {
+                frame.fields = fields;
+            }
+        }
+    }
+
+    fn warn(&self, at: usize, message: String) {
+        self.warnings.lock().unwrap().push((at, message));
+    }
+
+    /// Records a warning along with the edit that would fix it.
+    fn warn_fixable(&self, at: usize, message: String, span: (usize, usize), replacement: &str) {
+        self.fixes.lock().unwrap().push(Fix::new(
+            span,
+            replacement.to_string(),
+            message.clone(),
+            true,
+        ));
+        self.warn(at, message);
+    }
+}
+
+/// What a [`Deserializer`] does with a key defined more than once in the
+/// same table, set with [`set_duplicate_keys`].
+///
+/// [`Deserializer`]: struct.Deserializer.html
+/// [`set_duplicate_keys`]: struct.Deserializer.html#method.set_duplicate_keys
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DuplicateKeys {
+    /// The document is rejected, as the TOML spec requires.
+    Error,
+    /// The first definition is kept and the later ones are ignored.
+    FirstWins,
+    /// Each definition replaces the one before it.
+    LastWins,
+}
+
+/// What a [`Deserializer`] does with keys of the same table that differ
+/// only in case, such as `Timeout` and `timeout`, set with
+/// [`set_case_conflicts`].
+///
+/// [`Deserializer`]: struct.Deserializer.html
+/// [`set_case_conflicts`]: struct.Deserializer.html#method.set_case_conflicts
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CaseConflicts {
+    /// The keys are distinct, as the TOML spec says.
+    Allow,
+    /// The keys are distinct, and a warning is recorded for each conflict.
+    Warn,
+    /// The document is rejected.
+    Error,
+}
+
+/// How a [`Deserializer`] matches a string against the variant names of an
+/// enum, set with [`set_variant_matching`].
+///
+/// [`Deserializer`]: struct.Deserializer.html
+/// [`set_variant_matching`]: struct.Deserializer.html#method.set_variant_matching
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum VariantMatching {
+    /// The string must be the name of the variant, as serde requires.
+    #[default]
+    Exact,
+    /// The string may differ from the name of the variant in case, so
+    /// `"Debug"`, `"debug"` and `"DEBUG"` all name `Debug`.
+    CaseInsensitive,
+    /// The string may also be the name of the variant in kebab case, so
+    /// `"dry-run"` names `DryRun` and `dry_run`.
+    KebabCase,
+}
+
+impl VariantMatching {
+    fn matches(self, variant: &str, name: &str) -> bool {
+        match self {
+            VariantMatching::Exact => variant == name,
+            VariantMatching::CaseInsensitive => variant.to_lowercase() == name.to_lowercase(),
+            VariantMatching::KebabCase => kebab_case(variant) == name,
+        }
+    }
+
+    /// Returns the name of a variant to suggest in errors.
+    fn display(self, variant: &str) -> String {
+        match self {
+            VariantMatching::KebabCase => kebab_case(variant),
+            _ => variant.to_string(),
+        }
+    }
+}
+
+/// Converts a variant name such as `DryRun` or `dry_run` to `dry-run`.
+fn kebab_case(name: &str) -> String {
+    let mut out = String::with_capacity(name.len() + 4);
+    let mut prev_lower = false;
+    for c in name.chars() {
+        if c == '_' {
+            out.push('-');
+            prev_lower = false;
+            continue;
+        }
+        if c.is_uppercase() && prev_lower {
+            out.push('-');
+        }
+        prev_lower = c.is_lowercase() || c.is_numeric();
+        out.extend(c.to_lowercase());
+    }
+    out
+}
+
+/// Which keys of the input were read while deserializing, returned by
+/// [`Deserializer::key_usage`].
+///
+/// [`Deserializer::key_usage`]: struct.Deserializer.html#method.key_usage
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct KeyUsage {
+    consumed: Vec<KeyPath>,
+    defaulted: Vec<KeyPath>,
+    ignored: Vec<KeyPath>,
+}
+
+impl KeyUsage {
+    /// Returns the path of each key whose value was read, tables included,
+    /// in the order they were read.
+    pub fn consumed(&self) -> &[KeyPath] {
+        &self.consumed
+    }
+
+    /// Returns the path of each struct field that had no key in the input,
+    /// so was left to its default, such as `None` for an `Option`.
+    pub fn defaulted(&self) -> &[KeyPath] {
+        &self.defaulted
+    }
+
+    /// Returns the path of each key whose value was skipped, such as a key
+    /// that is not a field of the struct read. Keys inside a skipped value
+    /// are not listed.
+    pub fn ignored(&self) -> &[KeyPath] {
+        &self.ignored
+    }
+}
+
+/// A problem in the input that was tolerated rather than reported as an
+/// error, such as a value coerced to another type.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Warning {
+    message: String,
+    at: usize,
+    line: usize,
+    col: usize,
+}
+
+impl Warning {
+    /// Returns a description of the problem.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// Returns the byte offset of the problem in the input.
+    pub fn offset(&self) -> usize {
+        self.at
+    }
+
+    /// Produces a (line, column) pair of the position of the problem
+    ///
+    /// All indexes are 0-based.
+    pub fn line_col(&self) -> (usize, usize) {
+        (self.line, self.col)
+    }
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} at line {} column {}",
+            self.message,
+            self.line + 1,
+            self.col + 1
+        )
+    }
 }
 
 impl<'de, 'b> de::Deserializer<'de> for &'b mut Deserializer<'de> {
@@ -214,9 +791,14 @@ impl<'de, 'b> de::Deserializer<'de> for &'b mut Deserializer<'de> {
         V: de::Visitor<'de>,
     {
         let mut tables = self.tables()?;
+        self.check_redefinitions(&mut tables)?;
+        if let Some(ref mut rewrite) = self.rewrite {
+            rewrite_values(&mut tables, rewrite.get_mut().unwrap());
+        }
         let table_indices = build_table_indices(&tables);
         let table_pindices = build_table_pindices(&tables);
 
+        self.ctx.start_usage(&[]);
         let res = visitor.visit_map(MapVisitor {
             values: Vec::new().into_iter().peekable(),
             next_value: None,
@@ -224,12 +806,14 @@ impl<'de, 'b> de::Deserializer<'de> for &'b mut Deserializer<'de> {
             cur: 0,
             cur_parent: 0,
             max: tables.len(),
+            index: 0,
             table_indices: &table_indices,
             table_pindices: &table_pindices,
             tables: &mut tables,
             array: false,
             de: self,
         });
+        self.ctx.finish_usage();
         res.map_err(|mut err| {
             // Errors originating from this library (toml), have an offset
             // attached to them already. Other errors, like those originating
@@ -247,7 +831,7 @@ impl<'de, 'b> de::Deserializer<'de> for &'b mut Deserializer<'de> {
     fn deserialize_enum<V>(
         self,
         _name: &'static str,
-        _variants: &'static [&'static str],
+        variants: &'static [&'static str],
         visitor: V,
     ) -> Result<V::Value, Error>
     where
@@ -255,7 +839,16 @@ impl<'de, 'b> de::Deserializer<'de> for &'b mut Deserializer<'de> {
     {
         let (value, name) = self.string_or_table()?;
         match value.e {
-            E::String(val) => visitor.visit_enum(val.into_deserializer()),
+            E::String(val) => {
+                let val =
+                    self.ctx
+                        .match_variant(val, variants, value.start)
+                        .map_err(|mut err| {
+                            err.fix_linecol(|at| self.to_linecol(at));
+                            err
+                        })?;
+                visitor.visit_enum(val.into_deserializer())
+            }
             E::InlineTable(values) => {
                 if values.len() != 1 {
                     Err(Error::from_kind(
@@ -273,12 +866,14 @@ impl<'de, 'b> de::Deserializer<'de> for &'b mut Deserializer<'de> {
                     visitor.visit_enum(InlineTableDeserializer {
                         values: values.into_iter(),
                         next_value: None,
+                        ctx: self.ctx.clone(),
                     })
                 }
             }
             E::DottedTable(_) => visitor.visit_enum(DottedTableDeserializer {
                 name: name.expect("Expected table header to be passed."),
                 value,
+                ctx: self.ctx.clone(),
             }),
             e => Err(Error::from_kind(
                 Some(value.start),
@@ -312,6 +907,7 @@ impl<'de, 'b> de::Deserializer<'de> for &'b mut Deserializer<'de> {
             return res;
         }
 
+        self.ctx.start_usage(fields);
         self.deserialize_any(visitor)
     }
 
@@ -369,6 +965,117 @@ fn build_table_pindices<'de>(tables: &[Table<'de>]) -> HashMap<Vec<Cow<'de, str>
     res
 }
 
+fn join_path(path: &[Cow<'_, str>]) -> String {
+    path.iter().map(|k| &**k).collect::<Vec<_>>().join(".")
+}
+
+/// Calls `f` with each value in `tables` that is not an array or a table,
+/// along with its key path.
+fn visit_values<'a>(tables: &mut [Table<'a>], f: &mut dyn FnMut(&KeyPath, &mut Value<'a>)) {
+    // The number of elements of each array of tables so far.
+    let mut arrays: HashMap<Vec<Cow<'a, str>>, usize> = HashMap::new();
+    for table in tables {
+        let header = table.header.iter().map(|k| k.1.clone()).collect::<Vec<_>>();
+        if table.array {
+            arrays.retain(|k, _| k.len() <= header.len() || !k.starts_with(&header));
+            *arrays.entry(header.clone()).or_insert(0) += 1;
+        }
+        let mut path = KeyPath::new();
+        for len in 1..=header.len() {
+            path.push_key(header[len - 1].to_string());
+            if let Some(count) = arrays.get(&header[..len]) {
+                path.push_index(count - 1);
+            }
+        }
+        for ((_, key), value) in table.values.iter_mut().flatten() {
+            path.push_key(key.to_string());
+            visit_value(&mut path, value, f);
+            path.pop();
+        }
+    }
+}
+
+/// Replaces each value in `tables` that is not an array or a table with what
+/// `rewrite` returns for it.
+fn rewrite_values<'a>(
+    tables: &mut [Table<'a>],
+    rewrite: &mut dyn FnMut(&KeyPath, crate::Value) -> crate::Value,
+) {
+    visit_values(tables, &mut |path, value| {
+        if let Some(public) = public_value(&value.e) {
+            *value = internal_value(rewrite(path, public), value.start, value.end);
+        }
+    });
+}
+
+/// Returns the `Value` for `e`, which is not an array or a table, or `None`
+/// if it has none.
+fn public_value(e: &E<'_>) -> Option<crate::Value> {
+    let value = match *e {
+        E::String(ref s) => crate::Value::from(&**s),
+        E::Integer(i) => crate::Value::Integer(i),
+        E::Float(f) => crate::Value::Float(f),
+        E::Boolean(b) => crate::Value::Boolean(b),
+        E::Datetime(ref s) => crate::Value::Datetime(s.parse().ok()?),
+        E::RawNumber(ref s) => crate::Value::RawNumber(s.to_string().into()),
+        E::Array(_) | E::InlineTable(_) | E::DottedTable(_) => return None,
+    };
+    Some(value)
+}
+
+/// Returns `value` as a value read from `start..end`, with its tables as
+/// inline tables.
+fn internal_value<'a>(value: crate::Value, start: usize, end: usize) -> Value<'a> {
+    let e = match value {
+        crate::Value::String(s) => E::String(Cow::Owned(s.to_string())),
+        crate::Value::Integer(i) => E::Integer(i),
+        crate::Value::Float(f) => E::Float(f),
+        crate::Value::Boolean(b) => E::Boolean(b),
+        crate::Value::Datetime(d) => E::Datetime(Cow::Owned(d.to_string())),
+        crate::Value::RawNumber(s) => E::RawNumber(Cow::Owned(s.into_string())),
+        crate::Value::Array(values) => E::Array(
+            values
+                .into_iter()
+                .map(|value| internal_value(value, start, end))
+                .collect(),
+        ),
+        crate::Value::Table(table) => E::InlineTable(
+            table
+                .into_iter()
+                .map(|(key, value)| {
+                    let key = (Span { start, end }, Cow::Owned(key));
+                    (key, internal_value(value, start, end))
+                })
+                .collect(),
+        ),
+    };
+    Value { e, start, end }
+}
+
+fn visit_value<'a>(
+    path: &mut KeyPath,
+    value: &mut Value<'a>,
+    f: &mut dyn FnMut(&KeyPath, &mut Value<'a>),
+) {
+    match value.e {
+        E::Array(ref mut values) => {
+            for (i, value) in values.iter_mut().enumerate() {
+                path.push_index(i);
+                visit_value(path, value, f);
+                path.pop();
+            }
+        }
+        E::InlineTable(ref mut pairs) | E::DottedTable(ref mut pairs) => {
+            for ((_, key), value) in pairs {
+                path.push_key(key.to_string());
+                visit_value(path, value, f);
+                path.pop();
+            }
+        }
+        _ => f(path, value),
+    }
+}
+
 fn headers_equal<'a, 'b>(hdr_a: &[(Span, Cow<'a, str>)], hdr_b: &[(Span, Cow<'b, str>)]) -> bool {
     if hdr_a.len() != hdr_b.len() {
         return false;
@@ -390,6 +1097,8 @@ struct MapVisitor<'de, 'b> {
     cur: usize,
     cur_parent: usize,
     max: usize,
+    /// The index of the next element, when visiting an array of tables.
+    index: usize,
     table_indices: &'b HashMap<Vec<Cow<'de, str>>, Vec<usize>>,
     table_pindices: &'b HashMap<Vec<Cow<'de, str>>, Vec<usize>>,
     tables: &'b mut [Table<'de>],
@@ -407,6 +1116,9 @@ impl<'de, 'b> de::MapAccess<'de> for MapVisitor<'de, 'b> {
         if self.cur_parent == self.max || self.cur == self.max {
             return Ok(None);
         }
+        if self.depth == 0 && self.de.is_cancelled() {
+            return Err(Error::from_kind(None, ErrorKind::Cancelled));
+        }
 
         loop {
             assert!(self.next_value.is_none());
@@ -507,7 +1219,11 @@ impl<'de, 'b> de::MapAccess<'de> for MapVisitor<'de, 'b> {
         V: de::DeserializeSeed<'de>,
     {
         if let Some((k, v)) = self.next_value.take() {
-            match seed.deserialize(ValueDeserializer::new(v)) {
+            let ctx = self.de.ctx.clone();
+            ctx.enter_key(&k.1);
+            let res = seed.deserialize(ValueDeserializer::new(v, ctx.clone()));
+            ctx.leave();
+            match res {
                 Ok(v) => return Ok(v),
                 Err(mut e) => {
                     e.add_key_context(&k.1);
@@ -518,6 +1234,8 @@ impl<'de, 'b> de::MapAccess<'de> for MapVisitor<'de, 'b> {
 
         let array =
             self.tables[self.cur].array && self.depth == self.tables[self.cur].header.len() - 1;
+        let ctx = self.de.ctx.clone();
+        ctx.enter_key(&self.tables[self.cur].header[self.depth].1);
         self.cur += 1;
         let res = seed.deserialize(MapVisitor {
             values: Vec::new().into_iter().peekable(),
@@ -526,12 +1244,14 @@ impl<'de, 'b> de::MapAccess<'de> for MapVisitor<'de, 'b> {
             cur_parent: self.cur - 1,
             cur: 0,
             max: self.max,
+            index: 0,
             array,
             table_indices: &*self.table_indices,
             table_pindices: &*self.table_pindices,
             tables: &mut *self.tables,
             de: &mut *self.de,
         });
+        ctx.leave();
         res.map_err(|mut e| {
             e.add_key_context(&self.tables[self.cur - 1].header[self.depth].1);
             e
@@ -576,6 +1296,9 @@ impl<'de, 'b> de::SeqAccess<'de> for MapVisitor<'de, 'b> {
             })
             .unwrap_or(self.max);
 
+        let ctx = self.de.ctx.clone();
+        ctx.enter_index(self.index);
+        self.index += 1;
         let ret = seed.deserialize(MapVisitor {
             values: self.tables[self.cur_parent]
                 .values
@@ -588,12 +1311,15 @@ impl<'de, 'b> de::SeqAccess<'de> for MapVisitor<'de, 'b> {
             cur_parent: self.cur_parent,
             max: next,
             cur: 0,
+            index: 0,
             array: false,
             table_indices: &*self.table_indices,
             table_pindices: &*self.table_pindices,
             tables: &mut self.tables,
             de: &mut self.de,
-        })?;
+        });
+        ctx.leave();
+        let ret = ret?;
         self.cur_parent = next;
         Ok(Some(ret))
     }
@@ -663,6 +1389,15 @@ impl<'de, 'b> de::Deserializer<'de> for MapVisitor<'de, 'b> {
             return res;
         }
 
+        self.de.ctx.expect_fields(fields);
+        self.deserialize_any(visitor)
+    }
+
+    fn deserialize_ignored_any<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        self.de.ctx.ignore();
         self.deserialize_any(visitor)
     }
 
@@ -694,13 +1429,14 @@ impl<'de, 'b> de::Deserializer<'de> for MapVisitor<'de, 'b> {
                 start: 0,
                 end: 0,
             },
+            ctx: self.de.ctx.clone(),
         })
     }
 
     serde::forward_to_deserialize_any! {
         bool u8 u16 u32 u64 i8 i16 i32 i64 f32 f64 char str string seq
         bytes byte_buf map unit identifier
-        ignored_any unit_struct tuple_struct tuple
+        unit_struct tuple_struct tuple
     }
 }
 
@@ -771,16 +1507,29 @@ impl<'de> de::Deserializer<'de> for StrDeserializer<'de> {
     }
 }
 
-struct ValueDeserializer<'a> {
+macro_rules! forward_integers_to_i64 {
+    ($($method:ident)*) => {$(
+        fn $method<V>(self, visitor: V) -> Result<V::Value, Error>
+        where
+            V: de::Visitor<'de>,
+        {
+            self.deserialize_i64(visitor)
+        }
+    )*};
+}
+
+pub(crate) struct ValueDeserializer<'a> {
     value: Value<'a>,
     validate_struct_keys: bool,
+    ctx: Arc<Context>,
 }
 
 impl<'a> ValueDeserializer<'a> {
-    fn new(value: Value<'a>) -> ValueDeserializer<'a> {
+    fn new(value: Value<'a>, ctx: Arc<Context>) -> ValueDeserializer<'a> {
         ValueDeserializer {
             value,
             validate_struct_keys: false,
+            ctx,
         }
     }
 
@@ -808,8 +1557,13 @@ impl<'de> de::Deserializer<'de> for ValueDeserializer<'de> {
                 date: s,
                 visited: false,
             }),
+            E::RawNumber(s) => visitor.visit_map(RawNumberDeserializer::new(s)),
             E::Array(values) => {
-                let mut s = de::value::SeqDeserializer::new(values.into_iter());
+                let mut s = ArrayDeserializer {
+                    values: values.into_iter(),
+                    index: 0,
+                    ctx: self.ctx,
+                };
                 let ret = visitor.visit_seq(&mut s)?;
                 s.end()?;
                 Ok(ret)
@@ -818,6 +1572,7 @@ impl<'de> de::Deserializer<'de> for ValueDeserializer<'de> {
                 visitor.visit_map(InlineTableDeserializer {
                     values: values.into_iter(),
                     next_value: None,
+                    ctx: self.ctx,
                 })
             }
         };
@@ -838,9 +1593,9 @@ impl<'de> de::Deserializer<'de> for ValueDeserializer<'de> {
         V: de::Visitor<'de>,
     {
         if name == datetime::NAME && fields == [datetime::FIELD] {
-            if let E::Datetime(s) = self.value.e {
+            if let E::Datetime(ref s) = self.value.e {
                 return visitor.visit_map(DatetimeDeserializer {
-                    date: s,
+                    date: s.clone(),
                     visited: false,
                 });
             }
@@ -885,11 +1640,12 @@ impl<'de> de::Deserializer<'de> for ValueDeserializer<'de> {
             return visitor.visit_map(SpannedDeserializer {
                 phantom_data: PhantomData,
                 start: Some(start),
-                value: Some(self.value),
+                value: Some(self),
                 end: Some(end),
             });
         }
 
+        self.ctx.expect_fields(fields);
         self.deserialize_any(visitor)
     }
 
@@ -902,17 +1658,34 @@ impl<'de> de::Deserializer<'de> for ValueDeserializer<'de> {
         visitor.visit_some(self)
     }
 
+    fn deserialize_ignored_any<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        self.ctx.ignore();
+        self.deserialize_any(visitor)
+    }
+
     fn deserialize_enum<V>(
         self,
         _name: &'static str,
-        _variants: &'static [&'static str],
+        variants: &'static [&'static str],
         visitor: V,
     ) -> Result<V::Value, Error>
     where
         V: de::Visitor<'de>,
     {
         match self.value.e {
-            E::String(val) => visitor.visit_enum(val.into_deserializer()),
+            E::String(val) => {
+                let start = self.value.start;
+                let val = self.ctx.match_variant(val, variants, start)?;
+                let res: Result<V::Value, Error> = visitor.visit_enum(val.into_deserializer());
+                res.map_err(|mut err| {
+                    // Attribute errors such as an unknown variant to the string.
+                    err.fix_offset(|| Some(start));
+                    err
+                })
+            }
             E::InlineTable(values) => {
                 if values.len() != 1 {
                     Err(Error::from_kind(
@@ -930,6 +1703,7 @@ impl<'de> de::Deserializer<'de> for ValueDeserializer<'de> {
                     visitor.visit_enum(InlineTableDeserializer {
                         values: values.into_iter(),
                         next_value: None,
+                        ctx: self.ctx,
                     })
                 }
             }
@@ -954,34 +1728,201 @@ impl<'de> de::Deserializer<'de> for ValueDeserializer<'de> {
         visitor.visit_newtype_struct(self)
     }
 
-    serde::forward_to_deserialize_any! {
-        bool u8 u16 u32 u64 i8 i16 i32 i64 f32 f64 char str string seq
-        bytes byte_buf map unit identifier
-        ignored_any unit_struct tuple_struct tuple
-    }
-}
-
-impl<'de, 'b> de::IntoDeserializer<'de, Error> for MapVisitor<'de, 'b> {
-    type Deserializer = MapVisitor<'de, 'b>;
-
-    fn into_deserializer(self) -> Self::Deserializer {
-        self
+    fn deserialize_bool<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        let lenient = self.ctx.lenient_coercion.load(atomic::Ordering::Relaxed);
+        let legacy = self.ctx.legacy_booleans.load(atomic::Ordering::Relaxed);
+        match self.value.e {
+            E::String(ref s) if lenient || legacy => {
+                let b = match s.trim() {
+                    s if lenient && s.eq_ignore_ascii_case("true") => Some(true),
+                    s if lenient && s.eq_ignore_ascii_case("false") => Some(false),
+                    s if legacy && ["yes", "on", "1"].iter().any(|t| s.eq_ignore_ascii_case(t)) => {
+                        Some(true)
+                    }
+                    s if legacy && ["no", "off", "0"].iter().any(|t| s.eq_ignore_ascii_case(t)) => {
+                        Some(false)
+                    }
+                    _ => None,
+                };
+                if let Some(b) = b {
+                    self.coerced("string", s, "a boolean");
+                    return visitor.visit_bool(b);
+                }
+            }
+            E::Integer(i @ 0..=1) if legacy => {
+                self.coerced("integer", &i.to_string(), "a boolean");
+                return visitor.visit_bool(i == 1);
+            }
+            _ => {}
+        }
+        self.deserialize_any(visitor)
     }
-}
 
-impl<'de, 'b> de::IntoDeserializer<'de, Error> for &'b mut Deserializer<'de> {
-    type Deserializer = Self;
-
-    fn into_deserializer(self) -> Self::Deserializer {
+    fn deserialize_f64<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        if let E::RawNumber(ref s) = self.value.e {
+            return self.visit_raw_number(s, visitor);
+        }
+        if self.ctx.lenient_coercion.load(atomic::Ordering::Relaxed) {
+            match self.value.e {
+                E::String(ref s) => {
+                    if let Ok(f) = s.trim().replace('_', "").parse::<f64>() {
+                        self.coerced("string", s, "a float");
+                        return visitor.visit_f64(f);
+                    }
+                }
+                E::Integer(i) => {
+                    self.coerced("integer", &i.to_string(), "a float");
+                    return visitor.visit_f64(i as f64);
+                }
+                _ => {}
+            }
+        }
+        self.deserialize_any(visitor)
+    }
+
+    fn deserialize_f32<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        self.deserialize_f64(visitor)
+    }
+
+    fn deserialize_i64<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        if let E::RawNumber(ref s) = self.value.e {
+            return self.visit_raw_number(s, visitor);
+        }
+        if self.ctx.lenient_coercion.load(atomic::Ordering::Relaxed) {
+            if let E::String(ref s) = self.value.e {
+                if let Ok(i) = s.trim().replace('_', "").parse::<i64>() {
+                    self.coerced("string", s, "an integer");
+                    return visitor.visit_i64(i);
+                }
+            }
+        }
+        self.deserialize_any(visitor)
+    }
+
+    fn deserialize_u64<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        match self.big_integer_string::<u64>()? {
+            Some(n) => visitor.visit_u64(n),
+            None => self.deserialize_i64(visitor),
+        }
+    }
+
+    fn deserialize_i128<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        match self.big_integer_string::<i128>()? {
+            Some(n) => visitor.visit_i128(n),
+            None => self.deserialize_i64(visitor),
+        }
+    }
+
+    fn deserialize_u128<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        match self.big_integer_string::<u128>()? {
+            Some(n) => visitor.visit_u128(n),
+            None => self.deserialize_i64(visitor),
+        }
+    }
+
+    serde::forward_to_deserialize_any! {
+        char str string seq
+        bytes byte_buf map unit identifier
+        unit_struct tuple_struct tuple
+    }
+
+    forward_integers_to_i64! {
+        deserialize_i8 deserialize_i16 deserialize_i32
+        deserialize_u8 deserialize_u16 deserialize_u32
+    }
+}
+
+impl<'a> ValueDeserializer<'a> {
+    fn visit_raw_number<'de, V>(&self, s: &str, visitor: V) -> Result<V::Value, Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        let start = self.value.start;
+        visit_raw_number(s, visitor).map_err(|mut e| {
+            e.fix_offset(|| Some(start));
+            e
+        })
+    }
+
+    /// Parses a string holding a big integer, if that is enabled and the
+    /// value is a string.
+    fn big_integer_string<T>(&self) -> Result<Option<T>, Error>
+    where
+        T: FromStr,
+    {
+        match self.value.e {
+            E::String(ref s) if self.ctx.big_integer_strings.load(atomic::Ordering::Relaxed) => {
+                match s.parse() {
+                    Ok(n) => Ok(Some(n)),
+                    Err(_) => Err(Error::custom(
+                        Some(self.value.start),
+                        format!(
+                            "string {:?} is not a valid {}",
+                            s,
+                            std::any::type_name::<T>()
+                        ),
+                    )),
+                }
+            }
+            _ => Ok(None),
+        }
+    }
+
+    fn coerced(&self, found: &str, text: &str, expected: &str) {
+        let found = if found == "string" {
+            format!("string {:?}", text)
+        } else {
+            format!("{} `{}`", found, text)
+        };
+        self.ctx.warn(
+            self.value.start,
+            format!("coerced {} to {}", found, expected),
+        );
+    }
+}
+
+impl<'de, 'b> de::IntoDeserializer<'de, Error> for MapVisitor<'de, 'b> {
+    type Deserializer = MapVisitor<'de, 'b>;
+
+    fn into_deserializer(self) -> Self::Deserializer {
+        self
+    }
+}
+
+impl<'de, 'b> de::IntoDeserializer<'de, Error> for &'b mut Deserializer<'de> {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self::Deserializer {
         self
     }
 }
 
-impl<'de> de::IntoDeserializer<'de, Error> for Value<'de> {
+impl<'de> de::IntoDeserializer<'de, Error> for ValueDeserializer<'de> {
     type Deserializer = ValueDeserializer<'de>;
 
     fn into_deserializer(self) -> Self::Deserializer {
-        ValueDeserializer::new(self)
+        self
     }
 }
 
@@ -1034,7 +1975,7 @@ where
 
 struct DatetimeDeserializer<'a> {
     visited: bool,
-    date: &'a str,
+    date: Cow<'a, str>,
 }
 
 impl<'de> de::MapAccess<'de> for DatetimeDeserializer<'de> {
@@ -1055,7 +1996,7 @@ impl<'de> de::MapAccess<'de> for DatetimeDeserializer<'de> {
     where
         V: de::DeserializeSeed<'de>,
     {
-        seed.deserialize(StrDeserializer::new(self.date.into()))
+        seed.deserialize(StrDeserializer::new(self.date.clone()))
     }
 }
 
@@ -1078,9 +2019,48 @@ impl<'de> de::Deserializer<'de> for DatetimeFieldDeserializer {
     }
 }
 
+struct RawNumberDeserializer<'a> {
+    number: Option<Cow<'a, str>>,
+}
+
+impl<'a> RawNumberDeserializer<'a> {
+    fn new(number: Cow<'a, str>) -> RawNumberDeserializer<'a> {
+        RawNumberDeserializer {
+            number: Some(number),
+        }
+    }
+}
+
+impl<'de> de::MapAccess<'de> for RawNumberDeserializer<'de> {
+    type Error = Error;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>, Error>
+    where
+        K: de::DeserializeSeed<'de>,
+    {
+        if self.number.is_none() {
+            return Ok(None);
+        }
+        seed.deserialize(StrDeserializer::new(value::RAW_NUMBER_FIELD.into()))
+            .map(Some)
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value, Error>
+    where
+        V: de::DeserializeSeed<'de>,
+    {
+        let number = self
+            .number
+            .take()
+            .expect("next_value_seed called before next_key_seed");
+        seed.deserialize(StrDeserializer::new(number))
+    }
+}
+
 struct DottedTableDeserializer<'a> {
     name: Cow<'a, str>,
     value: Value<'a>,
+    ctx: Arc<Context>,
 }
 
 impl<'de> de::EnumAccess<'de> for DottedTableDeserializer<'de> {
@@ -1091,15 +2071,64 @@ impl<'de> de::EnumAccess<'de> for DottedTableDeserializer<'de> {
     where
         V: de::DeserializeSeed<'de>,
     {
-        let (name, value) = (self.name, self.value);
+        let (name, value, ctx) = (self.name, self.value, self.ctx);
         seed.deserialize(StrDeserializer::new(name))
-            .map(|val| (val, TableEnumDeserializer { value }))
+            .map(|val| (val, TableEnumDeserializer { value, ctx }))
+    }
+}
+
+struct ArrayDeserializer<'a> {
+    values: vec::IntoIter<Value<'a>>,
+    index: usize,
+    ctx: Arc<Context>,
+}
+
+impl<'a> ArrayDeserializer<'a> {
+    /// Checks that every element of the array was deserialized.
+    fn end(self) -> Result<(), Error> {
+        let remaining = self.values.len();
+        if remaining == 0 {
+            return Ok(());
+        }
+        let expected = if self.index == 1 {
+            "1 element in sequence".to_string()
+        } else {
+            format!("{} elements in sequence", self.index)
+        };
+        Err(de::Error::invalid_length(
+            self.index + remaining,
+            &expected.as_str(),
+        ))
+    }
+}
+
+impl<'de> de::SeqAccess<'de> for ArrayDeserializer<'de> {
+    type Error = Error;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>, Error>
+    where
+        T: de::DeserializeSeed<'de>,
+    {
+        let value = match self.values.next() {
+            Some(value) => value,
+            None => return Ok(None),
+        };
+        self.ctx.enter_index(self.index);
+        self.index += 1;
+        let res = seed.deserialize(ValueDeserializer::new(value, self.ctx.clone()));
+        self.ctx.leave();
+        res.map(Some)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.values.len())
     }
 }
 
 struct InlineTableDeserializer<'a> {
     values: vec::IntoIter<TablePair<'a>>,
-    next_value: Option<Value<'a>>,
+    next_value: Option<(Cow<'a, str>, Value<'a>)>,
+    ctx: Arc<Context>,
 }
 
 impl<'de> de::MapAccess<'de> for InlineTableDeserializer<'de> {
@@ -1113,7 +2142,7 @@ impl<'de> de::MapAccess<'de> for InlineTableDeserializer<'de> {
             Some(pair) => pair,
             None => return Ok(None),
         };
-        self.next_value = Some(value);
+        self.next_value = Some((key.1.clone(), value));
         seed.deserialize(StrDeserializer::spanned(key)).map(Some)
     }
 
@@ -1121,8 +2150,15 @@ impl<'de> de::MapAccess<'de> for InlineTableDeserializer<'de> {
     where
         V: de::DeserializeSeed<'de>,
     {
-        let value = self.next_value.take().expect("Unable to read table values");
-        seed.deserialize(ValueDeserializer::new(value))
+        let (key, value) = self.next_value.take().expect("Unable to read table values");
+        self.ctx.enter_key(&key);
+        let res = seed.deserialize(ValueDeserializer::new(value, self.ctx.clone()));
+        self.ctx.leave();
+        res
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.values.len())
     }
 }
 
@@ -1147,14 +2183,16 @@ impl<'de> de::EnumAccess<'de> for InlineTableDeserializer<'de> {
             }
         };
 
+        let ctx = self.ctx;
         seed.deserialize(StrDeserializer::new(key.1))
-            .map(|val| (val, TableEnumDeserializer { value }))
+            .map(|val| (val, TableEnumDeserializer { value, ctx }))
     }
 }
 
 /// Deserializes table values into enum variants.
 struct TableEnumDeserializer<'a> {
     value: Value<'a>,
+    ctx: Arc<Context>,
 }
 
 impl<'de> de::VariantAccess<'de> for TableEnumDeserializer<'de> {
@@ -1186,7 +2224,7 @@ impl<'de> de::VariantAccess<'de> for TableEnumDeserializer<'de> {
     where
         T: de::DeserializeSeed<'de>,
     {
-        seed.deserialize(ValueDeserializer::new(self.value))
+        seed.deserialize(ValueDeserializer::new(self.value, self.ctx))
     }
 
     fn tuple_variant<V>(self, len: usize, visitor: V) -> Result<V::Value, Self::Error>
@@ -1222,11 +2260,14 @@ impl<'de> de::VariantAccess<'de> for TableEnumDeserializer<'de> {
 
                 if tuple_values.len() == len {
                     de::Deserializer::deserialize_seq(
-                        ValueDeserializer::new(Value {
-                            e: E::Array(tuple_values),
-                            start: self.value.start,
-                            end: self.value.end,
-                        }),
+                        ValueDeserializer::new(
+                            Value {
+                                e: E::Array(tuple_values),
+                                start: self.value.start,
+                                end: self.value.end,
+                            },
+                            self.ctx,
+                        ),
                         visitor,
                     )
                 } else {
@@ -1255,7 +2296,7 @@ impl<'de> de::VariantAccess<'de> for TableEnumDeserializer<'de> {
         V: de::Visitor<'de>,
     {
         de::Deserializer::deserialize_struct(
-            ValueDeserializer::new(self.value).with_struct_key_validation(),
+            ValueDeserializer::new(self.value, self.ctx).with_struct_key_validation(),
             "", // TODO: this should be the variant name
             fields,
             visitor,
@@ -1272,6 +2313,17 @@ impl<'a> Deserializer<'a> {
             input,
             require_newline_after_table: true,
             allow_duplciate_after_longer_table: false,
+            lenient_numbers: false,
+            allow_trailing_commas: false,
+            allow_bare_boolean_keys: false,
+            allow_unquoted_strings: false,
+            merge_reopened_tables: false,
+            duplicate_keys: DuplicateKeys::Error,
+            case_conflicts: CaseConflicts::Allow,
+            progress: None,
+            cancel: None,
+            rewrite: None,
+            ctx: Arc::new(Context::default()),
         }
     }
 
@@ -1303,6 +2355,628 @@ impl<'a> Deserializer<'a> {
         self.allow_duplciate_after_longer_table = allow;
     }
 
+    /// Files written by older or non-conforming tools sometimes contain
+    /// numbers with leading zeros, such as `0123`, or a decimal point with no
+    /// digits after it, such as `1.`. The TOML spec forbids both.
+    ///
+    /// This option can be set to `true` (the default is `false`) to read
+    /// them as `123` and `1.0` instead, recording a warning for each, which
+    /// is available from [`warnings`].
+    ///
+    /// [`warnings`]: #method.warnings
+    pub fn set_lenient_numbers(&mut self, lenient: bool) {
+        self.lenient_numbers = lenient;
+    }
+
+    /// The TOML spec allows a trailing comma after the last element of an
+    /// array, but not after the last pair of an inline table, so
+    /// `{ a = 1, }` is an error.
+    ///
+    /// This option can be set to `true` (the default is `false`) to accept
+    /// such a comma, recording a warning for each, which is available from
+    /// [`warnings`].
+    ///
+    /// [`warnings`]: #method.warnings
+    pub fn set_allow_trailing_commas(&mut self, allow: bool) {
+        self.allow_trailing_commas = allow;
+    }
+
+    /// Hand-written files sometimes enable a flag by writing its key alone
+    /// on a line, such as `verbose`, which the TOML spec rejects.
+    ///
+    /// This option can be set to `true` (the default is `false`) to read
+    /// such a key as `verbose = true`, recording a warning for each, which
+    /// is available from [`warnings`].
+    ///
+    /// [`warnings`]: #method.warnings
+    pub fn set_allow_bare_boolean_keys(&mut self, allow: bool) {
+        self.allow_bare_boolean_keys = allow;
+    }
+
+    /// Files assembled from fragments sometimes define the same table more
+    /// than once, such as `[server]` in two places, which the TOML spec
+    /// rejects.
+    ///
+    /// This option can be set to `true` (the default is `false`) to instead
+    /// merge the keys of each later definition into the first, recording a
+    /// warning for each, which is available from [`warnings`]. Keys defined
+    /// in both are handled as set by [`set_duplicate_keys`]. A table
+    /// defined again in another form, such as an array of tables, is still
+    /// an error.
+    ///
+    /// ```rust
+    /// use serde::Deserialize;
+    ///
+    /// let mut de = toml::Deserializer::new("[server]\nhost = \"a\"\n[log]\n[server]\nport = 80\n");
+    /// de.set_merge_reopened_tables(true);
+    /// let value = toml::Value::deserialize(&mut de).unwrap();
+    /// assert_eq!(value["server"]["port"].as_integer(), Some(80));
+    /// assert_eq!(
+    ///     de.warnings()[0].to_string(),
+    ///     "table `server` is defined again, merged into its definition on line 1 at line 4 column 1",
+    /// );
+    /// ```
+    ///
+    /// [`warnings`]: #method.warnings
+    /// [`set_duplicate_keys`]: #method.set_duplicate_keys
+    pub fn set_merge_reopened_tables(&mut self, merge: bool) {
+        self.merge_reopened_tables = merge;
+    }
+
+    /// Log-structured and generated files are sometimes written by appending
+    /// lines, so that a key set again later is meant to override its earlier
+    /// value, which the TOML spec rejects.
+    ///
+    /// This option can be set to [`DuplicateKeys::FirstWins`] or
+    /// [`DuplicateKeys::LastWins`] (the default is [`DuplicateKeys::Error`])
+    /// to instead keep one of the definitions, recording a warning for each
+    /// one dropped, which is available from [`warnings`]. A key defined both
+    /// by a table header and in a key/value pair is still an error.
+    ///
+    /// ```rust
+    /// use serde::Deserialize;
+    /// use toml::de::DuplicateKeys;
+    ///
+    /// let mut de = toml::Deserializer::new("level = 1\nlevel = 2\n");
+    /// de.set_duplicate_keys(DuplicateKeys::LastWins);
+    /// let value = toml::Value::deserialize(&mut de).unwrap();
+    /// assert_eq!(value["level"].as_integer(), Some(2));
+    /// assert_eq!(
+    ///     de.warnings()[0].to_string(),
+    ///     "duplicate key `level`, replacing its earlier value at line 2 column 1",
+    /// );
+    /// ```
+    ///
+    /// [`DuplicateKeys::FirstWins`]: enum.DuplicateKeys.html#variant.FirstWins
+    /// [`DuplicateKeys::LastWins`]: enum.DuplicateKeys.html#variant.LastWins
+    /// [`DuplicateKeys::Error`]: enum.DuplicateKeys.html#variant.Error
+    /// [`warnings`]: #method.warnings
+    pub fn set_duplicate_keys(&mut self, policy: DuplicateKeys) {
+        self.duplicate_keys = policy;
+    }
+
+    /// Keys are case-sensitive, so a file can set both `Timeout` and
+    /// `timeout` in one table, which is usually a mistake when the program
+    /// reading it matches keys without regard to case.
+    ///
+    /// This option can be set to [`CaseConflicts::Warn`] or
+    /// [`CaseConflicts::Error`] (the default is [`CaseConflicts::Allow`]) to
+    /// record a warning, which is available from [`warnings`], or fail for
+    /// each key that differs only in case from an earlier key of the same
+    /// table. Keys keep the case they are written in either way.
+    ///
+    /// ```rust
+    /// use serde::Deserialize;
+    /// use toml::de::CaseConflicts;
+    ///
+    /// let mut de = toml::Deserializer::new("Timeout = 5\ntimeout = 10\n");
+    /// de.set_case_conflicts(CaseConflicts::Warn);
+    /// let value = toml::Value::deserialize(&mut de).unwrap();
+    /// assert_eq!(value["Timeout"].as_integer(), Some(5));
+    /// assert_eq!(
+    ///     de.warnings()[0].to_string(),
+    ///     "key `timeout` differs only in case from `Timeout` on line 1 at line 2 column 1",
+    /// );
+    /// ```
+    ///
+    /// [`CaseConflicts::Warn`]: enum.CaseConflicts.html#variant.Warn
+    /// [`CaseConflicts::Error`]: enum.CaseConflicts.html#variant.Error
+    /// [`CaseConflicts::Allow`]: enum.CaseConflicts.html#variant.Allow
+    /// [`warnings`]: #method.warnings
+    pub fn set_case_conflicts(&mut self, policy: CaseConflicts) {
+        self.case_conflicts = policy;
+    }
+
+    /// Enums without data are read from strings holding the name of the
+    /// variant, which must match exactly by default. Configuration files
+    /// written by hand often use another case, such as `level = "debug"` for
+    /// a variant named `Debug`.
+    ///
+    /// This option can be set to [`VariantMatching::CaseInsensitive`] or
+    /// [`VariantMatching::KebabCase`] (the default is
+    /// [`VariantMatching::Exact`]) to accept such strings as well. A string
+    /// that names no variant is then reported with the names that would be
+    /// accepted.
+    ///
+    /// ```rust
+    /// use serde_derive::Deserialize;
+    /// use toml::de::VariantMatching;
+    ///
+    /// #[derive(Debug, PartialEq, Deserialize)]
+    /// enum Mode {
+    ///     DryRun,
+    ///     Apply,
+    /// }
+    ///
+    /// #[derive(Deserialize)]
+    /// struct Config {
+    ///     mode: Mode,
+    /// }
+    ///
+    /// let mut de = toml::Deserializer::new("mode = 'dry-run'");
+    /// de.set_variant_matching(VariantMatching::KebabCase);
+    /// let config: Config = serde::Deserialize::deserialize(&mut de).unwrap();
+    /// assert_eq!(config.mode, Mode::DryRun);
+    ///
+    /// let mut de = toml::Deserializer::new("mode = 'dryrun'");
+    /// de.set_variant_matching(VariantMatching::KebabCase);
+    /// let err = <Config as serde::Deserialize>::deserialize(&mut de).err().unwrap();
+    /// assert_eq!(
+    ///     err.to_string(),
+    ///     "unknown variant `dryrun`, expected one of `dry-run`, `apply` \
+    ///      for key `mode` at line 1 column 8",
+    /// );
+    /// ```
+    ///
+    /// [`VariantMatching::CaseInsensitive`]: enum.VariantMatching.html#variant.CaseInsensitive
+    /// [`VariantMatching::KebabCase`]: enum.VariantMatching.html#variant.KebabCase
+    /// [`VariantMatching::Exact`]: enum.VariantMatching.html#variant.Exact
+    pub fn set_variant_matching(&mut self, matching: VariantMatching) {
+        *self.ctx.variant_matching.lock().unwrap() = matching;
+    }
+
+    /// Human-edited files often quote numbers and booleans, or write an
+    /// integer where a float is expected. By default such values are type
+    /// errors.
+    ///
+    /// This option can be set to `true` (the default is `false`) to instead
+    /// coerce `"8080"` to an integer, `"true"` to a boolean and `3` to a
+    /// float when the type being deserialized asks for one. Each coercion is
+    /// recorded as a warning, available from [`warnings`].
+    ///
+    /// [`warnings`]: #method.warnings
+    pub fn set_lenient_coercion(&mut self, lenient: bool) {
+        self.ctx
+            .lenient_coercion
+            .store(lenient, atomic::Ordering::Relaxed);
+    }
+
+    /// Configuration migrated from INI-style formats often writes booleans
+    /// as `yes` and `no`, `on` and `off`, or `1` and `0`, which TOML does
+    /// not accept as booleans.
+    ///
+    /// This option can be set to `true` (the default is `false`) to read
+    /// the strings `"yes"`, `"on"` and `"1"` as `true` and `"no"`, `"off"`
+    /// and `"0"` as `false`, ignoring case, along with the integers `1` and
+    /// `0`, when the type being deserialized asks for a boolean. Each use
+    /// is recorded as a warning, available from [`warnings`], so that the
+    /// file can be fixed.
+    ///
+    /// ```rust
+    /// use serde_derive::Deserialize;
+    ///
+    /// #[derive(Deserialize)]
+    /// struct Config {
+    ///     verbose: bool,
+    ///     color: bool,
+    /// }
+    ///
+    /// let mut de = toml::Deserializer::new("verbose = 'yes'\ncolor = 0\n");
+    /// de.set_legacy_booleans(true);
+    /// let config: Config = serde::Deserialize::deserialize(&mut de).unwrap();
+    /// assert!(config.verbose && !config.color);
+    /// assert_eq!(
+    ///     de.warnings()[0].to_string(),
+    ///     "coerced string \"yes\" to a boolean at line 1 column 11",
+    /// );
+    /// ```
+    ///
+    /// [`warnings`]: #method.warnings
+    pub fn set_legacy_booleans(&mut self, legacy: bool) {
+        self.ctx
+            .legacy_booleans
+            .store(legacy, atomic::Ordering::Relaxed);
+    }
+
+    /// TOML integers are 64-bit signed integers, so a `u64` above
+    /// `i64::MAX` or an `i128` or `u128` outside that range cannot be
+    /// written as one. `Serializer::big_integers` can write them as strings
+    /// of decimal digits instead.
+    ///
+    /// This option can be set to `true` (the default is `false`) to read
+    /// such strings back when the type being deserialized asks for a `u64`,
+    /// `i128` or `u128`. Integers are read as usual either way, and a
+    /// string that is not a decimal integer in range of the type is an
+    /// error.
+    ///
+    /// ```rust
+    /// use serde_derive::Deserialize;
+    ///
+    /// #[derive(Deserialize)]
+    /// struct Ledger {
+    ///     balance: i128,
+    ///     limit: i128,
+    /// }
+    ///
+    /// let mut de = toml::Deserializer::new("balance = '-170141183460469231731687303715884105728'\nlimit = 100\n");
+    /// de.set_big_integer_strings(true);
+    /// let ledger: Ledger = serde::Deserialize::deserialize(&mut de).unwrap();
+    /// assert_eq!(ledger.balance, i128::MIN);
+    /// assert_eq!(ledger.limit, 100);
+    /// ```
+    pub fn set_big_integer_strings(&mut self, allow: bool) {
+        self.ctx
+            .big_integer_strings
+            .store(allow, atomic::Ordering::Relaxed);
+    }
+
+    /// Integers out of range of an `i64`, and floats an `f64` cannot hold
+    /// exactly, are an error or get rounded by default.
+    ///
+    /// This option keeps the text of such numbers instead, as
+    /// `Value::RawNumber` when read into a `Value`, so that they are written
+    /// back unchanged. Typed fields still read them as numbers: an integer
+    /// field of 128 bits reads integers that fit in it, and a float field
+    /// the nearest float.
+    ///
+    /// ```rust
+    /// use toml::Value;
+    ///
+    /// let mut de = toml::Deserializer::new("id = 98765432109876543210\nn = 1\npi = 3.14159265358979323846264338\n");
+    /// de.set_raw_numbers(true);
+    /// let value: Value = serde::Deserialize::deserialize(&mut de).unwrap();
+    /// assert_eq!(value["id"].as_raw_number(), Some("98765432109876543210"));
+    /// assert_eq!(value["n"].as_integer(), Some(1));
+    /// assert_eq!(
+    ///     toml::to_string(&value).unwrap(),
+    ///     "id = 98765432109876543210\nn = 1\npi = 3.14159265358979323846264338\n",
+    /// );
+    /// ```
+    #[cfg(feature = "arbitrary_precision")]
+    pub fn set_raw_numbers(&mut self, raw: bool) {
+        self.ctx.raw_numbers.store(raw, atomic::Ordering::Relaxed);
+    }
+
+    /// Parsing a very large input, such as a generated data dump, can take a
+    /// while.
+    ///
+    /// This option sets a callback that is called with the number of bytes
+    /// parsed so far and the length of the input, every 64 KiB or so and
+    /// once more when the whole input has been parsed. Returning `false`
+    /// from it cancels parsing, which then fails with an error for which
+    /// [`Error::is_cancelled`] is true.
+    ///
+    /// ```rust
+    /// let input = "a = 1\n".repeat(100_000);
+    /// let mut calls = 0;
+    /// let mut de = toml::Deserializer::new(&input);
+    /// de.set_progress(|done, total| {
+    ///     assert!(done <= total);
+    ///     calls += 1;
+    ///     calls < 3
+    /// });
+    /// let err = serde::Deserialize::deserialize(&mut de)
+    ///     .map(|_: toml::Value| ())
+    ///     .unwrap_err();
+    /// assert!(err.is_cancelled());
+    /// ```
+    ///
+    /// [`Error::is_cancelled`]: struct.Error.html#method.is_cancelled
+    pub fn set_progress<F>(&mut self, progress: F)
+    where
+        F: FnMut(usize, usize) -> bool + Send + 'a,
+    {
+        self.progress = Some((Mutex::new(Box::new(progress)), 0));
+    }
+
+    /// Sets a callback that is given each value read, other than arrays
+    /// and tables, with its path, and returns the value to deserialize in its
+    /// place.
+    ///
+    /// This applies the same change to every value read, such as trimming
+    /// strings or expanding `~` in paths, without changing the types being
+    /// deserialized. The values inside arrays and inline tables are passed
+    /// one by one, and after any `${path}` references in them are replaced.
+    /// The callback may return any value, including an array or a table, and
+    /// errors about what it returns point at the value it replaced.
+    ///
+    /// ```rust
+    /// use toml::Value;
+    ///
+    /// let input = "name = '  app '\n[paths]\nlogs = '~/logs'\nports = [80, 443]\n";
+    /// let mut de = toml::Deserializer::new(input);
+    /// de.set_rewrite_values(|path, value| match value {
+    ///     Value::String(s) if path.to_string().starts_with("paths.") => {
+    ///         Value::from(s.replacen('~', "/home/app", 1))
+    ///     }
+    ///     Value::String(s) => Value::from(s.trim()),
+    ///     value => value,
+    /// });
+    /// let value: Value = serde::Deserialize::deserialize(&mut de).unwrap();
+    /// assert_eq!(value["name"].as_str(), Some("app"));
+    /// assert_eq!(value["paths"]["logs"].as_str(), Some("/home/app/logs"));
+    /// ```
+    pub fn set_rewrite_values<F>(&mut self, rewrite: F)
+    where
+        F: FnMut(&KeyPath, crate::Value) -> crate::Value + Send + 'a,
+    {
+        self.rewrite = Some(Mutex::new(Box::new(rewrite)));
+    }
+
+    /// Parsing and deserializing started from an interactive application
+    /// may need to be stopped before they finish, for example when the user
+    /// closes the file being loaded.
+    ///
+    /// This option sets a flag, which another thread can set to `true` to
+    /// cancel. It is checked after each line is parsed and before each key
+    /// of the top-level table is deserialized, and once it is set the
+    /// deserializer fails with an error for which [`Error::is_cancelled`]
+    /// is true.
+    ///
+    /// ```rust
+    /// use std::sync::atomic::{AtomicBool, Ordering};
+    /// use std::sync::Arc;
+    ///
+    /// let cancel = Arc::new(AtomicBool::new(false));
+    /// let mut de = toml::Deserializer::new("a = 1\nb = 2\n");
+    /// de.set_cancel_flag(cancel.clone());
+    ///
+    /// cancel.store(true, Ordering::Relaxed);
+    /// let err = serde::Deserialize::deserialize(&mut de)
+    ///     .map(|_: toml::Value| ())
+    ///     .unwrap_err();
+    /// assert!(err.is_cancelled());
+    /// ```
+    ///
+    /// [`Error::is_cancelled`]: struct.Error.html#method.is_cancelled
+    pub fn set_cancel_flag(&mut self, cancel: Arc<AtomicBool>) {
+        self.cancel = Some(cancel);
+    }
+
+    /// Starts over on a new input, so that one deserializer can be used
+    /// for many documents in turn.
+    ///
+    /// The options set and the progress callback and cancel flag, if any,
+    /// are kept, as are the buffers allocated while parsing earlier inputs.
+    /// The warnings and fixes found in earlier inputs are discarded.
+    ///
+    /// ```rust
+    /// use serde::Deserialize;
+    ///
+    /// let sources = ["verbose\n", "quiet\n"];
+    /// let mut de = toml::Deserializer::new("");
+    /// de.set_allow_bare_boolean_keys(true);
+    /// for source in &sources {
+    ///     de.reset(source);
+    ///     let value = toml::Value::deserialize(&mut de).unwrap();
+    ///     assert_eq!(value.as_table().unwrap().len(), 1);
+    ///     assert_eq!(de.warnings().len(), 1);
+    /// }
+    /// ```
+    pub fn reset(&mut self, input: &'a str) {
+        self.input = input;
+        self.tokens.reset(input);
+        if let Some((_, ref mut reported)) = self.progress {
+            *reported = 0;
+        }
+        match Arc::get_mut(&mut self.ctx) {
+            Some(ctx) => {
+                ctx.warnings.get_mut().unwrap().clear();
+                ctx.fixes.get_mut().unwrap().clear();
+                if let Some(ref mut tracker) = *ctx.usage.get_mut().unwrap() {
+                    *tracker = UsageTracker::default();
+                }
+            }
+            // A value deserializer handed out earlier is still alive.
+            None => {
+                let ctx = Context::default();
+                ctx.lenient_coercion.store(
+                    self.ctx.lenient_coercion.load(atomic::Ordering::Relaxed),
+                    atomic::Ordering::Relaxed,
+                );
+                ctx.legacy_booleans.store(
+                    self.ctx.legacy_booleans.load(atomic::Ordering::Relaxed),
+                    atomic::Ordering::Relaxed,
+                );
+                ctx.big_integer_strings.store(
+                    self.ctx.big_integer_strings.load(atomic::Ordering::Relaxed),
+                    atomic::Ordering::Relaxed,
+                );
+                ctx.raw_numbers.store(
+                    self.ctx.raw_numbers.load(atomic::Ordering::Relaxed),
+                    atomic::Ordering::Relaxed,
+                );
+                *ctx.variant_matching.lock().unwrap() = *self.ctx.variant_matching.lock().unwrap();
+                if self.ctx.usage.lock().unwrap().is_some() {
+                    *ctx.usage.lock().unwrap() = Some(UsageTracker::default());
+                }
+                self.ctx = Arc::new(ctx);
+            }
+        }
+    }
+
+    fn is_cancelled(&self) -> bool {
+        match self.cancel {
+            Some(ref cancel) => cancel.load(atomic::Ordering::Relaxed),
+            None => false,
+        }
+    }
+
+    /// Calls the progress callback, if any, when another interval of input
+    /// has been parsed or `done` is set, and checks the cancel flag.
+    fn report_progress(&mut self, done: bool) -> Result<(), Error> {
+        let at = self.tokens.current();
+        let total = self.input.len();
+        if self.is_cancelled() {
+            return Err(self.error(at, ErrorKind::Cancelled));
+        }
+        if let Some((ref mut progress, ref mut reported)) = self.progress {
+            if done || at >= *reported + PROGRESS_INTERVAL {
+                *reported = at;
+                if !(progress.get_mut().unwrap())(at, total) {
+                    return Err(self.error(at, ErrorKind::Cancelled));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Tools that check configuration files want to know which keys of a
+    /// file the program actually read, to report misspelled or obsolete
+    /// keys that would otherwise be skipped without a word.
+    ///
+    /// This option can be set to `true` (the default is `false`) to record
+    /// which keys were read, which were skipped and which struct fields had
+    /// no key, available from [`key_usage`] after deserializing.
+    ///
+    /// [`key_usage`]: #method.key_usage
+    pub fn set_track_key_usage(&mut self, track: bool) {
+        let usage = if track {
+            Some(UsageTracker::default())
+        } else {
+            None
+        };
+        *self.ctx.usage.lock().unwrap() = usage;
+    }
+
+    /// Returns which keys of the input were read by the last deserialization
+    /// of a document, if [`set_track_key_usage`] is enabled.
+    ///
+    /// ```rust
+    /// use serde_derive::Deserialize;
+    ///
+    /// #[derive(Deserialize)]
+    /// struct Config {
+    ///     name: String,
+    ///     port: Option<u16>,
+    /// }
+    ///
+    /// let mut de = toml::Deserializer::new("name = 'demo'\nprot = 80\n");
+    /// de.set_track_key_usage(true);
+    /// let _config: Config = serde::Deserialize::deserialize(&mut de).unwrap();
+    ///
+    /// let usage = de.key_usage();
+    /// assert_eq!(usage.consumed()[0].to_string(), "name");
+    /// assert_eq!(usage.ignored()[0].to_string(), "prot");
+    /// assert_eq!(usage.defaulted()[0].to_string(), "port");
+    /// ```
+    ///
+    /// [`set_track_key_usage`]: #method.set_track_key_usage
+    pub fn key_usage(&self) -> KeyUsage {
+        match *self.ctx.usage.lock().unwrap() {
+            Some(ref tracker) => tracker.usage.clone(),
+            None => KeyUsage::default(),
+        }
+    }
+
+    /// Returns the problems that were tolerated while deserializing, in the
+    /// order they were encountered.
+    pub fn warnings(&self) -> Vec<Warning> {
+        self.ctx
+            .warnings
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|&(at, ref message)| {
+                let (line, col) = self.to_linecol(at);
+                Warning {
+                    message: message.clone(),
+                    at,
+                    line,
+                    col,
+                }
+            })
+            .collect()
+    }
+
+    /// Parses the input as a single TOML value, such as `8080`, `"text"` or
+    /// `[1, 2]`, rather than as a document, and deserializes it into a `T`.
+    ///
+    /// Whitespace and comments may surround the value, but anything else
+    /// after it is an error.
+    ///
+    /// ```rust
+    /// let mut de = toml::Deserializer::new("[8080, 8443] # ports");
+    /// let ports: Vec<u16> = de.parse_value().unwrap();
+    /// assert_eq!(ports, [8080, 8443]);
+    /// ```
+    pub fn parse_value<T>(&mut self) -> Result<T, Error>
+    where
+        T: de::Deserialize<'a>,
+    {
+        self.eat_blank_lines()?;
+        let value = self.value()?;
+        self.expect_end()?;
+        self.fragment(value)
+    }
+
+    /// Parses the input as a single key/value pair, such as `log.level =
+    /// "debug"`, rather than as a document. Returns the key, which may be
+    /// dotted, and the value deserialized into a `T`.
+    ///
+    /// Whitespace and comments may surround the pair, but anything else
+    /// after it is an error.
+    ///
+    /// ```rust
+    /// let mut de = toml::Deserializer::new("log.level = 'debug'");
+    /// let (key, value): (_, String) = de.parse_key_value().unwrap();
+    /// assert_eq!(key.to_string(), "log.level");
+    /// assert_eq!(value, "debug");
+    /// ```
+    pub fn parse_key_value<T>(&mut self) -> Result<(KeyPath, T), Error>
+    where
+        T: de::Deserialize<'a>,
+    {
+        self.eat_blank_lines()?;
+        let key = self.dotted_key()?;
+        self.eat_whitespace()?;
+        self.expect(Token::Equals)?;
+        self.eat_whitespace()?;
+        let value = self.value()?;
+        self.expect_end()?;
+        let path = key
+            .into_iter()
+            .map(|(_, key)| PathSegment::Key(key.into_owned()))
+            .collect::<Vec<_>>();
+        Ok((KeyPath::from(path), self.fragment(value)?))
+    }
+
+    fn expect_end(&mut self) -> Result<(), Error> {
+        self.eat_blank_lines()?;
+        match self.peek()? {
+            Some((span, token)) => Err(self.error(
+                span.start,
+                ErrorKind::Wanted {
+                    expected: "end of input",
+                    found: token.describe(),
+                },
+            )),
+            None => Ok(()),
+        }
+    }
+
+    fn fragment<T>(&self, value: Value<'a>) -> Result<T, Error>
+    where
+        T: de::Deserialize<'a>,
+    {
+        let at = value.start;
+        T::deserialize(ValueDeserializer::new(value, self.ctx.clone())).map_err(|mut err| {
+            err.fix_offset(|| Some(at));
+            err.fix_linecol(|at| self.to_linecol(at));
+            err
+        })
+    }
+
     fn tables(&mut self) -> Result<Vec<Table<'a>>, Error> {
         let mut tables = Vec::new();
         let mut cur_table = Table {
@@ -1329,7 +3003,7 @@ impl<'a> Deserializer<'a> {
                         array,
                     };
                     loop {
-                        let part = header.next().map_err(|e| self.token_error(e));
+                        let part = header.next().map_err(|e| self.key_token_error(e));
                         match part? {
                             Some(part) => cur_table.header.push(part),
                             None => break,
@@ -1343,32 +3017,296 @@ impl<'a> Deserializer<'a> {
                     self.add_dotted_key(key, value, cur_table.values.as_mut().unwrap())?;
                 }
             }
+            self.report_progress(false)?;
         }
+        self.report_progress(true)?;
         if !cur_table.header.is_empty() || cur_table.values.is_some() {
             tables.push(cur_table);
         }
         Ok(tables)
     }
 
-    fn line(&mut self) -> Result<Option<Line<'a>>, Error> {
-        loop {
-            self.eat_whitespace()?;
-            if self.eat_comment()? {
-                continue;
+    /// Checks that no table is defined twice, or in two different ways, and
+    /// merges tables defined again when `merge_reopened_tables` is set. Keys
+    /// differing only in case are checked for here too, as both need to know
+    /// which array of tables element each table is in.
+    fn check_redefinitions(&self, tables: &mut Vec<Table<'a>>) -> Result<(), Error> {
+        let mut defs = Definitions::new();
+        let mut cases = KeyCases::new();
+        let mut merged = vec![false; tables.len()];
+        for i in 0..tables.len() {
+            let at = tables[i].at;
+            let array = tables[i].array;
+            let path = tables[i]
+                .header
+                .iter()
+                .map(|k| k.1.clone())
+                .collect::<Vec<_>>();
+            let how = if array {
+                Definition::ArrayOfTables
+            } else {
+                Definition::Table
+            };
+            let defined = Defined {
+                how,
+                at,
+                implicit: false,
+                table: i,
+            };
+
+            for len in 1..path.len() {
+                match defs.get(&path[..len]) {
+                    Some(first) if first.how == Definition::InlineTable => {
+                        return Err(self.redefinition(&path[..len], *first, Definition::Table, at));
+                    }
+                    Some(_) => {}
+                    None => {
+                        let implicit = Defined {
+                            how: Definition::Table,
+                            implicit: true,
+                            ..defined
+                        };
+                        defs.insert(path[..len].to_vec(), implicit);
+                    }
+                }
             }
-            if self.eat(Token::Newline)? {
-                continue;
+
+            let mut target = i;
+            match defs.get(&path).cloned() {
+                None => {
+                    defs.insert(path.clone(), defined);
+                }
+                Some(first) if first.how == how && array => {
+                    // A new element, whose tables are all new.
+                    defs.retain(|k, _| k.len() <= path.len() || !k.starts_with(&path));
+                    cases.retain(|k, _| k.0.len() < path.len() || !k.0.starts_with(&path));
+                    defs.insert(path.clone(), defined);
+                }
+                Some(first) if first.how == how && first.implicit => {
+                    defs.insert(path.clone(), defined);
+                }
+                Some(first) if first.how == how => {
+                    if self.merge_reopened_tables {
+                        let line = self.to_linecol(first.at).0 + 1;
+                        self.ctx.warn(
+                            at,
+                            format!(
+                                "table `{}` is defined again, merged into its definition on line {}",
+                                join_path(&path),
+                                line
+                            ),
+                        );
+                        let values = tables[i].values.take().unwrap_or_default();
+                        let into = tables[first.table].values.get_or_insert_with(Vec::new);
+                        self.merge_values(values, into)?;
+                        merged[i] = true;
+                        target = first.table;
+                    } else if !self.allow_duplciate_after_longer_table {
+                        return Err(self.redefinition(&path, first, how, at));
+                    }
+                }
+                Some(first) => {
+                    return Err(self.redefinition(&path, first, how, at));
+                }
+            }
+
+            if let Some(ref values) = tables[target].values {
+                self.check_dotted_redefinitions(&path, values, &mut defs)?;
+            }
+
+            if self.case_conflicts != CaseConflicts::Allow {
+                for (len, &(span, ref key)) in tables[i].header.iter().enumerate() {
+                    self.check_key_case(&path[..len], key, span.start, &mut cases)?;
+                }
+                if let Some(ref values) = tables[target].values {
+                    self.check_pairs_case(&path, values, &mut cases)?;
+                }
             }
-            break;
         }
 
-        match self.peek()? {
+        let mut merged = merged.into_iter();
+        tables.retain(|_| !merged.next().unwrap());
+        Ok(())
+    }
+
+    /// Records the tables defined by dotted keys and inline tables among
+    /// `values`, which are in the table at `path`, checking that none was
+    /// already defined by a header.
+    fn check_dotted_redefinitions(
+        &self,
+        path: &[Cow<'a, str>],
+        values: &[TablePair<'a>],
+        defs: &mut Definitions<'a>,
+    ) -> Result<(), Error> {
+        for ((span, key), value) in values {
+            let mut key_path = path.to_vec();
+            key_path.push(key.clone());
+            let how = match value.e {
+                E::DottedTable(_) => Definition::DottedKeys,
+                E::InlineTable(_) => Definition::InlineTable,
+                _ => continue,
+            };
+            match defs.get(&key_path) {
+                Some(&first)
+                    if how == Definition::DottedKeys
+                        && matches!(first.how, Definition::Table | Definition::ArrayOfTables) =>
+                {
+                    return Err(self.redefinition(&key_path, first, how, span.start));
+                }
+                Some(_) => {}
+                None => {
+                    let defined = Defined {
+                        how,
+                        at: span.start,
+                        implicit: false,
+                        table: 0,
+                    };
+                    defs.insert(key_path.clone(), defined);
+                }
+            }
+            if let E::DottedTable(ref values) = value.e {
+                self.check_dotted_redefinitions(&key_path, values, defs)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks the keys among `values`, which are in the table at `path`, and
+    /// in the tables they define for ones differing only in case.
+    fn check_pairs_case(
+        &self,
+        path: &[Cow<'a, str>],
+        values: &[TablePair<'a>],
+        cases: &mut KeyCases<'a>,
+    ) -> Result<(), Error> {
+        for ((span, key), value) in values {
+            self.check_key_case(path, key, span.start, cases)?;
+            let mut key_path = path.to_vec();
+            key_path.push(key.clone());
+            match value.e {
+                E::DottedTable(ref values) | E::InlineTable(ref values) => {
+                    self.check_pairs_case(&key_path, values, cases)?;
+                }
+                E::Array(ref elements) => {
+                    for element in elements {
+                        if let E::InlineTable(ref values) = element.e {
+                            self.check_pairs_case(&[], values, &mut KeyCases::new())?;
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn check_key_case(
+        &self,
+        path: &[Cow<'a, str>],
+        key: &str,
+        at: usize,
+        cases: &mut KeyCases<'a>,
+    ) -> Result<(), Error> {
+        let folded = (path.to_vec(), key.to_lowercase());
+        let (first, first_at) = match cases.get(&folded) {
+            Some((first, _)) if first == key => return Ok(()),
+            Some((first, first_at)) => (first.clone(), *first_at),
+            None => {
+                cases.insert(folded, (key.to_string(), at));
+                return Ok(());
+            }
+        };
+        let kind = ErrorKind::KeyCaseConflict {
+            key: key.to_string(),
+            first,
+            first_line: self.to_linecol(first_at).0 + 1,
+        };
+        if self.case_conflicts == CaseConflicts::Error {
+            return Err(self.error(at, kind));
+        }
+        self.ctx.warn(at, Error::from_kind(None, kind).to_string());
+        Ok(())
+    }
+
+    /// Adds the keys of a table defined again to its first definition.
+    fn merge_values(
+        &self,
+        values: Vec<TablePair<'a>>,
+        into: &mut Vec<TablePair<'a>>,
+    ) -> Result<(), Error> {
+        for (key, value) in values {
+            let existing = into.iter_mut().find(|(k, v)| {
+                k.1 == key.1
+                    && matches!(v.e, E::DottedTable(_))
+                    && matches!(value.e, E::DottedTable(_))
+            });
+            match (existing, value.e) {
+                (
+                    Some((
+                        _,
+                        Value {
+                            e: E::DottedTable(ref mut into),
+                            ..
+                        },
+                    )),
+                    E::DottedTable(values),
+                ) => {
+                    self.merge_values(values, into)?;
+                }
+                (_, e) => {
+                    let value = Value { e, ..value };
+                    self.add_dotted_key(vec![key], value, into)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn redefinition(
+        &self,
+        path: &[Cow<'a, str>],
+        first: Defined,
+        second: Definition,
+        at: usize,
+    ) -> Error {
+        let kind = ErrorKind::TableRedefined {
+            name: join_path(path),
+            first: first.how,
+            first_line: self.to_linecol(first.at).0 + 1,
+            second,
+        };
+        self.error(at, kind)
+    }
+
+    pub(crate) fn line(&mut self) -> Result<Option<Line<'a>>, Error> {
+        // Anything but a table header at the start of a line is read as a key.
+        self.skip_blank_lines()
+            .map_err(|e| self.key_token_error(e))?;
+
+        match self.tokens.peek().map_err(|e| self.key_token_error(e))? {
             Some((_, Token::LeftBracket)) => self.table_header().map(Some),
             Some(_) => self.key_value().map(Some),
             None => Ok(None),
         }
     }
 
+    fn eat_blank_lines(&mut self) -> Result<(), Error> {
+        self.skip_blank_lines().map_err(|e| self.token_error(e))
+    }
+
+    fn skip_blank_lines(&mut self) -> Result<(), TokenError> {
+        loop {
+            self.tokens.eat_whitespace()?;
+            if self.tokens.eat_comment()? {
+                continue;
+            }
+            if self.tokens.eat(Token::Newline)? {
+                continue;
+            }
+            return Ok(());
+        }
+    }
+
     fn table_header(&mut self) -> Result<Line<'a>, Error> {
         let start = self.tokens.current();
         self.expect(Token::LeftBracket)?;
@@ -1378,7 +3316,7 @@ impl<'a> Deserializer<'a> {
             self.tokens.skip_to_newline();
         } else {
             loop {
-                match self.next()? {
+                match self.tokens.next().map_err(|e| self.key_token_error(e))? {
                     Some((_, Token::RightBracket)) => {
                         if array {
                             self.eat(Token::RightBracket)?;
@@ -1401,6 +3339,11 @@ impl<'a> Deserializer<'a> {
     fn key_value(&mut self) -> Result<Line<'a>, Error> {
         let key = self.dotted_key()?;
         self.eat_whitespace()?;
+        if self.allow_bare_boolean_keys {
+            if let Some(value) = self.bare_boolean_key(&key)? {
+                return Ok(Line::KeyValue(key, value));
+            }
+        }
         self.expect(Token::Equals)?;
         self.eat_whitespace()?;
 
@@ -1413,6 +3356,84 @@ impl<'a> Deserializer<'a> {
         Ok(Line::KeyValue(key, value))
     }
 
+    /// Parses the input with every tolerance enabled, returning the fixes for
+    /// the problems that were tolerated and for duplicate keys.
+    pub(crate) fn fixes(mut self) -> Result<Vec<Fix>, Error> {
+        self.lenient_numbers = true;
+        self.allow_trailing_commas = true;
+        self.allow_bare_boolean_keys = true;
+        self.allow_unquoted_strings = true;
+        let tables = self.tables()?;
+        for table in &tables {
+            if let Some(ref values) = table.values {
+                self.duplicate_key_fixes(values);
+            }
+        }
+        let fixes = std::mem::take(&mut *self.ctx.fixes.lock().unwrap());
+        Ok(fixes)
+    }
+
+    /// Records a fix removing each later definition of a key, which is only
+    /// safe when it repeats the value of the first definition.
+    fn duplicate_key_fixes(&self, values: &[TablePair<'a>]) {
+        for (i, ((span, key), value)) in values.iter().enumerate() {
+            if let E::DottedTable(ref values) = value.e {
+                self.duplicate_key_fixes(values);
+                continue;
+            }
+            let first = values[..i]
+                .iter()
+                .find(|((_, k), v)| k == key && !matches!(v.e, E::DottedTable(_)));
+            let first = match first {
+                Some((_, first)) => first,
+                None => continue,
+            };
+            let line_start = self.input[..span.start]
+                .rfind('\n')
+                .map(|i| i + 1)
+                .unwrap_or(0);
+            let line_end = self.input[value.end..]
+                .find('\n')
+                .map(|i| value.end + i + 1)
+                .unwrap_or_else(|| self.input.len());
+            let safe = first.e.same_as(&value.e);
+            self.ctx.fixes.lock().unwrap().push(Fix::new(
+                (line_start, line_end),
+                String::new(),
+                format!("duplicate key `{}`", key),
+                safe,
+            ));
+        }
+    }
+
+    /// Reads a key with nothing after it on its line as `true`.
+    fn bare_boolean_key(
+        &mut self,
+        key: &[(Span, Cow<'a, str>)],
+    ) -> Result<Option<Value<'a>>, Error> {
+        match self.peek()? {
+            Some((_, Token::Newline)) | Some((_, Token::Comment(_))) | None => {}
+            Some(_) => return Ok(None),
+        }
+        let start = key[0].0.start;
+        let end = key[key.len() - 1].0.end;
+        let text = &self.input[start..end];
+        self.ctx.warn_fixable(
+            start,
+            format!("key `{}` has no value, read as `{} = true`", text, text),
+            (end, end),
+            " = true",
+        );
+        if !self.eat_comment()? {
+            self.eat_newline_or_eof()?;
+        }
+        Ok(Some(Value {
+            e: E::Boolean(true),
+            start,
+            end,
+        }))
+    }
+
     fn value(&mut self) -> Result<Value<'a>, Error> {
         let at = self.tokens.current();
         let value = match self.next()? {
@@ -1469,31 +3490,68 @@ impl<'a> Deserializer<'a> {
         let first_char = key.chars().next().expect("key should not be empty here");
         match first_char {
             '-' | '0'..='9' => self.number_or_date(span, key),
+            _ if self.allow_unquoted_strings => self.unquoted_string(span),
             _ => Err(self.error(at, ErrorKind::UnquotedString)),
         }
     }
 
+    /// Reads the rest of a value that was not quoted as a string, up to the
+    /// end of the line or a delimiter of the enclosing array or table.
+    fn unquoted_string(&mut self, span: Span) -> Result<Value<'a>, Error> {
+        let start = span.start;
+        let rest = &self.input[start..];
+        let len = rest
+            .find(&['\r', '\n', '#', ',', ']', '}'][..])
+            .unwrap_or(rest.len());
+        let mut end = start + rest[..len].trim_end().len();
+        // The rest of the value is read as tokens, so that text the tokenizer
+        // rejects, such as a stray control character, is still an error. A
+        // token that runs past the end, such as a quoted string holding a
+        // `#`, becomes part of the value.
+        while self.tokens.current() < end {
+            if self.next()?.is_none() {
+                break;
+            }
+        }
+        end = end.max(self.tokens.current());
+        let text = &self.input[start..end];
+        self.ctx.warn_fixable(
+            start,
+            format!("unquoted string `{}`", text),
+            (start, end),
+            &crate::Value::String(text.to_string()).to_string(),
+        );
+        Ok(Value {
+            e: E::String(Cow::Borrowed(text)),
+            start,
+            end,
+        })
+    }
+
     fn number_or_date(&mut self, span: Span, s: &'a str) -> Result<Value<'a>, Error> {
-        if s.contains('T')
+        let datetime = if s.contains('T')
             || s.contains('t')
             || (s.len() > 1 && s[1..].contains('-') && !s.contains("e-") && !s.contains("E-"))
         {
-            self.datetime(span, s, false)
-                .map(|(Span { start, end }, d)| Value {
-                    e: E::Datetime(d),
-                    start,
-                    end,
-                })
+            self.datetime(span, s, false)?
         } else if self.eat(Token::Colon)? {
-            self.datetime(span, s, true)
-                .map(|(Span { start, end }, d)| Value {
-                    e: E::Datetime(d),
-                    start,
-                    end,
-                })
+            self.datetime(span, s, true)?
         } else {
-            self.number(span, s)
+            return self.number(span, s);
+        };
+        let (Span { start, end }, d) = datetime;
+        // Malformed datetimes are reported when deserialized, but values that
+        // are out of range, such as `2024-02-30`, are rejected here.
+        if let Err(e) = d.parse::<datetime::Datetime>() {
+            if let Some(reason) = e.out_of_range_reason() {
+                return Err(self.error(start, ErrorKind::InvalidDateTime(reason.to_string())));
+            }
         }
+        Ok(Value {
+            e: E::Datetime(d.into()),
+            start,
+            end,
+        })
     }
 
     /// Returns a string or table value type.
@@ -1561,22 +3619,37 @@ impl<'a> Deserializer<'a> {
         } else if s.starts_with("0b") {
             self.integer(&s[2..], 2).map(to_integer)
         } else if s.contains('e') || s.contains('E') {
-            self.float(s, None).map(|f| Value {
-                e: E::Float(f),
-                start,
-                end,
-            })
+            let f = self.float(s, None)?;
+            Ok(self.float_value(start, f))
         } else if self.eat(Token::Period)? {
             let at = self.tokens.current();
-            match self.next()? {
-                Some((Span { start, end }, Token::Keylike(after))) => {
-                    self.float(s, Some(after)).map(|f| Value {
+            match self.peek()? {
+                Some((_, Token::Keylike(after))) => {
+                    self.next()?;
+                    let f = self.float(s, Some(after))?;
+                    Ok(self.float_value(start, f))
+                }
+                _ => {
+                    let suggestion = format!("{}.0", s);
+                    if !self.lenient_numbers {
+                        return Err(self.error(at, ErrorKind::TrailingDecimalPoint(suggestion)));
+                    }
+                    let f = self.float(s, None)?;
+                    self.ctx.warn_fixable(
+                        start,
+                        format!(
+                            "missing digits after decimal point in `{}.`, read as {}",
+                            s, suggestion
+                        ),
+                        (at, at),
+                        "0",
+                    );
+                    Ok(Value {
                         e: E::Float(f),
                         start,
-                        end,
+                        end: at,
                     })
                 }
-                _ => Err(self.error(at, ErrorKind::NumberInvalid)),
             }
         } else if s == "inf" {
             Ok(Value {
@@ -1603,10 +3676,32 @@ impl<'a> Deserializer<'a> {
                 end,
             })
         } else {
-            self.integer(s, 10).map(to_integer)
+            match self.checked_integer(s, 10)? {
+                Some(i) => Ok(to_integer(i)),
+                None if self.ctx.raw_numbers.load(atomic::Ordering::Relaxed) => Ok(Value {
+                    e: E::RawNumber(self.input[start..end].into()),
+                    start,
+                    end,
+                }),
+                None => Err(self.error(self.tokens.substr_offset(s), ErrorKind::NumberInvalid)),
+            }
         }
     }
 
+    /// Returns the value of a float ending at the current token, or its
+    /// text if raw numbers are read and `f` does not hold it exactly.
+    fn float_value(&mut self, start: usize, f: f64) -> Value<'a> {
+        let end = self.tokens.current();
+        let text = &self.input[start..end];
+        let e = if self.ctx.raw_numbers.load(atomic::Ordering::Relaxed) && !float_is_exact(text, f)
+        {
+            E::RawNumber(text.into())
+        } else {
+            E::Float(f)
+        };
+        Value { e, start, end }
+    }
+
     fn number_leading_plus(&mut self, Span { start, .. }: Span) -> Result<Value<'a>, Error> {
         let start_token = self.tokens.current();
         match self.next()? {
@@ -1616,6 +3711,14 @@ impl<'a> Deserializer<'a> {
     }
 
     fn integer(&self, s: &'a str, radix: u32) -> Result<i64, Error> {
+        let start = self.tokens.substr_offset(s);
+        self.checked_integer(s, radix)?
+            .ok_or_else(|| self.error(start, ErrorKind::NumberInvalid))
+    }
+
+    /// Like `integer`, but returns `None` for an integer out of range of an
+    /// `i64`.
+    fn checked_integer(&self, s: &'a str, radix: u32) -> Result<Option<i64>, Error> {
         let allow_sign = radix == 10;
         let allow_leading_zeros = radix != 10;
         let (prefix, suffix) = self.parse_integer(s, allow_sign, allow_leading_zeros, radix)?;
@@ -1623,8 +3726,7 @@ impl<'a> Deserializer<'a> {
         if suffix != "" {
             return Err(self.error(start, ErrorKind::NumberInvalid));
         }
-        i64::from_str_radix(&prefix.replace("_", "").trim_start_matches('+'), radix)
-            .map_err(|_e| self.error(start, ErrorKind::NumberInvalid))
+        Ok(i64::from_str_radix(&prefix.replace("_", "").trim_start_matches('+'), radix).ok())
     }
 
     fn parse_integer(
@@ -1638,6 +3740,7 @@ impl<'a> Deserializer<'a> {
 
         let mut first = true;
         let mut first_zero = false;
+        let mut leading_zeros = false;
         let mut underscore = false;
         let mut end = s.len();
         for (i, c) in s.char_indices() {
@@ -1649,8 +3752,16 @@ impl<'a> Deserializer<'a> {
             if c == '0' && first {
                 first_zero = true;
             } else if c.is_digit(radix) {
-                if !first && first_zero && !allow_leading_zeros {
-                    return Err(self.error(at, ErrorKind::NumberInvalid));
+                if !first && first_zero && !allow_leading_zeros && !leading_zeros {
+                    if !self.lenient_numbers {
+                        // A leading `+` is a token of its own, just before `s`.
+                        let mut suggestion = strip_leading_zeros(s);
+                        if allow_sign && self.input[..start].ends_with('+') {
+                            suggestion.insert(0, '+');
+                        }
+                        return Err(self.error(at, ErrorKind::LeadingZeros(suggestion)));
+                    }
+                    leading_zeros = true;
                 }
                 underscore = false;
             } else if c == '_' && first {
@@ -1666,11 +3777,30 @@ impl<'a> Deserializer<'a> {
         if first || underscore {
             return Err(self.error(start, ErrorKind::NumberInvalid));
         }
+        if leading_zeros {
+            let stripped = strip_leading_zeros(&s[..end]);
+            self.ctx.warn_fixable(
+                start,
+                format!("leading zeros in `{}`, read as {}", &s[..end], stripped),
+                (start, start + end),
+                &stripped,
+            );
+        }
         Ok((&s[..end], &s[end..]))
     }
 
     fn float(&mut self, s: &'a str, after_decimal: Option<&'a str>) -> Result<f64, Error> {
-        let (integral, mut suffix) = self.parse_integer(s, true, false, 10)?;
+        let (integral, mut suffix) = self.parse_integer(s, true, false, 10).map_err(|mut e| {
+            // Suggest the whole float, not just the part before the
+            // decimal point.
+            if let (ErrorKind::LeadingZeros(ref mut suggestion), Some(after)) =
+                (&mut e.inner.kind, after_decimal)
+            {
+                suggestion.push('.');
+                suggestion.push_str(after);
+            }
+            e
+        })?;
         let start = self.tokens.substr_offset(integral);
 
         let mut fraction = None;
@@ -1719,7 +3849,7 @@ impl<'a> Deserializer<'a> {
             .parse()
             .map_err(|_e| self.error(start, ErrorKind::NumberInvalid))
             .and_then(|n: f64| {
-                if n.is_finite() {
+                if n.is_finite() || self.ctx.raw_numbers.load(atomic::Ordering::Relaxed) {
                     Ok(n)
                 } else {
                     Err(self.error(start, ErrorKind::NumberInvalid))
@@ -1812,8 +3942,20 @@ impl<'a> Deserializer<'a> {
             if let Some(span) = self.eat_spanned(Token::RightBrace)? {
                 return Ok((span, ret));
             }
+            let comma = self.tokens.current();
             self.expect(Token::Comma)?;
             self.eat_whitespace()?;
+            if self.allow_trailing_commas {
+                if let Some(span) = self.eat_spanned(Token::RightBrace)? {
+                    self.ctx.warn_fixable(
+                        comma,
+                        "trailing comma in inline table".to_string(),
+                        (comma, comma + 1),
+                        "",
+                    );
+                    return Ok((span, ret));
+                }
+            }
         }
     }
 
@@ -1850,14 +3992,18 @@ impl<'a> Deserializer<'a> {
     }
 
     fn table_key(&mut self) -> Result<(Span, Cow<'a, str>), Error> {
-        self.tokens.table_key().map_err(|e| self.token_error(e))
+        self.tokens.table_key().map_err(|e| self.key_token_error(e))
     }
 
     fn dotted_key(&mut self) -> Result<Vec<(Span, Cow<'a, str>)>, Error> {
         let mut result = Vec::new();
         result.push(self.table_key()?);
         self.eat_whitespace()?;
-        while self.eat(Token::Period)? {
+        while self
+            .tokens
+            .eat(Token::Period)
+            .map_err(|e| self.key_token_error(e))?
+        {
             self.eat_whitespace()?;
             result.push(self.table_key()?);
             self.eat_whitespace()?;
@@ -1885,7 +4031,14 @@ impl<'a> Deserializer<'a> {
     ) -> Result<(), Error> {
         let key = key_parts.remove(0);
         if key_parts.is_empty() {
-            values.push((key, value));
+            let existing = match self.duplicate_keys {
+                DuplicateKeys::Error => None,
+                _ => values.iter().position(|(k, _)| k.1 == key.1),
+            };
+            match existing {
+                Some(i) => self.replace_duplicate(&mut values[i], (key, value)),
+                None => values.push((key, value)),
+            }
             return Ok(());
         }
         match values.iter_mut().find(|&&mut (ref k, _)| *k.1 == key.1) {
@@ -1924,6 +4077,21 @@ impl<'a> Deserializer<'a> {
         Ok(())
     }
 
+    /// Keeps one of two definitions of a key, as set by `duplicate_keys`.
+    fn replace_duplicate(&self, existing: &mut TablePair<'a>, new: TablePair<'a>) {
+        let at = (new.0).0.start;
+        let message = if self.duplicate_keys == DuplicateKeys::LastWins {
+            *existing = new;
+            format!(
+                "duplicate key `{}`, replacing its earlier value",
+                existing.0 .1
+            )
+        } else {
+            format!("duplicate key `{}`, ignoring its later value", (new.0).1)
+        };
+        self.ctx.warn(at, message);
+    }
+
     fn eat_whitespace(&mut self) -> Result<(), Error> {
         self.tokens
             .eat_whitespace()
@@ -1974,7 +4142,7 @@ impl<'a> Deserializer<'a> {
         self.error(self.input.len(), ErrorKind::UnexpectedEof)
     }
 
-    fn token_error(&self, error: TokenError) -> Error {
+    pub(crate) fn token_error(&self, error: TokenError) -> Error {
         match error {
             TokenError::InvalidCharInString(at, ch) => {
                 self.error(at, ErrorKind::InvalidCharInString(ch))
@@ -1997,6 +4165,33 @@ impl<'a> Deserializer<'a> {
         }
     }
 
+    /// Converts an error found while reading a key, explaining characters
+    /// that are only allowed in quoted keys.
+    fn key_token_error(&self, error: TokenError) -> Error {
+        let (at, ch) = match error {
+            TokenError::Unexpected(at, ch) if !ch.is_control() => (at, ch),
+            error => return self.token_error(error),
+        };
+        let is_separator = |c: char| c.is_whitespace() || ".[]{}=,#\"'".contains(c);
+        let start = match self.input[..at]
+            .char_indices()
+            .rev()
+            .find(|&(_, c)| is_separator(c))
+        {
+            // Text right after a quoted key is not part of a bare key.
+            Some((_, '"')) | Some((_, '\'')) => {
+                return self.error(at, ErrorKind::Unexpected(ch));
+            }
+            Some((i, c)) => i + c.len_utf8(),
+            None => 0,
+        };
+        let end = self.input[at..]
+            .find(is_separator)
+            .map_or(self.input.len(), |i| at + i);
+        let key = self.input[start..end].to_string();
+        self.error(at, ErrorKind::InvalidBareKey { key, ch })
+    }
+
     fn error(&self, at: usize, kind: ErrorKind) -> Error {
         let mut err = Error::from_kind(Some(at), kind);
         err.fix_linecol(|at| self.to_linecol(at));
@@ -2007,20 +4202,110 @@ impl<'a> Deserializer<'a> {
     ///
     /// All indexes are 0-based.
     fn to_linecol(&self, offset: usize) -> (usize, usize) {
-        let mut cur = 0;
-        // Use split_terminator instead of lines so that if there is a `\r`,
-        // it is included in the offset calculation. The `+1` values below
-        // account for the `\n`.
-        for (i, line) in self.input.split_terminator('\n').enumerate() {
-            if cur + line.len() + 1 > offset {
-                return (i, offset - cur);
-            }
-            cur += line.len() + 1;
+        to_linecol(self.input, offset)
+    }
+}
+
+/// Converts a byte offset into `input` to a (line, column) pair
+///
+/// All indexes are 0-based.
+pub(crate) fn to_linecol(input: &str, offset: usize) -> (usize, usize) {
+    let mut cur = 0;
+    // Use split_terminator instead of lines so that if there is a `\r`,
+    // it is included in the offset calculation. The `+1` values below
+    // account for the `\n`.
+    for (i, line) in input.split_terminator('\n').enumerate() {
+        if cur + line.len() + 1 > offset {
+            return (i, offset - cur);
+        }
+        cur += line.len() + 1;
+    }
+    (input.lines().count(), 0)
+}
+
+/// Removes the leading zeros from the decimal number `s`, keeping its sign
+/// and a single zero if nothing else precedes a decimal point or exponent.
+fn strip_leading_zeros(s: &str) -> String {
+    let (sign, digits) = match s.chars().next() {
+        Some(c @ '+') | Some(c @ '-') => (Some(c), &s[1..]),
+        _ => (None, s),
+    };
+    let digits = digits.trim_start_matches(&['0', '_'][..]);
+    let mut out = sign.map(String::from).unwrap_or_default();
+    if !digits.starts_with(|c: char| c.is_ascii_digit()) {
+        out.push('0');
+    }
+    out.push_str(digits);
+    out
+}
+
+/// Hands a raw number to a visitor that asked for a number: as an integer if
+/// it is one that fits in 128 bits, and otherwise as the nearest float.
+pub(crate) fn visit_raw_number<'de, V>(s: &str, visitor: V) -> Result<V::Value, Error>
+where
+    V: de::Visitor<'de>,
+{
+    let digits = s.replace('_', "");
+    let digits = digits.trim_start_matches('+');
+    if let Ok(i) = digits.parse::<i128>() {
+        visitor.visit_i128(i)
+    } else if let Ok(u) = digits.parse::<u128>() {
+        visitor.visit_u128(u)
+    } else {
+        match digits.parse::<f64>() {
+            Ok(f) => visitor.visit_f64(f),
+            Err(_) => Err(Error::custom(None, format!("invalid number `{}`", s))),
         }
-        (self.input.lines().count(), 0)
     }
 }
 
+/// Returns whether `text` is a TOML integer or float and nothing else, as
+/// the text of a raw number must be.
+pub(crate) fn is_number_literal(text: &str) -> bool {
+    let mut de = Deserializer::new(text);
+    de.ctx.raw_numbers.store(true, atomic::Ordering::Relaxed);
+    match de.value() {
+        Ok(Value {
+            e: E::Integer(_) | E::Float(_) | E::RawNumber(_),
+            ..
+        }) => de.expect_end().is_ok(),
+        _ => false,
+    }
+}
+
+/// Returns whether `f` is exactly the number written as the float `text`.
+fn float_is_exact(text: &str, f: f64) -> bool {
+    f.is_finite() && decimal_parts(text) == decimal_parts(&format!("{:e}", f))
+}
+
+/// Splits a decimal number into its sign, its significant digits and the
+/// exponent of its last digit, so that numbers written differently compare
+/// equal. Returns `None` if the exponent is out of range.
+pub(crate) fn decimal_parts(text: &str) -> Option<(bool, String, i64)> {
+    let text = text.replace('_', "");
+    let (negative, text) = match text.as_bytes().first() {
+        Some(b'-') => (true, &text[1..]),
+        Some(b'+') => (false, &text[1..]),
+        _ => (false, &text[..]),
+    };
+    let (mantissa, exponent) = match text.find(['e', 'E']) {
+        Some(i) => (&text[..i], text[i + 1..].parse::<i64>().ok()?),
+        None => (text, 0),
+    };
+    let fraction = mantissa.find('.').map_or(0, |i| mantissa.len() - i - 1);
+    let digits = mantissa.replace('.', "");
+    let digits = digits.trim_start_matches('0');
+    let significant = digits.trim_end_matches('0');
+    if significant.is_empty() {
+        return Some((negative, String::new(), 0));
+    }
+    let trailing = (digits.len() - significant.len()) as i64;
+    let exponent = exponent
+        .checked_sub(fraction as i64)?
+        .checked_add(trailing)?;
+    Some((negative, significant.to_string(), exponent))
+}
+
 impl Error {
     /// Produces a (line, column) pair of the position of the error if available
     ///
@@ -2029,6 +4314,27 @@ impl Error {
         self.inner.line.map(|line| (line, self.inner.col))
     }
 
+    /// Returns the byte offset in the input of the position of the error if
+    /// available.
+    pub fn offset(&self) -> Option<usize> {
+        self.inner.at
+    }
+
+    /// Returns whether this error is because deserializing was cancelled,
+    /// through a progress callback or the cancel flag, rather than because
+    /// of a problem with the input.
+    pub fn is_cancelled(&self) -> bool {
+        matches!(self.inner.kind, ErrorKind::Cancelled)
+    }
+
+    /// Returns whether this error is because an array read with
+    /// [`unique_set`] has a duplicate element.
+    ///
+    /// [`unique_set`]: fn.unique_set.html
+    pub fn is_duplicate_set_element(&self) -> bool {
+        matches!(self.inner.kind, ErrorKind::DuplicateSetElement(_))
+    }
+
     fn from_kind(at: Option<usize>, kind: ErrorKind) -> Error {
         Error {
             inner: Box::new(ErrorInner {
@@ -2042,7 +4348,7 @@ impl Error {
         }
     }
 
-    fn custom(at: Option<usize>, s: String) -> Error {
+    pub(crate) fn custom(at: Option<usize>, s: String) -> Error {
         Error {
             inner: Box::new(ErrorInner {
                 kind: ErrorKind::Custom,
@@ -2059,6 +4365,39 @@ impl Error {
         self.inner.key.insert(0, key.to_string());
     }
 
+    /// Returns how far deserializing got before this error: the number of
+    /// keys it is nested under, and whether the value itself had the right
+    /// type.
+    pub(crate) fn progress(&self) -> (usize, bool) {
+        let type_matched = !matches!(self.inner.kind, ErrorKind::InvalidType);
+        (self.inner.key.len(), type_matched)
+    }
+
+    /// Describes this error without its position, naming the keys it is
+    /// nested under so that another deserializer can add its own.
+    pub(crate) fn describe_nested(&self) -> String {
+        let mut error = self.clone();
+        error.inner.line = None;
+        let keys = std::mem::take(&mut error.inner.key);
+        if keys.is_empty() {
+            error.to_string()
+        } else {
+            format!("{} in `{}`", error, keys.join("."))
+        }
+    }
+
+    /// Moves this error, produced by parsing `input[offset..]`, to the
+    /// corresponding position in `input`.
+    pub(crate) fn relocate(&mut self, input: &str, offset: usize) {
+        if let Some(at) = self.inner.at {
+            let at = at + offset;
+            let (line, col) = to_linecol(input, at);
+            self.inner.at = Some(at);
+            self.inner.line = Some(line);
+            self.inner.col = col;
+        }
+    }
+
     fn fix_offset<F>(&mut self, f: F)
     where
         F: FnOnce() -> Option<usize>,
@@ -2121,13 +4460,50 @@ impl fmt::Display for Error {
             }
             ErrorKind::NumberInvalid => "invalid number".fmt(f)?,
             ErrorKind::DateInvalid => "invalid date".fmt(f)?,
+            ErrorKind::InvalidDateTime(ref reason) => write!(f, "invalid datetime: {}", reason)?,
+            ErrorKind::LeadingZeros(ref suggestion) => {
+                write!(f, "leading zeros are not allowed; write `{}`", suggestion)?
+            }
+            ErrorKind::TrailingDecimalPoint(ref suggestion) => write!(
+                f,
+                "a decimal point must be followed by digits; write `{}`",
+                suggestion
+            )?,
             ErrorKind::DuplicateTable(ref s) => {
                 write!(f, "redefinition of table `{}`", s)?;
             }
             ErrorKind::RedefineAsArray => "table redefined as array".fmt(f)?,
+            ErrorKind::TableRedefined {
+                ref name,
+                first,
+                first_line,
+                second,
+            } => {
+                let noun = match first {
+                    Definition::ArrayOfTables => "array of tables",
+                    Definition::InlineTable => "inline table",
+                    Definition::Table | Definition::DottedKeys => "table",
+                };
+                let change = match (first, second) {
+                    (_, Definition::DottedKeys) => " by dotted keys",
+                    (Definition::ArrayOfTables, Definition::ArrayOfTables) => "",
+                    (_, Definition::ArrayOfTables) => " as an array of tables",
+                    (Definition::ArrayOfTables, _) => " as a table",
+                    _ => "",
+                };
+                let origin = match first {
+                    Definition::DottedKeys => "by dotted keys ",
+                    _ => "",
+                };
+                write!(
+                    f,
+                    "redefinition of {} `{}`{}, already defined {}on line {}",
+                    noun, name, change, origin, first_line
+                )?
+            }
             ErrorKind::EmptyTableKey => "empty table key found".fmt(f)?,
             ErrorKind::MultilineStringKey => "multiline strings are not allowed for key".fmt(f)?,
-            ErrorKind::Custom => self.inner.message.fmt(f)?,
+            ErrorKind::Custom | ErrorKind::InvalidType => self.inner.message.fmt(f)?,
             ErrorKind::ExpectedTuple(l) => write!(f, "expected table with length {}", l)?,
             ErrorKind::ExpectedTupleIndex {
                 expected,
@@ -2149,6 +4525,44 @@ impl fmt::Display for Error {
                 f,
                 "invalid TOML value, did you mean to use a quoted string?"
             )?,
+            ErrorKind::InvalidBareKey { ref key, ch } => {
+                write!(
+                    f,
+                    "invalid character `{}` in bare key `{}`, quote the key: ",
+                    ch, key
+                )?;
+                crate::display::write_key(f, key)?
+            }
+            ErrorKind::KeyCaseConflict {
+                ref key,
+                ref first,
+                first_line,
+            } => write!(
+                f,
+                "key `{}` differs only in case from `{}` on line {}",
+                key, first, first_line
+            )?,
+            ErrorKind::UnknownVariant {
+                ref variant,
+                ref expected,
+            } => {
+                write!(f, "unknown variant `{}`, expected ", variant)?;
+                match expected.len() {
+                    0 => "no variants".fmt(f)?,
+                    1 => write!(f, "`{}`", expected[0])?,
+                    _ => {
+                        "one of ".fmt(f)?;
+                        for (i, name) in expected.iter().enumerate() {
+                            if i > 0 {
+                                ", ".fmt(f)?;
+                            }
+                            write!(f, "`{}`", name)?;
+                        }
+                    }
+                }
+            }
+            ErrorKind::Cancelled => "deserialization was cancelled".fmt(f)?,
+            ErrorKind::DuplicateSetElement(ref duplicate) => duplicate.fmt(f)?,
             ErrorKind::__Nonexhaustive => panic!(),
         }
 
@@ -2177,9 +4591,57 @@ impl de::Error for Error {
     fn custom<T: fmt::Display>(msg: T) -> Error {
         Error::custom(None, msg.to_string())
     }
+
+    fn invalid_type(unexp: de::Unexpected<'_>, exp: &dyn de::Expected) -> Error {
+        let expected = exp.to_string();
+        let mut msg = format!("invalid type: {}, expected {}", unexp, expected);
+        if let Some(hint) = coercion_hint(&unexp, &expected) {
+            msg.push_str(&format!(" (hint: {})", hint));
+        }
+        let mut error = Error::custom(None, msg);
+        error.inner.kind = ErrorKind::InvalidType;
+        error
+    }
+
+    fn invalid_value(unexp: de::Unexpected<'_>, exp: &dyn de::Expected) -> Error {
+        match unexp {
+            de::Unexpected::Other(duplicate) if exp.to_string() == UNIQUE_SET_EXPECTED => {
+                Error::from_kind(None, ErrorKind::DuplicateSetElement(duplicate.to_string()))
+            }
+            _ => Error::custom(None, format!("invalid value: {}, expected {}", unexp, exp)),
+        }
+    }
 }
 
-enum Line<'a> {
+/// Suggests how to fix a value that was written with the wrong type, for the
+/// common mistake of quoting a number or boolean.
+fn coercion_hint(unexp: &de::Unexpected<'_>, expected: &str) -> Option<&'static str> {
+    let s = match *unexp {
+        de::Unexpected::Str(s) => s.trim(),
+        _ => return None,
+    };
+    let integer = [
+        "i8", "i16", "i32", "i64", "i128", "isize", "u8", "u16", "u32", "u64", "u128", "usize",
+    ]
+    .contains(&expected)
+        || expected.contains("integer");
+    let float = expected == "f32" || expected == "f64" || expected.contains("float");
+    let boolean = expected == "a boolean" || expected.contains("bool");
+
+    if integer && s.replace('_', "").parse::<i64>().is_ok() {
+        Some("remove the quotes to make it an integer")
+    } else if float && s.replace('_', "").parse::<f64>().is_ok() {
+        Some("remove the quotes to make it a float")
+    } else if boolean && (s == "true" || s == "false") {
+        Some("remove the quotes to make it a boolean")
+    } else if boolean && ["yes", "no", "on", "off"].contains(&&*s.to_lowercase()) {
+        Some("booleans are written as `true` or `false`, without quotes")
+    } else {
+        None
+    }
+}
+
+pub(crate) enum Line<'a> {
     Table {
         at: usize,
         header: Header<'a>,
@@ -2188,7 +4650,7 @@ enum Line<'a> {
     KeyValue(Vec<(Span, Cow<'a, str>)>, Value<'a>),
 }
 
-struct Header<'a> {
+pub(crate) struct Header<'a> {
     first: bool,
     array: bool,
     require_newline_after_table: bool,
@@ -2205,7 +4667,7 @@ impl<'a> Header<'a> {
         }
     }
 
-    fn next(&mut self) -> Result<Option<(Span, Cow<'a, str>)>, TokenError> {
+    pub(crate) fn next(&mut self) -> Result<Option<(Span, Cow<'a, str>)>, TokenError> {
         self.tokens.eat_whitespace()?;
 
         if self.first || self.tokens.eat(Token::Period)? {
@@ -2228,19 +4690,22 @@ impl<'a> Header<'a> {
 }
 
 #[derive(Debug)]
-struct Value<'a> {
-    e: E<'a>,
-    start: usize,
-    end: usize,
+pub(crate) struct Value<'a> {
+    pub(crate) e: E<'a>,
+    pub(crate) start: usize,
+    pub(crate) end: usize,
 }
 
 #[derive(Debug)]
-enum E<'a> {
+pub(crate) enum E<'a> {
     Integer(i64),
     Float(f64),
     Boolean(bool),
     String(Cow<'a, str>),
-    Datetime(&'a str),
+    Datetime(Cow<'a, str>),
+    /// A number literal an `i64` or `f64` cannot hold exactly, read with
+    /// `set_raw_numbers`.
+    RawNumber(Cow<'a, str>),
     Array(Vec<Value<'a>>),
     InlineTable(Vec<TablePair<'a>>),
     DottedTable(Vec<TablePair<'a>>),
@@ -2254,9 +4719,33 @@ impl<'a> E<'a> {
             E::Float(..) => "float",
             E::Boolean(..) => "boolean",
             E::Datetime(..) => "datetime",
+            E::RawNumber(..) => "number",
             E::Array(..) => "array",
             E::InlineTable(..) => "inline table",
             E::DottedTable(..) => "dotted table",
         }
     }
+
+    /// Returns whether two values are equal, comparing the keys of tables in
+    /// the order they were written.
+    fn same_as(&self, other: &E<'_>) -> bool {
+        match (self, other) {
+            (E::Integer(a), E::Integer(b)) => a == b,
+            (E::Float(a), E::Float(b)) => a.to_bits() == b.to_bits(),
+            (E::Boolean(a), E::Boolean(b)) => a == b,
+            (E::String(a), E::String(b)) => a == b,
+            (E::Datetime(a), E::Datetime(b)) => a == b,
+            (E::RawNumber(a), E::RawNumber(b)) => a == b,
+            (E::Array(a), E::Array(b)) => {
+                a.len() == b.len() && a.iter().zip(b).all(|(a, b)| a.e.same_as(&b.e))
+            }
+            (E::InlineTable(a), E::InlineTable(b)) | (E::DottedTable(a), E::DottedTable(b)) => {
+                a.len() == b.len()
+                    && a.iter()
+                        .zip(b)
+                        .all(|(a, b)| (a.0).1 == (b.0).1 && a.1.e.same_as(&b.1.e))
+            }
+            _ => false,
+        }
+    }
 }
@@ -0,0 +1,184 @@
+//! Directives written in comments, such as `# toml-lint: disable=rule`.
+//!
+//! Tools that read TOML files often let users annotate them through
+//! comments, for example to silence a lint on one line. The [`directives`]
+//! function finds the comments that start with a given prefix, splits the
+//! rest into `name` or `name=value` directives, and works out what part of
+//! the document each applies to from where the comment is written:
+//!
+//! * a comment on the lines directly above a key or header applies to that
+//!   line, as does one after a key on the same line,
+//! * a comment after a table header on the same line, or on a line of its
+//!   own separated from the next key by a blank line, applies to the
+//!   enclosing table,
+//! * a comment of the latter kind above every key and header applies to
+//!   the whole file.
+//!
+//! ```rust
+//! use toml::directive::{directives, DirectiveScope};
+//!
+//! let src = "\
+//! ## toml-lint: disable=unsorted
+//!
+//! [dependencies] # toml-lint: disable=wildcard
+//! ## toml-lint: allow=git
+//! remote = { git = \"https://example.com/remote\" }
+//! ";
+//!
+//! let found = directives(src, "toml-lint:").unwrap();
+//! assert_eq!(found.len(), 3);
+//! assert_eq!(found[0].name(), "disable");
+//! assert_eq!(found[0].value(), Some("unsorted"));
+//! assert_eq!(*found[0].scope(), DirectiveScope::File);
+//! assert_eq!(*found[1].scope(), DirectiveScope::Table("dependencies".parse().unwrap()));
+//! assert_eq!(*found[2].scope(), DirectiveScope::Line((99, 146)));
+//! ```
+//!
+//! [`directives`]: fn.directives.html
+
+use crate::de;
+use crate::layout::Layout;
+use crate::path::KeyPath;
+use crate::ser::TableStyle;
+use crate::tokens::{Token, Tokenizer};
+
+/// A directive found in a comment.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Directive {
+    name: String,
+    value: Option<String>,
+    span: (usize, usize),
+    scope: DirectiveScope,
+}
+
+impl Directive {
+    /// Returns the name of the directive, the part before any `=`.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the part of the directive after the `=`, if any.
+    pub fn value(&self) -> Option<&str> {
+        self.value.as_deref()
+    }
+
+    /// Returns the start and end byte offsets of the directive in the
+    /// source text.
+    pub fn span(&self) -> (usize, usize) {
+        self.span
+    }
+
+    /// Returns the part of the document the directive applies to.
+    pub fn scope(&self) -> &DirectiveScope {
+        &self.scope
+    }
+}
+
+/// The part of a document a directive applies to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DirectiveScope {
+    /// A single line, given by the byte offsets of its start and end,
+    /// without the newline. For a key whose value spans several lines, this
+    /// is the line the key is on.
+    Line((usize, usize)),
+    /// The table at the given path, including the keys of its sub-tables.
+    Table(KeyPath),
+    /// The whole document.
+    File,
+}
+
+/// Returns the directives in the comments of `src` that start with
+/// `prefix`, ordered by position.
+///
+/// The text after the prefix is split on whitespace, and each part is a
+/// directive. An error is returned if `src` is not a valid TOML document.
+pub fn directives(src: &str, prefix: &str) -> Result<Vec<Directive>, de::Error> {
+    let layout = Layout::scan(src)?;
+    let mut tokens = Tokenizer::new(src);
+    let mut ret = Vec::new();
+    while let Ok(Some((span, token))) = tokens.next() {
+        let comment = match token {
+            Token::Comment(comment) => comment,
+            _ => continue,
+        };
+        let text = comment[1..].trim_start();
+        if !text.starts_with(prefix) {
+            continue;
+        }
+        let scope = scope(src, &layout, span.start);
+        let offset = span.start + comment.len() - text.len() + prefix.len();
+        let mut rest = &text[prefix.len()..];
+        while let Some(start) = rest.find(|c: char| !c.is_whitespace()) {
+            let len = rest[start..]
+                .find(char::is_whitespace)
+                .unwrap_or(rest.len() - start);
+            let part = &rest[start..start + len];
+            let at = offset + (text.len() - prefix.len() - rest.len()) + start;
+            let (name, value) = match part.find('=') {
+                Some(i) => (&part[..i], Some(part[i + 1..].to_string())),
+                None => (part, None),
+            };
+            ret.push(Directive {
+                name: name.to_string(),
+                value,
+                span: (at, at + len),
+                scope: scope.clone(),
+            });
+            rest = &rest[start + len..];
+        }
+    }
+    Ok(ret)
+}
+
+/// Works out what the comment starting at `at` applies to.
+fn scope(src: &str, layout: &Layout, at: usize) -> DirectiveScope {
+    let line_start = src[..at].rfind('\n').map_or(0, |i| i + 1);
+    let header = layout
+        .entries
+        .iter()
+        .rev()
+        .find(|e| e.style == Some(TableStyle::Standard) && e.key.start < at);
+
+    if !src[line_start..at].trim().is_empty() {
+        return match header {
+            Some(header) if header.key.start >= line_start => {
+                DirectiveScope::Table(header.path.clone())
+            }
+            _ => DirectiveScope::Line((line_start, line_end(src, line_start))),
+        };
+    }
+
+    // The first line after the comment and the ones directly below it.
+    let mut next = line_start;
+    loop {
+        next = match src[next..].find('\n') {
+            Some(i) => next + i + 1,
+            None => break,
+        };
+        let line = src[next..line_end(src, next)].trim();
+        if line.is_empty() {
+            break;
+        }
+        if !line.starts_with('#') {
+            return DirectiveScope::Line((next, line_end(src, next)));
+        }
+    }
+
+    match header {
+        Some(header) => DirectiveScope::Table(header.path.clone()),
+        None => match layout.key_values.first() {
+            Some(kv) if kv.start < at => DirectiveScope::Table(KeyPath::new()),
+            _ => DirectiveScope::File,
+        },
+    }
+}
+
+/// Returns the end of the line starting at `start`, before its newline.
+fn line_end(src: &str, start: usize) -> usize {
+    let end = src[start..].find('\n').map_or(src.len(), |i| start + i);
+    if src[..end].ends_with('\r') {
+        end - 1
+    } else {
+        end
+    }
+}
@@ -0,0 +1,254 @@
+//! Human-oriented renderings of values.
+//!
+//! The `Display` implementation of [`Value`] produces a complete TOML
+//! document, which is the right thing for writing files but not for log
+//! lines or debugging output. The types here render values for people
+//! instead.
+//!
+//! [`Value`]: ../value/enum.Value.html
+
+use std::fmt::{self, Write};
+
+use crate::value::Value;
+
+/// Limits applied by [`Value::display_truncated`].
+///
+/// [`Value::display_truncated`]: ../value/enum.Value.html#method.display_truncated
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Limits {
+    /// Maximum number of characters shown of a string.
+    pub max_string_len: usize,
+    /// Maximum number of elements shown of an array.
+    pub max_array_len: usize,
+    /// Maximum number of entries shown of a table.
+    pub max_table_len: usize,
+    /// Maximum nesting of arrays and tables shown; deeper values are
+    /// replaced by `[…]` or `{…}`.
+    pub max_depth: usize,
+}
+
+impl Default for Limits {
+    fn default() -> Limits {
+        Limits {
+            max_string_len: 64,
+            max_array_len: 16,
+            max_table_len: 32,
+            max_depth: 8,
+        }
+    }
+}
+
+/// A value displayed on a single line with long strings, arrays and tables
+/// cut short.
+///
+/// This is created by [`Value::display_truncated`].
+///
+/// [`Value::display_truncated`]: ../value/enum.Value.html#method.display_truncated
+#[derive(Debug, Clone, Copy)]
+pub struct Truncated<'a> {
+    pub(crate) value: &'a Value,
+    pub(crate) limits: Limits,
+}
+
+impl<'a> fmt::Display for Truncated<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_truncated(f, self.value, &self.limits, 0)
+    }
+}
+
+fn write_truncated(
+    f: &mut fmt::Formatter<'_>,
+    value: &Value,
+    limits: &Limits,
+    depth: usize,
+) -> fmt::Result {
+    match *value {
+        Value::String(ref s) => {
+            let mut chars = s.char_indices();
+            match chars.nth(limits.max_string_len) {
+                Some((end, _)) => {
+                    write_basic_str(f, &s[..end])?;
+                    write!(f, "… (+{} more)", s[end..].chars().count())
+                }
+                None => write_basic_str(f, s),
+            }
+        }
+        Value::Array(ref array) if depth >= limits.max_depth && !array.is_empty() => {
+            f.write_str("[…]")
+        }
+        Value::Table(ref table) if depth >= limits.max_depth && !table.is_empty() => {
+            f.write_str("{…}")
+        }
+        Value::Array(ref array) => {
+            f.write_str("[")?;
+            for (i, value) in array.iter().take(limits.max_array_len).enumerate() {
+                if i > 0 {
+                    f.write_str(", ")?;
+                }
+                write_truncated(f, value, limits, depth + 1)?;
+            }
+            if array.len() > limits.max_array_len {
+                if limits.max_array_len > 0 {
+                    f.write_str(", ")?;
+                }
+                write!(f, "… (+{} more)", array.len() - limits.max_array_len)?;
+            }
+            f.write_str("]")
+        }
+        Value::Table(ref table) => {
+            if table.is_empty() {
+                return f.write_str("{}");
+            }
+            f.write_str("{ ")?;
+            for (i, (key, value)) in table.iter().take(limits.max_table_len).enumerate() {
+                if i > 0 {
+                    f.write_str(", ")?;
+                }
+                write_key(f, key)?;
+                f.write_str(" = ")?;
+                write_truncated(f, value, limits, depth + 1)?;
+            }
+            if table.len() > limits.max_table_len {
+                if limits.max_table_len > 0 {
+                    f.write_str(", ")?;
+                }
+                write!(f, "… (+{} more)", table.len() - limits.max_table_len)?;
+            }
+            f.write_str(" }")
+        }
+        ref scalar => fmt::Display::fmt(scalar, f),
+    }
+}
+
+/// Limits used for the scalar previews in `Value::render_tree`.
+const TREE_PREVIEW: Limits = Limits {
+    max_string_len: 40,
+    max_array_len: 8,
+    max_table_len: 0,
+    max_depth: 1,
+};
+
+pub(crate) fn render_tree(value: &Value) -> String {
+    let mut out = String::new();
+    if !has_children(value) {
+        write_node(&mut out, None, value);
+        return out;
+    }
+    // Top-level entries are written flush left, and only their contents
+    // are drawn as a tree below them.
+    for (name, child) in children(value) {
+        write_node(&mut out, Some(&name), child);
+        write_children(&mut out, child, "");
+    }
+    out
+}
+
+fn children(value: &Value) -> Vec<(String, &Value)> {
+    match *value {
+        Value::Table(ref table) => table
+            .iter()
+            .map(|(key, value)| {
+                let mut name = String::new();
+                write_key(&mut name, key).unwrap();
+                (name, value)
+            })
+            .collect(),
+        Value::Array(ref array) => array
+            .iter()
+            .enumerate()
+            .map(|(i, value)| (format!("[{}]", i), value))
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn has_children(value: &Value) -> bool {
+    match *value {
+        Value::Table(ref table) => !table.is_empty(),
+        Value::Array(ref array) => array
+            .iter()
+            .any(|v| matches!(*v, Value::Table(..) | Value::Array(..))),
+        _ => false,
+    }
+}
+
+fn write_children(out: &mut String, value: &Value, prefix: &str) {
+    if !has_children(value) {
+        return;
+    }
+    let children = children(value);
+    let n = children.len();
+    for (i, (name, child)) in children.into_iter().enumerate() {
+        let last = i + 1 == n;
+        out.push_str(prefix);
+        out.push_str(if last { "`-- " } else { "|-- " });
+        write_node(out, Some(&name), child);
+        let prefix = format!("{}{}", prefix, if last { "    " } else { "|   " });
+        write_children(out, child, &prefix);
+    }
+}
+
+fn write_node(out: &mut String, name: Option<&str>, value: &Value) {
+    if let Some(name) = name {
+        out.push_str(name);
+        out.push_str(": ");
+    }
+    match *value {
+        Value::Table(ref table) => {
+            out.push_str("table");
+            if table.is_empty() {
+                out.push_str(" (empty)");
+            }
+        }
+        Value::Array(ref array) => {
+            write!(out, "array ({})", array.len()).unwrap();
+            if !has_children(value) && !array.is_empty() {
+                let preview = value.display_truncated(TREE_PREVIEW);
+                write!(out, " = {}", preview).unwrap();
+            }
+        }
+        ref scalar => {
+            let preview = scalar.display_truncated(TREE_PREVIEW);
+            write!(out, "{} = {}", scalar.type_str(), preview).unwrap();
+        }
+    }
+    out.push('\n');
+}
+
+/// Writes `key` bare if possible, as a literal string if it has quotes or
+/// backslashes a basic string would need to escape, and as a basic string
+/// otherwise.
+pub(crate) fn write_key<W: Write>(dst: &mut W, key: &str) -> fmt::Result {
+    if crate::path::is_bare_key(key) {
+        dst.write_str(key)
+    } else if key.contains(&['"', '\\'][..]) && is_literal_key(key) {
+        write!(dst, "'{}'", key)
+    } else {
+        write_basic_str(dst, key)
+    }
+}
+
+/// Returns whether `key` can be written as a literal string, which has no
+/// escapes.
+fn is_literal_key(key: &str) -> bool {
+    !key.contains(|c: char| c == '\'' || (c.is_control() && c != '\t'))
+}
+
+/// Writes `s` as a TOML basic string, escaping as needed.
+pub(crate) fn write_basic_str<W: Write>(dst: &mut W, s: &str) -> fmt::Result {
+    dst.write_char('"')?;
+    for ch in s.chars() {
+        match ch {
+            '\u{8}' => dst.write_str("\\b")?,
+            '\t' => dst.write_str("\\t")?,
+            '\n' => dst.write_str("\\n")?,
+            '\u{c}' => dst.write_str("\\f")?,
+            '\r' => dst.write_str("\\r")?,
+            '"' => dst.write_str("\\\"")?,
+            '\\' => dst.write_str("\\\\")?,
+            c if c <= '\u{1f}' || c == '\u{7f}' => write!(dst, "\\u{:04X}", c as u32)?,
+            c => dst.write_char(c)?,
+        }
+    }
+    dst.write_char('"')
+}
@@ -0,0 +1,1662 @@
+//! TOML documents that keep their original source text.
+//!
+//! A [`Document`] is parsed like any other TOML input, but in addition to the
+//! resulting table it keeps the text it was parsed from and where each key
+//! was written in it. Displaying a document reproduces its source exactly.
+//!
+//! ```rust
+//! use toml::document::Document;
+//!
+//! let doc: Document = "# settings\n[server]\nport = 80 # http\n".parse().unwrap();
+//!
+//! assert_eq!(doc.get("server.port").and_then(|v| v.as_integer()), Some(80));
+//! assert_eq!(doc.span("server.port"), Some((20, 24)));
+//! assert_eq!(doc.to_string(), "# settings\n[server]\nport = 80 # http\n");
+//! ```
+//!
+//! The comments around each key can be read and edited, which changes only
+//! the comment in the source text:
+//!
+//! ```rust
+//! use toml::document::Document;
+//!
+//! let mut doc: Document = "# settings\n[server]\nport = 80 # http\n".parse().unwrap();
+//!
+//! assert_eq!(doc.leading_comment("server").as_deref(), Some("settings"));
+//! assert_eq!(doc.trailing_comment("server.port"), Some("http"));
+//!
+//! doc.set_trailing_comment("server.port", Some("plain http")).unwrap();
+//! doc.set_leading_comment("server.port", Some("where to listen")).unwrap();
+//! assert_eq!(doc.to_string(), "# settings\n[server]\n# where to listen\nport = 80 # plain http\n");
+//! ```
+//!
+//! [`Document`]: struct.Document.html
+
+use std::cmp::Ordering;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::de;
+use crate::directive::Directive;
+use crate::layout::{Entry, KeyValue, Layout};
+use crate::metadata::Metadata;
+use crate::path::{KeyPath, PathSegment};
+use crate::ser::TableStyle;
+use crate::value::{Table, Value};
+
+/// A parsed TOML document along with its source text.
+#[derive(Debug, Clone)]
+pub struct Document {
+    source: String,
+    original: String,
+    edits: Vec<TextEdit>,
+    root: Value,
+    layout: Layout,
+    metadata: Metadata,
+    align_equals: bool,
+}
+
+impl Document {
+    /// Parses `source` into a document.
+    pub fn parse(source: &str) -> Result<Document, de::Error> {
+        let table = crate::from_str::<Table>(source)?;
+        let layout = Layout::scan(source)?;
+        Ok(Document {
+            source: source.to_string(),
+            original: source.to_string(),
+            edits: Vec::new(),
+            root: Value::Table(table),
+            layout,
+            metadata: Metadata::new(),
+            align_equals: false,
+        })
+    }
+
+    /// Returns the source text of this document.
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Returns the text edits that turn the source this document was parsed
+    /// from into its current source, ordered by position.
+    ///
+    /// The spans are byte offsets into the source as parsed, and do not
+    /// overlap, so the edits can be sent to an editor as they are instead
+    /// of the whole text. Each edit covers only the text that changed: an
+    /// edit that is later undone, or that rewrites a value to the same
+    /// text, leaves no edit behind.
+    ///
+    /// ```rust
+    /// use toml::document::Document;
+    ///
+    /// let mut doc: Document = "[server]\nhost = \"a\"\nport = 80\n".parse().unwrap();
+    /// doc.set("server.port", toml::Value::Integer(443)).unwrap();
+    /// doc.set_trailing_comment("server.host", Some("primary")).unwrap();
+    ///
+    /// let edits = doc
+    ///     .edits()
+    ///     .iter()
+    ///     .map(|edit| (edit.span(), edit.replacement()))
+    ///     .collect::<Vec<_>>();
+    /// assert_eq!(edits, [((19, 19), " # primary"), ((27, 29), "443")]);
+    /// ```
+    pub fn edits(&self) -> &[TextEdit] {
+        &self.edits
+    }
+
+    /// Returns the table this document parsed to.
+    pub fn as_table(&self) -> &Table {
+        self.root.as_table().unwrap()
+    }
+
+    /// Consumes this document, returning the table it parsed to.
+    pub fn into_table(self) -> Table {
+        match self.root {
+            Value::Table(table) => table,
+            _ => unreachable!(),
+        }
+    }
+
+    /// Looks up the value at `path`.
+    pub fn get<P: Into<KeyPath>>(&self, path: P) -> Option<&Value> {
+        path.into().lookup(&self.root)
+    }
+
+    /// Returns the byte range of the key at `path` in the source text.
+    ///
+    /// For a table defined with a header this is the name between the
+    /// brackets, and for an array element it is the element itself. Tables
+    /// that are only created implicitly, such as `a` in `a.b = 1`, have no
+    /// span.
+    pub fn span<P: Into<KeyPath>>(&self, path: P) -> Option<(usize, usize)> {
+        self.layout.find(&path.into()).map(|e| e.key.into())
+    }
+
+    /// Returns the entries of the table at `path` in the order their keys
+    /// first appear in the source text, along with the span of each key.
+    ///
+    /// The span is that of `Document::span`, except for a table created
+    /// implicitly, such as `a` in `a.b = 1`, where it is the span of the
+    /// first key or header that creates it. Nothing is returned if `path` is
+    /// not a table.
+    ///
+    /// ```rust
+    /// use toml::document::Document;
+    ///
+    /// let doc: Document = "zeta = 1\nalpha.beta = 2\n[mid]\n".parse().unwrap();
+    ///
+    /// let keys = doc
+    ///     .iter_with_spans("")
+    ///     .map(|(key, span, _)| (key, span))
+    ///     .collect::<Vec<_>>();
+    /// assert_eq!(keys, [("zeta", (0, 4)), ("alpha", (9, 19)), ("mid", (25, 28))]);
+    /// ```
+    pub fn iter_with_spans<P: Into<KeyPath>>(
+        &self,
+        path: P,
+    ) -> impl Iterator<Item = (&str, (usize, usize), &Value)> {
+        let path = path.into();
+        let mut ret: Vec<(&str, (usize, usize), &Value)> = Vec::new();
+        if let Some(Value::Table(table)) = path.lookup(&self.root) {
+            for entry in &self.layout.entries {
+                if entry.path.len() <= path.len() || !entry.path.starts_with(&path) {
+                    continue;
+                }
+                let key = match entry.path.segments()[path.len()] {
+                    PathSegment::Key(ref key) => key,
+                    PathSegment::Index(_) => continue,
+                };
+                if ret.iter().any(|(k, _, _)| k == key) {
+                    continue;
+                }
+                if let Some(value) = table.get(key) {
+                    ret.push((key, entry.key.into(), value));
+                }
+            }
+        }
+        ret.into_iter()
+    }
+
+    /// Returns how the table at `path` is written in the source text, or
+    /// `None` if `path` is not a table.
+    ///
+    /// A table created implicitly is `TableStyle::Standard` when the first
+    /// entry inside it is a header, such as `a` in `[a.b]`, and
+    /// `TableStyle::Dotted` otherwise, such as `a` in `a.b = 1`. The root
+    /// table is `TableStyle::Standard`.
+    ///
+    /// ```rust
+    /// use toml::document::Document;
+    /// use toml::ser::TableStyle;
+    ///
+    /// let doc: Document = "[package]\nmetadata.docs = true\n\n[dependencies]\nserde = { version = \"1\" }\n"
+    ///     .parse()
+    ///     .unwrap();
+    ///
+    /// assert_eq!(doc.table_style("package"), Some(TableStyle::Standard));
+    /// assert_eq!(doc.table_style("package.metadata"), Some(TableStyle::Dotted));
+    /// assert_eq!(doc.table_style("dependencies.serde"), Some(TableStyle::Inline));
+    /// assert_eq!(doc.table_style("dependencies.serde.version"), None);
+    /// ```
+    pub fn table_style<P: Into<KeyPath>>(&self, path: P) -> Option<TableStyle> {
+        let path = path.into();
+        match path.lookup(&self.root) {
+            Some(Value::Table(_)) if path.is_empty() => Some(TableStyle::Standard),
+            Some(Value::Table(_)) => self.layout.table_style(&path),
+            _ => None,
+        }
+    }
+
+    /// Returns whether the table at `path` was created implicitly, or
+    /// `None` if there is no table at `path`.
+    ///
+    /// A table is defined explicitly by its own header, as an inline table
+    /// or as an element of an array of tables, and created implicitly when
+    /// it is only named on the way to another key or table, such as `a` in
+    /// `[a.b]` or in `a.b = 1`. Writing a `[header]` for an implicit table,
+    /// even an empty one, would change the document: a later header for it
+    /// becomes a redefinition. The root table is never implicit.
+    ///
+    /// ```rust
+    /// use toml::document::Document;
+    ///
+    /// let doc: Document = "[servers.alpha]\nip = \"10.0.0.1\"\n\n[log]\nlevel.max = 3\n"
+    ///     .parse()
+    ///     .unwrap();
+    ///
+    /// assert_eq!(doc.is_implicit_table("servers"), Some(true));
+    /// assert_eq!(doc.is_implicit_table("servers.alpha"), Some(false));
+    /// assert_eq!(doc.is_implicit_table("log"), Some(false));
+    /// assert_eq!(doc.is_implicit_table("log.level"), Some(true));
+    /// assert_eq!(doc.is_implicit_table("log.level.max"), None);
+    /// ```
+    pub fn is_implicit_table<P: Into<KeyPath>>(&self, path: P) -> Option<bool> {
+        let path = path.into();
+        match path.lookup(&self.root) {
+            Some(Value::Table(_)) if path.is_empty() => Some(false),
+            Some(Value::Table(_)) => Some(self.layout.find(&path).and_then(|e| e.style).is_none()),
+            _ => None,
+        }
+    }
+
+    /// Returns the path and style of every table below the root, in the
+    /// order they first appear in the source text.
+    ///
+    /// Passing these to `Serializer::table_style` keeps the way the tables
+    /// were written when serializing the document's table again.
+    ///
+    /// ```rust
+    /// use toml::document::Document;
+    /// use toml::ser::Serializer;
+    ///
+    /// let src = "log.level = 3\nname = \"demo\"\nsite = { url = \"x\" }\n";
+    /// let doc: Document = src.parse().unwrap();
+    ///
+    /// let mut out = String::new();
+    /// let mut ser = Serializer::new(&mut out);
+    /// for (path, style) in doc.table_styles() {
+    ///     ser.table_style(path, style);
+    /// }
+    /// serde::Serialize::serialize(doc.as_table(), &mut ser).unwrap();
+    /// assert_eq!(out, "log.level = 3\nname = \"demo\"\nsite = { url = \"x\" }\n");
+    /// ```
+    pub fn table_styles(&self) -> impl Iterator<Item = (KeyPath, TableStyle)> {
+        let mut ret: Vec<(KeyPath, TableStyle)> = Vec::new();
+        for entry in &self.layout.entries {
+            for len in 1..=entry.path.len() {
+                let path = KeyPath::from(entry.path.segments()[..len].to_vec());
+                if ret.iter().any(|(p, _)| *p == path) {
+                    continue;
+                }
+                if let Some(style) = self.table_style(&path) {
+                    ret.push((path, style));
+                }
+            }
+        }
+        ret.into_iter()
+    }
+
+    /// Sets whether edits keep the `=` signs of a table lined up, which is
+    /// off by default.
+    ///
+    /// When set, a line added to a table whose `=` signs are aligned, as
+    /// `TableStats::is_aligned` tells, is padded to the same column, and if
+    /// its key is too long for that column the other lines of the table are
+    /// padded further to match it. A value that is replaced keeps the
+    /// comment after it on its line in the column it was in, as far as the
+    /// new value leaves room for.
+    ///
+    /// ```rust
+    /// use toml::document::{Document, InsertPosition};
+    /// use toml::Value;
+    ///
+    /// let mut doc: Document = "[package]\nname    = 'demo'  # crate\nversion = '0.1.0' # semver\n"
+    ///     .parse()
+    ///     .unwrap();
+    /// doc.align_equals(true);
+    /// doc.set("package.name", Value::from("demo-cli")).unwrap();
+    /// doc.insert("package.edition", Value::from("2018"), InsertPosition::End).unwrap();
+    /// doc.insert("package.description", Value::from("x"), InsertPosition::End).unwrap();
+    /// assert_eq!(
+    ///     doc.to_string(),
+    ///     "[package]\n\
+    ///      name        = \"demo-cli\" # crate\n\
+    ///      version     = '0.1.0' # semver\n\
+    ///      edition     = \"2018\"\n\
+    ///      description = \"x\"\n",
+    /// );
+    /// ```
+    pub fn align_equals(&mut self, align: bool) -> &mut Self {
+        self.align_equals = align;
+        self
+    }
+
+    /// Returns measurements of the `key = value` lines written directly
+    /// under the header of the table at `path`, or at the top of the
+    /// document for the root table, or `None` if there is no table at
+    /// `path`.
+    ///
+    /// Keys written inside inline tables, and the lines under the headers
+    /// of nested tables, are not counted, so a table written only with
+    /// dotted keys or inline has no lines of its own. The measurements let
+    /// a formatter or pretty-printer line up the `=` signs of a table the
+    /// way the document already does.
+    ///
+    /// ```rust
+    /// use toml::document::Document;
+    ///
+    /// let doc: Document = "[package]\nname    = \"demo\"\nversion = \"0.1.0\"\n".parse().unwrap();
+    /// let stats = doc.table_stats("package").unwrap();
+    ///
+    /// assert_eq!(stats.len(), 2);
+    /// assert_eq!(stats.longest_key(), 7);
+    /// assert_eq!(stats.longest_value(), 7);
+    /// assert_eq!(stats.equals_column(), Some(8));
+    /// ```
+    pub fn table_stats<P: Into<KeyPath>>(&self, path: P) -> Option<TableStats> {
+        let path = path.into();
+        match path.lookup(&self.root) {
+            Some(Value::Table(_)) => {}
+            _ => return None,
+        }
+        let lines = self
+            .layout
+            .key_values
+            .iter()
+            .filter(|kv| kv.table == path)
+            .map(|kv| self.line_stats(kv))
+            .collect();
+        Some(TableStats { lines })
+    }
+
+    /// Returns the path and measurements of every table with `key = value`
+    /// lines of its own, in the order of their first line in the source
+    /// text. See `table_stats` for what is measured.
+    pub fn stats(&self) -> impl Iterator<Item = (KeyPath, TableStats)> {
+        let mut ret: Vec<(KeyPath, TableStats)> = Vec::new();
+        for kv in &self.layout.key_values {
+            let line = self.line_stats(kv);
+            match ret.iter_mut().find(|(path, _)| *path == kv.table) {
+                Some((_, stats)) => stats.lines.push(line),
+                None => ret.push((kv.table.clone(), TableStats { lines: vec![line] })),
+            }
+        }
+        ret.into_iter()
+    }
+
+    fn line_stats(&self, kv: &KeyValue) -> LineStats {
+        let source = &self.source;
+        let line_start = source[..kv.start].rfind('\n').map_or(0, |i| i + 1);
+        let equals = kv.key_end + source[kv.key_end..kv.value_start].find('=').unwrap();
+        LineStats {
+            key: source[kv.start..kv.key_end].to_string(),
+            key_column: source[line_start..kv.start].chars().count(),
+            equals_column: source[line_start..equals].chars().count(),
+            value_width: source[kv.value_start..kv.end]
+                .lines()
+                .map(|line| line.chars().count())
+                .max()
+                .unwrap_or(0),
+        }
+    }
+
+    /// Returns the comment block written on the lines directly above the key
+    /// or header at `path`, without the `#` of each line and the space after
+    /// it.
+    ///
+    /// A blank line between the comments and the key ends the block, so a
+    /// comment separated from everything by blank lines belongs to no key.
+    /// `None` is returned if there is no such block, or if `path` is not
+    /// written in the document as a key, header or array element on its own
+    /// line.
+    pub fn leading_comment<P: Into<KeyPath>>(&self, path: P) -> Option<String> {
+        let entry = self.layout.find(&path.into())?;
+        let (start, end) = self.leading_comment_span(entry)?;
+        if start == end {
+            return None;
+        }
+        let lines = self.source[start..end]
+            .lines()
+            .map(|line| {
+                let line = line.trim()[1..].trim_end();
+                line.strip_prefix(' ').unwrap_or(line)
+            })
+            .collect::<Vec<_>>();
+        Some(lines.join("\n"))
+    }
+
+    /// Returns the comment written after the value or header at `path` on
+    /// the same line, without the `#` and the whitespace around it.
+    ///
+    /// A comma after an array element may come before the comment, as in
+    /// `1, # one`. `None` is returned if there is no such comment, or if
+    /// `path` is not written in the document.
+    pub fn trailing_comment<P: Into<KeyPath>>(&self, path: P) -> Option<&str> {
+        let entry = self.layout.find(&path.into())?;
+        let (_, comment) = self.trailing_comment_span(entry)?;
+        let (start, end) = comment?;
+        Some(self.source[start + 1..end].trim())
+    }
+
+    /// Replaces the comment block above the key or header at `path`, or
+    /// removes it if `comment` is `None`.
+    ///
+    /// Each line of `comment` is written as its own `#` line, indented like
+    /// the key. Only the comment changes in the source text. An error is
+    /// returned if `path` is not written in the document on its own line,
+    /// or if `comment` contains a control character.
+    pub fn set_leading_comment<P: Into<KeyPath>>(
+        &mut self,
+        path: P,
+        comment: Option<&str>,
+    ) -> Result<(), de::Error> {
+        let path = path.into();
+        let entry = self.entry(&path)?;
+        let (start, end) = match self.leading_comment_span(entry) {
+            Some(span) => span,
+            None => {
+                return Err(de::Error::custom(
+                    None,
+                    format!(
+                        "`{}` does not start its line, so it cannot have a comment above it",
+                        path
+                    ),
+                ))
+            }
+        };
+        let mut replacement = String::new();
+        if let Some(comment) = comment {
+            check_comment(&path, comment, true)?;
+            let indent = self.indent(end);
+            let newline = self.newline();
+            for line in comment.lines() {
+                replacement.push_str(indent);
+                replacement.push('#');
+                if !line.is_empty() {
+                    replacement.push(' ');
+                    replacement.push_str(line);
+                }
+                replacement.push_str(newline);
+            }
+        }
+        self.replace_source(start, end, &replacement)
+    }
+
+    /// Replaces the comment after the value or header at `path` on the same
+    /// line, or removes it if `comment` is `None`.
+    ///
+    /// An existing comment keeps its position. An error is returned if
+    /// `path` is not written in the document, if something other than a
+    /// comment follows it on its line, or if `comment` contains a newline or
+    /// another control character.
+    pub fn set_trailing_comment<P: Into<KeyPath>>(
+        &mut self,
+        path: P,
+        comment: Option<&str>,
+    ) -> Result<(), de::Error> {
+        let path = path.into();
+        let entry = self.entry(&path)?;
+        let (after, existing) = match self.trailing_comment_span(entry) {
+            Some(span) => span,
+            None => {
+                return Err(de::Error::custom(
+                    None,
+                    format!("`{}` is followed by more than a comment on its line", path),
+                ))
+            }
+        };
+        let line_end = self.line_end(after);
+        match (comment, existing) {
+            (Some(comment), Some((start, end))) => {
+                check_comment(&path, comment, false)?;
+                self.replace_source(start, end, &format!("# {}", comment))
+            }
+            (Some(comment), None) => {
+                check_comment(&path, comment, false)?;
+                self.replace_source(after, line_end, &format!(" # {}", comment))
+            }
+            (None, Some(_)) => self.replace_source(after, line_end, ""),
+            (None, None) => Ok(()),
+        }
+    }
+
+    /// Reorders the `key = value` lines of the table at `path` by their keys
+    /// with `compare`, moving the comments attached to each line along with
+    /// it.
+    ///
+    /// The lines sorted are those under the table's header, or before the
+    /// first header for the root table. Blank lines and comments that are
+    /// not attached to a key split the lines into groups, which stay where
+    /// they are and are sorted separately, so that keys grouped on purpose
+    /// stay grouped. Lines with dotted keys are sorted by their first part,
+    /// and equal keys keep their order. Sub-tables with their own headers
+    /// are not moved.
+    ///
+    /// An error is returned if `path` is not the root or a table with a
+    /// header.
+    ///
+    /// ```rust
+    /// use toml::document::Document;
+    ///
+    /// let mut doc: Document = "\
+    /// [dependencies]
+    /// serde = \"1\" # derive support
+    /// ## random numbers
+    /// rand = \"0.8\"
+    ///
+    /// toml = \"0.5\"
+    /// anyhow = \"1\"
+    /// ".parse().unwrap();
+    ///
+    /// doc.sort_keys("dependencies", |a, b| a.cmp(b)).unwrap();
+    /// assert_eq!(doc.to_string(), "\
+    /// [dependencies]
+    /// ## random numbers
+    /// rand = \"0.8\"
+    /// serde = \"1\" # derive support
+    ///
+    /// anyhow = \"1\"
+    /// toml = \"0.5\"
+    /// ");
+    /// ```
+    pub fn sort_keys<P, F>(&mut self, path: P, mut compare: F) -> Result<(), de::Error>
+    where
+        P: Into<KeyPath>,
+        F: FnMut(&str, &str) -> Ordering,
+    {
+        let path = path.into();
+        self.check_section(&path)?;
+
+        // Each line with its attached comments, and where the group of
+        // adjacent lines it belongs to starts.
+        let mut chunks: Vec<(&str, usize, usize)> = Vec::new();
+        let mut groups: Vec<usize> = Vec::new();
+        for kv in self.section(&path) {
+            let start = self.comment_block_start(self.line_start(kv.start));
+            let end = self.line_end(kv.end);
+            let adjacent = match chunks.last() {
+                Some(&(_, _, prev_end)) => {
+                    self.source[prev_end..start].trim().is_empty()
+                        && self.source[prev_end..start].matches('\n').count() == 1
+                }
+                None => false,
+            };
+            if !adjacent {
+                groups.push(chunks.len());
+            }
+            chunks.push((&kv.key, start, end));
+        }
+        if chunks.is_empty() {
+            return Ok(());
+        }
+        groups.push(chunks.len());
+
+        let mut sorted = chunks.clone();
+        for group in groups.windows(2) {
+            sorted[group[0]..group[1]].sort_by(|a, b| compare(a.0, b.0));
+        }
+
+        let (first, last) = (chunks[0].1, chunks[chunks.len() - 1].2);
+        let mut text = String::new();
+        for (i, &(_, start, end)) in sorted.iter().enumerate() {
+            text.push_str(&self.source[start..end]);
+            if let Some(next) = chunks.get(i + 1) {
+                text.push_str(&self.source[chunks[i].2..next.1]);
+            }
+        }
+        self.replace_source(first, last, &text)
+    }
+
+    /// Adds `value` at `path` as a new `key = value` line, placed in its
+    /// table as chosen by `position`.
+    ///
+    /// The table must be the root or a table with a header, and the new line
+    /// is indented like the line it is placed next to. Nothing else in the
+    /// source text changes. An error is returned if the table is not
+    /// written with a header, if the key already exists, or if the key that
+    /// `InsertPosition::After` names is not in the table.
+    ///
+    /// ```rust
+    /// use toml::document::{Document, InsertPosition};
+    /// use toml::Value;
+    ///
+    /// let mut doc: Document = "[dependencies]\nanyhow = \"1\"\ntoml = \"0.5\"\n".parse().unwrap();
+    ///
+    /// doc.insert("dependencies.serde", Value::from("1.0"), InsertPosition::Sorted).unwrap();
+    /// assert_eq!(
+    ///     doc.to_string(),
+    ///     "[dependencies]\nanyhow = \"1\"\nserde = \"1.0\"\ntoml = \"0.5\"\n",
+    /// );
+    /// ```
+    pub fn insert<P: Into<KeyPath>>(
+        &mut self,
+        path: P,
+        value: Value,
+        position: InsertPosition,
+    ) -> Result<(), de::Error> {
+        let path = path.into();
+        let (parent, key) = split_key(&path)?;
+        let mut entry = Table::new();
+        entry.insert(key.clone(), value.clone());
+        let line = crate::ser::to_string_inline(&Value::Table(entry))
+            .map_err(|e| de::Error::custom(None, e.to_string()))?;
+        self.insert_line(
+            &path,
+            parent,
+            key,
+            value,
+            line.trim_end_matches('\n'),
+            position,
+        )
+    }
+
+    /// Adds a new `key = value` line at `path` like `Document::insert`, with
+    /// the value written exactly as `text` rather than formatted from a
+    /// `Value`.
+    ///
+    /// This keeps formatting that serializing a value would lose, such as
+    /// an aligned array split over several lines. `text` must hold a single
+    /// TOML value, which may be followed by a comment; the whitespace around
+    /// it is dropped. An error is returned if it does not parse as one,
+    /// with the position of the problem in `text`.
+    ///
+    /// ```rust
+    /// use toml::document::{Document, InsertPosition};
+    ///
+    /// let mut doc: Document = "[matrix]\n".parse().unwrap();
+    /// doc.insert_raw("matrix.rows", "[\n    [1, 0],\n    [0, 1],\n]", InsertPosition::End).unwrap();
+    ///
+    /// assert_eq!(doc.to_string(), "[matrix]\nrows = [\n    [1, 0],\n    [0, 1],\n]\n");
+    /// assert_eq!(doc.get("matrix.rows[1][1]").and_then(|v| v.as_integer()), Some(1));
+    ///
+    /// assert!(doc.insert_raw("matrix.cols", "[1, 2", InsertPosition::End).is_err());
+    /// ```
+    pub fn insert_raw<P: Into<KeyPath>>(
+        &mut self,
+        path: P,
+        text: &str,
+        position: InsertPosition,
+    ) -> Result<(), de::Error> {
+        let path = path.into();
+        let (parent, key) = split_key(&path)?;
+        let value = de::Deserializer::new(text).parse_value::<Value>()?;
+        let text = text.trim();
+        if text.starts_with('#') {
+            return Err(de::Error::custom(
+                None,
+                format!("the text for `{}` must start with its value", path),
+            ));
+        }
+        let mut line = String::new();
+        crate::display::write_key(&mut line, &key).unwrap();
+        line.push_str(" = ");
+        line.push_str(text);
+        self.insert_line(&path, parent, key, value, &line, position)
+    }
+
+    fn insert_line(
+        &mut self,
+        path: &KeyPath,
+        parent: KeyPath,
+        key: String,
+        value: Value,
+        line: &str,
+        position: InsertPosition,
+    ) -> Result<(), de::Error> {
+        self.check_section(&parent)?;
+        let aligned = if self.align_equals {
+            self.table_stats(&parent)
+                .filter(TableStats::is_aligned)
+                .and_then(|stats| stats.equals_column())
+        } else {
+            None
+        };
+        if self.get(path).is_some() {
+            return Err(de::Error::custom(
+                None,
+                format!("`{}` already exists", path),
+            ));
+        }
+
+        let lines = self.section(&parent).collect::<Vec<_>>();
+        let before = match position {
+            InsertPosition::After(ref after) => {
+                match lines.iter().rposition(|kv| kv.key == *after) {
+                    Some(i) => lines.get(i + 1).copied(),
+                    None => {
+                        return Err(de::Error::custom(
+                            None,
+                            format!("there is no key `{}` in `{}`", after, parent),
+                        ))
+                    }
+                }
+            }
+            InsertPosition::Sorted => lines.iter().copied().find(|kv| kv.key > key),
+            InsertPosition::End => None,
+            InsertPosition::BeforeSubtables => lines.iter().copied().find(|kv| {
+                let value = parent.join_key(kv.key.clone()).lookup(&self.root);
+                matches!(value, Some(Value::Table(_)))
+            }),
+        };
+        let previous = match before {
+            Some(before) => lines
+                .iter()
+                .take_while(|kv| kv.start < before.start)
+                .last()
+                .copied(),
+            None => lines.last().copied(),
+        };
+
+        let newline = self.newline();
+        let (at, text) = match (previous, before) {
+            // Directly after the previous line, to stay in its group.
+            (Some(previous), _) => {
+                let indent = self.indent(previous.start);
+                let at = self.line_end(previous.end);
+                (at, format!("{}{}{}", newline, indent, line))
+            }
+            (None, Some(before)) => {
+                let indent = self.indent(before.start);
+                let at = self.comment_block_start(self.line_start(before.start));
+                (at, format!("{}{}{}", indent, line, newline))
+            }
+            (None, None) if parent.is_empty() => (0, format!("{}{}", line, newline)),
+            (None, None) => {
+                let header = self.layout.find(&parent).unwrap();
+                let at = self.line_end(header.end);
+                (at, format!("{}{}", newline, line))
+            }
+        };
+        self.replace_source(at, at, &text)?;
+        if let Some(Value::Table(table)) = parent.lookup_mut(&mut self.root) {
+            table.insert(key, value);
+        }
+        match aligned {
+            Some(column) => self.align_section(&parent, column),
+            None => Ok(()),
+        }
+    }
+
+    /// Pads the keys of the `key = value` lines of the table at `path` so
+    /// that their `=` signs are in `column`, or further right if a key is
+    /// too long for it.
+    fn align_section(&mut self, path: &KeyPath, column: usize) -> Result<(), de::Error> {
+        let lines = self.section(path).cloned().collect::<Vec<_>>();
+        let stats = lines
+            .iter()
+            .map(|kv| self.line_stats(kv))
+            .collect::<Vec<_>>();
+        let column = stats
+            .iter()
+            .map(|line| line.key_column + line.key_width() + 1)
+            .fold(column, usize::max);
+        // From the end, so that the offsets of earlier lines stay valid.
+        for (kv, line) in lines.iter().zip(&stats).rev() {
+            let equals = kv.key_end + self.source[kv.key_end..kv.value_start].find('=').unwrap();
+            let padding = " ".repeat(column - line.key_column - line.key_width());
+            if self.source[kv.key_end..equals] != padding {
+                self.replace_source(kv.key_end, equals, &padding)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Replaces the value at `path`, rewriting only the text of what
+    /// changed.
+    ///
+    /// A value written in the source text is replaced by `value` formatted
+    /// on its own; everything around it, including comments and the layout
+    /// of other values, is kept byte for byte. Setting a table changes it
+    /// key by key, as `Document::update` does. An error is returned if
+    /// nothing is at `path`.
+    ///
+    /// ```rust
+    /// use toml::document::Document;
+    /// use toml::Value;
+    ///
+    /// let mut doc: Document = "[package]\nname    = 'demo' # the name\nversion = '0.1.0'\n"
+    ///     .parse()
+    ///     .unwrap();
+    /// doc.set("package.version", Value::from("0.2.0")).unwrap();
+    /// assert_eq!(
+    ///     doc.to_string(),
+    ///     "[package]\nname    = 'demo' # the name\nversion = \"0.2.0\"\n",
+    /// );
+    /// ```
+    pub fn set<P: Into<KeyPath>>(&mut self, path: P, value: Value) -> Result<(), de::Error> {
+        let path = path.into();
+        let mut root = self.root.clone();
+        match path.lookup_mut(&mut root) {
+            Some(slot) => *slot = value,
+            None => {
+                return Err(de::Error::custom(
+                    None,
+                    format!("`{}` is not in the document", path),
+                ))
+            }
+        }
+        match root {
+            Value::Table(table) => self.update(table),
+            _ => unreachable!(),
+        }
+    }
+
+    /// Replaces the string at `path` with `value`, written with the same
+    /// kind of quotes as the string it replaces.
+    ///
+    /// This is meant for automated edits such as bumping a version or a
+    /// dependency requirement, where the result should look as if it was
+    /// edited by hand: a literal string stays a literal string, and a
+    /// multi-line string stays multi-line, keeping whether it starts with
+    /// a newline. When `value` cannot be written with the same quotes, such
+    /// as a literal string that would contain a `'`, it is written as a
+    /// basic string with escapes instead. Everything around the string is
+    /// kept as with `Document::set`.
+    ///
+    /// An error is returned if the value at `path` is not a string written
+    /// in the document.
+    ///
+    /// ```rust
+    /// use toml::document::Document;
+    ///
+    /// let mut doc: Document = "\
+    /// [package]
+    /// version = '0.1.0'
+    /// [dependencies]
+    /// serde = { version = \"1.0\", features = ['derive'] }
+    /// ".parse().unwrap();
+    ///
+    /// doc.update_string("package.version", "0.2.0").unwrap();
+    /// doc.update_string("dependencies.serde.version", "1.0.136").unwrap();
+    /// assert_eq!(doc.to_string(), "\
+    /// [package]
+    /// version = '0.2.0'
+    /// [dependencies]
+    /// serde = { version = \"1.0.136\", features = ['derive'] }
+    /// ");
+    ///
+    /// assert!(doc.update_string("dependencies.serde.features", "x").is_err());
+    /// ```
+    pub fn update_string<P: Into<KeyPath>>(
+        &mut self,
+        path: P,
+        value: &str,
+    ) -> Result<(), de::Error> {
+        let path = path.into();
+        match self.get(&path) {
+            Some(Value::String(_)) => {}
+            _ => {
+                return Err(de::Error::custom(
+                    None,
+                    format!("`{}` is not a string in the document", path),
+                ))
+            }
+        }
+        let span = match self.entry(&path)?.value {
+            Some(span) => (span.start, span.end),
+            None => unreachable!(),
+        };
+        let text = write_string_like(&self.source[span.0..span.1], value);
+        self.replace_value_text(&path, span, text, &Value::String(value.to_string()))
+    }
+
+    /// Changes this document to hold `table`, rewriting only the text of
+    /// the values that differ from the ones it holds now.
+    ///
+    /// This is how a document is saved after its table was changed in
+    /// code, or after a struct read from it was changed and serialized
+    /// again: every region of the source text whose value is unchanged is
+    /// copied as it is, so the result differs from the source only where
+    /// values changed. Changed values are formatted on their own, removed
+    /// keys have their lines removed along with the comments above them,
+    /// and new keys are added at the end of their table as with
+    /// `InsertPosition::End`. Values inside inline tables and arrays are
+    /// handled by formatting the whole inline value again. A table with a
+    /// header, or an array of tables, that is replaced by another kind of
+    /// value or gains elements has its sections removed and is added back
+    /// as a new key.
+    ///
+    /// An error is returned, leaving the document unchanged, for a change
+    /// that cannot be written this way, such as a key added to a table
+    /// that is defined with dotted keys.
+    ///
+    /// ```rust
+    /// use toml::document::Document;
+    /// use toml::Value;
+    ///
+    /// let mut doc: Document = "\
+    /// ## deployment
+    /// [server]
+    /// host = 'localhost'   # where to listen
+    /// port = 80
+    /// ## old setting
+    /// legacy = true
+    /// ".parse().unwrap();
+    ///
+    /// let mut table = doc.as_table().clone();
+    /// let server = table.get_mut("server").unwrap().as_table_mut().unwrap();
+    /// server.insert("port".to_string(), Value::from(8080));
+    /// server.insert("tls".to_string(), Value::from(true));
+    /// server.remove("legacy");
+    ///
+    /// doc.update(table).unwrap();
+    /// assert_eq!(doc.to_string(), "\
+    /// ## deployment
+    /// [server]
+    /// host = 'localhost'   # where to listen
+    /// port = 8080
+    /// tls = true
+    /// ");
+    /// ```
+    pub fn update(&mut self, table: Table) -> Result<(), de::Error> {
+        let target = Value::Table(table);
+        let mut changes = Vec::new();
+        diff_values(&KeyPath::new(), &self.root, &target, &mut changes);
+        if changes.is_empty() {
+            return Ok(());
+        }
+        let mut doc = self.clone();
+        for change in changes {
+            doc.apply(change, &target)?;
+        }
+        doc.root = target;
+        *self = doc;
+        Ok(())
+    }
+
+    /// Makes one change found by `diff_values`, unless an earlier change
+    /// already made it.
+    fn apply(&mut self, change: Change, target: &Value) -> Result<(), de::Error> {
+        let path = match change {
+            Change::Set(ref path) | Change::Remove(ref path) | Change::Insert(ref path) => {
+                path.clone()
+            }
+        };
+        let current = path.lookup(&self.root);
+        let wanted = path.lookup(target);
+        if current == wanted {
+            return Ok(());
+        }
+        let entry = self.layout.find(&path).cloned();
+        let value_span = entry.as_ref().and_then(|e| e.value);
+        match change {
+            Change::Set(_) => {
+                if let Some(span) = value_span {
+                    return self.replace_value(&path, span.into(), wanted.unwrap());
+                }
+                // A table with a header, or an array of tables, that became
+                // something else.
+                if self.remove_definitions(&path)? {
+                    self.set_in_root(&path, None);
+                    return self.apply(Change::Insert(path), target);
+                }
+            }
+            Change::Remove(_) => {
+                if self.remove_definitions(&path)? {
+                    self.set_in_root(&path, None);
+                    return Ok(());
+                }
+            }
+            Change::Insert(_) => {
+                let (parent, _) = split_key(&path)?;
+                if self.check_section(&parent).is_ok() {
+                    return self.insert(&path, wanted.unwrap().clone(), InsertPosition::End);
+                }
+            }
+        }
+        // Inside an inline table or array, which is written again as a whole.
+        let mut ancestor = path.parent();
+        while let Some(p) = ancestor {
+            if let Some(span) = self.layout.find(&p).and_then(|e| e.value) {
+                let value = p.lookup(target).unwrap();
+                return self.replace_value(&p, span.into(), value);
+            }
+            ancestor = p.parent();
+        }
+        Err(de::Error::custom(
+            None,
+            format!("`{}` cannot be updated in place", path),
+        ))
+    }
+
+    /// Replaces the text of the value at `path` with `value`.
+    fn replace_value(
+        &mut self,
+        path: &KeyPath,
+        span: (usize, usize),
+        value: &Value,
+    ) -> Result<(), de::Error> {
+        let text = crate::ser::to_inline_value(value)
+            .map_err(|e| de::Error::custom(None, e.to_string()))?;
+        self.replace_value_text(path, span, text, value)
+    }
+
+    /// Replaces the text of the value at `path` with `text`, which holds
+    /// `value`.
+    fn replace_value_text(
+        &mut self,
+        path: &KeyPath,
+        span: (usize, usize),
+        mut text: String,
+        value: &Value,
+    ) -> Result<(), de::Error> {
+        let mut end = span.1;
+        if self.align_equals {
+            if let Some(hash) = self.comment_after(span.1) {
+                let line_start = self.line_start(span.1);
+                let column = self.source[line_start..hash].chars().count();
+                let width = match text.rfind('\n') {
+                    Some(i) => text[i + 1..].chars().count(),
+                    None => {
+                        self.source[self.line_start(span.0)..span.0].chars().count()
+                            + text.chars().count()
+                    }
+                };
+                let padding = column.saturating_sub(width).max(1);
+                text.push_str(&" ".repeat(padding));
+                end = hash;
+            }
+        }
+        self.replace_source(span.0, end, &text)?;
+        self.set_in_root(path, Some(value.clone()));
+        Ok(())
+    }
+
+    /// Sets or removes the value at `path` in the table of this document.
+    fn set_in_root(&mut self, path: &KeyPath, value: Option<Value>) {
+        let (parent, last) = match path.segments().split_last() {
+            Some((last, parent)) => (KeyPath::from(parent.to_vec()), last),
+            None => return,
+        };
+        match (parent.lookup_mut(&mut self.root), last, value) {
+            (Some(Value::Table(table)), PathSegment::Key(key), Some(value)) => {
+                table.insert(key.clone(), value);
+            }
+            (Some(Value::Table(table)), PathSegment::Key(key), None) => {
+                table.remove(key);
+            }
+            (Some(Value::Array(array)), &PathSegment::Index(i), Some(value)) => array[i] = value,
+            (Some(Value::Array(array)), &PathSegment::Index(i), None) => {
+                array.remove(i);
+            }
+            _ => {}
+        }
+    }
+
+    /// Removes the text defining the value at `path` when it is written on
+    /// lines of its own: as `key = value` lines, or as sections starting
+    /// with a table header. Returns whether it is written that way.
+    fn remove_definitions(&mut self, path: &KeyPath) -> Result<bool, de::Error> {
+        let headers = self
+            .layout
+            .entries
+            .iter()
+            .filter(|e| e.value.is_none())
+            .collect::<Vec<_>>();
+        let mut ranges = Vec::new();
+        for (i, header) in headers.iter().enumerate() {
+            if !header.path.starts_with(path) {
+                continue;
+            }
+            let start = self.comment_block_start(self.line_start(header.key.start));
+            let end = match headers.get(i + 1) {
+                Some(next) => self.comment_block_start(self.line_start(next.key.start)),
+                None => self.source.len(),
+            };
+            ranges.push((start, end));
+        }
+        for entry in &self.layout.entries {
+            if entry.value.is_none() || !entry.path.starts_with(path) {
+                continue;
+            }
+            let on_own_line = self
+                .layout
+                .key_values
+                .iter()
+                .any(|kv| kv.start == entry.key.start);
+            if on_own_line {
+                let start = self.comment_block_start(self.line_start(entry.key.start));
+                let end = self.line_end(entry.end);
+                let end = match self.source[end..].find('\n') {
+                    Some(i) => end + i + 1,
+                    None => end,
+                };
+                ranges.push((start, end));
+            }
+        }
+        if ranges.is_empty() {
+            return Ok(false);
+        }
+        ranges.sort();
+        let mut merged: Vec<(usize, usize)> = Vec::new();
+        for (start, end) in ranges {
+            match merged.last_mut() {
+                Some(last) if start <= last.1 => last.1 = last.1.max(end),
+                _ => merged.push((start, end)),
+            }
+        }
+        let mut source = String::with_capacity(self.source.len());
+        let mut copied = 0;
+        for &(start, end) in &merged {
+            source.push_str(&self.source[copied..start]);
+            copied = end;
+        }
+        source.push_str(&self.source[copied..]);
+        self.layout = Layout::scan(&source)?;
+        for &(start, end) in merged.iter().rev() {
+            self.record_edit(start, end, "");
+        }
+        self.source = source;
+        Ok(true)
+    }
+
+    /// Returns an error unless `path` is the root table or a table with a
+    /// header, whose `key = value` lines are all in one place.
+    fn check_section(&self, path: &KeyPath) -> Result<(), de::Error> {
+        if self.table_style(path) != Some(TableStyle::Standard)
+            || (!path.is_empty() && self.layout.find(path).is_none())
+        {
+            return Err(de::Error::custom(
+                None,
+                format!("`{}` is not a table with a header", path),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Returns the `key = value` lines of the table at `path`.
+    fn section<'a>(&'a self, path: &'a KeyPath) -> impl Iterator<Item = &'a KeyValue> + 'a {
+        self.layout
+            .key_values
+            .iter()
+            .filter(move |kv| kv.table == *path)
+    }
+
+    /// Returns the newline the source text uses.
+    fn newline(&self) -> &'static str {
+        if self.source.contains("\r\n") {
+            "\r\n"
+        } else {
+            "\n"
+        }
+    }
+
+    /// Returns the start of the line containing `at`.
+    fn line_start(&self, at: usize) -> usize {
+        self.source[..at].rfind('\n').map_or(0, |i| i + 1)
+    }
+
+    /// Returns the whitespace the line containing `at` starts with.
+    fn indent(&self, at: usize) -> &str {
+        let line = &self.source[self.line_start(at)..];
+        &line[..line.len() - line.trim_start_matches(&[' ', '\t'][..]).len()]
+    }
+
+    fn entry(&self, path: &KeyPath) -> Result<&Entry, de::Error> {
+        self.layout.find(path).ok_or_else(|| {
+            de::Error::custom(None, format!("`{}` is not written in the document", path))
+        })
+    }
+
+    /// Returns the byte range of the comment lines directly above `entry`,
+    /// which is empty if there are none, or `None` if something else comes
+    /// before `entry` on its line.
+    fn leading_comment_span(&self, entry: &Entry) -> Option<(usize, usize)> {
+        let src = &self.source;
+        let line_start = self.line_start(entry.key.start);
+        let header = entry.style == Some(TableStyle::Standard);
+        let starts_line = src[line_start..entry.key.start]
+            .chars()
+            .all(|c| c == ' ' || c == '\t' || (header && c == '['));
+        if !starts_line {
+            return None;
+        }
+        Some((self.comment_block_start(line_start), line_start))
+    }
+
+    /// Returns the start of the comment lines directly above the line
+    /// starting at `line_start`, or `line_start` if there are none.
+    fn comment_block_start(&self, line_start: usize) -> usize {
+        let src = &self.source;
+        let mut start = line_start;
+        while start > 0 {
+            let prev = src[..start - 1].rfind('\n').map_or(0, |i| i + 1);
+            if !src[prev..start].trim_start().starts_with('#') {
+                break;
+            }
+            start = prev;
+        }
+        start
+    }
+
+    /// Returns where the text after `entry` on its line starts, past any
+    /// comma, along with the byte range of the comment there, from the `#`
+    /// to the end of the line. `None` is returned if anything but a comment
+    /// follows.
+    fn trailing_comment_span(&self, entry: &Entry) -> Option<(usize, Option<(usize, usize)>)> {
+        let src = &self.source;
+        let mut after = entry.end;
+        if src[after..]
+            .trim_start_matches(&[' ', '\t'][..])
+            .starts_with(',')
+        {
+            after = src[after..].find(',').unwrap() + after + 1;
+        }
+        let rest = src[after..].trim_start_matches(&[' ', '\t'][..]);
+        let hash = src.len() - rest.len();
+        if rest.starts_with('#') {
+            Some((after, Some((hash, self.line_end(hash)))))
+        } else if rest.is_empty() || rest.starts_with('\n') || rest.starts_with("\r\n") {
+            Some((after, None))
+        } else {
+            None
+        }
+    }
+
+    /// Returns where the comment after the value ending at `end` starts, if
+    /// only blanks separate them.
+    fn comment_after(&self, end: usize) -> Option<usize> {
+        let rest = self.source[end..].trim_start_matches(&[' ', '\t'][..]);
+        if rest.starts_with('#') {
+            Some(self.source.len() - rest.len())
+        } else {
+            None
+        }
+    }
+
+    /// Returns the end of the line containing `at`, before its newline.
+    fn line_end(&self, at: usize) -> usize {
+        let end = self.source[at..]
+            .find('\n')
+            .map_or(self.source.len(), |i| at + i);
+        if self.source[..end].ends_with('\r') {
+            end - 1
+        } else {
+            end
+        }
+    }
+
+    fn replace_source(&mut self, start: usize, end: usize, text: &str) -> Result<(), de::Error> {
+        let mut source = String::with_capacity(self.source.len() + text.len());
+        source.push_str(&self.source[..start]);
+        source.push_str(text);
+        source.push_str(&self.source[end..]);
+        self.layout = Layout::scan(&source)?;
+        self.record_edit(start, end, text);
+        self.source = source;
+        Ok(())
+    }
+
+    /// Adds the replacement of `start..end` of the current source by `text`
+    /// to the edits since parsing, merging it with the edits it touches.
+    fn record_edit(&mut self, start: usize, end: usize, text: &str) {
+        // `delta` is how far the current source has moved from the original
+        // at the point reached.
+        let mut delta = 0isize;
+        let mut lo = self.edits.len();
+        let mut hi = self.edits.len();
+        let mut start_delta = None;
+        let mut end_delta = 0;
+        for (i, edit) in self.edits.iter().enumerate() {
+            let cur_start = (edit.span.0 as isize + delta) as usize;
+            let cur_end = cur_start + edit.replacement.len();
+            if cur_end < start {
+                delta += edit.replacement.len() as isize - (edit.span.1 - edit.span.0) as isize;
+                continue;
+            }
+            if cur_start > end {
+                hi = i;
+                if start_delta.is_none() {
+                    lo = i;
+                }
+                break;
+            }
+            if start_delta.is_none() {
+                lo = i;
+                start_delta = Some(delta);
+            }
+            delta += edit.replacement.len() as isize - (edit.span.1 - edit.span.0) as isize;
+        }
+        let start_delta = start_delta.unwrap_or(delta);
+        if lo < hi {
+            end_delta = start_delta;
+            for edit in &self.edits[lo..hi - 1] {
+                end_delta += edit.replacement.len() as isize - (edit.span.1 - edit.span.0) as isize;
+            }
+        }
+
+        let mut orig_start = (start as isize - start_delta) as usize;
+        let mut orig_end = (end as isize - delta) as usize;
+        let mut replacement = String::new();
+        if lo < hi {
+            let first = &self.edits[lo];
+            let cur_start = (first.span.0 as isize + start_delta) as usize;
+            if cur_start <= start {
+                orig_start = first.span.0;
+                replacement.push_str(&first.replacement[..start - cur_start]);
+            }
+        }
+        replacement.push_str(text);
+        if lo < hi {
+            let last = &self.edits[hi - 1];
+            let cur_start = (last.span.0 as isize + end_delta) as usize;
+            if cur_start + last.replacement.len() >= end {
+                orig_end = last.span.1;
+                replacement.push_str(&last.replacement[end - cur_start..]);
+            }
+        }
+
+        let original = &self.original[orig_start..orig_end];
+        let mut prefix = original
+            .bytes()
+            .zip(replacement.bytes())
+            .take_while(|(a, b)| a == b)
+            .count();
+        while !original.is_char_boundary(prefix) {
+            prefix -= 1;
+        }
+        let mut suffix = original[prefix..]
+            .bytes()
+            .rev()
+            .zip(replacement[prefix..].bytes().rev())
+            .take_while(|(a, b)| a == b)
+            .count();
+        while !original.is_char_boundary(original.len() - suffix) {
+            suffix -= 1;
+        }
+        let edits = if prefix + suffix == original.len() && prefix + suffix == replacement.len() {
+            Vec::new()
+        } else {
+            vec![TextEdit {
+                span: (orig_start + prefix, orig_end - suffix),
+                replacement: replacement[prefix..replacement.len() - suffix].to_string(),
+            }]
+        };
+        self.edits.splice(lo..hi, edits);
+    }
+
+    /// Returns the directives in the comments of this document that start
+    /// with `prefix`, as found by `directive::directives`.
+    pub fn directives(&self, prefix: &str) -> Vec<Directive> {
+        crate::directive::directives(&self.source, prefix).unwrap_or_default()
+    }
+
+    /// Returns the annotations attached to paths of this document.
+    pub fn metadata(&self) -> &Metadata {
+        &self.metadata
+    }
+
+    /// Returns a mutable reference to the annotations attached to paths of
+    /// this document.
+    pub fn metadata_mut(&mut self) -> &mut Metadata {
+        &mut self.metadata
+    }
+}
+
+/// A replacement of part of the source a document was parsed from, as
+/// returned by `Document::edits`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextEdit {
+    span: (usize, usize),
+    replacement: String,
+}
+
+impl TextEdit {
+    /// Returns the start and end byte offsets of the text to replace.
+    ///
+    /// The span is empty when the edit only inserts text.
+    pub fn span(&self) -> (usize, usize) {
+        self.span
+    }
+
+    /// Returns the text to put in place of the span.
+    pub fn replacement(&self) -> &str {
+        &self.replacement
+    }
+}
+
+/// Measurements of the `key = value` lines of a table, as returned by
+/// `Document::table_stats`.
+///
+/// Widths and columns count characters, with the first column of a line
+/// being column 0.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct TableStats {
+    lines: Vec<LineStats>,
+}
+
+impl TableStats {
+    /// Returns the measurements of each line, in source order.
+    pub fn lines(&self) -> &[LineStats] {
+        &self.lines
+    }
+
+    /// Returns the number of lines.
+    pub fn len(&self) -> usize {
+        self.lines.len()
+    }
+
+    /// Returns whether the table has no lines of its own.
+    pub fn is_empty(&self) -> bool {
+        self.lines.is_empty()
+    }
+
+    /// Returns the width of the longest key as written, or 0 if there are
+    /// no lines.
+    pub fn longest_key(&self) -> usize {
+        self.lines
+            .iter()
+            .map(LineStats::key_width)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Returns the width of the widest value as written, or 0 if there are
+    /// no lines.
+    pub fn longest_value(&self) -> usize {
+        self.lines.iter().map(|l| l.value_width).max().unwrap_or(0)
+    }
+
+    /// Returns whether the `=` signs are lined up on purpose: there are at
+    /// least two lines, they all put the `=` in the same column, and at
+    /// least one key is followed by more than one space to reach it.
+    pub fn is_aligned(&self) -> bool {
+        self.lines.len() >= 2
+            && self.equals_column().is_some()
+            && self
+                .lines
+                .iter()
+                .any(|l| l.equals_column > l.key_column + l.key_width() + 1)
+    }
+
+    /// Returns the column of the `=` signs if every line puts it in the
+    /// same column, and `None` if they differ or there are no lines.
+    pub fn equals_column(&self) -> Option<usize> {
+        let column = self.lines.first()?.equals_column;
+        if self.lines.iter().all(|l| l.equals_column == column) {
+            Some(column)
+        } else {
+            None
+        }
+    }
+}
+
+/// Measurements of one `key = value` line, as returned by
+/// `TableStats::lines`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LineStats {
+    key: String,
+    key_column: usize,
+    equals_column: usize,
+    value_width: usize,
+}
+
+impl LineStats {
+    /// Returns the key as written, such as `"a b"` or `log.level`.
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Returns the width of the key as written.
+    pub fn key_width(&self) -> usize {
+        self.key.chars().count()
+    }
+
+    /// Returns the column the key starts at, which is its indentation.
+    pub fn key_column(&self) -> usize {
+        self.key_column
+    }
+
+    /// Returns the column of the `=` sign.
+    pub fn equals_column(&self) -> usize {
+        self.equals_column
+    }
+
+    /// Returns the width of the value as written, or of its widest line if
+    /// it spans several.
+    pub fn value_width(&self) -> usize {
+        self.value_width
+    }
+}
+
+impl FromStr for Document {
+    type Err = de::Error;
+
+    fn from_str(s: &str) -> Result<Document, de::Error> {
+        Document::parse(s)
+    }
+}
+
+impl fmt::Display for Document {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+/// Writes `value` as a string with the same kind of quotes as the string
+/// written as `old`, or as a basic string if it cannot be written that way.
+fn write_string_like(old: &str, value: &str) -> String {
+    let control = |c: char| c != '\t' && c != '\n' && (c <= '\u{1f}' || c == '\u{7f}');
+    let mut out = String::new();
+    if let Some(rest) = old.strip_prefix("'''") {
+        let literal = !value.contains("'''") && !value.ends_with('\'') && !value.contains(control);
+        if literal {
+            out.push_str("'''");
+            out.push_str(first_newline(rest, value));
+            out.push_str(value);
+            out.push_str("'''");
+            return out;
+        }
+    } else if let Some(rest) = old.strip_prefix("\"\"\"") {
+        out.push_str("\"\"\"");
+        out.push_str(first_newline(rest, value));
+        // Newlines stay as they are, and everything else is escaped as in a
+        // basic string.
+        for (i, line) in value.split('\n').enumerate() {
+            if i > 0 {
+                out.push('\n');
+            }
+            let mut escaped = String::new();
+            crate::display::write_basic_str(&mut escaped, line).unwrap();
+            out.push_str(&escaped[1..escaped.len() - 1]);
+        }
+        out.push_str("\"\"\"");
+        return out;
+    } else if old.starts_with('\'')
+        && !value.contains(|c: char| c == '\'' || c == '\n' || control(c))
+    {
+        return format!("'{}'", value);
+    }
+    crate::display::write_basic_str(&mut out, value).unwrap();
+    out
+}
+
+/// Returns the newline to write after the opening quotes of a multi-line
+/// string holding `value`, where `rest` follows the quotes of the string
+/// it replaces.
+fn first_newline(rest: &str, value: &str) -> &'static str {
+    if rest.starts_with("\r\n") {
+        "\r\n"
+    } else if rest.starts_with('\n') || value.starts_with('\n') {
+        "\n"
+    } else {
+        ""
+    }
+}
+
+/// Checks that `comment` can be written as a comment, on several lines if
+/// `multiline` is set.
+fn check_comment(path: &KeyPath, comment: &str, multiline: bool) -> Result<(), de::Error> {
+    let bad = comment.chars().find(|&c| {
+        if c == '\n' || c == '\r' {
+            !multiline
+        } else {
+            crate::ser::is_forbidden_control(c)
+        }
+    });
+    match bad {
+        Some('\n') | Some('\r') => Err(de::Error::custom(
+            None,
+            format!("the comment after `{}` must fit on one line", path),
+        )),
+        Some(c) => Err(de::Error::custom(
+            None,
+            format!(
+                "the comment for `{}` contains the control character U+{:04X}",
+                path, c as u32
+            ),
+        )),
+        None => Ok(()),
+    }
+}
+
+/// Where `Document::insert` places a new key in its table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InsertPosition {
+    /// Directly after the line of the given key of the table.
+    After(String),
+    /// Before the first key of the table that sorts after the new key, as
+    /// in a table kept in alphabetical order.
+    Sorted,
+    /// After the last key of the table.
+    End,
+    /// Before the first key of the table holding a table, written inline or
+    /// with dotted keys, so that plain values come first. This is the end of
+    /// the table if it has no such key.
+    BeforeSubtables,
+}
+
+/// A difference between the table of a document and the one it is updated
+/// to.
+enum Change {
+    /// The value at the path is different.
+    Set(KeyPath),
+    /// The key or array element at the path was removed.
+    Remove(KeyPath),
+    /// The key at the path was added.
+    Insert(KeyPath),
+}
+
+/// Lists the changes that turn `old` into `new`, going into tables and into
+/// arrays that only changed elements or lost some at the end.
+fn diff_values(path: &KeyPath, old: &Value, new: &Value, changes: &mut Vec<Change>) {
+    if old == new {
+        return;
+    }
+    match (old, new) {
+        (Value::Table(old), Value::Table(new)) => {
+            for key in old.keys() {
+                if !new.contains_key(key) {
+                    changes.push(Change::Remove(path.join_key(key.clone())));
+                }
+            }
+            for (key, value) in new {
+                let path = path.join_key(key.clone());
+                match old.get(key) {
+                    Some(old) => diff_values(&path, old, value, changes),
+                    None => changes.push(Change::Insert(path)),
+                }
+            }
+        }
+        (Value::Array(old), Value::Array(new)) if new.len() <= old.len() => {
+            for (i, (old, new)) in old.iter().zip(new).enumerate() {
+                diff_values(&path.join_index(i), old, new, changes);
+            }
+            for i in (new.len()..old.len()).rev() {
+                changes.push(Change::Remove(path.join_index(i)));
+            }
+        }
+        _ => changes.push(Change::Set(path.clone())),
+    }
+}
+
+/// Splits `path` into the table it is in and its last key.
+fn split_key(path: &KeyPath) -> Result<(KeyPath, String), de::Error> {
+    match path.segments().split_last() {
+        Some((PathSegment::Key(key), parent)) => Ok((KeyPath::from(parent.to_vec()), key.clone())),
+        _ => Err(de::Error::custom(
+            None,
+            format!("`{}` does not name a key", path),
+        )),
+    }
+}
@@ -0,0 +1,245 @@
+//! Comparing the configuration a program uses with the file it read.
+//!
+//! A program reads its configuration file into a struct, and from then on
+//! uses the struct: fields missing from the file take their defaults, keys
+//! that are not fields are dropped, and values may be changed after reading,
+//! for example from the command line. Serializing the struct again and
+//! comparing it with the table read from the file tells these apart, which
+//! is what an option such as `--print-effective-config` shows:
+//!
+//! ```rust
+//! use serde_derive::{Deserialize, Serialize};
+//! use toml::effective::{EffectiveConfig, Provenance};
+//!
+//! #[derive(Deserialize, Serialize)]
+//! struct Config {
+//!     host: String,
+//!     #[serde(default = "default_port")]
+//!     port: u16,
+//!     workers: u32,
+//! }
+//!
+//! fn default_port() -> u16 {
+//!     8080
+//! }
+//!
+//! let file: toml::value::Table = toml::from_str("host = 'a'\nworkers = 2\nlegacy = true\n").unwrap();
+//! let mut config: Config = toml::Value::Table(file.clone()).try_into().unwrap();
+//! config.workers = 8; // from the command line
+//!
+//! let effective = EffectiveConfig::from_config(&file, &config).unwrap();
+//! assert_eq!(effective.provenance("host"), Some(Provenance::File));
+//! assert_eq!(effective.provenance("port"), Some(Provenance::Defaulted));
+//! assert_eq!(effective.provenance("workers"), Some(Provenance::Overridden));
+//! assert_eq!(effective.provenance("legacy"), Some(Provenance::Ignored));
+//!
+//! assert_eq!(effective.to_annotated_string().unwrap(), "\
+//! ## ignored: `legacy` is set in the file but not used
+//!
+//! ## from the file
+//! host = \"a\"
+//! ## default
+//! port = 8080
+//! ## overridden; the file sets 2
+//! workers = 8
+//! ");
+//! ```
+
+use serde::ser::Serialize;
+
+use crate::path::KeyPath;
+use crate::ser::{self, Serializer};
+use crate::value::{Table, Value};
+
+/// Where the value of a setting comes from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Provenance {
+    /// The value is the one in the file.
+    File,
+    /// The file does not set the value, so it is a default.
+    Defaulted,
+    /// The file sets a different value.
+    Overridden,
+    /// The file sets a value that the program does not use.
+    Ignored,
+}
+
+/// One setting of an `EffectiveConfig`, with its value in the file and in
+/// effect.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Setting {
+    path: KeyPath,
+    provenance: Provenance,
+    file: Option<Value>,
+    effective: Option<Value>,
+}
+
+impl Setting {
+    /// Returns the path of the setting.
+    pub fn path(&self) -> &KeyPath {
+        &self.path
+    }
+
+    /// Returns where the value of the setting comes from.
+    pub fn provenance(&self) -> Provenance {
+        self.provenance
+    }
+
+    /// Returns the value in the file, if it sets one.
+    pub fn file_value(&self) -> Option<&Value> {
+        self.file.as_ref()
+    }
+
+    /// Returns the value in effect, or `None` for an ignored setting.
+    pub fn effective_value(&self) -> Option<&Value> {
+        self.effective.as_ref()
+    }
+}
+
+/// The configuration in effect, compared with the file it was read from.
+///
+/// Settings are compared value by value, going into tables on both sides.
+/// Any other value, arrays included, is one setting; a table on one side
+/// only has each of its values listed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EffectiveConfig {
+    effective: Table,
+    settings: Vec<Setting>,
+}
+
+impl EffectiveConfig {
+    /// Compares `effective`, the configuration in effect, with `file`, the
+    /// table read from the file.
+    pub fn new(file: &Table, effective: Table) -> EffectiveConfig {
+        let mut settings = Vec::new();
+        compare_tables(&mut KeyPath::new(), file, &effective, &mut settings);
+        EffectiveConfig {
+            effective,
+            settings,
+        }
+    }
+
+    /// Serializes `config`, the struct read from `file`, and compares it
+    /// with `file`.
+    pub fn from_config<T>(file: &Table, config: &T) -> Result<EffectiveConfig, ser::Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        match Value::try_from(config)? {
+            Value::Table(effective) => Ok(EffectiveConfig::new(file, effective)),
+            _ => Err(ser::Error::UnsupportedType),
+        }
+    }
+
+    /// Returns the configuration in effect.
+    pub fn effective(&self) -> &Table {
+        &self.effective
+    }
+
+    /// Returns every setting, in the order of the configuration in effect,
+    /// with the settings that only the file has after the others of their
+    /// table.
+    pub fn settings(&self) -> &[Setting] {
+        &self.settings
+    }
+
+    /// Returns where the value of the setting at `path` comes from, or
+    /// `None` if there is no such setting.
+    pub fn provenance<P: Into<KeyPath>>(&self, path: P) -> Option<Provenance> {
+        let path = path.into();
+        self.settings
+            .iter()
+            .find(|s| s.path == path)
+            .map(|s| s.provenance)
+    }
+
+    /// Returns the settings with the given provenance.
+    pub fn with_provenance(&self, provenance: Provenance) -> impl Iterator<Item = &Setting> {
+        self.settings
+            .iter()
+            .filter(move |s| s.provenance == provenance)
+    }
+
+    /// Writes the configuration in effect as TOML, with a comment above each
+    /// setting telling where its value comes from, and the ignored settings
+    /// listed in comments at the top.
+    pub fn to_annotated_string(&self) -> Result<String, ser::Error> {
+        let mut out = String::new();
+        for setting in self.with_provenance(Provenance::Ignored) {
+            out.push_str(&format!(
+                "# ignored: `{}` is set in the file but not used\n",
+                setting.path
+            ));
+        }
+        if !out.is_empty() {
+            out.push('\n');
+        }
+        let mut body = String::new();
+        let mut serializer = Serializer::new(&mut body);
+        for setting in &self.settings {
+            let note = match setting.provenance {
+                Provenance::File => "from the file".to_string(),
+                Provenance::Defaulted => "default".to_string(),
+                Provenance::Overridden => match setting.file {
+                    Some(ref value) => {
+                        format!("overridden; the file sets {}", ser::to_inline_value(value)?)
+                    }
+                    None => "overridden".to_string(),
+                },
+                Provenance::Ignored => continue,
+            };
+            serializer.key_doc(&setting.path, &note);
+        }
+        self.effective.serialize(&mut serializer)?;
+        out.push_str(&body);
+        Ok(out)
+    }
+}
+
+fn compare_tables(path: &mut KeyPath, file: &Table, effective: &Table, out: &mut Vec<Setting>) {
+    for (key, value) in effective {
+        path.push_key(key.clone());
+        compare(path, file.get(key), Some(value), out);
+        path.pop();
+    }
+    for (key, value) in file {
+        if !effective.contains_key(key) {
+            path.push_key(key.clone());
+            compare(path, Some(value), None, out);
+            path.pop();
+        }
+    }
+}
+
+fn compare(
+    path: &mut KeyPath,
+    file: Option<&Value>,
+    effective: Option<&Value>,
+    out: &mut Vec<Setting>,
+) {
+    let empty = Table::new();
+    match (file, effective) {
+        (Some(Value::Table(file)), Some(Value::Table(effective))) => {
+            return compare_tables(path, file, effective, out)
+        }
+        (Some(Value::Table(file)), None) if !file.is_empty() => {
+            return compare_tables(path, file, &empty, out)
+        }
+        (None, Some(Value::Table(effective))) if !effective.is_empty() => {
+            return compare_tables(path, &empty, effective, out)
+        }
+        _ => {}
+    }
+    let provenance = match (file, effective) {
+        (Some(file), Some(effective)) if file == effective => Provenance::File,
+        (Some(_), Some(_)) => Provenance::Overridden,
+        (None, _) => Provenance::Defaulted,
+        (Some(_), None) => Provenance::Ignored,
+    };
+    out.push(Setting {
+        path: path.clone(),
+        provenance,
+        file: file.cloned(),
+        effective: effective.cloned(),
+    });
+}
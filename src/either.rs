@@ -0,0 +1,329 @@
+use std::fmt;
+
+use serde::de::{self, Deserialize, DeserializeOwned, Deserializer};
+use serde::ser::{Serialize, Serializer};
+
+use crate::de::Error;
+use crate::value::Value;
+
+/// A value that is one of two types, read by trying each in order.
+///
+/// Serde's `#[serde(untagged)]` reports only "data did not match any
+/// variant" when every variant fails. `Either` instead reports the error of
+/// the alternative that got furthest: the one that failed deepest inside
+/// the value, or, at the same depth, the one that accepted the value's type.
+/// Ties go to the earlier alternative.
+///
+/// ```rust
+/// use serde_derive::Deserialize;
+/// use toml::Either;
+///
+/// #[derive(Debug, Deserialize)]
+/// struct Detailed {
+///     version: String,
+///     optional: bool,
+/// }
+///
+/// #[derive(Debug, Deserialize)]
+/// struct Manifest {
+///     dependency: Either<String, Detailed>,
+/// }
+///
+/// let manifest: Manifest = toml::from_str("dependency = \"1.0\"").unwrap();
+/// assert_eq!(manifest.dependency.left().unwrap(), "1.0");
+///
+/// let err = toml::from_str::<Manifest>("dependency = { version = \"1.0\", optional = 1 }")
+///     .unwrap_err();
+/// assert_eq!(
+///     err.to_string(),
+///     "invalid type: integer `1`, expected a boolean in `optional` \
+///      for key `dependency` at line 1 column 1",
+/// );
+/// ```
+///
+/// Values are buffered as a [`Value`] before the alternatives are tried, so
+/// an alternative cannot borrow from the input or use [`Spanned`].
+///
+/// [`Value`]: value/enum.Value.html
+/// [`Spanned`]: struct.Spanned.html
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Either<A, B> {
+    /// The value read as the first type.
+    Left(A),
+    /// The value read as the second type.
+    Right(B),
+}
+
+impl<A, B> Either<A, B> {
+    /// Returns whether the value is of the first type.
+    pub fn is_left(&self) -> bool {
+        matches!(self, Either::Left(_))
+    }
+
+    /// Returns whether the value is of the second type.
+    pub fn is_right(&self) -> bool {
+        matches!(self, Either::Right(_))
+    }
+
+    /// Returns the value if it is of the first type.
+    pub fn left(self) -> Option<A> {
+        match self {
+            Either::Left(a) => Some(a),
+            Either::Right(_) => None,
+        }
+    }
+
+    /// Returns the value if it is of the second type.
+    pub fn right(self) -> Option<B> {
+        match self {
+            Either::Left(_) => None,
+            Either::Right(b) => Some(b),
+        }
+    }
+}
+
+impl<'de, A, B> Deserialize<'de> for Either<A, B>
+where
+    A: DeserializeOwned + 'static,
+    B: DeserializeOwned + 'static,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        OneOf::new()
+            .or(Either::Left)
+            .or(Either::Right)
+            .deserialize(deserializer)
+    }
+}
+
+impl<A, B> Serialize for Either<A, B>
+where
+    A: Serialize,
+    B: Serialize,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match self {
+            Either::Left(a) => a.serialize(serializer),
+            Either::Right(b) => b.serialize(serializer),
+        }
+    }
+}
+
+struct Alternative<T> {
+    name: String,
+    read: Box<dyn Fn(Value) -> Result<T, Error>>,
+}
+
+/// Reads a value as the first of several types that accepts it.
+///
+/// This is the general form of [`Either`], for any number of alternatives
+/// and any resulting type, and is meant for implementing `Deserialize` by
+/// hand. Errors are reported the same way: the alternative that got
+/// furthest is the one whose error is returned.
+///
+/// ```rust
+/// use serde::{Deserialize, Deserializer};
+/// use toml::OneOf;
+///
+/// #[derive(Debug, PartialEq)]
+/// enum Port {
+///     Number(u16),
+///     Range(u16, u16),
+///     Named(String),
+/// }
+///
+/// impl<'de> Deserialize<'de> for Port {
+///     fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Port, D::Error> {
+///         OneOf::new()
+///             .or(Port::Number)
+///             .or(|(start, end)| Port::Range(start, end))
+///             .or(Port::Named)
+///             .deserialize(deserializer)
+///     }
+/// }
+///
+/// let ports: Vec<Port> = toml::Value::Array(vec![
+///     toml::Value::Integer(80),
+///     toml::Value::Array(vec![toml::Value::Integer(8000), toml::Value::Integer(8080)]),
+///     toml::Value::String("https".to_string()),
+/// ])
+/// .try_into()
+/// .unwrap();
+/// assert_eq!(ports, [Port::Number(80), Port::Range(8000, 8080), Port::Named("https".to_string())]);
+/// ```
+///
+/// [`Either`]: enum.Either.html
+pub struct OneOf<T> {
+    alternatives: Vec<Alternative<T>>,
+    report_all: bool,
+}
+
+impl<T> OneOf<T> {
+    /// Creates a combinator with no alternatives.
+    pub fn new() -> OneOf<T> {
+        OneOf {
+            alternatives: Vec::new(),
+            report_all: false,
+        }
+    }
+
+    /// Adds an alternative, tried after the ones before it, which reads the
+    /// value as an `A` and maps it with `f`.
+    ///
+    /// The alternative is named after `A` in errors, without its module
+    /// path.
+    pub fn or<A, F>(self, f: F) -> OneOf<T>
+    where
+        A: DeserializeOwned,
+        F: Fn(A) -> T + 'static,
+    {
+        let name = short_type_name(std::any::type_name::<A>());
+        self.or_named(&name, f)
+    }
+
+    /// Adds an alternative like [`or`], with the name it is given in errors.
+    ///
+    /// [`or`]: #method.or
+    pub fn or_named<A, F>(mut self, name: &str, f: F) -> OneOf<T>
+    where
+        A: DeserializeOwned,
+        F: Fn(A) -> T + 'static,
+    {
+        self.alternatives.push(Alternative {
+            name: name.to_string(),
+            read: Box::new(move |value: Value| value.try_into().map(&f)),
+        });
+        self
+    }
+
+    /// Reports why each alternative failed when none accepts the value,
+    /// rather than only the error of the alternative that got furthest.
+    ///
+    /// ```rust
+    /// use toml::{OneOf, Value};
+    ///
+    /// let limit = OneOf::new()
+    ///     .or_named("count", |n: u32| n.to_string())
+    ///     .or_named("enabled", |b: bool| b.to_string())
+    ///     .report_all();
+    ///
+    /// let err = limit.from_value(Value::String("lots".to_string())).unwrap_err();
+    /// assert_eq!(
+    ///     err.to_string(),
+    ///     "no alternative accepts the value: \
+    ///      count: invalid type: string \"lots\", expected u32; \
+    ///      enabled: invalid type: string \"lots\", expected a boolean",
+    /// );
+    /// ```
+    pub fn report_all(mut self) -> OneOf<T> {
+        self.report_all = true;
+        self
+    }
+
+    /// Reads `value` as each alternative in turn, returning the first that
+    /// accepts it, or the name and error of every alternative if none does.
+    pub fn try_each(&self, value: Value) -> Result<T, Vec<(String, Error)>> {
+        let mut failures = Vec::with_capacity(self.alternatives.len());
+        for alternative in &self.alternatives {
+            match (alternative.read)(value.clone()) {
+                Ok(t) => return Ok(t),
+                Err(e) => failures.push((alternative.name.clone(), e)),
+            }
+        }
+        Err(failures)
+    }
+
+    /// Reads `value` as the first alternative that accepts it.
+    ///
+    /// If none does, the error of the alternative that got furthest is
+    /// returned, or one listing the error of every alternative if
+    /// [`report_all`] is set.
+    ///
+    /// [`report_all`]: #method.report_all
+    pub fn from_value(&self, value: Value) -> Result<T, Error> {
+        let failures = match self.try_each(value) {
+            Ok(t) => return Ok(t),
+            Err(failures) => failures,
+        };
+        if failures.is_empty() {
+            return Err(de::Error::custom("no alternatives to try"));
+        }
+        if self.report_all {
+            let reasons = failures
+                .iter()
+                .map(|(name, e)| format!("{}: {}", name, e.describe_nested()))
+                .collect::<Vec<_>>();
+            return Err(de::Error::custom(format!(
+                "no alternative accepts the value: {}",
+                reasons.join("; ")
+            )));
+        }
+        let mut best: Option<Error> = None;
+        for (_, e) in failures {
+            match best {
+                Some(ref b) if b.progress() >= e.progress() => {}
+                _ => best = Some(e),
+            }
+        }
+        Err(best.expect("at least one alternative failed"))
+    }
+
+    /// Reads a value from `deserializer` as the first alternative that
+    /// accepts it, for use in a hand-written `Deserialize` implementation.
+    pub fn deserialize<'de, D>(&self, deserializer: D) -> Result<T, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let value = Value::deserialize(deserializer)?;
+        self.from_value(value)
+            .map_err(|e| de::Error::custom(e.describe_nested()))
+    }
+}
+
+impl<T> Default for OneOf<T> {
+    fn default() -> OneOf<T> {
+        OneOf::new()
+    }
+}
+
+impl<T> fmt::Debug for OneOf<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OneOf")
+            .field(
+                "alternatives",
+                &self
+                    .alternatives
+                    .iter()
+                    .map(|a| &a.name)
+                    .collect::<Vec<_>>(),
+            )
+            .field("report_all", &self.report_all)
+            .finish()
+    }
+}
+
+/// Drops the module paths from a type name, so that
+/// `alloc::vec::Vec<alloc::string::String>` becomes `Vec<String>`.
+fn short_type_name(name: &str) -> String {
+    let mut out = String::with_capacity(name.len());
+    let mut segment_start = 0;
+    let mut rest = name;
+    while let Some(c) = rest.chars().next() {
+        if rest.starts_with("::") {
+            out.truncate(segment_start);
+            rest = &rest[2..];
+            continue;
+        }
+        out.push(c);
+        if !(c.is_alphanumeric() || c == '_') {
+            segment_start = out.len();
+        }
+        rest = &rest[c.len_utf8()..];
+    }
+    out
+}
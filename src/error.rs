@@ -0,0 +1,102 @@
+//! A single error type for everything that can go wrong reading or writing
+//! TOML.
+
+use std::error;
+use std::fmt;
+
+use crate::{de, ser};
+
+/// An error from either deserializing or serializing TOML.
+///
+/// Functions such as `toml::from_str` and `toml::to_string` return the error
+/// type of their own direction, which converts into this one with `?`. An
+/// application that loads and saves its configuration can then report both
+/// through one type:
+///
+/// ```rust
+/// use serde_derive::{Deserialize, Serialize};
+///
+/// #[derive(Deserialize, Serialize)]
+/// struct Config {
+///     name: String,
+/// }
+///
+/// fn upgrade_config(text: &str) -> Result<String, toml::Error> {
+///     let config: Config = toml::from_str(text)?;
+///     Ok(toml::to_string(&config)?)
+/// }
+///
+/// let err = upgrade_config("name = 1").unwrap_err();
+/// assert!(matches!(err, toml::Error::De(_)));
+/// assert_eq!(err.line_col(), Some((0, 7)));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    /// Parsing a document, or deserializing a type from it, failed.
+    De(de::Error),
+    /// Serializing a type failed.
+    Ser(ser::Error),
+}
+
+impl Error {
+    /// Produces a (line, column) pair of the position of the error if
+    /// available.
+    ///
+    /// All indexes are 0-based. Serialization errors have no position.
+    pub fn line_col(&self) -> Option<(usize, usize)> {
+        match self {
+            Error::De(e) => e.line_col(),
+            Error::Ser(_) => None,
+        }
+    }
+
+    /// Returns the byte offset in the input of the position of the error if
+    /// available.
+    ///
+    /// Serialization errors have no position.
+    pub fn offset(&self) -> Option<usize> {
+        match self {
+            Error::De(e) => e.offset(),
+            Error::Ser(_) => None,
+        }
+    }
+}
+
+impl From<de::Error> for Error {
+    fn from(e: de::Error) -> Error {
+        Error::De(e)
+    }
+}
+
+impl From<ser::Error> for Error {
+    fn from(e: ser::Error) -> Error {
+        Error::Ser(e)
+    }
+}
+
+impl From<Error> for std::io::Error {
+    fn from(e: Error) -> Self {
+        match e {
+            Error::De(e) => e.into(),
+            Error::Ser(e) => e.into(),
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::De(e) => e.fmt(f),
+            Error::Ser(e) => e.fmt(f),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Error::De(e) => e.source(),
+            Error::Ser(e) => e.source(),
+        }
+    }
+}
@@ -0,0 +1,531 @@
+//! Types for common kinds of values stored as TOML strings.
+//!
+//! Manifests and similar files are full of version numbers and version
+//! requirements, written as strings that every reader parses again.
+//! [`Version`] and [`VersionReq`] are read from those strings directly, and
+//! a malformed one fails at its line and column like a value of the wrong
+//! type:
+//!
+//! ```rust
+//! use serde_derive::Deserialize;
+//! use toml::ext::{Version, VersionReq};
+//!
+//! #[derive(Debug, Deserialize)]
+//! struct Package {
+//!     version: Version,
+//!     requires: VersionReq,
+//! }
+//!
+//! let package: Package = toml::from_str("version = '1.4.2'\nrequires = '^1.2'\n").unwrap();
+//! assert!(package.version > "1.4.0".parse().unwrap());
+//! assert!(package.requires.matches(&package.version));
+//!
+//! let err = toml::from_str::<Package>("version = '1.4'\nrequires = '1'\n").unwrap_err();
+//! assert_eq!(
+//!     err.to_string(),
+//!     "`1.4` is not a version: expected major.minor.patch for key `version` at line 1 column 11",
+//! );
+//! ```
+//!
+//! [`Version`]: struct.Version.html
+//! [`VersionReq`]: struct.VersionReq.html
+
+use std::cmp::Ordering;
+use std::error;
+use std::fmt;
+use std::str::FromStr;
+
+use serde::de::{self, Deserialize, Deserializer};
+use serde::ser::{Serialize, Serializer};
+
+/// A semantic version, such as `1.2.3`, `1.0.0-beta.2` or `2.1.0+build.5`.
+///
+/// Versions are ordered by precedence as semantic versioning defines it: a
+/// pre-release sorts before the release it precedes, and pre-release
+/// identifiers compare numerically when they are numbers. Build metadata
+/// does not affect precedence; it only breaks ties between versions that
+/// are otherwise equal.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Version {
+    /// The major version.
+    pub major: u64,
+    /// The minor version.
+    pub minor: u64,
+    /// The patch version.
+    pub patch: u64,
+    /// The pre-release identifiers after `-`, or an empty string.
+    pub pre: String,
+    /// The build metadata after `+`, or an empty string.
+    pub build: String,
+}
+
+impl Version {
+    /// Creates the release version `major.minor.patch`.
+    pub fn new(major: u64, minor: u64, patch: u64) -> Version {
+        Version {
+            major,
+            minor,
+            patch,
+            pre: String::new(),
+            build: String::new(),
+        }
+    }
+
+    /// Parses a version.
+    pub fn parse(s: &str) -> Result<Version, VersionError> {
+        parse_version(s).map_err(|reason| VersionError::new(s, false, reason))
+    }
+
+    /// Returns whether this is a pre-release, such as `1.0.0-rc.1`.
+    pub fn is_prerelease(&self) -> bool {
+        !self.pre.is_empty()
+    }
+
+    /// Compares the precedence of two versions, ignoring build metadata.
+    fn cmp_precedence(&self, other: &Version) -> Ordering {
+        (self.major, self.minor, self.patch)
+            .cmp(&(other.major, other.minor, other.patch))
+            .then_with(|| cmp_pre(&self.pre, &other.pre))
+    }
+}
+
+impl PartialOrd for Version {
+    fn partial_cmp(&self, other: &Version) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Version {
+    fn cmp(&self, other: &Version) -> Ordering {
+        self.cmp_precedence(other)
+            .then_with(|| self.build.cmp(&other.build))
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)?;
+        if !self.pre.is_empty() {
+            write!(f, "-{}", self.pre)?;
+        }
+        if !self.build.is_empty() {
+            write!(f, "+{}", self.build)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Version {
+    type Err = VersionError;
+
+    fn from_str(s: &str) -> Result<Version, VersionError> {
+        Version::parse(s)
+    }
+}
+
+/// Compares pre-release identifiers, where no identifiers at all sort last.
+fn cmp_pre(a: &str, b: &str) -> Ordering {
+    match (a.is_empty(), b.is_empty()) {
+        (true, true) => return Ordering::Equal,
+        (true, false) => return Ordering::Greater,
+        (false, true) => return Ordering::Less,
+        (false, false) => {}
+    }
+    let mut a = a.split('.');
+    let mut b = b.split('.');
+    loop {
+        let ordering = match (a.next(), b.next()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(x), Some(y)) => match (x.parse::<u64>(), y.parse::<u64>()) {
+                (Ok(x), Ok(y)) => x.cmp(&y),
+                (Ok(_), Err(_)) => Ordering::Less,
+                (Err(_), Ok(_)) => Ordering::Greater,
+                (Err(_), Err(_)) => x.cmp(y),
+            },
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+}
+
+fn parse_version(s: &str) -> Result<Version, &'static str> {
+    let (rest, build) = match s.find('+') {
+        Some(i) => (&s[..i], &s[i + 1..]),
+        None => (s, ""),
+    };
+    let (core, pre) = match rest.find('-') {
+        Some(i) => (&rest[..i], &rest[i + 1..]),
+        None => (rest, ""),
+    };
+    let mut parts = core.split('.');
+    let mut number = || match parts.next() {
+        Some(part) => parse_number(part),
+        None => Err("expected major.minor.patch"),
+    };
+    let (major, minor, patch) = (number()?, number()?, number()?);
+    if parts.next().is_some() {
+        return Err("expected major.minor.patch");
+    }
+    if rest.len() != core.len() {
+        check_identifiers(pre, true)?;
+    }
+    if s.len() != rest.len() {
+        check_identifiers(build, false)?;
+    }
+    Ok(Version {
+        major,
+        minor,
+        patch,
+        pre: pre.to_string(),
+        build: build.to_string(),
+    })
+}
+
+fn parse_number(s: &str) -> Result<u64, &'static str> {
+    if s.is_empty() || !s.bytes().all(|b| b.is_ascii_digit()) {
+        return Err("expected major.minor.patch");
+    }
+    if s.len() > 1 && s.starts_with('0') {
+        return Err("numbers must not have leading zeros");
+    }
+    s.parse().map_err(|_| "number is too large")
+}
+
+/// Checks pre-release identifiers, or build metadata if `pre` is false.
+fn check_identifiers(s: &str, pre: bool) -> Result<(), &'static str> {
+    for ident in s.split('.') {
+        if ident.is_empty() {
+            return Err(if pre {
+                "empty pre-release identifier"
+            } else {
+                "empty build metadata identifier"
+            });
+        }
+        if !ident
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b == b'-')
+        {
+            return Err("identifiers may only contain ASCII letters, digits and `-`");
+        }
+        if pre
+            && ident.len() > 1
+            && ident.starts_with('0')
+            && ident.bytes().all(|b| b.is_ascii_digit())
+        {
+            return Err("numbers must not have leading zeros");
+        }
+    }
+    Ok(())
+}
+
+/// A version requirement, such as `^1.2`, `~1.2.3` or `>=1.0, <2.0`.
+///
+/// Requirements are read the way Cargo reads them: a bare version like
+/// `1.2` is a caret requirement, several comparators separated by commas
+/// must all match, and `*`, `1.*` and `1.2.*` are wildcards. A pre-release
+/// version only matches if a comparator names a pre-release of the same
+/// `major.minor.patch`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct VersionReq {
+    comparators: Vec<Comparator>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct Comparator {
+    op: Op,
+    major: u64,
+    minor: Option<u64>,
+    patch: Option<u64>,
+    pre: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Op {
+    Exact,
+    Greater,
+    GreaterEq,
+    Less,
+    LessEq,
+    Tilde,
+    Caret,
+    Wildcard,
+}
+
+impl VersionReq {
+    /// Parses a requirement.
+    pub fn parse(s: &str) -> Result<VersionReq, VersionError> {
+        parse_req(s).map_err(|reason| VersionError::new(s, true, reason))
+    }
+
+    /// Returns whether `version` meets this requirement.
+    pub fn matches(&self, version: &Version) -> bool {
+        self.comparators.iter().all(|c| c.matches(version))
+            && (!version.is_prerelease()
+                || self.comparators.iter().any(|c| {
+                    c.major == version.major
+                        && c.minor == Some(version.minor)
+                        && c.patch == Some(version.patch)
+                        && !c.pre.is_empty()
+                }))
+    }
+}
+
+impl Comparator {
+    fn matches(&self, v: &Version) -> bool {
+        let (minor, patch) = match (self.minor, self.patch) {
+            (Some(minor), Some(patch)) => (minor, patch),
+            (Some(minor), None) => return self.matches_partial(v, Some(minor)),
+            (None, _) => return self.matches_partial(v, None),
+        };
+        let bound = Version {
+            major: self.major,
+            minor,
+            patch,
+            pre: self.pre.clone(),
+            build: String::new(),
+        };
+        let ordering = v.cmp_precedence(&bound);
+        match self.op {
+            Op::Exact | Op::Wildcard => ordering == Ordering::Equal,
+            Op::Greater => ordering == Ordering::Greater,
+            Op::GreaterEq => ordering != Ordering::Less,
+            Op::Less => ordering == Ordering::Less,
+            Op::LessEq => ordering != Ordering::Greater,
+            Op::Tilde => v.major == self.major && v.minor == minor && ordering != Ordering::Less,
+            Op::Caret => {
+                ordering != Ordering::Less
+                    && v.major == self.major
+                    && (self.major > 0 || v.minor == minor && (minor > 0 || v.patch == patch))
+            }
+        }
+    }
+
+    /// Matches a comparator that leaves out the patch version, and the minor
+    /// version if `minor` is `None`.
+    fn matches_partial(&self, v: &Version, minor: Option<u64>) -> bool {
+        let ordering = match minor {
+            Some(minor) => (v.major, v.minor).cmp(&(self.major, minor)),
+            None => v.major.cmp(&self.major),
+        };
+        match self.op {
+            Op::Exact | Op::Wildcard | Op::Tilde => ordering == Ordering::Equal,
+            Op::Greater => ordering == Ordering::Greater,
+            Op::GreaterEq => ordering != Ordering::Less,
+            Op::Less => ordering == Ordering::Less,
+            Op::LessEq => ordering != Ordering::Greater,
+            Op::Caret => match minor {
+                Some(_) if self.major == 0 => ordering == Ordering::Equal,
+                Some(_) => v.major == self.major && ordering != Ordering::Less,
+                None => v.major == self.major,
+            },
+        }
+    }
+}
+
+fn parse_req(s: &str) -> Result<VersionReq, &'static str> {
+    let s = s.trim();
+    if s == "*" {
+        return Ok(VersionReq {
+            comparators: Vec::new(),
+        });
+    }
+    let mut comparators = Vec::new();
+    for part in s.split(',') {
+        let part = part.trim();
+        if part.is_empty() {
+            return Err("empty comparator");
+        }
+        comparators.push(parse_comparator(part)?);
+    }
+    Ok(VersionReq { comparators })
+}
+
+fn parse_comparator(s: &str) -> Result<Comparator, &'static str> {
+    let (op, rest) = [
+        (">=", Op::GreaterEq),
+        ("<=", Op::LessEq),
+        (">", Op::Greater),
+        ("<", Op::Less),
+        ("=", Op::Exact),
+        ("~", Op::Tilde),
+        ("^", Op::Caret),
+    ]
+    .iter()
+    .find_map(|&(prefix, op)| {
+        s.strip_prefix(prefix)
+            .map(|rest| (Some(op), rest.trim_start()))
+    })
+    .unwrap_or((None, s));
+
+    let (core, pre) = match rest.find('-') {
+        Some(i) => (&rest[..i], &rest[i + 1..]),
+        None => (rest, ""),
+    };
+    let mut numbers = [None; 3];
+    let mut wildcard = false;
+    for (count, part) in core.split('.').enumerate() {
+        if count == 3 {
+            return Err("expected at most major.minor.patch");
+        }
+        if part == "*" || part == "x" || part == "X" {
+            if count == 0 {
+                return Err("a wildcard cannot replace the major version");
+            }
+            wildcard = true;
+        } else if wildcard {
+            return Err("a wildcard must be the last number");
+        } else {
+            numbers[count] = Some(parse_number(part)?);
+        }
+    }
+    if rest.len() != core.len() {
+        if numbers[2].is_none() {
+            return Err("a pre-release needs major.minor.patch");
+        }
+        check_identifiers(pre, true)?;
+    }
+    let op = match op {
+        Some(_) if wildcard => return Err("a wildcard cannot follow an operator"),
+        Some(op) => op,
+        None if wildcard => Op::Wildcard,
+        None => Op::Caret,
+    };
+    Ok(Comparator {
+        op,
+        major: numbers[0].unwrap_or(0),
+        minor: numbers[1],
+        patch: numbers[2],
+        pre: pre.to_string(),
+    })
+}
+
+impl fmt::Display for VersionReq {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.comparators.is_empty() {
+            return f.write_str("*");
+        }
+        for (i, c) in self.comparators.iter().enumerate() {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+            f.write_str(match c.op {
+                Op::Exact => "=",
+                Op::Greater => ">",
+                Op::GreaterEq => ">=",
+                Op::Less => "<",
+                Op::LessEq => "<=",
+                Op::Tilde => "~",
+                Op::Caret => "^",
+                Op::Wildcard => "",
+            })?;
+            write!(f, "{}", c.major)?;
+            for n in [c.minor, c.patch].iter() {
+                match *n {
+                    Some(n) => write!(f, ".{}", n)?,
+                    None => {
+                        if c.op == Op::Wildcard {
+                            f.write_str(".*")?;
+                        }
+                        break;
+                    }
+                }
+            }
+            if !c.pre.is_empty() {
+                write!(f, "-{}", c.pre)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for VersionReq {
+    type Err = VersionError;
+
+    fn from_str(s: &str) -> Result<VersionReq, VersionError> {
+        VersionReq::parse(s)
+    }
+}
+
+/// Error returned when a version or version requirement cannot be parsed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VersionError {
+    text: String,
+    requirement: bool,
+    reason: &'static str,
+}
+
+impl VersionError {
+    fn new(text: &str, requirement: bool, reason: &'static str) -> VersionError {
+        VersionError {
+            text: text.to_string(),
+            requirement,
+            reason,
+        }
+    }
+
+    /// Returns the text that failed to parse.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+}
+
+impl fmt::Display for VersionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "`{}` is not a version{}: {}",
+            self.text,
+            if self.requirement { " requirement" } else { "" },
+            self.reason
+        )
+    }
+}
+
+impl error::Error for VersionError {}
+
+macro_rules! string_serde {
+    ($($ty:ident $expected:expr;)*) => {$(
+        impl<'de> Deserialize<'de> for $ty {
+            fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+            where
+                D: Deserializer<'de>,
+            {
+                struct StrVisitor;
+
+                impl<'de> de::Visitor<'de> for StrVisitor {
+                    type Value = $ty;
+
+                    fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+                        formatter.write_str($expected)
+                    }
+
+                    fn visit_str<E>(self, s: &str) -> Result<$ty, E>
+                    where
+                        E: de::Error,
+                    {
+                        $ty::parse(s).map_err(de::Error::custom)
+                    }
+                }
+
+                deserializer.deserialize_str(StrVisitor)
+            }
+        }
+
+        impl Serialize for $ty {
+            fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+            where
+                S: Serializer,
+            {
+                serializer.collect_str(self)
+            }
+        }
+    )*};
+}
+
+string_serde! {
+    Version "a version string such as \"1.2.3\"";
+    VersionReq "a version requirement string such as \"^1.2\"";
+}
@@ -0,0 +1,103 @@
+//! Emitting a table as key/value fields, such as those of a structured log
+//! record.
+//!
+//! [`emit`] walks a table and hands every value of `Table::flatten` to a
+//! [`FieldSink`], keyed by its path. Logging the effective configuration of
+//! a program at startup then takes a few lines of glue to the logging
+//! library in use, and a closure will do:
+//!
+//! ```rust
+//! use toml::fields::{self, FieldValue};
+//!
+//! let config: toml::value::Table =
+//!     toml::from_str("[server]\nhost = 'example.com'\nport = 8080\nports = [80, 443]\n").unwrap();
+//!
+//! let mut record = Vec::new();
+//! fields::emit(&config, &mut |key: &str, value: FieldValue<'_>| {
+//!     record.push(format!("{}={}", key, value));
+//! });
+//! assert_eq!(record, ["server.host=example.com", "server.port=8080", "server.ports=[80, 443]"]);
+//! ```
+//!
+//! [`emit`]: fn.emit.html
+//! [`FieldSink`]: trait.FieldSink.html
+
+use std::fmt;
+
+use crate::value::{Table, Value};
+
+/// A receiver of key/value fields, such as a structured log record under
+/// construction.
+///
+/// It is implemented for closures taking the same arguments as `field`.
+pub trait FieldSink {
+    /// Receives the field `key`, which is the path of the value written as
+    /// by `KeyPath`'s `Display`, such as `servers[0].host`.
+    fn field(&mut self, key: &str, value: FieldValue<'_>);
+}
+
+impl<F> FieldSink for F
+where
+    F: FnMut(&str, FieldValue<'_>),
+{
+    fn field(&mut self, key: &str, value: FieldValue<'_>) {
+        self(key, value)
+    }
+}
+
+/// The value of a field, with the types logging libraries record natively
+/// kept apart from the rest.
+///
+/// Its `Display` writes strings as they are, without quotes, and anything
+/// else in TOML syntax.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FieldValue<'a> {
+    /// A string.
+    String(&'a str),
+    /// An integer.
+    Integer(i64),
+    /// A floating point number.
+    Float(f64),
+    /// A boolean.
+    Boolean(bool),
+    /// A datetime, an array or an empty table.
+    Other(&'a Value),
+}
+
+impl<'a> FieldValue<'a> {
+    /// Returns the field value of `value`.
+    pub fn new(value: &'a Value) -> FieldValue<'a> {
+        match *value {
+            Value::String(ref s) => FieldValue::String(s),
+            Value::Integer(i) => FieldValue::Integer(i),
+            Value::Float(f) => FieldValue::Float(f),
+            Value::Boolean(b) => FieldValue::Boolean(b),
+            _ => FieldValue::Other(value),
+        }
+    }
+}
+
+impl<'a> fmt::Display for FieldValue<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            FieldValue::String(s) => f.write_str(s),
+            FieldValue::Integer(i) => Value::Integer(i).fmt(f),
+            FieldValue::Float(n) => Value::Float(n).fmt(f),
+            FieldValue::Boolean(b) => b.fmt(f),
+            FieldValue::Other(Value::Table(_)) => f.write_str("{}"),
+            FieldValue::Other(value) => value.fmt(f),
+        }
+    }
+}
+
+/// Hands every value of `table.flatten()` to `sink`, in order, keyed by its
+/// path.
+///
+/// Arrays of values other than tables are handed over whole, as
+/// `FieldValue::Other`, and an array of tables as one field for each value
+/// inside it, keyed like `users[0].name`.
+pub fn emit<S: FieldSink + ?Sized>(table: &Table, sink: &mut S) {
+    for (path, value) in table.flatten() {
+        sink.field(&path.to_string(), FieldValue::new(&value));
+    }
+}
@@ -0,0 +1,348 @@
+//! Reading file system paths from TOML strings.
+//!
+//! A path in a configuration file is rarely used exactly as written: `~`
+//! stands for the home directory, `$VAR` for an environment variable, and a
+//! setting may only make sense as an absolute path, or only as one relative
+//! to the configuration file. [`PathOptions`] states how a path is read, and
+//! reads it with these checks and expansions in one step, failing with an
+//! error that points at the string in the document:
+//!
+//! ```rust
+//! use std::path::PathBuf;
+//!
+//! use serde::Deserializer;
+//! use serde_derive::Deserialize;
+//! use toml::file_path::{PathKind, PathOptions};
+//!
+//! fn relative<'de, D: Deserializer<'de>>(deserializer: D) -> Result<PathBuf, D::Error> {
+//!     PathOptions::new().require(PathKind::Relative).deserialize(deserializer)
+//! }
+//!
+//! #[derive(Debug, Deserialize)]
+//! struct Config {
+//!     #[serde(deserialize_with = "relative")]
+//!     output: PathBuf,
+//! }
+//!
+//! let config: Config = toml::from_str("output = 'target/site'").unwrap();
+//! assert_eq!(config.output, PathBuf::from("target/site"));
+//!
+//! let err = toml::from_str::<Config>("output = '/tmp/site'").unwrap_err();
+//! assert_eq!(
+//!     err.to_string(),
+//!     "path `/tmp/site` is absolute, expected a relative path for key `output` at line 1 column 10",
+//! );
+//! ```
+//!
+//! [`ExpandedPath`] is the common case as a type of its own, which expands
+//! `~` and environment variables in any path.
+//!
+//! [`PathOptions`]: struct.PathOptions.html
+//! [`ExpandedPath`]: struct.ExpandedPath.html
+
+use std::env;
+use std::error;
+use std::ffi::OsString;
+use std::fmt;
+use std::ops::{Deref, DerefMut};
+use std::path::{Path, PathBuf};
+
+use serde::de::{self, Deserialize, Deserializer, Visitor};
+use serde::ser::{Serialize, Serializer};
+
+/// Whether a path must be absolute or relative, set with
+/// `PathOptions::require`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum PathKind {
+    /// Any path, which is the default.
+    #[default]
+    Any,
+    /// An absolute path, such as `/var/log` or `C:\logs`.
+    Absolute,
+    /// A relative path, such as `logs` or `../logs`.
+    Relative,
+}
+
+/// How a path is read from a string.
+///
+/// By default the string is taken as it is, as any path.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PathOptions {
+    expand_home: bool,
+    expand_env: bool,
+    kind: PathKind,
+}
+
+impl PathOptions {
+    /// Creates options reading any path as it is written.
+    pub fn new() -> PathOptions {
+        PathOptions::default()
+    }
+
+    /// Replaces a leading `~`, alone or followed by a separator, with the
+    /// home directory: `HOME`, or `USERPROFILE` on Windows.
+    pub fn expand_home(&mut self, expand: bool) -> &mut Self {
+        self.expand_home = expand;
+        self
+    }
+
+    /// Replaces `$NAME` and `${NAME}`, and `%NAME%` on Windows, with the
+    /// environment variable `NAME`. `$$` stands for a `$`.
+    pub fn expand_env(&mut self, expand: bool) -> &mut Self {
+        self.expand_env = expand;
+        self
+    }
+
+    /// Requires the path to be absolute or relative once expanded.
+    pub fn require(&mut self, kind: PathKind) -> &mut Self {
+        self.kind = kind;
+        self
+    }
+
+    /// Reads the path written as `s`.
+    ///
+    /// ```rust
+    /// use toml::file_path::{PathKind, PathOptions};
+    ///
+    /// std::env::set_var("APP_ROOT", "/srv/app");
+    /// let mut options = PathOptions::new();
+    /// options.expand_env(true).require(PathKind::Absolute);
+    /// assert_eq!(options.resolve("$APP_ROOT/logs").unwrap().to_str(), Some("/srv/app/logs"));
+    /// assert_eq!(
+    ///     options.resolve("logs").unwrap_err().to_string(),
+    ///     "path `logs` is relative, expected an absolute path",
+    /// );
+    /// ```
+    pub fn resolve(&self, s: &str) -> Result<PathBuf, PathError> {
+        let error = |kind| {
+            Err(PathError {
+                path: s.to_string(),
+                kind,
+            })
+        };
+        let mut path = OsString::new();
+        let mut rest = s;
+        if self.expand_home && starts_with_home(rest) {
+            match env::var_os(HOME) {
+                Some(home) => path.push(home),
+                None => return error(PathErrorKind::UnknownHome),
+            }
+            rest = &rest[1..];
+        }
+        if self.expand_env {
+            if let Err(name) = expand_env(rest, &mut path) {
+                return error(PathErrorKind::UnsetVariable(name));
+            }
+        } else {
+            path.push(rest);
+        }
+        let path = PathBuf::from(path);
+        match self.kind {
+            PathKind::Absolute if !path.is_absolute() => error(PathErrorKind::Relative),
+            PathKind::Relative if !is_relative(&path) => error(PathErrorKind::Absolute),
+            _ => Ok(path),
+        }
+    }
+
+    /// Deserializes a string as a path read with these options, for use in
+    /// a function named by `#[serde(deserialize_with)]`.
+    pub fn deserialize<'de, D>(&self, deserializer: D) -> Result<PathBuf, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct PathVisitor<'a>(&'a PathOptions);
+
+        impl<'a, 'de> Visitor<'de> for PathVisitor<'a> {
+            type Value = PathBuf;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+                formatter.write_str("a string holding a path")
+            }
+
+            fn visit_str<E>(self, s: &str) -> Result<PathBuf, E>
+            where
+                E: de::Error,
+            {
+                self.0.resolve(s).map_err(de::Error::custom)
+            }
+        }
+
+        deserializer.deserialize_str(PathVisitor(self))
+    }
+}
+
+#[cfg(not(windows))]
+const HOME: &str = "HOME";
+#[cfg(windows)]
+const HOME: &str = "USERPROFILE";
+
+/// Returns whether `s` starts with a `~` standing for the home directory.
+fn starts_with_home(s: &str) -> bool {
+    s == "~" || s.starts_with("~/") || cfg!(windows) && s.starts_with("~\\")
+}
+
+/// Returns whether `path` is relative, which on Windows excludes paths like
+/// `\logs` that are relative only to the current drive.
+fn is_relative(path: &Path) -> bool {
+    path.is_relative() && !path.has_root()
+}
+
+/// Pushes `s` onto `out` with environment variables replaced, returning the
+/// name of the first variable that is not set.
+fn expand_env(s: &str, out: &mut OsString) -> Result<(), String> {
+    let mut rest = s;
+    while let Some(i) = rest.find(|c| c == '$' || cfg!(windows) && c == '%') {
+        out.push(&rest[..i]);
+        let after = &rest[i + 1..];
+        let (name, len) = if rest[i..].starts_with('%') {
+            match after.find('%') {
+                Some(end) if end > 0 => (&after[..end], end + 1),
+                _ => ("", 0),
+            }
+        } else if let Some(after) = after.strip_prefix('$') {
+            out.push("$");
+            rest = after;
+            continue;
+        } else if after.starts_with('{') {
+            match after.find('}') {
+                Some(end) => (&after[1..end], end + 1),
+                None => ("", 0),
+            }
+        } else {
+            let end = after
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                .unwrap_or(after.len());
+            (&after[..end], end)
+        };
+        if name.is_empty() {
+            // Not a variable, such as a `$` at the end.
+            out.push(&rest[i..=i]);
+        } else {
+            match env::var_os(name) {
+                Some(value) => out.push(value),
+                None => return Err(name.to_string()),
+            }
+        }
+        rest = &after[len..];
+    }
+    out.push(rest);
+    Ok(())
+}
+
+/// Error returned when a path cannot be read from a string.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PathError {
+    path: String,
+    kind: PathErrorKind,
+}
+
+/// The reason a path could not be read.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PathErrorKind {
+    /// The path starts with `~`, but the home directory is not set.
+    UnknownHome,
+    /// The path refers to an environment variable that is not set; holds
+    /// its name.
+    UnsetVariable(String),
+    /// The path is absolute, but must be relative.
+    Absolute,
+    /// The path is relative, but must be absolute.
+    Relative,
+}
+
+impl PathError {
+    /// Returns the path as it was written.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Returns the reason the path could not be read.
+    pub fn kind(&self) -> &PathErrorKind {
+        &self.kind
+    }
+}
+
+impl fmt::Display for PathError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            PathErrorKind::UnknownHome => write!(
+                f,
+                "cannot expand `~` in path `{}`: {} is not set",
+                self.path, HOME
+            ),
+            PathErrorKind::UnsetVariable(ref name) => write!(
+                f,
+                "environment variable `{}` in path `{}` is not set",
+                name, self.path
+            ),
+            PathErrorKind::Absolute => write!(
+                f,
+                "path `{}` is absolute, expected a relative path",
+                self.path
+            ),
+            PathErrorKind::Relative => write!(
+                f,
+                "path `{}` is relative, expected an absolute path",
+                self.path
+            ),
+        }
+    }
+}
+
+impl error::Error for PathError {}
+
+/// A path with `~` and environment variables expanded, as read by
+/// `PathOptions` with `expand_home` and `expand_env` set.
+///
+/// It is written back as the expanded path.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct ExpandedPath(pub PathBuf);
+
+impl ExpandedPath {
+    /// Consumes the wrapper, returning the path.
+    pub fn into_inner(self) -> PathBuf {
+        self.0
+    }
+}
+
+impl From<PathBuf> for ExpandedPath {
+    fn from(path: PathBuf) -> ExpandedPath {
+        ExpandedPath(path)
+    }
+}
+
+impl Deref for ExpandedPath {
+    type Target = PathBuf;
+
+    fn deref(&self) -> &PathBuf {
+        &self.0
+    }
+}
+
+impl DerefMut for ExpandedPath {
+    fn deref_mut(&mut self) -> &mut PathBuf {
+        &mut self.0
+    }
+}
+
+impl<'de> Deserialize<'de> for ExpandedPath {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        PathOptions::new()
+            .expand_home(true)
+            .expand_env(true)
+            .deserialize(deserializer)
+            .map(ExpandedPath)
+    }
+}
+
+impl Serialize for ExpandedPath {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        self.0.serialize(serializer)
+    }
+}
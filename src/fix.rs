@@ -0,0 +1,129 @@
+//! Automatic fixes for common mistakes in TOML documents.
+//!
+//! Some invalid documents have an obvious intended meaning: a trailing comma
+//! in an inline table, a string value that was not quoted, a number written
+//! with leading zeros, or the same key defined twice. The [`fixes`] function
+//! parses a document leniently and returns an edit for each such problem,
+//! and [`autofix`] applies the edits that are safe to make without review:
+//!
+//! ```rust
+//! let src = "name = hello world\ndep = { version = \"1\", }\n";
+//!
+//! let fixed = toml::autofix(src).unwrap();
+//! assert_eq!(fixed, "name = \"hello world\"\ndep = { version = \"1\" }\n");
+//! assert!(fixed.parse::<toml::Value>().is_ok());
+//! ```
+//!
+//! Problems that cannot be fixed this way are reported as the usual parse
+//! error.
+//!
+//! [`fixes`]: fn.fixes.html
+//! [`autofix`]: fn.autofix.html
+
+use std::fmt;
+
+use crate::de;
+
+/// An edit to the source of a document that fixes one problem in it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fix {
+    span: (usize, usize),
+    replacement: String,
+    message: String,
+    safe: bool,
+}
+
+impl Fix {
+    pub(crate) fn new(
+        span: (usize, usize),
+        replacement: String,
+        message: String,
+        safe: bool,
+    ) -> Fix {
+        Fix {
+            span,
+            replacement,
+            message,
+            safe,
+        }
+    }
+
+    /// Returns the start and end byte offsets of the text to replace.
+    ///
+    /// The span is empty when the fix only inserts text.
+    pub fn span(&self) -> (usize, usize) {
+        self.span
+    }
+
+    /// Returns the text to put in place of the span.
+    pub fn replacement(&self) -> &str {
+        &self.replacement
+    }
+
+    /// Returns a description of the problem being fixed.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// Returns whether the fix can be applied without review.
+    ///
+    /// A fix is unsafe when it may change the meaning of the document, such
+    /// as removing one of two definitions of a key that have different
+    /// values.
+    pub fn is_safe(&self) -> bool {
+        self.safe
+    }
+}
+
+impl fmt::Display for Fix {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.message.fmt(f)
+    }
+}
+
+/// Returns the fixes for the problems in `src`, ordered by position.
+///
+/// An error is returned if `src` has a problem that cannot be fixed.
+pub fn fixes(src: &str) -> Result<Vec<Fix>, de::Error> {
+    let mut fixes = de::Deserializer::new(src).fixes()?;
+    fixes.sort_by_key(|fix| fix.span);
+    Ok(fixes)
+}
+
+/// Applies `fixes` to `src`, returning the corrected source.
+///
+/// Fixes that overlap one applied before them are skipped.
+pub fn apply(src: &str, fixes: &[Fix]) -> String {
+    let mut fixes = fixes.iter().collect::<Vec<_>>();
+    fixes.sort_by_key(|fix| fix.span);
+
+    let mut ret = String::with_capacity(src.len());
+    let mut pos = 0;
+    for fix in fixes {
+        let (start, end) = fix.span;
+        if start < pos {
+            continue;
+        }
+        ret.push_str(&src[pos..start]);
+        ret.push_str(&fix.replacement);
+        pos = end;
+    }
+    ret.push_str(&src[pos..]);
+    ret
+}
+
+/// Applies the safe fixes for the problems in `src`, returning the corrected
+/// source.
+///
+/// An error is returned if `src` has a problem that cannot be fixed, or one
+/// whose fix needs review, such as a key defined twice with different
+/// values. The positions of the latter refer to the corrected source.
+pub fn autofix(src: &str) -> Result<String, de::Error> {
+    let fixes = fixes(src)?
+        .into_iter()
+        .filter(Fix::is_safe)
+        .collect::<Vec<_>>();
+    let fixed = apply(src, &fixes);
+    crate::from_str::<crate::Value>(&fixed)?;
+    Ok(fixed)
+}
@@ -0,0 +1,75 @@
+//! TOML front matter at the top of Markdown, HTML and similar files.
+//!
+//! Static site generators and documentation tools commonly let a page start
+//! with a block of TOML metadata between two fence lines:
+//!
+//! ```text
+//! +++
+//! title = "Hello"
+//! tags = ["intro"]
+//! +++
+//! # Hello
+//! ```
+//!
+//! [`extract`] splits such a file into the parsed metadata and the rest of
+//! the document:
+//!
+//! ```rust
+//! let page = "+++\ntitle = \"Hello\"\n+++\n# Hello\n";
+//!
+//! let (meta, body) = toml::frontmatter::extract(page).unwrap().unwrap();
+//! assert_eq!(meta["title"].as_str(), Some("Hello"));
+//! assert_eq!(body, "# Hello\n");
+//! ```
+//!
+//! [`extract`]: fn.extract.html
+
+use crate::de;
+use crate::stream::find_separator;
+use crate::value::Table;
+
+/// Extracts front matter fenced by `+++` lines from the top of `text`.
+///
+/// This is `extract_with_fence(text, "+++")`.
+pub fn extract(text: &str) -> Result<Option<(Table, &str)>, de::Error> {
+    extract_with_fence(text, "+++")
+}
+
+/// Extracts front matter fenced by lines consisting only of `fence` from the
+/// top of `text`.
+///
+/// Returns `None` if `text` does not start with a fence line, ignoring a byte
+/// order mark. Otherwise the TOML up to the next fence line is parsed and
+/// returned along with the text after that line. A missing closing fence and
+/// invalid TOML are errors, with positions relative to `text`.
+pub fn extract_with_fence<'a>(
+    text: &'a str,
+    fence: &str,
+) -> Result<Option<(Table, &'a str)>, de::Error> {
+    let bom = if text.starts_with('\u{feff}') { 3 } else { 0 };
+    let start = match find_separator(&text[bom..], fence) {
+        Some((0, next)) => bom + next,
+        _ => return Ok(None),
+    };
+    let (end, rest) = match find_separator(&text[start..], fence) {
+        Some((end, next)) => (start + end, start + next),
+        None => {
+            let mut e = de::Error::custom(
+                Some(0),
+                format!(
+                    "unterminated front matter, expected a closing `{}` line",
+                    fence
+                ),
+            );
+            e.relocate(text, bom);
+            return Err(e);
+        }
+    };
+    match crate::from_str(&text[start..end]) {
+        Ok(table) => Ok(Some((table, &text[rest..]))),
+        Err(mut e) => {
+            e.relocate(text, start);
+            Err(e)
+        }
+    }
+}
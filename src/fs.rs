@@ -0,0 +1,308 @@
+//! Editing TOML files that other processes may edit at the same time.
+//!
+//! Changing a shared configuration file safely takes more than reading it,
+//! editing it and writing it back: two programs doing so at once lose one
+//! of the edits, a program that stops halfway through writing leaves half
+//! a file behind, and an edit made by hand in between is silently
+//! overwritten. [`update`] takes care of all three. It holds a lock file
+//! while it works, reads the file into a [`Document`] so that only the
+//! edited values change, checks that the file was not changed by anyone
+//! else before writing, and writes the new text to a temporary file that
+//! then replaces the old one, so readers see either the old file or the
+//! new one:
+//!
+//! ```rust
+//! # let dir = std::env::temp_dir().join(format!("toml-fs-doc-{}", std::process::id()));
+//! # std::fs::create_dir_all(&dir).unwrap();
+//! # let path = dir.join("Cargo.toml");
+//! std::fs::write(&path, "[package]\nname = 'demo'\nversion = '0.1.0' # bumped by CI\n").unwrap();
+//!
+//! toml::fs::update(&path, |doc| doc.update_string("package.version", "0.2.0")).unwrap();
+//!
+//! assert_eq!(
+//!     std::fs::read_to_string(&path).unwrap(),
+//!     "[package]\nname = 'demo'\nversion = '0.2.0' # bumped by CI\n",
+//! );
+//! # std::fs::remove_dir_all(&dir).unwrap();
+//! ```
+//!
+//! The lock is advisory: it is a file named after the edited file with
+//! `.lock` appended, such as `Cargo.toml.lock`, which every program taking
+//! part must create through this module, or in the same way, before
+//! editing. It is removed when the update ends, even if it fails, but is
+//! left behind if the process is killed while holding it; an update then
+//! fails with `UpdateErrorKind::Locked` once its timeout runs out, naming
+//! the lock file to remove.
+//!
+//! A symbolic link is followed: the file it points to is the one locked and
+//! replaced, and the link is kept.
+//!
+//! [`update`]: fn.update.html
+//! [`Document`]: ../document/struct.Document.html
+
+use std::error;
+use std::ffi::OsString;
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::de;
+use crate::document::Document;
+
+/// How a file is updated by `UpdateOptions::update`.
+///
+/// By default the file must exist, and the lock is waited for for up to ten
+/// seconds.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UpdateOptions {
+    create: bool,
+    timeout: Duration,
+}
+
+impl Default for UpdateOptions {
+    fn default() -> UpdateOptions {
+        UpdateOptions {
+            create: false,
+            timeout: Duration::from_secs(10),
+        }
+    }
+}
+
+impl UpdateOptions {
+    /// Creates the default options.
+    pub fn new() -> UpdateOptions {
+        UpdateOptions::default()
+    }
+
+    /// Sets whether a file that does not exist is updated as an empty
+    /// document and created, rather than failing with a not found error.
+    pub fn create(&mut self, create: bool) -> &mut Self {
+        self.create = create;
+        self
+    }
+
+    /// Sets how long to wait for another update to release the lock before
+    /// failing with `UpdateErrorKind::Locked`. A zero timeout tries once.
+    pub fn lock_timeout(&mut self, timeout: Duration) -> &mut Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Updates the file at `path` by calling `edit` on its document, as
+    /// described in the [module documentation](index.html).
+    ///
+    /// The value returned by `edit` is returned once the file is written.
+    /// The file is left as it is if `edit` fails, or if it leaves the
+    /// document's text unchanged.
+    pub fn update<P, F, T>(&self, path: P, edit: F) -> Result<T, UpdateError>
+    where
+        P: AsRef<Path>,
+        F: FnOnce(&mut Document) -> Result<T, de::Error>,
+    {
+        let path = path.as_ref();
+        let error = |kind| UpdateError {
+            path: path.to_path_buf(),
+            kind,
+        };
+        let target = resolve(path).map_err(|e| error(UpdateErrorKind::Io(e)))?;
+        let path = target.as_path();
+        let _lock = Lock::acquire(path, self.timeout).map_err(error)?;
+
+        let original = read(path, self.create).map_err(|e| error(UpdateErrorKind::Io(e)))?;
+        let text = original.as_ref().map_or("", |(text, _)| text.as_str());
+        let mut doc = Document::parse(text).map_err(|e| error(UpdateErrorKind::Parse(e)))?;
+        let ret = edit(&mut doc).map_err(|e| error(UpdateErrorKind::Edit(e)))?;
+        let updated = doc.to_string();
+        if original.is_some() && updated == text {
+            return Ok(ret);
+        }
+
+        let current = read(path, true).map_err(|e| error(UpdateErrorKind::Io(e)))?;
+        if current != original {
+            return Err(error(UpdateErrorKind::Conflict));
+        }
+        write_atomically(path, &updated).map_err(|e| error(UpdateErrorKind::Io(e)))?;
+        Ok(ret)
+    }
+}
+
+/// Updates the file at `path` by calling `edit` on its document, with the
+/// default `UpdateOptions`.
+pub fn update<P, F, T>(path: P, edit: F) -> Result<T, UpdateError>
+where
+    P: AsRef<Path>,
+    F: FnOnce(&mut Document) -> Result<T, de::Error>,
+{
+    UpdateOptions::new().update(path, edit)
+}
+
+/// Returns the file that `path` names, following symbolic links, so that
+/// the lock, the temporary file and the rename act on the file a link
+/// points to rather than replacing the link. A file that does not exist yet
+/// is named as it is.
+fn resolve(path: &Path) -> io::Result<PathBuf> {
+    match fs::canonicalize(path) {
+        Ok(target) => Ok(target),
+        Err(ref e) if e.kind() == io::ErrorKind::NotFound => Ok(path.to_path_buf()),
+        Err(e) => Err(e),
+    }
+}
+
+/// Reads the text and modification time of the file at `path`, or `None`
+/// if it does not exist and `missing_ok` is set.
+fn read(path: &Path, missing_ok: bool) -> io::Result<Option<(String, SystemTime)>> {
+    match fs::read_to_string(path) {
+        Ok(text) => {
+            let modified = fs::metadata(path)?.modified()?;
+            Ok(Some((text, modified)))
+        }
+        Err(ref e) if missing_ok && e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// Returns `path` with `suffix` appended to its file name.
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = OsString::from(path.as_os_str());
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+/// Writes `text` to a temporary file next to `path`, then moves it over
+/// `path`, keeping the permissions of the file it replaces.
+///
+/// The directory is synced after the move, so that the new file is the one
+/// found after a crash.
+fn write_atomically(path: &Path, text: &str) -> io::Result<()> {
+    let temp = with_suffix(path, &format!(".{}.tmp", std::process::id()));
+    let result = (|| {
+        let mut file = File::create(&temp)?;
+        file.write_all(text.as_bytes())?;
+        if let Ok(metadata) = fs::metadata(path) {
+            file.set_permissions(metadata.permissions())?;
+        }
+        file.sync_all()?;
+        fs::rename(&temp, path)
+    })();
+    if result.is_err() {
+        let _ = fs::remove_file(&temp);
+    }
+    result?;
+    sync_parent(path)
+}
+
+/// Syncs the directory holding `path`.
+#[cfg(unix)]
+fn sync_parent(path: &Path) -> io::Result<()> {
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    File::open(dir)?.sync_all()
+}
+
+/// Directories cannot be opened as files to sync them on this platform.
+#[cfg(not(unix))]
+fn sync_parent(_path: &Path) -> io::Result<()> {
+    Ok(())
+}
+
+/// A held lock file, removed when dropped.
+struct Lock {
+    path: PathBuf,
+}
+
+impl Lock {
+    fn acquire(path: &Path, timeout: Duration) -> Result<Lock, UpdateErrorKind> {
+        let lock = with_suffix(path, ".lock");
+        let start = Instant::now();
+        loop {
+            match OpenOptions::new().write(true).create_new(true).open(&lock) {
+                Ok(mut file) => {
+                    // The process id only helps whoever finds a stale lock.
+                    let _ = write!(file, "{}", std::process::id());
+                    return Ok(Lock { path: lock });
+                }
+                Err(ref e) if e.kind() == io::ErrorKind::AlreadyExists => {
+                    if start.elapsed() >= timeout {
+                        return Err(UpdateErrorKind::Locked(lock));
+                    }
+                    thread::sleep(Duration::from_millis(20));
+                }
+                Err(e) => return Err(UpdateErrorKind::Io(e)),
+            }
+        }
+    }
+}
+
+impl Drop for Lock {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// Error returned when a file cannot be updated.
+#[derive(Debug)]
+pub struct UpdateError {
+    path: PathBuf,
+    kind: UpdateErrorKind,
+}
+
+/// The reason a file could not be updated.
+#[derive(Debug)]
+pub enum UpdateErrorKind {
+    /// Reading or writing the file failed.
+    Io(io::Error),
+    /// The file is not a valid TOML document.
+    Parse(de::Error),
+    /// The edit returned an error.
+    Edit(de::Error),
+    /// Another update held the lock until the timeout ran out; holds the
+    /// path of the lock file.
+    Locked(PathBuf),
+    /// The file was changed by something that did not take the lock while
+    /// the update was running, and was left as that change wrote it.
+    Conflict,
+}
+
+impl UpdateError {
+    /// Returns the path of the file being updated.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns the reason the file could not be updated.
+    pub fn kind(&self) -> &UpdateErrorKind {
+        &self.kind
+    }
+}
+
+impl fmt::Display for UpdateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "cannot update `{}`: ", self.path.display())?;
+        match self.kind {
+            UpdateErrorKind::Io(ref e) => e.fmt(f),
+            UpdateErrorKind::Parse(ref e) => e.fmt(f),
+            UpdateErrorKind::Edit(ref e) => e.fmt(f),
+            UpdateErrorKind::Locked(ref lock) => write!(
+                f,
+                "it is locked by `{}`; remove the lock file if no update is running",
+                lock.display()
+            ),
+            UpdateErrorKind::Conflict => f.write_str("it was changed while it was being updated"),
+        }
+    }
+}
+
+impl error::Error for UpdateError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self.kind {
+            UpdateErrorKind::Io(ref e) => Some(e),
+            UpdateErrorKind::Parse(ref e) | UpdateErrorKind::Edit(ref e) => Some(e),
+            UpdateErrorKind::Locked(_) | UpdateErrorKind::Conflict => None,
+        }
+    }
+}
@@ -0,0 +1,264 @@
+//! Conversion of JSON-like value trees, such as those of JSON and YAML
+//! libraries, into TOML values.
+//!
+//! An application that accepts its configuration in several formats can
+//! convert each into a `toml::Value` and validate and read them all the
+//! same way. This crate depends on no such library; instead a tree type
+//! implements [`IntoToml`] by describing each of its nodes as a [`Node`].
+//! This can be done in the crate of the tree type, or in the application
+//! with a wrapper such as `struct Yaml<'a>(&'a yaml::Value)`:
+//!
+//! ```rust
+//! use toml::import::{IntoToml, Node};
+//!
+//! enum Json {
+//!     Null,
+//!     Number(f64),
+//!     Text(String),
+//!     List(Vec<Json>),
+//!     Object(Vec<(String, Json)>),
+//! }
+//!
+//! impl IntoToml for Json {
+//!     fn node(&self) -> Node<'_> {
+//!         match self {
+//!             Json::Null => Node::Null,
+//!             Json::Number(n) if n.fract() == 0.0 => Node::Integer(*n as i64),
+//!             Json::Number(n) => Node::Float(*n),
+//!             Json::Text(s) => Node::String(s),
+//!             Json::List(items) => Node::array(items),
+//!             Json::Object(entries) => Node::table(entries.iter().map(|(k, v)| (&k[..], v))),
+//!         }
+//!     }
+//! }
+//!
+//! let json = Json::Object(vec![
+//!     ("hosts".to_string(), Json::List(vec![Json::Text("a".to_string())])),
+//!     ("port".to_string(), Json::Number(8080.0)),
+//!     ("proxy".to_string(), Json::Null),
+//! ]);
+//! let value = json.to_toml().unwrap();
+//! assert_eq!(value.to_string(), "hosts = [\"a\"]\nport = 8080\n");
+//! ```
+//!
+//! This module is only available with the `import` feature.
+//!
+//! [`IntoToml`]: trait.IntoToml.html
+//! [`Node`]: enum.Node.html
+
+use std::convert::TryFrom;
+use std::error;
+use std::fmt;
+
+use crate::path::KeyPath;
+use crate::value::{Datetime, Table, Value};
+
+/// A node of a value tree, as described by `IntoToml::node`.
+pub enum Node<'a> {
+    /// A missing value, such as JSON's `null` or YAML's `~`.
+    Null,
+    /// A boolean.
+    Boolean(bool),
+    /// A signed integer.
+    Integer(i64),
+    /// An unsigned integer, which must fit in an `i64`.
+    Unsigned(u64),
+    /// A floating point number.
+    Float(f64),
+    /// A number kept as it was written, such as a JSON number read with
+    /// arbitrary precision.
+    RawNumber(&'a str),
+    /// A string.
+    String(&'a str),
+    /// A date, a time or both, such as a YAML timestamp.
+    Datetime(Datetime),
+    /// A sequence of values, as built by `Node::array`.
+    Array(Box<dyn Iterator<Item = Box<dyn IntoToml + 'a>> + 'a>),
+    /// A mapping from string keys to values, as built by `Node::table`.
+    Table(Box<dyn Iterator<Item = (&'a str, Box<dyn IntoToml + 'a>)> + 'a>),
+}
+
+impl<'a> Node<'a> {
+    /// Returns an array node holding `items`.
+    pub fn array<I>(items: I) -> Node<'a>
+    where
+        I: IntoIterator,
+        I::IntoIter: 'a,
+        I::Item: IntoToml + 'a,
+    {
+        let items = items.into_iter();
+        Node::Array(Box::new(
+            items.map(|item| Box::new(item) as Box<dyn IntoToml + 'a>),
+        ))
+    }
+
+    /// Returns a table node holding `entries`.
+    pub fn table<I, T>(entries: I) -> Node<'a>
+    where
+        I: IntoIterator<Item = (&'a str, T)>,
+        I::IntoIter: 'a,
+        T: IntoToml + 'a,
+    {
+        let entries = entries.into_iter();
+        Node::Table(Box::new(
+            entries.map(|(key, item)| (key, Box::new(item) as Box<dyn IntoToml + 'a>)),
+        ))
+    }
+}
+
+/// A value tree that can be converted into a TOML value.
+///
+/// Implementations only describe each node with `node`; the conversion
+/// itself is done by `to_toml`, the same way for every tree type.
+pub trait IntoToml {
+    /// Describes this node of the tree.
+    fn node(&self) -> Node<'_>;
+
+    /// Converts this tree into a TOML value.
+    ///
+    /// TOML has no null, so a key of a table whose value is null is left
+    /// out, as an `Option` field set to `None` is when serializing. A null
+    /// anywhere else, such as in an array or as the whole tree, is an
+    /// error, as is an unsigned integer too large for TOML's signed 64-bit
+    /// integers. The error names the path of the value.
+    fn to_toml(&self) -> Result<Value, ImportError> {
+        let mut path = KeyPath::new();
+        match convert(self, &mut path)? {
+            Some(value) => Ok(value),
+            None => Err(ImportError {
+                path,
+                kind: ImportErrorKind::Null,
+            }),
+        }
+    }
+}
+
+impl<T: IntoToml + ?Sized> IntoToml for &T {
+    fn node(&self) -> Node<'_> {
+        (**self).node()
+    }
+}
+
+impl<T: IntoToml + ?Sized> IntoToml for Box<T> {
+    fn node(&self) -> Node<'_> {
+        (**self).node()
+    }
+}
+
+impl IntoToml for Value {
+    fn node(&self) -> Node<'_> {
+        match self {
+            Value::String(s) => Node::String(s),
+            Value::Integer(i) => Node::Integer(*i),
+            Value::Float(f) => Node::Float(*f),
+            Value::Boolean(b) => Node::Boolean(*b),
+            Value::Datetime(d) => Node::Datetime(d.clone()),
+            Value::RawNumber(s) => Node::RawNumber(s.as_str()),
+            Value::Array(array) => Node::array(array),
+            Value::Table(table) => Node::table(table.iter().map(|(k, v)| (&k[..], v))),
+        }
+    }
+}
+
+/// Error returned by `IntoToml::to_toml` for a value that TOML cannot
+/// hold.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImportError {
+    path: KeyPath,
+    kind: ImportErrorKind,
+}
+
+/// The reason a value could not be converted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportErrorKind {
+    /// A null where one cannot be left out.
+    Null,
+    /// An unsigned integer larger than `i64::MAX`; holds the integer.
+    OutOfRange(u64),
+}
+
+impl ImportError {
+    /// Returns the path of the value that could not be converted, which is
+    /// empty for the whole tree.
+    pub fn path(&self) -> &KeyPath {
+        &self.path
+    }
+
+    /// Returns the reason the value could not be converted.
+    pub fn kind(&self) -> ImportErrorKind {
+        self.kind
+    }
+}
+
+impl fmt::Display for ImportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            ImportErrorKind::Null => f.write_str("TOML has no null value")?,
+            ImportErrorKind::OutOfRange(n) => {
+                write!(f, "integer {} is too large for a TOML integer", n)?
+            }
+        }
+        if self.path.is_empty() {
+            Ok(())
+        } else {
+            write!(f, " at `{}`", self.path)
+        }
+    }
+}
+
+impl error::Error for ImportError {}
+
+/// Converts `tree`, which is at `path`, returning `None` for a null.
+fn convert<T: IntoToml + ?Sized>(
+    tree: &T,
+    path: &mut KeyPath,
+) -> Result<Option<Value>, ImportError> {
+    let value = match tree.node() {
+        Node::Null => return Ok(None),
+        Node::Boolean(b) => Value::Boolean(b),
+        Node::Integer(i) => Value::Integer(i),
+        Node::Unsigned(n) => match i64::try_from(n) {
+            Ok(i) => Value::Integer(i),
+            Err(_) => {
+                return Err(ImportError {
+                    path: path.clone(),
+                    kind: ImportErrorKind::OutOfRange(n),
+                })
+            }
+        },
+        Node::Float(f) => Value::Float(f),
+        Node::RawNumber(s) => Value::RawNumber(s.into()),
+        Node::String(s) => Value::from(s),
+        Node::Datetime(d) => Value::Datetime(d),
+        Node::Array(items) => {
+            let mut array = Vec::new();
+            for (i, item) in items.enumerate() {
+                path.push_index(i);
+                match convert(&*item, path)? {
+                    Some(value) => array.push(value),
+                    None => {
+                        return Err(ImportError {
+                            path: path.clone(),
+                            kind: ImportErrorKind::Null,
+                        })
+                    }
+                }
+                path.pop();
+            }
+            Value::Array(array)
+        }
+        Node::Table(entries) => {
+            let mut table = Table::new();
+            for (key, item) in entries {
+                path.push_key(key);
+                let value = convert(&*item, path)?;
+                path.pop();
+                if let Some(value) = value {
+                    table.insert(key.to_string(), value);
+                }
+            }
+            Value::Table(table)
+        }
+    };
+    Ok(Some(value))
+}
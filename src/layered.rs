@@ -0,0 +1,273 @@
+//! Configuration composed from several TOML documents.
+//!
+//! A [`Layered`] configuration is built by stacking documents on top of each
+//! other, for example a system-wide file, then a per-user file, then values
+//! from the command line. Tables are merged key by key, while any other value
+//! in a later layer replaces the one before it. Along the way every value
+//! remembers which layer, and where in that layer's source, it came from:
+//!
+//! ```rust
+//! use toml::layered::Layered;
+//!
+//! let mut config = Layered::new();
+//! config.push_str("/etc/app.toml", "[log]\nlevel = 'warn'\nfile = 'app.log'").unwrap();
+//! config.push_str("~/.config/app.toml", "\n\n[log]\nlevel = 'debug'").unwrap();
+//!
+//! assert_eq!(config.get("log.level").and_then(|v| v.as_str()), Some("debug"));
+//! assert_eq!(
+//!     config.describe("log.level").unwrap(),
+//!     "set in /etc/app.toml line 2, overridden by ~/.config/app.toml line 4"
+//! );
+//! ```
+//!
+//! [`Layered`]: struct.Layered.html
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::de;
+use crate::layout::Layout;
+use crate::path::KeyPath;
+use crate::value::{Table, Value};
+
+/// Where a value in a [`Layered`] configuration was defined.
+///
+/// [`Layered`]: struct.Layered.html
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Origin {
+    source: String,
+    span: Option<(usize, usize)>,
+    line_col: Option<(usize, usize)>,
+}
+
+impl Origin {
+    /// Returns the name of the layer the value was defined in.
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Returns the byte range of the definition within the layer's source.
+    ///
+    /// This is `None` for layers added from a `Table` and for tables that
+    /// were only created implicitly, such as `a` in `a.b = 1`.
+    pub fn span(&self) -> Option<(usize, usize)> {
+        self.span
+    }
+
+    /// Produces a (line, column) pair of the start of the definition if
+    /// available
+    ///
+    /// All indexes are 0-based.
+    pub fn line_col(&self) -> Option<(usize, usize)> {
+        self.line_col
+    }
+}
+
+impl fmt::Display for Origin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)?;
+        if let Some((line, _)) = self.line_col {
+            write!(f, " line {}", line + 1)?;
+        }
+        Ok(())
+    }
+}
+
+/// A configuration built from several TOML documents, tracking the origin of
+/// every value.
+#[derive(Debug, Clone)]
+pub struct Layered {
+    root: Value,
+    origins: BTreeMap<KeyPath, Vec<Origin>>,
+    unset_with_empty_table: bool,
+}
+
+impl Default for Layered {
+    fn default() -> Layered {
+        Layered::new()
+    }
+}
+
+impl Layered {
+    /// Creates an empty configuration with no layers.
+    pub fn new() -> Layered {
+        Layered {
+            root: Value::Table(Table::new()),
+            origins: BTreeMap::new(),
+            unset_with_empty_table: false,
+        }
+    }
+
+    /// TOML has no null value, so a layer cannot normally remove a key set
+    /// by an earlier layer.
+    ///
+    /// This option can be set to `true` (the default is `false`) to treat an
+    /// empty table, `key = {}`, in a layer as removing `key` and everything
+    /// below it, as [`Table::merge_with_unset`] does given an empty table as
+    /// its marker. Origins of the removed values are forgotten.
+    ///
+    /// [`Table::merge_with_unset`]: ../map/struct.Map.html#method.merge_with_unset
+    pub fn set_unset_with_empty_table(&mut self, unset: bool) {
+        self.unset_with_empty_table = unset;
+    }
+
+    /// Parses `input` and merges it on top of the existing layers.
+    ///
+    /// `source` names the layer in origins, typically the path of the file
+    /// `input` was read from. If `input` fails to parse the configuration is
+    /// left unchanged.
+    pub fn push_str(&mut self, source: &str, input: &str) -> Result<(), de::Error> {
+        let table = crate::from_str::<Table>(input)?;
+        let layout = Layout::scan(input)?;
+        let origin = |path: &KeyPath| {
+            let entry = layout.find(path);
+            Origin {
+                source: source.to_string(),
+                span: entry.map(|e| e.key.into()),
+                line_col: entry.map(|e| de::to_linecol(input, e.key.start)),
+            }
+        };
+        self.merge(table, &origin);
+        Ok(())
+    }
+
+    /// Merges an already-built table on top of the existing layers.
+    ///
+    /// Values from this layer have an origin with no span.
+    pub fn push_table(&mut self, source: &str, table: Table) {
+        let origin = |_: &KeyPath| Origin {
+            source: source.to_string(),
+            span: None,
+            line_col: None,
+        };
+        self.merge(table, &origin);
+    }
+
+    fn merge(&mut self, table: Table, origin: &dyn Fn(&KeyPath) -> Origin) {
+        let mut merger = Merger {
+            origins: &mut self.origins,
+            origin,
+            unset_with_empty_table: self.unset_with_empty_table,
+        };
+        merger.merge(self.root.as_table_mut().unwrap(), table, &KeyPath::new());
+    }
+
+    /// Returns the merged table.
+    pub fn table(&self) -> &Table {
+        self.root.as_table().unwrap()
+    }
+
+    /// Consumes this configuration, returning the merged table.
+    pub fn into_table(self) -> Table {
+        match self.root {
+            Value::Table(table) => table,
+            _ => unreachable!(),
+        }
+    }
+
+    /// Looks up the merged value at `path`.
+    pub fn get<P: Into<KeyPath>>(&self, path: P) -> Option<&Value> {
+        path.into().lookup(&self.root)
+    }
+
+    /// Returns where the current value at `path` was defined.
+    ///
+    /// Origins are kept by the configuration rather than by each `Value`, so
+    /// that values stay plain data that compares, clones and serializes the
+    /// same whether or not it was read from a layered configuration.
+    pub fn origin<P: Into<KeyPath>>(&self, path: P) -> Option<&Origin> {
+        self.origins.get(&path.into()).and_then(|o| o.last())
+    }
+
+    /// Returns every definition of `path`, oldest first. The last entry is
+    /// the one that is currently in effect.
+    pub fn history<P: Into<KeyPath>>(&self, path: P) -> &[Origin] {
+        self.origins
+            .get(&path.into())
+            .map(|o| &o[..])
+            .unwrap_or(&[])
+    }
+
+    /// Describes where `path` was defined and overridden, suitable for use
+    /// in error messages, for example
+    /// `set in /etc/app.toml line 12, overridden by ~/.config/app.toml line 3`.
+    pub fn describe<P: Into<KeyPath>>(&self, path: P) -> Option<String> {
+        let history = self.history(path);
+        let (first, rest) = history.split_first()?;
+        let mut s = format!("set in {}", first);
+        for origin in rest {
+            s.push_str(&format!(", overridden by {}", origin));
+        }
+        Some(s)
+    }
+}
+
+struct Merger<'a> {
+    origins: &'a mut BTreeMap<KeyPath, Vec<Origin>>,
+    origin: &'a dyn Fn(&KeyPath) -> Origin,
+    unset_with_empty_table: bool,
+}
+
+impl<'a> Merger<'a> {
+    fn merge(&mut self, dst: &mut Table, src: Table, prefix: &KeyPath) {
+        for (key, value) in src {
+            let path = prefix.join_key(key.clone());
+            match (dst.get_mut(&key), value) {
+                (_, Value::Table(ref src)) if self.unset_with_empty_table && src.is_empty() => {
+                    self.forget(&path, true);
+                    dst.remove(&key);
+                }
+                (Some(Value::Table(dst)), Value::Table(src)) => {
+                    self.push(&path);
+                    self.merge(dst, src, &path);
+                }
+                (_, value) => {
+                    self.push(&path);
+                    self.forget(&path, false);
+                    self.record(&value, &path);
+                    dst.insert(key, value);
+                }
+            }
+        }
+    }
+
+    fn push(&mut self, path: &KeyPath) {
+        let origin = (self.origin)(path);
+        self.origins.entry(path.clone()).or_default().push(origin);
+    }
+
+    /// Drops the origins of everything below `path`, and of `path` itself
+    /// if `inclusive` is set.
+    fn forget(&mut self, path: &KeyPath, inclusive: bool) {
+        let stale = self
+            .origins
+            .range(path.clone()..)
+            .skip(if inclusive { 0 } else { 1 })
+            .take_while(|(p, _)| p.starts_with(path))
+            .map(|(p, _)| p.clone())
+            .collect::<Vec<_>>();
+        for p in stale {
+            self.origins.remove(&p);
+        }
+    }
+
+    fn record(&mut self, value: &Value, path: &KeyPath) {
+        match *value {
+            Value::Table(ref table) => {
+                for (key, value) in table {
+                    let path = path.join_key(key.clone());
+                    self.push(&path);
+                    self.record(value, &path);
+                }
+            }
+            Value::Array(ref array) => {
+                for (i, value) in array.iter().enumerate() {
+                    let path = path.join_index(i);
+                    self.push(&path);
+                    self.record(value, &path);
+                }
+            }
+            _ => {}
+        }
+    }
+}
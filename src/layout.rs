@@ -0,0 +1,190 @@
+//! Source layout of a TOML document.
+//!
+//! The deserializer throws away where in the input each value was written
+//! once it has built a table. The scanner here reuses the line parser to
+//! record, for every key, header and nested value, the full key path it
+//! resolves to along with the byte ranges it occupies in the original text.
+
+use crate::de::{self, Deserializer, Line, TablePair, Value, E};
+use crate::path::KeyPath;
+use crate::ser::TableStyle;
+use crate::tokens::Span;
+
+use std::collections::HashMap;
+
+#[derive(Debug, Clone)]
+pub(crate) struct Entry {
+    pub(crate) path: KeyPath,
+    /// The span of the key as written. For headers this is the text between
+    /// the brackets, and for array elements it is the span of the element.
+    pub(crate) key: Span,
+    /// The end of the entry: of its value, or of the closing brackets of a
+    /// header.
+    pub(crate) end: usize,
+    /// The span of the value, or `None` for a table header.
+    pub(crate) value: Option<Span>,
+    /// How the table at `path` is written, or `None` if this entry is not a
+    /// table header or an inline table.
+    pub(crate) style: Option<TableStyle>,
+}
+
+/// A `key = value` line.
+#[derive(Debug, Clone)]
+pub(crate) struct KeyValue {
+    /// The table of the header the line is under.
+    pub(crate) table: KeyPath,
+    /// The first part of the key, which names an entry of `table`.
+    pub(crate) key: String,
+    /// The start of the key.
+    pub(crate) start: usize,
+    /// The end of the whole key, dotted parts included.
+    pub(crate) key_end: usize,
+    /// The start of the value.
+    pub(crate) value_start: usize,
+    /// The end of the value.
+    pub(crate) end: usize,
+}
+
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Layout {
+    pub(crate) entries: Vec<Entry>,
+    pub(crate) key_values: Vec<KeyValue>,
+}
+
+impl Layout {
+    pub(crate) fn scan(input: &str) -> Result<Layout, de::Error> {
+        let mut d = Deserializer::new(input);
+        let mut layout = Layout::default();
+        let mut arrays: HashMap<KeyPath, usize> = HashMap::new();
+        let mut table = KeyPath::new();
+
+        while let Some(line) = d.line()? {
+            match line {
+                Line::Table {
+                    at,
+                    mut header,
+                    array,
+                } => {
+                    let mut parts = Vec::new();
+                    while let Some(part) = header.next().map_err(|e| d.token_error(e))? {
+                        parts.push(part);
+                    }
+                    let key_start = parts.first().map(|p| p.0.start).unwrap_or(at);
+                    let key_end = parts.last().map(|p| p.0.end).unwrap_or(at);
+                    let after_key = &input[key_end..];
+                    let brackets = if array { 2 } else { 1 };
+                    let end = input.len() - after_key.trim_start_matches(&[' ', '\t'][..]).len()
+                        + brackets;
+
+                    table = KeyPath::new();
+                    let n = parts.len();
+                    for (i, (_, key)) in parts.into_iter().enumerate() {
+                        table.push_key(key.into_owned());
+                        if array && i + 1 == n {
+                            break;
+                        }
+                        if let Some(&count) = arrays.get(&table) {
+                            table.push_index(count - 1);
+                        }
+                    }
+                    if array {
+                        let count = arrays.entry(table.clone()).or_insert(0);
+                        *count += 1;
+                        table.push_index(*count - 1);
+                    }
+
+                    layout.entries.push(Entry {
+                        path: table.clone(),
+                        key: Span {
+                            start: key_start,
+                            end: key_end,
+                        },
+                        end,
+                        value: None,
+                        style: Some(TableStyle::Standard),
+                    });
+                }
+                Line::KeyValue(key, value) => {
+                    let mut path = table.clone();
+                    let key_span = Span {
+                        start: key[0].0.start,
+                        end: key[key.len() - 1].0.end,
+                    };
+                    layout.key_values.push(KeyValue {
+                        table: table.clone(),
+                        key: key[0].1.to_string(),
+                        start: key_span.start,
+                        key_end: key_span.end,
+                        value_start: value.start,
+                        end: value.end,
+                    });
+                    for (_, part) in key {
+                        path.push_key(part.into_owned());
+                    }
+                    layout.push(path, key_span, &value);
+                }
+            }
+        }
+        Ok(layout)
+    }
+
+    fn push(&mut self, path: KeyPath, key: Span, value: &Value<'_>) {
+        if let E::DottedTable(ref pairs) = value.e {
+            return self.push_pairs(&path, pairs);
+        }
+        self.entries.push(Entry {
+            path: path.clone(),
+            key,
+            end: value.end,
+            value: Some(Span {
+                start: value.start,
+                end: value.end,
+            }),
+            style: match value.e {
+                E::InlineTable(_) => Some(TableStyle::Inline),
+                _ => None,
+            },
+        });
+        match value.e {
+            E::InlineTable(ref pairs) => self.push_pairs(&path, pairs),
+            E::Array(ref values) => {
+                for (i, value) in values.iter().enumerate() {
+                    let span = Span {
+                        start: value.start,
+                        end: value.end,
+                    };
+                    self.push(path.join_index(i), span, value);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn push_pairs(&mut self, parent: &KeyPath, pairs: &[TablePair<'_>]) {
+        for ((span, key), value) in pairs {
+            self.push(parent.join_key(key.clone().into_owned()), *span, value);
+        }
+    }
+
+    /// Returns how the table at `path` is written: as recorded for the
+    /// table itself, or for one created implicitly, with a header if the
+    /// first entry inside it is a header and with dotted keys otherwise.
+    pub(crate) fn table_style(&self, path: &KeyPath) -> Option<TableStyle> {
+        if let Some(style) = self.find(path).and_then(|e| e.style) {
+            return Some(style);
+        }
+        let first = self
+            .entries
+            .iter()
+            .find(|e| e.path.len() > path.len() && e.path.starts_with(path))?;
+        match first.style {
+            Some(TableStyle::Standard) => Some(TableStyle::Standard),
+            _ => Some(TableStyle::Dotted),
+        }
+    }
+
+    /// Returns the last entry recorded for `path`.
+    pub(crate) fn find(&self, path: &KeyPath) -> Option<&Entry> {
+        self.entries.iter().rev().find(|e| e.path == *path)
+    }
+}
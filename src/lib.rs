@@ -140,6 +140,26 @@
 //! }
 //! ```
 //!
+//! ## Threads
+//!
+//! Everything parsing produces is plain data that is `Send` and `Sync`:
+//! [`Value`] and its tables, [`Datetime`], [`Spanned`], the documents of the
+//! [`document`] module, key paths, and the error types of every module.
+//! A configuration parsed once can be shared between the threads of a
+//! server, behind an `Arc` or otherwise.
+//!
+//! A [`Deserializer`] can be moved to or shared with another thread too, so
+//! the callbacks it takes, such as `Deserializer::set_progress`, must be
+//! `Send`. The [`Serializer`] is the exception: its settings are shared with
+//! the serializers it creates for nested values, and it is meant to be used
+//! on the thread that created it.
+//!
+//! [`Value`]: value/enum.Value.html
+//! [`Datetime`]: value/struct.Datetime.html
+//! [`Spanned`]: struct.Spanned.html
+//! [`document`]: document/index.html
+//! [`Deserializer`]: struct.Deserializer.html
+//! [`Serializer`]: struct.Serializer.html
 //! [TOML]: https://github.com/toml-lang/toml
 //! [Cargo]: https://crates.io/
 //! [`serde`]: https://serde.rs/
@@ -156,7 +176,7 @@
 pub mod map;
 pub mod value;
 #[doc(no_inline)]
-pub use crate::value::Value;
+pub use crate::value::{from_value, semantically_equal, to_value, Value};
 mod datetime;
 
 pub mod ser;
@@ -173,8 +193,107 @@ pub mod macros;
 mod spanned;
 pub use crate::spanned::Spanned;
 
+#[cfg(feature = "cargo")]
+pub mod cargo;
+pub mod compat;
+#[doc(no_inline)]
+pub use crate::compat::from_env_prefix;
+pub mod conv;
+pub mod directive;
+pub mod display;
+pub mod document;
+pub mod effective;
+mod either;
+pub use crate::either::{Either, OneOf};
+mod error;
+pub use crate::error::Error;
+pub mod ext;
+pub mod fields;
+pub mod file_path;
+pub mod fix;
+#[doc(no_inline)]
+pub use crate::fix::autofix;
+pub mod frontmatter;
+pub mod fs;
+#[cfg(feature = "import")]
+pub mod import;
+pub mod layered;
+mod layout;
+pub mod metadata;
+pub mod net;
+pub mod patch;
+pub mod path;
+pub mod reference;
+pub mod schema;
+pub mod skeleton;
+pub mod stream;
+pub mod string_or_table;
+pub mod template;
+#[doc(no_inline)]
+pub use crate::template::render;
+pub mod validated;
+pub mod watch;
+pub mod workspace;
+
 // Just for rustdoc
 #[allow(unused_imports)]
 use crate::datetime::Datetime;
 #[allow(unused_imports)]
 use core::str::FromStr;
+
+/// Fails to compile if a type that is documented as safe to share between
+/// threads stops being so.
+#[allow(dead_code)]
+fn assert_send_sync() {
+    fn check<T: Send + Sync>() {}
+
+    check::<Value>();
+    check::<value::Table>();
+    check::<value::ValueKind>();
+    check::<map::IntoIter>();
+    check::<map::Transaction<'static>>();
+    check::<Datetime>();
+    check::<value::DatetimeParseError>();
+    check::<Spanned<Value>>();
+    check::<Error>();
+    check::<de::Error>();
+    check::<de::Deserializer<'static>>();
+    check::<de::KeyUsage>();
+    check::<de::Warning>();
+    check::<ser::Error>();
+    check::<ser::FieldRules>();
+    check::<document::Document>();
+    check::<document::TextEdit>();
+    check::<document::TableStats>();
+    check::<effective::EffectiveConfig>();
+    check::<workspace::Workspace>();
+    check::<compat::ArgError>();
+    check::<conv::EpochSeconds>();
+    check::<conv::Iso8601String>();
+    check::<directive::Directive>();
+    check::<ext::Version>();
+    check::<ext::VersionReq>();
+    check::<ext::VersionError>();
+    check::<fields::FieldValue<'static>>();
+    check::<file_path::PathError>();
+    check::<file_path::PathOptions>();
+    check::<fix::Fix>();
+    check::<fs::UpdateError>();
+    check::<fs::UpdateOptions>();
+    #[cfg(feature = "import")]
+    check::<import::ImportError>();
+    check::<layered::Layered>();
+    check::<metadata::Metadata>();
+    check::<path::KeyPath>();
+    check::<path::KeyPathPattern>();
+    check::<path::LookupError>();
+    check::<path::AccessError>();
+    check::<path::KeyPathParseError>();
+    check::<reference::ReferenceError>();
+    check::<schema::Schema>();
+    check::<schema::SchemaError>();
+    check::<schema::Constraint>();
+    check::<schema::PatternError>();
+    check::<stream::Segment>();
+    check::<template::RenderError>();
+}
@@ -167,6 +167,33 @@ impl Map<String, Value> {
         self.map.is_empty()
     }
 
+    #[cfg(not(feature = "preserve_order"))]
+    /// Estimates the heap memory held by the map's own allocations, not
+    /// counting what its keys and values allocate.
+    pub(crate) fn heap_size(&self) -> usize {
+        // Nodes hold up to 11 entries, plus a parent pointer and lengths,
+        // and are at least half full.
+        const NODE_CAPACITY: usize = 11;
+        let nodes = match self.map.len() {
+            0 => 0,
+            len => len * 2 / NODE_CAPACITY + 1,
+        };
+        let node = NODE_CAPACITY * std::mem::size_of::<(String, Value)>()
+            + 2 * std::mem::size_of::<usize>();
+        nodes * node
+    }
+
+    #[cfg(feature = "preserve_order")]
+    /// Estimates the heap memory held by the map's own allocations, not
+    /// counting what its keys and values allocate.
+    pub(crate) fn heap_size(&self) -> usize {
+        // Entries are stored with their hash, and indexed by a hash table of
+        // one index and one control byte per slot.
+        let entry = std::mem::size_of::<(usize, String, Value)>();
+        let slot = std::mem::size_of::<usize>() + 1;
+        self.map.capacity() * (entry + slot)
+    }
+
     /// Gets an iterator over the entries of the map.
     #[inline]
     pub fn iter(&self) -> Iter<'_> {
@@ -198,6 +225,135 @@ impl Map<String, Value> {
             iter: self.map.values(),
         }
     }
+
+    /// Recursively merges `other` into this map.
+    ///
+    /// Where both maps contain a table under the same key the two tables are
+    /// merged key by key; any other value in `other` replaces the one in this
+    /// map.
+    pub fn merge(&mut self, other: Map<String, Value>) {
+        for (key, value) in other {
+            match (self.get_mut(&key), value) {
+                (Some(Value::Table(dst)), Value::Table(src)) => dst.merge(src),
+                (_, value) => {
+                    self.insert(key, value);
+                }
+            }
+        }
+    }
+
+    /// Recursively merges `other` into this map like [`merge`], except that
+    /// a value in `other` equal to `unset` removes the key from this map
+    /// instead of being merged into it.
+    ///
+    /// TOML has no null value, so this gives layered configuration a way to
+    /// say "unset the inherited value", with a marker value chosen by the
+    /// application that a later layer writes in its place.
+    ///
+    /// ```rust
+    /// use toml::Value;
+    ///
+    /// let mut base: toml::value::Table = toml::from_str("proxy = 'http://proxy'\nport = 80").unwrap();
+    /// base.merge_with_unset(toml::from_str("proxy = '!unset'").unwrap(), &Value::from("!unset"));
+    /// assert!(!base.contains_key("proxy"));
+    /// assert!(base.contains_key("port"));
+    /// ```
+    ///
+    /// [`merge`]: #method.merge
+    pub fn merge_with_unset(&mut self, other: Map<String, Value>, unset: &Value) {
+        for (key, value) in other {
+            match (self.get_mut(&key), value) {
+                (_, ref value) if value == unset => {
+                    self.remove(&key);
+                }
+                (Some(Value::Table(dst)), Value::Table(src)) => dst.merge_with_unset(src, unset),
+                (_, value) => {
+                    self.insert(key, value);
+                }
+            }
+        }
+    }
+
+    /// Applies the profile `name` to this map, Cargo style.
+    ///
+    /// Profiles live under a top-level `profile` table, so that the settings
+    /// in `[profile.production.server]` override those in `[server]` when the
+    /// `production` profile is selected. The returned map is this one with
+    /// the selected profile merged on top (as with [`merge`]) and the
+    /// `profile` table removed. Selecting a profile that is not defined
+    /// simply strips the profiles.
+    ///
+    /// ```rust
+    /// let config: toml::value::Table = toml::from_str(r#"
+    ///     [server]
+    ///     host = "localhost"
+    ///     port = 8080
+    ///
+    ///     [profile.production.server]
+    ///     host = "example.com"
+    /// "#).unwrap();
+    ///
+    /// let production = config.resolve_profile("production");
+    /// assert_eq!(production["server"]["host"].as_str(), Some("example.com"));
+    /// assert_eq!(production["server"]["port"].as_integer(), Some(8080));
+    /// assert!(!production.contains_key("profile"));
+    /// ```
+    ///
+    /// [`merge`]: #method.merge
+    pub fn resolve_profile(&self, name: &str) -> Map<String, Value> {
+        let mut base = self.clone();
+        let overlay = match base.remove("profile") {
+            Some(Value::Table(mut profiles)) => profiles.remove(name),
+            _ => None,
+        };
+        if let Some(Value::Table(overlay)) = overlay {
+            base.merge(overlay);
+        }
+        base
+    }
+
+    /// Starts a set of changes to this map that is applied or undone as a
+    /// whole.
+    ///
+    /// The returned [`Transaction`] derefs to this map, so it is changed
+    /// through it like the map itself. The changes are kept by calling
+    /// [`commit`], and undone by calling [`rollback`] or by dropping the
+    /// transaction without committing, so returning early with `?` when a
+    /// later step fails leaves the map as it was:
+    ///
+    /// ```rust
+    /// use toml::value::Table;
+    /// use toml::Value;
+    ///
+    /// fn rename_port(config: &mut Table, to: &str) -> Result<(), String> {
+    ///     let mut tx = config.transaction();
+    ///     let port = tx.remove("port").ok_or("no port")?;
+    ///     if tx.contains_key(to) {
+    ///         return Err(format!("`{}` is already set", to));
+    ///     }
+    ///     tx.insert(to.to_string(), port);
+    ///     tx.commit();
+    ///     Ok(())
+    /// }
+    ///
+    /// let mut config: Table = toml::from_str("port = 80\nlisten = 8080").unwrap();
+    /// assert!(rename_port(&mut config, "listen").is_err());
+    /// assert_eq!(config["port"].as_integer(), Some(80));
+    ///
+    /// rename_port(&mut config, "http_port").unwrap();
+    /// assert_eq!(config.get("http_port"), Some(&Value::Integer(80)));
+    /// assert!(!config.contains_key("port"));
+    /// ```
+    ///
+    /// [`Transaction`]: struct.Transaction.html
+    /// [`commit`]: struct.Transaction.html#method.commit
+    /// [`rollback`]: struct.Transaction.html#method.rollback
+    pub fn transaction(&mut self) -> Transaction<'_> {
+        Transaction {
+            map: self,
+            original: None,
+        }
+    }
 }
 
 impl Default for Map<String, Value> {
@@ -493,6 +649,83 @@ impl<'a> OccupiedEntry<'a> {
 
 //////////////////////////////////////////////////////////////////////////////
 
+/// A set of changes to a map that is applied or undone as a whole. It is
+/// returned by the [`transaction`] method on [`Map`].
+///
+/// The map as it was is copied the first time it is borrowed mutably
+/// through the transaction, and put back unless the transaction is
+/// committed.
+///
+/// [`transaction`]: struct.Map.html#method.transaction
+/// [`Map`]: struct.Map.html
+#[derive(Debug)]
+pub struct Transaction<'a> {
+    map: &'a mut Map<String, Value>,
+    original: Option<Map<String, Value>>,
+}
+
+impl<'a> Transaction<'a> {
+    /// Keeps the changes made through this transaction.
+    pub fn commit(mut self) {
+        self.original = None;
+    }
+
+    /// Undoes the changes made through this transaction, which is also
+    /// what dropping it without committing does.
+    pub fn rollback(self) {}
+
+    /// Returns the map as it was when the transaction started.
+    pub fn original(&self) -> &Map<String, Value> {
+        self.original.as_ref().unwrap_or(self.map)
+    }
+
+    /// Returns the keys whose values were changed, added or removed by this
+    /// transaction so far: those of the map first, then removed keys.
+    pub fn changed_keys(&self) -> Vec<&str> {
+        let original = match self.original {
+            Some(ref original) => original,
+            None => return Vec::new(),
+        };
+        let changed = self
+            .map
+            .iter()
+            .filter(|&(key, value)| original.get(key) != Some(value))
+            .map(|(key, _)| key.as_str());
+        let removed = original
+            .keys()
+            .filter(|key| !self.map.contains_key(*key))
+            .map(String::as_str);
+        changed.chain(removed).collect()
+    }
+}
+
+impl<'a> ops::Deref for Transaction<'a> {
+    type Target = Map<String, Value>;
+
+    fn deref(&self) -> &Map<String, Value> {
+        self.map
+    }
+}
+
+impl<'a> ops::DerefMut for Transaction<'a> {
+    fn deref_mut(&mut self) -> &mut Map<String, Value> {
+        if self.original.is_none() {
+            self.original = Some(self.map.clone());
+        }
+        self.map
+    }
+}
+
+impl<'a> Drop for Transaction<'a> {
+    fn drop(&mut self) {
+        if let Some(original) = self.original.take() {
+            *self.map = original;
+        }
+    }
+}
+
+//////////////////////////////////////////////////////////////////////////////
+
 impl<'a> IntoIterator for &'a Map<String, Value> {
     type Item = (&'a String, &'a Value);
     type IntoIter = Iter<'a>;
@@ -0,0 +1,175 @@
+//! Side-table of annotations attached to key paths.
+//!
+//! [`Metadata`] stores a small table of attributes for any key path, for
+//! example a deprecation flag or a hint to show in a user interface, without
+//! changing the values of the document itself:
+//!
+//! ```rust
+//! use toml::metadata::Metadata;
+//! use toml::Value;
+//!
+//! let mut meta = Metadata::new();
+//! meta.set("server.port", "hint", Value::from("port to listen on"));
+//! meta.set("server.legacy", "deprecated", Value::from(true));
+//!
+//! assert_eq!(meta.get("server.port", "hint").and_then(|v| v.as_str()), Some("port to listen on"));
+//! ```
+//!
+//! Attributes can be attached to every path of a table matching a
+//! [`KeyPathPattern`] at once:
+//!
+//! ```rust
+//! use toml::metadata::Metadata;
+//! use toml::value::Table;
+//! use toml::Value;
+//!
+//! let config: Table = toml::from_str("[db]\nuser = 'app'\npassword = 'x'\n").unwrap();
+//! let mut meta = Metadata::new();
+//! let count = meta.set_matching(&config, &"**.password".parse().unwrap(), "secret", Value::from(true));
+//! assert_eq!(count, 1);
+//! assert_eq!(meta.get("db.password", "secret"), Some(&Value::from(true)));
+//! ```
+//!
+//! [`Metadata`]: struct.Metadata.html
+//! [`KeyPathPattern`]: ../path/struct.KeyPathPattern.html
+
+use std::collections::BTreeMap;
+
+use crate::path::{KeyPath, KeyPathPattern, PathSegment};
+use crate::value::{Table, Value};
+
+/// Attributes attached to key paths of a document.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Metadata {
+    entries: BTreeMap<KeyPath, Table>,
+}
+
+impl Metadata {
+    /// Creates an empty side-table.
+    pub fn new() -> Metadata {
+        Metadata {
+            entries: BTreeMap::new(),
+        }
+    }
+
+    /// Returns whether no attributes are stored.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Sets the attribute `name` of `path` to `value`, returning the previous
+    /// value of the attribute if there was one.
+    pub fn set<P: Into<KeyPath>>(&mut self, path: P, name: &str, value: Value) -> Option<Value> {
+        self.entries
+            .entry(path.into())
+            .or_default()
+            .insert(name.to_string(), value)
+    }
+
+    /// Sets the attribute `name` to `value` for every path of `table`
+    /// matching `pattern`, tables and arrays included, returning how many
+    /// paths matched.
+    pub fn set_matching(
+        &mut self,
+        table: &Table,
+        pattern: &KeyPathPattern,
+        name: &str,
+        value: Value,
+    ) -> usize {
+        let mut count = 0;
+        for path in table.all_paths() {
+            if pattern.matches(&path) {
+                self.set(path, name, value.clone());
+                count += 1;
+            }
+        }
+        count
+    }
+
+    /// Returns the attribute `name` of `path`.
+    pub fn get<P: Into<KeyPath>>(&self, path: P, name: &str) -> Option<&Value> {
+        self.entries.get(&path.into()).and_then(|t| t.get(name))
+    }
+
+    /// Returns all attributes of `path`.
+    pub fn attributes<P: Into<KeyPath>>(&self, path: P) -> Option<&Table> {
+        self.entries.get(&path.into())
+    }
+
+    /// Removes the attribute `name` of `path`, returning it if it was set.
+    pub fn remove<P: Into<KeyPath>>(&mut self, path: P, name: &str) -> Option<Value> {
+        let path = path.into();
+        let attrs = self.entries.get_mut(&path)?;
+        let ret = attrs.remove(name);
+        if attrs.is_empty() {
+            self.entries.remove(&path);
+        }
+        ret
+    }
+
+    /// Removes all attributes of `path` and of every path nested below it.
+    pub fn remove_all<P: Into<KeyPath>>(&mut self, path: P) {
+        let path = path.into();
+        self.entries.retain(|p, _| !p.starts_with(&path));
+    }
+
+    /// Moves the attributes of `from`, and of every path nested below it, to
+    /// the corresponding paths below `to`. Attributes already present at the
+    /// destination are overwritten.
+    ///
+    /// This keeps annotations attached to their values when a transform
+    /// renames a key or moves a table.
+    pub fn relocate<P: Into<KeyPath>, Q: Into<KeyPath>>(&mut self, from: P, to: Q) {
+        let from = from.into();
+        let to = to.into();
+        let moved = self
+            .entries
+            .keys()
+            .filter(|p| p.starts_with(&from))
+            .cloned()
+            .collect::<Vec<_>>();
+        for path in moved {
+            let attrs = self.entries.remove(&path).unwrap();
+            let mut dst = to.clone();
+            for segment in &path.segments()[from.len()..] {
+                match *segment {
+                    PathSegment::Key(ref key) => dst.push_key(key.clone()),
+                    PathSegment::Index(i) => dst.push_index(i),
+                }
+            }
+            self.entries.insert(dst, attrs);
+        }
+    }
+
+    /// Merges `other` into this side-table. For attributes set in both,
+    /// the value from `other` wins, mirroring how later documents override
+    /// earlier ones.
+    pub fn merge(&mut self, other: Metadata) {
+        for (path, attrs) in other.entries {
+            let dst = self.entries.entry(path).or_default();
+            for (name, value) in attrs {
+                dst.insert(name, value);
+            }
+        }
+    }
+
+    /// Drops attributes of paths that do not exist in `root`, for use after
+    /// a transform removed values.
+    pub fn retain_existing(&mut self, root: &Value) {
+        self.entries.retain(|p, _| p.lookup(root).is_some());
+    }
+
+    /// Iterates over annotated paths and their attributes in path order.
+    pub fn iter(&self) -> impl Iterator<Item = (&KeyPath, &Table)> {
+        self.entries.iter()
+    }
+
+    /// Iterates over the annotated paths matching `pattern` and their
+    /// attributes in path order.
+    pub fn matching<'a>(
+        &'a self,
+        pattern: &'a KeyPathPattern,
+    ) -> impl Iterator<Item = (&'a KeyPath, &'a Table)> {
+        self.entries.iter().filter(move |(p, _)| pattern.matches(p))
+    }
+}
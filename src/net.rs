@@ -0,0 +1,137 @@
+//! Reading IP addresses and socket addresses from TOML strings.
+//!
+//! The address types of `std::net` can be deserialized as they are, but a
+//! mistyped address then fails with an error such as "invalid IP address
+//! syntax", which does not say what was written. Reading them with this
+//! module, through `#[serde(with = "toml::net")]`, names the text and the
+//! kind of address expected, and points out a port written where none
+//! belongs or missing where one is needed:
+//!
+//! ```rust
+//! use std::net::{IpAddr, SocketAddr};
+//!
+//! use serde_derive::{Deserialize, Serialize};
+//!
+//! #[derive(Debug, Deserialize, Serialize)]
+//! struct Server {
+//!     #[serde(with = "toml::net")]
+//!     listen: SocketAddr,
+//!     #[serde(with = "toml::net")]
+//!     resolver: IpAddr,
+//! }
+//!
+//! let server: Server = toml::from_str("listen = '[::1]:8080'\nresolver = '10.0.0.53'\n").unwrap();
+//! assert_eq!(server.listen.port(), 8080);
+//!
+//! let err = toml::from_str::<Server>("listen = '10.0.0.1'\nresolver = '10.0.0.53'\n").unwrap_err();
+//! assert_eq!(
+//!     err.to_string(),
+//!     "`10.0.0.1` is not a socket address: missing port for key `listen` at line 1 column 10",
+//! );
+//! ```
+
+use std::fmt;
+use std::marker::PhantomData;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4, SocketAddrV6};
+use std::str::FromStr;
+
+use serde::de::{self, Deserializer, Visitor};
+use serde::ser::Serializer;
+
+mod private {
+    pub trait Sealed {}
+}
+
+/// An address type of `std::net` that this module reads and writes.
+///
+/// This trait is sealed: it is implemented for `IpAddr`, `Ipv4Addr`,
+/// `Ipv6Addr`, `SocketAddr`, `SocketAddrV4` and `SocketAddrV6` only.
+pub trait Address: FromStr + fmt::Display + private::Sealed {
+    /// Describes the address, such as "an IPv4 address".
+    const NAME: &'static str;
+
+    /// Returns a hint for `s`, which is not an address of this type, if it
+    /// is one of a related type.
+    #[doc(hidden)]
+    fn hint(s: &str) -> Option<&'static str>;
+}
+
+/// Returns the hint for an IP address written with a port.
+fn port_hint(s: &str) -> Option<&'static str> {
+    s.parse::<SocketAddr>().ok().map(|_| "unexpected port")
+}
+
+/// Returns the hint for a socket address written without a port.
+fn no_port_hint(s: &str) -> Option<&'static str> {
+    s.parse::<IpAddr>()
+        .or_else(|_| s.trim_start_matches('[').trim_end_matches(']').parse())
+        .ok()
+        .map(|_| "missing port")
+}
+
+macro_rules! address {
+    ($($ty:ident $name:expr, $hint:ident;)*) => {$(
+        impl private::Sealed for $ty {}
+
+        impl Address for $ty {
+            const NAME: &'static str = $name;
+
+            fn hint(s: &str) -> Option<&'static str> {
+                $hint(s)
+            }
+        }
+    )*};
+}
+
+address! {
+    IpAddr "an IP address", port_hint;
+    Ipv4Addr "an IPv4 address", port_hint;
+    Ipv6Addr "an IPv6 address", port_hint;
+    SocketAddr "a socket address", no_port_hint;
+    SocketAddrV4 "an IPv4 socket address", no_port_hint;
+    SocketAddrV6 "an IPv6 socket address", no_port_hint;
+}
+
+/// Parses `s` as an address of type `T`, returning an error message naming
+/// `s` if it is not one.
+pub fn parse<T: Address>(s: &str) -> Result<T, String> {
+    s.parse().map_err(|_| match T::hint(s) {
+        Some(hint) => format!("`{}` is not {}: {}", s, T::NAME, hint),
+        None => format!("`{}` is not {}", s, T::NAME),
+    })
+}
+
+/// Deserializes an address from a string, for `#[serde(with = "toml::net")]`.
+pub fn deserialize<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Address,
+{
+    struct AddressVisitor<T>(PhantomData<T>);
+
+    impl<'de, T: Address> Visitor<'de> for AddressVisitor<T> {
+        type Value = T;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(formatter, "a string holding {}", T::NAME)
+        }
+
+        fn visit_str<E>(self, s: &str) -> Result<T, E>
+        where
+            E: de::Error,
+        {
+            parse(s).map_err(de::Error::custom)
+        }
+    }
+
+    deserializer.deserialize_str(AddressVisitor(PhantomData))
+}
+
+/// Serializes an address as a string, for `#[serde(with = "toml::net")]`.
+pub fn serialize<S, T>(address: &T, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+    T: Address,
+{
+    serializer.collect_str(address)
+}
@@ -0,0 +1,119 @@
+//! Fields of a configuration layer that may inherit, set or unset a value.
+//!
+//! TOML has no null, so a layer that wants to remove a value set by an
+//! earlier layer writes an empty table instead, `key = {}`. This is the
+//! convention understood by [`Layered::set_unset_with_empty_table`], and by
+//! [`Table::merge_with_unset`] when given an empty table as its marker.
+//! [`Patch`] is the typed counterpart for use in
+//! `#[derive(Deserialize, Serialize)]` structs:
+//!
+//! ```rust
+//! use serde_derive::Deserialize;
+//! use toml::patch::Patch;
+//!
+//! #[derive(Deserialize)]
+//! struct Overrides {
+//!     #[serde(default)]
+//!     proxy: Patch<String>,
+//!     #[serde(default)]
+//!     port: Patch<u16>,
+//!     #[serde(default)]
+//!     user: Patch<String>,
+//! }
+//!
+//! let o: Overrides = toml::from_str("proxy = {}\nport = 8080").unwrap();
+//! assert_eq!(o.proxy, Patch::Unset);
+//! assert_eq!(o.port, Patch::Set(8080));
+//! assert_eq!(o.user, Patch::Inherit);
+//! ```
+//!
+//! [`Patch`]: enum.Patch.html
+//! [`Table::merge_with_unset`]: ../map/struct.Map.html#method.merge_with_unset
+//! [`Layered::set_unset_with_empty_table`]: ../layered/struct.Layered.html#method.set_unset_with_empty_table
+
+use serde::de::{self, IntoDeserializer};
+use serde::ser;
+
+use crate::value::{Table, Value};
+
+/// A value that a layer may leave alone, set, or explicitly unset.
+///
+/// This is the `Option<Option<T>>` pattern with names: `Inherit` is a missing
+/// key, `Unset` is `key = {}`, and `Set` is any other value. Fields of this
+/// type should be marked `#[serde(default)]` so that a missing key
+/// deserializes to `Inherit`. When serialized, `Inherit` is skipped like a
+/// `None` field and `Unset` is written as `{}`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Patch<T> {
+    /// The key is absent; the inherited value, if any, is kept.
+    #[default]
+    Inherit,
+    /// The key is an empty table; the inherited value is removed.
+    Unset,
+    /// The key has a value, which replaces the inherited one.
+    Set(T),
+}
+
+impl<T> Patch<T> {
+    /// Returns whether this is `Patch::Inherit`, for use with
+    /// `#[serde(skip_serializing_if = "Patch::is_inherit")]`.
+    pub fn is_inherit(&self) -> bool {
+        matches!(*self, Patch::Inherit)
+    }
+
+    /// Applies this patch to an inherited value.
+    pub fn apply(self, inherited: Option<T>) -> Option<T> {
+        match self {
+            Patch::Inherit => inherited,
+            Patch::Unset => None,
+            Patch::Set(value) => Some(value),
+        }
+    }
+}
+
+impl<T> From<Option<Option<T>>> for Patch<T> {
+    fn from(value: Option<Option<T>>) -> Patch<T> {
+        match value {
+            None => Patch::Inherit,
+            Some(None) => Patch::Unset,
+            Some(Some(value)) => Patch::Set(value),
+        }
+    }
+}
+
+impl<T> From<Patch<T>> for Option<Option<T>> {
+    fn from(patch: Patch<T>) -> Option<Option<T>> {
+        match patch {
+            Patch::Inherit => None,
+            Patch::Unset => Some(None),
+            Patch::Set(value) => Some(Some(value)),
+        }
+    }
+}
+
+impl<T: ser::Serialize> ser::Serialize for Patch<T> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: ser::Serializer,
+    {
+        match *self {
+            Patch::Inherit => serializer.serialize_none(),
+            Patch::Unset => Table::new().serialize(serializer),
+            Patch::Set(ref value) => value.serialize(serializer),
+        }
+    }
+}
+
+impl<'de, T: de::Deserialize<'de>> de::Deserialize<'de> for Patch<T> {
+    fn deserialize<D>(deserializer: D) -> Result<Patch<T>, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        match Value::deserialize(deserializer)? {
+            Value::Table(ref table) if table.is_empty() => Ok(Patch::Unset),
+            value => T::deserialize(value.into_deserializer())
+                .map(Patch::Set)
+                .map_err(de::Error::custom),
+        }
+    }
+}
@@ -0,0 +1,1087 @@
+//! Paths addressing values nested inside a TOML document.
+//!
+//! A [`KeyPath`] is a sequence of table keys and array indices, written in a
+//! syntax close to TOML's own dotted keys:
+//!
+//! ```rust
+//! use toml::path::KeyPath;
+//!
+//! let path: KeyPath = "servers.alpha.ports[0]".parse().unwrap();
+//! assert_eq!(path.len(), 4);
+//! assert_eq!(path.to_string(), "servers.alpha.ports[0]");
+//!
+//! let value: toml::Value = toml::from_str("[servers.alpha]\nports = [80]").unwrap();
+//! assert_eq!(path.lookup(&value).and_then(|v| v.as_integer()), Some(80));
+//! ```
+//!
+//! A [`KeyPathPattern`] matches many paths at once, for rules that apply to
+//! every value of some kind, such as all the passwords of a configuration:
+//!
+//! ```rust
+//! use toml::path::KeyPathPattern;
+//! use toml::value::Table;
+//! use toml::Value;
+//!
+//! let mut config: Table = toml::from_str(
+//!     "password = 'a'\n[db.primary]\nhost = 'db1'\npassword = 'b'\n",
+//! )
+//! .unwrap();
+//! let secrets: KeyPathPattern = "**.password".parse().unwrap();
+//! assert_eq!(config.redact(&secrets, Value::from("***")), 2);
+//! assert_eq!(config["db"]["primary"]["password"].as_str(), Some("***"));
+//! ```
+//!
+//! [`KeyPath`]: struct.KeyPath.html
+//! [`KeyPathPattern`]: struct.KeyPathPattern.html
+
+use std::error;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::display::{self, Limits};
+use crate::value::{Array, ArrayExt, Table, Value, ValueKind};
+
+/// A single step of a [`KeyPath`].
+///
+/// [`KeyPath`]: struct.KeyPath.html
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum PathSegment {
+    /// A key into a table.
+    Key(String),
+    /// An index into an array.
+    Index(usize),
+}
+
+/// A path from the root of a document to a nested value.
+#[derive(Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct KeyPath {
+    segments: Vec<PathSegment>,
+}
+
+/// A key path in which segments may be wildcards, matching many paths.
+///
+/// It is written like a `KeyPath`, where a `*` key matches any one key, a
+/// `[*]` index matches any one index and a `**` segment matches any number
+/// of keys and indices, none included:
+///
+/// ```rust
+/// use toml::path::{KeyPath, KeyPathPattern};
+///
+/// let ports: KeyPathPattern = "servers.*.port".parse().unwrap();
+/// assert!(ports.matches(&KeyPath::from("servers.alpha.port")));
+/// assert!(!ports.matches(&KeyPath::from("servers.port")));
+///
+/// let secrets: KeyPathPattern = "**.password".parse().unwrap();
+/// assert!(secrets.matches(&KeyPath::from("password")));
+/// assert!(secrets.matches(&KeyPath::from("db.replicas[1].password")));
+/// ```
+///
+/// Wildcards stand for whole segments: `pass*` is not a pattern.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct KeyPathPattern {
+    segments: Vec<PatternSegment>,
+}
+
+/// Error returned by `Table::require` when a path does not lead to a value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LookupError {
+    path: KeyPath,
+    segment: usize,
+    kind: LookupErrorKind,
+}
+
+/// The reason a path did not lead to a value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LookupErrorKind {
+    /// The table has no such key, or the array no such index.
+    Missing,
+    /// The value reached was not a table, for a key, or an array, for an
+    /// index; holds the type of the value found.
+    WrongType(&'static str),
+}
+
+/// Error returned by the checked accessors of `Value`, such as
+/// `Value::as_i64_checked`, and by `Table::require_checked`.
+///
+/// It describes the type wanted and the value found, along with the path of
+/// the value when known, so that it can be shown to a user as it is.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AccessError {
+    inner: Box<AccessErrorInner>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct AccessErrorInner {
+    path: Option<KeyPath>,
+    kind: AccessErrorKind,
+    expected: &'static str,
+    found: Option<ValueKind>,
+    value: Option<String>,
+    lookup: Option<LookupError>,
+}
+
+/// The reason a checked accessor failed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccessErrorKind {
+    /// The path did not lead to a value.
+    Lookup(LookupErrorKind),
+    /// The value is not of the type wanted.
+    WrongType,
+    /// The value is an integer that does not fit in the type wanted.
+    OutOfRange,
+}
+
+/// Error returned from parsing a `KeyPath` in the `FromStr` implementation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyPathParseError {
+    at: usize,
+    message: &'static str,
+}
+
+impl KeyPath {
+    /// Creates an empty path, which refers to the root of a document.
+    pub fn new() -> KeyPath {
+        KeyPath {
+            segments: Vec::new(),
+        }
+    }
+
+    /// Returns the segments of this path.
+    pub fn segments(&self) -> &[PathSegment] {
+        &self.segments
+    }
+
+    /// Returns the number of segments in this path.
+    pub fn len(&self) -> usize {
+        self.segments.len()
+    }
+
+    /// Tests whether this path refers to the root of a document.
+    pub fn is_empty(&self) -> bool {
+        self.segments.is_empty()
+    }
+
+    /// Appends a table key to this path.
+    pub fn push_key<S: Into<String>>(&mut self, key: S) {
+        self.segments.push(PathSegment::Key(key.into()));
+    }
+
+    /// Appends an array index to this path.
+    pub fn push_index(&mut self, index: usize) {
+        self.segments.push(PathSegment::Index(index));
+    }
+
+    /// Removes the last segment of this path, returning it if there was one.
+    pub fn pop(&mut self) -> Option<PathSegment> {
+        self.segments.pop()
+    }
+
+    /// Returns the last segment of this path, if any.
+    pub fn last(&self) -> Option<&PathSegment> {
+        self.segments.last()
+    }
+
+    /// Returns a new path with `key` appended to this one.
+    pub fn join_key<S: Into<String>>(&self, key: S) -> KeyPath {
+        let mut path = self.clone();
+        path.push_key(key);
+        path
+    }
+
+    /// Returns a new path with `index` appended to this one.
+    pub fn join_index(&self, index: usize) -> KeyPath {
+        let mut path = self.clone();
+        path.push_index(index);
+        path
+    }
+
+    /// Returns the path of the value containing this one, or `None` for the
+    /// root path.
+    pub fn parent(&self) -> Option<KeyPath> {
+        if self.segments.is_empty() {
+            return None;
+        }
+        let mut path = self.clone();
+        path.segments.pop();
+        Some(path)
+    }
+
+    /// Tests whether `prefix` is a prefix of this path. Every path starts with
+    /// itself and with the root path.
+    pub fn starts_with(&self, prefix: &KeyPath) -> bool {
+        self.segments.starts_with(&prefix.segments)
+    }
+
+    /// Looks up the value this path refers to, starting at `value`.
+    pub fn lookup<'a>(&self, value: &'a Value) -> Option<&'a Value> {
+        let mut cur = value;
+        for segment in &self.segments {
+            cur = match *segment {
+                PathSegment::Key(ref key) => cur.get(key)?,
+                PathSegment::Index(i) => cur.get(i)?,
+            };
+        }
+        Some(cur)
+    }
+
+    /// Looks up the value this path refers to in `table`. The root path
+    /// refers to no value here since `table` itself is not a `Value`.
+    pub(crate) fn lookup_in<'a>(&self, table: &'a Table) -> Option<&'a Value> {
+        let (first, rest) = self.segments.split_first()?;
+        let mut cur = match *first {
+            PathSegment::Key(ref key) => table.get(key)?,
+            PathSegment::Index(..) => return None,
+        };
+        for segment in rest {
+            cur = match *segment {
+                PathSegment::Key(ref key) => cur.get(key)?,
+                PathSegment::Index(i) => cur.get(i)?,
+            };
+        }
+        Some(cur)
+    }
+
+    /// Mutably looks up the value this path refers to, starting at `value`.
+    pub fn lookup_mut<'a>(&self, value: &'a mut Value) -> Option<&'a mut Value> {
+        let mut cur = value;
+        for segment in &self.segments {
+            cur = match *segment {
+                PathSegment::Key(ref key) => cur.get_mut(key)?,
+                PathSegment::Index(i) => cur.get_mut(i)?,
+            };
+        }
+        Some(cur)
+    }
+
+    /// Mutably looks up the value this path refers to in `table`.
+    fn lookup_mut_in<'a>(&self, table: &'a mut Table) -> Option<&'a mut Value> {
+        let (first, rest) = self.segments.split_first()?;
+        let mut cur = match *first {
+            PathSegment::Key(ref key) => table.get_mut(key)?,
+            PathSegment::Index(..) => return None,
+        };
+        for segment in rest {
+            cur = match *segment {
+                PathSegment::Key(ref key) => cur.get_mut(key)?,
+                PathSegment::Index(i) => cur.get_mut(i)?,
+            };
+        }
+        Some(cur)
+    }
+}
+
+impl KeyPathPattern {
+    /// Tests whether `path` matches this pattern.
+    pub fn matches(&self, path: &KeyPath) -> bool {
+        matches_segments(&self.segments, &path.segments)
+    }
+
+    /// Tests whether this pattern has no wildcards, and so matches a single
+    /// path.
+    pub fn is_exact(&self) -> bool {
+        self.segments.iter().all(|s| match s {
+            PatternSegment::Exact(_) => true,
+            PatternSegment::AnyKey | PatternSegment::AnyIndex | PatternSegment::AnySegments => {
+                false
+            }
+        })
+    }
+}
+
+fn matches_segments(pattern: &[PatternSegment], path: &[PathSegment]) -> bool {
+    match pattern.split_first() {
+        None => path.is_empty(),
+        Some((PatternSegment::AnySegments, rest)) => {
+            (0..=path.len()).any(|i| matches_segments(rest, &path[i..]))
+        }
+        Some((first, rest)) => match path.split_first() {
+            Some((segment, tail)) => {
+                let matched = match (first, segment) {
+                    (PatternSegment::Exact(p), s) => s == p,
+                    (PatternSegment::AnyKey, PathSegment::Key(_)) => true,
+                    (PatternSegment::AnyIndex, PathSegment::Index(_)) => true,
+                    _ => false,
+                };
+                matched && matches_segments(rest, tail)
+            }
+            None => false,
+        },
+    }
+}
+
+impl Table {
+    /// Looks up the value `path` refers to in this table, returning an error
+    /// describing where the lookup failed if there is none.
+    ///
+    /// ```rust
+    /// use toml::path::LookupErrorKind;
+    /// use toml::value::Table;
+    ///
+    /// let config: Table = toml::from_str("[server]\nport = 8080").unwrap();
+    /// assert_eq!(config.require("server.port").unwrap().as_integer(), Some(8080));
+    ///
+    /// let err = config.require("server.tls.cert").unwrap_err();
+    /// assert_eq!(err.segment(), 1);
+    /// assert_eq!(err.kind(), LookupErrorKind::Missing);
+    /// assert_eq!(err.to_string(), "missing key `server.tls` when looking up `server.tls.cert`");
+    ///
+    /// let err = config.require("server.port.number").unwrap_err();
+    /// assert_eq!(err.kind(), LookupErrorKind::WrongType("integer"));
+    /// ```
+    pub fn require<P: Into<KeyPath>>(&self, path: P) -> Result<&Value, LookupError> {
+        let path = path.into();
+        let error = |segment, kind| LookupError {
+            path: path.clone(),
+            segment,
+            kind,
+        };
+        let mut cur: Option<&Value> = None;
+        for (i, segment) in path.segments.iter().enumerate() {
+            let next = match (cur, segment) {
+                (None, PathSegment::Key(key)) => self.get(key),
+                (None, PathSegment::Index(..)) => {
+                    return Err(error(i, LookupErrorKind::WrongType("table")))
+                }
+                (Some(Value::Table(table)), PathSegment::Key(key)) => table.get(key),
+                (Some(Value::Array(array)), PathSegment::Index(index)) => array.get(*index),
+                (Some(value), _) => {
+                    return Err(error(i, LookupErrorKind::WrongType(value.type_str())))
+                }
+            };
+            match next {
+                Some(value) => cur = Some(value),
+                None => return Err(error(i, LookupErrorKind::Missing)),
+            }
+        }
+        cur.ok_or_else(|| error(0, LookupErrorKind::Missing))
+    }
+
+    /// Looks up the value `path` refers to in this table and extracts it
+    /// with `access`, one of the checked accessors of `Value`, returning an
+    /// error naming `path` if either fails.
+    ///
+    /// ```rust
+    /// use toml::value::{Table, Value};
+    ///
+    /// let config: Table = toml::from_str("[server]\nport = 80800\nhost = 'a'").unwrap();
+    /// let host = config.require_checked("server.host", Value::as_str_checked).unwrap();
+    /// assert_eq!(host, "a");
+    ///
+    /// let err = config
+    ///     .require_checked::<_, u16, _>("server.port", Value::as_integer_checked)
+    ///     .unwrap_err();
+    /// assert_eq!(err.to_string(), "integer `80800` at `server.port` is out of range for u16");
+    ///
+    /// let err = config.require_checked("server.host", Value::as_bool_checked).unwrap_err();
+    /// assert_eq!(err.to_string(), "expected boolean at `server.host`, found string `\"a\"`");
+    /// ```
+    pub fn require_checked<'a, P, T, F>(&'a self, path: P, access: F) -> Result<T, AccessError>
+    where
+        P: Into<KeyPath>,
+        F: FnOnce(&'a Value) -> Result<T, AccessError>,
+    {
+        let path = path.into();
+        let value = self.require(&path)?;
+        access(value).map_err(|err| err.with_path(path))
+    }
+
+    /// Appends `value` to the array at `path` unless the array already holds
+    /// a value equal to it, returning whether it was appended.
+    ///
+    /// The array, and the tables leading to it, are created if missing, so
+    /// adding a feature to `features.default` takes one call. The value must
+    /// be of the kind of the values already in the array, as for
+    /// `ArrayExt::concat`.
+    ///
+    /// ```rust
+    /// use toml::value::{Table, Value};
+    ///
+    /// let mut manifest: Table = toml::from_str("[features]\ndefault = ['std']").unwrap();
+    /// assert_eq!(manifest.array_insert_unique("features.default", Value::from("serde")), Ok(true));
+    /// assert_eq!(manifest.array_insert_unique("features.default", Value::from("std")), Ok(false));
+    /// assert_eq!(manifest["features"]["default"].as_str_array(), Some(vec!["std", "serde"]));
+    ///
+    /// manifest.array_insert_unique("package.keywords", Value::from("toml")).unwrap();
+    /// assert_eq!(manifest["package"]["keywords"].as_str_array(), Some(vec!["toml"]));
+    /// ```
+    pub fn array_insert_unique<P: Into<KeyPath>>(
+        &mut self,
+        path: P,
+        value: Value,
+    ) -> Result<bool, AccessError> {
+        let path = path.into();
+        let inserted = self.with_array(&path, true, |array| {
+            if array.contains(&value) {
+                return Ok(false);
+            }
+            array
+                .concat(vec![value])
+                .map(|()| true)
+                .map_err(|err| err.with_path(&path))
+        })?;
+        Ok(inserted.unwrap())
+    }
+
+    /// Removes every value equal to `value` from the array at `path`,
+    /// returning whether there was any.
+    ///
+    /// Nothing is removed, and `Ok(false)` returned, if there is nothing at
+    /// `path`. An error is returned if there is a value at `path` that is
+    /// not an array, or a value on the way that is not a table.
+    pub fn array_remove_value<P: Into<KeyPath>>(
+        &mut self,
+        path: P,
+        value: &Value,
+    ) -> Result<bool, AccessError> {
+        let path = path.into();
+        let removed = self.with_array(&path, false, |array| {
+            let len = array.len();
+            array.retain(|v| v != value);
+            Ok(array.len() != len)
+        })?;
+        Ok(removed.unwrap_or(false))
+    }
+
+    /// Calls `f` with the array at `path`, creating it if `create` is set,
+    /// or returns `None` if there is none.
+    fn with_array<F, R>(
+        &mut self,
+        path: &KeyPath,
+        create: bool,
+        f: F,
+    ) -> Result<Option<R>, AccessError>
+    where
+        F: FnOnce(&mut Array) -> Result<R, AccessError>,
+    {
+        let mut root = Value::Table(std::mem::take(self));
+        let ret = array_at(&mut root, path, create).and_then(|array| array.map(f).transpose());
+        *self = match root {
+            Value::Table(table) => table,
+            _ => unreachable!(),
+        };
+        ret
+    }
+
+    /// Returns the path of every leaf value in this table, in the order the
+    /// table iterates over its keys.
+    ///
+    /// A leaf is any value but a table, or an empty table. The elements of
+    /// an array holding a table are visited in turn, while any other array
+    /// is a leaf itself.
+    ///
+    /// ```rust
+    /// use toml::value::Table;
+    ///
+    /// let config: Table = toml::from_str(
+    ///     "log = {}\nname = 'demo'\nports = [80, 443]\n\n[[users]]\nid = 1\n",
+    /// )
+    /// .unwrap();
+    /// let paths = config.paths().iter().map(|p| p.to_string()).collect::<Vec<_>>();
+    /// assert_eq!(paths, ["log", "name", "ports", "users[0].id"]);
+    /// ```
+    pub fn paths(&self) -> Vec<KeyPath> {
+        let mut ret = Vec::new();
+        collect_paths(self, &mut KeyPath::new(), false, &mut ret);
+        ret
+    }
+
+    /// Returns the path of every value in this table, including tables and
+    /// arrays, each before the paths inside it.
+    pub fn all_paths(&self) -> Vec<KeyPath> {
+        let mut ret = Vec::new();
+        collect_paths(self, &mut KeyPath::new(), true, &mut ret);
+        ret
+    }
+
+    /// Returns every value of this table that is not a table or an array of
+    /// tables, along with its path, in the order of `paths`.
+    ///
+    /// Arrays of other values are returned whole, and an empty table is
+    /// returned as a value of its own.
+    ///
+    /// ```rust
+    /// use toml::value::Table;
+    ///
+    /// let config: Table = toml::from_str("[server]\nport = 80\n\n[[users]]\nname = 'a'\n").unwrap();
+    /// let flat = config.flatten();
+    /// assert_eq!(flat[0].0.to_string(), "server.port");
+    /// assert_eq!(flat[1].0.to_string(), "users[0].name");
+    /// assert_eq!(flat[1].1.as_str(), Some("a"));
+    /// ```
+    pub fn flatten(&self) -> Vec<(KeyPath, Value)> {
+        self.paths()
+            .into_iter()
+            .map(|path| {
+                let value = self.require(&path).unwrap().clone();
+                (path, value)
+            })
+            .collect()
+    }
+
+    /// Returns the paths that match `pattern`, a path in which a `*` key
+    /// matches any key, a `[*]` index matches any index and `**` matches any
+    /// number of segments, as in a `KeyPathPattern`.
+    ///
+    /// The paths are those of [`all_paths`] along with the elements of every
+    /// array, each before the paths inside it, so `ports[*]` matches each
+    /// element of an array of integers.
+    ///
+    /// ```rust
+    /// use toml::value::Table;
+    ///
+    /// let config: Table = toml::from_str(
+    ///     "[servers.alpha]\nport = 80\n\n[servers.beta]\nhost = 'b'\nport = 81\nalias = ['x', 'y']\n",
+    /// )
+    /// .unwrap();
+    /// let ports = config.paths_matching("servers.*.port").unwrap();
+    /// assert_eq!(ports.len(), 2);
+    /// assert_eq!(ports[1].to_string(), "servers.beta.port");
+    /// assert_eq!(config.paths_matching("**.alias[*]").unwrap().len(), 2);
+    /// ```
+    ///
+    /// [`all_paths`]: #method.all_paths
+    pub fn paths_matching(&self, pattern: &str) -> Result<Vec<KeyPath>, KeyPathParseError> {
+        let pattern = pattern.parse::<KeyPathPattern>()?;
+        let mut paths = self.value_paths();
+        paths.retain(|path| pattern.matches(path));
+        Ok(paths)
+    }
+
+    /// Returns the path of every value in this table, each before the paths
+    /// inside it, including the elements of every array.
+    pub(crate) fn value_paths(&self) -> Vec<KeyPath> {
+        let mut ret = Vec::new();
+        for (key, value) in self {
+            let mut path = KeyPath::new();
+            path.push_key(key.clone());
+            collect_every_path(value, &mut path, &mut ret);
+        }
+        ret
+    }
+
+    /// Replaces every value whose path matches `pattern` with `replacement`,
+    /// returning how many were replaced, so that a table can be logged or
+    /// shown without its secrets.
+    ///
+    /// A table or array that matches is replaced as a whole.
+    pub fn redact(&mut self, pattern: &KeyPathPattern, replacement: Value) -> usize {
+        let mut count = 0;
+        for path in self.all_paths() {
+            if !pattern.matches(&path) {
+                continue;
+            }
+            // Paths inside a value replaced earlier no longer lead anywhere.
+            if let Some(value) = path.lookup_mut_in(self) {
+                *value = replacement.clone();
+                count += 1;
+            }
+        }
+        count
+    }
+}
+
+fn array_at<'a>(
+    root: &'a mut Value,
+    path: &KeyPath,
+    create: bool,
+) -> Result<Option<&'a mut Array>, AccessError> {
+    let error = |segment, kind| {
+        AccessError::from(LookupError {
+            path: path.clone(),
+            segment,
+            kind,
+        })
+    };
+    let mut cur = root;
+    for (i, segment) in path.segments.iter().enumerate() {
+        cur = match (cur, segment) {
+            (Value::Table(table), PathSegment::Key(key)) => {
+                if create && !table.contains_key(key) {
+                    let empty = if i + 1 == path.len() {
+                        Value::Array(Array::new())
+                    } else {
+                        Value::Table(Table::new())
+                    };
+                    table.insert(key.clone(), empty);
+                }
+                match table.get_mut(key) {
+                    Some(value) => value,
+                    None => return Ok(None),
+                }
+            }
+            (Value::Array(array), PathSegment::Index(index)) => match array.get_mut(*index) {
+                Some(value) => value,
+                None if create => return Err(error(i, LookupErrorKind::Missing)),
+                None => return Ok(None),
+            },
+            (value, _) => return Err(error(i, LookupErrorKind::WrongType(value.type_str()))),
+        };
+    }
+    match cur {
+        Value::Array(array) => Ok(Some(array)),
+        value => Err(AccessError::wrong_type("array", value).with_path(path)),
+    }
+}
+
+fn collect_paths(table: &Table, path: &mut KeyPath, all: bool, ret: &mut Vec<KeyPath>) {
+    for (key, value) in table {
+        path.push_key(key.clone());
+        collect_value_paths(value, path, all, ret);
+        path.pop();
+    }
+}
+
+fn collect_value_paths(value: &Value, path: &mut KeyPath, all: bool, ret: &mut Vec<KeyPath>) {
+    match value {
+        Value::Table(table) if !table.is_empty() => {
+            if all {
+                ret.push(path.clone());
+            }
+            collect_paths(table, path, all, ret);
+        }
+        Value::Array(array) if array.iter().any(|v| v.is_table()) => {
+            if all {
+                ret.push(path.clone());
+            }
+            for (i, value) in array.iter().enumerate() {
+                path.push_index(i);
+                collect_value_paths(value, path, all, ret);
+                path.pop();
+            }
+        }
+        _ => ret.push(path.clone()),
+    }
+}
+
+fn collect_every_path(value: &Value, path: &mut KeyPath, ret: &mut Vec<KeyPath>) {
+    ret.push(path.clone());
+    match value {
+        Value::Table(table) => {
+            for (key, value) in table {
+                path.push_key(key.clone());
+                collect_every_path(value, path, ret);
+                path.pop();
+            }
+        }
+        Value::Array(array) => {
+            for (i, value) in array.iter().enumerate() {
+                path.push_index(i);
+                collect_every_path(value, path, ret);
+                path.pop();
+            }
+        }
+        _ => {}
+    }
+}
+
+impl LookupError {
+    pub(crate) fn new(path: KeyPath, segment: usize, kind: LookupErrorKind) -> LookupError {
+        LookupError {
+            path,
+            segment,
+            kind,
+        }
+    }
+
+    /// Returns the full path that was looked up.
+    pub fn path(&self) -> &KeyPath {
+        &self.path
+    }
+
+    /// Returns the index of the segment of the path at which the lookup
+    /// failed.
+    pub fn segment(&self) -> usize {
+        self.segment
+    }
+
+    /// Returns the reason the lookup failed.
+    pub fn kind(&self) -> LookupErrorKind {
+        self.kind
+    }
+}
+
+impl AccessError {
+    pub(crate) fn wrong_type(expected: &'static str, value: &Value) -> AccessError {
+        AccessError::new(AccessErrorKind::WrongType, expected, value)
+    }
+
+    pub(crate) fn out_of_range(expected: &'static str, value: &Value) -> AccessError {
+        AccessError::new(AccessErrorKind::OutOfRange, expected, value)
+    }
+
+    fn new(kind: AccessErrorKind, expected: &'static str, value: &Value) -> AccessError {
+        let text = match value {
+            Value::Array(..) | Value::Table(..) => None,
+            _ => Some(value.display_truncated(Limits::default()).to_string()),
+        };
+        AccessError {
+            inner: Box::new(AccessErrorInner {
+                path: None,
+                kind,
+                expected,
+                found: Some(value.kind()),
+                value: text,
+                lookup: None,
+            }),
+        }
+    }
+
+    /// Returns this error with `path` as the path of the value, for an
+    /// error from an accessor called on a value that was looked up.
+    pub fn with_path<P: Into<KeyPath>>(mut self, path: P) -> AccessError {
+        self.inner.path = Some(path.into());
+        self
+    }
+
+    /// Returns the path of the value, if known.
+    pub fn path(&self) -> Option<&KeyPath> {
+        self.inner.path.as_ref()
+    }
+
+    /// Returns the reason the accessor failed.
+    pub fn kind(&self) -> AccessErrorKind {
+        self.inner.kind
+    }
+
+    /// Returns the name of the type wanted, such as `integer` or `u16`.
+    ///
+    /// This is empty when the path did not lead to a value.
+    pub fn expected(&self) -> &str {
+        self.inner.expected
+    }
+
+    /// Returns the kind of the value found, or `None` if the path did not
+    /// lead to a value.
+    pub fn found(&self) -> Option<ValueKind> {
+        self.inner.found
+    }
+}
+
+impl From<LookupError> for AccessError {
+    fn from(err: LookupError) -> AccessError {
+        AccessError {
+            inner: Box::new(AccessErrorInner {
+                path: Some(err.path.clone()),
+                kind: AccessErrorKind::Lookup(err.kind),
+                expected: "",
+                found: None,
+                value: None,
+                lookup: Some(err),
+            }),
+        }
+    }
+}
+
+impl fmt::Display for AccessError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let inner = &*self.inner;
+        if let Some(ref lookup) = inner.lookup {
+            return lookup.fmt(f);
+        }
+        let found = inner.found.map_or("nothing", ValueKind::type_str);
+        match inner.kind {
+            AccessErrorKind::OutOfRange => {
+                write!(f, "{}", found)?;
+                if let Some(ref value) = inner.value {
+                    write!(f, " `{}`", value)?;
+                }
+                if let Some(ref path) = inner.path {
+                    write!(f, " at `{}`", path)?;
+                }
+                write!(f, " is out of range for {}", inner.expected)
+            }
+            _ => {
+                write!(f, "expected {}", inner.expected)?;
+                if let Some(ref path) = inner.path {
+                    write!(f, " at `{}`", path)?;
+                }
+                write!(f, ", found {}", found)?;
+                if let Some(ref value) = inner.value {
+                    write!(f, " `{}`", value)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl error::Error for AccessError {}
+
+impl fmt::Display for LookupError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let segment = match self.path.segments.get(self.segment) {
+            Some(segment) => segment,
+            None => return f.write_str("an empty key path refers to no value"),
+        };
+        let parent = KeyPath::from(self.path.segments[..self.segment].to_vec());
+        let found_at = KeyPath::from(self.path.segments[..=self.segment].to_vec());
+        match (self.kind, segment) {
+            (LookupErrorKind::Missing, PathSegment::Key(..)) => {
+                write!(f, "missing key `{}`", found_at)?
+            }
+            (LookupErrorKind::Missing, PathSegment::Index(..)) => {
+                write!(f, "missing index `{}`", found_at)?
+            }
+            (LookupErrorKind::WrongType(found), segment) => {
+                let expected = match segment {
+                    PathSegment::Key(..) => "a table",
+                    PathSegment::Index(..) => "an array",
+                };
+                if parent.is_empty() {
+                    write!(f, "expected {} at the root, found {}", expected, found)?
+                } else {
+                    write!(f, "expected {} at `{}`, found {}", expected, parent, found)?
+                }
+            }
+        }
+        if self.segment + 1 < self.path.len() {
+            write!(f, " when looking up `{}`", self.path)?;
+        }
+        Ok(())
+    }
+}
+
+impl error::Error for LookupError {}
+
+impl From<Vec<PathSegment>> for KeyPath {
+    fn from(segments: Vec<PathSegment>) -> KeyPath {
+        KeyPath { segments }
+    }
+}
+
+impl<'a> From<&'a KeyPath> for KeyPath {
+    fn from(path: &'a KeyPath) -> KeyPath {
+        path.clone()
+    }
+}
+
+/// Converts a string into a path, falling back to treating the whole string
+/// as a single key if it is not valid path syntax. Use `str::parse` to detect
+/// malformed paths instead.
+impl<'a> From<&'a str> for KeyPath {
+    fn from(s: &'a str) -> KeyPath {
+        s.parse().unwrap_or_else(|_| {
+            let mut path = KeyPath::new();
+            path.push_key(s);
+            path
+        })
+    }
+}
+
+impl<'a> From<&'a String> for KeyPath {
+    fn from(s: &'a String) -> KeyPath {
+        KeyPath::from(&s[..])
+    }
+}
+
+impl fmt::Display for PathSegment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            PathSegment::Key(ref key) => display::write_key(f, key),
+            PathSegment::Index(i) => write!(f, "[{}]", i),
+        }
+    }
+}
+
+impl From<KeyPath> for KeyPathPattern {
+    fn from(path: KeyPath) -> KeyPathPattern {
+        KeyPathPattern {
+            segments: path
+                .segments
+                .into_iter()
+                .map(PatternSegment::Exact)
+                .collect(),
+        }
+    }
+}
+
+impl fmt::Display for KeyPathPattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, segment) in self.segments.iter().enumerate() {
+            match *segment {
+                PatternSegment::Exact(PathSegment::Index(_)) | PatternSegment::AnyIndex => {}
+                _ if i > 0 => f.write_str(".")?,
+                _ => {}
+            }
+            match *segment {
+                PatternSegment::Exact(ref segment) => segment.fmt(f)?,
+                PatternSegment::AnyKey => f.write_str("*")?,
+                PatternSegment::AnyIndex => f.write_str("[*]")?,
+                PatternSegment::AnySegments => f.write_str("**")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for KeyPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, segment) in self.segments.iter().enumerate() {
+            if i > 0 {
+                if let PathSegment::Key(..) = *segment {
+                    f.write_str(".")?;
+                }
+            }
+            segment.fmt(f)?;
+        }
+        Ok(())
+    }
+}
+
+pub(crate) fn is_bare_key(key: &str) -> bool {
+    !key.is_empty()
+        && key
+            .chars()
+            .all(|c| matches!(c, 'a'..='z' | 'A'..='Z' | '0'..='9' | '-' | '_'))
+}
+
+impl FromStr for KeyPath {
+    type Err = KeyPathParseError;
+
+    fn from_str(s: &str) -> Result<KeyPath, KeyPathParseError> {
+        let segments = parse_segments(s, false)?
+            .into_iter()
+            .filter_map(|segment| match segment {
+                PatternSegment::Exact(segment) => Some(segment),
+                PatternSegment::AnyKey | PatternSegment::AnyIndex | PatternSegment::AnySegments => {
+                    None
+                }
+            })
+            .collect::<Vec<_>>();
+        Ok(KeyPath::from(segments))
+    }
+}
+
+impl FromStr for KeyPathPattern {
+    type Err = KeyPathParseError;
+
+    fn from_str(s: &str) -> Result<KeyPathPattern, KeyPathParseError> {
+        let mut segments = parse_segments(s, true)?;
+        // Consecutive `**` match nothing more than one does.
+        segments.dedup_by(|a, b| {
+            *a == PatternSegment::AnySegments && *b == PatternSegment::AnySegments
+        });
+        Ok(KeyPathPattern { segments })
+    }
+}
+
+/// A segment of a path that may be a wildcard.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum PatternSegment {
+    Exact(PathSegment),
+    /// A `*` key.
+    AnyKey,
+    /// A `[*]` index.
+    AnyIndex,
+    /// A `**` key, matching any number of segments.
+    AnySegments,
+}
+
+/// Parses a path, in which a `*` or `**` key or `[*]` index is a wildcard if
+/// `wildcards` is set.
+fn parse_segments(s: &str, wildcards: bool) -> Result<Vec<PatternSegment>, KeyPathParseError> {
+    let err = |at, message| Err(KeyPathParseError { at, message });
+    let mut path = Vec::new();
+    let mut chars = s.char_indices().peekable();
+    let mut expect_key = true;
+
+    if s.trim().is_empty() {
+        return Ok(path);
+    }
+
+    loop {
+        while let Some(&(_, ' ')) | Some(&(_, '\t')) = chars.peek() {
+            chars.next();
+        }
+        let (at, ch) = match chars.next() {
+            Some(pair) => pair,
+            None if expect_key => return err(s.len(), "expected a key"),
+            None => return Ok(path),
+        };
+        match ch {
+            '[' if !path.is_empty() || !expect_key => {
+                let start = at + 1;
+                let mut end = None;
+                for (i, c) in chars.by_ref() {
+                    if c == ']' {
+                        end = Some(i);
+                        break;
+                    }
+                }
+                let end = match end {
+                    Some(end) => end,
+                    None => return err(at, "unterminated array index"),
+                };
+                match s[start..end].trim() {
+                    "*" if wildcards => path.push(PatternSegment::AnyIndex),
+                    index => match index.parse() {
+                        Ok(i) => path.push(PatternSegment::Exact(PathSegment::Index(i))),
+                        Err(_) => return err(start, "invalid array index"),
+                    },
+                }
+                expect_key = false;
+            }
+            '.' if !expect_key => expect_key = true,
+            '"' | '\'' if expect_key => {
+                let mut key = String::new();
+                let mut closed = false;
+                while let Some((i, c)) = chars.next() {
+                    match c {
+                        c if c == ch => {
+                            closed = true;
+                            break;
+                        }
+                        '\\' if ch == '"' => match chars.next() {
+                            Some((_, '"')) => key.push('"'),
+                            Some((_, '\\')) => key.push('\\'),
+                            Some((_, 'n')) => key.push('\n'),
+                            Some((_, 't')) => key.push('\t'),
+                            _ => return err(i, "invalid escape in quoted key"),
+                        },
+                        c => key.push(c),
+                    }
+                }
+                if !closed {
+                    return err(at, "unterminated quoted key");
+                }
+                path.push(PatternSegment::Exact(PathSegment::Key(key)));
+                expect_key = false;
+            }
+            '*' if expect_key && wildcards => {
+                if let Some(&(_, '*')) = chars.peek() {
+                    chars.next();
+                    path.push(PatternSegment::AnySegments);
+                } else {
+                    path.push(PatternSegment::AnyKey);
+                }
+                expect_key = false;
+            }
+            c if expect_key && is_bare_key(c.encode_utf8(&mut [0; 4])) => {
+                let mut key = c.to_string();
+                while let Some(&(_, c)) = chars.peek() {
+                    if !is_bare_key(c.encode_utf8(&mut [0; 4])) {
+                        break;
+                    }
+                    key.push(c);
+                    chars.next();
+                }
+                path.push(PatternSegment::Exact(PathSegment::Key(key)));
+                expect_key = false;
+            }
+            _ if expect_key => return err(at, "expected a key"),
+            _ => return err(at, "expected `.` or `[`"),
+        }
+    }
+}
+
+impl fmt::Display for KeyPathParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "invalid key path: {} at offset {}",
+            self.message, self.at
+        )
+    }
+}
+
+impl error::Error for KeyPathParseError {}
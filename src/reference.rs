@@ -0,0 +1,311 @@
+//! Opt-in references between values of a document.
+//!
+//! Plain TOML has no way for one value to reuse another. This module adds a
+//! small convention on top of it: a string value of the form `"@path.to.key"`
+//! is a reference to the value at that key path of the same document. Nothing
+//! in the parser knows about references; they are only replaced when
+//! [`resolve`] is called explicitly.
+//!
+//! ```rust
+//! let config: toml::value::Table = toml::from_str(r#"
+//!     [paths]
+//!     base = "/var/lib/app"
+//!
+//!     [log]
+//!     dir = "@paths.base"
+//!     email = "@@admin"
+//! "#).unwrap();
+//!
+//! let config = toml::reference::resolve(&config).unwrap();
+//! assert_eq!(config["log"]["dir"].as_str(), Some("/var/lib/app"));
+//! assert_eq!(config["log"]["email"].as_str(), Some("@admin"));
+//! ```
+//!
+//! A string that should start with a literal `@` is written with `@@`.
+//!
+//! [`interpolate`] is the same pass for references written inside strings,
+//! as `${path.to.key}`:
+//!
+//! ```rust
+//! let config: toml::value::Table = toml::from_str(r#"
+//!     [paths]
+//!     base = "/var/lib/app"
+//!     logs = "${paths.base}/logs"
+//!
+//!     [server]
+//!     port = 8080
+//!     listen = "${server.port}"
+//! "#).unwrap();
+//!
+//! let config = toml::reference::interpolate(&config).unwrap();
+//! assert_eq!(config["paths"]["logs"].as_str(), Some("/var/lib/app/logs"));
+//! assert_eq!(config["server"]["listen"].as_integer(), Some(8080));
+//! ```
+//!
+//! [`resolve`]: fn.resolve.html
+//! [`interpolate`]: fn.interpolate.html
+
+use std::error;
+use std::fmt;
+
+use crate::path::KeyPath;
+use crate::value::{Table, Value};
+
+/// Returns a copy of `table` with every reference replaced by the value it
+/// refers to.
+///
+/// References are resolved transitively, so a reference may point at a value
+/// that is itself a reference, or at a table containing references. A chain
+/// of references that loops back on itself is reported as an error rather
+/// than followed forever.
+pub fn resolve(table: &Table) -> Result<Table, ReferenceError> {
+    let mut resolver = Resolver {
+        root: table,
+        syntax: Syntax::Whole,
+        stack: Vec::new(),
+    };
+    resolver.table(table, &mut KeyPath::new())
+}
+
+/// Returns a copy of `table` with every `${path.to.key}` in its strings
+/// replaced by the value at that key path.
+///
+/// A string holding nothing but one reference takes the value it refers to
+/// as it is, so it may become an integer or a table. Otherwise the text of
+/// the value is substituted, which must not be an array or a table. As with
+/// [`resolve`], references are followed transitively and a cycle is an
+/// error. A literal `${` is written `$${`.
+///
+/// ```rust
+/// let config: toml::value::Table =
+///     toml::from_str("a = '${b}'\nb = 'x${a}'\nprice = '$$${'").unwrap();
+/// let err = toml::reference::interpolate(&config).unwrap_err();
+/// assert_eq!(err.to_string(), "reference cycle in `a` -> `b` -> `a`");
+/// ```
+///
+/// [`resolve`]: fn.resolve.html
+pub fn interpolate(table: &Table) -> Result<Table, ReferenceError> {
+    let mut resolver = Resolver {
+        root: table,
+        syntax: Syntax::Embedded,
+        stack: Vec::new(),
+    };
+    resolver.table(table, &mut KeyPath::new())
+}
+
+/// Errors that can occur when resolving references.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReferenceError {
+    kind: ReferenceErrorKind,
+    chain: Vec<KeyPath>,
+    syntax: Syntax,
+}
+
+/// How references are written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Syntax {
+    /// A whole string, `"@path"`, as read by `resolve`.
+    Whole,
+    /// Inside a string, `"${path}"`, as read by `interpolate`.
+    Embedded,
+}
+
+/// The reason a reference could not be resolved.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReferenceErrorKind {
+    /// The reference points at a key that does not exist.
+    Unresolved(String),
+    /// The text after `@` is not a valid key path.
+    InvalidPath(String),
+    /// Following the references leads back to a value already being
+    /// resolved.
+    Cycle,
+    /// A `${` has no closing `}`; this holds the text after it.
+    Unterminated(String),
+    /// A reference inside a longer string points at an array or a table,
+    /// which has no text to substitute.
+    NotText(String),
+}
+
+impl ReferenceError {
+    /// Returns the reason the reference could not be resolved.
+    pub fn kind(&self) -> &ReferenceErrorKind {
+        &self.kind
+    }
+
+    /// Returns the chain of references that were being followed, starting
+    /// with the first value containing a reference. For a cycle the last
+    /// element is the value the chain looped back to.
+    pub fn chain(&self) -> &[KeyPath] {
+        &self.chain
+    }
+}
+
+struct Resolver<'a> {
+    root: &'a Table,
+    syntax: Syntax,
+    stack: Vec<KeyPath>,
+}
+
+impl<'a> Resolver<'a> {
+    fn table(&mut self, table: &Table, path: &mut KeyPath) -> Result<Table, ReferenceError> {
+        let mut out = Table::with_capacity(table.len());
+        for (key, value) in table {
+            path.push_key(key.clone());
+            let value = self.value(value, path);
+            path.pop();
+            out.insert(key.clone(), value?);
+        }
+        Ok(out)
+    }
+
+    fn value(&mut self, value: &Value, path: &mut KeyPath) -> Result<Value, ReferenceError> {
+        match *value {
+            Value::String(ref s) if self.syntax == Syntax::Embedded => self.substitute(s, path),
+            Value::String(ref s) => match reference(s) {
+                Reference::Literal(s) => Ok(Value::String(s.to_string())),
+                Reference::Target(target) => self.follow(target, path),
+                Reference::None => Ok(value.clone()),
+            },
+            Value::Array(ref array) => {
+                let mut out = Vec::with_capacity(array.len());
+                for (i, value) in array.iter().enumerate() {
+                    path.push_index(i);
+                    let value = self.value(value, path);
+                    path.pop();
+                    out.push(value?);
+                }
+                Ok(Value::Array(out))
+            }
+            Value::Table(ref table) => self.table(table, path).map(Value::Table),
+            ref other => Ok(other.clone()),
+        }
+    }
+
+    fn follow(&mut self, target: &str, path: &KeyPath) -> Result<Value, ReferenceError> {
+        if self.stack.contains(path) {
+            return Err(self.error(path, ReferenceErrorKind::Cycle));
+        }
+        let mut target_path = match target.parse::<KeyPath>() {
+            Ok(p) => p,
+            Err(_) => {
+                return Err(self.error(path, ReferenceErrorKind::InvalidPath(target.to_string())))
+            }
+        };
+        let raw = match target_path.lookup_in(self.root) {
+            Some(raw) => raw,
+            None => {
+                return Err(self.error(path, ReferenceErrorKind::Unresolved(target.to_string())))
+            }
+        };
+        self.stack.push(path.clone());
+        let ret = self.value(raw, &mut target_path);
+        self.stack.pop();
+        ret
+    }
+
+    /// Replaces the `${path}` references in `s`, the string at `path`.
+    fn substitute(&mut self, s: &str, path: &KeyPath) -> Result<Value, ReferenceError> {
+        let mut out = String::with_capacity(s.len());
+        let mut rest = s;
+        while let Some(i) = rest.find('$') {
+            out.push_str(&rest[..i]);
+            rest = &rest[i..];
+            if let Some(after) = rest.strip_prefix("$${") {
+                out.push_str("${");
+                rest = after;
+                continue;
+            }
+            let after = match rest.strip_prefix("${") {
+                Some(after) => after,
+                None => {
+                    out.push('$');
+                    rest = &rest[1..];
+                    continue;
+                }
+            };
+            let end = match after.find('}') {
+                Some(end) => end,
+                None => {
+                    let kind = ReferenceErrorKind::Unterminated(after.to_string());
+                    return Err(self.error(path, kind));
+                }
+            };
+            let target = &after[..end];
+            if target.len() + 3 == s.len() {
+                return self.follow(target.trim(), path);
+            }
+            match self.follow(target.trim(), path)? {
+                Value::String(value) => out.push_str(&value),
+                Value::Array(_) | Value::Table(_) => {
+                    let kind = ReferenceErrorKind::NotText(target.to_string());
+                    return Err(self.error(path, kind));
+                }
+                value => out.push_str(&value.to_string()),
+            }
+            rest = &after[end + 1..];
+        }
+        out.push_str(rest);
+        Ok(Value::String(out))
+    }
+
+    fn error(&self, path: &KeyPath, kind: ReferenceErrorKind) -> ReferenceError {
+        let mut chain = self.stack.clone();
+        chain.push(path.clone());
+        ReferenceError {
+            kind,
+            chain,
+            syntax: self.syntax,
+        }
+    }
+}
+
+enum Reference<'s> {
+    None,
+    Literal(&'s str),
+    Target(&'s str),
+}
+
+fn reference(s: &str) -> Reference<'_> {
+    if s.starts_with("@@") {
+        Reference::Literal(&s[1..])
+    } else if let Some(target) = s.strip_prefix('@') {
+        Reference::Target(target)
+    } else {
+        Reference::None
+    }
+}
+
+impl fmt::Display for ReferenceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let written = |target: &str| match self.syntax {
+            Syntax::Whole => format!("@{}", target),
+            Syntax::Embedded => format!("${{{}}}", target),
+        };
+        match self.kind {
+            ReferenceErrorKind::Unresolved(ref target) => {
+                write!(f, "unresolved reference `{}`", written(target))?
+            }
+            ReferenceErrorKind::InvalidPath(ref target) => {
+                write!(f, "invalid reference `{}`", written(target))?
+            }
+            ReferenceErrorKind::Cycle => f.write_str("reference cycle")?,
+            ReferenceErrorKind::Unterminated(ref rest) => {
+                write!(f, "unterminated reference `${{{}`", rest)?
+            }
+            ReferenceErrorKind::NotText(ref target) => {
+                write!(f, "reference to an array or table `{}`", written(target))?
+            }
+        }
+        f.write_str(" in ")?;
+        for (i, path) in self.chain.iter().enumerate() {
+            if i > 0 {
+                f.write_str(" -> ")?;
+            }
+            write!(f, "`{}`", path)?;
+        }
+        Ok(())
+    }
+}
+
+impl error::Error for ReferenceError {}
@@ -0,0 +1,1051 @@
+//! Declarative checks of the keys a configuration may contain.
+//!
+//! A [`Schema`] lists key paths along with whether each is required, optional
+//! or forbidden, and what type its value must have. Forbidden keys carry a
+//! hint telling users what to write instead, which makes them a good fit for
+//! settings that were renamed or removed. A key can also be required only
+//! when another key has a given value:
+//!
+//! ```rust
+//! use toml::schema::Schema;
+//! use toml::value::{Table, ValueKind};
+//! use toml::Value;
+//!
+//! let mut schema = Schema::new();
+//! schema
+//!     .required("name", ValueKind::String)
+//!     .optional("tls.enabled", ValueKind::Boolean)
+//!     .optional("tls.cert", ValueKind::String)
+//!     .forbidden("ssl", "the `ssl` table was renamed to `tls`")
+//!     .required_when("tls.enabled", Value::from(true), "tls.cert");
+//!
+//! let config: Table = toml::from_str("name = 'app'\n[tls]\nenabled = true").unwrap();
+//! let errors = schema.validate(&config).unwrap_err();
+//! assert_eq!(
+//!     errors[0].to_string(),
+//!     "missing key `tls.cert`, required when `tls.enabled` is true"
+//! );
+//! ```
+//!
+//! Rules that relate several values, such as a minimum that must not exceed a
+//! maximum, are added as checks, which run after the key rules and report
+//! their problems along with them:
+//!
+//! ```rust
+//! use toml::schema::Schema;
+//! use toml::value::{Table, ValueKind};
+//!
+//! let mut schema = Schema::new();
+//! schema
+//!     .required("limits.min", ValueKind::Integer)
+//!     .required("limits.max", ValueKind::Integer)
+//!     .check(["limits.min", "limits.max"], "must not exceed `limits.max`", |v| {
+//!         v[0].as_integer() <= v[1].as_integer()
+//!     });
+//!
+//! let config: Table = toml::from_str("[limits]\nmin = 10\nmax = 5").unwrap();
+//! let errors = schema.validate(&config).unwrap_err();
+//! assert_eq!(
+//!     errors[0].to_string(),
+//!     "invalid value for `limits.min`: must not exceed `limits.max`"
+//! );
+//! ```
+//!
+//! Rules can also be given for every key matching a [`KeyPathPattern`], for
+//! settings that repeat, such as the port of each server:
+//!
+//! ```rust
+//! use toml::schema::Schema;
+//! use toml::value::{Table, ValueKind};
+//!
+//! let mut schema = Schema::new();
+//! schema
+//!     .optional_matching("servers.*.port".parse().unwrap(), ValueKind::Integer)
+//!     .forbidden_matching("**.password".parse().unwrap(), "use `password-file` instead");
+//!
+//! let config: Table = toml::from_str(
+//!     "[servers.a]\nport = 80\n[servers.b]\nport = '81'\n[servers.b.auth]\npassword = 'x'\n",
+//! )
+//! .unwrap();
+//! let errors = schema.validate(&config).unwrap_err();
+//! assert_eq!(
+//!     errors[0].to_string(),
+//!     "invalid type for `servers.b.port`: expected integer, found string"
+//! );
+//! assert_eq!(
+//!     errors[1].to_string(),
+//!     "key `servers.b.auth.password` is not allowed: use `password-file` instead"
+//! );
+//! ```
+//!
+//! Values can be held to constraints on top of their type: a range for
+//! numbers, a length for strings and arrays, items that must all differ, or
+//! a pattern strings must match. Constraints combine with `and` and `or`:
+//!
+//! ```rust
+//! use toml::document::Document;
+//! use toml::schema::{Constraint, Schema};
+//!
+//! let mut schema = Schema::new();
+//! schema
+//!     .constrain("name", Constraint::pattern("^[a-z][a-z0-9-]*$").unwrap().and(Constraint::length(..=16)))
+//!     .constrain("ports", Constraint::items(1..).and(Constraint::unique_items()))
+//!     .constrain_matching("ports[*]".parse().unwrap(), Constraint::range(1..=65535));
+//!
+//! let src = "name = 'My App'\nports = [80, 80, 0]\n";
+//! let doc: Document = src.parse().unwrap();
+//! let errors = schema.validate_document(&doc).unwrap_err();
+//! let messages = errors.iter().map(|e| e.to_string()).collect::<Vec<_>>();
+//! assert_eq!(messages, [
+//!     "invalid value for `name`: must match `^[a-z][a-z0-9-]*$`",
+//!     "invalid value for `ports[1]`: must not repeat `ports[0]`",
+//!     "invalid value for `ports[2]`: must be at least 1 and at most 65535",
+//! ]);
+//! let (start, end) = errors[2].span().unwrap();
+//! assert_eq!(&src[start..end], "0");
+//! ```
+//!
+//! Validating a [`Document`] instead of a table also reports where in the
+//! source each problem is.
+//!
+//! [`Schema`]: struct.Schema.html
+//! [`Document`]: ../document/struct.Document.html
+//! [`KeyPathPattern`]: ../path/struct.KeyPathPattern.html
+
+use std::error;
+use std::fmt;
+use std::ops::{Bound, RangeBounds};
+use std::sync::Arc;
+
+use crate::document::Document;
+use crate::path::{KeyPath, KeyPathPattern};
+use crate::value::{Table, Value, ValueKind};
+
+/// A set of rules for the keys of a table.
+#[derive(Clone, Default)]
+pub struct Schema {
+    keys: Vec<(KeyPath, KeyRule)>,
+    patterns: Vec<(KeyPathPattern, KeyRule)>,
+    conditions: Vec<Condition>,
+    constraints: Vec<(KeyPathPattern, Constraint)>,
+    checks: Vec<Check>,
+}
+
+type Check = Arc<dyn Fn(&Table) -> Vec<(KeyPath, String)> + Send + Sync>;
+
+/// Whether a key must, may or must not be present.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Presence {
+    /// The key must be present.
+    Required,
+    /// The key may be present.
+    Optional,
+    /// The key must not be present.
+    Forbidden,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct KeyRule {
+    presence: Presence,
+    kind: Option<ValueKind>,
+    hint: String,
+}
+
+/// A condition on a value beyond its type, added to a schema with
+/// `Schema::constrain`.
+///
+/// A constraint only applies to values of the types it is about: a range
+/// to numbers, a length to strings, an item count to arrays. Values of
+/// other types meet it, leaving their type to the rules given with
+/// `required` or `optional`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Constraint {
+    kind: ConstraintKind,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum ConstraintKind {
+    Range(Bound<i64>, Bound<i64>),
+    FloatRange(Bound<f64>, Bound<f64>),
+    Length(usize, Option<usize>),
+    Items(usize, Option<usize>),
+    UniqueItems,
+    Pattern(Pattern),
+    And(Box<Constraint>, Box<Constraint>),
+    Or(Box<Constraint>, Box<Constraint>),
+}
+
+/// A regular expression of the small subset `Constraint::pattern` supports.
+///
+/// An item repeated with `+` is stored as the item followed by the item
+/// repeated with `*`.
+#[derive(Debug, Clone, PartialEq)]
+struct Pattern {
+    source: String,
+    start: bool,
+    end: bool,
+    items: Vec<(Atom, Repeat)>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Atom {
+    Char(char),
+    Any,
+    Class {
+        negated: bool,
+        ranges: Vec<(char, char)>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Repeat {
+    One,
+    ZeroOrOne,
+    ZeroOrMore,
+}
+
+/// Error returned by `Constraint::pattern` for a pattern it cannot parse.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PatternError {
+    at: usize,
+    message: &'static str,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Condition {
+    path: KeyPath,
+    value: Value,
+    required: KeyPath,
+}
+
+/// A problem found by validating a table against a schema.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SchemaError {
+    path: KeyPath,
+    kind: SchemaErrorKind,
+    span: Option<(usize, usize)>,
+}
+
+/// The reason a table does not match a schema.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SchemaErrorKind {
+    /// A required key is missing.
+    Missing,
+    /// A key is missing that is required because another key has a given
+    /// value.
+    MissingWhen {
+        /// The key whose value makes this one required.
+        path: KeyPath,
+        /// The value that makes this key required.
+        value: Value,
+    },
+    /// A key has a value of the wrong type.
+    WrongType {
+        /// The type the schema requires.
+        expected: ValueKind,
+        /// The type of the value found.
+        found: ValueKind,
+    },
+    /// A forbidden key is present; holds the hint given for it.
+    Forbidden(String),
+    /// A check added with `Schema::check` or `Schema::check_table` failed;
+    /// holds the message it reported.
+    Check(String),
+    /// A value does not meet a constraint added with `Schema::constrain` or
+    /// `Schema::constrain_matching`; holds what the constraint requires.
+    Constraint(String),
+}
+
+impl Schema {
+    /// Creates a schema with no rules, which every table matches.
+    pub fn new() -> Schema {
+        Schema::default()
+    }
+
+    /// Requires `path` to be present with a value of type `kind`.
+    pub fn required<P: Into<KeyPath>>(&mut self, path: P, kind: ValueKind) -> &mut Self {
+        self.insert(path.into(), Presence::Required, Some(kind), String::new())
+    }
+
+    /// Allows `path` to be absent, but requires a value of type `kind` if it
+    /// is present.
+    pub fn optional<P: Into<KeyPath>>(&mut self, path: P, kind: ValueKind) -> &mut Self {
+        self.insert(path.into(), Presence::Optional, Some(kind), String::new())
+    }
+
+    /// Forbids `path`, reporting `hint` if it is present.
+    ///
+    /// The hint should tell users what to do instead, such as which key
+    /// replaced a removed one.
+    pub fn forbidden<P: Into<KeyPath>>(&mut self, path: P, hint: &str) -> &mut Self {
+        self.insert(path.into(), Presence::Forbidden, None, hint.to_string())
+    }
+
+    /// Requires a value of type `kind` for every key matching `pattern`.
+    ///
+    /// Keys are matched against the paths of the table being validated, so
+    /// a pattern can only make keys optional or forbidden, never required.
+    pub fn optional_matching(&mut self, pattern: KeyPathPattern, kind: ValueKind) -> &mut Self {
+        self.patterns.push((
+            pattern,
+            KeyRule {
+                presence: Presence::Optional,
+                kind: Some(kind),
+                hint: String::new(),
+            },
+        ));
+        self
+    }
+
+    /// Forbids every key matching `pattern`, reporting `hint` for each one
+    /// present.
+    pub fn forbidden_matching(&mut self, pattern: KeyPathPattern, hint: &str) -> &mut Self {
+        self.patterns.push((
+            pattern,
+            KeyRule {
+                presence: Presence::Forbidden,
+                kind: None,
+                hint: hint.to_string(),
+            },
+        ));
+        self
+    }
+
+    /// Requires the value at `path`, if present, to meet `constraint`.
+    pub fn constrain<P: Into<KeyPath>>(&mut self, path: P, constraint: Constraint) -> &mut Self {
+        self.constraints
+            .push((KeyPathPattern::from(path.into()), constraint));
+        self
+    }
+
+    /// Requires every value whose path matches `pattern` to meet
+    /// `constraint`.
+    ///
+    /// Unlike the other pattern rules, the elements of every array are
+    /// matched, so `ports[*]` constrains each port of an array of integers.
+    pub fn constrain_matching(
+        &mut self,
+        pattern: KeyPathPattern,
+        constraint: Constraint,
+    ) -> &mut Self {
+        self.constraints.push((pattern, constraint));
+        self
+    }
+
+    /// Requires `required` to be present when the value at `path` equals
+    /// `value`.
+    ///
+    /// The type of `required`, if any, is checked by the rule given for it
+    /// with `required` or `optional`.
+    pub fn required_when<P, Q>(&mut self, path: P, value: Value, required: Q) -> &mut Self
+    where
+        P: Into<KeyPath>,
+        Q: Into<KeyPath>,
+    {
+        self.conditions.push(Condition {
+            path: path.into(),
+            value,
+            required: required.into(),
+        });
+        self
+    }
+
+    /// Adds a check that the values at `paths` are consistent with each
+    /// other, reporting `message` at the first of the paths if `predicate`
+    /// returns `false` for them.
+    ///
+    /// The values are passed in the order of `paths`. The check is skipped
+    /// if any of them is missing, which is left to the key rules to report.
+    pub fn check<I, P, F>(&mut self, paths: I, message: &str, predicate: F) -> &mut Self
+    where
+        I: IntoIterator<Item = P>,
+        P: Into<KeyPath>,
+        F: Fn(&[&Value]) -> bool + Send + Sync + 'static,
+    {
+        let paths = paths.into_iter().map(Into::into).collect::<Vec<KeyPath>>();
+        let message = message.to_string();
+        self.checks.push(Arc::new(move |table| {
+            let values = paths
+                .iter()
+                .map(|path| path.lookup_in(table))
+                .collect::<Option<Vec<_>>>();
+            match values {
+                Some(ref values) if !values.is_empty() && !predicate(values) => {
+                    vec![(paths[0].clone(), message.clone())]
+                }
+                _ => Vec::new(),
+            }
+        }));
+        self
+    }
+
+    /// Adds a check over the whole table, which returns the path and a
+    /// message for each problem it finds.
+    pub fn check_table<F>(&mut self, check: F) -> &mut Self
+    where
+        F: Fn(&Table) -> Vec<(KeyPath, String)> + Send + Sync + 'static,
+    {
+        self.checks.push(Arc::new(check));
+        self
+    }
+
+    /// Returns whether `path` is required, optional or forbidden, or `None`
+    /// if the schema has no rule for it.
+    pub fn presence<P: Into<KeyPath>>(&self, path: P) -> Option<Presence> {
+        let path = path.into();
+        self.keys
+            .iter()
+            .find(|(p, _)| *p == path)
+            .map(|(_, rule)| rule.presence)
+    }
+
+    /// Returns the keys with a rule, in the order they were first given one,
+    /// along with their presence and type.
+    pub(crate) fn keys(&self) -> impl Iterator<Item = (&KeyPath, Presence, Option<ValueKind>)> {
+        self.keys
+            .iter()
+            .map(|(path, rule)| (path, rule.presence, rule.kind))
+    }
+
+    fn insert(
+        &mut self,
+        path: KeyPath,
+        presence: Presence,
+        kind: Option<ValueKind>,
+        hint: String,
+    ) -> &mut Self {
+        let rule = KeyRule {
+            presence,
+            kind,
+            hint,
+        };
+        match self.keys.iter_mut().find(|(p, _)| *p == path) {
+            Some(entry) => entry.1 = rule,
+            None => self.keys.push((path, rule)),
+        }
+        self
+    }
+
+    /// Checks `table` against this schema, returning every problem found.
+    pub fn validate(&self, table: &Table) -> Result<(), Vec<SchemaError>> {
+        self.run(table, |_| None)
+    }
+
+    /// Checks the table of `doc` against this schema, returning every
+    /// problem found along with where it is in the source text.
+    ///
+    /// The span of a missing key is that of the table it is missing from, if
+    /// that table has one.
+    pub fn validate_document(&self, doc: &Document) -> Result<(), Vec<SchemaError>> {
+        self.run(doc.as_table(), |path| doc.span(path))
+    }
+
+    fn run<F>(&self, table: &Table, span: F) -> Result<(), Vec<SchemaError>>
+    where
+        F: Fn(&KeyPath) -> Option<(usize, usize)>,
+    {
+        let missing_span = |path: &KeyPath| path.parent().and_then(|parent| span(&parent));
+        let mut errors = Vec::new();
+        let matched = if self.patterns.is_empty() {
+            Vec::new()
+        } else {
+            let paths = table.all_paths();
+            self.patterns
+                .iter()
+                .flat_map(|(pattern, rule)| {
+                    paths
+                        .iter()
+                        .filter(move |path| pattern.matches(path))
+                        .map(move |path| (path.clone(), rule))
+                })
+                .collect()
+        };
+        let rules = self.keys.iter().map(|(path, rule)| (path.clone(), rule));
+        for (ref path, rule) in rules.chain(matched) {
+            let value = path.lookup_in(table);
+            let kind = match (rule.presence, value) {
+                (Presence::Required, None) => Some(SchemaErrorKind::Missing),
+                (Presence::Forbidden, Some(_)) => {
+                    Some(SchemaErrorKind::Forbidden(rule.hint.clone()))
+                }
+                (_, Some(value)) => match rule.kind {
+                    Some(expected) if value.kind() != expected => {
+                        Some(SchemaErrorKind::WrongType {
+                            expected,
+                            found: value.kind(),
+                        })
+                    }
+                    _ => None,
+                },
+                (_, None) => None,
+            };
+            if let Some(kind) = kind {
+                let span = match kind {
+                    SchemaErrorKind::Missing => missing_span(path),
+                    _ => span(path),
+                };
+                errors.push(SchemaError {
+                    path: path.clone(),
+                    kind,
+                    span,
+                });
+            }
+        }
+        for condition in &self.conditions {
+            if condition.path.lookup_in(table) == Some(&condition.value)
+                && condition.required.lookup_in(table).is_none()
+            {
+                errors.push(SchemaError {
+                    path: condition.required.clone(),
+                    kind: SchemaErrorKind::MissingWhen {
+                        path: condition.path.clone(),
+                        value: condition.value.clone(),
+                    },
+                    span: span(&condition.path),
+                });
+            }
+        }
+        if !self.constraints.is_empty() {
+            let mut values = Vec::new();
+            collect_values(table, &mut KeyPath::new(), &mut values);
+            for (pattern, constraint) in &self.constraints {
+                for (path, value) in &values {
+                    if !pattern.matches(path) {
+                        continue;
+                    }
+                    let mut failures = Vec::new();
+                    constraint.evaluate(path, value, &mut failures);
+                    for (path, message) in failures {
+                        let span = span(&path);
+                        errors.push(SchemaError {
+                            path,
+                            kind: SchemaErrorKind::Constraint(message),
+                            span,
+                        });
+                    }
+                }
+            }
+        }
+        for check in &self.checks {
+            for (path, message) in check(table) {
+                let span = span(&path);
+                errors.push(SchemaError {
+                    path,
+                    kind: SchemaErrorKind::Check(message),
+                    span,
+                });
+            }
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+/// Collects every value of `table` with its path, tables and arrays before
+/// what they hold, and the elements of every array.
+fn collect_values<'a>(table: &'a Table, path: &mut KeyPath, out: &mut Vec<(KeyPath, &'a Value)>) {
+    for (key, value) in table {
+        path.push_key(key.clone());
+        collect_value(value, path, out);
+        path.pop();
+    }
+}
+
+fn collect_value<'a>(value: &'a Value, path: &mut KeyPath, out: &mut Vec<(KeyPath, &'a Value)>) {
+    out.push((path.clone(), value));
+    match value {
+        Value::Table(table) => collect_values(table, path, out),
+        Value::Array(array) => {
+            for (i, value) in array.iter().enumerate() {
+                path.push_index(i);
+                collect_value(value, path, out);
+                path.pop();
+            }
+        }
+        _ => {}
+    }
+}
+
+impl Constraint {
+    /// Requires integers to be within `range`, such as `1..=65535`.
+    pub fn range<R: RangeBounds<i64>>(range: R) -> Constraint {
+        Constraint {
+            kind: ConstraintKind::Range(range.start_bound().cloned(), range.end_bound().cloned()),
+        }
+    }
+
+    /// Requires floats and integers to be within `range`, such as
+    /// `0.0..=1.0`.
+    pub fn float_range<R: RangeBounds<f64>>(range: R) -> Constraint {
+        Constraint {
+            kind: ConstraintKind::FloatRange(
+                range.start_bound().cloned(),
+                range.end_bound().cloned(),
+            ),
+        }
+    }
+
+    /// Requires strings to have a number of characters within `range`, such
+    /// as `1..=64`.
+    pub fn length<R: RangeBounds<usize>>(range: R) -> Constraint {
+        let (min, max) = count_bounds(range);
+        Constraint {
+            kind: ConstraintKind::Length(min, max),
+        }
+    }
+
+    /// Requires arrays to have a number of items within `range`, such as
+    /// `1..` for arrays that must not be empty.
+    pub fn items<R: RangeBounds<usize>>(range: R) -> Constraint {
+        let (min, max) = count_bounds(range);
+        Constraint {
+            kind: ConstraintKind::Items(min, max),
+        }
+    }
+
+    /// Requires the items of arrays to differ from each other. Each repeated
+    /// item is reported at its own path.
+    pub fn unique_items() -> Constraint {
+        Constraint {
+            kind: ConstraintKind::UniqueItems,
+        }
+    }
+
+    /// Requires strings to match `pattern`, a regular expression of which a
+    /// small subset is supported:
+    ///
+    /// - `.` for any character, and `\d`, `\w` and `\s` for digits, word
+    ///   characters and whitespace, with `\D`, `\W` and `\S` for the
+    ///   others;
+    /// - character classes such as `[a-z0-9_]` and `[^,]`;
+    /// - `?`, `*` and `+` after a character or class;
+    /// - `^` and `$` at the start and end of the pattern, to anchor it.
+    ///
+    /// Other characters match themselves, or themselves escaped with `\`.
+    /// Without anchors, the pattern may match any part of the string.
+    /// Groups, alternation and counted repetition are not supported, and are
+    /// reported as errors rather than matched literally.
+    pub fn pattern(pattern: &str) -> Result<Constraint, PatternError> {
+        Ok(Constraint {
+            kind: ConstraintKind::Pattern(Pattern::parse(pattern)?),
+        })
+    }
+
+    /// Requires values to meet both this constraint and `other`.
+    pub fn and(self, other: Constraint) -> Constraint {
+        Constraint {
+            kind: ConstraintKind::And(Box::new(self), Box::new(other)),
+        }
+    }
+
+    /// Requires values to meet this constraint or `other`.
+    pub fn or(self, other: Constraint) -> Constraint {
+        Constraint {
+            kind: ConstraintKind::Or(Box::new(self), Box::new(other)),
+        }
+    }
+
+    /// Tests whether `value` meets this constraint.
+    pub fn is_met_by(&self, value: &Value) -> bool {
+        let mut failures = Vec::new();
+        self.evaluate(&KeyPath::new(), value, &mut failures);
+        failures.is_empty()
+    }
+
+    /// Pushes the path and message of each way `value`, at `path`, fails
+    /// this constraint.
+    fn evaluate(&self, path: &KeyPath, value: &Value, out: &mut Vec<(KeyPath, String)>) {
+        let message = match (&self.kind, value) {
+            (ConstraintKind::Range(start, end), &Value::Integer(n)) => {
+                if contains(start, end, &n) {
+                    return;
+                }
+                format!("must be {}", bounds(start, end, "greater", "less"))
+            }
+            (ConstraintKind::FloatRange(start, end), &Value::Float(n)) => {
+                if contains(start, end, &n) {
+                    return;
+                }
+                format!("must be {}", bounds(start, end, "greater", "less"))
+            }
+            (ConstraintKind::FloatRange(start, end), &Value::Integer(n)) => {
+                if contains(start, end, &(n as f64)) {
+                    return;
+                }
+                format!("must be {}", bounds(start, end, "greater", "less"))
+            }
+            (&ConstraintKind::Length(min, max), Value::String(s)) => {
+                if within(s.chars().count(), min, max) {
+                    return;
+                }
+                format!(
+                    "must be {} long",
+                    counted(min, max, "character", "characters")
+                )
+            }
+            (&ConstraintKind::Items(min, max), Value::Array(array)) => {
+                if within(array.len(), min, max) {
+                    return;
+                }
+                format!("must have {}", counted(min, max, "item", "items"))
+            }
+            (ConstraintKind::UniqueItems, Value::Array(array)) => {
+                for (i, item) in array.iter().enumerate() {
+                    if let Some(first) = array[..i].iter().position(|v| v == item) {
+                        out.push((
+                            path.join_index(i),
+                            format!("must not repeat `{}`", path.join_index(first)),
+                        ));
+                    }
+                }
+                return;
+            }
+            (ConstraintKind::Pattern(pattern), Value::String(s)) => {
+                if pattern.is_match(s) {
+                    return;
+                }
+                format!("must match `{}`", pattern.source)
+            }
+            (ConstraintKind::And(a, b), _) => {
+                a.evaluate(path, value, out);
+                b.evaluate(path, value, out);
+                return;
+            }
+            (ConstraintKind::Or(a, b), _) => {
+                let mut first = Vec::new();
+                a.evaluate(path, value, &mut first);
+                if first.is_empty() {
+                    return;
+                }
+                let mut second = Vec::new();
+                b.evaluate(path, value, &mut second);
+                if second.is_empty() {
+                    return;
+                }
+                let messages = first
+                    .into_iter()
+                    .chain(second)
+                    .map(|(_, message)| message)
+                    .collect::<Vec<_>>();
+                messages.join(", or ")
+            }
+            _ => return,
+        };
+        out.push((path.clone(), message));
+    }
+}
+
+fn count_bounds<R: RangeBounds<usize>>(range: R) -> (usize, Option<usize>) {
+    let min = match range.start_bound() {
+        Bound::Included(&n) => n,
+        Bound::Excluded(&n) => n.saturating_add(1),
+        Bound::Unbounded => 0,
+    };
+    let max = match range.end_bound() {
+        Bound::Included(&n) => Some(n),
+        Bound::Excluded(&n) => Some(n.saturating_sub(1)),
+        Bound::Unbounded => None,
+    };
+    (min, max)
+}
+
+fn contains<T: PartialOrd>(start: &Bound<T>, end: &Bound<T>, n: &T) -> bool {
+    let above = match *start {
+        Bound::Included(ref start) => n >= start,
+        Bound::Excluded(ref start) => n > start,
+        Bound::Unbounded => true,
+    };
+    let below = match *end {
+        Bound::Included(ref end) => n <= end,
+        Bound::Excluded(ref end) => n < end,
+        Bound::Unbounded => true,
+    };
+    above && below
+}
+
+fn within(n: usize, min: usize, max: Option<usize>) -> bool {
+    n >= min && !matches!(max, Some(max) if n > max)
+}
+
+/// Describes a range of numbers, such as `at least 1 and at most 10`.
+fn bounds<T: fmt::Display>(start: &Bound<T>, end: &Bound<T>, above: &str, below: &str) -> String {
+    let start = match *start {
+        Bound::Included(ref n) => Some(format!("at least {}", n)),
+        Bound::Excluded(ref n) => Some(format!("{} than {}", above, n)),
+        Bound::Unbounded => None,
+    };
+    let end = match *end {
+        Bound::Included(ref n) => Some(format!("at most {}", n)),
+        Bound::Excluded(ref n) => Some(format!("{} than {}", below, n)),
+        Bound::Unbounded => None,
+    };
+    match (start, end) {
+        (Some(start), Some(end)) => format!("{} and {}", start, end),
+        (Some(bound), None) | (None, Some(bound)) => bound,
+        (None, None) => "anything".to_string(),
+    }
+}
+
+/// Describes a range of counts, such as `at least 1 item` or `exactly 2
+/// items`.
+fn counted(min: usize, max: Option<usize>, one: &str, many: &str) -> String {
+    let unit = |n: usize| if n == 1 { one } else { many };
+    match max {
+        Some(max) if max == min => format!("exactly {} {}", min, unit(min)),
+        Some(max) if min == 0 => format!("at most {} {}", max, unit(max)),
+        Some(max) => format!("at least {} and at most {} {}", min, max, unit(max)),
+        None => format!("at least {} {}", min, unit(min)),
+    }
+}
+
+impl Pattern {
+    fn parse(source: &str) -> Result<Pattern, PatternError> {
+        let err = |at, message| Err(PatternError { at, message });
+        let chars = source.char_indices().collect::<Vec<_>>();
+        let next_is = |i: usize, c: char| chars.get(i).map(|&(_, n)| n) == Some(c);
+        let start = next_is(0, '^');
+        let mut end = false;
+        let mut items = Vec::new();
+        let mut i = if start { 1 } else { 0 };
+        while i < chars.len() {
+            let (at, c) = chars[i];
+            i += 1;
+            let atom = match c {
+                '$' if i == chars.len() => {
+                    end = true;
+                    break;
+                }
+                '.' => Atom::Any,
+                '\\' => match chars.get(i) {
+                    Some(&(_, c)) => {
+                        i += 1;
+                        escape(c)
+                    }
+                    None => return err(at, "trailing `\\`"),
+                },
+                '[' => {
+                    let negated = next_is(i, '^');
+                    if negated {
+                        i += 1;
+                    }
+                    let mut ranges = Vec::new();
+                    loop {
+                        let (at, c) = match chars.get(i) {
+                            Some(&pair) => pair,
+                            None => return err(at, "unterminated character class"),
+                        };
+                        i += 1;
+                        if c == ']' && !ranges.is_empty() {
+                            break;
+                        }
+                        let low = match c {
+                            '\\' => match chars.get(i).map(|&(_, c)| escape(c)) {
+                                Some(Atom::Char(c)) => {
+                                    i += 1;
+                                    c
+                                }
+                                Some(Atom::Class {
+                                    negated: false,
+                                    ranges: class,
+                                }) => {
+                                    i += 1;
+                                    ranges.extend(class);
+                                    continue;
+                                }
+                                Some(_) => {
+                                    return err(at, "negated classes cannot be used in a class")
+                                }
+                                None => return err(at, "trailing `\\`"),
+                            },
+                            c => c,
+                        };
+                        match chars.get(i + 1) {
+                            Some(&(at, high)) if next_is(i, '-') && high != ']' => {
+                                if high < low {
+                                    return err(at, "invalid range in character class");
+                                }
+                                i += 2;
+                                ranges.push((low, high));
+                            }
+                            _ => ranges.push((low, low)),
+                        }
+                    }
+                    Atom::Class { negated, ranges }
+                }
+                '?' | '*' | '+' => return err(at, "nothing to repeat"),
+                '(' | ')' | '|' => return err(at, "groups and alternation are not supported"),
+                '{' | '}' => return err(at, "counted repetition is not supported"),
+                c => Atom::Char(c),
+            };
+            let repeat = match chars.get(i).map(|&(_, c)| c) {
+                Some('?') => Repeat::ZeroOrOne,
+                Some('*') => Repeat::ZeroOrMore,
+                Some('+') => {
+                    items.push((atom.clone(), Repeat::One));
+                    Repeat::ZeroOrMore
+                }
+                _ => Repeat::One,
+            };
+            if let Some('?') | Some('*') | Some('+') = chars.get(i).map(|&(_, c)| c) {
+                i += 1;
+            }
+            items.push((atom, repeat));
+        }
+        Ok(Pattern {
+            source: source.to_string(),
+            start,
+            end,
+            items,
+        })
+    }
+
+    /// Tests whether the pattern matches `s`, by following every way of
+    /// matching it at once: a state is the number of items matched so far,
+    /// so the time taken grows linearly with the length of `s`.
+    fn is_match(&self, s: &str) -> bool {
+        let done = self.items.len();
+        let mut states = vec![false; done + 1];
+        self.enter(&mut states, 0);
+        for c in s.chars() {
+            if states[done] && !self.end {
+                return true;
+            }
+            let mut next = vec![false; done + 1];
+            for (i, &(ref atom, repeat)) in self.items.iter().enumerate() {
+                if states[i] && atom.matches(c) {
+                    match repeat {
+                        Repeat::ZeroOrMore => self.enter(&mut next, i),
+                        Repeat::One | Repeat::ZeroOrOne => self.enter(&mut next, i + 1),
+                    }
+                }
+            }
+            if !self.start {
+                self.enter(&mut next, 0);
+            }
+            states = next;
+        }
+        states[done]
+    }
+
+    /// Adds the state `i` to `states`, along with those reached from it by
+    /// skipping items that may match nothing.
+    fn enter(&self, states: &mut [bool], mut i: usize) {
+        while !states[i] {
+            states[i] = true;
+            match self.items.get(i) {
+                Some(&(_, Repeat::ZeroOrOne)) | Some(&(_, Repeat::ZeroOrMore)) => i += 1,
+                _ => return,
+            }
+        }
+    }
+}
+
+fn escape(c: char) -> Atom {
+    let class = |negated, ranges: &[(char, char)]| Atom::Class {
+        negated,
+        ranges: ranges.to_vec(),
+    };
+    let digits = [('0', '9')];
+    let word = [('a', 'z'), ('A', 'Z'), ('0', '9'), ('_', '_')];
+    let space = [(' ', ' '), ('\t', '\t'), ('\n', '\n'), ('\r', '\r')];
+    match c {
+        'd' => class(false, &digits),
+        'D' => class(true, &digits),
+        'w' => class(false, &word),
+        'W' => class(true, &word),
+        's' => class(false, &space),
+        'S' => class(true, &space),
+        'n' => Atom::Char('\n'),
+        't' => Atom::Char('\t'),
+        c => Atom::Char(c),
+    }
+}
+
+impl Atom {
+    fn matches(&self, c: char) -> bool {
+        match *self {
+            Atom::Char(m) => c == m,
+            Atom::Any => c != '\n',
+            Atom::Class {
+                negated,
+                ref ranges,
+            } => ranges.iter().any(|&(low, high)| low <= c && c <= high) != negated,
+        }
+    }
+}
+
+impl fmt::Display for PatternError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid pattern: {} at offset {}", self.message, self.at)
+    }
+}
+
+impl error::Error for PatternError {}
+
+impl SchemaError {
+    /// Returns the path of the key the problem is with.
+    pub fn path(&self) -> &KeyPath {
+        &self.path
+    }
+
+    /// Returns the reason the table does not match the schema.
+    pub fn kind(&self) -> &SchemaErrorKind {
+        &self.kind
+    }
+
+    /// Returns the byte range in the source text the problem is reported at,
+    /// if the table was validated as part of a document.
+    ///
+    /// For a conditionally required key this is the span of the key whose
+    /// value makes it required.
+    pub fn span(&self) -> Option<(usize, usize)> {
+        self.span
+    }
+}
+
+impl fmt::Display for SchemaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            SchemaErrorKind::Missing => write!(f, "missing required key `{}`", self.path),
+            SchemaErrorKind::MissingWhen {
+                ref path,
+                ref value,
+            } => write!(
+                f,
+                "missing key `{}`, required when `{}` is {}",
+                self.path, path, value
+            ),
+            SchemaErrorKind::WrongType { expected, found } => write!(
+                f,
+                "invalid type for `{}`: expected {}, found {}",
+                self.path, expected, found
+            ),
+            SchemaErrorKind::Forbidden(ref hint) if hint.is_empty() => {
+                write!(f, "key `{}` is not allowed", self.path)
+            }
+            SchemaErrorKind::Forbidden(ref hint) => {
+                write!(f, "key `{}` is not allowed: {}", self.path, hint)
+            }
+            SchemaErrorKind::Check(ref message) | SchemaErrorKind::Constraint(ref message) => {
+                write!(f, "invalid value for `{}`: {}", self.path, message)
+            }
+        }
+    }
+}
+
+impl fmt::Debug for Schema {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Schema")
+            .field("keys", &self.keys)
+            .field("patterns", &self.patterns)
+            .field("conditions", &self.conditions)
+            .field("constraints", &self.constraints)
+            .field("checks", &self.checks.len())
+            .finish()
+    }
+}
+
+impl error::Error for SchemaError {}
@@ -26,12 +26,18 @@
 //! ```
 
 use std::cell::Cell;
+use std::collections::{BTreeMap, HashMap};
+use std::convert::TryFrom;
 use std::error;
 use std::fmt::{self, Write};
 use std::marker;
 use std::rc::Rc;
+use std::time::SystemTime;
 
-use crate::datetime;
+use crate::datetime::{self, Datetime, Offset};
+use crate::metadata::Metadata;
+use crate::path::{KeyPath, PathSegment};
+use crate::value::{self, Table, Value};
 use serde::ser;
 
 /// Serialize the given data structure as a TOML byte vector.
@@ -106,6 +112,21 @@ where
     Ok(dst)
 }
 
+/// Serialize the given data structure as a String of TOML, checking that the
+/// output reads back as the same data.
+///
+/// This is identical to `to_string` except that the output is parsed again
+/// and compared with `value`, returning an error describing the first
+/// difference. See `check_round_trip` for more details.
+pub fn to_string_checked<T: ?Sized>(value: &T) -> Result<String, Error>
+where
+    T: ser::Serialize,
+{
+    let dst = to_string(value)?;
+    check_round_trip(value, &dst)?;
+    Ok(dst)
+}
+
 /// Errors that can occur when serializing a type.
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub enum Error {
@@ -124,8 +145,8 @@ pub enum Error {
     #[doc(hidden)]
     KeyNewline,
 
-    /// An array had to be homogeneous, but now it is allowed to be heterogeneous.
-    #[doc(hidden)]
+    /// An array contained tables along with other values, or an array of
+    /// tables was not the value of a key, such as one nested in another array.
     ArrayMixedType,
 
     /// All values in a TOML table must be emitted before further tables are
@@ -139,9 +160,26 @@ pub enum Error {
     /// A serialized number was invalid.
     NumberInvalid,
 
+    /// An integer outside the range of TOML's 64-bit signed integers was
+    /// serialized, with `Serializer::big_integers` left at
+    /// `BigIntegers::Error`; holds the integer in decimal.
+    IntegerOutOfRange(Box<str>),
+
     /// None was attempted to be serialized, but it's not supported.
     UnsupportedNone,
 
+    /// Values were nested more deeply than the limit set with
+    /// `Serializer::max_depth`.
+    RecursionLimitExceeded,
+
+    /// A key or string contained a control character forbidden by
+    /// `Serializer::forbid_control_chars`.
+    ControlCharacter(Box<ControlCharacterError>),
+
+    /// Serialized output did not read back as the value it was written from,
+    /// as found by `check_round_trip`.
+    RoundTrip(Box<RoundTripError>),
+
     /// A custom error which could be generated when serializing a particular
     /// type.
     Custom(String),
@@ -179,11 +217,205 @@ impl StringSettings {
     }
 }
 
-#[derive(Debug, Default, Clone)]
+#[derive(Debug, Clone)]
 /// Internal struct for holding serialization settings
 struct Settings {
     array: Option<ArraySettings>,
+    array_width: Option<usize>,
     string: Option<StringSettings>,
+    fields: FieldRules,
+    table_styles: HashMap<KeyPath, TableStyle>,
+    sort_fields: bool,
+    max_depth: usize,
+    defaults: Option<Table>,
+    docs: HashMap<KeyPath, String>,
+    forbid_control_chars: bool,
+    empty_tables: EmptyTables,
+    datetime_offset: Option<Offset>,
+    big_integers: BigIntegers,
+    rewrite: Option<Rewrite>,
+}
+
+type RewriteFn = dyn Fn(&KeyPath, Value) -> Option<Value>;
+
+/// The function set with `Serializer::rewrite_values`.
+#[derive(Clone)]
+struct Rewrite(Rc<RewriteFn>);
+
+impl fmt::Debug for Rewrite {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Rewrite")
+    }
+}
+
+impl Default for Settings {
+    fn default() -> Settings {
+        Settings {
+            array: None,
+            array_width: None,
+            string: None,
+            fields: FieldRules::default(),
+            table_styles: HashMap::new(),
+            sort_fields: false,
+            max_depth: usize::MAX,
+            defaults: None,
+            docs: HashMap::new(),
+            forbid_control_chars: false,
+            empty_tables: EmptyTables::Header,
+            datetime_offset: None,
+            big_integers: BigIntegers::Error,
+            rewrite: None,
+        }
+    }
+}
+
+impl Settings {
+    /// Returns the settings for arrays written one element per line, or
+    /// `None` if arrays are written on one line, possibly to be wrapped
+    /// afterwards.
+    fn multiline_array(&self) -> Option<&ArraySettings> {
+        match self.array_width {
+            Some(_) => None,
+            None => self.array.as_ref(),
+        }
+    }
+}
+
+/// How a table is written, set for a key path with
+/// `Serializer::table_style`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TableStyle {
+    /// A `[header]` line followed by the entries of the table, which is how
+    /// tables are written by default.
+    Standard,
+    /// Dotted keys among the entries of the parent table, such as
+    /// `package.name = "toml"`.
+    Dotted,
+    /// An inline table among the entries of the parent table, such as
+    /// `package = { name = "toml" }`.
+    Inline,
+}
+
+/// How tables without entries are written, set with
+/// `Serializer::empty_tables`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EmptyTables {
+    /// A `[header]` line with nothing after it, which is how empty tables
+    /// are written by default.
+    Header,
+    /// An empty inline table among the entries of the parent table, such as
+    /// `section = {}`.
+    Inline,
+    /// Nothing at all.
+    Skip,
+    /// Nothing at all, leaving out empty arrays as well, since an empty
+    /// array of tables is otherwise written as `[]`.
+    SkipWithArrays,
+}
+
+/// How integers that do not fit in TOML's 64-bit signed integers are
+/// written, set with `Serializer::big_integers`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BigIntegers {
+    /// Fail with `Error::IntegerOutOfRange`, which is the default.
+    Error,
+    /// Write a string of decimal digits, such as `"18446744073709551615"`,
+    /// which `Deserializer::set_big_integer_strings` reads back.
+    String,
+}
+
+/// Runtime adjustments to how the fields of structs are serialized.
+///
+/// Rules are keyed by the name of the struct as passed to serde, which for a
+/// `#[derive(Serialize)]` struct is its Rust name (or the name given with
+/// `#[serde(rename = "...")]`), and by the name of the field. They apply to
+/// every struct of that name being serialized, at any depth.
+///
+/// ```rust
+/// use serde_derive::Serialize;
+/// use toml::ser::{FieldRules, Serializer};
+///
+/// #[derive(Serialize)]
+/// struct Config {
+///     max_conns: u32,
+///     secret: String,
+///     net: Net,
+/// }
+///
+/// #[derive(Serialize)]
+/// struct Net {
+///     port: u16,
+/// }
+///
+/// let config = Config {
+///     max_conns: 10,
+///     secret: "hunter2".to_string(),
+///     net: Net { port: 80 },
+/// };
+///
+/// let mut rules = FieldRules::new();
+/// rules
+///     .rename("Config", "max_conns", "max-connections")
+///     .skip("Config", "secret")
+///     .flatten("Config", "net");
+///
+/// let mut out = String::new();
+/// let mut ser = Serializer::new(&mut out);
+/// ser.field_rules(rules);
+/// serde::Serialize::serialize(&config, &mut ser).unwrap();
+/// assert_eq!(out, "max-connections = 10\nport = 80\n");
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct FieldRules {
+    rules: BTreeMap<String, BTreeMap<String, FieldRule>>,
+}
+
+#[derive(Debug, Clone)]
+enum FieldRule {
+    Skip,
+    Rename(String),
+    Flatten,
+}
+
+impl FieldRules {
+    /// Creates an empty set of rules, under which every field is serialized
+    /// as usual.
+    pub fn new() -> FieldRules {
+        FieldRules::default()
+    }
+
+    /// Omits `field` of the struct `name` from the output.
+    pub fn skip(&mut self, name: &str, field: &str) -> &mut Self {
+        self.insert(name, field, FieldRule::Skip)
+    }
+
+    /// Writes `field` of the struct `name` under the key `to` instead.
+    pub fn rename(&mut self, name: &str, field: &str, to: &str) -> &mut Self {
+        self.insert(name, field, FieldRule::Rename(to.to_string()))
+    }
+
+    /// Writes the entries of `field` of the struct `name`, which must be a
+    /// struct or a map, directly into the enclosing table instead of into a
+    /// table of its own.
+    ///
+    /// As with any other table, all non-table values must still come before
+    /// tables, so a flattened field containing plain values must not follow
+    /// a field that is a table.
+    pub fn flatten(&mut self, name: &str, field: &str) -> &mut Self {
+        self.insert(name, field, FieldRule::Flatten)
+    }
+
+    fn insert(&mut self, name: &str, field: &str, rule: FieldRule) -> &mut Self {
+        self.rules
+            .entry(name.to_string())
+            .or_default()
+            .insert(field.to_string(), rule);
+        self
+    }
+
+    fn get(&self, name: &str, field: &str) -> Option<&FieldRule> {
+        self.rules.get(name)?.get(field)
+    }
 }
 
 /// Serialization implementation for TOML.
@@ -199,6 +431,7 @@ pub struct Serializer<'a> {
     dst: &'a mut String,
     state: State<'a>,
     settings: Rc<Settings>,
+    depth: usize,
 }
 
 #[derive(Debug, Copy, Clone)]
@@ -217,7 +450,9 @@ enum State<'a> {
     },
     Array {
         parent: &'a State<'a>,
+        index: usize,
         first: &'a Cell<bool>,
+        open: &'a Cell<usize>,
         type_: &'a Cell<Option<ArrayState>>,
         len: Option<usize>,
     },
@@ -228,6 +463,8 @@ enum State<'a> {
 pub struct SerializeSeq<'a, 'b> {
     ser: &'b mut Serializer<'a>,
     first: Cell<bool>,
+    open: Cell<usize>,
+    ends: Vec<usize>,
     type_: Cell<Option<ArrayState>>,
     len: Option<usize>,
 }
@@ -237,9 +474,11 @@ pub enum SerializeTable<'a, 'b> {
     Datetime(&'b mut Serializer<'a>),
     Table {
         ser: &'b mut Serializer<'a>,
+        name: Option<&'static str>,
         key: String,
         first: Cell<bool>,
         table_emitted: Cell<bool>,
+        sorted: Vec<(&'static str, Value)>,
     },
 }
 
@@ -253,6 +492,7 @@ impl<'a> Serializer<'a> {
             dst,
             state: State::End,
             settings: Rc::new(Settings::default()),
+            depth: 0,
         }
     }
 
@@ -271,7 +511,9 @@ impl<'a> Serializer<'a> {
             settings: Rc::new(Settings {
                 array: Some(ArraySettings::pretty()),
                 string: Some(StringSettings::pretty()),
+                ..Settings::default()
             }),
+            depth: 0,
         }
     }
 
@@ -423,6 +665,506 @@ impl<'a> Serializer<'a> {
         self
     }
 
+    /// Write arrays on a single line unless that line would be longer than
+    /// `width` characters, in which case each element is written on its own
+    /// line, as with `Serializer::pretty_array`.
+    ///
+    /// This takes precedence over `Serializer::pretty_array`, but the
+    /// indentation and trailing comma of wrapped arrays can still be set with
+    /// `Serializer::pretty_array_indent` and
+    /// `Serializer::pretty_array_trailing_comma`.
+    ///
+    /// ```rust
+    /// use serde::Serialize;
+    ///
+    /// let mut deps = toml::value::Table::new();
+    /// deps.insert("long".into(), vec!["serde", "serde_derive", "toml"].into());
+    /// deps.insert("short".into(), vec!["a", "b"].into());
+    ///
+    /// let mut out = String::new();
+    /// let mut ser = toml::Serializer::new(&mut out);
+    /// ser.pretty_array_max_width(30);
+    /// deps.serialize(&mut ser).unwrap();
+    /// assert_eq!(out, "\
+    /// long = [
+    ///     \"serde\",
+    ///     \"serde_derive\",
+    ///     \"toml\",
+    /// ]
+    /// short = [\"a\", \"b\"]
+    /// ");
+    /// ```
+    pub fn pretty_array_max_width(&mut self, width: usize) -> &mut Self {
+        Rc::get_mut(&mut self.settings).unwrap().array_width = Some(width);
+        self
+    }
+
+    /// Skip, rename or flatten fields of structs according to `rules`,
+    /// replacing any rules set before.
+    ///
+    /// See `FieldRules` for more details.
+    pub fn field_rules(&mut self, rules: FieldRules) -> &mut Self {
+        Rc::get_mut(&mut self.settings).unwrap().fields = rules;
+        self
+    }
+
+    /// Write the fields of structs in alphabetical order rather than the
+    /// order they are declared in.
+    ///
+    /// Fields holding tables or arrays of tables are still written after the
+    /// other fields, as TOML requires. Maps are not affected, except those
+    /// held by the fields of a sorted struct, which are sorted along with it.
+    ///
+    /// ```rust
+    /// use serde_derive::Serialize;
+    /// use toml::ser::Serializer;
+    ///
+    /// #[derive(Serialize)]
+    /// struct Package {
+    ///     name: &'static str,
+    ///     version: &'static str,
+    ///     authors: Vec<&'static str>,
+    /// }
+    ///
+    /// let package = Package {
+    ///     name: "demo",
+    ///     version: "0.1.0",
+    ///     authors: vec!["Alice"],
+    /// };
+    ///
+    /// let mut out = String::new();
+    /// serde::Serialize::serialize(&package, Serializer::new(&mut out).sort_fields(true)).unwrap();
+    /// assert_eq!(out, "\
+    /// authors = [\"Alice\"]
+    /// name = \"demo\"
+    /// version = \"0.1.0\"
+    /// ");
+    /// ```
+    pub fn sort_fields(&mut self, value: bool) -> &mut Self {
+        Rc::get_mut(&mut self.settings).unwrap().sort_fields = value;
+        self
+    }
+
+    /// Write the entries whose value equals the one at the same path in
+    /// `defaults` commented out, such as `# timeout = 30`.
+    ///
+    /// This documents the defaults in a generated configuration file without
+    /// fixing them in it, so later changes to the defaults still apply.
+    ///
+    /// ```rust
+    /// use serde_derive::Serialize;
+    /// use toml::ser::Serializer;
+    ///
+    /// #[derive(Serialize)]
+    /// struct Config {
+    ///     name: String,
+    ///     timeout: u32,
+    /// }
+    ///
+    /// let defaults = toml::toml! {
+    ///     name = "app"
+    ///     timeout = 30
+    /// };
+    /// let config = Config { name: "proxy".to_string(), timeout: 30 };
+    ///
+    /// let mut out = String::new();
+    /// let mut ser = Serializer::new(&mut out);
+    /// ser.comment_defaults(defaults.as_table().unwrap().clone());
+    /// serde::Serialize::serialize(&config, &mut ser).unwrap();
+    /// assert_eq!(out, "name = \"proxy\"\n# timeout = 30\n");
+    /// ```
+    pub fn comment_defaults(&mut self, defaults: Table) -> &mut Self {
+        Rc::get_mut(&mut self.settings).unwrap().defaults = Some(defaults);
+        self
+    }
+
+    /// Write `doc` as a block of comments above the key at `path`, or above
+    /// the header of the table at `path`.
+    ///
+    /// ```rust
+    /// use toml::ser::Serializer;
+    ///
+    /// let config = toml::toml! {
+    ///     workers = 4
+    ///     [log]
+    ///     level = "info"
+    /// };
+    ///
+    /// let mut out = String::new();
+    /// let mut ser = Serializer::new(&mut out);
+    /// ser.key_doc("workers", "Number of worker threads.\nDefaults to the number of CPUs.")
+    ///     .key_doc("log", "Logging settings.");
+    /// serde::Serialize::serialize(&config, &mut ser).unwrap();
+    /// assert_eq!(out, "\
+    /// ## Number of worker threads.
+    /// ## Defaults to the number of CPUs.
+    /// workers = 4
+    ///
+    /// ## Logging settings.
+    /// [log]
+    /// level = \"info\"
+    /// ");
+    /// ```
+    pub fn key_doc<P: Into<KeyPath>>(&mut self, path: P, doc: &str) -> &mut Self {
+        Rc::get_mut(&mut self.settings)
+            .unwrap()
+            .docs
+            .insert(path.into(), doc.to_string());
+        self
+    }
+
+    /// Write the string attribute `doc` of every path in `metadata` as with
+    /// `Serializer::key_doc`.
+    pub fn key_docs(&mut self, metadata: &Metadata) -> &mut Self {
+        for (path, attrs) in metadata.iter() {
+            if let Some(doc) = attrs.get("doc").and_then(Value::as_str) {
+                self.key_doc(path, doc);
+            }
+        }
+        self
+    }
+
+    /// Fail with `Error::RecursionLimitExceeded` when tables and arrays are
+    /// nested more than `depth` levels deep, rather than overflowing the
+    /// stack on deeply recursive data.
+    ///
+    /// There is no limit by default.
+    ///
+    /// ```rust
+    /// use serde_derive::Serialize;
+    /// use toml::ser::{Error, Serializer};
+    ///
+    /// #[derive(Serialize)]
+    /// struct Node {
+    ///     next: Option<Box<Node>>,
+    /// }
+    ///
+    /// let chain = Node { next: Some(Box::new(Node { next: Some(Box::new(Node { next: None })) })) };
+    ///
+    /// let mut out = String::new();
+    /// let err = serde::Serialize::serialize(&chain, Serializer::new(&mut out).max_depth(1)).unwrap_err();
+    /// assert_eq!(err, Error::RecursionLimitExceeded);
+    /// ```
+    pub fn max_depth(&mut self, depth: usize) -> &mut Self {
+        Rc::get_mut(&mut self.settings).unwrap().max_depth = depth;
+        self
+    }
+
+    /// Checks that a table or array at `depth` is within the limit.
+    fn check_depth(&self, depth: usize) -> Result<(), Error> {
+        if depth > self.settings.max_depth {
+            return Err(Error::RecursionLimitExceeded);
+        }
+        Ok(())
+    }
+
+    /// Fail with `Error::ControlCharacter` on keys and strings containing
+    /// control characters other than tab, newline, carriage return,
+    /// backspace and form feed, rather than writing them as `\uXXXX`
+    /// escapes.
+    ///
+    /// Such characters are valid TOML when escaped, but are usually a sign of
+    /// binary data or corruption that should not end up in a document.
+    ///
+    /// ```rust
+    /// use toml::ser::{Error, Serializer};
+    ///
+    /// let value = toml::toml! {
+    ///     [servers]
+    ///     names = ["alpha", "be\u{1b}[0mta"]
+    /// };
+    ///
+    /// let mut out = String::new();
+    /// let err = serde::Serialize::serialize(&value, Serializer::new(&mut out).forbid_control_chars(true))
+    ///     .unwrap_err();
+    /// assert_eq!(
+    ///     err.to_string(),
+    ///     "control character U+001B at byte 2 of the string at `servers.names[1]`"
+    /// );
+    /// ```
+    pub fn forbid_control_chars(&mut self, value: bool) -> &mut Self {
+        Rc::get_mut(&mut self.settings)
+            .unwrap()
+            .forbid_control_chars = value;
+        self
+    }
+
+    /// Checks that `s`, the key or string at `path`, has no forbidden
+    /// control characters.
+    fn check_control_chars<F>(&self, s: &str, path: F) -> Result<(), Error>
+    where
+        F: FnOnce() -> KeyPath,
+    {
+        if !self.settings.forbid_control_chars {
+            return Ok(());
+        }
+        match s.char_indices().find(|&(_, c)| is_forbidden_control(c)) {
+            Some((offset, character)) => {
+                Err(Error::ControlCharacter(Box::new(ControlCharacterError {
+                    path: path(),
+                    offset,
+                    character,
+                })))
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// Checks the keys and strings of `value`, which is being written in one
+    /// piece, for forbidden control characters.
+    fn check_value_control_chars(&self, value: &Value) -> Result<(), Error> {
+        if !self.settings.forbid_control_chars {
+            return Ok(());
+        }
+        let mut stack = vec![(self.key_path(), value)];
+        while let Some((path, value)) = stack.pop() {
+            match value {
+                Value::String(s) => self.check_control_chars(s, || path)?,
+                Value::Array(array) => {
+                    for (i, value) in array.iter().enumerate().rev() {
+                        stack.push((path.join_index(i), value));
+                    }
+                }
+                Value::Table(table) => {
+                    let mut entries = Vec::with_capacity(table.len());
+                    for (key, value) in table {
+                        let path = path.join_key(key.as_str());
+                        self.check_control_chars(key, || path.clone())?;
+                        entries.push((path, value));
+                    }
+                    stack.extend(entries.into_iter().rev());
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Write the table at `path` in the given style.
+    ///
+    /// Paths are relative to the value being serialized and match tables
+    /// exactly, so styling `dependencies` does not affect
+    /// `dependencies.serde`. Tables inside an array of tables are not
+    /// matched. A table written dotted or inline is one of the entries of
+    /// its parent, so like any other non-table entry it must come before
+    /// the parent's sub-tables; everything nested inside an inline table is
+    /// written inline as well.
+    ///
+    /// ```rust
+    /// use toml::ser::{Serializer, TableStyle};
+    ///
+    /// let manifest: toml::Value = toml::toml! {
+    ///     [dependencies]
+    ///     serde = { features = ["derive"], version = "1" }
+    ///
+    ///     [package]
+    ///     metadata = { docs = { all-features = true } }
+    ///     name = "demo"
+    /// };
+    ///
+    /// let mut out = String::new();
+    /// let mut ser = Serializer::new(&mut out);
+    /// ser.table_style("dependencies.serde", TableStyle::Inline)
+    ///     .table_style("package.metadata", TableStyle::Dotted);
+    /// serde::Serialize::serialize(&manifest, &mut ser).unwrap();
+    /// assert_eq!(out, "\
+    /// [dependencies]
+    /// serde = { features = [\"derive\"], version = \"1\" }
+    ///
+    /// [package]
+    /// name = \"demo\"
+    /// metadata.docs.all-features = true
+    /// ");
+    /// ```
+    pub fn table_style<P: Into<KeyPath>>(&mut self, path: P, style: TableStyle) -> &mut Self {
+        Rc::get_mut(&mut self.settings)
+            .unwrap()
+            .table_styles
+            .insert(path.into(), style);
+        self
+    }
+
+    /// Write tables without entries in the given style.
+    ///
+    /// Tools reading TOML differ on whether an empty section is meaningful,
+    /// so it can be kept as a header, written inline, or left out.
+    /// Leaving them out also leaves out a table whose only entries were
+    /// empty tables, or with `EmptyTables::SkipWithArrays` empty arrays.
+    /// An empty table can only be
+    /// written inline before the first sub-table of its parent, so one
+    /// after it still gets a header. Entries of an array of tables always
+    /// get a header, as leaving them out would change the array.
+    ///
+    /// ```rust
+    /// use toml::ser::{EmptyTables, Serializer};
+    ///
+    /// let config = toml::toml! {
+    ///     features = []
+    ///     name = "demo"
+    ///     [plugins]
+    ///     [server]
+    ///     port = 80
+    /// };
+    ///
+    /// let mut out = String::new();
+    /// serde::Serialize::serialize(&config, Serializer::new(&mut out).empty_tables(EmptyTables::Inline))
+    ///     .unwrap();
+    /// assert_eq!(out, "features = []\nname = \"demo\"\nplugins = {}\n\n[server]\nport = 80\n");
+    ///
+    /// let mut out = String::new();
+    /// serde::Serialize::serialize(&config, Serializer::new(&mut out).empty_tables(EmptyTables::Skip))
+    ///     .unwrap();
+    /// assert_eq!(out, "features = []\nname = \"demo\"\n\n[server]\nport = 80\n");
+    ///
+    /// let mut out = String::new();
+    /// serde::Serialize::serialize(&config, Serializer::new(&mut out).empty_tables(EmptyTables::SkipWithArrays))
+    ///     .unwrap();
+    /// assert_eq!(out, "name = \"demo\"\n\n[server]\nport = 80\n");
+    /// ```
+    pub fn empty_tables(&mut self, style: EmptyTables) -> &mut Self {
+        Rc::get_mut(&mut self.settings).unwrap().empty_tables = style;
+        self
+    }
+
+    /// Sets how integers outside the range of TOML's 64-bit signed integers
+    /// are written: a `u64` above `i64::MAX`, or an `i128` or `u128` outside
+    /// the range of `i64`. Those inside it are written as integers either
+    /// way.
+    ///
+    /// By default they fail with `Error::IntegerOutOfRange`, rather than
+    /// being written as integers that no TOML parser reads back.
+    ///
+    /// ```rust
+    /// use serde_derive::Serialize;
+    /// use toml::ser::{BigIntegers, Error, Serializer};
+    ///
+    /// #[derive(Serialize)]
+    /// struct Ledger {
+    ///     balance: u128,
+    ///     limit: u128,
+    /// }
+    ///
+    /// let ledger = Ledger { balance: u128::MAX, limit: 100 };
+    ///
+    /// let mut out = String::new();
+    /// let err = serde::Serialize::serialize(&ledger, &mut Serializer::new(&mut out)).unwrap_err();
+    /// assert_eq!(err, Error::IntegerOutOfRange(u128::MAX.to_string().into()));
+    ///
+    /// let mut out = String::new();
+    /// serde::Serialize::serialize(&ledger, Serializer::new(&mut out).big_integers(BigIntegers::String))
+    ///     .unwrap();
+    /// assert_eq!(out, "balance = \"340282366920938463463374607431768211455\"\nlimit = 100\n");
+    /// ```
+    pub fn big_integers(&mut self, policy: BigIntegers) -> &mut Self {
+        Rc::get_mut(&mut self.settings).unwrap().big_integers = policy;
+        self
+    }
+
+    /// Passes every entry of a table to `f` before it is written, with its
+    /// path and its value, and writes what `f` returns instead: the value
+    /// as it is, another value, or nothing for `None`.
+    ///
+    /// This applies the same change to every value written, such as
+    /// rounding floats or leaving out secrets, without changing the types
+    /// being serialized. Entries of tables are passed to `f` after the table
+    /// holding them, so a table it returns has its entries passed in turn.
+    /// Arrays are passed whole, except for the tables of an array of tables,
+    /// whose entries are passed one by one with paths like `servers[0].host`.
+    /// A value returned unchanged is written as it would be without `f`, so
+    /// structs keep the order of their fields and their field rules.
+    /// As with any table, a table returned for an entry written before other
+    /// values of its table fails with `Error::ValueAfterTable`.
+    ///
+    /// ```rust
+    /// use toml::ser::Serializer;
+    /// use toml::Value;
+    ///
+    /// let config = toml::toml! {
+    ///     ratio = 0.3333333333
+    ///     [database]
+    ///     user = "admin"
+    ///     password = "hunter2"
+    /// };
+    ///
+    /// let mut out = String::new();
+    /// let mut ser = Serializer::new(&mut out);
+    /// ser.rewrite_values(|path, value| match value {
+    ///     _ if path.to_string().ends_with("password") => None,
+    ///     Value::Float(f) => Some(Value::Float((f * 100.0).round() / 100.0)),
+    ///     value => Some(value),
+    /// });
+    /// serde::Serialize::serialize(&config, &mut ser).unwrap();
+    /// assert_eq!(out, "ratio = 0.33\n\n[database]\nuser = \"admin\"\n");
+    /// ```
+    pub fn rewrite_values<F>(&mut self, f: F) -> &mut Self
+    where
+        F: Fn(&KeyPath, Value) -> Option<Value> + 'static,
+    {
+        Rc::get_mut(&mut self.settings).unwrap().rewrite = Some(Rewrite(Rc::new(f)));
+        self
+    }
+
+    /// Writes `n`, an integer outside the range of `i64`, as set by
+    /// `big_integers`.
+    fn big_integer<T: fmt::Display>(&mut self, n: T) -> Result<(), Error> {
+        match self.settings.big_integers {
+            BigIntegers::Error => Err(Error::IntegerOutOfRange(n.to_string().into())),
+            BigIntegers::String => ser::Serializer::serialize_str(self, &n.to_string()),
+        }
+    }
+
+    /// Writes every offset date-time with the given offset, converting it to
+    /// the same instant in that offset, such as `Offset::Z` to write times in
+    /// UTC or the offset of the local time zone to write local times.
+    ///
+    /// By default offset date-times are written with the offset they have.
+    ///
+    /// ```rust
+    /// use serde_derive::Serialize;
+    /// use toml::value::{Datetime, Offset};
+    ///
+    /// #[derive(Serialize)]
+    /// struct Build {
+    ///     finished: Datetime,
+    /// }
+    ///
+    /// let build = Build {
+    ///     finished: "1979-05-27T07:32:00Z".parse().unwrap(),
+    /// };
+    /// let mut out = String::new();
+    /// let mut ser = toml::Serializer::new(&mut out);
+    /// ser.datetime_offset(Offset::Custom { negative: false, hours: 2, minutes: 0 });
+    /// serde::Serialize::serialize(&build, &mut ser).unwrap();
+    /// assert_eq!(out, "finished = 1979-05-27T09:32:00+02:00\n");
+    /// ```
+    pub fn datetime_offset(&mut self, offset: Offset) -> &mut Self {
+        Rc::get_mut(&mut self.settings).unwrap().datetime_offset = Some(offset);
+        self
+    }
+
+    // Writes the table being written when it turned out to have no entries.
+    fn emit_empty_table(&mut self) -> Result<(), Error> {
+        let table_emitted = match self.state {
+            State::Table { table_emitted, .. } => table_emitted,
+            _ => {
+                let state = self.state.clone();
+                return self.emit_table_header(&state);
+            }
+        };
+        match self.settings.empty_tables {
+            EmptyTables::Skip | EmptyTables::SkipWithArrays => Err(Error::UnsupportedNone),
+            EmptyTables::Inline if !table_emitted.get() => {
+                self.emit_key(ArrayState::Started)?;
+                self.dst.push_str("{}\n");
+                Ok(())
+            }
+            _ => {
+                let state = self.state.clone();
+                self.emit_table_header(&state)
+            }
+        }
+    }
+
     fn display<T: fmt::Display>(&mut self, t: T, type_: ArrayState) -> Result<(), Error> {
         self.emit_key(type_)?;
         write!(self.dst, "{}", t).map_err(ser::Error::custom)?;
@@ -445,14 +1187,16 @@ impl<'a> Serializer<'a> {
             State::Array {
                 parent,
                 first,
+                open,
                 type_,
                 len,
+                ..
             } => {
                 assert!(type_.get().is_some());
                 if first.get() {
                     self._emit_key(parent)?;
                 }
-                self.emit_array(first, len)
+                self.emit_array(first, open, len)
             }
             State::Table {
                 parent,
@@ -460,13 +1204,7 @@ impl<'a> Serializer<'a> {
                 table_emitted,
                 key,
             } => {
-                if table_emitted.get() {
-                    return Err(Error::ValueAfterTable);
-                }
-                if first.get() {
-                    self.emit_table_header(parent)?;
-                    first.set(false);
-                }
+                self.start_entry(parent, first, table_emitted)?;
                 self.escape_key(key)?;
                 self.dst.push_str(" = ");
                 Ok(())
@@ -474,63 +1212,366 @@ impl<'a> Serializer<'a> {
         }
     }
 
-    fn emit_array(&mut self, first: &Cell<bool>, len: Option<usize>) -> Result<(), Error> {
-        match (len, &self.settings.array) {
-            (Some(0..=1), _) | (_, &None) => {
-                if first.get() {
-                    self.dst.push_str("[")
-                } else {
-                    self.dst.push_str(", ")
+    // Emits the header of the table `parent` before its first entry.
+    fn start_entry(
+        &mut self,
+        parent: &State<'_>,
+        first: &Cell<bool>,
+        table_emitted: &Cell<bool>,
+    ) -> Result<(), Error> {
+        if table_emitted.get() {
+            return Err(Error::ValueAfterTable);
+        }
+        if first.get() {
+            self.emit_table_header(parent)?;
+            first.set(false);
+        }
+        Ok(())
+    }
+
+    // Returns the path of the value being written, or `None` inside an
+    // array.
+    fn table_path(&self) -> Option<KeyPath> {
+        let mut keys = Vec::new();
+        let mut state = &self.state;
+        loop {
+            match *state {
+                State::Table { key, parent, .. } => {
+                    keys.push(key);
+                    state = parent;
                 }
+                State::Array { .. } => return None,
+                State::End => break,
             }
-            (_, &Some(ref a)) => {
-                if first.get() {
-                    self.dst.push_str("[\n")
-                } else {
-                    self.dst.push_str(",\n")
+        }
+        let mut path = KeyPath::new();
+        for key in keys.into_iter().rev() {
+            path.push_key(key);
+        }
+        Some(path)
+    }
+
+    // Returns the path of the value being written, including array indices.
+    fn key_path(&self) -> KeyPath {
+        let mut segments = Vec::new();
+        let mut state = &self.state;
+        loop {
+            match *state {
+                State::Table { key, parent, .. } => {
+                    segments.push(PathSegment::Key(key.to_string()));
+                    state = parent;
                 }
-                for _ in 0..a.indent {
-                    self.dst.push_str(" ");
+                State::Array { index, parent, .. } => {
+                    segments.push(PathSegment::Index(index));
+                    state = parent;
                 }
+                State::End => break,
             }
         }
-        Ok(())
+        segments.reverse();
+        KeyPath::from(segments)
     }
 
-    fn array_type(&mut self, type_: ArrayState) -> Result<(), Error> {
-        let prev = match self.state {
-            State::Array { type_, .. } => type_,
-            _ => return Ok(()),
-        };
-        if prev.get().is_none() {
-            prev.set(Some(type_));
+    // Returns the style set for the table being written, other than the
+    // standard one.
+    fn styled_table(&self) -> Option<TableStyle> {
+        if self.settings.table_styles.is_empty() {
+            return None;
+        }
+        match self.settings.table_styles.get(&self.table_path()?) {
+            Some(&TableStyle::Standard) | None => None,
+            Some(&style) => Some(style),
         }
-        Ok(())
     }
 
-    fn escape_key(&mut self, key: &str) -> Result<(), Error> {
-        let ok = key.len() > 0
-            && key.chars().all(|c| match c {
-                'a'..='z' | 'A'..='Z' | '0'..='9' | '-' | '_' => true,
-                _ => false,
-            });
-        if ok {
-            write!(self.dst, "{}", key).map_err(ser::Error::custom)?;
-        } else {
-            self.emit_str(key, true)?;
+    // Returns whether the entry being written has the value set for it with
+    // `comment_defaults`. Tables are never a default as a whole, only their
+    // entries.
+    fn is_default<T>(&self, value: &T) -> bool
+    where
+        T: ?Sized + ser::Serialize,
+    {
+        let defaults = match self.settings.defaults {
+            Some(ref defaults) => defaults,
+            None => return false,
+        };
+        let default = match self.table_path().and_then(|path| path.lookup_in(defaults)) {
+            Some(default) => default,
+            None => return false,
+        };
+        match default {
+            Value::Table(_) => false,
+            Value::Array(array) if array.iter().any(Value::is_table) => false,
+            _ => crate::value::to_value(value).ok().as_ref() == Some(default),
         }
-        Ok(())
     }
 
-    fn emit_str(&mut self, value: &str, is_key: bool) -> Result<(), Error> {
-        #[derive(PartialEq)]
-        enum Type {
-            NewlineTripple,
-            OnelineTripple,
-            OnelineSingle,
+    // Writes the entry being written commented out, leaving any table header
+    // written before it as is.
+    fn serialize_commented<T>(&mut self, value: &T) -> Result<(), Error>
+    where
+        T: ?Sized + ser::Serialize,
+    {
+        let start = self.dst.len();
+        value.serialize(&mut *self)?;
+        let written = self.dst.split_off(start);
+        let mut in_entry = false;
+        for line in written.split_inclusive('\n') {
+            in_entry = in_entry || !(line == "\n" || line.starts_with('['));
+            if in_entry {
+                self.dst.push_str("# ");
+            }
+            self.dst.push_str(line);
         }
+        Ok(())
+    }
 
-        enum Repr {
+    fn serialize_styled<T>(&mut self, value: &T, style: TableStyle) -> Result<(), Error>
+    where
+        T: ?Sized + ser::Serialize,
+    {
+        let value = crate::value::to_value(value)?;
+        self.check_value_control_chars(&value)?;
+        match (style, &value) {
+            (TableStyle::Dotted, Value::Table(table)) if !table.is_empty() => {
+                self.array_type(ArrayState::Started)?;
+                if let State::Table {
+                    key,
+                    parent,
+                    first,
+                    table_emitted,
+                } = self.state
+                {
+                    self.start_entry(parent, first, table_emitted)?;
+                    let mut prefix = Vec::new();
+                    prefix.push(key);
+                    self.emit_dotted(&mut prefix, table)?;
+                }
+                Ok(())
+            }
+            (_, Value::Table(_)) => {
+                self.emit_key(ArrayState::Started)?;
+                self.emit_inline(&value)?;
+                self.dst.push('\n');
+                Ok(())
+            }
+            _ => ser::Serialize::serialize(&value, self),
+        }
+    }
+
+    // Emits one `a.b.c = value` line for each leaf of `table`.
+    fn emit_dotted<'t>(
+        &mut self,
+        prefix: &mut Vec<&'t str>,
+        table: &'t Table,
+    ) -> Result<(), Error> {
+        for (key, value) in table {
+            prefix.push(key);
+            match *value {
+                Value::Table(ref table) if !table.is_empty() => {
+                    self.emit_dotted(prefix, table)?;
+                }
+                _ => {
+                    for (i, key) in prefix.iter().enumerate() {
+                        if i > 0 {
+                            self.dst.push('.');
+                        }
+                        self.escape_key(key)?;
+                    }
+                    self.dst.push_str(" = ");
+                    self.emit_inline(value)?;
+                    self.dst.push('\n');
+                }
+            }
+            prefix.pop();
+        }
+        Ok(())
+    }
+
+    // Emits `value` on one line, with tables as inline tables. Open arrays
+    // and tables are kept on a stack rather than recursed into, so only the
+    // depth limit bounds how deeply they can nest.
+    fn emit_inline(&mut self, value: &Value) -> Result<(), Error> {
+        enum Open<'v> {
+            Array(std::slice::Iter<'v, Value>, bool),
+            Table(crate::map::Iter<'v>, bool),
+        }
+
+        let mut stack = Vec::new();
+        let mut next = Some(value);
+        loop {
+            if let Some(value) = next.take() {
+                match value {
+                    Value::Array(array) => {
+                        self.check_depth(self.depth + stack.len())?;
+                        self.dst.push('[');
+                        stack.push(Open::Array(array.iter(), true));
+                    }
+                    Value::Table(table) if table.is_empty() => self.dst.push_str("{}"),
+                    Value::Table(table) => {
+                        self.check_depth(self.depth + stack.len())?;
+                        self.dst.push_str("{ ");
+                        stack.push(Open::Table(table.iter(), true));
+                    }
+                    _ => ser::Serialize::serialize(
+                        value,
+                        &mut Serializer {
+                            dst: &mut *self.dst,
+                            state: State::End,
+                            settings: self.settings.clone(),
+                            depth: self.depth,
+                        },
+                    )?,
+                }
+            }
+            match stack.last_mut() {
+                None => return Ok(()),
+                Some(Open::Array(iter, first)) => match iter.next() {
+                    Some(value) => {
+                        if !*first {
+                            self.dst.push_str(", ");
+                        }
+                        *first = false;
+                        next = Some(value);
+                    }
+                    None => {
+                        self.dst.push(']');
+                        stack.pop();
+                    }
+                },
+                Some(Open::Table(iter, first)) => match iter.next() {
+                    Some((key, value)) => {
+                        if !*first {
+                            self.dst.push_str(", ");
+                        }
+                        *first = false;
+                        self.escape_key(key)?;
+                        self.dst.push_str(" = ");
+                        next = Some(value);
+                    }
+                    None => {
+                        self.dst.push_str(" }");
+                        stack.pop();
+                    }
+                },
+            }
+        }
+    }
+
+    fn emit_array(
+        &mut self,
+        first: &Cell<bool>,
+        open: &Cell<usize>,
+        len: Option<usize>,
+    ) -> Result<(), Error> {
+        let settings = self.settings.clone();
+        match (len, settings.multiline_array()) {
+            (Some(0..=1), _) | (_, None) => {
+                if first.get() {
+                    self.dst.push_str("[");
+                    open.set(self.dst.len());
+                } else {
+                    self.dst.push_str(", ")
+                }
+            }
+            (_, Some(a)) => {
+                if first.get() {
+                    self.dst.push_str("[\n")
+                } else {
+                    self.dst.push_str(",\n")
+                }
+                for _ in 0..a.indent {
+                    self.dst.push_str(" ");
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Rewrites the array that was just written on one line, with its
+    /// elements starting at `open` and ending at `ends`, one element per line
+    /// if its line is longer than the maximum width.
+    ///
+    /// Only the outermost array is wrapped; nested arrays stay on one line.
+    fn wrap_array(&mut self, open: usize, ends: &[usize]) {
+        let width = match (self.settings.array_width, &self.state) {
+            (Some(_), State::Array { .. }) | (None, _) => return,
+            (Some(width), _) => width,
+        };
+        let line_start = self.dst[..open].rfind('\n').map_or(0, |i| i + 1);
+        if ends.len() < 2 || self.dst[line_start..].chars().count() <= width {
+            return;
+        }
+        let settings = self
+            .settings
+            .array
+            .clone()
+            .unwrap_or_else(ArraySettings::pretty);
+        let mut start = open;
+        let elements = ends
+            .iter()
+            .map(|&end| {
+                let element = self.dst[start..end].to_string();
+                start = end + 2;
+                element
+            })
+            .collect::<Vec<_>>();
+        self.dst.truncate(open);
+        for (i, element) in elements.iter().enumerate() {
+            if i > 0 {
+                self.dst.push(',');
+            }
+            self.dst.push('\n');
+            for _ in 0..settings.indent {
+                self.dst.push(' ');
+            }
+            self.dst.push_str(element);
+        }
+        if settings.trailing_comma {
+            self.dst.push(',');
+        }
+        self.dst.push_str("\n]");
+    }
+
+    fn array_type(&mut self, type_: ArrayState) -> Result<(), Error> {
+        let prev = match self.state {
+            State::Array { type_, .. } => type_,
+            _ => return Ok(()),
+        };
+        match (prev.get(), type_) {
+            (None, ArrayState::StartedAsATable) => {
+                if let State::Array {
+                    parent: State::Array { .. } | State::End,
+                    ..
+                } = self.state
+                {
+                    return Err(Error::ArrayMixedType);
+                }
+                prev.set(Some(type_));
+            }
+            (None, _) => prev.set(Some(type_)),
+            (Some(ArrayState::Started), ArrayState::StartedAsATable)
+            | (Some(ArrayState::StartedAsATable), ArrayState::Started) => {
+                return Err(Error::ArrayMixedType)
+            }
+            (Some(_), _) => {}
+        }
+        Ok(())
+    }
+
+    fn escape_key(&mut self, key: &str) -> Result<(), Error> {
+        crate::display::write_key(self.dst, key).map_err(ser::Error::custom)
+    }
+
+    fn emit_str(&mut self, value: &str) -> Result<(), Error> {
+        #[derive(PartialEq)]
+        enum Type {
+            NewlineTripple,
+            OnelineTripple,
+            OnelineSingle,
+        }
+
+        enum Repr {
             /// represent as a literal string (using '')
             Literal(String, Type),
             /// represent the std way (using "")
@@ -603,7 +1644,7 @@ impl<'a> Serializer<'a> {
             Repr::Literal(out, ty)
         }
 
-        let repr = if !is_key && self.settings.string.is_some() {
+        let repr = if self.settings.string.is_some() {
             match (&self.settings.string, do_pretty(value)) {
                 (&Some(StringSettings { literal: false, .. }), Repr::Literal(_, ty)) => {
                     Repr::Std(ty)
@@ -818,7 +1859,24 @@ impl<'a, 'b> ser::Serializer for &'b mut Serializer<'a> {
     }
 
     fn serialize_u64(self, v: u64) -> Result<(), Self::Error> {
-        self.display(v, ArrayState::Started)
+        match i64::try_from(v) {
+            Ok(v) => self.display(v, ArrayState::Started),
+            Err(_) => self.big_integer(v),
+        }
+    }
+
+    fn serialize_i128(self, v: i128) -> Result<(), Self::Error> {
+        match i64::try_from(v) {
+            Ok(v) => self.display(v, ArrayState::Started),
+            Err(_) => self.big_integer(v),
+        }
+    }
+
+    fn serialize_u128(self, v: u128) -> Result<(), Self::Error> {
+        match i64::try_from(v) {
+            Ok(v) => self.display(v, ArrayState::Started),
+            Err(_) => self.big_integer(v),
+        }
     }
 
     fn serialize_f32(self, v: f32) -> Result<(), Self::Error> {
@@ -835,8 +1893,9 @@ impl<'a, 'b> ser::Serializer for &'b mut Serializer<'a> {
     }
 
     fn serialize_str(self, value: &str) -> Result<(), Self::Error> {
+        self.check_control_chars(value, || self.key_path())?;
         self.emit_key(ArrayState::Started)?;
-        self.emit_str(value, false)?;
+        self.emit_str(value)?;
         if let State::Table { .. } = self.state {
             self.dst.push_str("\n");
         }
@@ -878,12 +1937,19 @@ impl<'a, 'b> ser::Serializer for &'b mut Serializer<'a> {
 
     fn serialize_newtype_struct<T: ?Sized>(
         self,
-        _name: &'static str,
+        name: &'static str,
         value: &T,
     ) -> Result<(), Self::Error>
     where
         T: ser::Serialize,
     {
+        if name == value::RAW_NUMBER_NAME {
+            let number = value.serialize(StringExtractor)?;
+            if !crate::de::is_number_literal(&number) {
+                return Err(Error::NumberInvalid);
+            }
+            return self.display(number, ArrayState::Started);
+        }
         value.serialize(self)
     }
 
@@ -901,10 +1967,13 @@ impl<'a, 'b> ser::Serializer for &'b mut Serializer<'a> {
     }
 
     fn serialize_seq(self, len: Option<usize>) -> Result<Self::SerializeSeq, Self::Error> {
+        self.check_depth(self.depth)?;
         self.array_type(ArrayState::Started)?;
         Ok(SerializeSeq {
             ser: self,
             first: Cell::new(true),
+            open: Cell::new(0),
+            ends: Vec::new(),
             type_: Cell::new(None),
             len,
         })
@@ -933,12 +2002,15 @@ impl<'a, 'b> ser::Serializer for &'b mut Serializer<'a> {
     }
 
     fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, Self::Error> {
+        self.check_depth(self.depth)?;
         self.array_type(ArrayState::StartedAsATable)?;
         Ok(SerializeTable::Table {
             ser: self,
+            name: None,
             key: String::new(),
             first: Cell::new(true),
             table_emitted: Cell::new(false),
+            sorted: Vec::new(),
         })
     }
 
@@ -951,12 +2023,15 @@ impl<'a, 'b> ser::Serializer for &'b mut Serializer<'a> {
             self.array_type(ArrayState::Started)?;
             Ok(SerializeTable::Datetime(self))
         } else {
+            self.check_depth(self.depth)?;
             self.array_type(ArrayState::StartedAsATable)?;
             Ok(SerializeTable::Table {
                 ser: self,
+                name: Some(name),
                 key: String::new(),
                 first: Cell::new(true),
                 table_emitted: Cell::new(false),
+                sorted: Vec::new(),
             })
         }
     }
@@ -984,24 +2059,29 @@ impl<'a, 'b> ser::SerializeSeq for SerializeSeq<'a, 'b> {
             dst: &mut *self.ser.dst,
             state: State::Array {
                 parent: &self.ser.state,
+                index: self.ends.len(),
                 first: &self.first,
+                open: &self.open,
                 type_: &self.type_,
                 len: self.len,
             },
             settings: self.ser.settings.clone(),
+            depth: self.ser.depth + 1,
         })?;
         self.first.set(false);
+        self.ends.push(self.ser.dst.len());
         Ok(())
     }
 
     fn end(self) -> Result<(), Error> {
         match self.type_.get() {
             Some(ArrayState::StartedAsATable) => return Ok(()),
-            Some(ArrayState::Started) => match (self.len, &self.ser.settings.array) {
-                (Some(0..=1), _) | (_, &None) => {
+            Some(ArrayState::Started) => match (self.len, self.ser.settings.multiline_array()) {
+                (Some(0..=1), _) | (_, None) => {
                     self.ser.dst.push_str("]");
+                    self.ser.wrap_array(self.open.get(), &self.ends);
                 }
-                (_, &Some(ref a)) => {
+                (_, Some(a)) => {
                     if a.trailing_comma {
                         self.ser.dst.push_str(",");
                     }
@@ -1010,6 +2090,11 @@ impl<'a, 'b> ser::SerializeSeq for SerializeSeq<'a, 'b> {
             },
             None => {
                 assert!(self.first.get());
+                if let (State::Table { .. }, EmptyTables::SkipWithArrays) =
+                    (&self.ser.state, self.ser.settings.empty_tables)
+                {
+                    return Err(Error::UnsupportedNone);
+                }
                 self.ser.emit_key(ArrayState::Started)?;
                 self.ser.dst.push_str("[]")
             }
@@ -1019,57 +2104,555 @@ impl<'a, 'b> ser::SerializeSeq for SerializeSeq<'a, 'b> {
         }
         Ok(())
     }
-}
+}
+
+impl<'a, 'b> ser::SerializeTuple for SerializeSeq<'a, 'b> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_element<T: ?Sized>(&mut self, value: &T) -> Result<(), Error>
+    where
+        T: ser::Serialize,
+    {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+impl<'a, 'b> ser::SerializeTupleVariant for SerializeSeq<'a, 'b> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: ?Sized>(&mut self, value: &T) -> Result<(), Error>
+    where
+        T: ser::Serialize,
+    {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+impl<'a, 'b> ser::SerializeTupleStruct for SerializeSeq<'a, 'b> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: ?Sized>(&mut self, value: &T) -> Result<(), Error>
+    where
+        T: ser::Serialize,
+    {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+impl<'a, 'b> ser::SerializeMap for SerializeTable<'a, 'b> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_key<T: ?Sized>(&mut self, input: &T) -> Result<(), Error>
+    where
+        T: ser::Serialize,
+    {
+        match *self {
+            SerializeTable::Datetime(_) => panic!(), // shouldn't be possible
+            SerializeTable::Table { ref mut key, .. } => {
+                key.truncate(0);
+                *key = input.serialize(StringExtractor)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn serialize_value<T: ?Sized>(&mut self, value: &T) -> Result<(), Error>
+    where
+        T: ser::Serialize,
+    {
+        match *self {
+            SerializeTable::Datetime(_) => panic!(), // shouldn't be possible
+            SerializeTable::Table {
+                ref mut ser,
+                ref key,
+                ref first,
+                ref table_emitted,
+                ..
+            } => serialize_entry(ser, key, first, table_emitted, value)?,
+        }
+        Ok(())
+    }
+
+    fn end(self) -> Result<(), Error> {
+        match self {
+            SerializeTable::Datetime(_) => panic!(), // shouldn't be possible
+            SerializeTable::Table { ser, first, .. } => {
+                if first.get() {
+                    ser.emit_empty_table()?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<'a, 'b> ser::SerializeStruct for SerializeTable<'a, 'b> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: ?Sized>(&mut self, key: &'static str, value: &T) -> Result<(), Error>
+    where
+        T: ser::Serialize,
+    {
+        match *self {
+            SerializeTable::Datetime(ref mut ser) => {
+                if key == datetime::FIELD {
+                    value.serialize(DateStrEmitter(&mut *ser))?;
+                } else {
+                    return Err(Error::DateInvalid);
+                }
+            }
+            SerializeTable::Table {
+                ref ser,
+                ref mut sorted,
+                ..
+            } if ser.settings.sort_fields => match Value::try_from(value) {
+                Ok(value) => sorted.push((key, sort_tables(value))),
+                Err(Error::UnsupportedNone) => {}
+                Err(e) => return Err(e),
+            },
+            SerializeTable::Table {
+                ref mut ser,
+                name,
+                ref first,
+                ref table_emitted,
+                ..
+            } => serialize_field(ser, name, key, first, table_emitted, value)?,
+        }
+        Ok(())
+    }
+
+    fn end(self) -> Result<(), Error> {
+        match self {
+            SerializeTable::Datetime(_) => {}
+            SerializeTable::Table {
+                ser,
+                name,
+                first,
+                table_emitted,
+                mut sorted,
+                ..
+            } => {
+                let settings = ser.settings.clone();
+                sorted.sort_by(|(a, a_value), (b, b_value)| {
+                    let output_key =
+                        |key| match name.and_then(|name| settings.fields.get(name, key)) {
+                            Some(FieldRule::Rename(to)) => to,
+                            _ => key,
+                        };
+                    (field_rank(a_value), output_key(a)).cmp(&(field_rank(b_value), output_key(b)))
+                });
+                for (key, value) in &sorted {
+                    serialize_field(ser, name, key, &first, &table_emitted, value)?;
+                }
+                if first.get() {
+                    ser.emit_empty_table()?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Returns whether `c` is a control character without a short escape.
+pub(crate) fn is_forbidden_control(c: char) -> bool {
+    matches!(c, '\u{0}'..='\u{7}' | '\u{b}' | '\u{e}'..='\u{1f}' | '\u{7f}')
+}
+
+/// Sorts the keys of the tables in `value`.
+///
+/// The fields of a sorted struct are held as `Value`s until it ends, so the
+/// fields of a struct nested in one are then in a table, in the order the
+/// map keeps.
+fn sort_tables(value: Value) -> Value {
+    match value {
+        Value::Table(table) => {
+            let mut entries = table
+                .into_iter()
+                .map(|(key, value)| (key, sort_tables(value)))
+                .collect::<Vec<_>>();
+            entries.sort_by(|a, b| a.0.cmp(&b.0));
+            Value::Table(entries.into_iter().collect())
+        }
+        Value::Array(array) => Value::Array(array.into_iter().map(sort_tables).collect()),
+        value => value,
+    }
+}
+
+/// Orders the fields of a struct being written sorted so that tables and
+/// arrays of tables come after the other fields.
+fn field_rank(value: &Value) -> u8 {
+    match value {
+        Value::Table(_) => 2,
+        Value::Array(array) if array.iter().any(Value::is_table) => 1,
+        _ => 0,
+    }
+}
+
+/// Serializes `value` as an inline value, with tables written inline.
+pub(crate) fn to_inline_value(value: &Value) -> Result<String, Error> {
+    let mut dst = String::new();
+    Serializer::new(&mut dst).emit_inline(value)?;
+    Ok(dst)
+}
+
+/// Serializes `value` with every table below the top level written inline,
+/// which unlike an array of tables can hold any value.
+pub(crate) fn to_string_inline(value: &Value) -> Result<String, Error> {
+    let mut dst = String::new();
+    {
+        let mut ser = Serializer::new(&mut dst);
+        match *value {
+            Value::Table(ref table) => {
+                for (key, value) in table {
+                    ser.escape_key(key)?;
+                    ser.dst.push_str(" = ");
+                    ser.emit_inline(value)?;
+                    ser.dst.push('\n');
+                }
+            }
+            _ => ser.emit_inline(value)?,
+        }
+    }
+    Ok(dst)
+}
+
+/// Serializes `value` as the entry `key` of the table being written by `ser`.
+fn serialize_entry<T>(
+    ser: &mut Serializer<'_>,
+    key: &str,
+    first: &Cell<bool>,
+    table_emitted: &Cell<bool>,
+    value: &T,
+) -> Result<(), Error>
+where
+    T: ?Sized + ser::Serialize,
+{
+    let depth = ser.depth + 1;
+    let mut ser = Serializer {
+        dst: &mut *ser.dst,
+        state: State::Table {
+            key,
+            parent: &ser.state,
+            first,
+            table_emitted,
+        },
+        settings: ser.settings.clone(),
+        depth,
+    };
+    ser.check_control_chars(key, || ser.key_path())?;
+    let rewrite = match ser.settings.rewrite {
+        Some(Rewrite(ref f)) => f.clone(),
+        None => return write_entry(&mut ser, first, value),
+    };
+    let converted = match crate::value::to_value(value) {
+        Ok(value) => value,
+        Err(Error::UnsupportedNone) => return Ok(()),
+        Err(e) => return Err(e),
+    };
+    match rewrite(&ser.key_path(), converted.clone()) {
+        // Writing the original rather than its `Value` keeps the order of
+        // struct fields and the settings that apply to structs, such as
+        // field rules, while its entries still go through `rewrite`.
+        Some(ref rewritten) if *rewritten == converted => write_entry(&mut ser, first, value),
+        Some(rewritten) => write_entry(&mut ser, first, &rewritten),
+        None => Ok(()),
+    }
+}
+
+/// Writes `value` as the entry `ser` is set up for by `serialize_entry`.
+fn write_entry<T>(ser: &mut Serializer<'_>, first: &Cell<bool>, value: &T) -> Result<(), Error>
+where
+    T: ?Sized + ser::Serialize,
+{
+    let start = ser.dst.len();
+    let style = ser.styled_table();
+    let res = match style {
+        Some(style) => ser.serialize_styled(value, style),
+        None if ser.is_default(value) => ser.serialize_commented(value),
+        None => value.serialize(&mut *ser),
+    };
+    match res {
+        Ok(()) => first.set(false),
+        Err(Error::UnsupportedNone) => return Ok(()),
+        Err(e) => return Err(e),
+    }
+    let settings = ser.settings.clone();
+    if settings.docs.is_empty() {
+        return Ok(());
+    }
+    if let Some(doc) = ser.table_path().and_then(|path| settings.docs.get(&path)) {
+        let header = style.is_none()
+            && match crate::value::to_value(value)? {
+                Value::Table(_) => true,
+                Value::Array(array) => array.iter().any(Value::is_table),
+                _ => false,
+            };
+        insert_doc(ser.dst, start, doc, header);
+    }
+    Ok(())
+}
+
+/// Inserts `doc` as comment lines above the entry written to `dst` from
+/// `start` on, which is either its first table header or, after any header
+/// of its parent, its first line.
+fn insert_doc(dst: &mut String, start: usize, doc: &str, header: bool) {
+    let mut at = start;
+    for line in dst[start..].split_inclusive('\n') {
+        let is_header = line.starts_with('[');
+        if line != "\n" && is_header == header {
+            break;
+        }
+        at += line.len();
+    }
+    let mut comment = String::new();
+    for line in doc.lines() {
+        comment.push('#');
+        if !line.is_empty() {
+            comment.push(' ');
+            comment.push_str(line);
+        }
+        comment.push('\n');
+    }
+    dst.insert_str(at, &comment);
+}
+
+/// Serializes `value` as the field `key` of the struct `name`, applying any
+/// `FieldRules` for it.
+fn serialize_field<T>(
+    ser: &mut Serializer<'_>,
+    name: Option<&'static str>,
+    key: &str,
+    first: &Cell<bool>,
+    table_emitted: &Cell<bool>,
+    value: &T,
+) -> Result<(), Error>
+where
+    T: ?Sized + ser::Serialize,
+{
+    let settings = ser.settings.clone();
+    match name.and_then(|name| settings.fields.get(name, key)) {
+        None => serialize_entry(ser, key, first, table_emitted, value),
+        Some(FieldRule::Skip) => Ok(()),
+        Some(FieldRule::Rename(to)) => serialize_entry(ser, to, first, table_emitted, value),
+        Some(FieldRule::Flatten) => value.serialize(Flatten {
+            ser,
+            first,
+            table_emitted,
+        }),
+    }
+}
+
+/// Serializes the entries of a flattened struct or map into the table that
+/// contains it.
+struct Flatten<'a, 'b> {
+    ser: &'b mut Serializer<'a>,
+    first: &'b Cell<bool>,
+    table_emitted: &'b Cell<bool>,
+}
+
+struct SerializeFlatten<'a, 'b> {
+    inner: Flatten<'a, 'b>,
+    name: Option<&'static str>,
+    key: String,
+}
+
+impl<'a, 'b> ser::Serializer for Flatten<'a, 'b> {
+    type Ok = ();
+    type Error = Error;
+    type SerializeSeq = ser::Impossible<(), Error>;
+    type SerializeTuple = ser::Impossible<(), Error>;
+    type SerializeTupleStruct = ser::Impossible<(), Error>;
+    type SerializeTupleVariant = ser::Impossible<(), Error>;
+    type SerializeMap = SerializeFlatten<'a, 'b>;
+    type SerializeStruct = SerializeFlatten<'a, 'b>;
+    type SerializeStructVariant = ser::Impossible<(), Error>;
+
+    fn serialize_bool(self, _v: bool) -> Result<(), Self::Error> {
+        Err(Error::UnsupportedType)
+    }
+
+    fn serialize_i8(self, _v: i8) -> Result<(), Self::Error> {
+        Err(Error::UnsupportedType)
+    }
+
+    fn serialize_i16(self, _v: i16) -> Result<(), Self::Error> {
+        Err(Error::UnsupportedType)
+    }
+
+    fn serialize_i32(self, _v: i32) -> Result<(), Self::Error> {
+        Err(Error::UnsupportedType)
+    }
+
+    fn serialize_i64(self, _v: i64) -> Result<(), Self::Error> {
+        Err(Error::UnsupportedType)
+    }
+
+    fn serialize_u8(self, _v: u8) -> Result<(), Self::Error> {
+        Err(Error::UnsupportedType)
+    }
+
+    fn serialize_u16(self, _v: u16) -> Result<(), Self::Error> {
+        Err(Error::UnsupportedType)
+    }
+
+    fn serialize_u32(self, _v: u32) -> Result<(), Self::Error> {
+        Err(Error::UnsupportedType)
+    }
+
+    fn serialize_u64(self, _v: u64) -> Result<(), Self::Error> {
+        Err(Error::UnsupportedType)
+    }
+
+    fn serialize_f32(self, _v: f32) -> Result<(), Self::Error> {
+        Err(Error::UnsupportedType)
+    }
+
+    fn serialize_f64(self, _v: f64) -> Result<(), Self::Error> {
+        Err(Error::UnsupportedType)
+    }
+
+    fn serialize_char(self, _v: char) -> Result<(), Self::Error> {
+        Err(Error::UnsupportedType)
+    }
+
+    fn serialize_str(self, _value: &str) -> Result<(), Self::Error> {
+        Err(Error::UnsupportedType)
+    }
+
+    fn serialize_bytes(self, _value: &[u8]) -> Result<(), Self::Error> {
+        Err(Error::UnsupportedType)
+    }
+
+    fn serialize_none(self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    fn serialize_some<T: ?Sized>(self, value: &T) -> Result<(), Self::Error>
+    where
+        T: ser::Serialize,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<(), Self::Error> {
+        Err(Error::UnsupportedType)
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<(), Self::Error> {
+        Err(Error::UnsupportedType)
+    }
 
-impl<'a, 'b> ser::SerializeTuple for SerializeSeq<'a, 'b> {
-    type Ok = ();
-    type Error = Error;
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+    ) -> Result<(), Self::Error> {
+        Err(Error::UnsupportedType)
+    }
 
-    fn serialize_element<T: ?Sized>(&mut self, value: &T) -> Result<(), Error>
+    fn serialize_newtype_struct<T: ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<(), Self::Error>
     where
         T: ser::Serialize,
     {
-        ser::SerializeSeq::serialize_element(self, value)
-    }
-
-    fn end(self) -> Result<(), Error> {
-        ser::SerializeSeq::end(self)
+        value.serialize(self)
     }
-}
-
-impl<'a, 'b> ser::SerializeTupleVariant for SerializeSeq<'a, 'b> {
-    type Ok = ();
-    type Error = Error;
 
-    fn serialize_field<T: ?Sized>(&mut self, value: &T) -> Result<(), Error>
+    fn serialize_newtype_variant<T: ?Sized>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<(), Self::Error>
     where
         T: ser::Serialize,
     {
-        ser::SerializeSeq::serialize_element(self, value)
+        Err(Error::UnsupportedType)
     }
 
-    fn end(self) -> Result<(), Error> {
-        ser::SerializeSeq::end(self)
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, Self::Error> {
+        Err(Error::UnsupportedType)
     }
-}
 
-impl<'a, 'b> ser::SerializeTupleStruct for SerializeSeq<'a, 'b> {
-    type Ok = ();
-    type Error = Error;
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple, Self::Error> {
+        Err(Error::UnsupportedType)
+    }
 
-    fn serialize_field<T: ?Sized>(&mut self, value: &T) -> Result<(), Error>
-    where
-        T: ser::Serialize,
-    {
-        ser::SerializeSeq::serialize_element(self, value)
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct, Self::Error> {
+        Err(Error::UnsupportedType)
     }
 
-    fn end(self) -> Result<(), Error> {
-        ser::SerializeSeq::end(self)
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant, Self::Error> {
+        Err(Error::UnsupportedType)
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, Self::Error> {
+        Ok(SerializeFlatten {
+            inner: self,
+            name: None,
+            key: String::new(),
+        })
+    }
+
+    fn serialize_struct(
+        self,
+        name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStruct, Self::Error> {
+        if name == datetime::NAME {
+            return Err(Error::UnsupportedType);
+        }
+        Ok(SerializeFlatten {
+            inner: self,
+            name: Some(name),
+            key: String::new(),
+        })
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant, Self::Error> {
+        Err(Error::UnsupportedType)
     }
 }
 
-impl<'a, 'b> ser::SerializeMap for SerializeTable<'a, 'b> {
+impl<'a, 'b> ser::SerializeMap for SerializeFlatten<'a, 'b> {
     type Ok = ();
     type Error = Error;
 
@@ -1077,13 +2660,7 @@ impl<'a, 'b> ser::SerializeMap for SerializeTable<'a, 'b> {
     where
         T: ser::Serialize,
     {
-        match *self {
-            SerializeTable::Datetime(_) => panic!(), // shouldn't be possible
-            SerializeTable::Table { ref mut key, .. } => {
-                key.truncate(0);
-                *key = input.serialize(StringExtractor)?;
-            }
-        }
+        self.key = input.serialize(StringExtractor)?;
         Ok(())
     }
 
@@ -1091,50 +2668,20 @@ impl<'a, 'b> ser::SerializeMap for SerializeTable<'a, 'b> {
     where
         T: ser::Serialize,
     {
-        match *self {
-            SerializeTable::Datetime(_) => panic!(), // shouldn't be possible
-            SerializeTable::Table {
-                ref mut ser,
-                ref key,
-                ref first,
-                ref table_emitted,
-                ..
-            } => {
-                let res = value.serialize(&mut Serializer {
-                    dst: &mut *ser.dst,
-                    state: State::Table {
-                        key,
-                        parent: &ser.state,
-                        first,
-                        table_emitted,
-                    },
-                    settings: ser.settings.clone(),
-                });
-                match res {
-                    Ok(()) => first.set(false),
-                    Err(Error::UnsupportedNone) => {}
-                    Err(e) => return Err(e),
-                }
-            }
-        }
-        Ok(())
+        let Flatten {
+            ref mut ser,
+            first,
+            table_emitted,
+        } = self.inner;
+        serialize_entry(ser, &self.key, first, table_emitted, value)
     }
 
     fn end(self) -> Result<(), Error> {
-        match self {
-            SerializeTable::Datetime(_) => panic!(), // shouldn't be possible
-            SerializeTable::Table { ser, first, .. } => {
-                if first.get() {
-                    let state = ser.state.clone();
-                    ser.emit_table_header(&state)?;
-                }
-            }
-        }
         Ok(())
     }
 }
 
-impl<'a, 'b> ser::SerializeStruct for SerializeTable<'a, 'b> {
+impl<'a, 'b> ser::SerializeStruct for SerializeFlatten<'a, 'b> {
     type Ok = ();
     type Error = Error;
 
@@ -1142,50 +2689,15 @@ impl<'a, 'b> ser::SerializeStruct for SerializeTable<'a, 'b> {
     where
         T: ser::Serialize,
     {
-        match *self {
-            SerializeTable::Datetime(ref mut ser) => {
-                if key == datetime::FIELD {
-                    value.serialize(DateStrEmitter(&mut *ser))?;
-                } else {
-                    return Err(Error::DateInvalid);
-                }
-            }
-            SerializeTable::Table {
-                ref mut ser,
-                ref first,
-                ref table_emitted,
-                ..
-            } => {
-                let res = value.serialize(&mut Serializer {
-                    dst: &mut *ser.dst,
-                    state: State::Table {
-                        key,
-                        parent: &ser.state,
-                        first,
-                        table_emitted,
-                    },
-                    settings: ser.settings.clone(),
-                });
-                match res {
-                    Ok(()) => first.set(false),
-                    Err(Error::UnsupportedNone) => {}
-                    Err(e) => return Err(e),
-                }
-            }
-        }
-        Ok(())
+        let Flatten {
+            ref mut ser,
+            first,
+            table_emitted,
+        } = self.inner;
+        serialize_field(ser, self.name, key, first, table_emitted, value)
     }
 
     fn end(self) -> Result<(), Error> {
-        match self {
-            SerializeTable::Datetime(_) => {}
-            SerializeTable::Table { ser, first, .. } => {
-                if first.get() {
-                    let state = ser.state.clone();
-                    ser.emit_table_header(&state)?;
-                }
-            }
-        }
         Ok(())
     }
 }
@@ -1252,6 +2764,16 @@ impl<'a, 'b> ser::Serializer for DateStrEmitter<'a, 'b> {
     }
 
     fn serialize_str(self, value: &str) -> Result<(), Self::Error> {
+        if let Some(ref offset) = self.0.settings.datetime_offset {
+            let datetime = value.parse::<Datetime>().map_err(|_| Error::DateInvalid)?;
+            if datetime.offset.is_some() {
+                let datetime = datetime
+                    .to_offset(offset.clone())
+                    .ok_or(Error::DateInvalid)?;
+                self.0.display(datetime, ArrayState::Started)?;
+                return Ok(());
+            }
+        }
         self.0.display(value, ArrayState::Started)?;
         Ok(())
     }
@@ -1539,10 +3061,18 @@ impl fmt::Display for Error {
             Error::ValueAfterTable => "values must be emitted before tables".fmt(f),
             Error::DateInvalid => "a serialized date was invalid".fmt(f),
             Error::NumberInvalid => "a serialized number was invalid".fmt(f),
+            Error::IntegerOutOfRange(ref n) => {
+                write!(f, "integer {} is out of range for a TOML integer", n)
+            }
             Error::UnsupportedNone => "unsupported None value".fmt(f),
+            Error::RecursionLimitExceeded => "values nested too deeply".fmt(f),
+            Error::ControlCharacter(ref e) => e.fmt(f),
+            Error::RoundTrip(ref e) => e.fmt(f),
             Error::Custom(ref s) => s.fmt(f),
             Error::KeyNewline => unreachable!(),
-            Error::ArrayMixedType => unreachable!(),
+            Error::ArrayMixedType => {
+                "arrays of tables must only contain tables and be the value of a key".fmt(f)
+            }
             Error::__Nonexhaustive => panic!(),
         }
     }
@@ -1550,6 +3080,12 @@ impl fmt::Display for Error {
 
 impl error::Error for Error {}
 
+impl std::convert::From<Error> for std::io::Error {
+    fn from(e: Error) -> Self {
+        std::io::Error::new(std::io::ErrorKind::InvalidData, e.to_string())
+    }
+}
+
 impl ser::Error for Error {
     fn custom<T: fmt::Display>(msg: T) -> Error {
         Error::Custom(msg.to_string())
@@ -1562,6 +3098,257 @@ enum Category {
     Table,
 }
 
+/// Checks that `output`, the result of serializing `value`, reads back as
+/// exactly the same data.
+///
+/// This guards against serializer bugs with unusual values, such as strings
+/// with control characters, floats at the limits of their range or
+/// datetimes, and is meant for debug builds and tests. Floats are compared
+/// bit for bit except that NaN equals NaN, and strings exactly. An
+/// `Error::RoundTrip` describes the first value that differs. Output from a
+/// serializer that leaves out values on purpose, such as with
+/// `Serializer::comment_defaults` or `FieldRules`, does not round trip.
+///
+/// ```rust
+/// use toml::ser::{check_round_trip, Error};
+///
+/// let value = toml::toml! {
+///     [server]
+///     name = "a\u{1}b"
+///     ratio = 1e300
+/// };
+/// let output = toml::to_string(&value).unwrap();
+/// check_round_trip(&value, &output).unwrap();
+///
+/// let err = check_round_trip(&value, "[server]\nname = 'ab'\nratio = 1e300").unwrap_err();
+/// assert_eq!(
+///     err.to_string(),
+///     "output does not read back the same at `server.name`: wrote \"a\\u0001b\", read \"ab\""
+/// );
+/// ```
+pub fn check_round_trip<T>(value: &T, output: &str) -> Result<(), Error>
+where
+    T: ?Sized + ser::Serialize,
+{
+    let mut written = crate::value::to_value(value)?;
+    unwrap_datetimes(&mut written);
+    let read = match written {
+        Value::Table(_) => output.parse::<Value>(),
+        _ => crate::de::from_str::<Value>(&format!("value = {}", output))
+            .map(|mut v| v.as_table_mut().unwrap().remove("value").unwrap()),
+    }
+    .map_err(|e| Error::Custom(format!("output is not valid TOML: {}", e)))?;
+    let mut path = KeyPath::new();
+    if first_difference(&mut path, &written, &read) {
+        let written = path.lookup(&written).map(Value::to_string);
+        let read = path.lookup(&read).map(Value::to_string);
+        return Err(Error::RoundTrip(Box::new(RoundTripError {
+            path,
+            written,
+            read,
+        })));
+    }
+    Ok(())
+}
+
+/// A control character found in a key or string by
+/// `Serializer::forbid_control_chars`.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ControlCharacterError {
+    path: KeyPath,
+    offset: usize,
+    character: char,
+}
+
+impl ControlCharacterError {
+    /// Returns the path of the key or string.
+    pub fn path(&self) -> &KeyPath {
+        &self.path
+    }
+
+    /// Returns the byte offset of the character in the key or string.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Returns the control character.
+    pub fn character(&self) -> char {
+        self.character
+    }
+}
+
+impl fmt::Display for ControlCharacterError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "control character U+{:04X} at byte {} of the string",
+            self.character as u32, self.offset
+        )?;
+        if !self.path.is_empty() {
+            write!(f, " at `{}`", self.path)?;
+        }
+        Ok(())
+    }
+}
+
+/// The first difference between a value and its serialized output, as found
+/// by `check_round_trip`.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct RoundTripError {
+    path: KeyPath,
+    written: Option<String>,
+    read: Option<String>,
+}
+
+impl RoundTripError {
+    /// Returns the path of the first value that differs.
+    pub fn path(&self) -> &KeyPath {
+        &self.path
+    }
+
+    /// Returns the value serialized at the path, or `None` if there was none.
+    pub fn written(&self) -> Option<&str> {
+        self.written.as_deref()
+    }
+
+    /// Returns the value read back at the path, or `None` if there was none.
+    pub fn read(&self) -> Option<&str> {
+        self.read.as_deref()
+    }
+}
+
+impl fmt::Display for RoundTripError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "output does not read back the same")?;
+        if !self.path.is_empty() {
+            write!(f, " at `{}`", self.path)?;
+        }
+        match (&self.written, &self.read) {
+            (Some(written), Some(read)) => write!(f, ": wrote {}, read {}", written, read),
+            (Some(written), None) => write!(f, ": wrote {}, read nothing", written),
+            (None, Some(read)) => write!(f, ": wrote nothing, read {}", read),
+            (None, None) => Ok(()),
+        }
+    }
+}
+
+/// Replaces the tables `to_value` produces for datetimes with the datetimes
+/// they stand for.
+fn unwrap_datetimes(value: &mut Value) {
+    let datetime = match value {
+        Value::Array(array) => {
+            array.iter_mut().for_each(unwrap_datetimes);
+            return;
+        }
+        Value::Table(table) if table.len() == 1 => {
+            match table.get(datetime::FIELD).and_then(Value::as_str) {
+                Some(s) => s.parse().ok(),
+                None => None,
+            }
+        }
+        _ => None,
+    };
+    match (datetime, value) {
+        (Some(datetime), value) => *value = Value::Datetime(datetime),
+        (None, Value::Table(table)) => table.iter_mut().for_each(|(_, v)| unwrap_datetimes(v)),
+        _ => {}
+    }
+}
+
+/// Finds the first place `a` and `b` differ, leaving its path in `path` and
+/// returning `true`, or returns `false` if they are the same.
+fn first_difference(path: &mut KeyPath, a: &Value, b: &Value) -> bool {
+    match (a, b) {
+        (Value::Float(a), Value::Float(b)) => {
+            a.to_bits() != b.to_bits() && !(a.is_nan() && b.is_nan())
+        }
+        (Value::Array(a), Value::Array(b)) => {
+            for i in 0..a.len().max(b.len()) {
+                path.push_index(i);
+                match (a.get(i), b.get(i)) {
+                    (Some(a), Some(b)) if !first_difference(path, a, b) => {}
+                    _ => return true,
+                }
+                path.pop();
+            }
+            false
+        }
+        (Value::Table(a), Value::Table(b)) => {
+            let mut keys = a.keys().chain(b.keys()).collect::<Vec<_>>();
+            keys.sort();
+            keys.dedup();
+            for key in keys {
+                path.push_key(key.clone());
+                match (a.get(key), b.get(key)) {
+                    (Some(a), Some(b)) if !first_difference(path, a, b) => {}
+                    _ => return true,
+                }
+                path.pop();
+            }
+            false
+        }
+        (a, b) => a != b,
+    }
+}
+
+/// Serializes a `SystemTime` as a TOML offset date-time in UTC, for use with
+/// `#[serde(serialize_with = "toml::ser::system_time")]`.
+///
+/// ```rust
+/// # use serde_derive::Serialize;
+/// use std::time::{Duration, SystemTime, UNIX_EPOCH};
+///
+/// #[derive(Serialize)]
+/// struct Build {
+///     #[serde(serialize_with = "toml::ser::system_time")]
+///     finished: SystemTime,
+/// }
+///
+/// let build = Build {
+///     finished: UNIX_EPOCH + Duration::from_secs(296_638_320),
+/// };
+/// let out = toml::to_string(&build).unwrap();
+/// assert_eq!(out, "finished = 1979-05-27T07:32:00Z\n");
+/// ```
+pub fn system_time<S>(time: &SystemTime, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: ser::Serializer,
+{
+    match Datetime::from_system_time(*time) {
+        Some(datetime) => ser::Serialize::serialize(&datetime, serializer),
+        None => Err(ser::Error::custom(
+            "time is out of range for a TOML datetime",
+        )),
+    }
+}
+
+/// Serializes a number of seconds since 1970-01-01T00:00:00Z as a TOML
+/// offset date-time in UTC, for use with
+/// `#[serde(serialize_with = "toml::ser::unix_seconds")]`.
+///
+/// ```rust
+/// # use serde_derive::Serialize;
+/// #[derive(Serialize)]
+/// struct Build {
+///     #[serde(serialize_with = "toml::ser::unix_seconds")]
+///     finished: i64,
+/// }
+///
+/// let out = toml::to_string(&Build { finished: 296_638_320 }).unwrap();
+/// assert_eq!(out, "finished = 1979-05-27T07:32:00Z\n");
+/// ```
+pub fn unix_seconds<S>(seconds: &i64, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: ser::Serializer,
+{
+    match Datetime::from_unix_timestamp(*seconds, 0) {
+        Some(datetime) => ser::Serialize::serialize(&datetime, serializer),
+        None => Err(ser::Error::custom(
+            "time is out of range for a TOML datetime",
+        )),
+    }
+}
+
 /// Convenience function to serialize items in a map in an order valid with
 /// TOML.
 ///
@@ -1665,6 +3452,14 @@ impl<E: ser::Error> ser::Serializer for Categorize<E> {
         Ok(Category::Primitive)
     }
 
+    fn serialize_i128(self, _: i128) -> Result<Self::Ok, Self::Error> {
+        Ok(Category::Primitive)
+    }
+
+    fn serialize_u128(self, _: u128) -> Result<Self::Ok, Self::Error> {
+        Ok(Category::Primitive)
+    }
+
     fn serialize_f32(self, _: f32) -> Result<Self::Ok, Self::Error> {
         Ok(Category::Primitive)
     }
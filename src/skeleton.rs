@@ -0,0 +1,165 @@
+//! Example documents to start a configuration file from.
+//!
+//! A skeleton lists the keys a configuration may contain, so that users can
+//! fill in the ones they need instead of looking them up. Keys that are not
+//! required are commented out:
+//!
+//! ```rust
+//! use toml::schema::Schema;
+//! use toml::value::ValueKind;
+//!
+//! let mut schema = Schema::new();
+//! schema
+//!     .required("name", ValueKind::String)
+//!     .optional("workers", ValueKind::Integer)
+//!     .required("server.port", ValueKind::Integer)
+//!     .optional("tls.cert", ValueKind::String);
+//!
+//! assert_eq!(toml::skeleton::from_schema(&schema), "\
+//! name = \"\"
+//! ## workers = 0
+//!
+//! [server]
+//! port = 0
+//!
+//! ## [tls]
+//! ## cert = \"\"
+//! ");
+//! ```
+//!
+//! A skeleton can also be made from a value, typically the default
+//! configuration of an application, in which case every key is commented out
+//! and shows its default.
+
+use serde::ser;
+
+use crate::path::{KeyPath, PathSegment};
+use crate::schema::{Presence, Schema};
+use crate::value::ValueKind;
+
+/// A table of a skeleton and the keys written in it.
+type Section<'a> = (KeyPath, Vec<(&'a str, Presence, Option<ValueKind>)>);
+
+/// Returns an example document with a key for each key of `schema` that is
+/// not forbidden.
+///
+/// Required keys are given a placeholder value of their type, such as `""`
+/// for a string, and optional keys are commented out. So is the header of a
+/// table with no required keys. Keys inside arrays are left out.
+pub fn from_schema(schema: &Schema) -> String {
+    let mut sections: Vec<Section<'_>> = Vec::new();
+    let paths = schema
+        .keys()
+        .filter(|(path, presence, _)| *presence != Presence::Forbidden && !path.is_empty())
+        .filter(|(path, _, _)| {
+            path.segments()
+                .iter()
+                .all(|segment| matches!(segment, PathSegment::Key(..)))
+        })
+        .collect::<Vec<_>>();
+    let is_table = |path: &KeyPath| {
+        paths
+            .iter()
+            .any(|(other, _, _)| other.len() > path.len() && other.starts_with(path))
+    };
+
+    sections.push((KeyPath::new(), Vec::new()));
+    for &(path, presence, kind) in &paths {
+        if is_table(path) {
+            continue;
+        }
+        let table = path.parent().unwrap();
+        let key = match path.last() {
+            Some(PathSegment::Key(key)) => &key[..],
+            _ => continue,
+        };
+        let i = match sections.iter().position(|(t, _)| *t == table) {
+            Some(i) => i,
+            None => {
+                sections.push((table, Vec::new()));
+                sections.len() - 1
+            }
+        };
+        sections[i].1.push((key, presence, kind));
+    }
+
+    let mut dst = String::new();
+    for (table, keys) in &sections {
+        if keys.is_empty() {
+            continue;
+        }
+        let required = keys
+            .iter()
+            .any(|(_, presence, _)| *presence == Presence::Required)
+            || paths
+                .iter()
+                .any(|(path, presence, _)| *path == table && *presence == Presence::Required);
+        if !table.is_empty() {
+            if !dst.is_empty() {
+                dst.push('\n');
+            }
+            if !required {
+                dst.push_str("# ");
+            }
+            dst.push_str(&format!("[{}]\n", table));
+        }
+        for (key, presence, kind) in keys {
+            if *presence != Presence::Required {
+                dst.push_str("# ");
+            }
+            let key = KeyPath::from(vec![PathSegment::Key(key.to_string())]);
+            dst.push_str(&format!("{} = {}\n", key, placeholder(*kind)));
+        }
+    }
+    dst
+}
+
+/// Returns `value` serialized as TOML with every line commented out.
+///
+/// ```rust
+/// use serde_derive::Serialize;
+///
+/// #[derive(Serialize)]
+/// struct Config {
+///     workers: u32,
+///     log: Log,
+/// }
+///
+/// #[derive(Serialize)]
+/// struct Log {
+///     level: String,
+/// }
+///
+/// let defaults = Config { workers: 4, log: Log { level: "info".to_string() } };
+/// assert_eq!(toml::skeleton::from_value(&defaults).unwrap(), "\
+/// ## workers = 4
+///
+/// ## [log]
+/// ## level = \"info\"
+/// ");
+/// ```
+pub fn from_value<T: ?Sized + ser::Serialize>(value: &T) -> Result<String, crate::ser::Error> {
+    let src = crate::to_string(value)?;
+    let mut dst = String::with_capacity(src.len() + src.len() / 8);
+    for line in src.lines() {
+        if !line.is_empty() {
+            dst.push_str("# ");
+        }
+        dst.push_str(line);
+        dst.push('\n');
+    }
+    Ok(dst)
+}
+
+fn placeholder(kind: Option<ValueKind>) -> &'static str {
+    match kind {
+        Some(ValueKind::String) | None => "\"\"",
+        Some(ValueKind::Integer) => "0",
+        Some(ValueKind::RawNumber) => "0",
+        Some(ValueKind::Float) => "0.0",
+        Some(ValueKind::Boolean) => "false",
+        Some(ValueKind::Datetime) => "1979-05-27T07:32:00Z",
+        Some(ValueKind::Array) => "[]",
+        Some(ValueKind::Table) => "{}",
+    }
+}
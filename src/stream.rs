@@ -0,0 +1,106 @@
+//! Several TOML documents in one input.
+//!
+//! Some tools keep more than one document in a single file or stream, with a
+//! line consisting only of a separator such as `+++` between them. The
+//! [`documents`] function splits such input and parses each document in turn:
+//!
+//! ```rust
+//! let input = "title = 'one'\n+++\ntitle = 'two'\n";
+//!
+//! let titles = toml::stream::documents(input, "+++")
+//!     .map(|doc| doc.unwrap().table()["title"].as_str().unwrap().to_string())
+//!     .collect::<Vec<_>>();
+//! assert_eq!(titles, ["one", "two"]);
+//! ```
+//!
+//! [`documents`]: fn.documents.html
+
+use crate::de;
+use crate::value::Table;
+
+/// Returns an iterator over the documents in `input`, which are separated by
+/// lines consisting only of `separator`.
+///
+/// Trailing whitespace on a separator line is ignored. Every piece of input
+/// between separators is a document, so input that starts or ends with a
+/// separator line produces an empty first or last document. A document that
+/// fails to parse is reported as an error, with positions relative to the
+/// whole of `input`, and iteration continues with the next document.
+pub fn documents<'a>(input: &'a str, separator: &'a str) -> Documents<'a> {
+    Documents {
+        input,
+        separator,
+        pos: Some(0),
+    }
+}
+
+/// An iterator over the documents of a stream, created by [`documents`].
+///
+/// [`documents`]: fn.documents.html
+#[derive(Debug, Clone)]
+pub struct Documents<'a> {
+    input: &'a str,
+    separator: &'a str,
+    pos: Option<usize>,
+}
+
+/// One document of a stream.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Segment {
+    table: Table,
+    span: (usize, usize),
+}
+
+impl Segment {
+    /// Returns the parsed document.
+    pub fn table(&self) -> &Table {
+        &self.table
+    }
+
+    /// Consumes this segment, returning the parsed document.
+    pub fn into_table(self) -> Table {
+        self.table
+    }
+
+    /// Returns the byte range of the document within the stream, not
+    /// including the separator lines around it.
+    pub fn span(&self) -> (usize, usize) {
+        self.span
+    }
+}
+
+impl<'a> Iterator for Documents<'a> {
+    type Item = Result<Segment, de::Error>;
+
+    fn next(&mut self) -> Option<Result<Segment, de::Error>> {
+        let start = self.pos?;
+        let (end, next) = match find_separator(&self.input[start..], self.separator) {
+            Some((end, next)) => (start + end, Some(start + next)),
+            None => (self.input.len(), None),
+        };
+        self.pos = next;
+
+        let span = (start, end);
+        Some(match crate::from_str(&self.input[start..end]) {
+            Ok(table) => Ok(Segment { table, span }),
+            Err(mut e) => {
+                e.relocate(self.input, start);
+                Err(e)
+            }
+        })
+    }
+}
+
+/// Finds the first line of `input` that is `separator`, returning the offset
+/// of the start of that line and of the line after it.
+pub(crate) fn find_separator(input: &str, separator: &str) -> Option<(usize, usize)> {
+    let mut start = 0;
+    for line in input.split_inclusive('\n') {
+        let end = start + line.len();
+        if line.trim_end() == separator {
+            return Some((start, end));
+        }
+        start = end;
+    }
+    None
+}
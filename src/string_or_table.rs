@@ -0,0 +1,228 @@
+//! Values written either as a string or as a table.
+//!
+//! A common TOML idiom is to let a value with one important field be written
+//! as just that field, as a string, and as a table when more is needed.
+//! Cargo dependencies are the best known example:
+//!
+//! ```toml
+//! serde = "1.0"
+//! rand = { version = "0.8", features = ["small_rng"] }
+//! ```
+//!
+//! Implementing [`Shorthand`] for the table form of such a value lets it be
+//! read from either form with [`StringOrTable`], or with this module's
+//! `deserialize` and `serialize` functions through `#[serde(with)]`. When
+//! written, the string form is used whenever only the primary field is set.
+//!
+//! ```rust
+//! use serde_derive::{Deserialize, Serialize};
+//! use toml::string_or_table::Shorthand;
+//!
+//! #[derive(Debug, Default, PartialEq, Deserialize, Serialize)]
+//! #[serde(default)]
+//! struct Tool {
+//!     version: String,
+//!     #[serde(skip_serializing_if = "Vec::is_empty")]
+//!     args: Vec<String>,
+//! }
+//!
+//! impl Shorthand for Tool {
+//!     fn from_shorthand(version: String) -> Tool {
+//!         Tool { version, ..Tool::default() }
+//!     }
+//!
+//!     fn shorthand(&self) -> Option<&str> {
+//!         if self.args.is_empty() { Some(&self.version) } else { None }
+//!     }
+//! }
+//!
+//! #[derive(Debug, Deserialize, Serialize)]
+//! struct Config {
+//!     #[serde(with = "toml::string_or_table")]
+//!     linter: Tool,
+//!     #[serde(with = "toml::string_or_table")]
+//!     formatter: Tool,
+//! }
+//!
+//! let config: Config = toml::from_str(r#"
+//!     linter = "1.2"
+//!     formatter = { version = "0.4", args = ["--check"] }
+//! "#).unwrap();
+//! assert_eq!(config.linter, Tool::from_shorthand("1.2".to_string()));
+//! assert_eq!(config.formatter.args, ["--check"]);
+//!
+//! assert_eq!(
+//!     toml::to_string(&config).unwrap(),
+//!     "linter = \"1.2\"\n\n[formatter]\nversion = \"0.4\"\nargs = [\"--check\"]\n",
+//! );
+//! ```
+//!
+//! [`Shorthand`]: trait.Shorthand.html
+//! [`StringOrTable`]: struct.StringOrTable.html
+
+use std::fmt;
+use std::marker::PhantomData;
+use std::ops::{Deref, DerefMut};
+
+use serde::de::{self, Deserialize, Deserializer, MapAccess, Visitor};
+use serde::ser::{Serialize, Serializer};
+
+/// A type that can also be written as a string holding its primary field.
+pub trait Shorthand: Sized {
+    /// Builds the value from its string form, with only the primary field
+    /// set.
+    fn from_shorthand(s: String) -> Self;
+
+    /// Returns the primary field if no other field is set, so that the value
+    /// can be written as a string, or `None` if it must be written as a
+    /// table.
+    fn shorthand(&self) -> Option<&str>;
+}
+
+/// A value read from either a string or a table, and written as a string
+/// when possible.
+///
+/// This is the wrapper form of this module's `deserialize` and `serialize`
+/// functions, for use where `#[serde(with)]` cannot be, such as the values
+/// of a map.
+///
+/// ```rust
+/// use serde_derive::Deserialize;
+/// use std::collections::BTreeMap;
+/// use toml::string_or_table::{Shorthand, StringOrTable};
+///
+/// #[derive(Debug, Default, Deserialize)]
+/// #[serde(default)]
+/// struct Mirror {
+///     url: String,
+///     priority: u32,
+/// }
+///
+/// impl Shorthand for Mirror {
+///     fn from_shorthand(url: String) -> Mirror {
+///         Mirror { url, ..Mirror::default() }
+///     }
+///
+///     fn shorthand(&self) -> Option<&str> {
+///         if self.priority == 0 { Some(&self.url) } else { None }
+///     }
+/// }
+///
+/// let mirrors: BTreeMap<String, StringOrTable<Mirror>> = toml::from_str(r#"
+///     eu = "https://eu.example.com"
+///     us = { url = "https://us.example.com", priority = 1 }
+/// "#).unwrap();
+/// assert_eq!(mirrors["eu"].url, "https://eu.example.com");
+/// assert_eq!(mirrors["us"].priority, 1);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct StringOrTable<T>(pub T);
+
+impl<T> StringOrTable<T> {
+    /// Consumes the wrapper, returning the value.
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> From<T> for StringOrTable<T> {
+    fn from(value: T) -> StringOrTable<T> {
+        StringOrTable(value)
+    }
+}
+
+impl<T> Deref for StringOrTable<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> DerefMut for StringOrTable<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+}
+
+impl<'de, T> Deserialize<'de> for StringOrTable<T>
+where
+    T: Shorthand + Deserialize<'de>,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserialize(deserializer).map(StringOrTable)
+    }
+}
+
+impl<T> Serialize for StringOrTable<T>
+where
+    T: Shorthand + Serialize,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serialize(&self.0, serializer)
+    }
+}
+
+/// Reads a value written either as a string or as a table, for use with
+/// `#[serde(deserialize_with = "toml::string_or_table::deserialize")]`.
+pub fn deserialize<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Shorthand + Deserialize<'de>,
+{
+    struct StringOrTableVisitor<T>(PhantomData<T>);
+
+    impl<'de, T> Visitor<'de> for StringOrTableVisitor<T>
+    where
+        T: Shorthand + Deserialize<'de>,
+    {
+        type Value = T;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+            formatter.write_str("a string or a table")
+        }
+
+        fn visit_str<E>(self, s: &str) -> Result<T, E>
+        where
+            E: de::Error,
+        {
+            Ok(T::from_shorthand(s.to_string()))
+        }
+
+        fn visit_string<E>(self, s: String) -> Result<T, E>
+        where
+            E: de::Error,
+        {
+            Ok(T::from_shorthand(s))
+        }
+
+        fn visit_map<A>(self, map: A) -> Result<T, A::Error>
+        where
+            A: MapAccess<'de>,
+        {
+            T::deserialize(de::value::MapAccessDeserializer::new(map))
+        }
+    }
+
+    deserializer.deserialize_any(StringOrTableVisitor(PhantomData))
+}
+
+/// Writes a value as a string if only its primary field is set and as a
+/// table otherwise, for use with
+/// `#[serde(serialize_with = "toml::string_or_table::serialize")]`.
+pub fn serialize<S, T>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+    T: Shorthand + Serialize,
+{
+    match value.shorthand() {
+        Some(s) => serializer.serialize_str(s),
+        None => value.serialize(serializer),
+    }
+}
@@ -0,0 +1,229 @@
+//! Rendering of templates with `{{placeholder}}` substitutions.
+//!
+//! A template is an ordinary TOML table whose string values may contain
+//! placeholders naming a key path in a second table of variables. See
+//! [`render`] for the substitution rules.
+//!
+//! [`render`]: fn.render.html
+
+use std::error;
+use std::fmt;
+
+use crate::path::KeyPath;
+use crate::value::{Table, Value};
+
+/// Substitutes `{{var.path}}` placeholders in the string values of
+/// `template` with values looked up in `vars`.
+///
+/// A string consisting of nothing but a single placeholder is replaced by
+/// the variable's value as-is, so `port = "{{server.port}}"` renders to an
+/// integer if `server.port` is one. Placeholders embedded in longer strings
+/// are replaced by the text of the variable, which must then be a string,
+/// number, boolean or datetime. Keys are never substituted.
+///
+/// Every placeholder that could not be resolved is reported in the error,
+/// not just the first one.
+///
+/// ```rust
+/// let template: toml::value::Table = toml::from_str(r#"
+///     url = "http://{{host}}:{{port}}/"
+///     port = "{{port}}"
+/// "#).unwrap();
+/// let vars: toml::value::Table = toml::from_str("host = 'example.com'\nport = 8080").unwrap();
+///
+/// let rendered = toml::render(&template, &vars).unwrap();
+/// assert_eq!(rendered["url"].as_str(), Some("http://example.com:8080/"));
+/// assert_eq!(rendered["port"].as_integer(), Some(8080));
+/// ```
+pub fn render(template: &Table, vars: &Table) -> Result<Table, RenderError> {
+    let mut failures = Vec::new();
+    let mut path = KeyPath::new();
+    let table = render_table(template, vars, &mut path, &mut failures);
+    if failures.is_empty() {
+        Ok(table)
+    } else {
+        Err(RenderError { failures })
+    }
+}
+
+/// Errors that can occur when rendering a template.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RenderError {
+    failures: Vec<Failure>,
+}
+
+/// A single placeholder that could not be substituted.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Failure {
+    path: KeyPath,
+    placeholder: String,
+    kind: FailureKind,
+}
+
+/// The reason a placeholder could not be substituted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FailureKind {
+    /// The placeholder names a key that does not exist in the variables.
+    Unresolved,
+    /// The placeholder is embedded in a longer string but names an array or
+    /// a table.
+    NotScalar,
+    /// A `{{` was not followed by a matching `}}`.
+    Unterminated,
+}
+
+impl RenderError {
+    /// Returns every placeholder that failed to render, in document order.
+    pub fn failures(&self) -> &[Failure] {
+        &self.failures
+    }
+}
+
+impl Failure {
+    /// Returns the path of the template value containing the placeholder.
+    pub fn path(&self) -> &KeyPath {
+        &self.path
+    }
+
+    /// Returns the text between the braces of the placeholder, trimmed.
+    pub fn placeholder(&self) -> &str {
+        &self.placeholder
+    }
+
+    /// Returns why the placeholder could not be substituted.
+    pub fn kind(&self) -> FailureKind {
+        self.kind
+    }
+}
+
+fn render_table(
+    template: &Table,
+    vars: &Table,
+    path: &mut KeyPath,
+    failures: &mut Vec<Failure>,
+) -> Table {
+    let mut table = Table::with_capacity(template.len());
+    for (key, value) in template {
+        path.push_key(key.clone());
+        table.insert(key.clone(), render_value(value, vars, path, failures));
+        path.pop();
+    }
+    table
+}
+
+fn render_value(
+    value: &Value,
+    vars: &Table,
+    path: &mut KeyPath,
+    failures: &mut Vec<Failure>,
+) -> Value {
+    match *value {
+        Value::String(ref s) => render_str(s, vars, path, failures),
+        Value::Array(ref array) => {
+            let mut out = Vec::with_capacity(array.len());
+            for (i, value) in array.iter().enumerate() {
+                path.push_index(i);
+                out.push(render_value(value, vars, path, failures));
+                path.pop();
+            }
+            Value::Array(out)
+        }
+        Value::Table(ref table) => Value::Table(render_table(table, vars, path, failures)),
+        ref other => other.clone(),
+    }
+}
+
+fn render_str(s: &str, vars: &Table, path: &KeyPath, failures: &mut Vec<Failure>) -> Value {
+    let mut fail = |placeholder: &str, kind| {
+        failures.push(Failure {
+            path: path.clone(),
+            placeholder: placeholder.trim().to_string(),
+            kind,
+        })
+    };
+    let lookup = |name: &str| name.trim().parse::<KeyPath>().ok()?.lookup_in(vars);
+
+    if let Some(name) = whole_placeholder(s) {
+        return match lookup(name) {
+            Some(value) => value.clone(),
+            None => {
+                fail(name, FailureKind::Unresolved);
+                Value::String(s.to_string())
+            }
+        };
+    }
+
+    let mut out = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(start) = rest.find("{{") {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let end = match after.find("}}") {
+            Some(end) => end,
+            None => {
+                fail(after, FailureKind::Unterminated);
+                rest = &rest[start..];
+                break;
+            }
+        };
+        let name = &after[..end];
+        match lookup(name) {
+            Some(Value::String(s)) => out.push_str(s),
+            Some(Value::Array(..)) | Some(Value::Table(..)) => {
+                fail(name, FailureKind::NotScalar);
+                out.push_str(&rest[start..start + end + 4]);
+            }
+            Some(scalar) => out.push_str(&scalar.to_string()),
+            None => {
+                fail(name, FailureKind::Unresolved);
+                out.push_str(&rest[start..start + end + 4]);
+            }
+        }
+        rest = &after[end + 2..];
+    }
+    out.push_str(rest);
+    Value::String(out)
+}
+
+fn whole_placeholder(s: &str) -> Option<&str> {
+    let inner = s.strip_prefix("{{")?.strip_suffix("}}")?;
+    if inner.contains("{{") || inner.contains("}}") {
+        None
+    } else {
+        Some(inner)
+    }
+}
+
+impl fmt::Display for FailureKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            FailureKind::Unresolved => "unresolved placeholder".fmt(f),
+            FailureKind::NotScalar => "placeholder does not name a scalar value".fmt(f),
+            FailureKind::Unterminated => "unterminated placeholder".fmt(f),
+        }
+    }
+}
+
+impl fmt::Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} `{{{{{}}}}}` in `{}`",
+            self.kind, self.placeholder, self.path
+        )
+    }
+}
+
+impl fmt::Display for RenderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, failure) in self.failures.iter().enumerate() {
+            if i > 0 {
+                f.write_str("; ")?;
+            }
+            failure.fmt(f)?;
+        }
+        Ok(())
+    }
+}
+
+impl error::Error for RenderError {}
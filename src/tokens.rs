@@ -1,5 +1,6 @@
 use std::borrow::Cow;
 use std::char;
+use std::mem;
 use std::str;
 use std::string;
 use std::string::String as StdString;
@@ -67,32 +68,52 @@ pub enum Error {
 pub struct Tokenizer<'a> {
     input: &'a str,
     chars: CrlfFold<'a>,
+    /// Buffer strings with escapes are decoded into, kept between strings
+    /// so that it only grows a few times per document. It is empty between
+    /// strings, so that cloning the tokenizer to peek copies none of it.
+    scratch: StdString,
 }
 
+/// The characters of the input and their offsets, with `\r\n` read as a
+/// single `\n`. ASCII, which most documents are almost entirely made of, is
+/// read a byte at a time without decoding.
 #[derive(Clone)]
 struct CrlfFold<'a> {
-    chars: str::CharIndices<'a>,
+    input: &'a str,
+    pos: usize,
 }
 
+/// The largest scratch buffer a tokenizer keeps between strings.
+const MAX_SCRATCH: usize = 64 * 1024;
+
+/// A string value being read. Until its first escape it is the input from
+/// the given offset on, and after that it is decoded into the scratch buffer
+/// of the tokenizer, the text from the given offset on not copied yet.
 #[derive(Debug)]
 enum MaybeString {
     NotEscaped(usize),
-    Owned(string::String),
+    Owned(string::String, usize),
 }
 
 impl<'a> Tokenizer<'a> {
     pub fn new(input: &'a str) -> Tokenizer<'a> {
         let mut t = Tokenizer {
             input,
-            chars: CrlfFold {
-                chars: input.char_indices(),
-            },
+            chars: CrlfFold { input, pos: 0 },
+            scratch: StdString::new(),
         };
         // Eat utf-8 BOM
         t.eatc('\u{feff}');
         t
     }
 
+    /// Starts over on `input`, keeping the scratch buffer.
+    pub fn reset(&mut self, input: &'a str) {
+        self.input = input;
+        self.chars = CrlfFold { input, pos: 0 };
+        self.eatc('\u{feff}');
+    }
+
     pub fn next(&mut self) -> Result<Option<(Span, Token<'a>)>, Error> {
         let (start, token) = match self.one() {
             Some((start, '\n')) => (start, Newline),
@@ -212,9 +233,7 @@ impl<'a> Tokenizer<'a> {
     }
 
     pub fn eat_whitespace(&mut self) -> Result<(), Error> {
-        while self.eatc(' ') || self.eatc('\t') {
-            // ...
-        }
+        self.chars.skip_bytes(|b| b == b' ' || b == b'\t');
         Ok(())
     }
 
@@ -258,11 +277,7 @@ impl<'a> Tokenizer<'a> {
     }
 
     pub fn current(&mut self) -> usize {
-        self.chars
-            .clone()
-            .next()
-            .map(|i| i.0)
-            .unwrap_or_else(|| self.input.len())
+        self.chars.pos
     }
 
     pub fn input(&self) -> &'a str {
@@ -270,19 +285,14 @@ impl<'a> Tokenizer<'a> {
     }
 
     fn whitespace_token(&mut self, start: usize) -> Token<'a> {
-        while self.eatc(' ') || self.eatc('\t') {
-            // ...
-        }
+        self.chars.skip_bytes(|b| b == b' ' || b == b'\t');
         Whitespace(&self.input[start..self.current()])
     }
 
     fn comment_token(&mut self, start: usize) -> Token<'a> {
-        while let Some((_, ch)) = self.chars.clone().next() {
-            if ch != '\t' && (ch < '\u{20}' || ch > '\u{10ffff}') {
-                break;
-            }
-            self.one();
-        }
+        // Every character but the control characters other than tab, which
+        // are all ASCII, may appear in a comment.
+        self.chars.skip_bytes(|b| b == b'\t' || b >= 0x20);
         Comment(&self.input[start..self.current()])
     }
 
@@ -314,16 +324,17 @@ impl<'a> Tokenizer<'a> {
         let mut n = 0;
         'outer: loop {
             n += 1;
+            if n > 1 {
+                self.skip_plain_string(delim);
+            }
             match self.one() {
                 Some((i, '\n')) => {
                     if multiline {
-                        if self.input.as_bytes()[i] == b'\r' {
-                            val.to_owned(&self.input[..i]);
-                        }
                         if n == 1 {
                             val = MaybeString::NotEscaped(self.current());
-                        } else {
-                            val.push('\n');
+                        } else if self.input.as_bytes()[i] == b'\r' {
+                            let resume = self.current();
+                            val.replace(self, i, Some('\n'), resume);
                         }
                         continue;
                     } else {
@@ -332,27 +343,20 @@ impl<'a> Tokenizer<'a> {
                 }
                 Some((mut i, ch)) if ch == delim => {
                     if multiline {
-                        if !self.eatc(delim) {
-                            val.push(delim);
-                            continue 'outer;
-                        }
-                        if !self.eatc(delim) {
-                            val.push(delim);
-                            val.push(delim);
+                        if !self.eatc(delim) || !self.eatc(delim) {
                             continue 'outer;
                         }
                         if self.eatc(delim) {
-                            val.push(delim);
                             i += 1;
                         }
                         if self.eatc(delim) {
-                            val.push(delim);
                             i += 1;
                         }
                     }
+                    let val = val.finish(self, i);
                     return Ok(String {
                         src: &self.input[start..self.current()],
-                        val: val.into_cow(&self.input[..i]),
+                        val,
                         multiline,
                     });
                 }
@@ -362,10 +366,20 @@ impl<'a> Tokenizer<'a> {
         }
     }
 
+    /// Skips the characters of a string that are kept as they are, which is
+    /// every character but control characters, the delimiter and, in a basic
+    /// string, a backslash.
+    fn skip_plain_string(&mut self, delim: char) {
+        let delim = delim as u8;
+        let escape = if delim == b'"' { b'\\' } else { delim };
+        self.chars.skip_bytes(|b| {
+            b >= 0x80 || b == b'\t' || (b >= 0x20 && b != 0x7f && b != delim && b != escape)
+        });
+    }
+
     fn literal_string(&mut self, start: usize) -> Result<Token<'a>, Error> {
-        self.read_string('\'', start, &mut |_me, val, _multi, i, ch| {
+        self.read_string('\'', start, &mut |_me, _val, _multi, i, ch| {
             if ch == '\u{09}' || ('\u{20}' <= ch && ch <= '\u{10ffff}' && ch != '\u{7f}') {
-                val.push(ch);
                 Ok(())
             } else {
                 Err(Error::InvalidCharInString(i, ch))
@@ -376,18 +390,17 @@ impl<'a> Tokenizer<'a> {
     fn basic_string(&mut self, start: usize) -> Result<Token<'a>, Error> {
         self.read_string('"', start, &mut |me, val, multi, i, ch| match ch {
             '\\' => {
-                val.to_owned(&me.input[..i]);
-                match me.chars.next() {
-                    Some((_, '"')) => val.push('"'),
-                    Some((_, '\\')) => val.push('\\'),
-                    Some((_, 'b')) => val.push('\u{8}'),
-                    Some((_, 'f')) => val.push('\u{c}'),
-                    Some((_, 'n')) => val.push('\n'),
-                    Some((_, 'r')) => val.push('\r'),
-                    Some((_, 't')) => val.push('\t'),
+                let decoded = match me.chars.next() {
+                    Some((_, '"')) => Some('"'),
+                    Some((_, '\\')) => Some('\\'),
+                    Some((_, 'b')) => Some('\u{8}'),
+                    Some((_, 'f')) => Some('\u{c}'),
+                    Some((_, 'n')) => Some('\n'),
+                    Some((_, 'r')) => Some('\r'),
+                    Some((_, 't')) => Some('\t'),
                     Some((i, c @ 'u')) | Some((i, c @ 'U')) => {
                         let len = if c == 'u' { 4 } else { 8 };
-                        val.push(me.hex(start, i, len)?);
+                        Some(me.hex(start, i, len)?)
                     }
                     Some((i, c @ ' ')) | Some((i, c @ '\t')) | Some((i, c @ '\n')) if multi => {
                         if c != '\n' {
@@ -413,14 +426,16 @@ impl<'a> Tokenizer<'a> {
                                 _ => break,
                             }
                         }
+                        None
                     }
                     Some((i, c)) => return Err(Error::InvalidEscape(i, c)),
                     None => return Err(Error::UnterminatedString(start)),
-                }
+                };
+                let resume = me.current();
+                val.replace(me, i, decoded, resume);
                 Ok(())
             }
             ch if ch == '\u{09}' || ('\u{20}' <= ch && ch <= '\u{10ffff}' && ch != '\u{7f}') => {
-                val.push(ch);
                 Ok(())
             }
             _ => Err(Error::InvalidCharInString(i, ch)),
@@ -444,12 +459,7 @@ impl<'a> Tokenizer<'a> {
     }
 
     fn keylike(&mut self, start: usize) -> Token<'a> {
-        while let Some((_, ch)) = self.peek_one() {
-            if !is_keylike(ch) {
-                break;
-            }
-            self.one();
-        }
+        self.chars.skip_bytes(|b| is_keylike(b as char));
         Keylike(&self.input[start..self.current()])
     }
 
@@ -481,44 +491,74 @@ impl<'a> Tokenizer<'a> {
     }
 }
 
+impl<'a> CrlfFold<'a> {
+    /// Skips bytes for as long as `f` returns true for them.
+    ///
+    /// For this to stop on a character boundary, `f` must either return false
+    /// for every byte of a multi-byte character, which are all `0x80` or
+    /// above, or true for all of them.
+    fn skip_bytes<F: Fn(u8) -> bool>(&mut self, f: F) {
+        let bytes = self.input.as_bytes();
+        while self.pos < bytes.len() && f(bytes[self.pos]) {
+            self.pos += 1;
+        }
+    }
+}
+
 impl<'a> Iterator for CrlfFold<'a> {
     type Item = (usize, char);
 
     fn next(&mut self) -> Option<(usize, char)> {
-        self.chars.next().map(|(i, c)| {
-            if c == '\r' {
-                let mut attempt = self.chars.clone();
-                if let Some((_, '\n')) = attempt.next() {
-                    self.chars = attempt;
-                    return (i, '\n');
-                }
+        let i = self.pos;
+        let bytes = self.input.as_bytes();
+        let b = *bytes.get(i)?;
+        if b < 0x80 {
+            self.pos += 1;
+            if b == b'\r' && bytes.get(i + 1) == Some(&b'\n') {
+                self.pos += 1;
+                return Some((i, '\n'));
             }
-            (i, c)
-        })
+            return Some((i, b as char));
+        }
+        let c = self.input[i..].chars().next().unwrap();
+        self.pos += c.len_utf8();
+        Some((i, c))
     }
 }
 
 impl MaybeString {
-    fn push(&mut self, ch: char) {
-        match *self {
-            MaybeString::NotEscaped(..) => {}
-            MaybeString::Owned(ref mut s) => s.push(ch),
+    /// Replaces the input from `at` to `resume` with `ch`, or with nothing,
+    /// copying the text before it into the scratch buffer of `tokens`.
+    fn replace(&mut self, tokens: &mut Tokenizer<'_>, at: usize, ch: Option<char>, resume: usize) {
+        if let MaybeString::NotEscaped(start) = *self {
+            let buf = mem::take(&mut tokens.scratch);
+            *self = MaybeString::Owned(buf, start);
         }
-    }
-
-    fn to_owned(&mut self, input: &str) {
-        match *self {
-            MaybeString::NotEscaped(start) => {
-                *self = MaybeString::Owned(input[start..].to_owned());
+        if let MaybeString::Owned(ref mut buf, ref mut start) = *self {
+            buf.push_str(&tokens.input[*start..at]);
+            if let Some(ch) = ch {
+                buf.push(ch);
             }
-            MaybeString::Owned(..) => {}
+            *start = resume;
         }
     }
 
-    fn into_cow(self, input: &str) -> Cow<'_, str> {
+    /// Returns the string, which ends at `end`, and hands the scratch buffer
+    /// back to `tokens` unless an unusually long string made it grow past
+    /// `MAX_SCRATCH`.
+    fn finish<'a>(self, tokens: &mut Tokenizer<'a>, end: usize) -> Cow<'a, str> {
+        let input = tokens.input;
         match self {
-            MaybeString::NotEscaped(start) => Cow::Borrowed(&input[start..]),
-            MaybeString::Owned(s) => Cow::Owned(s),
+            MaybeString::NotEscaped(start) => Cow::Borrowed(&input[start..end]),
+            MaybeString::Owned(mut buf, start) => {
+                buf.push_str(&input[start..end]);
+                let s = Cow::Owned(StdString::from(buf.as_str()));
+                if buf.capacity() <= MAX_SCRATCH {
+                    buf.clear();
+                    tokens.scratch = buf;
+                }
+                s
+            }
         }
     }
 }
@@ -594,6 +634,8 @@ mod tests {
         t("''''a'''", "'a", true);
         t("'''\n'a\n'''", "'a\n", true);
         t("'''a\n'a\r\n'''", "a\n'a\n", true);
+        t("'caf\u{e9} \u{1f980}'", "caf\u{e9} \u{1f980}", false);
+        t("'''\u{e9}\r\n\u{e9}'''", "\u{e9}\n\u{e9}", true);
     }
 
     #[test]
@@ -633,6 +675,11 @@ mod tests {
         t(r#""\n""#, "\n", false);
         t(r#""\b""#, "\u{8}", false);
         t(r#""a\fa""#, "a\u{c}a", false);
+        t(
+            "\"\u{e9}t\u{e9}\\t\u{1f980}\"",
+            "\u{e9}t\u{e9}\t\u{1f980}",
+            false,
+        );
         t(r#""\"a""#, "\"a", false);
         t("\"\"\"\na\"\"\"", "a", true);
         t("\"\"\"\n\"\"\"", "", true);
@@ -726,6 +773,8 @@ mod tests {
         err("\r", Error::Unexpected(0, '\r'));
         err("'\n", Error::NewlineInString(1));
         err("'\u{0}", Error::InvalidCharInString(1, '\u{0}'));
+        err("'ab\u{e9}\u{7f}", Error::InvalidCharInString(5, '\u{7f}'));
+        err("\"ab\u{e9}\r", Error::InvalidCharInString(5, '\r'));
         err("'", Error::UnterminatedString(0));
         err("\u{0}", Error::Unexpected(0, '\u{0}'));
     }
@@ -736,5 +785,21 @@ mod tests {
         t.next().unwrap().unwrap();
         assert_eq!(t.next(), Err(Error::Unexpected(1, '\u{0}')));
         assert!(t.next().unwrap().is_none());
+
+        let mut t = Tokenizer::new("# \u{e9}t\u{e9}\u{7f}\u{1}");
+        assert_eq!(
+            t.next().unwrap().unwrap().1,
+            Token::Comment("# \u{e9}t\u{e9}\u{7f}")
+        );
+        assert_eq!(t.next(), Err(Error::Unexpected(8, '\u{1}')));
+    }
+
+    #[test]
+    fn scratch_is_empty_between_strings() {
+        let mut t = Tokenizer::new("\"a\\tb\" 'c'");
+        t.next().unwrap().unwrap();
+        assert!(t.scratch.is_empty());
+        assert!(t.scratch.capacity() > 0);
+        assert_eq!(t.clone().scratch.capacity(), 0);
     }
 }
@@ -0,0 +1,290 @@
+//! Strings that are checked while they are read.
+//!
+//! A setting that must look like a URL, an identifier or a version is a
+//! string to TOML. Checking it after the whole document is read loses the
+//! position of the string, so the error cannot point at it. A
+//! [`StringRule`] states the check instead, and [`Validated`] runs it as the
+//! string is deserialized, so that a string breaking the rule fails like a
+//! value of the wrong type, at its line and column:
+//!
+//! ```rust
+//! use serde_derive::Deserialize;
+//! use toml::validated::{Identifier, StringRule, Validated};
+//!
+//! struct Lowercase;
+//!
+//! impl StringRule for Lowercase {
+//!     const EXPECTED: &'static str = "a lowercase string";
+//!
+//!     fn check(s: &str) -> Result<(), String> {
+//!         match s.chars().find(|c| c.is_uppercase()) {
+//!             Some(c) => Err(format!("`{}` is not lowercase", c)),
+//!             None => Ok(()),
+//!         }
+//!     }
+//! }
+//!
+//! #[derive(Debug, Deserialize)]
+//! struct Service {
+//!     name: Validated<Identifier>,
+//!     region: Validated<Lowercase>,
+//! }
+//!
+//! let service: Service = toml::from_str("name = 'api_v2'\nregion = 'eu-west'\n").unwrap();
+//! assert_eq!(service.name.as_str(), "api_v2");
+//!
+//! let err = toml::from_str::<Service>("name = 'api_v2'\nregion = 'EU'\n").unwrap_err();
+//! assert_eq!(
+//!     err.to_string(),
+//!     "invalid string \"EU\": `E` is not lowercase, expected a lowercase string \
+//!      for key `region` at line 2 column 10",
+//! );
+//! ```
+//!
+//! A rule can also check a plain `String` field, with [`deserialize`] called
+//! from a function named by `#[serde(deserialize_with)]`.
+//!
+//! [`StringRule`]: trait.StringRule.html
+//! [`Validated`]: struct.Validated.html
+//! [`deserialize`]: fn.deserialize.html
+
+use std::borrow::Borrow;
+use std::fmt;
+use std::marker::PhantomData;
+use std::ops::Deref;
+
+use serde::de::{self, Deserialize, Deserializer, Visitor};
+use serde::ser::{Serialize, Serializer};
+
+/// A check a string must pass to be read.
+pub trait StringRule {
+    /// Describes the strings that pass, such as "an identifier", for error
+    /// messages.
+    const EXPECTED: &'static str;
+
+    /// Checks `s`, returning why it fails if it does.
+    fn check(s: &str) -> Result<(), String>;
+}
+
+/// A string that passed the rule `R` when it was read.
+///
+/// It derefs to `str`, and is written as the string. It can only be built by
+/// reading it or with `Validated::new`, which checks the rule too.
+pub struct Validated<R> {
+    value: String,
+    rule: PhantomData<fn() -> R>,
+}
+
+impl<R: StringRule> Validated<R> {
+    /// Checks `value` against the rule, returning it as a validated string
+    /// if it passes and why it fails otherwise.
+    pub fn new(value: String) -> Result<Validated<R>, String> {
+        R::check(&value)?;
+        Ok(Validated {
+            value,
+            rule: PhantomData,
+        })
+    }
+}
+
+impl<R> Validated<R> {
+    /// Returns the string.
+    pub fn as_str(&self) -> &str {
+        &self.value
+    }
+
+    /// Consumes the wrapper, returning the string.
+    pub fn into_inner(self) -> String {
+        self.value
+    }
+}
+
+impl<R> Clone for Validated<R> {
+    fn clone(&self) -> Validated<R> {
+        Validated {
+            value: self.value.clone(),
+            rule: PhantomData,
+        }
+    }
+}
+
+impl<R> PartialEq for Validated<R> {
+    fn eq(&self, other: &Validated<R>) -> bool {
+        self.value == other.value
+    }
+}
+
+impl<R> Eq for Validated<R> {}
+
+impl<R> std::hash::Hash for Validated<R> {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.value.hash(state)
+    }
+}
+
+impl<R> fmt::Debug for Validated<R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.value.fmt(f)
+    }
+}
+
+impl<R> fmt::Display for Validated<R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.value.fmt(f)
+    }
+}
+
+impl<R> Deref for Validated<R> {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.value
+    }
+}
+
+impl<R> AsRef<str> for Validated<R> {
+    fn as_ref(&self) -> &str {
+        &self.value
+    }
+}
+
+impl<R> Borrow<str> for Validated<R> {
+    fn borrow(&self) -> &str {
+        &self.value
+    }
+}
+
+impl<'de, R: StringRule> Deserialize<'de> for Validated<R> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserialize::<D, R>(deserializer).map(|value| Validated {
+            value,
+            rule: PhantomData,
+        })
+    }
+}
+
+impl<R> Serialize for Validated<R> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&self.value)
+    }
+}
+
+/// Deserializes a string that passes the rule `R`.
+///
+/// ```rust
+/// use serde::Deserializer;
+/// use serde_derive::Deserialize;
+/// use toml::validated::Url;
+///
+/// fn url<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+///     toml::validated::deserialize::<D, Url>(deserializer)
+/// }
+///
+/// #[derive(Deserialize)]
+/// struct Remote {
+///     #[serde(deserialize_with = "url")]
+///     endpoint: String,
+/// }
+///
+/// assert!(toml::from_str::<Remote>("endpoint = 'https://example.com/api'").is_ok());
+/// assert!(toml::from_str::<Remote>("endpoint = 'example.com'").is_err());
+/// ```
+pub fn deserialize<'de, D, R>(deserializer: D) -> Result<String, D::Error>
+where
+    D: Deserializer<'de>,
+    R: StringRule,
+{
+    struct RuleVisitor<R>(PhantomData<fn() -> R>);
+
+    impl<'de, R: StringRule> Visitor<'de> for RuleVisitor<R> {
+        type Value = String;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+            formatter.write_str(R::EXPECTED)
+        }
+
+        fn visit_str<E>(self, s: &str) -> Result<String, E>
+        where
+            E: de::Error,
+        {
+            self.visit_string(s.to_string())
+        }
+
+        fn visit_string<E>(self, s: String) -> Result<String, E>
+        where
+            E: de::Error,
+        {
+            match R::check(&s) {
+                Ok(()) => Ok(s),
+                Err(why) => Err(de::Error::custom(format_args!(
+                    "invalid string {:?}: {}, expected {}",
+                    s,
+                    why,
+                    R::EXPECTED
+                ))),
+            }
+        }
+    }
+
+    deserializer.deserialize_string(RuleVisitor::<R>(PhantomData))
+}
+
+/// An identifier: an ASCII letter or `_`, followed by ASCII letters,
+/// digits, `_` and `-`.
+pub enum Identifier {}
+
+impl StringRule for Identifier {
+    const EXPECTED: &'static str = "an identifier";
+
+    fn check(s: &str) -> Result<(), String> {
+        let mut chars = s.chars();
+        match chars.next() {
+            None => return Err("it is empty".to_string()),
+            Some(c) if !(c.is_ascii_alphabetic() || c == '_') => {
+                return Err(format!("it starts with `{}`", c))
+            }
+            Some(_) => {}
+        }
+        match chars.find(|&c| !(c.is_ascii_alphanumeric() || c == '_' || c == '-')) {
+            Some(c) => Err(format!("it contains `{}`", c)),
+            None => Ok(()),
+        }
+    }
+}
+
+/// An absolute URL, such as `https://example.com/path`: a scheme, a colon,
+/// and no whitespace.
+///
+/// This checks the shape of a URL, not every rule of the URL standard.
+pub enum Url {}
+
+impl StringRule for Url {
+    const EXPECTED: &'static str = "a URL";
+
+    fn check(s: &str) -> Result<(), String> {
+        let scheme = match s.find(':') {
+            Some(i) => &s[..i],
+            None => return Err("it has no scheme, such as `https:`".to_string()),
+        };
+        let valid_scheme = scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+            && scheme
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '+' || c == '-' || c == '.');
+        if !valid_scheme {
+            return Err(format!("`{}` is not a scheme", scheme));
+        }
+        if s.len() == scheme.len() + 1 {
+            return Err("it has nothing after the scheme".to_string());
+        }
+        match s.chars().find(|c| c.is_whitespace() || c.is_control()) {
+            Some(c) => Err(format!("it contains {:?}", c)),
+            None => Ok(()),
+        }
+    }
+}
@@ -1,6 +1,8 @@
 //! Definition of a TOML value
 
+use std::cmp::Ordering;
 use std::collections::{BTreeMap, HashMap};
+use std::convert::TryFrom;
 use std::fmt;
 use std::hash::Hash;
 use std::mem::discriminant;
@@ -14,6 +16,8 @@ use serde::ser;
 
 use crate::datetime::{self, DatetimeFromString};
 pub use crate::datetime::{Date, Datetime, DatetimeParseError, Offset, Time};
+use crate::display::{Limits, Truncated};
+use crate::path::AccessError;
 
 pub use crate::map::{Entry, Map};
 
@@ -34,6 +38,109 @@ pub enum Value {
     Array(Array),
     /// Represents a TOML table
     Table(Table),
+    /// Represents a TOML integer or float, as it was written, that an `i64`
+    /// or `f64` cannot hold exactly
+    ///
+    /// Values are only read as raw numbers with
+    /// `Deserializer::set_raw_numbers`, and are written back unchanged.
+    RawNumber(RawNumber),
+}
+
+/// The name of the newtype struct a raw number is serialized as.
+pub(crate) const RAW_NUMBER_NAME: &str = "$__toml_private_RawNumber";
+/// The key of the single-entry map a raw number is deserialized as.
+pub(crate) const RAW_NUMBER_FIELD: &str = "$__toml_private_raw_number";
+
+/// The text of a TOML integer or float, kept as it was written.
+///
+/// The TOML serializer writes it back as a bare number; other serializers
+/// see a plain string.
+#[derive(PartialEq, Eq, Hash, Clone, Debug)]
+pub struct RawNumber {
+    text: String,
+}
+
+impl RawNumber {
+    /// Returns the number as it was written.
+    pub fn as_str(&self) -> &str {
+        &self.text
+    }
+
+    /// Consumes this number, returning its text.
+    pub fn into_string(self) -> String {
+        self.text
+    }
+}
+
+impl From<String> for RawNumber {
+    fn from(text: String) -> RawNumber {
+        RawNumber { text }
+    }
+}
+
+impl<'a> From<&'a str> for RawNumber {
+    fn from(text: &'a str) -> RawNumber {
+        RawNumber::from(text.to_string())
+    }
+}
+
+impl fmt::Display for RawNumber {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.text)
+    }
+}
+
+impl ser::Serialize for RawNumber {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: ser::Serializer,
+    {
+        serializer.serialize_newtype_struct(RAW_NUMBER_NAME, &self.text)
+    }
+}
+
+/// The kind of a `Value`, without its payload.
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug)]
+pub enum ValueKind {
+    /// A TOML string
+    String,
+    /// A TOML integer
+    Integer,
+    /// A TOML float
+    Float,
+    /// A TOML boolean
+    Boolean,
+    /// A TOML datetime
+    Datetime,
+    /// A TOML array
+    Array,
+    /// A TOML table
+    Table,
+    /// A TOML number kept as it was written
+    RawNumber,
+}
+
+impl ValueKind {
+    /// Returns a human-readable name of this kind, as used by
+    /// `Value::type_str`.
+    pub fn type_str(self) -> &'static str {
+        match self {
+            ValueKind::String => "string",
+            ValueKind::Integer => "integer",
+            ValueKind::Float => "float",
+            ValueKind::Boolean => "boolean",
+            ValueKind::Datetime => "datetime",
+            ValueKind::Array => "array",
+            ValueKind::Table => "table",
+            ValueKind::RawNumber => "number",
+        }
+    }
+}
+
+impl fmt::Display for ValueKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.type_str())
+    }
 }
 
 /// Type representing a TOML array, payload of the `Value::Array` variant
@@ -44,7 +151,97 @@ pub type Array = Vec<Value>;
 /// to use a LinkedHashMap instead.
 pub type Table = Map<String, Value>;
 
+/// Convert a `T` into a `toml::Value`.
+///
+/// Unlike `to_string`, which always produces a document and so requires `T`
+/// to serialize as a table, this accepts any value TOML can represent,
+/// including bare scalars and arrays. It is equivalent to `Value::try_from`.
+///
+/// ```rust
+/// use toml::Value;
+///
+/// assert_eq!(toml::to_value(&8080).unwrap(), Value::Integer(8080));
+/// assert_eq!(
+///     toml::to_value(&["a", "b"]).unwrap(),
+///     Value::Array(vec![Value::from("a"), Value::from("b")])
+/// );
+/// ```
+pub fn to_value<T>(value: &T) -> Result<Value, crate::ser::Error>
+where
+    T: ?Sized + ser::Serialize,
+{
+    value.serialize(Serializer)
+}
+
+/// Interpret a `toml::Value` as an instance of type `T`.
+///
+/// This is the counterpart of `to_value`, and like it is not restricted to
+/// tables: a scalar can be decoded directly into a primitive type. It is
+/// equivalent to `Value::try_into`.
+///
+/// ```rust
+/// use toml::Value;
+///
+/// let port: u16 = toml::from_value(Value::Integer(8080)).unwrap();
+/// assert_eq!(port, 8080);
+/// assert!(toml::from_value::<u16>(Value::Integer(-1)).is_err());
+/// ```
+pub fn from_value<'de, T>(value: Value) -> Result<T, crate::de::Error>
+where
+    T: de::Deserialize<'de>,
+{
+    de::Deserialize::deserialize(value)
+}
+
+/// Parses two TOML documents and returns whether they hold the same data,
+/// however differently they are formatted.
+///
+/// Comments, whitespace, the order of keys, the choice between headers,
+/// dotted keys and inline tables, and the quoting of strings and keys are all
+/// ignored; see `Value::semantically_eq` for how values are compared. This
+/// is meant for checking that a generated file matches a committed one.
+///
+/// ```rust
+/// let a = "[server]\nport = 80\nname = 'web'\n";
+/// let b = "server = { name = \"web\", port = 80 } # generated\n";
+///
+/// assert!(toml::semantically_equal(a, b).unwrap());
+/// assert!(!toml::semantically_equal(a, "server.port = 8080").unwrap());
+/// ```
+pub fn semantically_equal(a: &str, b: &str) -> Result<bool, crate::de::Error> {
+    let a = a.parse::<Value>()?;
+    let b = b.parse::<Value>()?;
+    Ok(a.semantically_eq(&b))
+}
+
 impl Value {
+    /// Returns whether two values hold the same data.
+    ///
+    /// This is stricter than `==` in treating NaN as equal to itself, so a
+    /// document compares equal to itself, and looser in ignoring the
+    /// difference between `\r\n` and `\n` line endings within strings, which
+    /// depend on how a multi-line string was checked out rather than on
+    /// what it says. Integers and floats are never equal to each other.
+    pub fn semantically_eq(&self, other: &Value) -> bool {
+        match (self, other) {
+            (Value::String(a), Value::String(b)) => {
+                a == b || a.replace("\r\n", "\n") == b.replace("\r\n", "\n")
+            }
+            (Value::Float(a), Value::Float(b)) => a == b || (a.is_nan() && b.is_nan()),
+            (Value::Array(a), Value::Array(b)) => {
+                a.len() == b.len() && a.iter().zip(b).all(|(a, b)| a.semantically_eq(b))
+            }
+            (Value::Table(a), Value::Table(b)) => {
+                a.len() == b.len()
+                    && a.iter().all(|(key, a)| match b.get(key) {
+                        Some(b) => a.semantically_eq(b),
+                        None => false,
+                    })
+            }
+            (a, b) => a == b,
+        }
+    }
+
     /// Convert a `T` into `toml::Value` which is an enum that can represent
     /// any valid TOML data.
     ///
@@ -169,6 +366,14 @@ impl Value {
         self.as_datetime().is_some()
     }
 
+    /// Extracts the text of a number if it is a raw number.
+    pub fn as_raw_number(&self) -> Option<&str> {
+        match *self {
+            Value::RawNumber(ref s) => Some(s.as_str()),
+            _ => None,
+        }
+    }
+
     /// Extracts the array value if it is an array.
     pub fn as_array(&self) -> Option<&Vec<Value>> {
         match *self {
@@ -211,6 +416,163 @@ impl Value {
         self.as_table().is_some()
     }
 
+    /// Extracts the elements of this array as strings, if this is an array
+    /// containing only strings.
+    ///
+    /// ```rust
+    /// let value = toml::Value::from(vec!["a", "b"]);
+    /// assert_eq!(value.as_str_array(), Some(vec!["a", "b"]));
+    /// assert_eq!(toml::Value::from(vec![1]).as_str_array(), None);
+    /// ```
+    pub fn as_str_array(&self) -> Option<Vec<&str>> {
+        self.array_of(Value::as_str)
+    }
+
+    /// Extracts the elements of this array as integers, if this is an array
+    /// containing only integers.
+    pub fn as_int_array(&self) -> Option<Vec<i64>> {
+        self.array_of(Value::as_integer)
+    }
+
+    /// Extracts the elements of this array as floats, if this is an array
+    /// containing only floats.
+    pub fn as_float_array(&self) -> Option<Vec<f64>> {
+        self.array_of(Value::as_float)
+    }
+
+    /// Extracts the elements of this array as booleans, if this is an array
+    /// containing only booleans.
+    pub fn as_bool_array(&self) -> Option<Vec<bool>> {
+        self.array_of(Value::as_bool)
+    }
+
+    /// Extracts the elements of this array as datetimes, if this is an array
+    /// containing only datetimes.
+    pub fn as_datetime_array(&self) -> Option<Vec<&Datetime>> {
+        self.array_of(Value::as_datetime)
+    }
+
+    /// Extracts the elements of this array as tables, if this is an array
+    /// containing only tables.
+    pub fn as_table_array(&self) -> Option<Vec<&Table>> {
+        self.array_of(Value::as_table)
+    }
+
+    /// Extracts the integer value, or returns an error describing the value
+    /// found if it is not an integer.
+    ///
+    /// The `_checked` accessors return errors that read well on their own,
+    /// such as `expected integer, found string "80"`, for configuration
+    /// code that passes them on to a user. `Table::require_checked` adds the
+    /// path of the value to them.
+    pub fn as_i64_checked(&self) -> Result<i64, AccessError> {
+        self.as_integer()
+            .ok_or_else(|| AccessError::wrong_type("integer", self))
+    }
+
+    /// Extracts the integer value converted to `T`, such as `u16` for a
+    /// port, or returns an error if it is not an integer or does not fit in
+    /// `T`.
+    ///
+    /// ```rust
+    /// use toml::Value;
+    ///
+    /// assert_eq!(Value::Integer(443).as_integer_checked::<u16>(), Ok(443));
+    ///
+    /// let err = Value::Integer(-1).as_integer_checked::<u16>().unwrap_err();
+    /// assert_eq!(err.to_string(), "integer `-1` is out of range for u16");
+    ///
+    /// let err = Value::from("443").as_integer_checked::<u16>().unwrap_err();
+    /// assert_eq!(err.to_string(), "expected u16, found string `\"443\"`");
+    /// ```
+    pub fn as_integer_checked<T: TryFrom<i64>>(&self) -> Result<T, AccessError> {
+        let name = std::any::type_name::<T>();
+        let i = self
+            .as_integer()
+            .ok_or_else(|| AccessError::wrong_type(name, self))?;
+        T::try_from(i).map_err(|_| AccessError::out_of_range(name, self))
+    }
+
+    /// Extracts the float value, or returns an error describing the value
+    /// found if it is not a float.
+    pub fn as_f64_checked(&self) -> Result<f64, AccessError> {
+        self.as_float()
+            .ok_or_else(|| AccessError::wrong_type("float", self))
+    }
+
+    /// Extracts the boolean value, or returns an error describing the value
+    /// found if it is not a boolean.
+    pub fn as_bool_checked(&self) -> Result<bool, AccessError> {
+        self.as_bool()
+            .ok_or_else(|| AccessError::wrong_type("boolean", self))
+    }
+
+    /// Extracts the string of this value, or returns an error describing
+    /// the value found if it is not a string.
+    pub fn as_str_checked(&self) -> Result<&str, AccessError> {
+        self.as_str()
+            .ok_or_else(|| AccessError::wrong_type("string", self))
+    }
+
+    /// Extracts the datetime value, or returns an error describing the
+    /// value found if it is not a datetime.
+    pub fn as_datetime_checked(&self) -> Result<&Datetime, AccessError> {
+        self.as_datetime()
+            .ok_or_else(|| AccessError::wrong_type("datetime", self))
+    }
+
+    /// Extracts the array value, or returns an error describing the value
+    /// found if it is not an array.
+    pub fn as_array_checked(&self) -> Result<&Vec<Value>, AccessError> {
+        self.as_array()
+            .ok_or_else(|| AccessError::wrong_type("array", self))
+    }
+
+    /// Extracts the table value, or returns an error describing the value
+    /// found if it is not a table.
+    pub fn as_table_checked(&self) -> Result<&Table, AccessError> {
+        self.as_table()
+            .ok_or_else(|| AccessError::wrong_type("table", self))
+    }
+
+    fn array_of<'a, T>(&'a self, f: fn(&'a Value) -> Option<T>) -> Option<Vec<T>> {
+        self.as_array()?.iter().map(f).collect()
+    }
+
+    /// Returns the kind of the elements of this array, if this is a
+    /// non-empty array whose elements all have the same kind.
+    ///
+    /// ```rust
+    /// use toml::value::ValueKind;
+    ///
+    /// let value: toml::Value = toml::from_str("a = [1, 2]\nb = [1, 'x']").unwrap();
+    /// assert_eq!(value["a"].element_type(), Some(ValueKind::Integer));
+    /// assert_eq!(value["b"].element_type(), None);
+    /// ```
+    pub fn element_type(&self) -> Option<ValueKind> {
+        let mut array = self.as_array()?.iter();
+        let kind = array.next()?.kind();
+        if array.all(|v| v.kind() == kind) {
+            Some(kind)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the kind of this value.
+    pub fn kind(&self) -> ValueKind {
+        match *self {
+            Value::String(..) => ValueKind::String,
+            Value::Integer(..) => ValueKind::Integer,
+            Value::Float(..) => ValueKind::Float,
+            Value::Boolean(..) => ValueKind::Boolean,
+            Value::Datetime(..) => ValueKind::Datetime,
+            Value::Array(..) => ValueKind::Array,
+            Value::Table(..) => ValueKind::Table,
+            Value::RawNumber(..) => ValueKind::RawNumber,
+        }
+    }
+
     /// Tests whether this and another value have the same type.
     pub fn same_type(&self, other: &Value) -> bool {
         discriminant(self) == discriminant(other)
@@ -218,14 +580,124 @@ impl Value {
 
     /// Returns a human-readable representation of the type of this value.
     pub fn type_str(&self) -> &'static str {
+        self.kind().type_str()
+    }
+
+    /// Writes this value as TOML, a document for a table and a bare value
+    /// otherwise, like its `Display` implementation.
+    ///
+    /// Unlike `Display`, which falls back to writing inline tables, this
+    /// returns an error for a value that cannot be written with arrays of
+    /// tables, such as one built in code with an array mixing tables with
+    /// other values.
+    ///
+    /// ```rust
+    /// use toml::value::{Table, Value};
+    ///
+    /// let mut point = Table::new();
+    /// point.insert("x".to_string(), Value::from(1));
+    /// let mut doc = Table::new();
+    /// doc.insert(
+    ///     "points".to_string(),
+    ///     Value::Array(vec![Value::Table(point), Value::from("origin")]),
+    /// );
+    /// let doc = Value::Table(doc);
+    ///
+    /// assert!(doc.try_to_string().is_err());
+    /// assert_eq!(doc.to_string(), "points = [{ x = 1 }, \"origin\"]\n");
+    /// ```
+    pub fn try_to_string(&self) -> Result<String, crate::ser::Error> {
+        crate::ser::to_string(self)
+    }
+
+    /// Returns an object displaying this value on a single line, with long
+    /// strings, arrays and tables cut short according to `limits`.
+    ///
+    /// This is intended for logging configuration without risking dumping
+    /// megabytes of data. The output resembles an inline TOML value, but is
+    /// not necessarily valid TOML.
+    ///
+    /// ```rust
+    /// use toml::display::Limits;
+    ///
+    /// let value = toml::Value::from((1..=100).collect::<Vec<i64>>());
+    /// let limits = Limits { max_array_len: 3, ..Limits::default() };
+    /// assert_eq!(value.display_truncated(limits).to_string(), "[1, 2, 3, … (+97 more)]");
+    /// ```
+    pub fn display_truncated(&self, limits: Limits) -> Truncated<'_> {
+        Truncated {
+            value: self,
+            limits,
+        }
+    }
+
+    /// Renders an indented outline of this value, one line per key with its
+    /// type and a short preview of scalar values.
+    ///
+    /// ```rust
+    /// let value: toml::Value = toml::from_str(r#"
+    ///     name = "app"
+    ///     [server]
+    ///     ports = [80, 443]
+    ///     tls = { enabled = true }
+    /// "#).unwrap();
+    ///
+    /// assert_eq!(value.render_tree(), "\
+    /// name: string = \"app\"
+    /// server: table
+    /// |-- ports: array (2) = [80, 443]
+    /// `-- tls: table
+    ///     `-- enabled: boolean = true
+    /// ");
+    /// ```
+    pub fn render_tree(&self) -> String {
+        crate::display::render_tree(self)
+    }
+
+    /// Estimates the memory this value takes, in bytes: its own size plus
+    /// everything it holds on the heap, such as the capacity of its strings
+    /// and arrays and the entries of its tables, recursively.
+    ///
+    /// The result is an estimate, as the allocator and the map
+    /// implementation add overhead that cannot be measured. It is meant
+    /// for comparing values and enforcing rough memory quotas, such as when
+    /// holding the configurations of many tenants.
+    ///
+    /// ```rust
+    /// use std::mem::size_of;
+    /// use toml::Value;
+    ///
+    /// assert_eq!(Value::Integer(1).deep_size_bytes(), size_of::<Value>());
+    ///
+    /// let name = String::with_capacity(64);
+    /// assert_eq!(Value::String(name).deep_size_bytes(), size_of::<Value>() + 64);
+    ///
+    /// let small: Value = toml::from_str("a = 1").unwrap();
+    /// let large: Value = toml::from_str("a = 1\nb = [1, 2, 3]\n[c]\nd = 'x'").unwrap();
+    /// assert!(small.deep_size_bytes() < large.deep_size_bytes());
+    /// ```
+    pub fn deep_size_bytes(&self) -> usize {
+        std::mem::size_of::<Value>() + self.heap_size()
+    }
+
+    /// Estimates the heap memory held by this value, not counting its own
+    /// size.
+    fn heap_size(&self) -> usize {
         match *self {
-            Value::String(..) => "string",
-            Value::Integer(..) => "integer",
-            Value::Float(..) => "float",
-            Value::Boolean(..) => "boolean",
-            Value::Datetime(..) => "datetime",
-            Value::Array(..) => "array",
-            Value::Table(..) => "table",
+            Value::String(ref s) => s.capacity(),
+            Value::RawNumber(ref s) => s.text.capacity(),
+            Value::Integer(_) | Value::Float(_) | Value::Boolean(_) | Value::Datetime(_) => 0,
+            Value::Array(ref array) => {
+                array.capacity() * std::mem::size_of::<Value>()
+                    + array.iter().map(Value::heap_size).sum::<usize>()
+            }
+            Value::Table(ref table) => {
+                table.heap_size()
+                    + table
+                        .iter()
+                        .map(|(k, v)| k.capacity() + v.heap_size())
+                        .sum::<usize>()
+            }
         }
     }
 }
@@ -302,6 +774,201 @@ impl_into_value!(Boolean: bool);
 impl_into_value!(Datetime: Datetime);
 impl_into_value!(Table: Table);
 
+/// Operations on the values of an `Array`, for tools that maintain lists
+/// such as hosts or features inside TOML files.
+///
+/// `Array` is a `Vec`, so these are brought into scope with
+/// `use toml::value::ArrayExt`:
+///
+/// ```rust
+/// use toml::value::{Array, ArrayExt};
+/// use toml::Value;
+///
+/// let mut hosts: Array = vec!["b".into(), "a".into(), "b".into()];
+/// hosts.concat(vec!["c".into()]).unwrap();
+/// hosts.dedup_values();
+/// hosts.sort_values();
+/// assert_eq!(Value::Array(hosts).to_string(), r#"["a", "b", "c"]"#);
+/// ```
+///
+/// This trait is sealed and not intended for implementation outside of the
+/// `toml` crate.
+pub trait ArrayExt: Sealed {
+    /// Sorts the values of this array.
+    ///
+    /// Values of different kinds are ordered booleans, numbers, strings,
+    /// datetimes, arrays and then tables. Within those:
+    ///
+    /// - `false` comes before `true`.
+    /// - Integers and floats are ordered by their numeric value, an integer
+    ///   before a float equal to it, and NaN after every other number.
+    /// - Strings are ordered by their UTF-8 bytes.
+    /// - Datetimes are ordered offset date-times first, then local
+    ///   date-times, local dates and local times, each chronologically.
+    /// - Arrays are compared value by value, and tables key by key in
+    ///   sorted order, then value by value.
+    ///
+    /// The sort is stable, so values that compare equal, such as `0.0` and
+    /// `-0.0`, keep their order.
+    fn sort_values(&mut self);
+
+    /// Removes every value equal to one before it, keeping the first of each
+    /// and the order of the rest.
+    ///
+    /// Unlike `Vec::dedup`, equal values need not be next to each other.
+    /// Values are equal if they compare equal in the order of
+    /// `sort_values`, which unlike `==` makes NaN equal to itself but
+    /// never an integer equal to a float.
+    fn dedup_values(&mut self);
+
+    /// Appends the values of `other` to this array.
+    ///
+    /// If all the values of this array are of one kind, those of `other`
+    /// must be of that kind too, as an array of integers only takes more
+    /// integers; otherwise an error describing the first value of another
+    /// kind is returned and this array is left unchanged. An empty array,
+    /// or one that already mixes kinds, takes values of any kind.
+    fn concat(&mut self, other: Array) -> Result<(), AccessError>;
+}
+
+impl Sealed for Array {}
+
+impl ArrayExt for Array {
+    fn sort_values(&mut self) {
+        self.sort_by(cmp_values);
+    }
+
+    fn dedup_values(&mut self) {
+        let mut i = 0;
+        while i < self.len() {
+            if self[..i]
+                .iter()
+                .any(|v| cmp_values(v, &self[i]) == Ordering::Equal)
+            {
+                self.remove(i);
+            } else {
+                i += 1;
+            }
+        }
+    }
+
+    fn concat(&mut self, other: Array) -> Result<(), AccessError> {
+        let mut kinds = self.iter().map(Value::kind);
+        if let Some(kind) = kinds.next() {
+            if kinds.all(|k| k == kind) {
+                if let Some(value) = other.iter().find(|v| v.kind() != kind) {
+                    return Err(AccessError::wrong_type(kind.type_str(), value));
+                }
+            }
+        }
+        self.extend(other);
+        Ok(())
+    }
+}
+
+/// The order of `ArrayExt::sort_values`.
+fn cmp_values(a: &Value, b: &Value) -> Ordering {
+    let rank = |v: &Value| match *v {
+        Value::Boolean(..) => 0,
+        Value::Integer(..) | Value::Float(..) => 1,
+        Value::String(..) => 2,
+        Value::Datetime(..) => 3,
+        Value::Array(..) => 4,
+        Value::Table(..) => 5,
+        Value::RawNumber(..) => 1,
+    };
+    match (a, b) {
+        (Value::Boolean(a), Value::Boolean(b)) => a.cmp(b),
+        (Value::Integer(a), Value::Integer(b)) => a.cmp(b),
+        (Value::Integer(a), Value::Float(b)) => cmp_numbers(*a as f64, *b).then(Ordering::Less),
+        (Value::Float(a), Value::Integer(b)) => cmp_numbers(*a, *b as f64).then(Ordering::Greater),
+        (Value::Float(a), Value::Float(b)) => cmp_numbers(*a, *b),
+        (Value::RawNumber(_), _) | (_, Value::RawNumber(_)) if rank(a) == rank(b) => {
+            cmp_raw_numbers(a, b)
+        }
+        (Value::String(a), Value::String(b)) => a.cmp(b),
+        (Value::Datetime(a), Value::Datetime(b)) => a.total_cmp(b),
+        (Value::Array(a), Value::Array(b)) => cmp_seqs(a.iter(), b.iter(), cmp_values),
+        (Value::Table(a), Value::Table(b)) => {
+            let mut a = a.iter().collect::<Vec<_>>();
+            let mut b = b.iter().collect::<Vec<_>>();
+            a.sort_by_key(|(k, _)| *k);
+            b.sort_by_key(|(k, _)| *k);
+            cmp_seqs(a.into_iter(), b.into_iter(), |(ka, va), (kb, vb)| {
+                ka.cmp(kb).then_with(|| cmp_values(va, vb))
+            })
+        }
+        _ => rank(a).cmp(&rank(b)),
+    }
+}
+
+/// Orders two numbers, one of them raw, by their exact values where they
+/// have one.
+fn cmp_raw_numbers(a: &Value, b: &Value) -> Ordering {
+    let decimal = |v: &Value| match *v {
+        Value::Integer(i) => crate::de::decimal_parts(&i.to_string()),
+        Value::Float(f) if f.is_finite() => crate::de::decimal_parts(&format!("{:e}", f)),
+        Value::RawNumber(ref s) => crate::de::decimal_parts(s.as_str()),
+        _ => None,
+    };
+    let approximate = |v: &Value| match *v {
+        Value::Integer(i) => i as f64,
+        Value::Float(f) => f,
+        Value::RawNumber(ref s) => s.as_str().replace('_', "").parse().unwrap_or(f64::NAN),
+        _ => f64::NAN,
+    };
+    let (a, b) = match (decimal(a), decimal(b)) {
+        (Some(a), Some(b)) => (a, b),
+        _ => return cmp_numbers(approximate(a), approximate(b)),
+    };
+    let sign = |(negative, digits, _): &(bool, String, i64)| match (digits.is_empty(), negative) {
+        (true, _) => 0,
+        (false, true) => -1,
+        (false, false) => 1,
+    };
+    let magnitude =
+        |(_, digits, exponent): &(bool, String, i64)| digits.len() as i128 + i128::from(*exponent);
+    match sign(&a).cmp(&sign(&b)) {
+        Ordering::Equal if sign(&a) == 0 => Ordering::Equal,
+        Ordering::Equal => {
+            let ordering = magnitude(&a)
+                .cmp(&magnitude(&b))
+                .then_with(|| a.1.cmp(&b.1));
+            if sign(&a) < 0 {
+                ordering.reverse()
+            } else {
+                ordering
+            }
+        }
+        ordering => ordering,
+    }
+}
+
+/// Orders numbers numerically, with NaN after every other number and equal
+/// to itself.
+fn cmp_numbers(a: f64, b: f64) -> Ordering {
+    match a.partial_cmp(&b) {
+        Some(ordering) => ordering,
+        None => a.is_nan().cmp(&b.is_nan()),
+    }
+}
+
+fn cmp_seqs<T, I, F>(mut a: I, mut b: I, mut cmp: F) -> Ordering
+where
+    I: Iterator<Item = T>,
+    F: FnMut(T, T) -> Ordering,
+{
+    loop {
+        match (a.next(), b.next()) {
+            (Some(x), Some(y)) => match cmp(x, y) {
+                Ordering::Equal => continue,
+                ordering => return ordering,
+            },
+            (x, y) => return x.is_some().cmp(&y.is_some()),
+        }
+    }
+}
+
 /// Types that can be used to index a `toml::Value`
 ///
 /// Currently this is implemented for `usize` to index arrays and `str` to index
@@ -380,11 +1047,26 @@ where
     }
 }
 
+/// Writes the value as TOML. A value that cannot be written with arrays of
+/// tables, such as an array mixing tables with other values, is written with
+/// inline tables instead; see `Value::try_to_string` to detect this.
 impl fmt::Display for Value {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        crate::ser::to_string(self)
-            .expect("Unable to represent value as string")
-            .fmt(f)
+        match self
+            .try_to_string()
+            .or_else(|_| crate::ser::to_string_inline(self))
+        {
+            Ok(s) => s.fmt(f),
+            Err(_) => {
+                let limits = Limits {
+                    max_string_len: usize::MAX,
+                    max_array_len: usize::MAX,
+                    max_table_len: usize::MAX,
+                    max_depth: usize::MAX,
+                };
+                self.display_truncated(limits).fmt(f)
+            }
+        }
     }
 }
 
@@ -395,6 +1077,20 @@ impl FromStr for Value {
     }
 }
 
+/// Returns the order in which a table entry is serialized: plain values
+/// first, then arrays of tables, then tables.
+///
+/// This is kept out of `Value::serialize`, which recurses into tables and
+/// arrays and so should keep its stack frame small.
+#[inline(never)]
+fn entry_group(value: &Value) -> u8 {
+    match *value {
+        Value::Table(_) => 2,
+        Value::Array(ref a) if a.iter().any(Value::is_table) => 1,
+        _ => 0,
+    }
+}
+
 impl ser::Serialize for Value {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
@@ -408,32 +1104,17 @@ impl ser::Serialize for Value {
             Value::Float(f) => serializer.serialize_f64(f),
             Value::Boolean(b) => serializer.serialize_bool(b),
             Value::Datetime(ref s) => s.serialize(serializer),
+            Value::RawNumber(ref s) => s.serialize(serializer),
             Value::Array(ref a) => a.serialize(serializer),
             Value::Table(ref t) => {
                 let mut map = serializer.serialize_map(Some(t.len()))?;
                 // Be sure to visit non-tables first (and also non
                 // array-of-tables) as all keys must be emitted first.
-                for (k, v) in t {
-                    if !v.is_table() && !v.is_array()
-                        || (v
-                            .as_array()
-                            .map(|a| !a.iter().any(|v| v.is_table()))
-                            .unwrap_or(false))
-                    {
-                        map.serialize_entry(k, v)?;
-                    }
-                }
-                for (k, v) in t {
-                    if v.as_array()
-                        .map(|a| a.iter().any(|v| v.is_table()))
-                        .unwrap_or(false)
-                    {
-                        map.serialize_entry(k, v)?;
-                    }
-                }
-                for (k, v) in t {
-                    if v.is_table() {
-                        map.serialize_entry(k, v)?;
+                for group in 0..3 {
+                    for (k, v) in t {
+                        if entry_group(v) == group {
+                            map.serialize_entry(k, v)?;
+                        }
                     }
                 }
                 map.end()
@@ -503,7 +1184,7 @@ impl<'de> de::Deserialize<'de> for Value {
             where
                 V: de::SeqAccess<'de>,
             {
-                let mut vec = Vec::new();
+                let mut vec = Vec::with_capacity(cautious(visitor.size_hint()));
                 while let Some(elem) = visitor.next_element()? {
                     vec.push(elem);
                 }
@@ -515,16 +1196,20 @@ impl<'de> de::Deserialize<'de> for Value {
                 V: de::MapAccess<'de>,
             {
                 let mut key = String::new();
-                let datetime = visitor.next_key_seed(DatetimeOrTable { key: &mut key })?;
-                match datetime {
-                    Some(true) => {
+                let first = visitor.next_key_seed(DatetimeOrTable { key: &mut key })?;
+                match first {
+                    Some(FirstKey::Datetime) => {
                         let date: DatetimeFromString = visitor.next_value()?;
                         return Ok(Value::Datetime(date.value));
                     }
+                    Some(FirstKey::RawNumber) => {
+                        let text: String = visitor.next_value()?;
+                        return Ok(Value::RawNumber(RawNumber::from(text)));
+                    }
                     None => return Ok(Value::Table(Map::new())),
-                    Some(false) => {}
+                    Some(FirstKey::Table) => {}
                 }
-                let mut map = Map::new();
+                let mut map = Map::with_capacity(cautious(visitor.size_hint()) + 1);
                 map.insert(key, visitor.next_value()?);
                 while let Some(key) = visitor.next_key::<String>()? {
                     if let Entry::Vacant(vacant) = map.entry(&key) {
@@ -542,6 +1227,17 @@ impl<'de> de::Deserialize<'de> for Value {
     }
 }
 
+macro_rules! forward_numbers_to_i64 {
+    ($($method:ident)*) => {$(
+        fn $method<V>(self, visitor: V) -> Result<V::Value, crate::de::Error>
+        where
+            V: de::Visitor<'de>,
+        {
+            self.deserialize_i64(visitor)
+        }
+    )*};
+}
+
 impl<'de> de::Deserializer<'de> for Value {
     type Error = crate::de::Error;
 
@@ -555,6 +1251,9 @@ impl<'de> de::Deserializer<'de> for Value {
             Value::Float(n) => visitor.visit_f64(n),
             Value::String(v) => visitor.visit_string(v),
             Value::Datetime(v) => visitor.visit_string(v.to_string()),
+            Value::RawNumber(v) => visitor.visit_map(de::value::MapDeserializer::new(
+                std::iter::once((RAW_NUMBER_FIELD, v.text)),
+            )),
             Value::Array(v) => {
                 let len = v.len();
                 let mut deserializer = SeqDeserializer::new(v);
@@ -619,11 +1318,27 @@ impl<'de> de::Deserializer<'de> for Value {
         visitor.visit_newtype_struct(self)
     }
 
+    fn deserialize_i64<V>(self, visitor: V) -> Result<V::Value, crate::de::Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        match self {
+            Value::RawNumber(s) => crate::de::visit_raw_number(s.as_str(), visitor),
+            value => value.deserialize_any(visitor),
+        }
+    }
+
     serde::forward_to_deserialize_any! {
-        bool u8 u16 u32 u64 i8 i16 i32 i64 f32 f64 char str string unit seq
+        bool char str string unit seq
         bytes byte_buf map unit_struct tuple_struct struct
         tuple ignored_any identifier
     }
+
+    forward_numbers_to_i64! {
+        deserialize_i8 deserialize_i16 deserialize_i32
+        deserialize_u8 deserialize_u16 deserialize_u32 deserialize_u64
+        deserialize_i128 deserialize_u128 deserialize_f32 deserialize_f64
+    }
 }
 
 struct SeqDeserializer {
@@ -711,6 +1426,12 @@ impl<'de> de::MapAccess<'de> for MapDeserializer {
     }
 }
 
+/// Returns the capacity to reserve for a collection of `hint` elements,
+/// capped so that a wrong hint cannot cause a huge allocation up front.
+fn cautious(hint: Option<usize>) -> usize {
+    std::cmp::min(hint.unwrap_or(0), 4096)
+}
+
 impl<'de> de::IntoDeserializer<'de, crate::de::Error> for Value {
     type Deserializer = Self;
 
@@ -773,6 +1494,24 @@ impl ser::Serializer for Serializer {
         }
     }
 
+    fn serialize_i128(self, value: i128) -> Result<Value, crate::ser::Error> {
+        match i64::try_from(value) {
+            Ok(value) => self.serialize_i64(value),
+            Err(_) => Err(crate::ser::Error::IntegerOutOfRange(
+                value.to_string().into(),
+            )),
+        }
+    }
+
+    fn serialize_u128(self, value: u128) -> Result<Value, crate::ser::Error> {
+        match i64::try_from(value) {
+            Ok(value) => self.serialize_i64(value),
+            Err(_) => Err(crate::ser::Error::IntegerOutOfRange(
+                value.to_string().into(),
+            )),
+        }
+    }
+
     fn serialize_f32(self, value: f32) -> Result<Value, crate::ser::Error> {
         self.serialize_f64(value.into())
     }
@@ -815,13 +1554,18 @@ impl ser::Serializer for Serializer {
 
     fn serialize_newtype_struct<T: ?Sized>(
         self,
-        _name: &'static str,
+        name: &'static str,
         value: &T,
     ) -> Result<Value, crate::ser::Error>
     where
         T: ser::Serialize,
     {
-        value.serialize(self)
+        match value.serialize(self)? {
+            Value::String(text) if name == RAW_NUMBER_NAME => {
+                Ok(Value::RawNumber(RawNumber::from(text)))
+            }
+            value => Ok(value),
+        }
     }
 
     fn serialize_newtype_variant<T: ?Sized>(
@@ -1037,8 +1781,24 @@ struct DatetimeOrTable<'a> {
     key: &'a mut String,
 }
 
+/// What the first key of a map read into a `Value` makes of it.
+enum FirstKey {
+    Datetime,
+    RawNumber,
+    Table,
+}
+
+/// Returns what a map starting with `key` stands for.
+fn first_key(key: &str) -> FirstKey {
+    match key {
+        datetime::FIELD => FirstKey::Datetime,
+        RAW_NUMBER_FIELD => FirstKey::RawNumber,
+        _ => FirstKey::Table,
+    }
+}
+
 impl<'a, 'de> de::DeserializeSeed<'de> for DatetimeOrTable<'a> {
-    type Value = bool;
+    type Value = FirstKey;
 
     fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
     where
@@ -1049,33 +1809,31 @@ impl<'a, 'de> de::DeserializeSeed<'de> for DatetimeOrTable<'a> {
 }
 
 impl<'a, 'de> de::Visitor<'de> for DatetimeOrTable<'a> {
-    type Value = bool;
+    type Value = FirstKey;
 
     fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
         formatter.write_str("a string key")
     }
 
-    fn visit_str<E>(self, s: &str) -> Result<bool, E>
+    fn visit_str<E>(self, s: &str) -> Result<FirstKey, E>
     where
         E: de::Error,
     {
-        if s == datetime::FIELD {
-            Ok(true)
-        } else {
+        let first = first_key(s);
+        if let FirstKey::Table = first {
             self.key.push_str(s);
-            Ok(false)
         }
+        Ok(first)
     }
 
-    fn visit_string<E>(self, s: String) -> Result<bool, E>
+    fn visit_string<E>(self, s: String) -> Result<FirstKey, E>
     where
         E: de::Error,
     {
-        if s == datetime::FIELD {
-            Ok(true)
-        } else {
+        let first = first_key(&s);
+        if let FirstKey::Table = first {
             *self.key = s;
-            Ok(false)
         }
+        Ok(first)
     }
 }
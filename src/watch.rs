@@ -0,0 +1,299 @@
+//! Tables that report their changes.
+//!
+//! A program that reloads its configuration while running usually cares
+//! about a few settings: the log level, the size of a pool, the address to
+//! listen on. A [`WatchedTable`] is changed only through methods that take
+//! the path of the value to change, and calls the callbacks registered for
+//! that path with the old and new values, so each part of the program can
+//! react to its own settings without comparing whole documents:
+//!
+//! ```rust
+//! use std::sync::mpsc;
+//!
+//! use toml::watch::WatchedTable;
+//! use toml::Value;
+//!
+//! let mut config = WatchedTable::new(toml::from_str("[log]\nlevel = 'info'\n").unwrap());
+//!
+//! let (tx, rx) = mpsc::channel();
+//! config.watch("log.level", move |path, old, new| {
+//!     tx.send(format!("{}: {:?} -> {:?}", path, old, new)).unwrap();
+//! });
+//!
+//! config.insert("log.level", Value::from("debug")).unwrap();
+//! config.insert("log.file", Value::from("app.log")).unwrap();
+//! config.replace(toml::from_str("[log]\nlevel = 'warn'\n").unwrap());
+//!
+//! let seen = rx.try_iter().collect::<Vec<_>>();
+//! assert_eq!(seen, [
+//!     "log.level: Some(String(\"info\")) -> Some(String(\"debug\"))",
+//!     "log.level: Some(String(\"debug\")) -> Some(String(\"warn\"))",
+//! ]);
+//! ```
+//!
+//! [`WatchedTable`]: struct.WatchedTable.html
+
+use std::fmt;
+use std::ops::Deref;
+
+use crate::path::{KeyPath, LookupError, LookupErrorKind, PathSegment};
+use crate::value::{Table, Value};
+
+type Callback = dyn FnMut(&KeyPath, Option<&Value>, Option<&Value>) + Send;
+
+/// A table that calls registered callbacks when its values change.
+///
+/// It derefs to the table for reading, and is changed with `insert`,
+/// `remove`, `update` and `replace`. A callback registered with `watch` for
+/// a path is called:
+///
+/// - when the value at the path or below it changes, with the path that
+///   changed and its old and new values, `None` standing for no value;
+/// - when a table or array containing the path is replaced or removed as a
+///   whole, with the watched path and the values found at it before and
+///   after, if they differ.
+///
+/// Nothing is called for a change that leaves a value as it was.
+/// Callbacks are called in the order they were registered, after the
+/// change is made.
+pub struct WatchedTable {
+    root: Value,
+    watchers: Vec<Watcher>,
+    next_id: usize,
+}
+
+struct Watcher {
+    id: WatchId,
+    path: KeyPath,
+    callback: Box<Callback>,
+}
+
+/// Identifies a callback registered with `WatchedTable::watch`, to remove it
+/// with `WatchedTable::unwatch`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct WatchId(usize);
+
+impl WatchedTable {
+    /// Wraps `table`, with no callbacks registered.
+    pub fn new(table: Table) -> WatchedTable {
+        WatchedTable {
+            root: Value::Table(table),
+            watchers: Vec::new(),
+            next_id: 0,
+        }
+    }
+
+    /// Returns the table.
+    pub fn table(&self) -> &Table {
+        self.root.as_table().unwrap()
+    }
+
+    /// Consumes the wrapper, returning the table.
+    pub fn into_inner(self) -> Table {
+        match self.root {
+            Value::Table(table) => table,
+            _ => unreachable!(),
+        }
+    }
+
+    /// Registers `callback` to be called with the path, old value and new
+    /// value of every change at or below `path`. The root path watches the
+    /// whole table.
+    pub fn watch<P, F>(&mut self, path: P, callback: F) -> WatchId
+    where
+        P: Into<KeyPath>,
+        F: FnMut(&KeyPath, Option<&Value>, Option<&Value>) + Send + 'static,
+    {
+        let id = WatchId(self.next_id);
+        self.next_id += 1;
+        self.watchers.push(Watcher {
+            id,
+            path: path.into(),
+            callback: Box::new(callback),
+        });
+        id
+    }
+
+    /// Removes the callback registered as `id`, returning whether it was
+    /// registered.
+    pub fn unwatch(&mut self, id: WatchId) -> bool {
+        let len = self.watchers.len();
+        self.watchers.retain(|w| w.id != id);
+        self.watchers.len() != len
+    }
+
+    /// Sets the value at `path`, returning the value it replaces.
+    ///
+    /// Missing tables on the way are created. An error is returned if a
+    /// value on the way is neither a table nor an array, if an index is
+    /// out of bounds, or if `path` is the root path.
+    pub fn insert<P: Into<KeyPath>>(
+        &mut self,
+        path: P,
+        value: Value,
+    ) -> Result<Option<Value>, LookupError> {
+        let path = path.into();
+        self.check_insert(&path)?;
+        let (last, parent) = path.segments().split_last().unwrap();
+        let mut cur = &mut self.root;
+        for segment in parent {
+            cur = match (cur, segment) {
+                (Value::Table(table), PathSegment::Key(key)) => table
+                    .entry(key.clone())
+                    .or_insert_with(|| Value::Table(Table::new())),
+                (Value::Array(array), &PathSegment::Index(index)) => &mut array[index],
+                _ => unreachable!(),
+            };
+        }
+        let old = match (cur, last) {
+            (Value::Table(table), PathSegment::Key(key)) => table.insert(key.clone(), value),
+            (Value::Array(array), &PathSegment::Index(index)) => {
+                Some(std::mem::replace(&mut array[index], value))
+            }
+            _ => unreachable!(),
+        };
+        self.notify(&path, old.as_ref());
+        Ok(old)
+    }
+
+    /// Returns the error `insert` gives for `path`, found before anything is
+    /// changed so that a failed insert leaves no tables created on the way.
+    fn check_insert(&self, path: &KeyPath) -> Result<(), LookupError> {
+        if path.is_empty() {
+            return Err(LookupError::new(path.clone(), 0, LookupErrorKind::Missing));
+        }
+        let error = |segment, kind| LookupError::new(path.clone(), segment, kind);
+        // `None` stands for a table that `insert` would create.
+        let mut cur = Some(&self.root);
+        for (i, segment) in path.segments().iter().enumerate() {
+            cur = match (cur, segment) {
+                (None, PathSegment::Key(_)) => None,
+                (None, PathSegment::Index(_)) => {
+                    return Err(error(i, LookupErrorKind::WrongType("table")))
+                }
+                (Some(Value::Table(table)), PathSegment::Key(key)) => table.get(key),
+                (Some(Value::Array(array)), &PathSegment::Index(index)) => match array.get(index) {
+                    Some(value) => Some(value),
+                    None => return Err(error(i, LookupErrorKind::Missing)),
+                },
+                (Some(value), _) => {
+                    return Err(error(i, LookupErrorKind::WrongType(value.type_str())))
+                }
+            };
+        }
+        Ok(())
+    }
+
+    /// Removes the value at `path`, returning it, or `None` if there is
+    /// none.
+    ///
+    /// Removing an array element moves the elements after it, so the change
+    /// is reported for the whole array.
+    pub fn remove<P: Into<KeyPath>>(&mut self, path: P) -> Option<Value> {
+        let path = path.into();
+        let parent = path.parent()?;
+        // The old value is the removed one, unless the change is reported
+        // for the whole array.
+        let (changed, old, removed) = match path.last() {
+            Some(PathSegment::Key(key)) => match parent.lookup_mut(&mut self.root)? {
+                Value::Table(table) => (path.clone(), None, table.remove(key)?),
+                _ => return None,
+            },
+            Some(&PathSegment::Index(index)) => match parent.lookup_mut(&mut self.root)? {
+                Value::Array(array) if index < array.len() => {
+                    let old = Value::Array(array.clone());
+                    (parent, Some(old), array.remove(index))
+                }
+                _ => return None,
+            },
+            None => return None,
+        };
+        self.notify(&changed, old.as_ref().or(Some(&removed)));
+        Some(removed)
+    }
+
+    /// Changes the value at `path` in place with `f`.
+    ///
+    /// An error is returned, and `f` not called, if there is no value at
+    /// `path`.
+    pub fn update<P, F>(&mut self, path: P, f: F) -> Result<(), LookupError>
+    where
+        P: Into<KeyPath>,
+        F: FnOnce(&mut Value),
+    {
+        let path = path.into();
+        if path.is_empty() {
+            let mut root = self.root.clone();
+            f(&mut root);
+            return match root {
+                Value::Table(table) => {
+                    self.replace(table);
+                    Ok(())
+                }
+                other => Err(LookupError::new(
+                    path,
+                    0,
+                    LookupErrorKind::WrongType(other.type_str()),
+                )),
+            };
+        }
+        self.table().require(&path)?;
+        let value = path.lookup_mut(&mut self.root).unwrap();
+        let old = value.clone();
+        f(value);
+        self.notify(&path, Some(&old));
+        Ok(())
+    }
+
+    /// Replaces the whole table, as when a configuration file is read
+    /// again, calling the callbacks of every watched path whose value
+    /// changed.
+    pub fn replace(&mut self, table: Table) {
+        let old = std::mem::replace(&mut self.root, Value::Table(table));
+        self.notify(&KeyPath::new(), Some(&old));
+    }
+
+    /// Calls the callbacks concerned by the change of the value at `path`
+    /// from `old` to its current value.
+    fn notify(&mut self, path: &KeyPath, old: Option<&Value>) {
+        let new = path.lookup(&self.root);
+        if old == new {
+            return;
+        }
+        for watcher in &mut self.watchers {
+            if path.starts_with(&watcher.path) {
+                (watcher.callback)(path, old, new);
+            } else if watcher.path.starts_with(path) {
+                let rest = KeyPath::from(watcher.path.segments()[path.len()..].to_vec());
+                let old = old.and_then(|v| rest.lookup(v));
+                let new = new.and_then(|v| rest.lookup(v));
+                if old != new {
+                    (watcher.callback)(&watcher.path, old, new);
+                }
+            }
+        }
+    }
+}
+
+impl Deref for WatchedTable {
+    type Target = Table;
+
+    fn deref(&self) -> &Table {
+        self.table()
+    }
+}
+
+impl From<Table> for WatchedTable {
+    fn from(table: Table) -> WatchedTable {
+        WatchedTable::new(table)
+    }
+}
+
+impl fmt::Debug for WatchedTable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WatchedTable")
+            .field("table", self.table())
+            .field("watchers", &self.watchers.len())
+            .finish()
+    }
+}
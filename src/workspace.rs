@@ -0,0 +1,307 @@
+//! Several documents that share values, such as the manifests of a Cargo
+//! workspace.
+//!
+//! A [`Workspace`] holds a root document and any number of member
+//! documents, each under a name such as its file path. A member inherits a
+//! value from the root by writing a table with `workspace = true` in its
+//! place, following Cargo's rules:
+//!
+//! - A dependency, in a `dependencies`, `dev-dependencies` or
+//!   `build-dependencies` table, inherits `workspace.dependencies.<name>`
+//!   of the root. Other keys written next to `workspace = true` are added
+//!   to the inherited table, and its `features` are extended.
+//! - Any other value inherits the value at the same path under
+//!   `workspace` in the root, so `package.version` inherits
+//!   `workspace.package.version`.
+//!
+//! Changes made through the workspace are written to the document that
+//! defines the value:
+//!
+//! ```rust
+//! use toml::workspace::Workspace;
+//! use toml::Value;
+//!
+//! let root = "[workspace.package]\nversion = \"1.2.0\"\n\n[workspace.dependencies]\nserde = \"1.0\"\n";
+//! let app = "[package]\nname = \"app\"\nversion.workspace = true\n\n[dependencies]\nserde = { workspace = true, features = [\"derive\"] }\n";
+//!
+//! let mut workspace = Workspace::new("Cargo.toml", root.parse().unwrap());
+//! workspace.add_member("app/Cargo.toml", app.parse().unwrap());
+//!
+//! let manifest = workspace.resolve("app/Cargo.toml").unwrap();
+//! assert_eq!(manifest["package"]["version"].as_str(), Some("1.2.0"));
+//! assert_eq!(manifest["dependencies"]["serde"]["version"].as_str(), Some("1.0"));
+//!
+//! let written = workspace
+//!     .set("app/Cargo.toml", "package.version", Value::from("1.3.0"))
+//!     .unwrap();
+//! assert_eq!(written, "Cargo.toml");
+//! assert_eq!(
+//!     workspace.root().to_string(),
+//!     "[workspace.package]\nversion = \"1.3.0\"\n\n[workspace.dependencies]\nserde = \"1.0\"\n",
+//! );
+//! ```
+//!
+//! [`Workspace`]: struct.Workspace.html
+
+use crate::de;
+use crate::document::Document;
+use crate::path::{KeyPath, PathSegment};
+use crate::value::{Table, Value};
+
+/// The tables whose entries inherit from `workspace.dependencies`.
+const DEPENDENCY_TABLES: &[&str] = &["dependencies", "dev-dependencies", "build-dependencies"];
+
+/// A root document and the member documents that inherit values from it.
+#[derive(Debug, Clone)]
+pub struct Workspace {
+    // The root document comes first.
+    documents: Vec<(String, Document)>,
+}
+
+impl Workspace {
+    /// Creates a workspace whose root document is `root`, named `name`.
+    pub fn new<S: Into<String>>(name: S, root: Document) -> Workspace {
+        Workspace {
+            documents: vec![(name.into(), root)],
+        }
+    }
+
+    /// Adds `member` to the workspace under `name`, returning the document
+    /// previously held under that name, if any.
+    ///
+    /// The root document cannot be replaced this way; a member named like
+    /// it is never found.
+    pub fn add_member<S: Into<String>>(&mut self, name: S, member: Document) -> Option<Document> {
+        let name = name.into();
+        match self.documents[1..].iter_mut().find(|(n, _)| *n == name) {
+            Some((_, doc)) => Some(std::mem::replace(doc, member)),
+            None => {
+                self.documents.push((name, member));
+                None
+            }
+        }
+    }
+
+    /// Returns the root document.
+    pub fn root(&self) -> &Document {
+        &self.documents[0].1
+    }
+
+    /// Returns the name of the root document.
+    pub fn root_name(&self) -> &str {
+        &self.documents[0].0
+    }
+
+    /// Returns the document named `name`, which may be the root.
+    pub fn document(&self, name: &str) -> Option<&Document> {
+        self.index(name).map(|i| &self.documents[i].1)
+    }
+
+    /// Returns the documents of the workspace along with their names,
+    /// starting with the root.
+    pub fn documents(&self) -> impl Iterator<Item = (&str, &Document)> {
+        self.documents.iter().map(|(name, doc)| (&name[..], doc))
+    }
+
+    /// Returns the documents whose source text changed since they were
+    /// parsed, which are the files to write back.
+    pub fn modified(&self) -> impl Iterator<Item = (&str, &Document)> {
+        self.documents().filter(|(_, doc)| !doc.edits().is_empty())
+    }
+
+    /// Returns the table of the document named `name` with every inherited
+    /// value replaced by the value it inherits.
+    ///
+    /// An error is returned if there is no such document, if a value
+    /// inherits something the root does not define, or if anything but a
+    /// dependency sets other keys next to `workspace = true`.
+    pub fn resolve(&self, name: &str) -> Result<Table, de::Error> {
+        let doc = self
+            .document(name)
+            .ok_or_else(|| de::Error::custom(None, format!("no document is named `{}`", name)))?;
+        let mut path = KeyPath::new();
+        self.resolve_table(name, &mut path, doc.as_table())
+    }
+
+    /// Returns the name of the document that defines the value at `path`
+    /// of the document named `name`, along with its path there.
+    ///
+    /// This is `name` and `path` unless the value, or a table containing
+    /// it, is inherited from the root. A key written next to
+    /// `workspace = true` in a dependency is defined by the member.
+    /// `None` is returned if nothing is at `path`.
+    pub fn definition<P: Into<KeyPath>>(&self, name: &str, path: P) -> Option<(&str, KeyPath)> {
+        let path = path.into();
+        let member = self.index(name)?;
+        let doc = &self.documents[member].1;
+        let segments = path.segments();
+        for i in 0..segments.len() {
+            let prefix = KeyPath::from(segments[..=i].to_vec());
+            let value = doc.get(&prefix)?;
+            if !inherits(value) {
+                continue;
+            }
+            let rest = &segments[i + 1..];
+            if let Some(PathSegment::Key(key)) = rest.first() {
+                if is_dependency(&prefix) && key != "workspace" && value.get(key).is_some() {
+                    return Some((&self.documents[member].0, path));
+                }
+            }
+            let target = root_path(&prefix);
+            let inherited = self.root().get(&target)?;
+            let full = KeyPath::from([target.segments(), rest].concat());
+            if self.root().get(&full).is_some() {
+                return Some((self.root_name(), full));
+            }
+            // `dep.version` of a dependency inherited as `dep = "1.0"`.
+            let version = [PathSegment::Key("version".to_string())];
+            if is_dependency(&prefix) && rest == version && inherited.is_str() {
+                return Some((self.root_name(), target));
+            }
+            return None;
+        }
+        Some((&self.documents[member].0, path))
+    }
+
+    /// Replaces the value at `path` of the document named `name`, writing
+    /// the change to the document that defines it as found by
+    /// `Workspace::definition`, and returns the name of that document.
+    ///
+    /// The document is changed with `Document::set`, so only the text of
+    /// the value changes. An error is returned if nothing is at `path`.
+    pub fn set<P: Into<KeyPath>>(
+        &mut self,
+        name: &str,
+        path: P,
+        value: Value,
+    ) -> Result<&str, de::Error> {
+        let path = path.into();
+        let (target, target_path) = match self.definition(name, &path) {
+            Some((target, target_path)) => (self.index(target).unwrap(), target_path),
+            None => {
+                return Err(de::Error::custom(
+                    None,
+                    format!("`{}` is not in `{}`", path, name),
+                ))
+            }
+        };
+        let (target_name, doc) = &mut self.documents[target];
+        doc.set(target_path, value)?;
+        Ok(target_name)
+    }
+
+    fn index(&self, name: &str) -> Option<usize> {
+        self.documents.iter().position(|(n, _)| n == name)
+    }
+
+    fn resolve_table(
+        &self,
+        name: &str,
+        path: &mut KeyPath,
+        table: &Table,
+    ) -> Result<Table, de::Error> {
+        let mut ret = Table::new();
+        for (key, value) in table {
+            path.push_key(key.clone());
+            let value = match value {
+                Value::Table(_) if inherits(value) => self.inherit(name, path, value)?,
+                Value::Table(table) => Value::Table(self.resolve_table(name, path, table)?),
+                _ => value.clone(),
+            };
+            path.pop();
+            ret.insert(key.clone(), value);
+        }
+        Ok(ret)
+    }
+
+    /// Returns the value that `value`, a table with `workspace = true` at
+    /// `path` of the document named `name`, inherits.
+    fn inherit(&self, name: &str, path: &KeyPath, value: &Value) -> Result<Value, de::Error> {
+        let target = root_path(path);
+        let inherited = self.root().get(&target).ok_or_else(|| {
+            de::Error::custom(
+                None,
+                format!(
+                    "`{}` in `{}` inherits `{}`, which `{}` does not define",
+                    path,
+                    name,
+                    target,
+                    self.root_name()
+                ),
+            )
+        })?;
+        let own = value
+            .as_table()
+            .unwrap()
+            .iter()
+            .filter(|(key, _)| *key != "workspace");
+        if !is_dependency(path) {
+            return match own.map(|(key, _)| key).next() {
+                Some(key) => Err(de::Error::custom(
+                    None,
+                    format!(
+                        "`{}` in `{}` sets `{}` next to `workspace = true`",
+                        path, name, key
+                    ),
+                )),
+                None => Ok(inherited.clone()),
+            };
+        }
+        let mut ret = match inherited {
+            Value::Table(table) => table.clone(),
+            Value::String(_) => {
+                let mut table = Table::new();
+                table.insert("version".to_string(), inherited.clone());
+                table
+            }
+            _ => {
+                return Err(de::Error::custom(
+                    None,
+                    format!("`{}` in `{}` is not a dependency", target, self.root_name()),
+                ))
+            }
+        };
+        for (key, value) in own {
+            match (ret.get_mut(key), value) {
+                (Some(Value::Array(features)), Value::Array(more)) if key == "features" => {
+                    for feature in more {
+                        if !features.contains(feature) {
+                            features.push(feature.clone());
+                        }
+                    }
+                }
+                _ => {
+                    ret.insert(key.clone(), value.clone());
+                }
+            }
+        }
+        Ok(Value::Table(ret))
+    }
+}
+
+/// Returns whether `value` is a table that inherits from the root.
+fn inherits(value: &Value) -> bool {
+    value.get("workspace").and_then(Value::as_bool) == Some(true)
+}
+
+/// Returns whether `path` names an entry of a dependency table.
+fn is_dependency(path: &KeyPath) -> bool {
+    let segments = path.segments();
+    match segments.len().checked_sub(2).map(|i| &segments[i]) {
+        Some(PathSegment::Key(table)) => DEPENDENCY_TABLES.contains(&&table[..]),
+        _ => false,
+    }
+}
+
+/// Returns the path in the root of the value that the value at `path` of a
+/// member inherits.
+fn root_path(path: &KeyPath) -> KeyPath {
+    let mut ret = vec![PathSegment::Key("workspace".to_string())];
+    if is_dependency(path) {
+        ret.push(PathSegment::Key("dependencies".to_string()));
+        ret.push(path.last().unwrap().clone());
+    } else {
+        ret.extend_from_slice(path.segments());
+    }
+    KeyPath::from(ret)
+}
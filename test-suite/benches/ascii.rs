@@ -0,0 +1,38 @@
+// Parsing documents made almost entirely of ASCII keys, comments and plain
+// strings, which the tokenizer scans a byte at a time.
+
+use bencher::{benchmark_group, benchmark_main, black_box, Bencher};
+use toml::Value;
+
+fn document(tables: usize) -> String {
+    let mut s = String::new();
+    for i in 0..tables {
+        s += &format!("# The settings for package number {}.\n", i);
+        s += &format!("[package_{}]\n", i);
+        s += "name = \"some-package-with-a-long-name\"\n";
+        s += "description = 'A literal string describing what the package is for'\n";
+        s += "authors = [\"First Author <first@example.com>\", \"Second Author\"]\n";
+        s += "edition = \"2018\"    # trailing comment on a key\n";
+        s += "publish = false\n\n";
+    }
+    s
+}
+
+fn parse_document(bench: &mut Bencher, tables: usize) {
+    let s = black_box(document(tables));
+    bench.bytes = s.len() as u64;
+    bench.iter(|| {
+        black_box(s.parse::<Value>().unwrap());
+    })
+}
+
+fn tables_10(bench: &mut Bencher) {
+    parse_document(bench, 10)
+}
+
+fn tables_1000(bench: &mut Bencher) {
+    parse_document(bench, 1000)
+}
+
+benchmark_group!(benches, tables_10, tables_1000);
+benchmark_main!(benches);
@@ -0,0 +1,67 @@
+// Parsing strings with escapes, which are decoded into a buffer reused
+// between strings rather than built up a character at a time.
+
+use bencher::{benchmark_group, benchmark_main, black_box, Bencher};
+use toml::Value;
+
+fn document(strings: usize) -> String {
+    let mut s = String::new();
+    for i in 0..strings {
+        s += &format!(
+            "s{} = \"C:\\\\Program Files\\\\app\\\\bin\\t\\\"quoted\\\" \\u00e9t\\u00e9 and plain text\"\n",
+            i
+        );
+    }
+    s
+}
+
+fn parse_document(bench: &mut Bencher, strings: usize) {
+    let s = black_box(document(strings));
+    bench.bytes = s.len() as u64;
+    bench.iter(|| {
+        black_box(s.parse::<Value>().unwrap());
+    })
+}
+
+fn strings_100(bench: &mut Bencher) {
+    parse_document(bench, 100)
+}
+
+fn strings_1000(bench: &mut Bencher) {
+    parse_document(bench, 1000)
+}
+
+fn long_string(bench: &mut Bencher) {
+    let s = format!(
+        "\"{}\"",
+        "some plain text, then an escaped \\\"quote\\\" and a tab\\t. ".repeat(20_000)
+    );
+    let s = black_box(s);
+    bench.bytes = s.len() as u64;
+    bench.iter(|| {
+        let value: String = toml::Deserializer::new(&s).parse_value().unwrap();
+        black_box(value);
+    })
+}
+
+fn multiline_string(bench: &mut Bencher) {
+    let mut s = String::from("s = \"\"\"\n");
+    for i in 0..10_000 {
+        s += &format!("line {} with a \\\"quote\\\" and a tab\\t\r\n", i);
+    }
+    s += "\"\"\"\n";
+    let s = black_box(s);
+    bench.bytes = s.len() as u64;
+    bench.iter(|| {
+        black_box(s.parse::<Value>().unwrap());
+    })
+}
+
+benchmark_group!(
+    benches,
+    strings_100,
+    strings_1000,
+    long_string,
+    multiline_string
+);
+benchmark_main!(benches);
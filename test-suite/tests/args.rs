@@ -0,0 +1,73 @@
+extern crate toml;
+
+use toml::compat::from_args;
+use toml::value::Table;
+use toml::Value;
+
+#[test]
+fn key_values() {
+    let args = vec![
+        "server.port=8080".to_string(),
+        "server.host = \"example.com\"".to_string(),
+        "'quoted.key'.x=true".to_string(),
+        "limits={ cpu = 2 }".to_string(),
+        "server.port=8081".to_string(),
+    ];
+    let table = from_args(&args).unwrap();
+    assert_eq!(table["server"]["port"], Value::Integer(8081));
+    assert_eq!(table["server"]["host"].as_str(), Some("example.com"));
+    assert_eq!(table["quoted.key"]["x"], Value::Boolean(true));
+    assert_eq!(table["limits"]["cpu"], Value::Integer(2));
+    assert!(from_args(Vec::<String>::new()).unwrap().is_empty());
+}
+
+#[test]
+fn layered_over_file_and_env() {
+    let mut config: Table = toml::from_str("[log]\nlevel = 'warn'\nfile = 'a.log'\n").unwrap();
+    config.merge(toml::compat::from_vars(vec![("APP_LOG__LEVEL", "info")], "APP_").unwrap());
+    config.merge(from_args(&["log.level='debug'"]).unwrap());
+    assert_eq!(config["log"]["level"].as_str(), Some("debug"));
+    assert_eq!(config["log"]["file"].as_str(), Some("a.log"));
+}
+
+#[test]
+fn errors_are_aggregated() {
+    let args = [
+        "name=demo",
+        "port=80",
+        "port.tcp=1",
+        "a=1 b=2",
+        "= 1",
+        "ok=true",
+    ];
+    let errors = from_args(&args).unwrap_err();
+    let errors = errors
+        .iter()
+        .map(|e| (e.index(), e.to_string()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        errors,
+        [
+            (
+                0,
+                "invalid argument `name=demo`: invalid TOML value, did you mean to use a quoted string? at line 1 column 6"
+                    .to_string()
+            ),
+            (
+                2,
+                "invalid argument `port.tcp=1`: `port` is set to a value that is not a table"
+                    .to_string()
+            ),
+            (
+                3,
+                "invalid argument `a=1 b=2`: expected end of input, found an identifier at line 1 column 5"
+                    .to_string()
+            ),
+            (
+                4,
+                "invalid argument `= 1`: expected a table key, found an equals at line 1 column 1"
+                    .to_string()
+            ),
+        ]
+    );
+}
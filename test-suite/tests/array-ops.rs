@@ -0,0 +1,60 @@
+extern crate toml;
+
+use toml::value::{Array, ArrayExt, ValueKind};
+use toml::Value;
+
+fn array(src: &str) -> Array {
+    let value: Value = toml::from_str(&format!("a = {}", src)).unwrap();
+    value["a"].as_array().unwrap().clone()
+}
+
+fn render(array: Array) -> String {
+    Value::Array(array).to_string()
+}
+
+#[test]
+fn sort_mixed() {
+    let mut values = array(
+        "[{ b = 1 }, 'b', 2.5, nan, 2, true, [1], 1979-05-27, 'a', 2.0, 1979-05-27T07:32:00Z, -inf, false, [0, 1], { a = 2 }, 07:32:00]",
+    );
+    values.sort_values();
+    assert_eq!(
+        render(values),
+        "[false, true, -inf, 2, 2.0, 2.5, nan, \"a\", \"b\", 1979-05-27T07:32:00Z, 1979-05-27, 07:32:00, [0, 1], [1], { a = 2 }, { b = 1 }]"
+    );
+}
+
+#[test]
+fn sort_is_stable() {
+    let mut values = array("[0.0, -0.0, 1, 0.0]");
+    values.sort_values();
+    assert_eq!(render(values), "[0.0, -0.0, 0.0, 1]");
+}
+
+#[test]
+fn dedup_anywhere() {
+    let mut values = array("['b', 'a', 'b', 'c', 'a']");
+    values.dedup_values();
+    assert_eq!(render(values), r#"["b", "a", "c"]"#);
+
+    let mut values = array("[nan, 1, 1.0, nan, 1]");
+    values.dedup_values();
+    assert_eq!(render(values), "[nan, 1, 1.0]");
+}
+
+#[test]
+fn concat_checks_kinds() {
+    let mut hosts = array("['a', 'b']");
+    hosts.concat(array("['c']")).unwrap();
+    assert_eq!(render(hosts.clone()), r#"["a", "b", "c"]"#);
+
+    let err = hosts.concat(array("['d', 4]")).unwrap_err();
+    assert_eq!(err.to_string(), "expected string, found integer `4`");
+    assert_eq!(err.found(), Some(ValueKind::Integer));
+    assert_eq!(hosts.len(), 3);
+
+    let mut empty = Array::new();
+    empty.concat(array("[1, 'x']")).unwrap();
+    empty.concat(array("[true]")).unwrap();
+    assert_eq!(render(empty), r#"[1, "x", true]"#);
+}
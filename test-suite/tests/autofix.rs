@@ -0,0 +1,133 @@
+extern crate toml;
+
+const BROKEN: &str = "\
+name = hello world # the name
+verbose
+port = 08080
+ratio = 1.
+dep = { version = \"1\", optional = true, }
+tags = [alpha, beta]
+name = \"hello world\"
+";
+
+const FIXED: &str = "\
+name = \"hello world\" # the name
+verbose = true
+port = 8080
+ratio = 1.0
+dep = { version = \"1\", optional = true }
+tags = [\"alpha\", \"beta\"]
+";
+
+#[test]
+fn autofix() {
+    let fixed = toml::autofix(BROKEN).unwrap();
+    assert_eq!(fixed, FIXED);
+    assert!(fixed.parse::<toml::Value>().is_ok());
+    assert_eq!(toml::autofix(FIXED).unwrap(), FIXED);
+}
+
+#[test]
+fn fixes() {
+    let fixes = toml::fix::fixes(BROKEN).unwrap();
+    let described = fixes
+        .iter()
+        .map(|f| {
+            let (start, end) = f.span();
+            (
+                f.to_string(),
+                &BROKEN[start..end],
+                f.replacement(),
+                f.is_safe(),
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        described,
+        [
+            (
+                "unquoted string `hello world`".to_string(),
+                "hello world",
+                "\"hello world\"",
+                true
+            ),
+            (
+                "key `verbose` has no value, read as `verbose = true`".to_string(),
+                "",
+                " = true",
+                true
+            ),
+            (
+                "leading zeros in `08080`, read as 8080".to_string(),
+                "08080",
+                "8080",
+                true
+            ),
+            (
+                "missing digits after decimal point in `1.`, read as 1.0".to_string(),
+                "",
+                "0",
+                true
+            ),
+            ("trailing comma in inline table".to_string(), ",", "", true),
+            (
+                "unquoted string `alpha`".to_string(),
+                "alpha",
+                "\"alpha\"",
+                true
+            ),
+            (
+                "unquoted string `beta`".to_string(),
+                "beta",
+                "\"beta\"",
+                true
+            ),
+            (
+                "duplicate key `name`".to_string(),
+                "name = \"hello world\"\n",
+                "",
+                true
+            ),
+        ]
+    );
+}
+
+#[test]
+fn conflicting_duplicates_need_review() {
+    let src = "[server]\nport = 80\nhost.name = 'a'\nhost.name = 'b'\nport = 80\n";
+    let fixes = toml::fix::fixes(src).unwrap();
+    let described = fixes
+        .iter()
+        .map(|f| (f.to_string(), f.is_safe()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        described,
+        [
+            ("duplicate key `name`".to_string(), false),
+            ("duplicate key `port`".to_string(), true),
+        ]
+    );
+
+    let err = toml::autofix(src).unwrap_err();
+    assert!(err.to_string().contains("duplicate key"), "{}", err);
+    assert_eq!(
+        toml::fix::apply(src, &fixes),
+        "[server]\nport = 80\nhost.name = 'a'\n"
+    );
+}
+
+#[test]
+fn unfixable() {
+    let err = toml::autofix("a = [1, 2\n").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "expected a right bracket, found eof at line 2 column 1"
+    );
+
+    for src in &["a = 1\na = 2\n", "[a]\nx = 1\n[a]\ny = 2\n"] {
+        assert!(toml::autofix(src).is_err(), "{}", src);
+    }
+
+    let err = toml::autofix("name = hello\u{1}world\n").unwrap_err();
+    assert_eq!(err.line_col(), Some((0, 12)));
+}
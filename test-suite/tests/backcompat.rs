@@ -44,7 +44,7 @@ fn allow_duplicate_after_longer() {
     ";
     bad!(
         s,
-        "redefinition of table `dependencies` for key `dependencies` at line 8 column 9"
+        "redefinition of table `dependencies`, already defined on line 5 at line 8 column 9"
     );
 
     let mut d = toml::de::Deserializer::new(s);
@@ -0,0 +1,127 @@
+extern crate serde;
+extern crate toml;
+#[macro_use]
+extern crate serde_derive;
+
+use serde::de::Deserialize;
+use serde::ser::Serialize;
+use toml::ser::{BigIntegers, Error, Serializer};
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Wide {
+    small: i128,
+    negative: i128,
+    unsigned: u128,
+    big: u64,
+}
+
+const WIDE: Wide = Wide {
+    small: 42,
+    negative: i128::MIN,
+    unsigned: u128::MAX,
+    big: u64::MAX,
+};
+
+fn serialize(value: &Wide, policy: Option<BigIntegers>) -> Result<String, Error> {
+    let mut out = String::new();
+    let mut ser = Serializer::new(&mut out);
+    if let Some(policy) = policy {
+        ser.big_integers(policy);
+    }
+    value.serialize(&mut ser)?;
+    Ok(out)
+}
+
+#[test]
+fn in_range_are_integers() {
+    let narrow = Wide {
+        small: -7,
+        negative: i64::MIN.into(),
+        unsigned: 9,
+        big: i64::MAX as u64,
+    };
+    let out = serialize(&narrow, None).unwrap();
+    assert_eq!(
+        out,
+        "small = -7\nnegative = -9223372036854775808\nunsigned = 9\nbig = 9223372036854775807\n"
+    );
+    assert_eq!(toml::from_str::<Wide>(&out).unwrap(), narrow);
+}
+
+#[test]
+fn error_by_default() {
+    let err = serialize(&WIDE, None).unwrap_err();
+    assert_eq!(
+        err,
+        Error::IntegerOutOfRange(
+            "-170141183460469231731687303715884105728"
+                .to_string()
+                .into()
+        )
+    );
+    assert_eq!(
+        err.to_string(),
+        "integer -170141183460469231731687303715884105728 is out of range for a TOML integer"
+    );
+    assert_eq!(
+        toml::to_string(&u64::MAX).unwrap_err(),
+        Error::IntegerOutOfRange(u64::MAX.to_string().into())
+    );
+    assert_eq!(
+        toml::Value::try_from(u128::MAX).unwrap_err(),
+        Error::IntegerOutOfRange(u128::MAX.to_string().into())
+    );
+    assert_eq!(
+        toml::Value::try_from(5i128).unwrap(),
+        toml::Value::Integer(5)
+    );
+}
+
+#[test]
+fn strings_round_trip() {
+    let out = serialize(&WIDE, Some(BigIntegers::String)).unwrap();
+    assert_eq!(
+        out,
+        "\
+small = 42
+negative = \"-170141183460469231731687303715884105728\"
+unsigned = \"340282366920938463463374607431768211455\"
+big = \"18446744073709551615\"
+"
+    );
+
+    assert!(toml::from_str::<Wide>(&out).is_err());
+    let mut de = toml::Deserializer::new(&out);
+    de.set_big_integer_strings(true);
+    assert_eq!(Wide::deserialize(&mut de).unwrap(), WIDE);
+}
+
+#[test]
+fn invalid_strings() {
+    let src = "small = '12x'\nnegative = 0\nunsigned = 0\nbig = 0\n";
+    let mut de = toml::Deserializer::new(src);
+    de.set_big_integer_strings(true);
+    let err = Wide::deserialize(&mut de).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "string \"12x\" is not a valid i128 for key `small` at line 1 column 9"
+    );
+
+    let src = "small = 0\nnegative = 0\nunsigned = '-1'\nbig = 0\n";
+    let mut de = toml::Deserializer::new(src);
+    de.set_big_integer_strings(true);
+    assert!(Wide::deserialize(&mut de).is_err());
+}
+
+#[test]
+fn value_round_trip() {
+    let value = toml::Value::try_from(Wide {
+        small: 1,
+        negative: -1,
+        unsigned: 2,
+        big: 3,
+    })
+    .unwrap();
+    let back: Wide = value.try_into().unwrap();
+    assert_eq!(back.unsigned, 2);
+}
@@ -0,0 +1,141 @@
+extern crate serde;
+extern crate toml;
+
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize, PartialEq)]
+struct Config {
+    port: u16,
+    ratio: f64,
+    enabled: bool,
+    name: String,
+    limits: Vec<u32>,
+    nested: Nested,
+}
+
+#[derive(Debug, Deserialize, PartialEq)]
+struct Nested {
+    timeout: Option<i64>,
+}
+
+const SLOPPY: &str = r#"
+port = "8080"
+ratio = 3
+enabled = "TRUE"
+name = "8080"
+limits = ["1", 2]
+nested = { timeout = " 30 " }
+"#;
+
+#[test]
+fn strict_by_default() {
+    assert!(toml::from_str::<Config>(SLOPPY).is_err());
+}
+
+#[test]
+fn lenient_coercion() {
+    let mut d = toml::Deserializer::new(SLOPPY);
+    d.set_lenient_coercion(true);
+    let config = Config::deserialize(&mut d).unwrap();
+    assert_eq!(
+        config,
+        Config {
+            port: 8080,
+            ratio: 3.0,
+            enabled: true,
+            name: "8080".to_string(),
+            limits: vec![1, 2],
+            nested: Nested { timeout: Some(30) },
+        }
+    );
+
+    let warnings = d
+        .warnings()
+        .iter()
+        .map(|w| w.to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        warnings,
+        vec![
+            "coerced string \"8080\" to an integer at line 2 column 8",
+            "coerced integer `3` to a float at line 3 column 9",
+            "coerced string \"TRUE\" to a boolean at line 4 column 11",
+            "coerced string \"1\" to an integer at line 6 column 11",
+            "coerced string \" 30 \" to an integer at line 7 column 22",
+        ]
+    );
+    assert_eq!(d.warnings()[0].line_col(), (1, 7));
+}
+
+#[test]
+fn lenient_coercion_still_rejects_garbage() {
+    let mut d = toml::Deserializer::new(
+        "port = 'http'\nratio = 1.0\nenabled = true\nname = 'x'\nlimits = []\nnested = {}",
+    );
+    d.set_lenient_coercion(true);
+    let err = Config::deserialize(&mut d).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "invalid type: string \"http\", expected u16 for key `port` at line 1 column 8"
+    );
+}
+
+#[test]
+fn legacy_booleans() {
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Flags {
+        a: bool,
+        b: bool,
+        c: bool,
+        d: bool,
+        e: Vec<bool>,
+        name: String,
+        count: u8,
+    }
+
+    let input = "a = 'Yes'\nb = 'off'\nc = 1\nd = true\ne = ['on', 'NO', '1', 0]\nname = 'yes'\ncount = 1\n";
+    let mut d = toml::Deserializer::new(input);
+    d.set_legacy_booleans(true);
+    let flags = Flags::deserialize(&mut d).unwrap();
+    assert_eq!(
+        flags,
+        Flags {
+            a: true,
+            b: false,
+            c: true,
+            d: true,
+            e: vec![true, false, true, false],
+            name: "yes".to_string(),
+            count: 1,
+        }
+    );
+    let warnings = d
+        .warnings()
+        .iter()
+        .map(|w| w.to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        warnings,
+        vec![
+            "coerced string \"Yes\" to a boolean at line 1 column 5",
+            "coerced string \"off\" to a boolean at line 2 column 5",
+            "coerced integer `1` to a boolean at line 3 column 5",
+            "coerced string \"on\" to a boolean at line 5 column 6",
+            "coerced string \"NO\" to a boolean at line 5 column 12",
+            "coerced string \"1\" to a boolean at line 5 column 18",
+            "coerced integer `0` to a boolean at line 5 column 23",
+        ]
+    );
+
+    // `true` and `false` as strings are still left to lenient coercion.
+    let mut d =
+        toml::Deserializer::new("a = 'true'\nb = 2\nc = 0\nd = 0\ne = []\nname = ''\ncount = 0");
+    d.set_legacy_booleans(true);
+    let err = Flags::deserialize(&mut d).unwrap_err();
+    assert!(
+        err.to_string()
+            .starts_with("invalid type: string \"true\", expected a boolean"),
+        "{}",
+        err
+    );
+}
@@ -0,0 +1,138 @@
+use serde::Serialize;
+use toml::ser::Serializer;
+use toml::value::Table;
+
+#[derive(Serialize)]
+struct Config {
+    name: String,
+    timeout: u32,
+    ports: Vec<u16>,
+    server: Server,
+    log: Log,
+    workers: Vec<Worker>,
+}
+
+#[derive(Serialize)]
+struct Server {
+    host: String,
+    port: u16,
+}
+
+#[derive(Serialize)]
+struct Log {
+    level: String,
+}
+
+#[derive(Serialize)]
+struct Worker {
+    id: u32,
+}
+
+fn defaults() -> Table {
+    toml::from_str(
+        r#"
+name = "app"
+timeout = 30
+ports = [80, 443]
+
+[server]
+host = "localhost"
+port = 8080
+
+[log]
+level = "info"
+
+[[workers]]
+id = 1
+"#,
+    )
+    .unwrap()
+}
+
+fn serialize(config: &Config, pretty: bool) -> String {
+    let mut out = String::new();
+    let mut ser = if pretty {
+        Serializer::pretty(&mut out)
+    } else {
+        Serializer::new(&mut out)
+    };
+    ser.comment_defaults(defaults());
+    config.serialize(&mut ser).unwrap();
+    out
+}
+
+fn config() -> Config {
+    Config {
+        name: "proxy".to_string(),
+        timeout: 30,
+        ports: vec![80, 443],
+        server: Server {
+            host: "localhost".to_string(),
+            port: 9090,
+        },
+        log: Log {
+            level: "info".to_string(),
+        },
+        workers: vec![Worker { id: 1 }],
+    }
+}
+
+#[test]
+fn defaults_are_commented_out() {
+    let out = serialize(&config(), false);
+    assert_eq!(
+        out,
+        "\
+name = \"proxy\"
+# timeout = 30
+# ports = [80, 443]
+
+[server]
+# host = \"localhost\"
+port = 9090
+
+[log]
+# level = \"info\"
+
+[[workers]]
+id = 1
+"
+    );
+    let table: Table = toml::from_str(&out).unwrap();
+    assert!(table.get("timeout").is_none());
+    assert_eq!(table["server"]["port"].as_integer(), Some(9090));
+    assert!(table["log"].as_table().unwrap().is_empty());
+}
+
+#[test]
+fn multiline_arrays() {
+    let out = serialize(&config(), true);
+    assert!(
+        out.contains("# ports = [\n#     80,\n#     443,\n# ]\n"),
+        "{}",
+        out
+    );
+    toml::from_str::<Table>(&out).unwrap();
+}
+
+#[test]
+fn changed_values_are_written() {
+    let mut config = config();
+    config.timeout = 60;
+    config.ports.push(8443);
+    let out = serialize(&config, false);
+    assert!(out.contains("\ntimeout = 60\n"), "{}", out);
+    assert!(out.contains("\nports = [80, 443, 8443]\n"), "{}", out);
+}
+
+#[test]
+fn first_entry_of_table() {
+    let mut config = config();
+    config.server.port = 8080;
+    let out = serialize(&config, false);
+    assert!(
+        out.contains("\n[server]\n# host = \"localhost\"\n# port = 8080\n"),
+        "{}",
+        out
+    );
+}
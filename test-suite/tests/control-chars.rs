@@ -0,0 +1,105 @@
+use serde::Serialize;
+use std::collections::BTreeMap;
+use toml::ser::{Error, Serializer, TableStyle};
+
+#[derive(Serialize)]
+struct Config {
+    name: String,
+    servers: Vec<Server>,
+}
+
+#[derive(Serialize)]
+struct Server {
+    host: String,
+}
+
+fn config(host: &str) -> Config {
+    Config {
+        name: "a\tb\r\n".to_string(),
+        servers: vec![
+            Server {
+                host: "one".to_string(),
+            },
+            Server {
+                host: host.to_string(),
+            },
+        ],
+    }
+}
+
+fn write<T: Serialize>(value: &T, style: Option<(&str, TableStyle)>) -> Result<String, Error> {
+    let mut out = String::new();
+    let mut ser = Serializer::new(&mut out);
+    ser.forbid_control_chars(true);
+    if let Some((path, style)) = style {
+        ser.table_style(path, style);
+    }
+    value.serialize(&mut ser)?;
+    Ok(out)
+}
+
+fn control_error(err: Error) -> (String, usize, char) {
+    match err {
+        Error::ControlCharacter(e) => (e.path().to_string(), e.offset(), e.character()),
+        other => panic!("unexpected {:?}", other),
+    }
+}
+
+#[test]
+fn escapable_characters_allowed() {
+    let out = write(&config("two\u{8}\u{c}"), None).unwrap();
+    assert_eq!(out, toml::to_string(&config("two\u{8}\u{c}")).unwrap());
+}
+
+#[test]
+fn allowed_by_default() {
+    let out = toml::to_string(&config("t\u{0}wo")).unwrap();
+    assert!(out.contains("host = \"t\\u0000wo\""));
+}
+
+#[test]
+fn value_in_array_of_tables() {
+    let err = write(&config("tw\u{7f}o"), None).unwrap_err();
+    assert_eq!(
+        control_error(err.clone()),
+        ("servers[1].host".to_string(), 2, '\u{7f}')
+    );
+    assert_eq!(
+        err.to_string(),
+        "control character U+007F at byte 2 of the string at `servers[1].host`"
+    );
+}
+
+#[test]
+fn keys() {
+    let mut map = BTreeMap::new();
+    let mut inner = BTreeMap::new();
+    inner.insert("k\u{1}ey".to_string(), 1);
+    map.insert("table".to_string(), inner);
+    let err = write(&map, None).unwrap_err();
+    assert_eq!(
+        control_error(err),
+        ("table.\"k\\u0001ey\"".to_string(), 1, '\u{1}')
+    );
+}
+
+#[test]
+fn styled_tables() {
+    let value = toml::toml! {
+        [a]
+        b = { c = ["x", "é\u{0}"] }
+    };
+    for &style in &[TableStyle::Inline, TableStyle::Dotted] {
+        let err = write(&value, Some(("a", style))).unwrap_err();
+        assert_eq!(control_error(err), ("a.b.c[1]".to_string(), 2, '\u{0}'));
+    }
+}
+
+#[test]
+fn bare_string() {
+    let err = write(&"\u{1b}", None).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "control character U+001B at byte 0 of the string"
+    );
+}
@@ -0,0 +1,77 @@
+extern crate serde;
+extern crate toml;
+
+use serde::{Deserialize, Serialize};
+use toml::conv::{EpochSeconds, Iso8601String};
+
+#[derive(Debug, Deserialize, Serialize)]
+struct Stamps {
+    seconds: EpochSeconds,
+    string: Iso8601String,
+}
+
+#[test]
+fn read_either_form() {
+    let stamps: Stamps = toml::from_str(
+        "seconds = 1979-05-27T00:32:00.75-07:00\nstring = '1979-05-27T00:32:00-07:00'\n",
+    )
+    .unwrap();
+    assert_eq!(stamps.seconds, EpochSeconds(296_638_320));
+    assert_eq!(stamps.string.to_string(), "1979-05-27T00:32:00-07:00");
+
+    let stamps: Stamps = toml::from_str("seconds = 296638320\nstring = 1979-05-28\n").unwrap();
+    assert_eq!(*stamps.seconds, 296_638_320);
+    assert_eq!(stamps.string.to_string(), "1979-05-28");
+
+    let stamps: Stamps =
+        toml::from_str("seconds = 1970-01-01T00:00:00-00:30\nstring = 1979-05-28\n").unwrap();
+    assert_eq!(*stamps.seconds, 1800);
+}
+
+#[test]
+fn write() {
+    let stamps = Stamps {
+        seconds: EpochSeconds(-1),
+        string: Iso8601String("1979-05-27T00:32:00.5".parse().unwrap()),
+    };
+    let out = toml::to_string(&stamps).unwrap();
+    assert_eq!(
+        out,
+        "seconds = 1969-12-31T23:59:59Z\nstring = \"1979-05-27T00:32:00.5\"\n"
+    );
+
+    let back: Stamps = toml::from_str(&out).unwrap();
+    assert_eq!(back.seconds, stamps.seconds);
+    assert_eq!(back.string, stamps.string);
+}
+
+#[test]
+fn errors() {
+    let err = toml::from_str::<Stamps>("seconds = 1979-05-27T00:32:00\nstring = ''\n").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "datetime `1979-05-27T00:32:00` has no offset, so does not denote an instant \
+         for key `seconds` at line 1 column 11"
+    );
+
+    let err = toml::from_str::<Stamps>("seconds = 1\nstring = 'tomorrow'\n").unwrap_err();
+    assert!(
+        err.to_string().starts_with("failed to parse datetime"),
+        "{}",
+        err
+    );
+
+    let err = toml::from_str::<Stamps>("seconds = 'now'\nstring = ''\n").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "invalid type: string \"now\", expected an offset date-time or an integer \
+         for key `seconds` at line 1 column 11"
+    );
+
+    let err = toml::to_string(&Stamps {
+        seconds: EpochSeconds(i64::MAX),
+        string: Iso8601String("1979-05-28".parse().unwrap()),
+    })
+    .unwrap_err();
+    assert_eq!(err.to_string(), "time is out of range for a TOML datetime");
+}
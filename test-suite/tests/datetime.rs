@@ -1,6 +1,11 @@
+extern crate serde;
 extern crate toml;
 
 use std::str::FromStr;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::Serialize;
+use toml::value::{Datetime, Offset};
 
 macro_rules! bad {
     ($toml:expr, $msg:expr) => {
@@ -113,22 +118,236 @@ fn bad_times() {
 
     bad!(
         "foo = 1997-00-09T09:09:09.09Z",
-        "failed to parse datetime for key `foo` at line 1 column 7"
+        "invalid datetime: month 00 is out of range at line 1 column 7"
     );
     bad!(
         "foo = 1997-09-00T09:09:09.09Z",
-        "failed to parse datetime for key `foo` at line 1 column 7"
+        "invalid datetime: day 00 is out of range for 1997-09 at line 1 column 7"
     );
     bad!(
         "foo = 1997-09-09T30:09:09.09Z",
-        "failed to parse datetime for key `foo` at line 1 column 7"
+        "invalid datetime: hour 30 is out of range at line 1 column 7"
     );
     bad!(
         "foo = 1997-09-09T12:69:09.09Z",
-        "failed to parse datetime for key `foo` at line 1 column 7"
+        "invalid datetime: minute 69 is out of range at line 1 column 7"
     );
     bad!(
         "foo = 1997-09-09T12:09:69.09Z",
-        "failed to parse datetime for key `foo` at line 1 column 7"
+        "invalid datetime: second 69 is out of range at line 1 column 7"
+    );
+}
+
+#[test]
+fn unix_timestamps() {
+    fn dt(s: &str) -> toml::value::Datetime {
+        s.parse().unwrap()
+    }
+    use toml::value::Datetime;
+
+    assert_eq!(dt("1970-01-01T00:00:00Z").unix_timestamp(), Some(0));
+    assert_eq!(dt("1969-12-31T23:59:59Z").unix_timestamp(), Some(-1));
+    assert_eq!(
+        dt("2000-03-01T01:00:00+01:00").unix_timestamp(),
+        Some(951_868_800)
+    );
+    assert_eq!(dt("1979-05-27T07:32:00").unix_timestamp(), None);
+    assert_eq!(dt("1979-05-27").unix_timestamp(), None);
+
+    for &s in &[
+        "0000-01-01T00:00:00Z",
+        "1600-02-29T12:00:00Z",
+        "2024-02-29T23:59:59.25Z",
+        "9999-12-31T23:59:59Z",
+    ] {
+        let t = dt(s);
+        let back = Datetime::from_unix_timestamp(
+            t.unix_timestamp().unwrap(),
+            t.time.as_ref().unwrap().nanosecond,
+        );
+        assert_eq!(back.unwrap(), t);
+    }
+    assert!(Datetime::from_unix_timestamp(253_402_300_800, 0).is_none());
+    assert!(Datetime::from_unix_timestamp(0, 1_000_000_000).is_none());
+
+    assert_eq!(
+        dt("1979-05-27T00:32:00-07:30").to_utc().unwrap(),
+        dt("1979-05-27T08:02:00Z")
+    );
+    assert_eq!(dt("1979-05-27T00:32:00").to_utc(), None);
+}
+
+#[test]
+fn ordering() {
+    fn dt(s: &str) -> toml::value::Datetime {
+        s.parse().unwrap()
+    }
+    assert!(dt("1979-05-27T00:32:00-07:00") > dt("1979-05-27T07:00:00Z"));
+    assert!(dt("1979-05-27T07:32:00.1Z") > dt("1979-05-27T00:32:00-07:00"));
+    assert!(dt("1979-05-27T07:32:00Z") != dt("1979-05-27T00:32:00-07:00"));
+    assert_ne!(
+        dt("1979-05-27T07:32:00Z").partial_cmp(&dt("1979-05-27T00:32:00-07:00")),
+        Some(std::cmp::Ordering::Equal)
+    );
+    assert!(dt("1979-05-27T07:32:00") < dt("1979-05-28T00:00:00"));
+    assert!(dt("1979-05-27") < dt("1980-01-01"));
+    assert!(dt("07:32:00") < dt("07:32:00.5"));
+    assert_eq!(dt("1979-05-27").partial_cmp(&dt("07:32:00")), None);
+    assert_eq!(
+        dt("1979-05-27T07:32:00").partial_cmp(&dt("1979-05-27T07:32:00Z")),
+        None
+    );
+}
+
+#[test]
+fn out_of_range() {
+    bad!(
+        "foo = 2023-02-29",
+        "invalid datetime: day 29 is out of range for 2023-02 at line 1 column 7"
+    );
+    bad!(
+        "foo = 1900-02-29T00:00:00",
+        "invalid datetime: day 29 is out of range for 1900-02 at line 1 column 7"
+    );
+    bad!(
+        "\nfoo = [2024-04-30, 2024-04-31]",
+        "invalid datetime: day 31 is out of range for 2024-04 at line 2 column 20"
+    );
+    bad!(
+        "foo = 24:00:00",
+        "invalid datetime: hour 24 is out of range at line 1 column 7"
+    );
+    bad!(
+        "foo = 1997-09-09T09:09:09+24:00",
+        "invalid datetime: offset hour 24 is out of range at line 1 column 7"
+    );
+    bad!(
+        "foo = 1997-09-09T09:09:09-05:60",
+        "invalid datetime: offset minute 60 is out of range at line 1 column 7"
+    );
+
+    assert!("foo = 2024-02-29".parse::<toml::Value>().is_ok());
+    assert!("foo = 2000-02-29T23:59:59-23:59"
+        .parse::<toml::Value>()
+        .is_ok());
+    assert_eq!(
+        "2023-02-29"
+            .parse::<toml::value::Datetime>()
+            .unwrap_err()
+            .to_string(),
+        "failed to parse datetime: day 29 is out of range for 2023-02"
+    );
+}
+
+#[test]
+fn system_times() {
+    let dt = |time: SystemTime| Datetime::from_system_time(time).unwrap().to_string();
+    assert_eq!(dt(UNIX_EPOCH), "1970-01-01T00:00:00Z");
+    assert_eq!(
+        dt(UNIX_EPOCH + Duration::new(296_638_320, 5_000)),
+        "1979-05-27T07:32:00.000005Z"
+    );
+    assert_eq!(
+        dt(UNIX_EPOCH - Duration::from_millis(1_500)),
+        "1969-12-31T23:59:58.5Z"
+    );
+    assert_eq!(
+        dt(UNIX_EPOCH - Duration::from_secs(86_400)),
+        "1969-12-31T00:00:00Z"
+    );
+}
+
+#[test]
+fn offsets() {
+    let dt: Datetime = "1979-05-27T00:32:00.5-07:00".parse().unwrap();
+    let east = Offset::Custom {
+        negative: false,
+        hours: 5,
+        minutes: 30,
+    };
+    assert_eq!(
+        dt.to_offset(east).unwrap().to_string(),
+        "1979-05-27T13:02:00.5+05:30"
+    );
+    assert_eq!(
+        dt.to_offset(Offset::Z).unwrap().to_string(),
+        "1979-05-27T07:32:00.5Z"
     );
+    let local: Datetime = "1979-05-27T00:32:00".parse().unwrap();
+    assert_eq!(local.to_offset(Offset::Z), None);
+
+    let west: Datetime = "1979-05-27T00:32:00-00:30".parse().unwrap();
+    assert_eq!(west.to_string(), "1979-05-27T00:32:00-00:30");
+    assert_eq!(west.offset.as_ref().unwrap().minutes(), -30);
+    assert_eq!(
+        west.to_offset(Offset::Z).unwrap().to_string(),
+        "1979-05-27T01:02:00Z"
+    );
+    let half = Offset::Custom {
+        negative: true,
+        hours: 0,
+        minutes: 30,
+    };
+    assert_eq!(
+        dt.to_offset(half).unwrap().to_string(),
+        "1979-05-27T07:02:00.5-00:30"
+    );
+}
+
+#[derive(Serialize)]
+struct Build {
+    #[serde(serialize_with = "toml::ser::system_time")]
+    started: SystemTime,
+    #[serde(serialize_with = "toml::ser::unix_seconds")]
+    finished: i64,
+    released: Datetime,
+    scheduled: Datetime,
+}
+
+#[test]
+fn serialize_times() {
+    let build = Build {
+        started: UNIX_EPOCH + Duration::from_secs(296_638_200),
+        finished: 296_638_320,
+        released: "1979-05-27T00:32:00-07:00".parse().unwrap(),
+        scheduled: "1979-05-28T09:00:00".parse().unwrap(),
+    };
+    assert_eq!(
+        toml::to_string(&build).unwrap(),
+        "\
+started = 1979-05-27T07:30:00Z
+finished = 1979-05-27T07:32:00Z
+released = 1979-05-27T00:32:00-07:00
+scheduled = 1979-05-28T09:00:00
+"
+    );
+
+    let mut out = String::new();
+    let mut ser = toml::Serializer::new(&mut out);
+    ser.datetime_offset(Offset::Custom {
+        negative: true,
+        hours: 7,
+        minutes: 0,
+    });
+    build.serialize(&mut ser).unwrap();
+    assert_eq!(
+        out,
+        "\
+started = 1979-05-27T00:30:00-07:00
+finished = 1979-05-27T00:32:00-07:00
+released = 1979-05-27T00:32:00-07:00
+scheduled = 1979-05-28T09:00:00
+"
+    );
+}
+
+#[test]
+fn serialize_out_of_range() {
+    #[derive(Serialize)]
+    struct Stamp {
+        #[serde(serialize_with = "toml::ser::unix_seconds")]
+        at: i64,
+    }
+    let err = toml::to_string(&Stamp { at: i64::MAX }).unwrap_err();
+    assert_eq!(err.to_string(), "time is out of range for a TOML datetime");
 }
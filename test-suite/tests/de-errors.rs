@@ -349,3 +349,42 @@ fn error_handles_crlf() {
         "duplicate key: `a` for key `t2` at line 3 column 1"
     );
 }
+
+#[test]
+fn coercion_hints() {
+    #[derive(Debug, Deserialize)]
+    struct Config {
+        #[allow(dead_code)]
+        port: Option<u16>,
+        #[allow(dead_code)]
+        ratio: Option<f64>,
+        #[allow(dead_code)]
+        enabled: Option<bool>,
+    }
+
+    bad!(
+        "port = \"8080\"",
+        Config,
+        "invalid type: string \"8080\", expected u16 (hint: remove the quotes to make it an integer) for key `port` at line 1 column 8"
+    );
+    bad!(
+        "ratio = '0.5'",
+        Config,
+        "invalid type: string \"0.5\", expected f64 (hint: remove the quotes to make it a float) for key `ratio` at line 1 column 9"
+    );
+    bad!(
+        "enabled = \"true\"",
+        Config,
+        "invalid type: string \"true\", expected a boolean (hint: remove the quotes to make it a boolean) for key `enabled` at line 1 column 11"
+    );
+    bad!(
+        "enabled = \"yes\"",
+        Config,
+        "invalid type: string \"yes\", expected a boolean (hint: booleans are written as `true` or `false`, without quotes) for key `enabled` at line 1 column 11"
+    );
+    bad!(
+        "port = \"http\"",
+        Config,
+        "invalid type: string \"http\", expected u16 for key `port` at line 1 column 8"
+    );
+}
@@ -0,0 +1,71 @@
+extern crate toml;
+
+use std::mem::size_of;
+
+use toml::value::Table;
+use toml::Value;
+
+#[test]
+fn scalars() {
+    for value in &[
+        Value::Integer(1),
+        Value::Float(1.5),
+        Value::Boolean(true),
+        Value::Datetime("1979-05-27T07:32:00Z".parse().unwrap()),
+        Value::String(String::new()),
+    ] {
+        assert_eq!(value.deep_size_bytes(), size_of::<Value>());
+    }
+    let s = String::from("tenant");
+    let capacity = s.capacity();
+    assert_eq!(
+        Value::String(s).deep_size_bytes(),
+        size_of::<Value>() + capacity
+    );
+}
+
+#[test]
+fn arrays_count_capacity_and_elements() {
+    let mut array = Vec::with_capacity(8);
+    array.push(Value::String(String::with_capacity(100)));
+    array.push(Value::Integer(1));
+    assert_eq!(
+        Value::Array(array).deep_size_bytes(),
+        size_of::<Value>() + 8 * size_of::<Value>() + 100
+    );
+}
+
+#[test]
+fn tables_count_keys_and_values() {
+    let mut table = Table::new();
+    let empty = Value::Table(table.clone()).deep_size_bytes();
+    assert_eq!(empty, size_of::<Value>());
+
+    table.insert("key".to_string(), Value::Integer(1));
+    let one = Value::Table(table.clone()).deep_size_bytes();
+    assert!(one >= empty + size_of::<String>() + size_of::<Value>() + 3);
+
+    table.insert(
+        "blob".to_string(),
+        Value::String(String::with_capacity(10_000)),
+    );
+    let two = Value::Table(table).deep_size_bytes();
+    assert!(two >= one + 10_000);
+}
+
+#[test]
+fn biggest_configs_are_found() {
+    let tenants = [
+        "name = 'a'",
+        "name = 'b'\n[limits]\ncpu = 2\nmemory = '4G'\nzones = ['eu', 'us', 'ap']",
+        "name = 'c'\nzones = ['eu']",
+    ];
+    let mut sizes = tenants
+        .iter()
+        .map(|t| t.parse::<Value>().unwrap().deep_size_bytes())
+        .enumerate()
+        .collect::<Vec<_>>();
+    sizes.sort_by_key(|&(_, size)| size);
+    let order = sizes.iter().map(|&(i, _)| i).collect::<Vec<_>>();
+    assert_eq!(order, [0, 2, 1]);
+}
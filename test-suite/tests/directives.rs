@@ -0,0 +1,76 @@
+use toml::directive::{directives, DirectiveScope};
+use toml::document::Document;
+
+#[test]
+fn names_values_and_spans() {
+    let src = "a = 1 #lint:  disable=x,y   allow\n";
+    let found = directives(src, "lint:").unwrap();
+    assert_eq!(found.len(), 2);
+    assert_eq!(found[0].name(), "disable");
+    assert_eq!(found[0].value(), Some("x,y"));
+    assert_eq!(&src[found[0].span().0..found[0].span().1], "disable=x,y");
+    assert_eq!(found[1].name(), "allow");
+    assert_eq!(found[1].value(), None);
+    assert_eq!(&src[found[1].span().0..found[1].span().1], "allow");
+    assert_eq!(*found[0].scope(), DirectiveScope::Line((0, 33)));
+}
+
+#[test]
+fn scopes() {
+    let src = "\
+top = 1
+# lint: root
+
+[a]
+# lint: above
+# another comment
+b = [
+  1, # lint: element
+]
+# lint: unattached
+
+c = 2
+[[arr]] # lint: header
+d = 3
+";
+    let doc: Document = src.parse().unwrap();
+    let scopes = doc
+        .directives("lint:")
+        .into_iter()
+        .map(|d| (d.name().to_string(), d.scope().clone()))
+        .collect::<Vec<_>>();
+    let line = |text: &str| {
+        let start = src.find(text).unwrap();
+        DirectiveScope::Line((start, start + text.len()))
+    };
+    assert_eq!(
+        scopes,
+        [
+            (
+                "root".to_string(),
+                DirectiveScope::Table("".parse().unwrap())
+            ),
+            ("above".to_string(), line("b = [")),
+            ("element".to_string(), line("  1, # lint: element")),
+            (
+                "unattached".to_string(),
+                DirectiveScope::Table("a".parse().unwrap())
+            ),
+            (
+                "header".to_string(),
+                DirectiveScope::Table("arr[0]".parse().unwrap())
+            ),
+        ]
+    );
+}
+
+#[test]
+fn file_scope_and_strings() {
+    let src = "# lint: everything\n\ns = '''\n# lint: not a comment\n'''\n";
+    let found = directives(src, "lint:").unwrap();
+    assert_eq!(found.len(), 1);
+    assert_eq!(*found[0].scope(), DirectiveScope::File);
+
+    assert!(directives("a = ", "lint:").is_err());
+    assert!(directives("# other: x\n", "lint:").unwrap().is_empty());
+}
@@ -0,0 +1,89 @@
+extern crate toml;
+
+use toml::display::Limits;
+use toml::Value;
+
+fn limits(strings: usize, arrays: usize, tables: usize, depth: usize) -> Limits {
+    Limits {
+        max_string_len: strings,
+        max_array_len: arrays,
+        max_table_len: tables,
+        max_depth: depth,
+    }
+}
+
+#[test]
+fn short_values_are_untouched() {
+    let value: Value = "a = 'x'\nb = [1, 2.5, true]\nc = { \"d e\" = 1979-05-27 }\nf = {}"
+        .parse()
+        .unwrap();
+    assert_eq!(
+        value.display_truncated(Limits::default()).to_string(),
+        "{ a = \"x\", b = [1, 2.5, true], c = { \"d e\" = 1979-05-27 }, f = {} }"
+    );
+}
+
+#[test]
+fn long_values_are_cut() {
+    let s = Value::from("héllo \"world\"\n");
+    assert_eq!(
+        s.display_truncated(limits(3, 1, 1, 1)).to_string(),
+        "\"hél\"… (+11 more)"
+    );
+    assert_eq!(
+        s.display_truncated(Limits::default()).to_string(),
+        "\"héllo \\\"world\\\"\\n\""
+    );
+
+    let value: Value = "a = [1, 2, 3]\nb = 2\nc = 3".parse().unwrap();
+    assert_eq!(
+        value.display_truncated(limits(10, 2, 2, 8)).to_string(),
+        "{ a = [1, 2, … (+1 more)], b = 2, … (+1 more) }"
+    );
+    assert_eq!(
+        value.display_truncated(limits(10, 0, 0, 8)).to_string(),
+        "{ … (+3 more) }"
+    );
+}
+
+#[test]
+fn deep_values_are_elided() {
+    let value: Value = "a = { b = [[1]], c = [] }".parse().unwrap();
+    assert_eq!(
+        value.display_truncated(limits(10, 10, 10, 2)).to_string(),
+        "{ a = { b = […], c = [] } }"
+    );
+    assert_eq!(
+        value.display_truncated(limits(10, 10, 10, 0)).to_string(),
+        "{…}"
+    );
+}
+
+#[test]
+fn render_tree() {
+    let value: Value = r#"
+        bin = [{ name = "one" }, { name = "two", paths = [["a"], []] }]
+        empty = {}
+        list = [1, 2, 3, 4, 5, 6, 7, 8, 9, 10]
+        title = "a very long title that goes on and on and on and on forever"
+    "#
+    .parse()
+    .unwrap();
+    assert_eq!(
+        value.render_tree(),
+        "\
+bin: array (2)
+|-- [0]: table
+|   `-- name: string = \"one\"
+`-- [1]: table
+    |-- name: string = \"two\"
+    `-- paths: array (2)
+        |-- [0]: array (1) = [\"a\"]
+        `-- [1]: array (0)
+empty: table (empty)
+list: array (10) = [1, 2, 3, 4, 5, 6, 7, 8, … (+2 more)]
+title: string = \"a very long title that goes on and on an\"… (+19 more)
+"
+    );
+    assert_eq!(Value::from(1.5).render_tree(), "float = 1.5\n");
+}
@@ -75,7 +75,7 @@ fn table() {
              "foo\"bar" => Integer(2)
         })
         .to_string(),
-        "\"foo\\\"bar\" = 2\n\
+        "'foo\"bar' = 2\n\
          \"foo.bar\" = 2\n"
     );
     assert_eq!(
@@ -115,3 +115,36 @@ fn table() {
          test2 = 2\n"
     );
 }
+
+#[test]
+fn unrepresentable_arrays() {
+    let point = || Table(map! { "x" => Integer(1) });
+
+    let mixed = Table(map! {
+        "name" => String("a".to_string()),
+        "points" => Array(vec![point(), String("origin".to_string())])
+    });
+    assert_eq!(
+        mixed.try_to_string().unwrap_err(),
+        toml::ser::Error::ArrayMixedType
+    );
+    assert_eq!(
+        mixed.to_string(),
+        "name = \"a\"\n\
+         points = [{ x = 1 }, \"origin\"]\n"
+    );
+
+    let nested = Table(map! {
+        "grid" => Array(vec![Array(vec![point()]), Array(vec![])])
+    });
+    assert!(nested.try_to_string().is_err());
+    assert_eq!(nested.to_string(), "grid = [[{ x = 1 }], []]\n");
+
+    let bare = Array(vec![point(), point()]);
+    assert!(bare.try_to_string().is_err());
+    assert_eq!(bare.to_string(), "[{ x = 1 }, { x = 1 }]");
+
+    let fine = Table(map! { "points" => Array(vec![point()]) });
+    assert_eq!(fine.try_to_string().unwrap(), fine.to_string());
+    assert_eq!(fine.to_string(), "[[points]]\nx = 1\n");
+}
@@ -0,0 +1,985 @@
+extern crate toml;
+
+use toml::document::{Document, InsertPosition};
+use toml::metadata::Metadata;
+use toml::Value;
+
+#[test]
+fn source_is_preserved() {
+    let src = "# top\n[a]   # comment\nb  =  1\nc = { d = 'x' }\n";
+    let doc: Document = src.parse().unwrap();
+    assert_eq!(doc.to_string(), src);
+    assert_eq!(doc.source(), src);
+    assert_eq!(doc.get("a.c.d").and_then(|v| v.as_str()), Some("x"));
+    assert_eq!(doc.span("a"), Some((7, 8)));
+    assert_eq!(doc.span("a.b"), Some((22, 23)));
+    assert_eq!(doc.span("a.c.d"), Some((36, 37)));
+    assert_eq!(doc.span("missing"), None);
+    assert!("a = ".parse::<Document>().is_err());
+}
+
+#[test]
+fn metadata_attached_to_paths() {
+    let mut doc: Document = "[server]\nport = 80\nold = true\n".parse().unwrap();
+    doc.metadata_mut()
+        .set("server.old", "deprecated", Value::from(true));
+    doc.metadata_mut()
+        .set("server.port", "hint", Value::from("listen port"));
+
+    let meta = doc.metadata();
+    assert_eq!(
+        meta.get("server.old", "deprecated"),
+        Some(&Value::from(true))
+    );
+    assert_eq!(meta.get("server.port", "deprecated"), None);
+    assert_eq!(meta.attributes("server.port").unwrap().len(), 1);
+    assert_eq!(meta.iter().count(), 2);
+}
+
+#[test]
+fn metadata_survives_merge_and_relocate() {
+    let mut a = Metadata::new();
+    a.set("db.url", "hint", Value::from("connection string"));
+    a.set("db.pool[0].size", "unit", Value::from("connections"));
+
+    let mut b = Metadata::new();
+    b.set("db.url", "hint", Value::from("overridden"));
+    b.set("db.url", "secret", Value::from(true));
+    a.merge(b);
+    assert_eq!(a.get("db.url", "hint"), Some(&Value::from("overridden")));
+    assert_eq!(a.get("db.url", "secret"), Some(&Value::from(true)));
+
+    a.relocate("db", "database");
+    assert_eq!(a.get("db.url", "hint"), None);
+    assert_eq!(a.get("database.url", "secret"), Some(&Value::from(true)));
+    assert_eq!(
+        a.get("database.pool[0].size", "unit"),
+        Some(&Value::from("connections"))
+    );
+
+    let root: Value = "[database]\nurl = 'x'\n".parse().unwrap();
+    a.retain_existing(&root);
+    assert_eq!(a.get("database.pool[0].size", "unit"), None);
+    assert!(a.attributes("database.url").is_some());
+
+    assert_eq!(a.remove("database.url", "secret"), Some(Value::from(true)));
+    a.remove_all("database");
+    assert!(a.is_empty());
+}
+
+#[test]
+fn iter_with_spans() {
+    let src = "\
+name = 'demo'
+[server]
+port = 80
+tls.cert = 'a.pem'
+host = 'localhost'
+tls.key = 'a.key'
+
+[[server.routes]]
+path = '/'
+[server.limits]
+[[server.routes]]
+path = '/api'
+";
+    let doc: Document = src.parse().unwrap();
+
+    let server = doc
+        .iter_with_spans("server")
+        .map(|(key, (start, end), value)| (key, &src[start..end], value.type_str()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        server,
+        [
+            ("port", "port", "integer"),
+            ("tls", "tls.cert", "table"),
+            ("host", "host", "string"),
+            ("routes", "server.routes", "array"),
+            ("limits", "server.limits", "table"),
+        ]
+    );
+
+    let root = doc
+        .iter_with_spans("")
+        .map(|(key, span, _)| (key, span))
+        .collect::<Vec<_>>();
+    assert_eq!(root, [("name", (0, 4)), ("server", (15, 21))]);
+
+    let route = doc
+        .iter_with_spans("server.routes[1]")
+        .map(|(key, span, value)| (key, &src[span.0..span.1], value.as_str()))
+        .collect::<Vec<_>>();
+    assert_eq!(route, [("path", "path", Some("/api"))]);
+
+    assert_eq!(doc.iter_with_spans("server.port").count(), 0);
+    assert_eq!(doc.iter_with_spans("server.routes").count(), 0);
+    assert_eq!(doc.iter_with_spans("missing").count(), 0);
+}
+
+#[test]
+fn table_styles() {
+    use toml::ser::TableStyle::{Dotted, Inline, Standard};
+
+    let src = "\
+name = 'demo'
+site = { url = 'x', meta = { a.b = 1 } }
+log.level.max = 3
+
+[deps.serde]
+version = '1'
+
+[[bins]]
+name = 'a'
+opts = { lto = true }
+
+[[bins]]
+name = 'b'
+
+[deps.rand]
+features = [{ std = true }]
+";
+    let doc: Document = src.parse().unwrap();
+
+    assert_eq!(doc.table_style(""), Some(Standard));
+    assert_eq!(doc.table_style("site"), Some(Inline));
+    assert_eq!(doc.table_style("site.meta"), Some(Inline));
+    assert_eq!(doc.table_style("site.meta.a"), Some(Dotted));
+    assert_eq!(doc.table_style("log"), Some(Dotted));
+    assert_eq!(doc.table_style("log.level"), Some(Dotted));
+    assert_eq!(doc.table_style("deps"), Some(Standard));
+    assert_eq!(doc.table_style("deps.serde"), Some(Standard));
+    assert_eq!(doc.table_style("bins[0]"), Some(Standard));
+    assert_eq!(doc.table_style("bins[0].opts"), Some(Inline));
+    assert_eq!(doc.table_style("deps.rand.features[0]"), Some(Inline));
+    assert_eq!(doc.table_style("bins"), None);
+    assert_eq!(doc.table_style("name"), None);
+    assert_eq!(doc.table_style("missing"), None);
+
+    let styles = doc
+        .table_styles()
+        .map(|(path, style)| (path.to_string(), style))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        styles,
+        [
+            ("site".to_string(), Inline),
+            ("site.meta".to_string(), Inline),
+            ("site.meta.a".to_string(), Dotted),
+            ("log".to_string(), Dotted),
+            ("log.level".to_string(), Dotted),
+            ("deps".to_string(), Standard),
+            ("deps.serde".to_string(), Standard),
+            ("bins[0]".to_string(), Standard),
+            ("bins[0].opts".to_string(), Inline),
+            ("bins[1]".to_string(), Standard),
+            ("deps.rand".to_string(), Standard),
+            ("deps.rand.features[0]".to_string(), Inline),
+        ]
+    );
+}
+
+#[test]
+fn implicit_tables() {
+    let src = "\
+site = { url = 'x', meta = { a.b = 1 } }
+log.level.max = 3
+
+[deps.serde]
+version = '1'
+
+[deps]
+rand = '0.8'
+
+[[bins]]
+name = 'a'
+
+[[tools.fmt]]
+name = 'b'
+";
+    let doc: Document = src.parse().unwrap();
+
+    assert_eq!(doc.is_implicit_table(""), Some(false));
+    assert_eq!(doc.is_implicit_table("site"), Some(false));
+    assert_eq!(doc.is_implicit_table("site.meta"), Some(false));
+    assert_eq!(doc.is_implicit_table("site.meta.a"), Some(true));
+    assert_eq!(doc.is_implicit_table("log"), Some(true));
+    assert_eq!(doc.is_implicit_table("log.level"), Some(true));
+    assert_eq!(doc.is_implicit_table("deps"), Some(false));
+    assert_eq!(doc.is_implicit_table("deps.serde"), Some(false));
+    assert_eq!(doc.is_implicit_table("bins[0]"), Some(false));
+    assert_eq!(doc.is_implicit_table("tools"), Some(true));
+    assert_eq!(doc.is_implicit_table("tools.fmt[0]"), Some(false));
+    assert_eq!(doc.is_implicit_table("bins"), None);
+    assert_eq!(doc.is_implicit_table("deps.rand"), None);
+    assert_eq!(doc.is_implicit_table("missing"), None);
+}
+
+#[test]
+fn comments_read() {
+    let src = "\
+# The server.
+# Second line.
+[server] # main
+#  indented
+host = \"x\"   #   padded
+port = 80
+
+# detached
+
+ports = [
+    # web
+    80, # http
+    443,
+]
+inline = { a = 1 } # after
+[[bin]]
+# first bin
+name = \"a\"
+";
+    let doc: Document = src.parse().unwrap();
+    assert_eq!(
+        doc.leading_comment("server").as_deref(),
+        Some("The server.\nSecond line.")
+    );
+    assert_eq!(doc.trailing_comment("server"), Some("main"));
+    assert_eq!(
+        doc.leading_comment("server.host").as_deref(),
+        Some(" indented")
+    );
+    assert_eq!(doc.trailing_comment("server.host"), Some("padded"));
+    assert_eq!(doc.leading_comment("server.port"), None);
+    assert_eq!(doc.trailing_comment("server.port"), None);
+    assert_eq!(doc.leading_comment("server.ports"), None);
+    assert_eq!(
+        doc.leading_comment("server.ports[0]").as_deref(),
+        Some("web")
+    );
+    assert_eq!(doc.trailing_comment("server.ports[0]"), Some("http"));
+    assert_eq!(doc.trailing_comment("server.ports[1]"), None);
+    assert_eq!(doc.trailing_comment("server.inline"), Some("after"));
+    assert_eq!(doc.trailing_comment("server.inline.a"), None);
+    assert_eq!(
+        doc.leading_comment("bin[0].name").as_deref(),
+        Some("first bin")
+    );
+    assert_eq!(doc.leading_comment("missing"), None);
+}
+
+#[test]
+fn comments_edited() {
+    let mut doc: Document = "[a]\nx = 1 # old\n  y = [1, 2]\nz = 3\n".parse().unwrap();
+
+    doc.set_trailing_comment("a.x", Some("new")).unwrap();
+    doc.set_trailing_comment("a.z", Some("added")).unwrap();
+    doc.set_leading_comment("a.y", Some("two\n\nlines"))
+        .unwrap();
+    doc.set_leading_comment("a", Some("table")).unwrap();
+    assert_eq!(
+        doc.to_string(),
+        "# table\n[a]\nx = 1 # new\n  # two\n  #\n  # lines\n  y = [1, 2]\nz = 3 # added\n"
+    );
+    assert_eq!(doc.trailing_comment("a.z"), Some("added"));
+    assert_eq!(doc.span("a.z"), Some((59, 60)));
+
+    doc.set_leading_comment("a.y", None).unwrap();
+    doc.set_trailing_comment("a.x", None).unwrap();
+    doc.set_leading_comment("a.z", None).unwrap();
+    assert_eq!(
+        doc.to_string(),
+        "# table\n[a]\nx = 1\n  y = [1, 2]\nz = 3 # added\n"
+    );
+    assert_eq!(doc.get("a.y[1]").and_then(|v| v.as_integer()), Some(2));
+}
+
+#[test]
+fn comments_rejected() {
+    let mut doc: Document = "a = [1, 2]\nb = { c = 1 }\n".parse().unwrap();
+
+    let err = doc.set_trailing_comment("a[0]", Some("x")).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "`a[0]` is followed by more than a comment on its line"
+    );
+    let err = doc.set_leading_comment("b.c", Some("x")).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "`b.c` does not start its line, so it cannot have a comment above it"
+    );
+    let err = doc.set_trailing_comment("a", Some("x\ny")).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "the comment after `a` must fit on one line"
+    );
+    let err = doc.set_leading_comment("a", Some("\u{1b}")).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "the comment for `a` contains the control character U+001B"
+    );
+    let err = doc.set_leading_comment("d", None).unwrap_err();
+    assert_eq!(err.to_string(), "`d` is not written in the document");
+    assert_eq!(doc.to_string(), "a = [1, 2]\nb = { c = 1 }\n");
+}
+
+#[test]
+fn sort_keys_moves_comments() {
+    let src = "\
+b = 1
+# about a
+a = [
+    1,
+] # trailing
+c.y = 2
+c.x = 3
+
+# detached
+
+z = 1
+y = 2
+[t]
+b = 1
+a = 2
+[[bin]]
+name = 'x'
+# path
+path = 'y'
+";
+    let mut doc: Document = src.parse().unwrap();
+    doc.sort_keys("", |a, b| a.cmp(b)).unwrap();
+    doc.sort_keys("bin[0]", |a, b| b.cmp(a)).unwrap();
+    assert_eq!(
+        doc.to_string(),
+        "\
+# about a
+a = [
+    1,
+] # trailing
+b = 1
+c.y = 2
+c.x = 3
+
+# detached
+
+y = 2
+z = 1
+[t]
+b = 1
+a = 2
+[[bin]]
+# path
+path = 'y'
+name = 'x'
+"
+    );
+    assert_eq!(doc.span("y"), Some((71, 72)));
+    assert_eq!(doc.leading_comment("bin[0].path").as_deref(), Some("path"));
+
+    doc.sort_keys("t", |a, b| a.cmp(b)).unwrap();
+    assert!(doc.to_string().contains("[t]\na = 2\nb = 1\n"));
+}
+
+#[test]
+fn sort_keys_last_line_without_newline() {
+    let mut doc: Document = "b = 1\na = 2".parse().unwrap();
+    doc.sort_keys("", |a, b| a.cmp(b)).unwrap();
+    assert_eq!(doc.to_string(), "a = 2\nb = 1");
+}
+
+#[test]
+fn sort_keys_requires_a_header() {
+    let mut doc: Document = "a = { c = 1, b = 2 }\n[x.y]\n".parse().unwrap();
+    let err = doc.sort_keys("a", |a, b| a.cmp(b)).unwrap_err();
+    assert_eq!(err.to_string(), "`a` is not a table with a header");
+    assert!(doc.sort_keys("x", |a, b| a.cmp(b)).is_err());
+    assert!(doc.sort_keys("x.y", |a, b| a.cmp(b)).is_ok());
+}
+
+#[test]
+fn insert_positions() {
+    use toml::document::InsertPosition;
+
+    let src = "\
+top = 1
+[package]
+  name = 'demo'
+  # the version
+  version = '1'
+  metadata.docs = true
+[empty]
+[deps]
+";
+    let mut doc: Document = src.parse().unwrap();
+    doc.insert(
+        "package.edition",
+        Value::from("2018"),
+        InsertPosition::After("name".to_string()),
+    )
+    .unwrap();
+    doc.insert(
+        "package.license",
+        Value::from("MIT"),
+        InsertPosition::Sorted,
+    )
+    .unwrap();
+    doc.insert(
+        "package.publish",
+        Value::from(false),
+        InsertPosition::BeforeSubtables,
+    )
+    .unwrap();
+    doc.insert("package.zz", Value::from(1), InsertPosition::End)
+        .unwrap();
+    doc.insert("empty.a", Value::from(1), InsertPosition::End)
+        .unwrap();
+    doc.insert("deps.b", Value::from(1), InsertPosition::Sorted)
+        .unwrap();
+    doc.insert("deps.a", Value::from(1), InsertPosition::Sorted)
+        .unwrap();
+    doc.insert("a", Value::from(1), InsertPosition::Sorted)
+        .unwrap();
+    assert_eq!(
+        doc.to_string(),
+        "\
+a = 1
+top = 1
+[package]
+  license = \"MIT\"
+  name = 'demo'
+  edition = \"2018\"
+  # the version
+  version = '1'
+  publish = false
+  metadata.docs = true
+  zz = 1
+[empty]
+a = 1
+[deps]
+a = 1
+b = 1
+"
+    );
+    assert_eq!(doc.get("package.publish"), Some(&Value::Boolean(false)));
+    assert_eq!(doc.get("empty.a"), Some(&Value::Integer(1)));
+}
+
+#[test]
+fn insert_tables_inline() {
+    use toml::document::InsertPosition;
+
+    let mut doc: Document = "[deps]\n".parse().unwrap();
+    let mut dep = toml::value::Table::new();
+    dep.insert("features".to_string(), Value::from(vec!["derive"]));
+    dep.insert("version".to_string(), Value::from("1"));
+    doc.insert("deps.serde", Value::Table(dep), InsertPosition::End)
+        .unwrap();
+    assert_eq!(
+        doc.to_string(),
+        "[deps]\nserde = { features = [\"derive\"], version = \"1\" }\n"
+    );
+    assert_eq!(
+        doc.to_string().parse::<Value>().unwrap()["deps"]["serde"]["version"].as_str(),
+        Some("1")
+    );
+}
+
+#[test]
+fn insert_rejected() {
+    use toml::document::InsertPosition;
+
+    let mut doc: Document = "a = 1\nb = { c = 1 }\n".parse().unwrap();
+    let err = doc
+        .insert("a", Value::from(2), InsertPosition::End)
+        .unwrap_err();
+    assert_eq!(err.to_string(), "`a` already exists");
+    let err = doc
+        .insert("b.d", Value::from(2), InsertPosition::End)
+        .unwrap_err();
+    assert_eq!(err.to_string(), "`b` is not a table with a header");
+    let err = doc
+        .insert("x", Value::from(2), InsertPosition::After("y".to_string()))
+        .unwrap_err();
+    assert_eq!(err.to_string(), "there is no key `y` in ``");
+    assert_eq!(doc.to_string(), "a = 1\nb = { c = 1 }\n");
+}
+
+#[test]
+fn insert_raw_keeps_formatting() {
+    use toml::document::InsertPosition;
+
+    let mut doc: Document = "[server]\nhost = 'x'\n".parse().unwrap();
+    doc.insert_raw(
+        "server.ports",
+        "  [ 80,   # http\n    443 ] # both\n",
+        InsertPosition::End,
+    )
+    .unwrap();
+    doc.insert_raw("server.\"a b\"", "0x1F", InsertPosition::Sorted)
+        .unwrap();
+    assert_eq!(
+        doc.to_string(),
+        "[server]\n\"a b\" = 0x1F\nhost = 'x'\nports = [ 80,   # http\n    443 ] # both\n"
+    );
+    assert_eq!(doc.get("server.ports[1]"), Some(&Value::Integer(443)));
+    assert_eq!(doc.get("server.\"a b\""), Some(&Value::Integer(31)));
+    assert_eq!(doc.trailing_comment("server.ports"), Some("both"));
+}
+
+#[test]
+fn insert_raw_rejects_bad_values() {
+    use toml::document::InsertPosition;
+
+    let mut doc: Document = "a = 1\n".parse().unwrap();
+    let err = doc.insert_raw("b", "1 2", InsertPosition::End).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "expected end of input, found an identifier at line 1 column 3"
+    );
+    let err = doc
+        .insert_raw("b", "1\nc = 2", InsertPosition::End)
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "expected end of input, found an identifier at line 2 column 1"
+    );
+    let err = doc
+        .insert_raw("b", "# one\n1", InsertPosition::End)
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "the text for `b` must start with its value"
+    );
+    let err = doc.insert_raw("a", "2", InsertPosition::End).unwrap_err();
+    assert_eq!(err.to_string(), "`a` already exists");
+    assert_eq!(doc.to_string(), "a = 1\n");
+}
+
+const SERVICE: &str = "\
+# service settings
+name   = \"api\"    # shown in logs
+ports  = [ 80,  443 ]
+limits = { cpu = 2,  memory = '1G' }
+tags.env = 'prod'
+tags.team = 'core'
+
+[database]
+url = 'postgres://db'   # primary
+pool = 10
+
+# replicas, nearest first
+[[replica]]
+url = 'postgres://r1'
+
+[[replica]]
+url = 'postgres://r2'
+";
+
+fn updated(f: impl FnOnce(&mut Value)) -> Result<Document, toml::de::Error> {
+    let mut doc: Document = SERVICE.parse().unwrap();
+    let mut root = Value::Table(doc.as_table().clone());
+    f(&mut root);
+    let table = root.as_table().unwrap().clone();
+    doc.update(table.clone())?;
+    assert_eq!(doc.as_table(), &table);
+    assert_eq!(doc.to_string().parse::<Value>().unwrap(), root);
+    assert_eq!(apply_edits(&doc, SERVICE), doc.to_string());
+    Ok(doc)
+}
+
+fn remove(value: &mut Value, key: &str) {
+    value.as_table_mut().unwrap().remove(key);
+}
+
+#[test]
+fn update_unchanged_is_identical() {
+    let doc = updated(|_| {}).unwrap();
+    assert_eq!(doc.to_string(), SERVICE);
+}
+
+#[test]
+fn update_rewrites_only_changed_values() {
+    let doc = updated(|t| {
+        t["name"] = Value::from("gateway");
+        t["database"]["pool"] = Value::from(20);
+        t["tags"]["team"] = Value::from("edge");
+    })
+    .unwrap();
+    assert_eq!(
+        doc.to_string(),
+        SERVICE
+            .replace("\"api\"", "\"gateway\"")
+            .replace("pool = 10", "pool = 20")
+            .replace("'core'", "\"edge\"")
+    );
+}
+
+#[test]
+fn update_inline_values_as_a_whole() {
+    let doc = updated(|t| {
+        t["ports"].as_array_mut().unwrap().push(Value::from(8080));
+        remove(&mut t["limits"], "memory");
+    })
+    .unwrap();
+    assert_eq!(
+        doc.to_string(),
+        SERVICE
+            .replace("[ 80,  443 ]", "[80, 443, 8080]")
+            .replace("{ cpu = 2,  memory = '1G' }", "{ cpu = 2 }")
+    );
+
+    // An element that changed is written on its own.
+    let doc = updated(|t| t["ports"][1] = Value::from(8443)).unwrap();
+    assert_eq!(
+        doc.to_string(),
+        SERVICE.replace("[ 80,  443 ]", "[ 80,  8443 ]")
+    );
+}
+
+#[test]
+fn update_removes_and_adds_keys() {
+    let doc = updated(|t| {
+        remove(t, "name");
+        remove(&mut t["tags"], "env");
+        remove(&mut t["database"], "url");
+        t["database"]
+            .as_table_mut()
+            .unwrap()
+            .insert("timeout".to_string(), Value::from(30));
+    })
+    .unwrap();
+    assert_eq!(
+        doc.to_string(),
+        "\
+ports  = [ 80,  443 ]
+limits = { cpu = 2,  memory = '1G' }
+tags.team = 'core'
+
+[database]
+pool = 10
+timeout = 30
+
+# replicas, nearest first
+[[replica]]
+url = 'postgres://r1'
+
+[[replica]]
+url = 'postgres://r2'
+"
+    );
+}
+
+#[test]
+fn update_removes_tables() {
+    let doc = updated(|t| {
+        remove(t, "database");
+        t["replica"].as_array_mut().unwrap().pop();
+    })
+    .unwrap();
+    assert_eq!(
+        doc.to_string(),
+        "\
+# service settings
+name   = \"api\"    # shown in logs
+ports  = [ 80,  443 ]
+limits = { cpu = 2,  memory = '1G' }
+tags.env = 'prod'
+tags.team = 'core'
+
+# replicas, nearest first
+[[replica]]
+url = 'postgres://r1'
+
+"
+    );
+}
+
+#[test]
+fn update_replaces_a_table_with_a_value() {
+    let doc = updated(|t| t["database"] = Value::from("postgres://db")).unwrap();
+    assert!(doc
+        .to_string()
+        .contains("tags.team = 'core'\ndatabase = \"postgres://db\"\n"));
+    assert!(!doc.to_string().contains("[database]"));
+}
+
+#[test]
+fn update_rejects_what_cannot_be_written_in_place() {
+    let err = updated(|t| {
+        t["tags"]
+            .as_table_mut()
+            .unwrap()
+            .insert("owner".to_string(), Value::from("me"));
+    })
+    .unwrap_err();
+    assert_eq!(err.to_string(), "`tags.owner` cannot be updated in place");
+
+    let mut doc: Document = SERVICE.parse().unwrap();
+    let mut root = Value::Table(doc.as_table().clone());
+    remove(&mut root, "name");
+    remove(&mut root["tags"], "env");
+    root["tags"]
+        .as_table_mut()
+        .unwrap()
+        .insert("lead".to_string(), Value::from("me"));
+    assert!(doc.update(root.as_table().unwrap().clone()).is_err());
+    assert_eq!(doc.to_string(), SERVICE);
+}
+
+#[test]
+fn update_grown_array_of_tables() {
+    let doc = updated(|t| {
+        t["replica"]
+            .as_array_mut()
+            .unwrap()
+            .push(Value::Table(Default::default()))
+    })
+    .unwrap();
+    assert_eq!(
+        doc.to_string(),
+        "\
+# service settings
+name   = \"api\"    # shown in logs
+ports  = [ 80,  443 ]
+limits = { cpu = 2,  memory = '1G' }
+tags.env = 'prod'
+tags.team = 'core'
+replica = [{ url = \"postgres://r1\" }, { url = \"postgres://r2\" }, {}]
+
+[database]
+url = 'postgres://db'   # primary
+pool = 10
+
+"
+    );
+}
+
+#[test]
+fn set_values() {
+    let mut doc: Document = SERVICE.parse().unwrap();
+    doc.set("replica[1].url", Value::from("postgres://r3"))
+        .unwrap();
+    doc.set("limits.cpu", Value::from(4)).unwrap();
+    assert_eq!(
+        doc.to_string(),
+        SERVICE
+            .replace("'postgres://r2'", "\"postgres://r3\"")
+            .replace("cpu = 2,", "cpu = 4,")
+    );
+    assert_eq!(doc.get("limits.cpu").and_then(Value::as_integer), Some(4));
+
+    let err = doc.set("database.user", Value::from("me")).unwrap_err();
+    assert_eq!(err.to_string(), "`database.user` is not in the document");
+}
+
+fn apply_edits(doc: &Document, src: &str) -> String {
+    let mut text = src.to_string();
+    let mut end = src.len();
+    for edit in doc.edits().iter().rev() {
+        let (start, stop) = edit.span();
+        assert!(start <= stop && stop <= end, "{:?}", doc.edits());
+        text.replace_range(start..stop, edit.replacement());
+        end = start;
+    }
+    text
+}
+
+#[test]
+fn edits_are_minimal() {
+    let doc = updated(|t| {
+        t["name"] = Value::from("apx");
+        t["database"]["pool"] = Value::from(12);
+        remove(&mut t["tags"], "team");
+    })
+    .unwrap();
+    assert_eq!(apply_edits(&doc, SERVICE), doc.to_string());
+    let edits = doc
+        .edits()
+        .iter()
+        .map(|edit| (&SERVICE[edit.span().0..edit.span().1], edit.replacement()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        edits,
+        [("i", "x"), ("tags.team = 'core'\n", ""), ("0", "2")]
+    );
+}
+
+#[test]
+fn edits_merge_and_cancel() {
+    let mut doc: Document = SERVICE.parse().unwrap();
+    doc.set("name", Value::from("gateway")).unwrap();
+    doc.set("name", Value::from("api")).unwrap();
+    assert_eq!(doc.edits(), []);
+
+    doc.set_trailing_comment("name", Some("shown in the logs"))
+        .unwrap();
+    doc.set_trailing_comment("name", Some("shown in all the logs"))
+        .unwrap();
+    doc.set_leading_comment("database.url", Some("main database"))
+        .unwrap();
+    doc.sort_keys("database", |a, b| a.cmp(b)).unwrap();
+    doc.insert(
+        "database.user",
+        Value::from("svc"),
+        toml::document::InsertPosition::End,
+    )
+    .unwrap();
+    assert_eq!(apply_edits(&doc, SERVICE), doc.to_string());
+    assert_eq!(doc.edits()[0].span(), (48, 48));
+    assert_eq!(doc.edits()[0].replacement(), "all the ");
+
+    let mut doc: Document = SERVICE.parse().unwrap();
+    let table = doc.as_table().clone();
+    doc.update(toml::value::Table::new()).unwrap();
+    assert_eq!(apply_edits(&doc, SERVICE), doc.to_string());
+    doc.update(table).unwrap();
+    assert_eq!(apply_edits(&doc, SERVICE), doc.to_string());
+}
+
+#[test]
+fn table_stats() {
+    let src = "\
+title = \"x\"
+
+[server]
+  host    = \"localhost\"
+  port    = 8080
+  \"a b\"   = '''
+first
+longest line'''
+log.level = 3
+inline = { deep = 1 }
+
+[server.tls]
+cert = 'c'
+";
+    let doc: Document = src.parse().unwrap();
+
+    let server = doc.table_stats("server").unwrap();
+    assert_eq!(server.len(), 5);
+    assert_eq!(server.longest_key(), 9);
+    assert_eq!(server.longest_value(), 15);
+    assert_eq!(server.equals_column(), None);
+    let keys = server.lines().iter().map(|l| l.key()).collect::<Vec<_>>();
+    assert_eq!(keys, ["host", "port", "\"a b\"", "log.level", "inline"]);
+    let first = &server.lines()[0];
+    assert_eq!(
+        (
+            first.key_column(),
+            first.equals_column(),
+            first.value_width()
+        ),
+        (2, 10, 11)
+    );
+
+    let tls = doc.table_stats("server.tls").unwrap();
+    assert_eq!(tls.equals_column(), Some(5));
+    assert!(doc.table_stats("server.log").unwrap().is_empty());
+    assert!(doc.table_stats("server.inline").unwrap().is_empty());
+    assert_eq!(doc.table_stats("server.port"), None);
+
+    let tables = doc
+        .stats()
+        .map(|(path, stats)| (path.to_string(), stats.len()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        tables,
+        [
+            ("".to_string(), 1),
+            ("server".to_string(), 5),
+            ("server.tls".to_string(), 1)
+        ]
+    );
+}
+
+#[test]
+fn align_equals() {
+    let src = "\
+[deps]
+serde      = \"1.0\"   # serialization
+toml       = \"0.5\"   # config
+[plain]
+a = 1
+b = 2
+";
+    let mut doc: Document = src.parse().unwrap();
+    assert!(doc.table_stats("deps").unwrap().is_aligned());
+    assert!(!doc.table_stats("plain").unwrap().is_aligned());
+
+    // Without the flag, edits are written as they always were.
+    let mut plain = doc.clone();
+    plain
+        .insert("deps.log", Value::from("0.4"), InsertPosition::End)
+        .unwrap();
+    plain.set("deps.toml", Value::from("0.5.9")).unwrap();
+    assert!(plain
+        .to_string()
+        .contains("toml       = \"0.5.9\"   # config\nlog = \"0.4\"\n"));
+
+    doc.align_equals(true);
+    doc.set("deps.toml", Value::from("0.5.9")).unwrap();
+    doc.insert("deps.log", Value::from("0.4"), InsertPosition::Sorted)
+        .unwrap();
+    doc.insert("deps.serde_derive", Value::from("1.0"), InsertPosition::End)
+        .unwrap();
+    doc.insert("plain.long", Value::from(3), InsertPosition::End)
+        .unwrap();
+    assert_eq!(
+        doc.to_string(),
+        "\
+[deps]
+log          = \"0.4\"
+serde        = \"1.0\"   # serialization
+toml         = \"0.5.9\" # config
+serde_derive = \"1.0\"
+[plain]
+a = 1
+b = 2
+long = 3
+"
+    );
+    assert_eq!(doc.table_stats("deps").unwrap().equals_column(), Some(13));
+    assert_eq!(doc.get("deps.toml").and_then(|v| v.as_str()), Some("0.5.9"));
+}
+
+#[test]
+fn update_string_keeps_quotes() {
+    let src = "\
+basic = \"a\"
+literal = 'b'   # note
+multi = '''
+old'''
+escaped = \"\"\"x\"\"\"
+deps = [\"one\", 'two']
+count = 1
+";
+    let mut doc: Document = src.parse().unwrap();
+    doc.update_string("basic", "a \"quoted\" value").unwrap();
+    doc.update_string("literal", "C:\\path").unwrap();
+    doc.update_string("multi", "new\ntext").unwrap();
+    doc.update_string("escaped", "tab\there\n\"end\"").unwrap();
+    doc.update_string("deps[1]", "it's").unwrap();
+    assert_eq!(
+        doc.to_string(),
+        "\
+basic = \"a \\\"quoted\\\" value\"
+literal = 'C:\\path'   # note
+multi = '''
+new
+text'''
+escaped = \"\"\"tab\\there
+\\\"end\\\"\"\"\"
+deps = [\"one\", \"it's\"]
+count = 1
+"
+    );
+    let reparsed: Document = doc.to_string().parse().unwrap();
+    assert_eq!(reparsed.as_table(), doc.as_table());
+    assert_eq!(doc.get("deps[1]").and_then(|v| v.as_str()), Some("it's"));
+
+    // A literal string that cannot hold the value becomes a basic string.
+    doc.update_string("multi", "ends with '").unwrap();
+    assert!(doc.to_string().contains("multi = \"ends with '\"\n"));
+
+    let err = doc.update_string("count", "2").unwrap_err();
+    assert_eq!(err.to_string(), "`count` is not a string in the document");
+    assert!(doc.update_string("missing", "x").is_err());
+}
@@ -0,0 +1,112 @@
+extern crate serde;
+extern crate toml;
+
+use serde::Deserialize;
+use toml::de::DuplicateKeys;
+use toml::Value;
+
+const LOG: &str = "\
+level = 1
+name = \"first\"
+dotted.a = 1
+inline = { x = 1, x = 2 }
+level = 2
+
+[server]
+port = 80
+dotted.a = 2
+port = 8080
+";
+
+fn read(policy: DuplicateKeys) -> (Value, Vec<String>) {
+    let mut d = toml::Deserializer::new(LOG);
+    d.set_duplicate_keys(policy);
+    let value = Value::deserialize(&mut d).unwrap();
+    let warnings = d.warnings().iter().map(|w| w.to_string()).collect();
+    (value, warnings)
+}
+
+#[test]
+fn error_by_default() {
+    let err = toml::from_str::<Value>(LOG).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "duplicate key: `x` for key `inline` at line 4 column 10"
+    );
+
+    let mut d = toml::Deserializer::new("a = 1\na = 2\n");
+    d.set_duplicate_keys(DuplicateKeys::Error);
+    assert!(Value::deserialize(&mut d).is_err());
+}
+
+#[test]
+fn first_wins() {
+    let (value, warnings) = read(DuplicateKeys::FirstWins);
+    let expected: Value = toml::from_str(
+        "level = 1\n\
+         name = \"first\"\n\
+         dotted.a = 1\n\
+         inline = { x = 1 }\n\
+         [server]\n\
+         port = 80\n\
+         dotted.a = 2\n",
+    )
+    .unwrap();
+    assert_eq!(value, expected);
+    assert_eq!(
+        warnings,
+        [
+            "duplicate key `x`, ignoring its later value at line 4 column 19",
+            "duplicate key `level`, ignoring its later value at line 5 column 1",
+            "duplicate key `port`, ignoring its later value at line 10 column 1",
+        ]
+    );
+}
+
+#[test]
+fn last_wins() {
+    let (value, warnings) = read(DuplicateKeys::LastWins);
+    let expected: Value = toml::from_str(
+        "level = 2\n\
+         name = \"first\"\n\
+         dotted.a = 1\n\
+         inline = { x = 2 }\n\
+         [server]\n\
+         port = 8080\n\
+         dotted.a = 2\n",
+    )
+    .unwrap();
+    assert_eq!(value, expected);
+    assert_eq!(
+        warnings,
+        [
+            "duplicate key `x`, replacing its earlier value at line 4 column 19",
+            "duplicate key `level`, replacing its earlier value at line 5 column 1",
+            "duplicate key `port`, replacing its earlier value at line 10 column 1",
+        ]
+    );
+}
+
+#[test]
+fn dotted_keys() {
+    let mut d = toml::Deserializer::new("a.b = 1\na.b = 2\na.c = 3\n");
+    d.set_duplicate_keys(DuplicateKeys::LastWins);
+    let value = Value::deserialize(&mut d).unwrap();
+    assert_eq!(value, toml::from_str("a.b = 2\na.c = 3\n").unwrap());
+
+    let mut d = toml::Deserializer::new("a.b = 1\na = 2\n");
+    d.set_duplicate_keys(DuplicateKeys::LastWins);
+    let value = Value::deserialize(&mut d).unwrap();
+    assert_eq!(value, toml::from_str("a = 2\n").unwrap());
+
+    let mut d = toml::Deserializer::new("a = 1\na.b = 2\n");
+    d.set_duplicate_keys(DuplicateKeys::LastWins);
+    assert!(Value::deserialize(&mut d).is_err());
+}
+
+#[test]
+fn table_and_key() {
+    let mut d = toml::Deserializer::new("a = 1\n[a]\nb = 2\n");
+    d.set_duplicate_keys(DuplicateKeys::LastWins);
+    assert!(Value::deserialize(&mut d).is_err());
+}
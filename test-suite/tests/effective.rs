@@ -0,0 +1,90 @@
+use toml::effective::{EffectiveConfig, Provenance};
+use toml::value::Table;
+use toml::Value;
+
+fn table(s: &str) -> Table {
+    toml::from_str(s).unwrap()
+}
+
+#[test]
+fn classifies_settings() {
+    let file = table(
+        "name = 'app'\nports = [80]\n[log]\nlevel = 'info'\nold = 1\n[legacy]\na = 1\nb = 2\n",
+    );
+    let effective = table(
+        "name = 'app'\nports = [80, 443]\n[log]\nlevel = 'info'\nfile = 'app.log'\n[pool]\nsize = 4\n",
+    );
+    let config = EffectiveConfig::new(&file, effective.clone());
+    assert_eq!(config.effective(), &effective);
+
+    let mut settings = config
+        .settings()
+        .iter()
+        .map(|s| (s.path().to_string(), s.provenance()))
+        .collect::<Vec<_>>();
+    settings.sort_by(|a, b| a.0.cmp(&b.0));
+    assert_eq!(
+        settings,
+        [
+            ("legacy.a".to_string(), Provenance::Ignored),
+            ("legacy.b".to_string(), Provenance::Ignored),
+            ("log.file".to_string(), Provenance::Defaulted),
+            ("log.level".to_string(), Provenance::File),
+            ("log.old".to_string(), Provenance::Ignored),
+            ("name".to_string(), Provenance::File),
+            ("pool.size".to_string(), Provenance::Defaulted),
+            ("ports".to_string(), Provenance::Overridden),
+        ]
+    );
+
+    let ports = config
+        .with_provenance(Provenance::Overridden)
+        .next()
+        .unwrap();
+    assert_eq!(
+        ports.file_value(),
+        Some(&Value::Array(vec![Value::from(80)]))
+    );
+    assert_eq!(
+        ports
+            .effective_value()
+            .and_then(|v| v.as_array())
+            .map(|a| a.len()),
+        Some(2)
+    );
+    assert_eq!(config.provenance("log"), None);
+    assert_eq!(config.provenance("legacy.a"), Some(Provenance::Ignored));
+}
+
+#[test]
+fn annotated_output() {
+    let file = table("[server]\nport = 80\ndebug = true\n");
+    let effective = table("[server]\nhost = 'localhost'\nport = 8080\n");
+    let config = EffectiveConfig::new(&file, effective);
+    assert_eq!(
+        config.to_annotated_string().unwrap(),
+        "\
+# ignored: `server.debug` is set in the file but not used
+
+[server]
+# default
+host = \"localhost\"
+# overridden; the file sets 80
+port = 8080
+"
+    );
+
+    let same = EffectiveConfig::new(&file, file.clone());
+    assert!(same
+        .settings()
+        .iter()
+        .all(|s| s.provenance() == Provenance::File));
+}
+
+#[test]
+fn from_config_requires_a_table() {
+    let file = Table::new();
+    assert!(EffectiveConfig::from_config(&file, &1).is_err());
+    let config = EffectiveConfig::from_config(&file, &table("a = 1")).unwrap();
+    assert_eq!(config.provenance("a"), Some(Provenance::Defaulted));
+}
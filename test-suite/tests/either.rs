@@ -0,0 +1,164 @@
+use serde::{Deserialize, Deserializer, Serialize};
+use toml::{Either, OneOf, Value};
+
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
+struct Detailed {
+    version: String,
+    #[serde(default)]
+    features: Vec<String>,
+}
+
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
+struct Config {
+    dep: Either<String, Detailed>,
+}
+
+#[test]
+fn either_reads_and_writes_both_sides() {
+    let config: Config = toml::from_str("dep = \"1.0\"").unwrap();
+    assert_eq!(config.dep, Either::Left("1.0".to_string()));
+    assert_eq!(toml::to_string(&config).unwrap(), "dep = \"1.0\"\n");
+
+    let config: Config = toml::from_str("dep = { version = \"2\", features = [\"a\"] }").unwrap();
+    assert!(config.dep.is_right());
+    assert_eq!(config.dep.right().unwrap().features, ["a"]);
+}
+
+#[test]
+fn deepest_error_is_reported() {
+    let err = toml::from_str::<Config>("dep = { version = \"2\", features = \"a\" }").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "invalid type: string \"a\", expected a sequence in `features` for key `dep` \
+         at line 1 column 1"
+    );
+
+    let err = toml::from_str::<Config>("dep = { features = [] }").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "missing field `version` for key `dep` at line 1 column 1"
+    );
+}
+
+#[test]
+fn type_mismatch_ties_go_to_the_first_alternative() {
+    let err = toml::from_str::<Config>("dep = 1").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "invalid type: integer `1`, expected a string for key `dep` at line 1 column 1"
+    );
+}
+
+#[derive(Debug, PartialEq)]
+enum Limit {
+    Count(u32),
+    Size(String),
+    Unlimited(bool),
+}
+
+impl<'de> Deserialize<'de> for Limit {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Limit, D::Error> {
+        OneOf::new()
+            .or(Limit::Count)
+            .or(Limit::Size)
+            .or(Limit::Unlimited)
+            .deserialize(deserializer)
+    }
+}
+
+#[test]
+fn one_of_tries_alternatives_in_order() {
+    let limits: Vec<Limit> = Value::Array(vec![
+        Value::Integer(3),
+        Value::String("10MB".to_string()),
+        Value::Boolean(true),
+    ])
+    .try_into()
+    .unwrap();
+    assert_eq!(
+        limits,
+        [
+            Limit::Count(3),
+            Limit::Size("10MB".to_string()),
+            Limit::Unlimited(true)
+        ]
+    );
+
+    let err = Value::Float(1.5).try_into::<Limit>().unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "invalid type: floating point `1.5`, expected u32"
+    );
+}
+
+#[test]
+fn one_of_without_alternatives() {
+    let err = OneOf::<()>::new()
+        .from_value(Value::Integer(1))
+        .unwrap_err();
+    assert_eq!(err.to_string(), "no alternatives to try");
+}
+
+#[test]
+fn one_of_names_each_failure() {
+    let one_of = OneOf::new()
+        .or(Limit::Count)
+        .or_named("detailed", |d: Detailed| Limit::Size(d.version))
+        .or(|v: Vec<String>| Limit::Size(v.join(",")));
+
+    let failures = match one_of.try_each(Value::Boolean(true)) {
+        Ok(limit) => panic!("read {:?}", limit),
+        Err(failures) => failures,
+    };
+    let failures = failures
+        .iter()
+        .map(|(name, e)| format!("{}: {}", name, e))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        failures,
+        [
+            "u32: invalid type: boolean `true`, expected u32",
+            "detailed: invalid type: boolean `true`, expected struct Detailed",
+            "Vec<String>: invalid type: boolean `true`, expected a sequence",
+        ]
+    );
+    assert_eq!(
+        format!("{:?}", one_of),
+        "OneOf { alternatives: [\"u32\", \"detailed\", \"Vec<String>\"], report_all: false }"
+    );
+}
+
+#[derive(Debug, PartialEq)]
+struct Verbose(Limit);
+
+impl<'de> Deserialize<'de> for Verbose {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Verbose, D::Error> {
+        OneOf::new()
+            .or_named("count", Limit::Count)
+            .or_named("detailed", |d: Detailed| Limit::Size(d.version))
+            .report_all()
+            .deserialize(deserializer)
+            .map(Verbose)
+    }
+}
+
+#[test]
+fn one_of_reports_all_failures() {
+    #[derive(Debug, Deserialize)]
+    struct Quota {
+        #[allow(dead_code)]
+        limit: Verbose,
+    }
+
+    let quota: Quota = toml::from_str("limit = 5").unwrap();
+    assert_eq!(quota.limit, Verbose(Limit::Count(5)));
+
+    let err = toml::from_str::<Quota>("limit = { version = 2 }").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "no alternative accepts the value: \
+         count: invalid type: map, expected u32; \
+         detailed: invalid type: integer `2`, expected a string in `version` \
+         for key `limit` at line 1 column 1"
+    );
+}
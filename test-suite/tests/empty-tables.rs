@@ -0,0 +1,163 @@
+use serde::Serialize;
+use std::collections::BTreeMap;
+use toml::ser::{EmptyTables, Serializer};
+
+#[derive(Serialize)]
+struct Item {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    x: Option<u32>,
+}
+
+#[derive(Serialize)]
+struct Config {
+    a: u32,
+    list: Vec<Item>,
+    empty: BTreeMap<String, u32>,
+    nested: Nested,
+    items: Vec<Item>,
+}
+
+#[derive(Serialize)]
+struct Nested {
+    deep: BTreeMap<String, u32>,
+    item: Item,
+    full: BTreeMap<String, u32>,
+    after: Item,
+}
+
+fn config() -> Config {
+    let mut full = BTreeMap::new();
+    full.insert("k".to_string(), 1);
+    Config {
+        a: 1,
+        list: vec![],
+        empty: BTreeMap::new(),
+        nested: Nested {
+            deep: BTreeMap::new(),
+            item: Item { x: None },
+            full,
+            after: Item { x: None },
+        },
+        items: vec![Item { x: None }, Item { x: Some(1) }],
+    }
+}
+
+fn write(style: EmptyTables) -> String {
+    let mut out = String::new();
+    config()
+        .serialize(Serializer::new(&mut out).empty_tables(style))
+        .unwrap();
+    out
+}
+
+#[test]
+fn header() {
+    let out = write(EmptyTables::Header);
+    assert_eq!(out, toml::to_string(&config()).unwrap());
+    assert_eq!(
+        out,
+        "\
+a = 1
+list = []
+
+[empty]
+[nested.deep]
+
+[nested.item]
+
+[nested.full]
+k = 1
+
+[nested.after]
+
+[[items]]
+
+[[items]]
+x = 1
+"
+    );
+}
+
+#[test]
+fn inline() {
+    let out = write(EmptyTables::Inline);
+    assert_eq!(
+        out,
+        "\
+a = 1
+list = []
+empty = {}
+
+[nested]
+deep = {}
+item = {}
+
+[nested.full]
+k = 1
+
+[nested.after]
+
+[[items]]
+
+[[items]]
+x = 1
+"
+    );
+    out.parse::<toml::Value>().unwrap();
+}
+
+#[test]
+fn skip() {
+    let out = write(EmptyTables::Skip);
+    assert_eq!(
+        out,
+        "\
+a = 1
+list = []
+[nested.full]
+k = 1
+
+[[items]]
+
+[[items]]
+x = 1
+"
+    );
+}
+
+#[test]
+fn skip_with_arrays() {
+    let out = write(EmptyTables::SkipWithArrays);
+    assert_eq!(
+        out,
+        "\
+a = 1
+[nested.full]
+k = 1
+
+[[items]]
+
+[[items]]
+x = 1
+"
+    );
+}
+
+#[test]
+fn skip_nested_empty_tables() {
+    let value = toml::toml! {
+        a = 1
+        [outer.inner.deeper]
+    };
+    let mut out = String::new();
+    value
+        .serialize(Serializer::new(&mut out).empty_tables(EmptyTables::Skip))
+        .unwrap();
+    assert_eq!(out, "a = 1\n");
+
+    let mut out = String::new();
+    toml::value::Table::new()
+        .serialize(Serializer::new(&mut out).empty_tables(EmptyTables::Skip))
+        .unwrap();
+    assert_eq!(out, "");
+}
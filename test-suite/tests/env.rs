@@ -0,0 +1,82 @@
+extern crate toml;
+
+use toml::compat::{from_vars, to_env_with};
+use toml::value::Table;
+use toml::Value;
+
+#[test]
+fn nested_and_sniffed() {
+    let vars = vec![
+        ("MYAPP_LOG__LEVEL", "debug"),
+        ("MYAPP_LOG__FILE_NAME", "app.log"),
+        ("MYAPP_PORT", "8080"),
+        ("MYAPP_RATIO", "0.5"),
+        ("MYAPP_TLS", "true"),
+        ("MYAPP_VERSION", "'1'"),
+        ("MYAPP_HOSTS", "[\"a\", \"b\"]"),
+        ("MYAPP_LIMITS", "{ cpu = 2 }"),
+        ("MYAPP_BROKEN", "[not toml"),
+        ("MYAPP_UMASK", "0022"),
+        ("OTHER_PORT", "1"),
+    ];
+    let table = from_vars(vars, "MYAPP_").unwrap();
+    assert_eq!(table["log"]["level"].as_str(), Some("debug"));
+    assert_eq!(table["log"]["file_name"].as_str(), Some("app.log"));
+    assert_eq!(table["port"], Value::Integer(8080));
+    assert_eq!(table["ratio"], Value::Float(0.5));
+    assert_eq!(table["tls"], Value::Boolean(true));
+    assert_eq!(table["version"].as_str(), Some("1"));
+    assert_eq!(table["hosts"], Value::Array(vec!["a".into(), "b".into()]));
+    assert_eq!(table["limits"]["cpu"], Value::Integer(2));
+    assert_eq!(table["broken"].as_str(), Some("[not toml"));
+    assert_eq!(table["umask"].as_str(), Some("0022"));
+    assert!(!table.contains_key("other_port"));
+    assert_eq!(table.len(), 9);
+}
+
+#[test]
+fn merged_over_file() {
+    let mut config: Table = toml::from_str("[log]\nlevel = 'warn'\nfile_name = 'x.log'\n").unwrap();
+    config.merge(from_vars(vec![("APP_LOG__LEVEL", "trace")], "APP_").unwrap());
+    assert_eq!(config["log"]["level"].as_str(), Some("trace"));
+    assert_eq!(config["log"]["file_name"].as_str(), Some("x.log"));
+}
+
+#[test]
+fn round_trip() {
+    let config: Table =
+        toml::from_str("ports = [80, 443]\n\n[log]\nlevel = 'debug'\nfile = 'a b.log'\n").unwrap();
+    let env = to_env_with(&config, "APP_", "__").unwrap();
+    let vars = env.lines().map(|line| {
+        // What a shell sourcing the lines would do for these values.
+        let (name, value) = line.split_at(line.find('=').unwrap());
+        (name.to_string(), value[1..].trim_matches('"').to_string())
+    });
+    assert_eq!(from_vars(vars, "APP_").unwrap(), config);
+}
+
+#[test]
+fn errors() {
+    let err = from_vars(vec![("APP_LOG", "1"), ("APP_LOG__FILE", "a")], "APP_").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "environment variable `APP_LOG__FILE` sets `log`, which another variable set"
+    );
+    let err = from_vars(vec![("APP_LOG", "1"), ("APP_log", "2")], "APP_").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "environment variable `APP_log` sets `log`, which another variable set"
+    );
+    let err = from_vars(vec![("APP_A____B", "1")], "APP_").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "environment variable `APP_A____B` has an empty key"
+    );
+}
+
+#[test]
+fn process_environment() {
+    std::env::set_var("TOML_ENV_TEST_SERVER__PORT", "81");
+    let table = toml::from_env_prefix("TOML_ENV_TEST_").unwrap();
+    assert_eq!(table["server"]["port"], Value::Integer(81));
+}
@@ -0,0 +1,115 @@
+use std::error::Error as StdError;
+use std::io;
+
+use toml::path::KeyPath;
+use toml::value::{Datetime, Table};
+use toml::Value;
+
+#[derive(Debug)]
+enum AppError {
+    Toml(Box<dyn StdError + Send + Sync>),
+    Io(io::Error),
+}
+
+impl<E: StdError + Send + Sync + 'static> From<E> for AppError {
+    fn from(e: E) -> AppError {
+        AppError::Toml(Box::new(e))
+    }
+}
+
+fn check(f: impl FnOnce() -> Result<(), AppError>) -> String {
+    match f().unwrap_err() {
+        AppError::Toml(e) => e.to_string(),
+        AppError::Io(e) => panic!("unexpected io error: {}", e),
+    }
+}
+
+#[test]
+fn compose_with_question_mark() {
+    let msg = check(|| {
+        "x = ".parse::<Value>()?;
+        Ok(())
+    });
+    assert!(msg.contains("unexpected eof"), "{}", msg);
+
+    let msg = check(|| {
+        toml::to_string(&Some(()))?;
+        Ok(())
+    });
+    assert_eq!(msg, "unsupported Rust type");
+
+    let msg = check(|| {
+        "1979-05".parse::<Datetime>()?;
+        Ok(())
+    });
+    assert!(msg.contains("datetime"), "{}", msg);
+
+    let msg = check(|| {
+        "a..b".parse::<KeyPath>()?;
+        Ok(())
+    });
+    assert!(!msg.is_empty());
+
+    let msg = check(|| {
+        let table: Table = toml::from_str("a = '@b'").unwrap();
+        toml::reference::resolve(&table)?;
+        Ok(())
+    });
+    assert!(msg.contains('b'), "{}", msg);
+
+    let msg = check(|| {
+        let template: Table = toml::from_str("a = '{{ b }}'").unwrap();
+        toml::render(&template, &Table::new())?;
+        Ok(())
+    });
+    assert!(msg.contains('b'), "{}", msg);
+}
+
+#[test]
+fn into_io_error() {
+    fn load() -> io::Result<Value> {
+        Ok("x = ".parse::<Value>()?)
+    }
+    fn store() -> io::Result<String> {
+        Ok(toml::to_string(&Some(()))?)
+    }
+
+    let err = load().unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    let err = store().unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    assert_eq!(err.to_string(), "unsupported Rust type");
+    let _ = AppError::Io(err);
+}
+
+#[test]
+fn umbrella_error() {
+    fn load(text: &str) -> Result<Table, toml::Error> {
+        let table: Table = toml::from_str(text)?;
+        toml::to_string(&table)?;
+        Ok(table)
+    }
+    fn save(value: &Option<()>) -> Result<String, toml::Error> {
+        Ok(toml::to_string(value)?)
+    }
+
+    let err = load("a = 1\nb = ").unwrap_err();
+    assert!(matches!(err, toml::Error::De(_)));
+    assert_eq!(err.line_col(), Some((1, 4)));
+    assert_eq!(err.offset(), Some(10));
+    assert_eq!(
+        err.to_string(),
+        "unexpected eof encountered at line 2 column 5"
+    );
+
+    let err = save(&Some(())).unwrap_err();
+    assert_eq!(err, toml::Error::Ser(toml::ser::Error::UnsupportedType));
+    assert_eq!(err.line_col(), None);
+    assert_eq!(err.offset(), None);
+    assert_eq!(io::Error::from(err).kind(), io::ErrorKind::InvalidData);
+
+    let _ = check(|| {
+        save(&Some(()))?;
+        Ok(())
+    });
+}
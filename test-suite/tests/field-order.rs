@@ -0,0 +1,146 @@
+use serde::Serialize;
+use toml::ser::{FieldRules, Serializer};
+
+#[derive(Serialize)]
+struct Manifest {
+    package: Package,
+    dependencies: Dependencies,
+}
+
+#[derive(Serialize)]
+struct Package {
+    name: &'static str,
+    version: &'static str,
+    edition: &'static str,
+    description: Option<&'static str>,
+    authors: Vec<&'static str>,
+}
+
+#[derive(Serialize)]
+struct Dependencies {
+    toml: &'static str,
+    serde: &'static str,
+}
+
+fn manifest() -> Manifest {
+    Manifest {
+        package: Package {
+            name: "demo",
+            version: "0.1.0",
+            edition: "2018",
+            description: None,
+            authors: vec!["Alice"],
+        },
+        dependencies: Dependencies {
+            toml: "0.5",
+            serde: "1",
+        },
+    }
+}
+
+#[test]
+fn declaration_order() {
+    assert_eq!(
+        toml::to_string(&manifest()).unwrap(),
+        "\
+[package]
+name = \"demo\"
+version = \"0.1.0\"
+edition = \"2018\"
+authors = [\"Alice\"]
+
+[dependencies]
+toml = \"0.5\"
+serde = \"1\"
+"
+    );
+}
+
+#[test]
+fn alphabetical_order() {
+    let mut out = String::new();
+    manifest()
+        .serialize(Serializer::new(&mut out).sort_fields(true))
+        .unwrap();
+    assert_eq!(
+        out,
+        "\
+[dependencies]
+serde = \"1\"
+toml = \"0.5\"
+
+[package]
+authors = [\"Alice\"]
+edition = \"2018\"
+name = \"demo\"
+version = \"0.1.0\"
+"
+    );
+}
+
+#[test]
+fn alphabetical_order_puts_tables_last() {
+    #[derive(Serialize)]
+    struct Config {
+        workers: Vec<Worker>,
+        log: Log,
+        name: &'static str,
+    }
+
+    #[derive(Serialize)]
+    struct Worker {
+        id: u32,
+    }
+
+    #[derive(Serialize)]
+    struct Log {
+        level: &'static str,
+    }
+
+    let config = Config {
+        workers: vec![Worker { id: 1 }],
+        log: Log { level: "info" },
+        name: "demo",
+    };
+    let mut out = String::new();
+    config
+        .serialize(Serializer::new(&mut out).sort_fields(true))
+        .unwrap();
+    assert_eq!(
+        out,
+        "\
+name = \"demo\"
+
+[[workers]]
+id = 1
+
+[log]
+level = \"info\"
+"
+    );
+}
+
+#[test]
+fn alphabetical_order_uses_renamed_fields() {
+    let mut rules = FieldRules::new();
+    rules.rename("Package", "version", "a-version");
+
+    let mut out = String::new();
+    manifest()
+        .package
+        .serialize(
+            Serializer::new(&mut out)
+                .sort_fields(true)
+                .field_rules(rules),
+        )
+        .unwrap();
+    assert_eq!(
+        out,
+        "\
+a-version = \"0.1.0\"
+authors = [\"Alice\"]
+edition = \"2018\"
+name = \"demo\"
+"
+    );
+}
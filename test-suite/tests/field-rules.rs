@@ -0,0 +1,105 @@
+extern crate toml;
+
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+use toml::ser::{FieldRules, Serializer};
+
+#[derive(Serialize)]
+struct Package {
+    name: String,
+    version: String,
+    metadata: Meta,
+    build: Option<Build>,
+}
+
+#[derive(Serialize)]
+struct Build {
+    jobs: u32,
+    internal_cache_dir: String,
+}
+
+#[derive(Serialize)]
+struct Meta {
+    extra: BTreeMap<String, String>,
+}
+
+fn package() -> Package {
+    let mut extra = BTreeMap::new();
+    extra.insert("license".to_string(), "MIT".to_string());
+    Package {
+        name: "app".to_string(),
+        version: "1.0.0".to_string(),
+        metadata: Meta { extra },
+        build: Some(Build {
+            jobs: 4,
+            internal_cache_dir: "/tmp".to_string(),
+        }),
+    }
+}
+
+fn encode(value: &Package, rules: FieldRules) -> Result<String, toml::ser::Error> {
+    let mut out = String::new();
+    let mut ser = Serializer::new(&mut out);
+    ser.field_rules(rules);
+    value.serialize(&mut ser)?;
+    Ok(out)
+}
+
+#[test]
+fn no_rules() {
+    assert_eq!(
+        encode(&package(), FieldRules::new()).unwrap(),
+        toml::to_string(&package()).unwrap()
+    );
+}
+
+#[test]
+fn skip_rename_flatten() {
+    let mut rules = FieldRules::new();
+    rules
+        .rename("Package", "version", "pkg-version")
+        .skip("Build", "internal_cache_dir")
+        .rename("Build", "jobs", "build-jobs")
+        .flatten("Package", "metadata")
+        .flatten("Meta", "extra");
+    assert_eq!(
+        encode(&package(), rules).unwrap(),
+        "\
+name = \"app\"
+pkg-version = \"1.0.0\"
+license = \"MIT\"
+
+[build]
+build-jobs = 4
+"
+    );
+}
+
+#[test]
+fn skip_table() {
+    let mut rules = FieldRules::new();
+    rules.skip("Package", "build").skip("Package", "metadata");
+    assert_eq!(
+        encode(&package(), rules).unwrap(),
+        "name = \"app\"\nversion = \"1.0.0\"\n"
+    );
+}
+
+#[test]
+fn flatten_errors() {
+    let mut rules = FieldRules::new();
+    rules.flatten("Package", "name");
+    assert_eq!(
+        encode(&package(), rules),
+        Err(toml::ser::Error::UnsupportedType)
+    );
+
+    // the flattened build values would follow the [metadata.extra] table
+    let mut rules = FieldRules::new();
+    rules.flatten("Package", "build");
+    assert_eq!(
+        encode(&package(), rules),
+        Err(toml::ser::Error::ValueAfterTable)
+    );
+}
@@ -0,0 +1,78 @@
+extern crate toml;
+
+use toml::fields::{emit, FieldSink, FieldValue};
+use toml::value::Table;
+
+/// A sink recording typed fields, as a logging library's record would.
+#[derive(Default)]
+struct Record {
+    strings: Vec<(String, String)>,
+    integers: Vec<(String, i64)>,
+    others: Vec<(String, String)>,
+}
+
+impl FieldSink for Record {
+    fn field(&mut self, key: &str, value: FieldValue<'_>) {
+        match value {
+            FieldValue::String(s) => self.strings.push((key.to_string(), s.to_string())),
+            FieldValue::Integer(i) => self.integers.push((key.to_string(), i)),
+            _ => self.others.push((key.to_string(), value.to_string())),
+        }
+    }
+}
+
+#[test]
+fn typed_fields() {
+    let config: Table = toml::from_str(
+        r#"
+debug = false
+empty = {}
+name = "demo"
+ratio = 0.5
+started = 1979-05-27T07:32:00Z
+tags = ["a", "b"]
+
+[server]
+port = 8080
+
+[[users]]
+name = "ann"
+"#,
+    )
+    .unwrap();
+    let mut record = Record::default();
+    emit(&config, &mut record);
+    assert_eq!(
+        record.strings,
+        [
+            ("name".to_string(), "demo".to_string()),
+            ("users[0].name".to_string(), "ann".to_string()),
+        ]
+    );
+    assert_eq!(record.integers, [("server.port".to_string(), 8080)]);
+    let others = record
+        .others
+        .iter()
+        .map(|(k, v)| format!("{}={}", k, v))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        others,
+        [
+            "debug=false",
+            "empty={}",
+            "ratio=0.5",
+            "started=1979-05-27T07:32:00Z",
+            "tags=[\"a\", \"b\"]",
+        ]
+    );
+}
+
+#[test]
+fn dyn_sink() {
+    let config: Table = toml::from_str("a = 1\n'b.c' = 2\n").unwrap();
+    let mut keys = Vec::new();
+    let mut closure = |key: &str, _: FieldValue<'_>| keys.push(key.to_string());
+    let sink: &mut dyn FieldSink = &mut closure;
+    emit(&config, sink);
+    assert_eq!(keys, ["a", "\"b.c\""]);
+}
@@ -0,0 +1,105 @@
+use std::env;
+use std::path::PathBuf;
+
+use serde::Deserializer;
+use serde_derive::{Deserialize, Serialize};
+use toml::file_path::{ExpandedPath, PathErrorKind, PathKind, PathOptions};
+
+#[derive(Debug, Deserialize, Serialize)]
+struct Config {
+    data: ExpandedPath,
+    #[serde(deserialize_with = "absolute")]
+    logs: PathBuf,
+}
+
+fn absolute<'de, D: Deserializer<'de>>(deserializer: D) -> Result<PathBuf, D::Error> {
+    PathOptions::new()
+        .expand_env(true)
+        .require(PathKind::Absolute)
+        .deserialize(deserializer)
+}
+
+#[cfg(unix)]
+#[test]
+fn expands_home_and_variables() {
+    env::set_var("HOME", "/home/app");
+    env::set_var("TOML_TEST_LOGS", "/var/log");
+    let config: Config =
+        toml::from_str("data = '~/data/${TOML_TEST_LOGS}x'\nlogs = '$TOML_TEST_LOGS/app'\n")
+            .unwrap();
+    assert_eq!(*config.data, PathBuf::from("/home/app/data//var/logx"));
+    assert_eq!(config.logs, PathBuf::from("/var/log/app"));
+
+    assert_eq!(
+        toml::to_string(&config).unwrap(),
+        "data = \"/home/app/data//var/logx\"\nlogs = \"/var/log/app\"\n"
+    );
+}
+
+#[test]
+fn leaves_other_text() {
+    let mut options = PathOptions::new();
+    options.expand_home(true).expand_env(true);
+    for &(written, read) in &[
+        ("~user/x", "~user/x"),
+        ("a~/b", "a~/b"),
+        ("cost$", "cost$"),
+        ("$$HOME", "$HOME"),
+        ("${", "${"),
+        ("$-", "$-"),
+    ] {
+        assert_eq!(options.resolve(written).unwrap(), PathBuf::from(read));
+    }
+    assert_eq!(
+        PathOptions::new().resolve("~/$HOME").unwrap(),
+        PathBuf::from("~/$HOME")
+    );
+}
+
+#[test]
+fn unset_variable() {
+    let err = PathOptions::new()
+        .expand_env(true)
+        .resolve("${TOML_TEST_UNSET}/x")
+        .unwrap_err();
+    assert_eq!(
+        *err.kind(),
+        PathErrorKind::UnsetVariable("TOML_TEST_UNSET".to_string())
+    );
+    assert_eq!(err.path(), "${TOML_TEST_UNSET}/x");
+    assert_eq!(
+        err.to_string(),
+        "environment variable `TOML_TEST_UNSET` in path `${TOML_TEST_UNSET}/x` is not set"
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn kind_errors_point_at_value() {
+    let err = toml::from_str::<Config>("data = 'x'\nlogs = 'logs'\n").unwrap_err();
+    assert_eq!(err.line_col(), Some((1, 7)));
+    assert!(
+        err.to_string()
+            .starts_with("path `logs` is relative, expected an absolute path"),
+        "{}",
+        err
+    );
+
+    let mut relative = PathOptions::new();
+    relative.require(PathKind::Relative);
+    assert_eq!(
+        *relative.resolve("/etc").unwrap_err().kind(),
+        PathErrorKind::Absolute
+    );
+    assert!(relative.resolve("../etc").is_ok());
+}
+
+#[test]
+fn rejects_non_strings() {
+    let err = toml::from_str::<Config>("data = 1\nlogs = '/'\n").unwrap_err();
+    assert!(
+        err.to_string().contains("expected a string holding a path"),
+        "{}",
+        err
+    );
+}
@@ -0,0 +1,100 @@
+extern crate toml;
+
+use toml::compat::{to_env, to_env_with, to_properties};
+use toml::value::Table;
+use toml::Value;
+
+const CONFIG: &str = r#"
+motd = "say \"hi\"\n$HOME"
+name = "demo"
+ports = [80, 443]
+
+[server]
+empty = {}
+max-conns = 100
+started = 1979-05-27T07:32:00Z
+
+[[users]]
+name = "Zoë"
+"#;
+
+fn config() -> Table {
+    toml::from_str(CONFIG).unwrap()
+}
+
+#[test]
+fn flatten_leaves() {
+    let flat = config().flatten();
+    let paths = flat.iter().map(|(p, _)| p.to_string()).collect::<Vec<_>>();
+    assert_eq!(
+        paths,
+        [
+            "motd",
+            "name",
+            "ports",
+            "server.empty",
+            "server.max-conns",
+            "server.started",
+            "users[0].name",
+        ]
+    );
+    assert_eq!(flat[2].1, Value::Array(vec![80.into(), 443.into()]));
+    assert_eq!(flat[3].1, Value::Table(Table::new()));
+}
+
+#[test]
+fn env_lines() {
+    assert_eq!(
+        to_env(&config()).unwrap(),
+        "\
+MOTD=\"say \\\"hi\\\"\\n\\$HOME\"
+NAME=demo
+PORTS=\"[80, 443]\"
+SERVER_EMPTY=\"{}\"
+SERVER_MAX_CONNS=100
+SERVER_STARTED=1979-05-27T07:32:00Z
+USERS_0_NAME=\"Zoë\"
+"
+    );
+    let table: Table = toml::from_str("[log]\nfile = ''\nlevel = 'debug'\n").unwrap();
+    assert_eq!(
+        to_env_with(&table, "APP_", "__").unwrap(),
+        "APP_LOG__FILE=\"\"\nAPP_LOG__LEVEL=debug\n"
+    );
+}
+
+#[test]
+fn properties_lines() {
+    assert_eq!(
+        to_properties(&config()).unwrap(),
+        "\
+motd=say \"hi\"\\n$HOME
+name=demo
+ports=[80, 443]
+server.empty={}
+server.max-conns=100
+server.started=1979-05-27T07:32:00Z
+users.0.name=Zo\\u00EB
+"
+    );
+    let table: Table = toml::from_str("\"a key=1\" = ' lead\\tC:\\dir'\nemoji = '🦀'\n").unwrap();
+    assert_eq!(
+        to_properties(&table).unwrap(),
+        "a\\ key\\=1=\\ lead\\\\tC:\\\\dir\nemoji=\\uD83E\\uDD80\n"
+    );
+}
+
+#[test]
+fn collisions() {
+    let table: Table = toml::from_str("a = { b = 2 }\na-b = 1\n").unwrap();
+    let err = to_env(&table).unwrap_err();
+    assert_eq!(err.to_string(), "`a.b` and `a-b` are both written as `A_B`");
+    assert!(to_properties(&table).is_ok());
+
+    let table: Table = toml::from_str("a = { b = 2 }\n\"a.b\" = 1\n").unwrap();
+    let err = to_properties(&table).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "`a.b` and `\"a.b\"` are both written as `a.b`"
+    );
+}
@@ -54,3 +54,27 @@ fn no_unnecessary_newlines_table() {
     .unwrap()
     .starts_with("\n"));
 }
+
+#[test]
+fn key_quoting() {
+    let mut table = std::collections::BTreeMap::new();
+    table.insert("bare-key_1", 1);
+    table.insert("with space", 2);
+    table.insert("say \"hi\"", 3);
+    table.insert("C:\\dir", 4);
+    table.insert("it's \"quoted\"", 5);
+    table.insert("", 6);
+    let out = to_string(&table).unwrap();
+    assert_eq!(
+        out,
+        "\"\" = 6\n\
+         'C:\\dir' = 4\n\
+         bare-key_1 = 1\n\
+         \"it's \\\"quoted\\\"\" = 5\n\
+         'say \"hi\"' = 3\n\
+         \"with space\" = 2\n"
+    );
+    let back: std::collections::BTreeMap<String, i32> = toml::from_str(&out).unwrap();
+    assert_eq!(back.len(), 6);
+    assert_eq!(back["C:\\dir"], 4);
+}
@@ -0,0 +1,86 @@
+extern crate toml;
+
+use std::collections::BTreeMap;
+
+use toml::{Deserializer, Value};
+
+fn value<'a, T: serde::Deserialize<'a>>(s: &'a str) -> Result<T, String> {
+    Deserializer::new(s)
+        .parse_value()
+        .map_err(|e| e.to_string())
+}
+
+#[test]
+fn values() {
+    assert_eq!(value::<u16>("8080"), Ok(8080));
+    assert_eq!(value::<f64>("  1e3\n"), Ok(1000.0));
+    assert_eq!(value::<bool>("true # yes"), Ok(true));
+    assert_eq!(value::<String>("'a b'"), Ok("a b".to_string()));
+    assert_eq!(
+        value::<Vec<String>>("['a',\n 'b']"),
+        Ok(vec!["a".to_string(), "b".to_string()])
+    );
+    assert_eq!(
+        value::<Value>("1979-05-27")
+            .unwrap()
+            .as_datetime()
+            .unwrap()
+            .to_string(),
+        "1979-05-27"
+    );
+    let table = value::<BTreeMap<String, i64>>("{ a = 1, b.c = 2 }");
+    assert!(table.is_err());
+    let table = value::<BTreeMap<String, i64>>("{ a = 1, b = 2 }").unwrap();
+    assert_eq!(table["b"], 2);
+}
+
+#[test]
+fn value_errors() {
+    assert_eq!(
+        value::<i64>("1 2"),
+        Err("expected end of input, found an identifier at line 1 column 3".to_string())
+    );
+    assert_eq!(
+        value::<i64>("a = 1"),
+        Err(
+            "invalid TOML value, did you mean to use a quoted string? at line 1 column 1"
+                .to_string()
+        )
+    );
+    assert_eq!(
+        value::<i64>(""),
+        Err("unexpected eof encountered at line 1 column 1".to_string())
+    );
+    assert_eq!(
+        value::<u8>("\n\n300"),
+        Err("invalid value: integer `300`, expected u8 at line 3 column 1".to_string())
+    );
+}
+
+#[test]
+fn key_values() {
+    let (key, v): (_, Value) = Deserializer::new("server.\"bind addr\" = { port = 80 }\n")
+        .parse_key_value()
+        .unwrap();
+    assert_eq!(key.to_string(), "server.\"bind addr\"");
+    assert_eq!(v["port"].as_integer(), Some(80));
+
+    let (key, v): (_, u16) = Deserializer::new("port=8080").parse_key_value().unwrap();
+    assert_eq!(key.len(), 1);
+    assert_eq!(v, 8080);
+
+    let err = Deserializer::new("a = 1\nb = 2")
+        .parse_key_value::<i64>()
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "expected end of input, found an identifier at line 2 column 1"
+    );
+    let err = Deserializer::new("a 1")
+        .parse_key_value::<i64>()
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "expected an equals, found an identifier at line 1 column 3"
+    );
+}
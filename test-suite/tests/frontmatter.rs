@@ -0,0 +1,41 @@
+extern crate toml;
+
+use toml::frontmatter::{extract, extract_with_fence};
+
+#[test]
+fn extracts() {
+    let page = "+++\ntitle = \"Hello\"\ntags = [\"a\"]\n+++\n\n# Hello\n+++\n";
+    let (meta, body) = extract(page).unwrap().unwrap();
+    assert_eq!(meta["title"].as_str(), Some("Hello"));
+    assert_eq!(body, "\n# Hello\n+++\n");
+
+    let (meta, body) = extract("\u{feff}+++ \r\n+++").unwrap().unwrap();
+    assert!(meta.is_empty());
+    assert_eq!(body, "");
+
+    let (meta, body) = extract_with_fence("---\na = 1\n---\nbody", "---")
+        .unwrap()
+        .unwrap();
+    assert_eq!(meta["a"].as_integer(), Some(1));
+    assert_eq!(body, "body");
+}
+
+#[test]
+fn no_front_matter() {
+    assert!(extract("# Hello\n+++\na = 1\n+++\n").unwrap().is_none());
+    assert!(extract("\n+++\na = 1\n+++\n").unwrap().is_none());
+    assert!(extract("++++\n").unwrap().is_none());
+    assert!(extract("").unwrap().is_none());
+}
+
+#[test]
+fn errors() {
+    let err = extract("+++\na = 1\n").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "unterminated front matter, expected a closing `+++` line at line 1 column 1"
+    );
+
+    let err = extract("+++\na = 1\nb = \n+++\nbody").unwrap_err();
+    assert_eq!(err.line_col(), Some((2, 4)));
+}
@@ -0,0 +1,163 @@
+use std::fs;
+use std::path::PathBuf;
+use std::thread;
+use std::time::Duration;
+
+use toml::document::InsertPosition;
+use toml::fs::{UpdateErrorKind, UpdateOptions};
+use toml::Value;
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("toml-fs-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[test]
+fn updates_in_place() {
+    let dir = temp_dir("update");
+    let path = dir.join("config.toml");
+    fs::write(&path, "# settings\nport = 80 # http\nhost = 'a'\n").unwrap();
+
+    let old = toml::fs::update(&path, |doc| {
+        let old = doc.get("port").and_then(|v| v.as_integer());
+        doc.set("port", Value::from(8080))?;
+        Ok(old)
+    })
+    .unwrap();
+    assert_eq!(old, Some(80));
+    assert_eq!(
+        fs::read_to_string(&path).unwrap(),
+        "# settings\nport = 8080 # http\nhost = 'a'\n"
+    );
+
+    // Nothing but the file is left behind.
+    let names = fs::read_dir(&dir).unwrap().count();
+    assert_eq!(names, 1);
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn detects_conflicts() {
+    let dir = temp_dir("conflict");
+    let path = dir.join("config.toml");
+    fs::write(&path, "port = 80\n").unwrap();
+
+    let err = toml::fs::update(&path, |doc| {
+        // Written by something that does not take the lock.
+        fs::write(&path, "port = 81\n").unwrap();
+        doc.set("port", Value::from(82))
+    })
+    .unwrap_err();
+    assert!(matches!(err.kind(), UpdateErrorKind::Conflict));
+    assert_eq!(err.path(), path.as_path());
+    assert!(err
+        .to_string()
+        .ends_with("it was changed while it was being updated"));
+    assert_eq!(fs::read_to_string(&path).unwrap(), "port = 81\n");
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn waits_for_lock() {
+    let dir = temp_dir("lock");
+    let path = dir.join("config.toml");
+    let lock = dir.join("config.toml.lock");
+    fs::write(&path, "port = 80\n").unwrap();
+    fs::write(&lock, "").unwrap();
+
+    let err = UpdateOptions::new()
+        .lock_timeout(Duration::from_millis(0))
+        .update(&path, |doc| doc.set("port", Value::from(1)))
+        .unwrap_err();
+    match err.kind() {
+        UpdateErrorKind::Locked(p) => assert_eq!(*p, lock),
+        other => panic!("{:?}", other),
+    }
+    assert_eq!(fs::read_to_string(&path).unwrap(), "port = 80\n");
+    assert!(lock.exists());
+
+    fs::remove_file(&lock).unwrap();
+    toml::fs::update(&path, |doc| doc.set("port", Value::from(1))).unwrap();
+    assert!(!lock.exists());
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn concurrent_updates() {
+    let dir = temp_dir("concurrent");
+    let path = dir.join("counter.toml");
+    fs::write(&path, "count = 0\n").unwrap();
+
+    let threads = (0..4)
+        .map(|_| {
+            let path = path.clone();
+            thread::spawn(move || {
+                for _ in 0..5 {
+                    toml::fs::update(&path, |doc| {
+                        let count = doc.get("count").and_then(|v| v.as_integer()).unwrap();
+                        doc.set("count", Value::from(count + 1))
+                    })
+                    .unwrap();
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+    for thread in threads {
+        thread.join().unwrap();
+    }
+    assert_eq!(fs::read_to_string(&path).unwrap(), "count = 20\n");
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn errors_and_creation() {
+    let dir = temp_dir("errors");
+    let path = dir.join("new.toml");
+
+    let err = toml::fs::update(&path, |_| Ok(())).unwrap_err();
+    match err.kind() {
+        UpdateErrorKind::Io(e) => assert_eq!(e.kind(), std::io::ErrorKind::NotFound),
+        other => panic!("{:?}", other),
+    }
+
+    UpdateOptions::new()
+        .create(true)
+        .update(&path, |doc| {
+            doc.insert("name", Value::from("demo"), InsertPosition::End)
+        })
+        .unwrap();
+    assert_eq!(fs::read_to_string(&path).unwrap(), "name = \"demo\"\n");
+
+    let err = toml::fs::update(&path, |doc| doc.set("missing", Value::from(1))).unwrap_err();
+    assert!(matches!(err.kind(), UpdateErrorKind::Edit(_)));
+
+    fs::write(&path, "name = \n").unwrap();
+    let err = toml::fs::update(&path, |_| Ok(())).unwrap_err();
+    assert!(matches!(err.kind(), UpdateErrorKind::Parse(_)));
+    assert!(std::error::Error::source(&err).is_some());
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn follows_symlinks() {
+    let dir = temp_dir("symlink");
+    let target = dir.join("real.toml");
+    let link = dir.join("link.toml");
+    fs::write(&target, "port = 80\n").unwrap();
+    std::os::unix::fs::symlink(&target, &link).unwrap();
+
+    toml::fs::update(&link, |doc| doc.set("port", Value::from(8080))).unwrap();
+    assert!(fs::symlink_metadata(&link)
+        .unwrap()
+        .file_type()
+        .is_symlink());
+    assert_eq!(fs::read_to_string(&target).unwrap(), "port = 8080\n");
+
+    // Only the file and the link are left behind.
+    let names = fs::read_dir(&dir).unwrap().count();
+    assert_eq!(names, 2);
+    fs::remove_dir_all(&dir).unwrap();
+}
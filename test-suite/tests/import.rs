@@ -0,0 +1,88 @@
+extern crate serde_json;
+extern crate toml;
+
+use serde_json::json;
+use toml::import::{ImportErrorKind, IntoToml, Node};
+use toml::Value;
+
+/// A JSON value, wrapped to implement a trait of another crate.
+struct Json<'a>(&'a serde_json::Value);
+
+impl<'a> IntoToml for Json<'a> {
+    fn node(&self) -> Node<'_> {
+        use serde_json::Value as J;
+
+        match self.0 {
+            J::Null => Node::Null,
+            J::Bool(b) => Node::Boolean(*b),
+            J::Number(n) => match (n.as_i64(), n.as_u64()) {
+                (Some(i), _) => Node::Integer(i),
+                (None, Some(u)) => Node::Unsigned(u),
+                _ => Node::Float(n.as_f64().unwrap()),
+            },
+            J::String(s) => Node::String(s),
+            J::Array(items) => Node::array(items.iter().map(Json)),
+            J::Object(map) => Node::table(map.iter().map(|(k, v)| (&k[..], Json(v)))),
+        }
+    }
+}
+
+#[test]
+fn converts_json() {
+    let json = json!({
+        "name": "demo",
+        "debug": false,
+        "ratio": 0.5,
+        "proxy": null,
+        "server": { "port": 8080, "hosts": ["a", "b"], "tls": null },
+        "workers": [{ "id": 1 }, { "id": 2 }],
+    });
+    let value = Json(&json).to_toml().unwrap();
+    let expected: Value = toml::from_str(
+        "\
+name = 'demo'
+debug = false
+ratio = 0.5
+
+[server]
+port = 8080
+hosts = ['a', 'b']
+
+[[workers]]
+id = 1
+
+[[workers]]
+id = 2
+",
+    )
+    .unwrap();
+    assert_eq!(value, expected);
+}
+
+#[test]
+fn values_toml_cannot_hold() {
+    let json = json!({ "a": { "b": [1, null] } });
+    let err = Json(&json).to_toml().unwrap_err();
+    assert_eq!(err.kind(), ImportErrorKind::Null);
+    assert_eq!(err.path().to_string(), "a.b[1]");
+    assert_eq!(err.to_string(), "TOML has no null value at `a.b[1]`");
+
+    let err = Json(&serde_json::Value::Null).to_toml().unwrap_err();
+    assert_eq!(err.to_string(), "TOML has no null value");
+
+    let json = json!({ "big": u64::MAX });
+    let err = Json(&json).to_toml().unwrap_err();
+    assert_eq!(err.kind(), ImportErrorKind::OutOfRange(u64::MAX));
+    assert_eq!(
+        err.to_string(),
+        "integer 18446744073709551615 is too large for a TOML integer at `big`"
+    );
+}
+
+#[test]
+fn toml_values_convert_to_themselves() {
+    let value: Value = toml::from_str("a = 1979-05-27\nb = [{ c = 1.5 }]").unwrap();
+    assert_eq!(value.to_toml().unwrap(), value);
+    let boxed: Box<dyn IntoToml> = Box::new(value.clone());
+    assert_eq!(boxed.to_toml().unwrap(), value);
+}
@@ -0,0 +1,107 @@
+extern crate toml;
+
+use toml::compat::{from_ini, from_ini_with, IniValues};
+use toml::Value;
+
+const LEGACY: &str = "\
+; written by the old installer
+# and edited by hand
+title = \"My App\"
+debug=yes
+
+[server]
+host : example.com
+port = 8080
+ratio = 0.75
+started = 1979-05-27T07:32:00Z
+tls = TRUE
+path = C:\\data ; not a comment
+
+[database.replica]
+url = 'postgres://r1'
+pool = '10'
+";
+
+#[test]
+fn everything_strings() {
+    let table = from_ini(LEGACY).unwrap();
+    assert_eq!(table["title"].as_str(), Some("My App"));
+    assert_eq!(table["debug"].as_str(), Some("yes"));
+    assert_eq!(table["server"]["host"].as_str(), Some("example.com"));
+    assert_eq!(table["server"]["port"].as_str(), Some("8080"));
+    assert_eq!(table["server"]["tls"].as_str(), Some("TRUE"));
+    assert_eq!(
+        table["server"]["path"].as_str(),
+        Some("C:\\data ; not a comment")
+    );
+    assert_eq!(
+        table["database"]["replica"]["url"].as_str(),
+        Some("postgres://r1")
+    );
+}
+
+#[test]
+fn sniffed_scalars() {
+    let table = from_ini_with(LEGACY, IniValues::Sniffed).unwrap();
+    let server = &table["server"];
+    assert_eq!(server["port"], Value::Integer(8080));
+    assert_eq!(server["ratio"], Value::Float(0.75));
+    assert_eq!(server["tls"], Value::Boolean(true));
+    assert_eq!(
+        server["started"].as_datetime().map(|d| d.to_string()),
+        Some("1979-05-27T07:32:00Z".to_string())
+    );
+    assert_eq!(server["host"].as_str(), Some("example.com"));
+    assert_eq!(table["debug"].as_str(), Some("yes"));
+    assert_eq!(table["database"]["replica"]["pool"].as_str(), Some("10"));
+    assert_eq!(table["title"].as_str(), Some("My App"));
+
+    let table = from_ini_with(
+        "mode = 0755\nzip = 02134\nzero = 0\nhalf = 0.5\n",
+        IniValues::Sniffed,
+    )
+    .unwrap();
+    assert_eq!(table["mode"].as_str(), Some("0755"));
+    assert_eq!(table["zip"].as_str(), Some("02134"));
+    assert_eq!(table["zero"], Value::Integer(0));
+    assert_eq!(table["half"], Value::Float(0.5));
+}
+
+#[test]
+fn errors() {
+    let err = from_ini("a = 1\njust text\n").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "expected `key = value`, found `just text` at line 2 column 1"
+    );
+
+    let err = from_ini("[s]\na = 1\nA = 2\na = 3\n").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "duplicate key `a` in section `s` at line 4 column 1"
+    );
+
+    let err = from_ini("a = 1\n[a]\n").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "section `a` has the name of a key at line 2 column 1"
+    );
+
+    let err = from_ini("[a.b\n").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "expected `]` at the end of a section at line 1 column 1"
+    );
+
+    let err = from_ini("[a..b]\n").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "invalid section name `a..b` at line 1 column 1"
+    );
+
+    let err = from_ini("  = 1\n").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "expected a key before the `=` at line 1 column 1"
+    );
+}
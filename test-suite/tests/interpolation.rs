@@ -0,0 +1,113 @@
+extern crate serde;
+extern crate toml;
+
+use serde::Deserialize;
+use toml::reference::{self, ReferenceError, ReferenceErrorKind};
+use toml::Value;
+
+fn read(input: &str) -> Result<Value, ReferenceError> {
+    reference::interpolate(&toml::from_str(input).unwrap()).map(Value::Table)
+}
+
+#[test]
+fn off_by_default() {
+    let value: Value = toml::from_str("a = 1\nb = '${a}'").unwrap();
+    assert_eq!(value["b"].as_str(), Some("${a}"));
+}
+
+#[test]
+fn substitutes_values() {
+    let value = read(
+        "\
+name = 'app'
+root = '/srv/${name}'
+port = 8080
+ratio = 1.0
+when = 1979-05-27
+url = 'http://localhost:${port}/${name}?r=${ratio}&d=${ when }'
+price = '$5 or $${name}'
+copy = '${port}'
+first = '${servers[0].host}'
+
+[[servers]]
+host = 'a.${domain.name}'
+
+[[servers]]
+host = 'b'
+
+[domain]
+name = 'example.com'
+logs = { dir = '${root}/logs', files = ['${domain.logs.dir}/out'] }
+",
+    )
+    .unwrap();
+    assert_eq!(value["root"].as_str(), Some("/srv/app"));
+    assert_eq!(
+        value["url"].as_str(),
+        Some("http://localhost:8080/app?r=1.0&d=1979-05-27")
+    );
+    assert_eq!(value["price"].as_str(), Some("$5 or ${name}"));
+    assert_eq!(value["copy"].as_integer(), Some(8080));
+    assert_eq!(value["first"].as_str(), Some("a.example.com"));
+    assert_eq!(
+        value["domain"]["logs"]["dir"].as_str(),
+        Some("/srv/app/logs")
+    );
+    assert_eq!(
+        value["domain"]["logs"]["files"][0].as_str(),
+        Some("/srv/app/logs/out")
+    );
+}
+
+#[test]
+fn into_structs() {
+    #[derive(Deserialize)]
+    struct Config {
+        port: u16,
+        listen: u16,
+        banner: String,
+    }
+
+    let config: Config = read("port = 80\nlisten = '${port}'\nbanner = 'port ${port}'\n")
+        .unwrap()
+        .try_into()
+        .unwrap();
+    assert_eq!(config.listen, 80);
+    assert_eq!(config.port, 80);
+    assert_eq!(config.banner, "port 80");
+}
+
+#[test]
+fn errors_list_the_chain() {
+    let err = read("a = '${b}'\nb = 'x ${c.d}'\n").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "unresolved reference `${c.d}` in `a` -> `b`"
+    );
+
+    let err = read("[t]\na = 1\n[u]\nb = 'x${ t }'\n").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "reference to an array or table `${ t }` in `u.b`"
+    );
+
+    let err = read("a = 'x'\nb = '${a..b}'\n").unwrap_err();
+    assert_eq!(err.to_string(), "invalid reference `${a..b}` in `b`");
+
+    let err = read("a = 'x ${b'\n").unwrap_err();
+    assert_eq!(
+        *err.kind(),
+        ReferenceErrorKind::Unterminated("b".to_string())
+    );
+    assert_eq!(err.to_string(), "unterminated reference `${b` in `a`");
+
+    let err = read("a = '${a}'\n").unwrap_err();
+    assert_eq!(*err.kind(), ReferenceErrorKind::Cycle);
+    assert_eq!(err.to_string(), "reference cycle in `a` -> `a`");
+
+    let err = read("x = 1\n[[s]]\na = '${s[1].b}'\n[[s]]\nb = '${s[0].a}'\n").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "reference cycle in `s[0].a` -> `s[1].b` -> `s[0].a`"
+    );
+}
@@ -11,7 +11,10 @@ macro_rules! bad {
 
 #[test]
 fn bad() {
-    bad!("a = 01", "invalid number at line 1 column 6");
+    bad!(
+        "a = 01",
+        "leading zeros are not allowed; write `1` at line 1 column 6"
+    );
     bad!("a = 1__1", "invalid number at line 1 column 5");
     bad!("a = 1_", "invalid number at line 1 column 5");
     bad!("''", "expected an equals, found eof at line 1 column 3");
@@ -47,12 +47,12 @@ test!(
 test!(
     duplicate_table,
     include_str!("invalid/duplicate-table.toml"),
-    "redefinition of table `dependencies` for key `dependencies` at line 7 column 1"
+    "redefinition of table `dependencies`, already defined on line 4 at line 7 column 1"
 );
 test!(
     duplicate_tables,
     include_str!("invalid/duplicate-tables.toml"),
-    "redefinition of table `a` for key `a` at line 2 column 1"
+    "redefinition of table `a`, already defined on line 1 at line 2 column 1"
 );
 test!(
     empty_implicit_table,
@@ -77,7 +77,7 @@ test!(
 test!(
     float_no_trailing_digits,
     include_str!("invalid/float-no-trailing-digits.toml"),
-    "invalid number at line 1 column 12"
+    "a decimal point must be followed by digits; write `1.0` at line 1 column 12"
 );
 test!(
     key_after_array,
@@ -157,7 +157,7 @@ test!(
 test!(
     table_array_implicit,
     include_str!("invalid/table-array-implicit.toml"),
-    "table redefined as array for key `albums` at line 13 column 1"
+    "redefinition of table `albums` as an array of tables, already defined on line 10 at line 13 column 1"
 );
 test!(
     table_array_malformed_bracket,
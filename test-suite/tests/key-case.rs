@@ -0,0 +1,79 @@
+extern crate serde;
+extern crate toml;
+
+use serde::Deserialize;
+use toml::de::CaseConflicts;
+use toml::Value;
+
+const SRC: &str = "\
+Timeout = 5
+timeout = 10
+log.Level = 1
+log.level = 2
+
+[Server]
+host = 'a'
+
+[server]
+Host = 'b'
+proxy = { Port = 1, port = 2 }
+
+[[bins]]
+name = 'a'
+
+[[bins]]
+Name = 'b'
+
+[[bins]]
+name = 'c'
+Name = 'd'
+";
+
+fn warnings(policy: CaseConflicts) -> Vec<String> {
+    let mut d = toml::Deserializer::new(SRC);
+    d.set_case_conflicts(policy);
+    Value::deserialize(&mut d).unwrap();
+    d.warnings().iter().map(|w| w.to_string()).collect()
+}
+
+#[test]
+fn allowed_by_default() {
+    let value: Value = SRC.parse().unwrap();
+    assert_eq!(value["Timeout"].as_integer(), Some(5));
+    assert_eq!(value["timeout"].as_integer(), Some(10));
+    assert!(warnings(CaseConflicts::Allow).is_empty());
+}
+
+#[test]
+fn warn() {
+    assert_eq!(
+        warnings(CaseConflicts::Warn),
+        [
+            "key `timeout` differs only in case from `Timeout` on line 1 at line 2 column 1",
+            "key `level` differs only in case from `Level` on line 3 at line 4 column 5",
+            "key `server` differs only in case from `Server` on line 6 at line 9 column 2",
+            "key `port` differs only in case from `Port` on line 11 at line 11 column 21",
+            "key `Name` differs only in case from `name` on line 20 at line 21 column 1",
+        ]
+    );
+}
+
+#[test]
+fn error() {
+    let mut d = toml::Deserializer::new(SRC);
+    d.set_case_conflicts(CaseConflicts::Error);
+    let err = Value::deserialize(&mut d).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "key `timeout` differs only in case from `Timeout` on line 1 at line 2 column 1"
+    );
+
+    let mut d = toml::Deserializer::new("a = [{ x = 1, X = 2 }]\n");
+    d.set_case_conflicts(CaseConflicts::Error);
+    assert!(Value::deserialize(&mut d).is_err());
+
+    let mut d = toml::Deserializer::new("a = [{ x = 1 }, { X = 2 }]\nA = 1\n");
+    d.set_case_conflicts(CaseConflicts::Error);
+    let err = Value::deserialize(&mut d).unwrap_err();
+    assert_eq!(err.line_col(), Some((1, 0)));
+}
@@ -0,0 +1,124 @@
+use serde::Serialize;
+use toml::metadata::Metadata;
+use toml::ser::{Serializer, TableStyle};
+use toml::value::Table;
+use toml::Value;
+
+fn config() -> Value {
+    toml::toml! {
+        name = "app"
+        ports = [80, 443]
+
+        [deps]
+        serde = { version = "1" }
+
+        [server]
+        host = "localhost"
+
+        [server.tls]
+        cert = "cert.pem"
+
+        [[workers]]
+        id = 1
+
+        [[workers]]
+        id = 2
+    }
+}
+
+#[test]
+fn docs_above_keys_and_tables() {
+    let mut out = String::new();
+    let mut ser = Serializer::pretty(&mut out);
+    ser.key_doc("name", "The name of the application.")
+        .key_doc("ports", "Ports to listen on.\n\nAt least one is required.")
+        .key_doc("server", "Server settings.")
+        .key_doc("server.host", "Host name.")
+        .key_doc("server.tls", "TLS settings.")
+        .key_doc("workers", "One table per worker.")
+        .key_doc("workers.id", "Not used, since arrays are not matched.");
+    config().serialize(&mut ser).unwrap();
+    assert_eq!(
+        out,
+        "\
+# The name of the application.
+name = 'app'
+# Ports to listen on.
+#
+# At least one is required.
+ports = [
+    80,
+    443,
+]
+
+# One table per worker.
+[[workers]]
+id = 1
+
+[[workers]]
+id = 2
+[deps.serde]
+version = '1'
+
+# Server settings.
+[server]
+# Host name.
+host = 'localhost'
+
+# TLS settings.
+[server.tls]
+cert = 'cert.pem'
+"
+    );
+    assert_eq!(toml::from_str::<Value>(&out).unwrap(), config());
+}
+
+#[test]
+fn docs_with_styles_and_defaults() {
+    let mut defaults = Table::new();
+    defaults.insert("name".to_string(), Value::from("app"));
+
+    let mut config = config();
+    config.as_table_mut().unwrap().remove("workers");
+
+    let mut out = String::new();
+    let mut ser = Serializer::new(&mut out);
+    ser.table_style("deps", TableStyle::Inline)
+        .comment_defaults(defaults)
+        .key_doc("deps", "Dependencies.")
+        .key_doc("name", "The name of the application.");
+    config.serialize(&mut ser).unwrap();
+    assert!(
+        out.starts_with(
+            "\
+# The name of the application.
+# name = \"app\"
+ports = [80, 443]
+# Dependencies.
+deps = { serde = { version = \"1\" } }
+"
+        ),
+        "{}",
+        out
+    );
+}
+
+#[test]
+fn docs_from_metadata() {
+    let mut meta = Metadata::new();
+    meta.set("server.host", "doc", Value::from("Host name."));
+    meta.set("server.host", "hint", Value::from("not a doc"));
+    meta.set("name", "doc", Value::from(1));
+
+    let mut out = String::new();
+    let mut ser = Serializer::new(&mut out);
+    ser.key_docs(&meta);
+    config().serialize(&mut ser).unwrap();
+    assert!(out.starts_with("name = \"app\"\n"), "{}", out);
+    assert!(
+        out.contains("[server]\n# Host name.\nhost = \"localhost\"\n"),
+        "{}",
+        out
+    );
+    assert!(!out.contains("not a doc"));
+}
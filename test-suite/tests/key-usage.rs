@@ -0,0 +1,129 @@
+extern crate serde;
+extern crate toml;
+
+use serde::Deserialize;
+use toml::de::KeyUsage;
+use toml::path::KeyPath;
+use toml::Value;
+
+#[derive(Deserialize)]
+#[allow(dead_code)]
+struct Config {
+    name: String,
+    timeout: Option<u32>,
+    server: Server,
+    #[serde(default)]
+    plugins: Vec<Plugin>,
+}
+
+#[derive(Deserialize)]
+#[allow(dead_code)]
+struct Server {
+    host: String,
+    #[serde(default)]
+    port: u16,
+}
+
+#[derive(Deserialize)]
+#[allow(dead_code)]
+struct Plugin {
+    path: String,
+    enabled: Option<bool>,
+}
+
+const CONFIG: &str = "\
+name = \"demo\"
+colour = \"blue\"
+legacy = { a = 1, b = [2, 3] }
+
+[server]
+host = \"localhost\"
+tls.cert = \"cert.pem\"
+
+[[plugins]]
+path = \"a.so\"
+enabled = true
+
+[[plugins]]
+path = \"b.so\"
+";
+
+fn paths(paths: &[KeyPath]) -> Vec<String> {
+    paths.iter().map(|p| p.to_string()).collect()
+}
+
+fn usage<'de, T: Deserialize<'de>>(input: &'de str) -> KeyUsage {
+    let mut d = toml::Deserializer::new(input);
+    d.set_track_key_usage(true);
+    T::deserialize(&mut d).unwrap();
+    d.key_usage()
+}
+
+#[test]
+fn struct_usage() {
+    let usage = usage::<Config>(CONFIG);
+    assert_eq!(
+        paths(usage.consumed()),
+        [
+            "name",
+            "server.host",
+            "server",
+            "plugins[0].path",
+            "plugins[0].enabled",
+            "plugins[1].path",
+            "plugins",
+        ]
+    );
+    assert_eq!(paths(usage.ignored()), ["colour", "legacy", "server.tls"]);
+    assert_eq!(
+        paths(usage.defaulted()),
+        ["server.port", "plugins[1].enabled", "timeout"]
+    );
+}
+
+#[test]
+fn value_consumes_everything() {
+    let usage = usage::<Value>("a = 1\nb = { c = [{ d = 2 }] }\n");
+    assert_eq!(paths(usage.consumed()), ["a", "b.c[0].d", "b.c", "b"]);
+    assert!(usage.ignored().is_empty());
+    assert!(usage.defaulted().is_empty());
+}
+
+#[test]
+fn disabled_by_default() {
+    let mut d = toml::Deserializer::new("name = 'demo'\n");
+    Value::deserialize(&mut d).unwrap();
+    assert_eq!(d.key_usage(), KeyUsage::default());
+}
+
+#[test]
+fn reset_clears_usage() {
+    let mut d = toml::Deserializer::new("a = 1\n");
+    d.set_track_key_usage(true);
+    Value::deserialize(&mut d).unwrap();
+    assert_eq!(paths(d.key_usage().consumed()), ["a"]);
+
+    d.reset("b = 2\n");
+    assert!(d.key_usage().consumed().is_empty());
+    Value::deserialize(&mut d).unwrap();
+    assert_eq!(paths(d.key_usage().consumed()), ["b"]);
+}
+
+#[test]
+fn array_length_still_checked() {
+    let mut d = toml::Deserializer::new("a = [1, 2, 3]\n");
+    d.set_track_key_usage(true);
+
+    #[derive(Deserialize, Debug)]
+    #[allow(dead_code)]
+    struct Pair {
+        a: (u8, u8),
+    }
+    let err = Pair::deserialize(&mut d).unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("invalid length 3, expected 2 elements in sequence"),
+        "{}",
+        err
+    );
+}
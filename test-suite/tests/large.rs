@@ -0,0 +1,36 @@
+extern crate toml;
+
+use std::fmt::Write;
+
+use toml::Value;
+
+#[test]
+fn large_document() {
+    let mut src = String::new();
+    for i in 0..100 {
+        writeln!(src, "root{} = {}", i, i).unwrap();
+    }
+    for t in 0..500 {
+        writeln!(src, "[table{}]", t).unwrap();
+        for i in 0..20 {
+            writeln!(src, "key{} = \"{}\"", i, i * t).unwrap();
+        }
+        // Multi-line values, with lines that look like keys and headers.
+        src.push_str("matrix = [\n  [1, 2],\n  [3, 4],\n]\n");
+        src.push_str("text = '''\na = b\n'''\n");
+        src.push_str("inline = { x = 1, y = 2 }\n");
+    }
+    for _ in 0..100 {
+        src.push_str("[[items]]\nname = 'x'\n");
+    }
+
+    let value: Value = src.parse().unwrap();
+    let root = value.as_table().unwrap();
+    assert_eq!(root.len(), 100 + 500 + 1);
+    let table = root["table499"].as_table().unwrap();
+    assert_eq!(table.len(), 23);
+    assert_eq!(table["key19"].as_str(), Some("9481"));
+    assert_eq!(table["matrix"].as_array().unwrap().len(), 2);
+    assert_eq!(table["inline"]["y"].as_integer(), Some(2));
+    assert_eq!(root["items"].as_array().unwrap().len(), 100);
+}
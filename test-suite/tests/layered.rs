@@ -0,0 +1,143 @@
+extern crate toml;
+
+use toml::layered::Layered;
+use toml::path::{KeyPath, PathSegment};
+
+#[test]
+fn key_path_round_trip() {
+    let path: KeyPath = "a.\"b c\".d[2][0].e".parse().unwrap();
+    assert_eq!(
+        path.segments(),
+        &[
+            PathSegment::Key("a".to_string()),
+            PathSegment::Key("b c".to_string()),
+            PathSegment::Key("d".to_string()),
+            PathSegment::Index(2),
+            PathSegment::Index(0),
+            PathSegment::Key("e".to_string()),
+        ]
+    );
+    assert_eq!(path.to_string(), "a.\"b c\".d[2][0].e");
+    assert_eq!("'x.y'".parse::<KeyPath>().unwrap().to_string(), "\"x.y\"");
+
+    assert!("a..b".parse::<KeyPath>().is_err());
+    assert!("a[x]".parse::<KeyPath>().is_err());
+    assert!("\"a".parse::<KeyPath>().is_err());
+    assert_eq!(KeyPath::from("a..b").segments().len(), 1);
+}
+
+#[test]
+fn origins_across_layers() {
+    let mut config = Layered::new();
+    config
+        .push_str(
+            "base.toml",
+            "name = 'app'\n\n[server]\nport = 80\nhost = 'localhost'\n",
+        )
+        .unwrap();
+    config
+        .push_str("local.toml", "[server]\nport = 8080\n")
+        .unwrap();
+
+    assert_eq!(config.get("server.port").unwrap().as_integer(), Some(8080));
+    assert_eq!(
+        config.get("server.host").unwrap().as_str(),
+        Some("localhost")
+    );
+
+    let origin = config.origin("server.port").unwrap();
+    assert_eq!(origin.source(), "local.toml");
+    assert_eq!(origin.line_col(), Some((1, 0)));
+    assert_eq!(origin.span(), Some((9, 13)));
+    assert_eq!(config.origin("server.host").unwrap().source(), "base.toml");
+    assert_eq!(config.history("server.port").len(), 2);
+    assert_eq!(
+        config.describe("server.port").unwrap(),
+        "set in base.toml line 4, overridden by local.toml line 2"
+    );
+    assert_eq!(config.describe("name").unwrap(), "set in base.toml line 1");
+    assert!(config.origin("missing").is_none());
+}
+
+#[test]
+fn replaced_tables_drop_nested_origins() {
+    let mut config = Layered::new();
+    config.push_str("a", "[db]\nurl = 'x'\n").unwrap();
+    config.push_str("b", "db = 'sqlite'\n").unwrap();
+
+    assert_eq!(config.get("db").unwrap().as_str(), Some("sqlite"));
+    assert!(config.origin("db.url").is_none());
+    assert_eq!(
+        config.describe("db").unwrap(),
+        "set in a line 1, overridden by b line 1"
+    );
+}
+
+#[test]
+fn nested_and_array_origins() {
+    let mut config = Layered::new();
+    config
+        .push_str(
+            "a",
+            "x = { y = [1, 2.5] }\n[[bin]]\nname = 'one'\n[[bin]]\nname = 'two'\n",
+        )
+        .unwrap();
+    config.push_table("defaults", toml::toml! { extra = true }.try_into().unwrap());
+
+    assert_eq!(config.origin("x.y[1]").unwrap().span(), Some((14, 17)));
+    assert_eq!(
+        config.origin("bin[1].name").unwrap().line_col(),
+        Some((4, 0))
+    );
+    assert_eq!(config.origin("bin[1]").unwrap().line_col(), Some((3, 2)));
+    let extra = config.origin("extra").unwrap();
+    assert_eq!(extra.source(), "defaults");
+    assert_eq!(extra.span(), None);
+    assert_eq!(extra.to_string(), "defaults");
+}
+
+#[test]
+fn float_spans() {
+    let mut config = Layered::new();
+    config.push_str("a", "f = [1.5, +2e3, 3.0e-1]").unwrap();
+    assert_eq!(config.origin("f[0]").unwrap().span(), Some((5, 8)));
+    assert_eq!(config.origin("f[1]").unwrap().span(), Some((10, 14)));
+    assert_eq!(config.origin("f[2]").unwrap().span(), Some((16, 22)));
+}
+
+#[test]
+fn invalid_layer_is_rejected() {
+    let mut config = Layered::new();
+    config.push_str("a", "x = 1").unwrap();
+    assert!(config.push_str("b", "x = ").is_err());
+    assert_eq!(config.get("x").unwrap().as_integer(), Some(1));
+    assert_eq!(config.history("x").len(), 1);
+}
+
+#[test]
+fn empty_table_unsets() {
+    let mut config = Layered::new();
+    config.set_unset_with_empty_table(true);
+    config
+        .push_str("a", "[proxy]\nurl = 'x'\n[log]\nlevel = 'info'")
+        .unwrap();
+    config
+        .push_str("b", "proxy = {}\nlog = { level = {} }")
+        .unwrap();
+    assert!(config.get("proxy").is_none());
+    assert!(config.origin("proxy").is_none());
+    assert!(config.origin("proxy.url").is_none());
+    assert_eq!(
+        config.table()["log"],
+        toml::Value::Table(Default::default())
+    );
+    assert_eq!(
+        config.describe("log").unwrap(),
+        "set in a line 3, overridden by b line 2"
+    );
+
+    let mut config = Layered::new();
+    config.push_str("a", "proxy = 'x'").unwrap();
+    config.push_str("b", "proxy = {}").unwrap();
+    assert!(config.get("proxy").unwrap().is_table());
+}
@@ -0,0 +1,64 @@
+extern crate serde;
+extern crate toml;
+
+use serde::Deserialize;
+use toml::Value;
+
+const LEGACY: &str = "a = 0123\nb = -007\nc = 1.\nd = [2., 05.5]\ne = 01e2\n";
+
+#[test]
+fn strict_by_default() {
+    let err = toml::from_str::<Value>(LEGACY).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "leading zeros are not allowed; write `123` at line 1 column 6"
+    );
+    let err = toml::from_str::<Value>("d = [05.5]").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "leading zeros are not allowed; write `5.5` at line 1 column 7"
+    );
+}
+
+#[test]
+fn lenient() {
+    let mut d = toml::Deserializer::new(LEGACY);
+    d.set_lenient_numbers(true);
+    let value = Value::deserialize(&mut d).unwrap();
+    assert_eq!(value["a"], Value::Integer(123));
+    assert_eq!(value["b"], Value::Integer(-7));
+    assert_eq!(value["c"], Value::Float(1.0));
+    assert_eq!(
+        value["d"],
+        Value::Array(vec![Value::Float(2.0), Value::Float(5.5)])
+    );
+    assert_eq!(value["e"], Value::Float(100.0));
+
+    let warnings = d
+        .warnings()
+        .iter()
+        .map(|w| w.to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        warnings,
+        [
+            "leading zeros in `0123`, read as 123 at line 1 column 5",
+            "leading zeros in `-007`, read as -7 at line 2 column 5",
+            "missing digits after decimal point in `1.`, read as 1.0 at line 3 column 5",
+            "missing digits after decimal point in `2.`, read as 2.0 at line 4 column 6",
+            "leading zeros in `05`, read as 5 at line 4 column 10",
+            "leading zeros in `01`, read as 1 at line 5 column 5",
+        ]
+    );
+}
+
+#[test]
+fn lenient_still_rejects_garbage() {
+    let mut d = toml::Deserializer::new("a = 0x\n");
+    d.set_lenient_numbers(true);
+    assert!(Value::deserialize(&mut d).is_err());
+
+    let mut d = toml::Deserializer::new("a = 1.x\n");
+    d.set_lenient_numbers(true);
+    assert!(Value::deserialize(&mut d).is_err());
+}
@@ -0,0 +1,122 @@
+extern crate toml;
+
+use toml::value::Table;
+use toml::Value;
+
+fn table(s: &str) -> Table {
+    toml::from_str(s).unwrap()
+}
+
+#[test]
+fn merge_is_recursive() {
+    let mut base = table(
+        r#"
+        list = [1, 2]
+        [a]
+        x = 1
+        y = { z = 2, w = 3 }
+        "#,
+    );
+    base.merge(table(
+        r#"
+        list = [3]
+        [a]
+        y = { z = 20 }
+        v = 'new'
+        "#,
+    ));
+    assert_eq!(
+        base,
+        table(
+            r#"
+            list = [3]
+            [a]
+            x = 1
+            y = { z = 20, w = 3 }
+            v = 'new'
+            "#
+        )
+    );
+}
+
+#[test]
+fn merge_replaces_mismatched_types() {
+    let mut base = table("a = { b = 1 }\nc = 1");
+    base.merge(table("a = 'flat'\nc = { d = 1 }"));
+    assert_eq!(base, table("a = 'flat'\nc = { d = 1 }"));
+}
+
+#[test]
+fn resolve_profile() {
+    let config = table(
+        r#"
+        debug = true
+        [server]
+        host = "localhost"
+        port = 8080
+
+        [profile.production]
+        debug = false
+        [profile.production.server]
+        host = "example.com"
+
+        [profile.test.server]
+        port = 0
+        "#,
+    );
+
+    let production = config.resolve_profile("production");
+    assert_eq!(
+        production,
+        table(
+            r#"
+            debug = false
+            [server]
+            host = "example.com"
+            port = 8080
+            "#
+        )
+    );
+
+    let test = config.resolve_profile("test");
+    assert_eq!(test["server"]["port"].as_integer(), Some(0));
+    assert_eq!(test["debug"].as_bool(), Some(true));
+
+    let unknown = config.resolve_profile("staging");
+    assert!(!unknown.contains_key("profile"));
+    assert_eq!(unknown["server"]["host"].as_str(), Some("localhost"));
+
+    // the original is left untouched
+    assert!(config.contains_key("profile"));
+}
+
+#[test]
+fn merge_with_unset() {
+    let mut base = table(
+        r#"
+        keep = 1
+        drop = "x"
+        [proxy]
+        url = "http://proxy"
+        [nested]
+        a = 1
+        b = 2
+        "#,
+    );
+    let unset = Value::from("!unset");
+    base.merge_with_unset(
+        table("drop = '!unset'\nproxy = '!unset'\nmissing = '!unset'\nnested = { a = '!unset', c = {} }"),
+        &unset,
+    );
+    assert_eq!(base, table("keep = 1\n[nested]\nb = 2\nc = {}"));
+
+    // any value can be the marker, including an empty table
+    let mut base = table("a = 1\nb = 2");
+    base.merge_with_unset(table("a = {}"), &Value::Table(Table::new()));
+    assert_eq!(base, table("b = 2"));
+
+    // plain merge keeps empty tables as values
+    let mut base = table("a = 1");
+    base.merge(table("a = {}"));
+    assert_eq!(base, table("a = {}"));
+}
@@ -0,0 +1,81 @@
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4};
+
+use serde_derive::{Deserialize, Serialize};
+
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
+struct Network {
+    #[serde(with = "toml::net")]
+    gateway: Ipv4Addr,
+    #[serde(with = "toml::net")]
+    dns: IpAddr,
+    #[serde(with = "toml::net")]
+    listen: SocketAddr,
+    #[serde(with = "toml::net")]
+    admin: SocketAddrV4,
+}
+
+#[test]
+fn round_trip() {
+    let input = "\
+gateway = \"192.168.1.1\"
+dns = \"2001:db8::53\"
+listen = \"[::1]:8080\"
+admin = \"127.0.0.1:9000\"
+";
+    let network: Network = toml::from_str(input).unwrap();
+    assert_eq!(network.gateway, Ipv4Addr::new(192, 168, 1, 1));
+    assert_eq!(
+        network.dns,
+        IpAddr::V6(Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 0x53))
+    );
+    assert_eq!(network.listen.port(), 8080);
+    assert_eq!(toml::to_string(&network).unwrap(), input);
+}
+
+fn error(gateway: &str, dns: &str, listen: &str, admin: &str) -> String {
+    let input = format!(
+        "gateway = '{}'\ndns = '{}'\nlisten = '{}'\nadmin = '{}'\n",
+        gateway, dns, listen, admin
+    );
+    toml::from_str::<Network>(&input).unwrap_err().to_string()
+}
+
+#[test]
+fn names_text_and_path() {
+    assert_eq!(
+        error("300.1.1.1", "::1", "[::1]:80", "127.0.0.1:1"),
+        "`300.1.1.1` is not an IPv4 address for key `gateway` at line 1 column 11"
+    );
+    assert_eq!(
+        error("10.0.0.1", "10.0.0.53:53", "[::1]:80", "127.0.0.1:1"),
+        "`10.0.0.53:53` is not an IP address: unexpected port for key `dns` at line 2 column 7"
+    );
+    assert_eq!(
+        error("10.0.0.1", "::1", "[::1]", "127.0.0.1:1"),
+        "`[::1]` is not a socket address: missing port for key `listen` at line 3 column 10"
+    );
+    assert_eq!(
+        error("10.0.0.1", "::1", "[::1]:80", "localhost:80"),
+        "`localhost:80` is not an IPv4 socket address for key `admin` at line 4 column 9"
+    );
+}
+
+#[test]
+fn parse_and_types() {
+    assert_eq!(
+        toml::net::parse::<SocketAddr>("10.0.0.1:80").unwrap(),
+        "10.0.0.1:80".parse::<SocketAddr>().unwrap()
+    );
+    assert_eq!(
+        toml::net::parse::<Ipv6Addr>("1.2.3.4").unwrap_err(),
+        "`1.2.3.4` is not an IPv6 address"
+    );
+
+    let err = toml::from_str::<Network>("gateway = 1").unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("expected a string holding an IPv4 address"),
+        "{}",
+        err
+    );
+}
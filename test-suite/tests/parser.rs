@@ -245,12 +245,30 @@ fn string_no_newline() {
 
 #[test]
 fn bad_leading_zeros() {
-    bad!("a = 00", "invalid number at line 1 column 6");
-    bad!("a = -00", "invalid number at line 1 column 7");
-    bad!("a = +00", "invalid number at line 1 column 7");
-    bad!("a = 00.0", "invalid number at line 1 column 6");
-    bad!("a = -00.0", "invalid number at line 1 column 7");
-    bad!("a = +00.0", "invalid number at line 1 column 7");
+    bad!(
+        "a = 00",
+        "leading zeros are not allowed; write `0` at line 1 column 6"
+    );
+    bad!(
+        "a = -00",
+        "leading zeros are not allowed; write `-0` at line 1 column 7"
+    );
+    bad!(
+        "a = +00",
+        "leading zeros are not allowed; write `+0` at line 1 column 7"
+    );
+    bad!(
+        "a = 00.0",
+        "leading zeros are not allowed; write `0.0` at line 1 column 6"
+    );
+    bad!(
+        "a = -00.0",
+        "leading zeros are not allowed; write `-0.0` at line 1 column 7"
+    );
+    bad!(
+        "a = +00.0",
+        "leading zeros are not allowed; write `+0.0` at line 1 column 7"
+    );
     bad!(
         "a = 9223372036854775808",
         "invalid number at line 1 column 5"
@@ -263,7 +281,10 @@ fn bad_leading_zeros() {
 
 #[test]
 fn bad_floats() {
-    bad!("a = 0.", "invalid number at line 1 column 7");
+    bad!(
+        "a = 0.",
+        "a decimal point must be followed by digits; write `0.0` at line 1 column 7"
+    );
     bad!("a = 0.e", "invalid number at line 1 column 7");
     bad!("a = 0.E", "invalid number at line 1 column 7");
     bad!("a = 0.0E", "invalid number at line 1 column 5");
@@ -340,7 +361,19 @@ fn bad_keys() {
     );
     bad!(
         "key|=3",
-        "unexpected character found: `|` at line 1 column 4"
+        "invalid character `|` in bare key `key|`, quote the key: \"key|\" at line 1 column 4"
+    );
+    bad!(
+        "a.b$c.d = 3",
+        "invalid character `$` in bare key `b$c`, quote the key: \"b$c\" at line 1 column 4"
+    );
+    bad!(
+        "[a.'b'.\"c\"@]",
+        "unexpected character found: `@` at line 1 column 11"
+    );
+    bad!(
+        "x = { ok = 1, k\\y = 2 }",
+        "invalid character `\\` in bare key `k\\y`, quote the key: 'k\\y' at line 1 column 16"
     );
     bad!(
         "=3",
@@ -391,7 +424,7 @@ fn bad_table_names() {
     bad!("[\"\n\"]", "newline in string found at line 1 column 3");
     bad!(
         "[a.b]\n[a.\"b\"]",
-        "redefinition of table `a.b` for key `a.b` at line 2 column 1"
+        "redefinition of table `a.b`, already defined on line 1 at line 2 column 1"
     );
     bad!("[']", "unterminated string at line 1 column 2");
     bad!("[''']", "unterminated string at line 1 column 2");
@@ -594,7 +627,7 @@ fn bad_nesting() {
         [a.b]
         c = 2
         ",
-        "duplicate key: `b` for key `a` at line 4 column 9"
+        "redefinition of inline table `a.b`, already defined on line 3 at line 4 column 9"
     );
 }
 
@@ -608,7 +641,7 @@ fn bad_table_redefine() {
         foo=\"bar\"
         [a]
         ",
-        "redefinition of table `a` for key `a` at line 6 column 9"
+        "redefinition of table `a`, already defined on line 2 at line 6 column 9"
     );
     bad!(
         "
@@ -617,7 +650,7 @@ fn bad_table_redefine() {
         b = { foo = \"bar\" }
         [a]
         ",
-        "redefinition of table `a` for key `a` at line 5 column 9"
+        "redefinition of table `a`, already defined on line 2 at line 5 column 9"
     );
     bad!(
         "
@@ -625,7 +658,7 @@ fn bad_table_redefine() {
         b = {}
         [a.b]
         ",
-        "duplicate key: `b` for key `a` at line 4 column 9"
+        "redefinition of inline table `a.b`, already defined on line 3 at line 4 column 9"
     );
 
     bad!(
@@ -634,7 +667,7 @@ fn bad_table_redefine() {
         b = {}
         [a]
         ",
-        "redefinition of table `a` for key `a` at line 4 column 9"
+        "redefinition of table `a`, already defined on line 2 at line 4 column 9"
     );
 }
 
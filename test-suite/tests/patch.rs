@@ -0,0 +1,42 @@
+extern crate toml;
+#[macro_use]
+extern crate serde_derive;
+
+use toml::patch::Patch;
+
+#[derive(Debug, Deserialize, Serialize, PartialEq)]
+struct Overrides {
+    #[serde(default, skip_serializing_if = "Patch::is_inherit")]
+    port: Patch<u16>,
+    #[serde(default, skip_serializing_if = "Patch::is_inherit")]
+    proxy: Patch<String>,
+    #[serde(default, skip_serializing_if = "Patch::is_inherit")]
+    tags: Patch<Vec<String>>,
+}
+
+#[test]
+fn round_trip() {
+    let o: Overrides = toml::from_str("proxy = {}\nport = 8080").unwrap();
+    assert_eq!(
+        o,
+        Overrides {
+            port: Patch::Set(8080),
+            proxy: Patch::Unset,
+            tags: Patch::Inherit,
+        }
+    );
+    assert_eq!(toml::to_string(&o).unwrap(), "port = 8080\n\n[proxy]\n");
+
+    let err = toml::from_str::<Overrides>("port = 'x'").unwrap_err();
+    assert!(err.to_string().contains("expected u16"), "{}", err);
+}
+
+#[test]
+fn apply_and_convert() {
+    assert_eq!(Patch::Inherit.apply(Some(1)), Some(1));
+    assert_eq!(Patch::Unset.apply(Some(1)), None);
+    assert_eq!(Patch::Set(2).apply(Some(1)), Some(2));
+    assert_eq!(Patch::from(Some(None::<u8>)), Patch::Unset);
+    assert_eq!(Option::<Option<u8>>::from(Patch::Set(3)), Some(Some(3)));
+    assert_eq!(Option::<Option<u8>>::from(Patch::Inherit), None);
+}
@@ -0,0 +1,229 @@
+extern crate toml;
+
+use toml::path::{KeyPath, KeyPathPattern};
+use toml::value::Table;
+use toml::Value;
+
+const SRC: &str = "\
+empty = {}
+name = 'demo'
+ports = [80, 443]
+servers = { alpha = { host = 'a', port = 80 }, beta = { port = 81 } }
+tags = []
+
+[[users]]
+id = 1
+roles = [{ name = 'admin' }]
+
+[[users]]
+id = 2
+";
+
+fn strings(paths: Vec<KeyPath>) -> Vec<String> {
+    paths.iter().map(|p| p.to_string()).collect()
+}
+
+#[test]
+fn leaves() {
+    let table: Table = toml::from_str(SRC).unwrap();
+    assert_eq!(
+        strings(table.paths()),
+        [
+            "empty",
+            "name",
+            "ports",
+            "servers.alpha.host",
+            "servers.alpha.port",
+            "servers.beta.port",
+            "tags",
+            "users[0].id",
+            "users[0].roles[0].name",
+            "users[1].id",
+        ]
+    );
+    assert!(Table::new().paths().is_empty());
+}
+
+#[test]
+fn all() {
+    let table: Table = toml::from_str(SRC).unwrap();
+    assert_eq!(
+        strings(table.all_paths()),
+        [
+            "empty",
+            "name",
+            "ports",
+            "servers",
+            "servers.alpha",
+            "servers.alpha.host",
+            "servers.alpha.port",
+            "servers.beta",
+            "servers.beta.port",
+            "tags",
+            "users",
+            "users[0]",
+            "users[0].id",
+            "users[0].roles",
+            "users[0].roles[0]",
+            "users[0].roles[0].name",
+            "users[1]",
+            "users[1].id",
+        ]
+    );
+}
+
+#[test]
+fn matching() {
+    let table: Table = toml::from_str(SRC).unwrap();
+    let matching = |pattern| strings(table.paths_matching(pattern).unwrap());
+    assert_eq!(
+        matching("servers.*.port"),
+        ["servers.alpha.port", "servers.beta.port"]
+    );
+    assert_eq!(matching("servers.*"), ["servers.alpha", "servers.beta"]);
+    assert_eq!(matching("users[*].id"), ["users[0].id", "users[1].id"]);
+    assert_eq!(matching("users[1]"), ["users[1]"]);
+    assert_eq!(matching("ports[*]"), ["ports[0]", "ports[1]"]);
+    assert_eq!(matching("users[0].roles[*]"), ["users[0].roles[0]"]);
+    assert_eq!(
+        matching("*"),
+        ["empty", "name", "ports", "servers", "tags", "users"]
+    );
+    assert!(matching("users.*").is_empty());
+    assert!(matching("servers[*]").is_empty());
+    assert!(matching("missing.*").is_empty());
+
+    let err = table.paths_matching("servers.*x").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "invalid key path: expected `.` or `[` at offset 9"
+    );
+    assert!("servers.*".parse::<KeyPath>().is_err());
+}
+
+#[test]
+fn patterns() {
+    let pattern = |s: &str| s.parse::<KeyPathPattern>().unwrap();
+    let path = |s: &str| s.parse::<KeyPath>().unwrap();
+
+    assert!(pattern("**").matches(&KeyPath::new()));
+    assert!(pattern("**").matches(&path("a[0].b")));
+    assert!(pattern("a.**.b").matches(&path("a.b")));
+    assert!(pattern("a.**.b").matches(&path("a[1].x.b")));
+    assert!(!pattern("a.**.b").matches(&path("a.b.c")));
+    assert!(pattern("**[*]").matches(&path("a.b[2]")));
+    assert!(!pattern("*").matches(&path("a.b")));
+    assert!(pattern("'*'").matches(&path("'*'")));
+    assert!(!pattern("'*'").matches(&path("a")));
+
+    assert!(pattern("a.b").is_exact());
+    assert!(!pattern("a.*").is_exact());
+    assert_eq!(KeyPathPattern::from(path("a[0]")), pattern("a[0]"));
+    assert_eq!(
+        pattern("a.** . **.*[*].\"b c\"").to_string(),
+        "a.**.*[*].\"b c\""
+    );
+
+    let err = "a.**b".parse::<KeyPathPattern>().unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "invalid key path: expected `.` or `[` at offset 4"
+    );
+}
+
+#[test]
+fn matching_any_depth() {
+    let table: Table = toml::from_str(SRC).unwrap();
+    let mut ids = strings(table.paths_matching("**.id").unwrap());
+    ids.sort();
+    assert_eq!(ids, ["users[0].id", "users[1].id"]);
+    assert_eq!(
+        strings(table.paths_matching("**.roles[*].*").unwrap()),
+        ["users[0].roles[0].name"]
+    );
+}
+
+#[test]
+fn redact() {
+    let mut table: Table = toml::from_str(SRC).unwrap();
+    let hidden = Value::from("<redacted>");
+    assert_eq!(table.redact(&"**.port".parse().unwrap(), hidden.clone()), 2);
+    assert_eq!(table["servers"]["alpha"]["port"], hidden);
+    assert_eq!(table["servers"]["beta"]["port"], hidden);
+    assert_eq!(table["servers"]["alpha"]["host"].as_str(), Some("a"));
+
+    // A matching table is replaced whole, and what was inside it is not
+    // counted again.
+    assert_eq!(
+        table.redact(&"users.**".parse().unwrap(), hidden.clone()),
+        1
+    );
+    assert_eq!(table["users"], hidden);
+    assert_eq!(table.redact(&"missing".parse().unwrap(), hidden), 0);
+}
+
+#[test]
+fn array_insert_unique() {
+    let mut config: Table = toml::from_str(SRC).unwrap();
+    assert_eq!(config.array_insert_unique("tags", "a".into()), Ok(true));
+    assert_eq!(config.array_insert_unique("tags", "a".into()), Ok(false));
+    assert_eq!(config.array_insert_unique("ports", 8080.into()), Ok(true));
+    assert_eq!(config["ports"].as_int_array(), Some(vec![80, 443, 8080]));
+
+    assert_eq!(
+        config.array_insert_unique("features.default", "std".into()),
+        Ok(true)
+    );
+    assert_eq!(
+        config["features"]["default"].as_str_array(),
+        Some(vec!["std"])
+    );
+
+    let err = config
+        .array_insert_unique("ports", "http".into())
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "expected integer at `ports`, found string `\"http\"`"
+    );
+    let err = config.array_insert_unique("name", "x".into()).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "expected array at `name`, found string `\"demo\"`"
+    );
+    let err = config
+        .array_insert_unique("name.list", "x".into())
+        .unwrap_err();
+    assert_eq!(err.to_string(), "expected a table at `name`, found string");
+}
+
+#[test]
+fn array_remove_value() {
+    let mut config: Table =
+        toml::from_str("features = ['a', 'b', 'a']\n[[bin]]\nlibs = [1, 2]\n").unwrap();
+    assert_eq!(
+        config.array_remove_value("features", &Value::from("a")),
+        Ok(true)
+    );
+    assert_eq!(config["features"].as_str_array(), Some(vec!["b"]));
+    assert_eq!(
+        config.array_remove_value("features", &Value::from("a")),
+        Ok(false)
+    );
+    assert_eq!(
+        config.array_remove_value("missing.list", &Value::from("a")),
+        Ok(false)
+    );
+    assert_eq!(
+        config.array_remove_value("bin[0].libs", &Value::from(2)),
+        Ok(true)
+    );
+    assert_eq!(
+        config.array_remove_value("bin[3].libs", &Value::from(2)),
+        Ok(false)
+    );
+    assert_eq!(config["bin"][0]["libs"].as_int_array(), Some(vec![1]));
+    assert!(config
+        .array_remove_value("bin.libs", &Value::from(2))
+        .is_err());
+}
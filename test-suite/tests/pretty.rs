@@ -312,3 +312,52 @@ fn pretty_tricky_non_literal() {
     println!("\nRESULT:\n{}", result);
     assert_eq!(toml, &result);
 }
+
+const WRAPPED: &str = "\
+[package]
+keywords = [\"toml\", \"serde\"]
+nested = [
+    [1, 2, 3, 4],
+    [5, 6, 7, 8],
+]
+single = [\"a very long single element that does not fit\"]
+stop-words = [
+    \"alpha\",
+    \"beta\",
+    \"gamma\",
+    \"delta\",
+]
+";
+
+#[test]
+fn wrap_long_arrays() {
+    let value: toml::Value = toml::from_str(WRAPPED).unwrap();
+    let mut result = String::with_capacity(128);
+    value
+        .serialize(toml::Serializer::new(&mut result).pretty_array_max_width(30))
+        .unwrap();
+    println!("EXPECTED:\n{}", WRAPPED);
+    println!("\nRESULT:\n{}", result);
+    assert_eq!(WRAPPED, &result);
+}
+
+#[test]
+fn wrap_long_arrays_custom_layout() {
+    let value: toml::Value = toml::from_str("a = [111, 222, 333]\nb = [1]\n").unwrap();
+    let mut result = String::with_capacity(128);
+    value
+        .serialize(
+            toml::Serializer::new(&mut result)
+                .pretty_array_max_width(18)
+                .pretty_array_indent(2)
+                .pretty_array_trailing_comma(false),
+        )
+        .unwrap();
+    assert_eq!(result, "a = [\n  111,\n  222,\n  333\n]\nb = [1]\n");
+
+    let mut result = String::with_capacity(128);
+    value
+        .serialize(toml::Serializer::new(&mut result).pretty_array_max_width(19))
+        .unwrap();
+    assert_eq!(result, "a = [111, 222, 333]\nb = [1]\n");
+}
@@ -0,0 +1,110 @@
+use serde::Deserialize;
+use toml::{Deserializer, Value};
+
+#[test]
+fn reports_progress_until_done() {
+    let input = (0..500)
+        .map(|i| format!("key{} = '{}'\n", i, "x".repeat(1000)))
+        .collect::<String>();
+    let mut reports = Vec::new();
+    {
+        let mut de = Deserializer::new(&input);
+        de.set_progress(|done, total| {
+            reports.push((done, total));
+            true
+        });
+        let value = Value::deserialize(&mut de).unwrap();
+        assert_eq!(value.as_table().unwrap().len(), 500);
+    }
+
+    assert!(reports.len() > 5);
+    assert!(reports.windows(2).all(|w| w[0].0 < w[1].0));
+    assert!(reports.iter().all(|&(_, total)| total == input.len()));
+    assert_eq!(*reports.last().unwrap(), (input.len(), input.len()));
+}
+
+#[test]
+fn small_input_reports_once() {
+    let mut reports = Vec::new();
+    {
+        let mut de = Deserializer::new("a = 1\n");
+        de.set_progress(|done, total| {
+            reports.push((done, total));
+            true
+        });
+        Value::deserialize(&mut de).unwrap();
+    }
+    assert_eq!(reports, [(6, 6)]);
+}
+
+#[test]
+fn returning_false_cancels() {
+    let input = "a = 1\n";
+    let mut de = Deserializer::new(input);
+    de.set_progress(|_, _| false);
+    let err = Value::deserialize(&mut de).unwrap_err();
+    assert!(err.is_cancelled());
+    assert_eq!(
+        err.to_string(),
+        "deserialization was cancelled at line 2 column 1"
+    );
+
+    let err = toml::from_str::<Value>("a = ").unwrap_err();
+    assert!(!err.is_cancelled());
+}
+
+#[test]
+fn cancel_flag_stops_parsing() {
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+
+    let cancel = Arc::new(AtomicBool::new(false));
+    let mut de = Deserializer::new("a = 1\nb = 2\n");
+    de.set_cancel_flag(cancel.clone());
+    cancel.store(true, Ordering::Relaxed);
+    let err = Value::deserialize(&mut de).unwrap_err();
+    assert!(err.is_cancelled());
+    assert_eq!(
+        err.to_string(),
+        "deserialization was cancelled at line 2 column 1"
+    );
+}
+
+#[test]
+fn cancel_flag_stops_decoding() {
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+
+    thread_local! {
+        static CANCEL: Arc<AtomicBool> = Arc::new(AtomicBool::new(false));
+    }
+
+    struct Cancels;
+
+    impl<'de> Deserialize<'de> for Cancels {
+        fn deserialize<D: serde::Deserializer<'de>>(d: D) -> Result<Cancels, D::Error> {
+            i64::deserialize(d)?;
+            CANCEL.with(|c| c.store(true, Ordering::Relaxed));
+            Ok(Cancels)
+        }
+    }
+
+    #[derive(Deserialize)]
+    #[allow(dead_code)]
+    struct Config {
+        first: Cancels,
+        second: i64,
+    }
+
+    let mut de = Deserializer::new("first = 1\nsecond = 2\n");
+    de.set_cancel_flag(CANCEL.with(|c| c.clone()));
+    let err = match Config::deserialize(&mut de) {
+        Ok(_) => panic!("decoding was not cancelled"),
+        Err(err) => err,
+    };
+    assert!(err.is_cancelled());
+    assert_eq!(
+        err.to_string(),
+        "deserialization was cancelled at line 1 column 1"
+    );
+}
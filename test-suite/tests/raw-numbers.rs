@@ -0,0 +1,150 @@
+extern crate serde;
+extern crate serde_json;
+extern crate toml;
+#[macro_use]
+extern crate serde_derive;
+
+use serde::de::Deserialize;
+use toml::value::{ArrayExt, RawNumber};
+use toml::{Deserializer, Value};
+
+const DOC: &str = "\
+big = 123456789012345678901234567890
+negative = -98_765_432_109_876_543_210
+small = 42
+pi = 3.14159265358979323846264338327950288
+half = 0.5
+tiny = 1e-400
+huge = 1.5e+400
+";
+
+fn raw<'de, T: Deserialize<'de>>(input: &'de str) -> Result<T, toml::de::Error> {
+    let mut de = Deserializer::new(input);
+    de.set_raw_numbers(true);
+    T::deserialize(&mut de)
+}
+
+#[test]
+fn keeps_inexact_numbers() {
+    let value: Value = raw(DOC).unwrap();
+    assert_eq!(
+        value["big"].as_raw_number(),
+        Some("123456789012345678901234567890")
+    );
+    assert_eq!(
+        value["negative"].as_raw_number(),
+        Some("-98_765_432_109_876_543_210")
+    );
+    assert_eq!(
+        value["pi"].as_raw_number(),
+        Some("3.14159265358979323846264338327950288")
+    );
+    assert_eq!(value["tiny"].as_raw_number(), Some("1e-400"));
+    assert_eq!(value["huge"].as_raw_number(), Some("1.5e+400"));
+    assert_eq!(value["small"], Value::Integer(42));
+    assert_eq!(value["half"], Value::Float(0.5));
+    assert_eq!(value["big"].type_str(), "number");
+    assert!(!value["big"].is_table());
+}
+
+#[test]
+fn other_formats_see_strings() {
+    let value: Value = raw("big = 123456789012345678901234567890").unwrap();
+    assert_eq!(
+        serde_json::to_string(&value).unwrap(),
+        r#"{"big":"123456789012345678901234567890"}"#
+    );
+}
+
+#[test]
+fn merge_replaces_raw_numbers() {
+    let mut base: Value = raw("big = 123456789012345678901234567890").unwrap();
+    let top: Value = raw("big = {small = 1}").unwrap();
+    base.as_table_mut()
+        .unwrap()
+        .merge(top.as_table().unwrap().clone());
+    assert_eq!(base["big"]["small"].as_integer(), Some(1));
+}
+
+#[test]
+fn round_trips_unchanged() {
+    let value: Value = raw(DOC).unwrap();
+    let out = toml::to_string(&value).unwrap();
+    let again: Value = raw(&out).unwrap();
+    assert_eq!(again, value);
+    assert!(out.contains("pi = 3.14159265358979323846264338327950288\n"));
+    assert!(out.contains("huge = 1.5e+400\n"));
+}
+
+#[test]
+fn off_by_default() {
+    let err = toml::from_str::<Value>("big = 123456789012345678901234567890").unwrap_err();
+    assert!(err.to_string().contains("invalid number"), "{}", err);
+    let value: Value = toml::from_str("pi = 3.14159265358979323846264338327950288").unwrap();
+    assert_eq!(value["pi"].as_float(), Some(std::f64::consts::PI));
+}
+
+#[test]
+fn typed_fields_read_numbers() {
+    #[derive(Deserialize)]
+    struct Numbers {
+        big: u128,
+        negative: i128,
+        small: u8,
+        pi: f64,
+        huge: f64,
+    }
+
+    let numbers: Numbers = raw(DOC).unwrap();
+    assert_eq!(numbers.big, 123456789012345678901234567890);
+    assert_eq!(numbers.negative, -98765432109876543210);
+    assert_eq!(numbers.small, 42);
+    assert_eq!(numbers.pi, std::f64::consts::PI);
+    assert_eq!(numbers.huge, f64::INFINITY);
+
+    let value: Value = raw(DOC).unwrap();
+    let big = u128::deserialize(value["big"].clone()).unwrap();
+    assert_eq!(big, 123456789012345678901234567890);
+}
+
+#[test]
+fn too_large_for_field() {
+    #[derive(Debug, Deserialize)]
+    struct Small {
+        #[allow(dead_code)]
+        big: i64,
+    }
+
+    let err = raw::<Small>("big = 123456789012345678901234567890").unwrap_err();
+    assert_eq!(err.line_col(), Some((0, 6)));
+}
+
+#[test]
+fn rejects_invalid_text() {
+    for text in &["12abc", "1979-05-27", "\"1\"", "1 2", ""] {
+        let mut table = toml::value::Table::new();
+        table.insert("n".to_string(), Value::RawNumber(RawNumber::from(*text)));
+        let err = toml::to_string(&table).unwrap_err();
+        assert_eq!(err, toml::ser::Error::NumberInvalid, "{}", text);
+    }
+}
+
+#[test]
+fn sorts_exactly() {
+    let mut values: Value =
+        raw("a = [123456789012345678901234567891, 2, 123456789012345678901234567890, -1e400, 2.5]")
+            .unwrap();
+    let array = values["a"].as_array_mut().unwrap();
+    array.sort_values();
+    let sorted = array.iter().map(|v| v.to_string()).collect::<Vec<_>>();
+    assert_eq!(
+        sorted,
+        [
+            "-1e400",
+            "2",
+            "2.5",
+            "123456789012345678901234567890",
+            "123456789012345678901234567891",
+        ]
+    );
+}
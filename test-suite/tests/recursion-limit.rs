@@ -0,0 +1,102 @@
+use serde::Serialize;
+use toml::ser::{Error, Serializer, TableStyle};
+use toml::Value;
+
+#[derive(Serialize)]
+struct Node {
+    id: u32,
+    next: Option<Box<Node>>,
+}
+
+fn chain(len: u32) -> Node {
+    let mut node = Node { id: 0, next: None };
+    for id in 1..len {
+        node = Node {
+            id,
+            next: Some(Box::new(node)),
+        };
+    }
+    node
+}
+
+#[test]
+fn unlimited_by_default() {
+    assert!(toml::to_string(&chain(200)).is_ok());
+}
+
+#[test]
+fn configured_limit() {
+    let mut out = String::new();
+    chain(4)
+        .serialize(Serializer::new(&mut out).max_depth(3))
+        .unwrap();
+    assert_eq!(
+        out,
+        "\
+id = 3
+
+[next]
+id = 2
+
+[next.next]
+id = 1
+
+[next.next.next]
+id = 0
+"
+    );
+
+    let mut out = String::new();
+    assert_eq!(
+        chain(5).serialize(Serializer::new(&mut out).max_depth(3)),
+        Err(Error::RecursionLimitExceeded)
+    );
+}
+
+#[test]
+fn nested_arrays() {
+    let mut value = Value::from(1);
+    for _ in 0..10 {
+        value = Value::Array(vec![value]);
+    }
+    let mut table = toml::value::Table::new();
+    table.insert("a".to_string(), value);
+
+    let mut out = String::new();
+    table
+        .serialize(Serializer::new(&mut out).max_depth(10))
+        .unwrap();
+    assert_eq!(out, "a = [[[[[[[[[[1]]]]]]]]]]\n");
+
+    let mut out = String::new();
+    assert_eq!(
+        table.serialize(Serializer::new(&mut out).max_depth(9)),
+        Err(Error::RecursionLimitExceeded)
+    );
+}
+
+#[test]
+fn deep_inline_tables() {
+    let mut value = Value::from(1);
+    for _ in 0..1_000 {
+        let mut table = toml::value::Table::new();
+        table.insert("a".to_string(), value);
+        value = Value::Table(table);
+    }
+
+    let mut out = String::new();
+    let mut ser = Serializer::new(&mut out);
+    ser.max_depth(128).table_style("a", TableStyle::Inline);
+    assert_eq!(
+        value.serialize(&mut ser),
+        Err(Error::RecursionLimitExceeded)
+    );
+
+    let mut out = String::new();
+    let mut ser = Serializer::new(&mut out);
+    ser.table_style("a", TableStyle::Inline);
+    value.serialize(&mut ser).unwrap();
+    assert!(out.starts_with("a = { a = { a = "));
+    assert!(out.contains("{ a = 1 } }"));
+    assert_eq!(out.matches('{').count(), 999);
+}
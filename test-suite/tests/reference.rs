@@ -0,0 +1,75 @@
+extern crate toml;
+
+use toml::reference::{self, ReferenceErrorKind};
+use toml::value::Table;
+
+fn table(s: &str) -> Table {
+    toml::from_str(s).unwrap()
+}
+
+#[test]
+fn resolves_transitively() {
+    let config = table(
+        r#"
+        root = "/srv"
+        data = "@root"
+        dirs = ["@data", "@@literal", "plain"]
+        copy = "@defaults"
+        port = "@defaults.port"
+
+        [defaults]
+        port = 80
+        dir = "@dirs[0]"
+        "#,
+    );
+    let out = reference::resolve(&config).unwrap();
+    assert_eq!(
+        out,
+        table(
+            r#"
+            root = "/srv"
+            data = "/srv"
+            dirs = ["/srv", "@literal", "plain"]
+            copy = { port = 80, dir = "/srv" }
+            port = 80
+
+            [defaults]
+            port = 80
+            dir = "/srv"
+            "#
+        )
+    );
+}
+
+#[test]
+fn detects_cycles() {
+    let err = reference::resolve(&table("a = '@b'\nb = '@c'\nc = '@a'")).unwrap_err();
+    assert_eq!(*err.kind(), ReferenceErrorKind::Cycle);
+    assert_eq!(
+        err.to_string(),
+        "reference cycle in `a` -> `b` -> `c` -> `a`"
+    );
+
+    let err = reference::resolve(&table("[a]\nb = '@a'")).unwrap_err();
+    assert_eq!(*err.kind(), ReferenceErrorKind::Cycle);
+    assert_eq!(err.chain().len(), 2);
+}
+
+#[test]
+fn reports_missing_targets() {
+    let err = reference::resolve(&table("a = '@b'\nb = '@nope.x'")).unwrap_err();
+    assert_eq!(
+        *err.kind(),
+        ReferenceErrorKind::Unresolved("nope.x".to_string())
+    );
+    assert_eq!(
+        err.to_string(),
+        "unresolved reference `@nope.x` in `a` -> `b`"
+    );
+
+    let err = reference::resolve(&table("a = '@b..c'")).unwrap_err();
+    assert_eq!(
+        *err.kind(),
+        ReferenceErrorKind::InvalidPath("b..c".to_string())
+    );
+}
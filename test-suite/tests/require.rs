@@ -0,0 +1,151 @@
+use toml::path::{KeyPath, LookupErrorKind};
+use toml::value::Table;
+
+fn config() -> Table {
+    toml::from_str(
+        r#"
+name = "demo"
+
+[server]
+port = 8080
+hosts = ["a", "b"]
+
+[[workers]]
+id = 1
+"#,
+    )
+    .unwrap()
+}
+
+#[test]
+fn found() {
+    let config = config();
+    assert_eq!(config.require("name").unwrap().as_str(), Some("demo"));
+    assert_eq!(
+        config.require("server.hosts[1]").unwrap().as_str(),
+        Some("b")
+    );
+    assert_eq!(
+        config.require("workers[0].id").unwrap().as_integer(),
+        Some(1)
+    );
+}
+
+#[test]
+fn missing_key() {
+    let err = config().require("server.tls.cert").unwrap_err();
+    assert_eq!(err.kind(), LookupErrorKind::Missing);
+    assert_eq!(err.segment(), 1);
+    assert_eq!(err.path(), &"server.tls.cert".parse::<KeyPath>().unwrap());
+    assert_eq!(
+        err.to_string(),
+        "missing key `server.tls` when looking up `server.tls.cert`"
+    );
+
+    let err = config().require("version").unwrap_err();
+    assert_eq!(err.segment(), 0);
+    assert_eq!(err.to_string(), "missing key `version`");
+}
+
+#[test]
+fn missing_index() {
+    let err = config().require("server.hosts[2]").unwrap_err();
+    assert_eq!(err.kind(), LookupErrorKind::Missing);
+    assert_eq!(err.segment(), 2);
+    assert_eq!(err.to_string(), "missing index `server.hosts[2]`");
+}
+
+#[test]
+fn wrong_type() {
+    let err = config().require("server.port.number").unwrap_err();
+    assert_eq!(err.kind(), LookupErrorKind::WrongType("integer"));
+    assert_eq!(err.segment(), 2);
+    assert_eq!(
+        err.to_string(),
+        "expected a table at `server.port`, found integer"
+    );
+
+    let err = config().require("server[0].port").unwrap_err();
+    assert_eq!(err.kind(), LookupErrorKind::WrongType("table"));
+    assert_eq!(
+        err.to_string(),
+        "expected an array at `server`, found table when looking up `server[0].port`"
+    );
+
+    let err = config()
+        .require(KeyPath::from(vec![toml::path::PathSegment::Index(0)]))
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "expected an array at the root, found table"
+    );
+}
+
+#[test]
+fn empty_path() {
+    let err = config().require(KeyPath::new()).unwrap_err();
+    assert_eq!(err.kind(), LookupErrorKind::Missing);
+    assert_eq!(err.to_string(), "an empty key path refers to no value");
+}
+
+#[test]
+fn checked_accessors() {
+    use toml::path::AccessErrorKind;
+    use toml::value::{Value, ValueKind};
+
+    let config = config();
+    let port: u16 = config
+        .require_checked("server.port", Value::as_integer_checked)
+        .unwrap();
+    assert_eq!(port, 8080);
+    let name = config
+        .require_checked("name", Value::as_str_checked)
+        .unwrap();
+    assert_eq!(name, "demo");
+
+    let err = config
+        .require_checked::<_, u8, _>("server.port", Value::as_integer_checked)
+        .unwrap_err();
+    assert_eq!(err.kind(), AccessErrorKind::OutOfRange);
+    assert_eq!(err.expected(), "u8");
+    assert_eq!(err.found(), Some(ValueKind::Integer));
+    assert_eq!(err.path().unwrap().to_string(), "server.port");
+    assert_eq!(
+        err.to_string(),
+        "integer `8080` at `server.port` is out of range for u8"
+    );
+
+    let err = config
+        .require_checked("server.hosts", Value::as_table_checked)
+        .unwrap_err();
+    assert_eq!(err.kind(), AccessErrorKind::WrongType);
+    assert_eq!(
+        err.to_string(),
+        "expected table at `server.hosts`, found array"
+    );
+
+    let err = config
+        .require_checked("server.tls.cert", Value::as_str_checked)
+        .unwrap_err();
+    assert_eq!(
+        err.kind(),
+        AccessErrorKind::Lookup(LookupErrorKind::Missing)
+    );
+    assert_eq!(err.found(), None);
+    assert_eq!(
+        err.to_string(),
+        "missing key `server.tls` when looking up `server.tls.cert`"
+    );
+
+    let err = Value::Float(1.5).as_i64_checked().unwrap_err();
+    assert_eq!(err.path(), None);
+    assert_eq!(err.to_string(), "expected integer, found float `1.5`");
+    let err = err.with_path("limits.cpu");
+    assert_eq!(
+        err.to_string(),
+        "expected integer at `limits.cpu`, found float `1.5`"
+    );
+    assert_eq!(Value::Boolean(true).as_bool_checked(), Ok(true));
+    assert!(Value::Integer(1).as_datetime_checked().is_err());
+    assert_eq!(Value::Float(2.0).as_f64_checked(), Ok(2.0));
+}
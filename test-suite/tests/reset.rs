@@ -0,0 +1,66 @@
+extern crate serde;
+extern crate toml;
+
+use std::sync::Mutex;
+
+use serde::Deserialize;
+use toml::Value;
+
+#[test]
+fn reads_each_input() {
+    let sources = [
+        "\u{feff}a = \"tab\\there\"\n",
+        "[b]\nc = 'literal'\n",
+        "d = [1, 2]\n",
+    ];
+    let mut de = toml::Deserializer::new("");
+    for source in &sources {
+        de.reset(source);
+        let value = Value::deserialize(&mut de).unwrap();
+        assert_eq!(value, toml::from_str::<Value>(source).unwrap());
+    }
+}
+
+#[test]
+fn after_an_error() {
+    let mut de = toml::Deserializer::new("a = \"unterminated\nb = 1\n");
+    assert!(Value::deserialize(&mut de).is_err());
+
+    de.reset("a = 1\nb = =\n");
+    let err = Value::deserialize(&mut de).unwrap_err();
+    assert_eq!(err.line_col(), Some((1, 4)));
+
+    de.reset("a = 1\n");
+    let value = Value::deserialize(&mut de).unwrap();
+    assert_eq!(value["a"], Value::Integer(1));
+}
+
+#[test]
+fn keeps_options() {
+    let mut de = toml::Deserializer::new("a = 01\n");
+    de.set_lenient_numbers(true);
+    Value::deserialize(&mut de).unwrap();
+    assert_eq!(de.warnings().len(), 1);
+
+    de.reset("b = 02\nc = 3\n");
+    let value = Value::deserialize(&mut de).unwrap();
+    assert_eq!(value["b"], Value::Integer(2));
+    let warnings = de.warnings();
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(warnings[0].line_col(), (0, 4));
+}
+
+#[test]
+fn reports_progress_again() {
+    let calls = Mutex::new(Vec::new());
+    let mut de = toml::Deserializer::new("a = 1\n");
+    de.set_progress(|done, total| {
+        calls.lock().unwrap().push((done, total));
+        true
+    });
+    Value::deserialize(&mut de).unwrap();
+    de.reset("b = 22\n");
+    Value::deserialize(&mut de).unwrap();
+    drop(de);
+    assert_eq!(*calls.lock().unwrap(), [(6, 6), (7, 7)]);
+}
@@ -0,0 +1,270 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use serde::Serialize;
+use toml::ser::{FieldRules, Serializer};
+use toml::Value;
+
+#[derive(Serialize)]
+struct Config {
+    name: String,
+    timeout_ms: u64,
+    token: Option<String>,
+    server: Server,
+    workers: Vec<Worker>,
+}
+
+#[derive(Serialize)]
+struct Server {
+    host: String,
+    secret: String,
+}
+
+#[derive(Serialize)]
+struct Worker {
+    id: u32,
+    secret: String,
+}
+
+fn config() -> Config {
+    Config {
+        name: "app".to_string(),
+        timeout_ms: 2500,
+        token: None,
+        server: Server {
+            host: "localhost".to_string(),
+            secret: "s3".to_string(),
+        },
+        workers: vec![Worker {
+            id: 1,
+            secret: "w1".to_string(),
+        }],
+    }
+}
+
+fn write<F>(f: F) -> String
+where
+    F: Fn(&toml::path::KeyPath, Value) -> Option<Value> + 'static,
+{
+    let mut out = String::new();
+    let mut ser = Serializer::new(&mut out);
+    ser.rewrite_values(f);
+    config().serialize(&mut ser).unwrap();
+    out
+}
+
+#[test]
+fn sees_every_entry() {
+    let seen = Rc::new(RefCell::new(Vec::new()));
+    let log = seen.clone();
+    let out = write(move |path, value| {
+        log.borrow_mut().push(path.to_string());
+        Some(value)
+    });
+
+    let mut plain = String::new();
+    config()
+        .serialize(&mut Serializer::new(&mut plain))
+        .unwrap();
+    assert_eq!(out, plain);
+    assert_eq!(
+        *seen.borrow(),
+        [
+            "name",
+            "timeout_ms",
+            "server",
+            "server.host",
+            "server.secret",
+            "workers",
+            "workers[0].id",
+            "workers[0].secret",
+        ]
+    );
+}
+
+#[test]
+fn redacts_and_converts() {
+    let out = write(|path, value| {
+        let last = path.to_string().rsplit('.').next().unwrap().to_string();
+        match (last.as_str(), value) {
+            ("secret", _) => None,
+            ("timeout_ms", Value::Integer(ms)) => Some(Value::Float(ms as f64 / 1000.0)),
+            (_, value) => Some(value),
+        }
+    });
+    assert_eq!(
+        out,
+        "\
+name = \"app\"
+timeout_ms = 2.5
+
+[server]
+host = \"localhost\"
+
+[[workers]]
+id = 1
+"
+    );
+}
+
+#[test]
+fn suppresses_tables() {
+    let out = write(|path, value| match path.to_string().as_str() {
+        "server" | "workers" => None,
+        _ => Some(value),
+    });
+    assert_eq!(out, "name = \"app\"\ntimeout_ms = 2500\n");
+}
+
+#[test]
+fn rewritten_tables_are_walked() {
+    let out = write(|path, value| match path.to_string().as_str() {
+        "server" => Some(toml::toml! { address = "example.com:80" }),
+        "server.address" => Some(Value::from("localhost:80")),
+        "workers" => None,
+        _ => Some(value),
+    });
+    assert_eq!(
+        out,
+        "name = \"app\"\ntimeout_ms = 2500\n\n[server]\naddress = \"localhost:80\"\n"
+    );
+}
+
+#[test]
+fn table_among_values() {
+    let mut out = String::new();
+    let err = config()
+        .serialize(Serializer::new(&mut out).rewrite_values(|path, value| {
+            match path.to_string().as_str() {
+                "name" => Some(toml::toml! { first = "app" }),
+                _ => Some(value),
+            }
+        }))
+        .unwrap_err();
+    assert_eq!(err, toml::ser::Error::ValueAfterTable);
+}
+
+#[derive(Serialize)]
+struct Ordered {
+    zeta: u32,
+    alpha: u32,
+    inner: Inner,
+}
+
+#[derive(Serialize)]
+struct Inner {
+    zeta: u32,
+    secret: String,
+    alpha: u32,
+}
+
+#[test]
+fn unchanged_values_keep_structs() {
+    let ordered = Ordered {
+        zeta: 1,
+        alpha: 2,
+        inner: Inner {
+            zeta: 3,
+            secret: "s".to_string(),
+            alpha: 4,
+        },
+    };
+    let write = |rewrite: bool| {
+        let mut rules = FieldRules::new();
+        rules.skip("Inner", "secret");
+        let mut out = String::new();
+        let mut ser = Serializer::new(&mut out);
+        ser.field_rules(rules).key_doc("inner.alpha", "Alpha.");
+        if rewrite {
+            ser.rewrite_values(|path, value| match value {
+                Value::Integer(3) if path.to_string() == "inner.zeta" => Some(Value::Integer(30)),
+                value => Some(value),
+            });
+        }
+        ordered.serialize(&mut ser).unwrap();
+        out
+    };
+    assert_eq!(
+        write(false),
+        "zeta = 1\nalpha = 2\n\n[inner]\nzeta = 3\n# Alpha.\nalpha = 4\n"
+    );
+    assert_eq!(
+        write(true),
+        "zeta = 1\nalpha = 2\n\n[inner]\nzeta = 30\n# Alpha.\nalpha = 4\n"
+    );
+}
+
+fn read<F>(input: &str, f: F) -> Result<Value, toml::de::Error>
+where
+    F: FnMut(&toml::path::KeyPath, Value) -> Value + Send,
+{
+    let mut de = toml::Deserializer::new(input);
+    de.set_rewrite_values(f);
+    serde::Deserialize::deserialize(&mut de)
+}
+
+#[test]
+fn reads_rewritten_values() {
+    let mut seen = Vec::new();
+    let value = read(
+        "name = ' App '\nports = [80, 443]\nlog = { level = 'INFO' }\n[[workers]]\nid = 1\n",
+        |path, value| {
+            seen.push(path.to_string());
+            match value {
+                Value::String(s) => Value::from(s.trim().to_lowercase()),
+                Value::Integer(i) if path.to_string().starts_with("ports") => {
+                    Value::Integer(i + 8000)
+                }
+                value => value,
+            }
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        seen,
+        ["name", "ports[0]", "ports[1]", "log.level", "workers[0].id"]
+    );
+    assert_eq!(
+        value,
+        toml::toml! {
+            name = "app"
+            ports = [8080, 8443]
+            log = { level = "info" }
+            [[workers]]
+            id = 1
+        }
+    );
+}
+
+#[test]
+fn typed_fields_see_rewritten_values() {
+    #[derive(serde::Deserialize)]
+    struct Limits {
+        timeout: f64,
+        retries: Vec<u8>,
+    }
+
+    let mut de = toml::Deserializer::new("timeout = '2.5s'\nretries = 3\n");
+    de.set_rewrite_values(|path, value| match (path.to_string().as_str(), value) {
+        ("timeout", Value::String(s)) => Value::Float(s.trim_end_matches('s').parse().unwrap()),
+        ("retries", value) => Value::Array(vec![value]),
+        (_, value) => value,
+    });
+    let limits: Limits = serde::Deserialize::deserialize(&mut de).unwrap();
+    assert_eq!(limits.timeout, 2.5);
+    assert_eq!(limits.retries, [3]);
+}
+
+#[test]
+fn errors_point_at_replaced_value() {
+    #[derive(Debug, serde::Deserialize)]
+    struct Port {
+        #[allow(dead_code)]
+        port: u16,
+    }
+
+    let mut de = toml::Deserializer::new("\nport = 80\n");
+    de.set_rewrite_values(|_, _| Value::from("eighty"));
+    let err = <Port as serde::Deserialize>::deserialize(&mut de).unwrap_err();
+    assert_eq!(err.line_col(), Some((1, 7)));
+}
@@ -0,0 +1,102 @@
+use serde::Serialize;
+use toml::ser::{check_round_trip, to_string_checked, Error};
+use toml::value::Datetime;
+use toml::Value;
+
+#[derive(Serialize)]
+struct Odd {
+    text: String,
+    huge: f64,
+    tiny: f64,
+    nan: f64,
+    when: Datetime,
+    items: Vec<Item>,
+}
+
+#[derive(Serialize)]
+struct Item {
+    name: String,
+}
+
+#[test]
+fn odd_values() {
+    let odd = Odd {
+        text: "tab\t\"quote\" \u{7f} ''' \\ \r\n".to_string(),
+        huge: f64::MAX,
+        tiny: f64::MIN_POSITIVE,
+        nan: f64::NAN,
+        when: "1979-05-27T00:32:00.999999-07:00".parse().unwrap(),
+        items: vec![
+            Item {
+                name: "".to_string(),
+            },
+            Item {
+                name: "a.b".to_string(),
+            },
+        ],
+    };
+    let out = to_string_checked(&odd).unwrap();
+    assert_eq!(out, toml::to_string(&odd).unwrap());
+    check_round_trip(&odd, &toml::to_string_pretty(&odd).unwrap()).unwrap();
+}
+
+#[test]
+fn first_difference() {
+    let value = toml::toml! {
+        a = 1
+        [b]
+        c = [1.0, 2.0]
+        d = "x"
+    };
+
+    let err = check_round_trip(&value, "a = 1\n[b]\nc = [1.0, 2.5]\nd = 'y'\n").unwrap_err();
+    match err {
+        Error::RoundTrip(e) => {
+            assert_eq!(e.path().to_string(), "b.c[1]");
+            assert_eq!(e.written(), Some("2.0"));
+            assert_eq!(e.read(), Some("2.5"));
+        }
+        other => panic!("unexpected {:?}", other),
+    }
+
+    let err = check_round_trip(&value, "a = 1\n[b]\nc = [1.0, 2.0]\n").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "output does not read back the same at `b.d`: wrote \"x\", read nothing"
+    );
+
+    let err = check_round_trip(&value, "a = 1\ne = 2\n[b]\nc = [1.0, 2.0]\nd = 'x'\n").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "output does not read back the same at `e`: wrote nothing, read 2"
+    );
+
+    let err = check_round_trip(&value, "a = 1.0\n[b]\nc = [1.0, 2.0]\nd = 'x'\n").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "output does not read back the same at `a`: wrote 1, read 1.0"
+    );
+}
+
+#[test]
+fn floats_by_bits() {
+    let value = toml::toml! { z = 0.0 };
+    assert!(check_round_trip(&value, "z = -0.0").is_err());
+    check_round_trip(&value, "z = 0.0").unwrap();
+}
+
+#[test]
+fn invalid_output() {
+    let value = toml::toml! { a = 1 };
+    match check_round_trip(&value, "a = ") {
+        Err(Error::Custom(msg)) => assert!(msg.starts_with("output is not valid TOML")),
+        other => panic!("unexpected {:?}", other),
+    }
+}
+
+#[test]
+fn bare_values() {
+    check_round_trip(&Value::from("hi"), "\"hi\"").unwrap();
+    check_round_trip(&vec![1, 2], "[1, 2]").unwrap();
+    assert!(check_round_trip(&vec![1, 2], "[1]").is_err());
+}
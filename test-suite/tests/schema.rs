@@ -0,0 +1,394 @@
+use toml::document::Document;
+use toml::schema::{Constraint, Presence, Schema, SchemaErrorKind};
+use toml::value::{Table, ValueKind};
+use toml::Value;
+
+fn schema() -> Schema {
+    let mut schema = Schema::new();
+    schema
+        .required("name", ValueKind::String)
+        .required("server.port", ValueKind::Integer)
+        .optional("server.host", ValueKind::String)
+        .optional("tls.enabled", ValueKind::Boolean)
+        .optional("tls.cert", ValueKind::String)
+        .forbidden("server.legacy", "set `server.mode = \"legacy\"` instead")
+        .forbidden("debug", "")
+        .required_when("tls.enabled", Value::from(true), "tls.cert");
+    schema
+}
+
+fn errors(src: &str) -> Vec<String> {
+    let table: Table = toml::from_str(src).unwrap();
+    match schema().validate(&table) {
+        Ok(()) => Vec::new(),
+        Err(errors) => errors.iter().map(|e| e.to_string()).collect(),
+    }
+}
+
+#[test]
+fn valid() {
+    assert_eq!(
+        errors(
+            r#"
+name = "app"
+[server]
+port = 80
+[tls]
+enabled = false
+"#
+        ),
+        Vec::<String>::new()
+    );
+    assert_eq!(
+        errors(
+            r#"
+name = "app"
+server.port = 80
+tls = { enabled = true, cert = "cert.pem" }
+"#
+        ),
+        Vec::<String>::new()
+    );
+}
+
+#[test]
+fn missing_and_wrong_type() {
+    assert_eq!(
+        errors("name = 1\nserver.host = 2"),
+        [
+            "invalid type for `name`: expected string, found integer",
+            "missing required key `server.port`",
+            "invalid type for `server.host`: expected string, found integer",
+        ]
+    );
+}
+
+#[test]
+fn forbidden() {
+    assert_eq!(
+        errors("name = 'app'\ndebug = true\n[server]\nport = 80\nlegacy = true"),
+        [
+            "key `server.legacy` is not allowed: set `server.mode = \"legacy\"` instead",
+            "key `debug` is not allowed",
+        ]
+    );
+}
+
+#[test]
+fn conditional() {
+    assert_eq!(
+        errors("name = 'app'\nserver.port = 80\ntls.enabled = true"),
+        ["missing key `tls.cert`, required when `tls.enabled` is true"]
+    );
+
+    let table: Table =
+        toml::from_str("name = 'app'\nserver.port = 80\ntls.enabled = true").unwrap();
+    let errors = schema().validate(&table).unwrap_err();
+    assert_eq!(errors[0].path().to_string(), "tls.cert");
+    match errors[0].kind() {
+        SchemaErrorKind::MissingWhen { path, value } => {
+            assert_eq!(path.to_string(), "tls.enabled");
+            assert_eq!(value, &Value::from(true));
+        }
+        kind => panic!("unexpected {:?}", kind),
+    }
+}
+
+#[test]
+fn presence() {
+    let schema = schema();
+    assert_eq!(schema.presence("name"), Some(Presence::Required));
+    assert_eq!(schema.presence("server.host"), Some(Presence::Optional));
+    assert_eq!(schema.presence("debug"), Some(Presence::Forbidden));
+    assert_eq!(schema.presence("other"), None);
+}
+
+#[test]
+fn later_rules_replace_earlier_ones() {
+    let mut schema = Schema::new();
+    schema
+        .required("debug", ValueKind::Boolean)
+        .forbidden("debug", "remove it");
+    let table: Table = toml::from_str("").unwrap();
+    assert!(schema.validate(&table).is_ok());
+}
+
+#[test]
+fn document_spans() {
+    let src = "name = 'app'\n[server]\nlegacy = true\n[tls]\nenabled = true\n";
+    let doc: Document = src.parse().unwrap();
+    let errors = schema().validate_document(&doc).unwrap_err();
+    let spans = errors
+        .iter()
+        .map(|e| (e.to_string(), e.span().map(|(s, e)| &src[s..e])))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        spans,
+        [
+            (
+                "missing required key `server.port`".to_string(),
+                Some("server")
+            ),
+            (
+                "key `server.legacy` is not allowed: set `server.mode = \"legacy\"` instead"
+                    .to_string(),
+                Some("legacy")
+            ),
+            (
+                "missing key `tls.cert`, required when `tls.enabled` is true".to_string(),
+                Some("enabled")
+            ),
+        ]
+    );
+}
+
+fn limits() -> Schema {
+    let mut schema = Schema::new();
+    schema
+        .required("limits.min", ValueKind::Integer)
+        .required("limits.max", ValueKind::Integer)
+        .check(
+            ["limits.min", "limits.max"],
+            "must not exceed `limits.max`",
+            |v| v[0].as_integer() <= v[1].as_integer(),
+        )
+        .check_table(|table| {
+            let ports = match table.get("ports").and_then(Value::as_array) {
+                Some(ports) => ports,
+                None => return Vec::new(),
+            };
+            ports
+                .iter()
+                .enumerate()
+                .filter(|(_, port)| !matches!(port.as_integer(), Some(p) if p >= 1024))
+                .map(|(i, _)| {
+                    let mut path = toml::path::KeyPath::new();
+                    path.push_key("ports");
+                    path.push_index(i);
+                    (path, "must be an unprivileged port".to_string())
+                })
+                .collect()
+        });
+    schema
+}
+
+#[test]
+fn checks() {
+    let table: Table =
+        toml::from_str("ports = [8080, 80, 22]\n[limits]\nmin = 1\nmax = 5").unwrap();
+    let errors = limits().validate(&table).unwrap_err();
+    let messages = errors.iter().map(|e| e.to_string()).collect::<Vec<_>>();
+    assert_eq!(
+        messages,
+        [
+            "invalid value for `ports[1]`: must be an unprivileged port",
+            "invalid value for `ports[2]`: must be an unprivileged port",
+        ]
+    );
+
+    let table: Table = toml::from_str("[limits]\nmin = 10\nmax = 5").unwrap();
+    let errors = limits().validate(&table).unwrap_err();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(
+        errors[0].kind(),
+        &SchemaErrorKind::Check("must not exceed `limits.max`".to_string())
+    );
+}
+
+#[test]
+fn checks_skip_missing_values() {
+    let table: Table = toml::from_str("[limits]\nmin = 10").unwrap();
+    let errors = limits().validate(&table).unwrap_err();
+    let messages = errors.iter().map(|e| e.to_string()).collect::<Vec<_>>();
+    assert_eq!(messages, ["missing required key `limits.max`"]);
+}
+
+#[test]
+fn check_spans() {
+    let src = "[limits]\nmin = 10\nmax = 5\n";
+    let doc: Document = src.parse().unwrap();
+    let errors = limits().validate_document(&doc).unwrap_err();
+    let (start, end) = errors[0].span().unwrap();
+    assert_eq!(&src[start..end], "min");
+}
+
+#[test]
+fn pattern_rules() {
+    let mut schema = Schema::new();
+    schema
+        .optional_matching("servers.*.port".parse().unwrap(), ValueKind::Integer)
+        .forbidden_matching("**.password".parse().unwrap(), "");
+    assert_eq!(schema.validate(&Table::new()), Ok(()));
+
+    let table: Table =
+        toml::from_str("password = 'x'\n[servers.a]\nport = 'http'\n[servers.b]\nport = 81\n")
+            .unwrap();
+    let errors = schema.validate(&table).unwrap_err();
+    let messages = errors.iter().map(|e| e.to_string()).collect::<Vec<_>>();
+    assert_eq!(
+        messages,
+        [
+            "invalid type for `servers.a.port`: expected integer, found string",
+            "key `password` is not allowed",
+        ]
+    );
+    assert_eq!(schema.presence("servers.a.port"), None);
+}
+
+#[test]
+fn constraint_messages() {
+    let mut schema = Schema::new();
+    schema
+        .constrain("port", Constraint::range(1..=65535))
+        .constrain("workers", Constraint::range(..16))
+        .constrain("ratio", Constraint::float_range(0.0..1.0))
+        .constrain("name", Constraint::length(1..=3))
+        .constrain("tag", Constraint::length(..=1))
+        .constrain("pair", Constraint::items(2..=2))
+        .constrain("hosts", Constraint::items(1..));
+    let table: Table = toml::from_str(
+        "port = 0\nworkers = 16\nratio = 1\nname = 'äöüß'\ntag = 'ab'\npair = [1]\nhosts = []\n",
+    )
+    .unwrap();
+    let errors = schema.validate(&table).unwrap_err();
+    let messages = errors.iter().map(|e| e.to_string()).collect::<Vec<_>>();
+    assert_eq!(
+        messages,
+        [
+            "invalid value for `port`: must be at least 1 and at most 65535",
+            "invalid value for `workers`: must be less than 16",
+            "invalid value for `ratio`: must be at least 0 and less than 1",
+            "invalid value for `name`: must be at least 1 and at most 3 characters long",
+            "invalid value for `tag`: must be at most 1 character long",
+            "invalid value for `pair`: must have exactly 2 items",
+            "invalid value for `hosts`: must have at least 1 item",
+        ]
+    );
+    assert_eq!(
+        errors[0].kind(),
+        &SchemaErrorKind::Constraint("must be at least 1 and at most 65535".to_string())
+    );
+
+    let table: Table = toml::from_str(
+        "port = 80\nworkers = 2\nratio = 0.5\nname = 'äöü'\ntag = ''\npair = [1, 1]\nhosts = ['a']\n",
+    )
+    .unwrap();
+    assert_eq!(schema.validate(&table), Ok(()));
+
+    // Values of other types, and missing values, are left to the key rules.
+    let table: Table = toml::from_str("port = '80'\nname = 5\nhosts = 'a'\n").unwrap();
+    assert_eq!(schema.validate(&table), Ok(()));
+}
+
+#[test]
+fn unique_items() {
+    let mut schema = Schema::new();
+    schema.constrain_matching("**.tags".parse().unwrap(), Constraint::unique_items());
+    let src = "tags = ['a', 'b', 'a', 'a']\n[vendor]\ntags = [[1], [1]]\n";
+    let doc: Document = src.parse().unwrap();
+    let errors = schema.validate_document(&doc).unwrap_err();
+    let messages = errors.iter().map(|e| e.to_string()).collect::<Vec<_>>();
+    assert_eq!(
+        messages,
+        [
+            "invalid value for `tags[2]`: must not repeat `tags[0]`",
+            "invalid value for `tags[3]`: must not repeat `tags[0]`",
+            "invalid value for `vendor.tags[1]`: must not repeat `vendor.tags[0]`",
+        ]
+    );
+    let (start, end) = errors[0].span().unwrap();
+    assert_eq!(&src[start..end], "'a'");
+}
+
+#[test]
+fn patterns() {
+    let matches = |pattern: &str, s: &str| {
+        Constraint::pattern(pattern)
+            .unwrap()
+            .is_met_by(&Value::from(s))
+    };
+    assert!(matches("abc", "xabcx"));
+    assert!(!matches("^abc$", "xabcx"));
+    assert!(matches("^a.c$", "abc"));
+    assert!(!matches("^a.c$", "a\nc"));
+    assert!(matches("^\\d+$", "2024"));
+    assert!(!matches("^\\d+$", ""));
+    assert!(matches("^\\w*$", ""));
+    assert!(matches("^[a-z][a-z0-9-]*$", "my-app2"));
+    assert!(!matches("^[a-z][a-z0-9-]*$", "2app"));
+    assert!(matches("^[^,]+$", "a b"));
+    assert!(!matches("^[^,]+$", "a,b"));
+    assert!(matches("^[\\d.]+$", "1.2.3"));
+    assert!(matches("^v?1\\.0$", "1.0"));
+    assert!(!matches("^v?1\\.0$", "1x0"));
+    assert!(matches("^a*a*ab$", "aaab"));
+    assert!(matches("$a", "$a"));
+    assert!(matches("^[-a]+$", "-a-"));
+    assert!(matches("^\\S+\\s\\S+$", "a\tb"));
+
+    let error = |pattern: &str| Constraint::pattern(pattern).unwrap_err().to_string();
+    assert_eq!(
+        error("*a"),
+        "invalid pattern: nothing to repeat at offset 0"
+    );
+    assert_eq!(
+        error("a(b|c)"),
+        "invalid pattern: groups and alternation are not supported at offset 1"
+    );
+    assert_eq!(
+        error("a{2}"),
+        "invalid pattern: counted repetition is not supported at offset 1"
+    );
+    assert_eq!(
+        error("[a-"),
+        "invalid pattern: unterminated character class at offset 0"
+    );
+    assert_eq!(
+        error("[z-a]"),
+        "invalid pattern: invalid range in character class at offset 3"
+    );
+    assert_eq!(error("a\\"), "invalid pattern: trailing `\\` at offset 1");
+}
+
+#[test]
+fn patterns_take_linear_time() {
+    // Both of these take exponential time with a backtracking matcher.
+    let digits = "1".repeat(40);
+    let pattern = Constraint::pattern(&format!("{}x", "\\d*".repeat(8))).unwrap();
+    assert!(!pattern.is_met_by(&Value::from(&digits[..])));
+    assert!(pattern.is_met_by(&Value::from(format!("{}x", digits))));
+
+    let a = "a".repeat(30);
+    let pattern = Constraint::pattern(&format!("^{}{}$", "a?".repeat(30), a)).unwrap();
+    assert!(pattern.is_met_by(&Value::from(&a[..])));
+    assert!(!pattern.is_met_by(&Value::from(format!("{}b", a))));
+}
+
+#[test]
+fn combined_constraints() {
+    let name = Constraint::pattern("^[a-z]+$")
+        .unwrap()
+        .and(Constraint::length(..=4));
+    assert!(name.is_met_by(&Value::from("abc")));
+    assert!(!name.is_met_by(&Value::from("abcde")));
+    assert!(!name.is_met_by(&Value::from("ABC")));
+
+    let mut schema = Schema::new();
+    schema.constrain("name", name).constrain(
+        "port",
+        Constraint::range(..=0).or(Constraint::range(1024..=65535)),
+    );
+    let table: Table = toml::from_str("name = 'ABCDE'\nport = 80\n").unwrap();
+    let errors = schema.validate(&table).unwrap_err();
+    let messages = errors.iter().map(|e| e.to_string()).collect::<Vec<_>>();
+    assert_eq!(
+        messages,
+        [
+            "invalid value for `name`: must match `^[a-z]+$`",
+            "invalid value for `name`: must be at most 4 characters long",
+            "invalid value for `port`: must be at most 0, or must be at least 1024 and at most 65535",
+        ]
+    );
+    let table: Table = toml::from_str("name = 'ab'\nport = 0\n").unwrap();
+    assert_eq!(schema.validate(&table), Ok(()));
+}
@@ -0,0 +1,67 @@
+extern crate toml;
+
+use toml::semantically_equal;
+
+#[test]
+fn formatting_is_ignored() {
+    let committed = r#"
+# Generated file, do not edit.
+title = "demo"
+
+[owner]
+name = 'Tom'
+dob = 1979-05-27T07:32:00Z
+
+[[servers]]
+ip = "10.0.0.1"
+ports = [ 8000, 8001 ]
+
+[[servers]]
+ip = "10.0.0.2"
+ports = [8002]
+"#;
+    let generated = "\
+owner.dob = 1979-05-27 07:32:00z
+owner.\"name\" = \"Tom\"
+servers = [{ ports = [8000, 8001], ip = '10.0.0.1' }, { ip = \"10.0.0.2\", ports = [0x1F42] }]
+title = '''demo'''
+";
+    assert!(semantically_equal(committed, generated).unwrap());
+    assert!(semantically_equal(generated, committed).unwrap());
+}
+
+#[test]
+fn differences_are_found() {
+    let base = "a = 1\nb = [1, 2]\n[c]\nd = 'x'\n";
+    assert!(semantically_equal(base, base).unwrap());
+    assert!(!semantically_equal(base, "a = 1.0\nb = [1, 2]\n[c]\nd = 'x'\n").unwrap());
+    assert!(!semantically_equal(base, "a = 1\nb = [2, 1]\n[c]\nd = 'x'\n").unwrap());
+    assert!(!semantically_equal(base, "a = 1\nb = [1, 2]\n[c]\nd = 'X'\n").unwrap());
+    assert!(!semantically_equal(base, "a = 1\nb = [1, 2]\n[c]\nd = 'x'\ne = 2\n").unwrap());
+    assert!(!semantically_equal(base, "a = 1\nb = [1, 2]\nc = 'x'\n").unwrap());
+}
+
+#[test]
+fn normalized_values() {
+    assert!(semantically_equal("x = nan", "x = +nan").unwrap());
+    assert!(semantically_equal("x = [nan, 1.5]", "x = [-nan, 1.5]").unwrap());
+    assert!(semantically_equal(
+        "s = \"\"\"\nline one\nline two\"\"\"\n",
+        "s = \"\"\"\r\nline one\r\nline two\"\"\"\r\n",
+    )
+    .unwrap());
+    assert!(!semantically_equal("s = 'a\\r\\nb'", "s = \"a\\r\\nb\"").unwrap());
+
+    let a: toml::Value = "x = nan".parse().unwrap();
+    assert!(a != a.clone());
+    assert!(a.semantically_eq(&a));
+}
+
+#[test]
+fn parse_errors() {
+    let err = semantically_equal("a = 1", "a = ").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "unexpected eof encountered at line 1 column 5"
+    );
+}
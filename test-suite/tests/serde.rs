@@ -681,3 +681,38 @@ fn json_interoperability() {
     )
     .unwrap();
 }
+
+#[test]
+fn bare_values() {
+    assert_eq!(t!(toml::to_value(&8080u16)), Integer(8080));
+    assert_eq!(t!(toml::to_value("x")), Value::String("x".to_string()));
+    assert_eq!(
+        t!(toml::to_value(&vec![1.5, 2.0])),
+        Array(vec![Float(1.5), Float(2.0)])
+    );
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    enum Level {
+        Debug,
+        Warn,
+    }
+    assert_eq!(
+        t!(toml::to_value(&Level::Warn)),
+        Value::String("Warn".to_string())
+    );
+    assert_eq!(
+        t!(toml::from_value::<Level>(Value::String(
+            "Debug".to_string()
+        ))),
+        Level::Debug
+    );
+    assert_eq!(
+        t!(toml::from_value::<Vec<u8>>(Array(vec![
+            Integer(1),
+            Integer(2)
+        ]))),
+        vec![1, 2]
+    );
+    assert!(toml::from_value::<u8>(Integer(256)).is_err());
+    assert!(toml::from_value::<bool>(Integer(1)).is_err());
+}
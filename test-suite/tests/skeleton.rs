@@ -0,0 +1,96 @@
+use serde::Serialize;
+use toml::schema::Schema;
+use toml::value::{Table, ValueKind};
+
+fn schema() -> Schema {
+    let mut schema = Schema::new();
+    schema
+        .required("name", ValueKind::String)
+        .optional("started", ValueKind::Datetime)
+        .required("server", ValueKind::Table)
+        .optional("server.host", ValueKind::String)
+        .optional("server.ports", ValueKind::Array)
+        .optional("server.tls.enabled", ValueKind::Boolean)
+        .optional("server.tls.ratio", ValueKind::Float)
+        .forbidden("server.legacy", "removed")
+        .optional("\"quoted key\".a", ValueKind::Integer)
+        .optional("extra", ValueKind::Table);
+    schema
+}
+
+#[test]
+fn from_schema() {
+    assert_eq!(
+        toml::skeleton::from_schema(&schema()),
+        "\
+name = \"\"
+# started = 1979-05-27T07:32:00Z
+# extra = {}
+
+[server]
+# host = \"\"
+# ports = []
+
+# [server.tls]
+# enabled = false
+# ratio = 0.0
+
+# [\"quoted key\"]
+# a = 0
+"
+    );
+}
+
+#[test]
+fn skeleton_matches_schema() {
+    let schema = schema();
+    let table: Table = toml::from_str(&toml::skeleton::from_schema(&schema)).unwrap();
+    schema.validate(&table).unwrap();
+
+    let uncommented = toml::skeleton::from_schema(&schema).replace("# ", "");
+    let table: Table = toml::from_str(&uncommented).unwrap();
+    schema.validate(&table).unwrap();
+}
+
+#[test]
+fn empty_schema() {
+    assert_eq!(toml::skeleton::from_schema(&Schema::new()), "");
+}
+
+#[derive(Serialize)]
+struct Config {
+    name: String,
+    motd: String,
+    ports: Vec<u16>,
+    log: Log,
+}
+
+#[derive(Serialize)]
+struct Log {
+    level: String,
+}
+
+#[test]
+fn from_value() {
+    let defaults = Config {
+        name: "app".to_string(),
+        motd: "hello\nworld".to_string(),
+        ports: vec![80, 443],
+        log: Log {
+            level: "info".to_string(),
+        },
+    };
+    let skeleton = toml::skeleton::from_value(&defaults).unwrap();
+    assert_eq!(
+        skeleton,
+        "\
+# name = \"app\"
+# motd = \"hello\\nworld\"
+# ports = [80, 443]
+
+# [log]
+# level = \"info\"
+"
+    );
+    assert!(toml::from_str::<Table>(&skeleton).unwrap().is_empty());
+}
@@ -0,0 +1,47 @@
+extern crate toml;
+
+use toml::stream::documents;
+
+#[test]
+fn splits_documents() {
+    let input = "a = 1\n+++\nb = 2\n+++   \r\n\n+++\nc = 3";
+    let docs = documents(input, "+++")
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert_eq!(docs.len(), 4);
+    assert_eq!(docs[0].table()["a"].as_integer(), Some(1));
+    assert_eq!(docs[1].table()["b"].as_integer(), Some(2));
+    assert!(docs[2].table().is_empty());
+    assert_eq!(docs[3].table()["c"].as_integer(), Some(3));
+
+    let spans = docs.iter().map(|d| d.span()).collect::<Vec<_>>();
+    assert_eq!(spans, [(0, 6), (10, 16), (24, 25), (29, 34)]);
+    assert_eq!(&input[spans[1].0..spans[1].1], "b = 2\n");
+}
+
+#[test]
+fn separator_must_be_whole_line() {
+    let input = "s = '''\n +++\n'''\nt = \"+++\"\n";
+    let docs = documents(input, "+++").collect::<Vec<_>>();
+    assert_eq!(docs.len(), 1);
+
+    let docs = documents("+++\na = 1\n+++\n", "+++").collect::<Vec<_>>();
+    assert_eq!(docs.len(), 3);
+    assert_eq!(docs[1].as_ref().unwrap().span(), (4, 10));
+
+    assert_eq!(documents("", "---").count(), 1);
+}
+
+#[test]
+fn errors_are_positioned_in_stream() {
+    let input = "a = 1\n---\nb = \n---\nc = 3\n";
+    let mut docs = documents(input, "---");
+    assert!(docs.next().unwrap().is_ok());
+    let err = docs.next().unwrap().unwrap_err();
+    assert_eq!(err.line_col(), Some((2, 4)));
+    assert_eq!(
+        docs.next().unwrap().unwrap().into_table()["c"].as_integer(),
+        Some(3)
+    );
+    assert!(docs.next().is_none());
+}
@@ -0,0 +1,86 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use toml::string_or_table::{Shorthand, StringOrTable};
+use toml::Value;
+
+#[derive(Debug, Default, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+struct Source {
+    url: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    branch: Option<String>,
+}
+
+impl Shorthand for Source {
+    fn from_shorthand(url: String) -> Source {
+        Source { url, branch: None }
+    }
+
+    fn shorthand(&self) -> Option<&str> {
+        match self.branch {
+            None => Some(&self.url),
+            Some(_) => None,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
+struct Config {
+    #[serde(with = "toml::string_or_table")]
+    main: Source,
+    sources: BTreeMap<String, StringOrTable<Source>>,
+}
+
+#[test]
+fn both_forms() {
+    let config: Config = toml::from_str(
+        r#"
+        main = { url = "a", branch = "dev" }
+
+        [sources]
+        x = "b"
+        y = { url = "c" }
+        "#,
+    )
+    .unwrap();
+    assert_eq!(config.main.branch.as_deref(), Some("dev"));
+    assert_eq!(
+        *config.sources["x"],
+        Source::from_shorthand("b".to_string())
+    );
+    assert_eq!(config.sources["y"].url, "c");
+
+    let out = toml::to_string(&config).unwrap();
+    assert_eq!(
+        out,
+        "[main]\nurl = \"a\"\nbranch = \"dev\"\n\n[sources]\nx = \"b\"\ny = \"c\"\n"
+    );
+    assert_eq!(toml::from_str::<Config>(&out).unwrap(), config);
+}
+
+#[test]
+fn from_value() {
+    let value: Value = toml::toml! {
+        one = "a"
+        two = { url = "b", branch = "main" }
+    };
+    let sources: BTreeMap<String, StringOrTable<Source>> = value.clone().try_into().unwrap();
+    assert_eq!(sources["one"].clone().into_inner().url, "a");
+    assert_eq!(sources["two"].branch.as_deref(), Some("main"));
+    assert_eq!(Value::try_from(&sources).unwrap(), value);
+}
+
+#[test]
+fn wrong_type() {
+    let err = toml::from_str::<BTreeMap<String, StringOrTable<Source>>>("x = 1").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "invalid type: integer `1`, expected a string or a table for key `x` at line 1 column 5"
+    );
+
+    let err =
+        toml::from_str::<BTreeMap<String, StringOrTable<Source>>>("x = { url = 1 }").unwrap_err();
+    assert!(err
+        .to_string()
+        .starts_with("invalid type: integer `1`, expected a string"));
+}
@@ -0,0 +1,135 @@
+extern crate serde;
+extern crate toml;
+
+use serde::Deserialize;
+use toml::de::DuplicateKeys;
+use toml::Value;
+
+macro_rules! bad {
+    ($toml:expr, $msg:expr) => {
+        match $toml.parse::<Value>() {
+            Ok(s) => panic!("parsed to: {:#?}", s),
+            Err(e) => assert_eq!(e.to_string(), $msg),
+        }
+    };
+}
+
+#[test]
+fn table_and_array() {
+    bad!(
+        "[[a]]\nx = 1\n[a]\n",
+        "redefinition of array of tables `a` as a table, already defined on line 1 at line 3 column 1"
+    );
+    bad!(
+        "[a]\n[[a]]\n",
+        "redefinition of table `a` as an array of tables, already defined on line 1 at line 2 column 1"
+    );
+    bad!(
+        "[a.b]\n[[a]]\n",
+        "redefinition of table `a` as an array of tables, already defined on line 1 at line 2 column 1"
+    );
+}
+
+#[test]
+fn table_twice() {
+    bad!(
+        "[a]\nx = 1\n\n[b]\n[a]\ny = 2\n",
+        "redefinition of table `a`, already defined on line 1 at line 5 column 1"
+    );
+    bad!(
+        "[a.b]\n[a]\n[a.b]\n",
+        "redefinition of table `a.b`, already defined on line 1 at line 3 column 1"
+    );
+}
+
+#[test]
+fn dotted_keys() {
+    bad!(
+        "a.b.x = 1\n[a.b]\n",
+        "redefinition of table `a.b`, already defined by dotted keys on line 1 at line 2 column 1"
+    );
+    bad!(
+        "[a]\nb.x = 1\n[[a.b]]\n",
+        "redefinition of table `a.b` as an array of tables, already defined by dotted keys on line 2 at line 3 column 1"
+    );
+    bad!(
+        "[a.b]\nx = 1\n[a]\nb.y = 2\n",
+        "redefinition of table `a.b` by dotted keys, already defined on line 1 at line 4 column 1"
+    );
+    bad!(
+        "a = {}\n[a.b]\n",
+        "redefinition of inline table `a`, already defined on line 1 at line 2 column 1"
+    );
+}
+
+#[test]
+fn array_elements() {
+    let value: Value = "[[a]]\n[a.b]\nx = 1\n[[a]]\n[a.b]\nx = 2\n"
+        .parse()
+        .unwrap();
+    assert_eq!(value["a"][1]["b"]["x"].as_integer(), Some(2));
+    bad!(
+        "[[a]]\n[a.b]\n[[a]]\n[a.b]\n[a.b]\n",
+        "redefinition of table `a.b`, already defined on line 4 at line 5 column 1"
+    );
+}
+
+#[test]
+fn merge() {
+    let s = "\
+[server]
+host = \"a\"
+tls.cert = \"a.pem\"
+
+[log]
+level = 1
+
+[server]
+port = 80
+tls.key = \"a.key\"
+";
+    let mut d = toml::Deserializer::new(s);
+    d.set_merge_reopened_tables(true);
+    let value = Value::deserialize(&mut d).unwrap();
+    let expected: Value = toml::from_str(
+        "[server]\n\
+         host = \"a\"\n\
+         port = 80\n\
+         tls = { cert = \"a.pem\", key = \"a.key\" }\n\
+         [log]\n\
+         level = 1\n",
+    )
+    .unwrap();
+    assert_eq!(value, expected);
+    let warnings = d
+        .warnings()
+        .iter()
+        .map(|w| w.to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        warnings,
+        ["table `server` is defined again, merged into its definition on line 1 at line 8 column 1"]
+    );
+}
+
+#[test]
+fn merge_duplicate_keys() {
+    let s = "[a]\nx = 1\n[a]\nx = 2\n";
+    let mut d = toml::Deserializer::new(s);
+    d.set_merge_reopened_tables(true);
+    assert!(Value::deserialize(&mut d).is_err());
+
+    let mut d = toml::Deserializer::new(s);
+    d.set_merge_reopened_tables(true);
+    d.set_duplicate_keys(DuplicateKeys::LastWins);
+    let value = Value::deserialize(&mut d).unwrap();
+    assert_eq!(value["a"]["x"].as_integer(), Some(2));
+    assert_eq!(d.warnings().len(), 2);
+}
+
+#[test]
+fn merge_keeps_other_errors() {
+    let mut d = toml::Deserializer::new("[a]\n[[a]]\n");
+    d.set_merge_reopened_tables(true);
+    assert!(Value::deserialize(&mut d).is_err());
+}
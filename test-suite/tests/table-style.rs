@@ -0,0 +1,160 @@
+extern crate serde;
+extern crate toml;
+
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+use toml::ser::{Error, Serializer, TableStyle};
+
+#[derive(Serialize)]
+struct Manifest {
+    package: Package,
+    dependencies: BTreeMap<String, Dependency>,
+}
+
+#[derive(Serialize)]
+struct Package {
+    name: String,
+    authors: Vec<String>,
+    metadata: Metadata,
+}
+
+#[derive(Serialize)]
+struct Metadata {
+    docs: Docs,
+    release: BTreeMap<String, String>,
+}
+
+#[derive(Serialize)]
+struct Docs {
+    features: Vec<String>,
+    targets: Vec<Target>,
+}
+
+#[derive(Serialize)]
+struct Target {
+    triple: String,
+}
+
+#[derive(Serialize)]
+struct Dependency {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    path: Option<String>,
+    version: String,
+}
+
+fn manifest() -> Manifest {
+    let mut dependencies = BTreeMap::new();
+    dependencies.insert(
+        "serde".to_string(),
+        Dependency {
+            path: None,
+            version: "1".to_string(),
+        },
+    );
+    dependencies.insert(
+        "local thing".to_string(),
+        Dependency {
+            path: Some("../local".to_string()),
+            version: "0.1".to_string(),
+        },
+    );
+    Manifest {
+        package: Package {
+            name: "demo".to_string(),
+            authors: vec!["A".to_string()],
+            metadata: Metadata {
+                docs: Docs {
+                    features: vec!["full".to_string()],
+                    targets: vec![Target {
+                        triple: "x86_64".to_string(),
+                    }],
+                },
+                release: BTreeMap::new(),
+            },
+        },
+        dependencies,
+    }
+}
+
+fn to_string(styles: &[(&str, TableStyle)]) -> Result<String, Error> {
+    let mut out = String::new();
+    let mut ser = Serializer::new(&mut out);
+    for &(path, style) in styles {
+        ser.table_style(path, style);
+    }
+    manifest().serialize(&mut ser)?;
+    Ok(out)
+}
+
+#[test]
+fn standard_by_default() {
+    let expected = "\
+[package]
+name = \"demo\"
+authors = [\"A\"]
+[package.metadata.docs]
+features = [\"full\"]
+
+[[package.metadata.docs.targets]]
+triple = \"x86_64\"
+
+[package.metadata.release]
+[dependencies.\"local thing\"]
+path = \"../local\"
+version = \"0.1\"
+
+[dependencies.serde]
+version = \"1\"
+";
+    assert_eq!(to_string(&[]).unwrap(), expected);
+    assert_eq!(
+        to_string(&[("package.metadata", TableStyle::Standard)]).unwrap(),
+        expected
+    );
+}
+
+#[test]
+fn inline_and_dotted() {
+    let out = to_string(&[
+        ("package.metadata", TableStyle::Dotted),
+        ("dependencies.serde", TableStyle::Inline),
+        ("dependencies.\"local thing\"", TableStyle::Inline),
+    ])
+    .unwrap();
+    assert_eq!(
+        out,
+        "\
+[package]
+name = \"demo\"
+authors = [\"A\"]
+metadata.docs.features = [\"full\"]
+metadata.docs.targets = [{ triple = \"x86_64\" }]
+metadata.release = {}
+
+[dependencies]
+\"local thing\" = { path = \"../local\", version = \"0.1\" }
+serde = { version = \"1\" }
+"
+    );
+    assert!(out.parse::<toml::Value>().is_ok());
+
+    let out = to_string(&[("package.metadata", TableStyle::Inline)]).unwrap();
+    assert!(out.starts_with(
+        "[package]\n\
+         name = \"demo\"\n\
+         authors = [\"A\"]\n\
+         metadata = { docs = { features = [\"full\"], targets = [{ triple = \"x86_64\" }] }, \
+         release = {} }\n"
+    ));
+}
+
+#[test]
+fn styled_tables_are_values_of_their_parent() {
+    // `package.metadata.docs` is written as a sub-table of `metadata`, so
+    // the inline `release` table after it cannot be placed.
+    assert!(matches!(
+        to_string(&[("package.metadata.release", TableStyle::Inline)]),
+        Err(Error::ValueAfterTable)
+    ));
+}
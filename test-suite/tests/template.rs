@@ -0,0 +1,88 @@
+extern crate toml;
+
+use toml::template::FailureKind;
+use toml::value::Table;
+use toml::Value;
+
+fn table(s: &str) -> Table {
+    toml::from_str(s).unwrap()
+}
+
+#[test]
+fn substitutes_placeholders() {
+    let template = table(
+        r#"
+        name = "{{ app.name }}-server"
+        port = "{{app.port}}"
+        tags = ["{{app.name}}", "static"]
+        started = "{{app.started}}"
+
+        [nested]
+        url = "http://{{host}}:{{app.port}}/{{ missing_braces"
+        "#,
+    );
+    let vars = table(
+        r#"
+        host = "example.com"
+        [app]
+        name = "demo"
+        port = 8080
+        started = 1979-05-27T07:32:00Z
+        "#,
+    );
+    let err = toml::render(&template, &vars).unwrap_err();
+    assert_eq!(err.failures().len(), 1);
+    assert_eq!(err.failures()[0].kind(), FailureKind::Unterminated);
+    assert_eq!(err.failures()[0].path().to_string(), "nested.url");
+
+    let mut template = template;
+    template["nested"].as_table_mut().unwrap().insert(
+        "url".to_string(),
+        Value::from("http://{{host}}:{{app.port}}/"),
+    );
+    let out = toml::render(&template, &vars).unwrap();
+    assert_eq!(out["name"].as_str(), Some("demo-server"));
+    assert_eq!(out["port"].as_integer(), Some(8080));
+    assert_eq!(out["tags"][0].as_str(), Some("demo"));
+    assert!(out["started"].is_datetime());
+    assert_eq!(
+        out["nested"]["url"].as_str(),
+        Some("http://example.com:8080/")
+    );
+}
+
+#[test]
+fn reports_every_unresolved_placeholder() {
+    let template = table(
+        r#"
+        a = "{{nope}}"
+        b = "x{{app}}y"
+        c = ["{{also.nope}}"]
+        "#,
+    );
+    let vars = table("[app]\nname = 'x'");
+    let err = toml::render(&template, &vars).unwrap_err();
+    let failures = err
+        .failures()
+        .iter()
+        .map(|f| (f.path().to_string(), f.placeholder().to_string(), f.kind()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        failures,
+        vec![
+            ("a".to_string(), "nope".to_string(), FailureKind::Unresolved),
+            ("b".to_string(), "app".to_string(), FailureKind::NotScalar),
+            (
+                "c[0]".to_string(),
+                "also.nope".to_string(),
+                FailureKind::Unresolved
+            ),
+        ]
+    );
+    assert_eq!(
+        err.to_string(),
+        "unresolved placeholder `{{nope}}` in `a`; \
+         placeholder does not name a scalar value `{{app}}` in `b`; \
+         unresolved placeholder `{{also.nope}}` in `c[0]`"
+    );
+}
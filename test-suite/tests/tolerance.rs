@@ -0,0 +1,73 @@
+extern crate serde;
+extern crate toml;
+
+use serde::Deserialize;
+use toml::Value;
+
+const SLOPPY: &str = "\
+verbose
+dep = { version = \"1\", optional = true, }
+ports = [80, 443,]
+
+[features]
+default # enabled by default
+extra.fast
+";
+
+#[test]
+fn strict_by_default() {
+    let err = toml::from_str::<Value>("dep = { a = 1, }").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "expected a table key, found a right brace at line 1 column 16"
+    );
+    let err = toml::from_str::<Value>("verbose\n").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "expected an equals, found a newline at line 1 column 8"
+    );
+}
+
+#[test]
+fn tolerant() {
+    let mut d = toml::Deserializer::new(SLOPPY);
+    d.set_allow_trailing_commas(true);
+    d.set_allow_bare_boolean_keys(true);
+    let value = Value::deserialize(&mut d).unwrap();
+    let expected: Value = toml::from_str(
+        "verbose = true\n\
+         dep = { version = \"1\", optional = true }\n\
+         ports = [80, 443]\n\
+         [features]\n\
+         default = true\n\
+         extra.fast = true\n",
+    )
+    .unwrap();
+    assert_eq!(value, expected);
+
+    let warnings = d
+        .warnings()
+        .iter()
+        .map(|w| w.to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        warnings,
+        [
+            "key `verbose` has no value, read as `verbose = true` at line 1 column 1",
+            "trailing comma in inline table at line 2 column 39",
+            "key `default` has no value, read as `default = true` at line 6 column 1",
+            "key `extra.fast` has no value, read as `extra.fast = true` at line 7 column 1",
+        ]
+    );
+}
+
+#[test]
+fn tolerant_still_rejects_garbage() {
+    let mut d = toml::Deserializer::new("dep = { a = 1,, }\n");
+    d.set_allow_trailing_commas(true);
+    assert!(Value::deserialize(&mut d).is_err());
+
+    let mut d = toml::Deserializer::new("verbose yes\n");
+    d.set_allow_bare_boolean_keys(true);
+    assert!(Value::deserialize(&mut d).is_err());
+}
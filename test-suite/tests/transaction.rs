@@ -0,0 +1,51 @@
+use toml::value::Table;
+use toml::Value;
+
+fn table(s: &str) -> Table {
+    toml::from_str(s).unwrap()
+}
+
+#[test]
+fn commit_keeps_changes() {
+    let mut config = table("a = 1\n[server]\nport = 80\n");
+    let mut tx = config.transaction();
+    tx.insert("b".to_string(), Value::from(2));
+    tx.get_mut("server").unwrap()["port"] = Value::from(8080);
+    tx.remove("a");
+    assert_eq!(tx.changed_keys(), ["b", "server", "a"]);
+    assert_eq!(tx.original(), &table("a = 1\n[server]\nport = 80\n"));
+    tx.commit();
+    assert_eq!(config, table("b = 2\n[server]\nport = 8080\n"));
+}
+
+#[test]
+fn rollback_and_drop_undo_changes() {
+    let src = "a = 1\n[server]\nport = 80\n";
+    let mut config = table(src);
+
+    let mut tx = config.transaction();
+    tx.clear();
+    tx.rollback();
+    assert_eq!(config, table(src));
+
+    {
+        let mut tx = config.transaction();
+        tx.get_mut("server")
+            .and_then(Value::as_table_mut)
+            .unwrap()
+            .insert("host".to_string(), Value::from("x"));
+        tx.insert("a".to_string(), Value::from(2));
+    }
+    assert_eq!(config, table(src));
+}
+
+#[test]
+fn reads_do_not_copy() {
+    let mut config = table("a = 1\n");
+    let tx = config.transaction();
+    assert_eq!(tx["a"].as_integer(), Some(1));
+    assert!(tx.changed_keys().is_empty());
+    assert_eq!(tx.original().len(), 1);
+    drop(tx);
+    assert_eq!(config, table("a = 1\n"));
+}
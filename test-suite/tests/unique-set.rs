@@ -0,0 +1,64 @@
+use serde_derive::Deserialize;
+use std::collections::{BTreeSet, HashSet};
+
+#[derive(Debug, Deserialize)]
+struct Config {
+    #[serde(deserialize_with = "toml::de::unique_set")]
+    features: BTreeSet<String>,
+    #[serde(default, deserialize_with = "toml::de::unique_set")]
+    ports: HashSet<u16>,
+}
+
+#[test]
+fn distinct_elements() {
+    let config: Config = toml::from_str(
+        r#"
+features = ["serde", "std"]
+ports = [80, 443]
+"#,
+    )
+    .unwrap();
+    assert_eq!(
+        config.features,
+        ["serde", "std"].iter().map(|s| s.to_string()).collect()
+    );
+    assert_eq!(config.ports, [80, 443].iter().cloned().collect());
+}
+
+#[test]
+fn duplicate_string() {
+    let err = toml::from_str::<Config>(r#"features = ["serde", "std", "serde"]"#).unwrap_err();
+    assert!(err.is_duplicate_set_element());
+    assert!(
+        err.to_string()
+            .contains("duplicate set element `\"serde\"` at index 2"),
+        "{}",
+        err
+    );
+}
+
+#[test]
+fn duplicate_integer() {
+    let err = toml::from_str::<Config>(
+        r#"
+features = []
+ports = [80, 443, 8080, 443]
+"#,
+    )
+    .unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("duplicate set element `443` at index 3"),
+        "{}",
+        err
+    );
+}
+
+#[test]
+fn plain_sets_still_deduplicate() {
+    let set: BTreeSet<String> = toml::from_str::<toml::Value>("x = ['a', 'a']").unwrap()["x"]
+        .clone()
+        .try_into()
+        .unwrap();
+    assert_eq!(set.len(), 1);
+}
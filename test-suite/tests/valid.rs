@@ -31,6 +31,7 @@ fn to_json(toml: toml::Value) -> Json {
         ),
         Toml::Boolean(b) => doit("bool", Json::String(format!("{}", b))),
         Toml::Datetime(s) => doit("datetime", Json::String(s.to_string())),
+        Toml::RawNumber(s) => doit("number", Json::String(s.into_string())),
         Toml::Array(arr) => {
             let is_table = match arr.first() {
                 Some(&Toml::Table(..)) => true,
@@ -0,0 +1,99 @@
+use serde::Deserializer;
+use serde_derive::{Deserialize, Serialize};
+use toml::validated::{Identifier, StringRule, Url, Validated};
+
+enum Semver {}
+
+impl StringRule for Semver {
+    const EXPECTED: &'static str = "a version such as 1.2.3";
+
+    fn check(s: &str) -> Result<(), String> {
+        let parts: Vec<&str> = s.split('.').collect();
+        if parts.len() != 3 {
+            return Err(format!("it has {} parts", parts.len()));
+        }
+        match parts.iter().find(|p| p.parse::<u64>().is_err()) {
+            Some(p) => Err(format!("`{}` is not a number", p)),
+            None => Ok(()),
+        }
+    }
+}
+
+fn version<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    toml::validated::deserialize::<D, Semver>(deserializer)
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+struct Package {
+    name: Validated<Identifier>,
+    #[serde(deserialize_with = "version")]
+    version: String,
+    homepage: Option<Validated<Url>>,
+}
+
+#[test]
+fn accepts_and_round_trips() {
+    let input = "\
+name = \"my-crate\"
+version = \"1.2.3\"
+homepage = \"https://example.com/my-crate\"
+";
+    let package: Package = toml::from_str(input).unwrap();
+    assert_eq!(&*package.name, "my-crate");
+    assert_eq!(package.version, "1.2.3");
+    assert_eq!(
+        package.homepage.as_ref().map(|u| u.as_str()),
+        Some("https://example.com/my-crate")
+    );
+    assert_eq!(toml::to_string(&package).unwrap(), input);
+}
+
+#[test]
+fn errors_point_at_string() {
+    let err = toml::from_str::<Package>("name = '2fast'\nversion = '1.0.0'\n").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "invalid string \"2fast\": it starts with `2`, expected an identifier \
+         for key `name` at line 1 column 8"
+    );
+
+    let err = toml::from_str::<Package>("name = 'a'\nversion = '1.x.0'\n").unwrap_err();
+    assert_eq!(err.line_col(), Some((1, 10)));
+    assert!(
+        err.to_string()
+            .starts_with("invalid string \"1.x.0\": `x` is not a number"),
+        "{}",
+        err
+    );
+
+    let err =
+        toml::from_str::<Package>("name = 'a'\nversion = '1.0.0'\nhomepage = 'example.com'\n")
+            .unwrap_err();
+    assert!(err.to_string().contains("it has no scheme"), "{}", err);
+}
+
+#[test]
+fn rules() {
+    assert!(Identifier::check("_a-b9").is_ok());
+    assert_eq!(Identifier::check("").unwrap_err(), "it is empty");
+    assert_eq!(Identifier::check("a b").unwrap_err(), "it contains ` `");
+    assert!(Url::check("mailto:a@example.com").is_ok());
+    assert_eq!(
+        Url::check("1http://x").unwrap_err(),
+        "`1http` is not a scheme"
+    );
+    assert_eq!(
+        Url::check("http:").unwrap_err(),
+        "it has nothing after the scheme"
+    );
+    assert_eq!(Url::check("http://a b").unwrap_err(), "it contains ' '");
+    assert!(Validated::<Identifier>::new("ok".to_string()).is_ok());
+    assert!(Validated::<Identifier>::new("-no".to_string()).is_err());
+
+    let err = toml::from_str::<Package>("name = 1\nversion = '1.0.0'\n").unwrap_err();
+    assert!(
+        err.to_string().contains("expected an identifier"),
+        "{}",
+        err
+    );
+}
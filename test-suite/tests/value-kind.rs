@@ -0,0 +1,51 @@
+extern crate toml;
+
+use toml::value::ValueKind;
+use toml::Value;
+
+#[test]
+fn bulk_extraction() {
+    let value: Value = toml::from_str(
+        r#"
+        names = ["a", "b"]
+        ports = [80, 443]
+        ratios = [0.5, 1.0]
+        flags = [true, false]
+        dates = [1979-05-27, 1980-01-01]
+        mixed = [1, "x"]
+        empty = []
+        [[servers]]
+        name = "a"
+        "#,
+    )
+    .unwrap();
+
+    assert_eq!(value["names"].as_str_array(), Some(vec!["a", "b"]));
+    assert_eq!(value["ports"].as_int_array(), Some(vec![80, 443]));
+    assert_eq!(value["ratios"].as_float_array(), Some(vec![0.5, 1.0]));
+    assert_eq!(value["flags"].as_bool_array(), Some(vec![true, false]));
+    assert_eq!(value["dates"].as_datetime_array().unwrap().len(), 2);
+    assert_eq!(
+        value["servers"].as_table_array().unwrap()[0]["name"].as_str(),
+        Some("a")
+    );
+    assert_eq!(value["empty"].as_str_array(), Some(vec![]));
+
+    assert_eq!(value["mixed"].as_int_array(), None);
+    assert_eq!(value["ports"].as_str_array(), None);
+    assert_eq!(value["names"][0].as_str_array(), None);
+}
+
+#[test]
+fn element_type() {
+    let value: Value =
+        toml::from_str("a = [1, 2]\nb = [[1], ['x']]\nc = [1, 2.0]\nd = []").unwrap();
+    assert_eq!(value["a"].element_type(), Some(ValueKind::Integer));
+    assert_eq!(value["b"].element_type(), Some(ValueKind::Array));
+    assert_eq!(value["c"].element_type(), None);
+    assert_eq!(value["d"].element_type(), None);
+    assert_eq!(value["a"][0].element_type(), None);
+
+    assert_eq!(value.kind(), ValueKind::Table);
+    assert_eq!(ValueKind::Datetime.to_string(), "datetime");
+}
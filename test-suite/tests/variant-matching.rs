@@ -0,0 +1,105 @@
+extern crate serde;
+extern crate toml;
+
+use serde::Deserialize;
+use toml::de::VariantMatching;
+
+#[derive(Debug, PartialEq, Deserialize)]
+enum Level {
+    Debug,
+    Info,
+    #[serde(rename = "dry_run")]
+    DryRun,
+    HTTPProxy,
+}
+
+#[derive(Debug, PartialEq, Deserialize)]
+struct Config {
+    level: Level,
+    #[serde(default)]
+    others: Vec<Level>,
+}
+
+fn read(input: &str, matching: VariantMatching) -> Result<Config, toml::de::Error> {
+    let mut de = toml::Deserializer::new(input);
+    de.set_variant_matching(matching);
+    Config::deserialize(&mut de)
+}
+
+#[test]
+fn exact_by_default() {
+    let config: Config = toml::from_str("level = 'Debug'").unwrap();
+    assert_eq!(config.level, Level::Debug);
+    let err = toml::from_str::<Config>("level = 'debug'").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "unknown variant `debug`, expected one of `Debug`, `Info`, `dry_run`, `HTTPProxy` \
+         for key `level` at line 1 column 9"
+    );
+}
+
+#[test]
+fn case_insensitive() {
+    let config = read(
+        "level = 'DEBUG'\nothers = ['info', 'Dry_Run', 'httpproxy']",
+        VariantMatching::CaseInsensitive,
+    )
+    .unwrap();
+    assert_eq!(config.level, Level::Debug);
+    assert_eq!(
+        config.others,
+        [Level::Info, Level::DryRun, Level::HTTPProxy]
+    );
+
+    let err = read("level = 'dry-run'", VariantMatching::CaseInsensitive).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "unknown variant `dry-run`, expected one of `Debug`, `Info`, `dry_run`, `HTTPProxy` \
+         for key `level` at line 1 column 9"
+    );
+}
+
+#[test]
+fn kebab_case() {
+    let config = read(
+        "level = 'dry-run'\nothers = ['info', 'Debug', 'httpproxy']",
+        VariantMatching::KebabCase,
+    )
+    .unwrap();
+    assert_eq!(config.level, Level::DryRun);
+    assert_eq!(config.others, [Level::Info, Level::Debug, Level::HTTPProxy]);
+
+    let err = read("level = 'Dry-Run'", VariantMatching::KebabCase).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "unknown variant `Dry-Run`, expected one of `debug`, `info`, `dry-run`, `httpproxy` \
+         for key `level` at line 1 column 9"
+    );
+}
+
+#[test]
+fn top_level_enum() {
+    let mut de = toml::Deserializer::new("'info'");
+    de.set_variant_matching(VariantMatching::CaseInsensitive);
+    assert_eq!(Level::deserialize(&mut de).unwrap(), Level::Info);
+}
+
+#[test]
+fn single_variant() {
+    #[derive(Debug, Deserialize)]
+    enum Only {
+        Thing,
+    }
+    #[derive(Debug, Deserialize)]
+    struct Holder {
+        #[allow(dead_code)]
+        only: Only,
+    }
+    let mut de = toml::Deserializer::new("only = 'other'");
+    de.set_variant_matching(VariantMatching::KebabCase);
+    let err = Holder::deserialize(&mut de).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "unknown variant `other`, expected `thing` for key `only` at line 1 column 8"
+    );
+}
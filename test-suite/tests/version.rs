@@ -0,0 +1,126 @@
+use serde_derive::{Deserialize, Serialize};
+use toml::ext::{Version, VersionReq};
+
+fn v(s: &str) -> Version {
+    s.parse().unwrap()
+}
+
+fn req(s: &str) -> VersionReq {
+    s.parse().unwrap()
+}
+
+#[test]
+fn ordering() {
+    let ordered = [
+        "1.0.0-alpha",
+        "1.0.0-alpha.1",
+        "1.0.0-alpha.beta",
+        "1.0.0-beta",
+        "1.0.0-beta.2",
+        "1.0.0-beta.11",
+        "1.0.0-rc.1",
+        "1.0.0",
+        "1.0.1",
+        "1.10.0",
+        "2.0.0",
+    ];
+    for pair in ordered.windows(2) {
+        assert!(v(pair[0]) < v(pair[1]), "{} < {}", pair[0], pair[1]);
+    }
+    assert_ne!(v("1.0.0+a"), v("1.0.0+b"));
+    assert_eq!(v("1.2.3-rc.1+build.5").to_string(), "1.2.3-rc.1+build.5");
+    assert_eq!(v("1.2.3"), Version::new(1, 2, 3));
+}
+
+#[test]
+fn parse_errors() {
+    for &(text, message) in &[
+        ("1.2", "`1.2` is not a version: expected major.minor.patch"),
+        ("1.2.3.4", "`1.2.3.4` is not a version: expected major.minor.patch"),
+        ("01.2.3", "`01.2.3` is not a version: numbers must not have leading zeros"),
+        ("1.2.3-", "`1.2.3-` is not a version: empty pre-release identifier"),
+        ("1.2.3-01", "`1.2.3-01` is not a version: numbers must not have leading zeros"),
+        ("1.2.3+a_b", "`1.2.3+a_b` is not a version: identifiers may only contain ASCII letters, digits and `-`"),
+    ] {
+        assert_eq!(Version::parse(text).unwrap_err().to_string(), message);
+    }
+    assert_eq!(
+        VersionReq::parse("^1.*").unwrap_err().to_string(),
+        "`^1.*` is not a version requirement: a wildcard cannot follow an operator"
+    );
+    assert!(VersionReq::parse("1.2-rc.1").is_err());
+    assert!(VersionReq::parse(">=1.0,").is_err());
+}
+
+#[test]
+fn requirements() {
+    let cases: &[(&str, &[&str], &[&str])] = &[
+        ("1.2", &["1.2.0", "1.9.9"], &["1.1.9", "2.0.0"]),
+        ("^1.2.3", &["1.2.3", "1.3.0"], &["1.2.2", "2.0.0"]),
+        ("^0.2.3", &["0.2.3", "0.2.9"], &["0.3.0", "0.2.2"]),
+        ("^0.0.3", &["0.0.3"], &["0.0.4"]),
+        ("^0.2", &["0.2.0", "0.2.7"], &["0.3.0"]),
+        ("~1.2.3", &["1.2.3", "1.2.9"], &["1.3.0"]),
+        ("~1", &["1.0.0", "1.9.0"], &["2.0.0"]),
+        ("=1.2.3", &["1.2.3"], &["1.2.4"]),
+        (">=1.0, <2.0", &["1.0.0", "1.99.0"], &["0.9.0", "2.0.0"]),
+        ("1.2.*", &["1.2.0", "1.2.5"], &["1.3.0"]),
+        ("*", &["0.0.1", "9.0.0"], &["1.0.0-rc.1"]),
+        (">1.2", &["1.3.0"], &["1.2.9"]),
+        ("<=1.2", &["1.2.9"], &["1.3.0"]),
+        (
+            "^1.0.0-rc.1",
+            &["1.0.0-rc.2", "1.0.0", "1.5.0"],
+            &["1.0.0-beta", "1.1.0-rc.1"],
+        ),
+    ];
+    for &(text, matching, failing) in cases {
+        let r = req(text);
+        for m in matching {
+            assert!(r.matches(&v(m)), "{} should match {}", text, m);
+        }
+        for f in failing {
+            assert!(!r.matches(&v(f)), "{} should not match {}", text, f);
+        }
+    }
+    assert_eq!(req(">= 1.0 ,<2").to_string(), ">=1.0, <2");
+    assert_eq!(req("1.x").to_string(), "1.*");
+    assert_eq!(req("1.2").to_string(), "^1.2");
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+struct Manifest {
+    version: Version,
+    dependencies: std::collections::BTreeMap<String, VersionReq>,
+}
+
+#[test]
+fn serde() {
+    let input = "\
+version = \"0.3.1-beta.1\"
+
+[dependencies]
+serde = \"^1.0\"
+toml = \"~0.5\"
+";
+    let manifest: Manifest = toml::from_str(input).unwrap();
+    assert!(manifest.version.is_prerelease());
+    assert!(manifest.dependencies["serde"].matches(&v("1.0.136")));
+    assert_eq!(toml::to_string(&manifest).unwrap(), input);
+
+    let err = toml::from_str::<Manifest>("version = '1.0.0'\n[dependencies]\nserde = '>> 1'\n")
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "`>> 1` is not a version requirement: expected major.minor.patch \
+         for key `dependencies.serde` at line 3 column 9"
+    );
+
+    let err = toml::from_str::<Manifest>("version = 1\n").unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("expected a version string such as \"1.2.3\""),
+        "{}",
+        err
+    );
+}
@@ -0,0 +1,120 @@
+use std::sync::{Arc, Mutex};
+
+use toml::path::LookupErrorKind;
+use toml::watch::WatchedTable;
+use toml::Value;
+
+type Log = Arc<Mutex<Vec<String>>>;
+
+fn watched(src: &str) -> WatchedTable {
+    WatchedTable::new(toml::from_str(src).unwrap())
+}
+
+fn record(table: &mut WatchedTable, path: &str) -> Log {
+    let log = Log::default();
+    let events = log.clone();
+    let name = path.to_string();
+    table.watch(path, move |path, old, new| {
+        let show = |v: Option<&Value>| v.map_or("-".to_string(), |v| v.to_string());
+        events
+            .lock()
+            .unwrap()
+            .push(format!("{} {}: {} -> {}", name, path, show(old), show(new)));
+    });
+    log
+}
+
+fn take(log: &Log) -> Vec<String> {
+    std::mem::take(&mut *log.lock().unwrap())
+}
+
+#[test]
+fn insert_remove_update() {
+    let mut config = watched("[server]\nport = 80\nhosts = ['a', 'b']\n");
+    let server = record(&mut config, "server");
+    let port = record(&mut config, "server.port");
+
+    assert_eq!(
+        config.insert("server.port", Value::from(8080)),
+        Ok(Some(Value::from(80)))
+    );
+    assert_eq!(
+        config.insert("server.port", Value::from(8080)),
+        Ok(Some(Value::from(8080)))
+    );
+    config.insert("server.tls.cert", Value::from("c")).unwrap();
+    config
+        .update("server.hosts", |v| {
+            v.as_array_mut().unwrap().push(Value::from("c"));
+        })
+        .unwrap();
+    assert_eq!(config.remove("server.hosts[0]"), Some(Value::from("a")));
+    assert_eq!(config.remove("server.missing"), None);
+
+    assert_eq!(
+        take(&server),
+        [
+            "server server.port: 80 -> 8080",
+            "server server.tls.cert: - -> \"c\"",
+            "server server.hosts: [\"a\", \"b\"] -> [\"a\", \"b\", \"c\"]",
+            "server server.hosts: [\"a\", \"b\", \"c\"] -> [\"b\", \"c\"]",
+        ]
+    );
+    assert_eq!(take(&port), ["server.port server.port: 80 -> 8080"]);
+
+    // Removing a table reports the watched values inside it.
+    config.remove("server");
+    assert_eq!(take(&port), ["server.port server.port: 8080 -> -"]);
+    assert_eq!(take(&server).len(), 1);
+    assert!(config.is_empty());
+}
+
+#[test]
+fn replace_reports_watched_changes_only() {
+    let mut config = watched("[log]\nlevel = 'info'\n[pool]\nsize = 4\n");
+    let level = record(&mut config, "log.level");
+    let size = record(&mut config, "pool.size");
+    let all = record(&mut config, "");
+
+    config.replace(toml::from_str("[log]\nlevel = 'info'\n[pool]\nsize = 8\n").unwrap());
+    assert!(take(&level).is_empty());
+    assert_eq!(take(&size), ["pool.size pool.size: 4 -> 8"]);
+    assert_eq!(take(&all).len(), 1);
+
+    config.replace(config.table().clone());
+    assert!(take(&all).is_empty());
+
+    config
+        .update("", |root| {
+            root.as_table_mut().unwrap().remove("log");
+        })
+        .unwrap();
+    assert_eq!(take(&level), ["log.level log.level: \"info\" -> -"]);
+}
+
+#[test]
+fn unwatch_and_errors() {
+    let mut config = watched("port = 80\nlist = [1]\n");
+    let log = Log::default();
+    let events = log.clone();
+    let id = config.watch("port", move |path, _, _| {
+        events.lock().unwrap().push(path.to_string());
+    });
+    config.insert("port", Value::from(1)).unwrap();
+    assert!(config.unwatch(id));
+    assert!(!config.unwatch(id));
+    config.insert("port", Value::from(2)).unwrap();
+    assert_eq!(take(&log), ["port"]);
+
+    let err = config.insert("port.x", Value::from(1)).unwrap_err();
+    assert_eq!(err.kind(), LookupErrorKind::WrongType("integer"));
+    let err = config.insert("list[3]", Value::from(1)).unwrap_err();
+    assert_eq!(err.kind(), LookupErrorKind::Missing);
+    assert!(config.update("missing", |_| {}).is_err());
+    assert!(config.insert("", Value::from(1)).is_err());
+    let err = config.insert("x.y[0].z", Value::from(1)).unwrap_err();
+    assert_eq!(err.kind(), LookupErrorKind::WrongType("table"));
+    assert!(config.get("x").is_none());
+    assert_eq!(config["port"].as_integer(), Some(2));
+    assert_eq!(config.into_inner().len(), 2);
+}
@@ -0,0 +1,168 @@
+extern crate toml;
+
+use toml::workspace::Workspace;
+use toml::Value;
+
+const ROOT: &str = "\
+[workspace]
+members = ['app', 'lib']
+
+[workspace.package]
+version = '1.2.0'   # released together
+edition = '2018'
+
+[workspace.dependencies]
+serde = { version = '1.0', features = ['std'] }
+log = '0.4'
+";
+
+const APP: &str = "\
+[package]
+name = 'app'
+version.workspace = true
+edition = '2021'
+
+[dependencies]
+serde = { workspace = true, features = ['derive'], optional = true }
+log.workspace = true
+";
+
+fn workspace() -> Workspace {
+    let mut workspace = Workspace::new("Cargo.toml", ROOT.parse().unwrap());
+    assert!(workspace
+        .add_member("app/Cargo.toml", APP.parse().unwrap())
+        .is_none());
+    workspace
+}
+
+#[test]
+fn resolve_inherited_values() {
+    let workspace = workspace();
+    let app = workspace.resolve("app/Cargo.toml").unwrap();
+    assert_eq!(app["package"]["version"].as_str(), Some("1.2.0"));
+    assert_eq!(app["package"]["edition"].as_str(), Some("2021"));
+
+    let serde = &app["dependencies"]["serde"];
+    assert_eq!(serde["version"].as_str(), Some("1.0"));
+    assert_eq!(
+        serde["features"],
+        Value::Array(vec![Value::from("std"), Value::from("derive")])
+    );
+    assert_eq!(serde["optional"].as_bool(), Some(true));
+    assert_eq!(serde.get("workspace"), None);
+    assert_eq!(app["dependencies"]["log"]["version"].as_str(), Some("0.4"));
+
+    let root = workspace.resolve("Cargo.toml").unwrap();
+    assert_eq!(&root, workspace.root().as_table());
+
+    let err = workspace.resolve("lib/Cargo.toml").unwrap_err();
+    assert_eq!(err.to_string(), "no document is named `lib/Cargo.toml`");
+}
+
+#[test]
+fn resolve_errors() {
+    let mut workspace = workspace();
+    workspace.add_member(
+        "lib/Cargo.toml",
+        "[package]\nlicense.workspace = true\n".parse().unwrap(),
+    );
+    let err = workspace.resolve("lib/Cargo.toml").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "`package.license` in `lib/Cargo.toml` inherits `workspace.package.license`, \
+         which `Cargo.toml` does not define"
+    );
+
+    let old = workspace.add_member(
+        "lib/Cargo.toml",
+        "[package]\nedition = { workspace = true, x = 1 }\n"
+            .parse()
+            .unwrap(),
+    );
+    assert!(old.is_some());
+    let err = workspace.resolve("lib/Cargo.toml").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "`package.edition` in `lib/Cargo.toml` sets `x` next to `workspace = true`"
+    );
+}
+
+#[test]
+fn definitions() {
+    let workspace = workspace();
+    let app = "app/Cargo.toml";
+    let def = |path: &str| {
+        workspace
+            .definition(app, path)
+            .map(|(name, path)| format!("{}:{}", name, path))
+    };
+    assert_eq!(
+        def("package.name").as_deref(),
+        Some("app/Cargo.toml:package.name")
+    );
+    assert_eq!(
+        def("package.version").as_deref(),
+        Some("Cargo.toml:workspace.package.version")
+    );
+    assert_eq!(
+        def("dependencies.serde.version").as_deref(),
+        Some("Cargo.toml:workspace.dependencies.serde.version")
+    );
+    assert_eq!(
+        def("dependencies.serde.optional").as_deref(),
+        Some("app/Cargo.toml:dependencies.serde.optional")
+    );
+    assert_eq!(
+        def("dependencies.log.version").as_deref(),
+        Some("Cargo.toml:workspace.dependencies.log")
+    );
+    assert_eq!(def("dependencies.log.features"), None);
+    assert_eq!(def("package.license"), None);
+    assert_eq!(workspace.definition("lib/Cargo.toml", "package"), None);
+}
+
+#[test]
+fn writes_go_to_the_defining_document() {
+    let mut workspace = workspace();
+    let app = "app/Cargo.toml";
+    assert_eq!(workspace.modified().count(), 0);
+
+    let written = workspace
+        .set(app, "package.version", Value::from("1.3.0"))
+        .unwrap();
+    assert_eq!(written, "Cargo.toml");
+    let written = workspace
+        .set(app, "dependencies.log.version", Value::from("0.4.17"))
+        .unwrap();
+    assert_eq!(written, "Cargo.toml");
+    let written = workspace
+        .set(app, "dependencies.serde.optional", Value::from(false))
+        .unwrap();
+    assert_eq!(written, app);
+
+    assert_eq!(
+        workspace.root().to_string(),
+        ROOT.replace("'1.2.0'", "\"1.3.0\"")
+            .replace("'0.4'", "\"0.4.17\"")
+    );
+    assert_eq!(
+        workspace.document(app).unwrap().to_string(),
+        APP.replace("optional = true", "optional = false")
+    );
+    let modified = workspace
+        .modified()
+        .map(|(name, _)| name)
+        .collect::<Vec<_>>();
+    assert_eq!(modified, ["Cargo.toml", app]);
+
+    let resolved = workspace.resolve(app).unwrap();
+    assert_eq!(resolved["package"]["version"].as_str(), Some("1.3.0"));
+
+    let err = workspace
+        .set(app, "package.license", Value::from("MIT"))
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "`package.license` is not in `app/Cargo.toml`"
+    );
+}
@@ -0,0 +1,151 @@
+#![cfg(feature = "cargo")]
+
+extern crate toml;
+
+use toml::cargo::{Dependency, DependencyDetail, Inheritable, Manifest};
+
+#[test]
+fn package_and_dependencies() {
+    let manifest: Manifest = r#"
+        [package]
+        name = "demo"
+        version.workspace = true
+        edition = "2021"
+        authors = ["A <a@example.com>"]
+        keywords = { workspace = true }
+
+        [package.metadata.docs.rs]
+        all-features = true
+
+        [lib]
+        name = "demo"
+
+        [dependencies]
+        serde = "1.0"
+        rand = { version = "0.8", optional = true, default_features = false }
+        log.workspace = true
+
+        [dependencies.git-dep]
+        git = "https://example.com/repo"
+        branch = "main"
+        package = "real-name"
+
+        [dev-dependencies]
+        tempfile = "3"
+
+        [target.'cfg(unix)'.dependencies]
+        libc = { version = "0.2", features = ["extra_traits"] }
+
+        [features]
+        default = []
+        fast = ["rand", "serde/std"]
+    "#
+    .parse()
+    .unwrap();
+
+    let package = manifest.package.unwrap();
+    assert_eq!(package.name, "demo");
+    assert_eq!(package.version, Some(Inheritable::Workspace));
+    assert!(package.keywords.unwrap().is_inherited());
+    assert_eq!(
+        package.edition.as_ref().and_then(|e| e.value()),
+        Some(&"2021".to_string())
+    );
+    assert_eq!(
+        package.authors,
+        Some(Inheritable::Value(vec!["A <a@example.com>".to_string()]))
+    );
+    assert_eq!(package.description, None);
+    let metadata = toml::Value::Table(package.metadata.unwrap());
+    assert_eq!(metadata["docs"]["rs"]["all-features"].as_bool(), Some(true));
+
+    let deps = &manifest.dependencies;
+    assert_eq!(deps["serde"], Dependency::Simple("1.0".to_string()));
+    assert!(!deps["serde"].is_optional());
+    assert!(deps["rand"].is_optional());
+    assert_eq!(deps["rand"].detail().default_features, Some(false));
+    assert!(deps["log"].detail().workspace);
+    assert_eq!(deps["log"].version(), None);
+    assert_eq!(
+        deps["git-dep"],
+        Dependency::Detailed(DependencyDetail {
+            git: Some("https://example.com/repo".to_string()),
+            branch: Some("main".to_string()),
+            package: Some("real-name".to_string()),
+            ..DependencyDetail::default()
+        })
+    );
+    assert_eq!(manifest.dev_dependencies["tempfile"].version(), Some("3"));
+    assert_eq!(
+        manifest.target["cfg(unix)"].dependencies["libc"]
+            .detail()
+            .features,
+        ["extra_traits"]
+    );
+    assert!(manifest.build_dependencies.is_empty());
+    assert_eq!(manifest.features["fast"], ["rand", "serde/std"]);
+    assert!(manifest.features["default"].is_empty());
+    assert_eq!(manifest.workspace, None);
+}
+
+#[test]
+fn virtual_workspace() {
+    let manifest: Manifest = r#"
+        [workspace]
+        members = ["crates/*"]
+        exclude = ["old"]
+        resolver = "2"
+
+        [workspace.package]
+        version = "1.2.3"
+
+        [workspace.dependencies]
+        serde = { version = "1", features = ["derive"] }
+    "#
+    .parse()
+    .unwrap();
+
+    assert_eq!(manifest.package, None);
+    let workspace = manifest.workspace.unwrap();
+    assert_eq!(workspace.members, ["crates/*"]);
+    assert_eq!(workspace.exclude, ["old"]);
+    assert!(workspace.default_members.is_empty());
+    assert_eq!(workspace.resolver.as_deref(), Some("2"));
+    assert_eq!(
+        workspace.package.unwrap()["version"].as_str(),
+        Some("1.2.3")
+    );
+    assert_eq!(workspace.dependencies["serde"].version(), Some("1"));
+}
+
+#[test]
+fn errors() {
+    let err = "[dependencies]\nserde = 1\n"
+        .parse::<Manifest>()
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "invalid type: integer `1`, expected a version string like \"1.0\" or a dependency table for key `dependencies.serde` at line 2 column 9"
+    );
+
+    let err = "[package]\nname = 'a'\nversion.workspace = false\n"
+        .parse::<Manifest>()
+        .unwrap_err();
+    assert!(err
+        .to_string()
+        .starts_with("`workspace` cannot be false, leave the field out instead"));
+}
+
+#[test]
+fn dependency_shorthand() {
+    use std::collections::BTreeMap;
+    use toml::string_or_table::{Shorthand, StringOrTable};
+
+    let deps: BTreeMap<String, StringOrTable<DependencyDetail>> =
+        toml::from_str("a = '1'\nb = { version = '2', optional = true }\nc = { version = '3' }\n")
+            .unwrap();
+    assert_eq!(deps["a"].shorthand(), Some("1"));
+    assert!(deps["b"].optional);
+    assert_eq!(deps["b"].shorthand(), None);
+    assert_eq!(deps["c"].shorthand(), Some("3"));
+}
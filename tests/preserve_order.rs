@@ -0,0 +1,28 @@
+#![cfg(feature = "preserve_order")]
+
+#[macro_use]
+extern crate serde_derive;
+extern crate toml;
+
+#[derive(Serialize)]
+struct Package {
+    name: &'static str,
+    version: &'static str,
+    edition: &'static str,
+}
+
+#[test]
+fn struct_fields_keep_declaration_order() {
+    let package = Package {
+        name: "demo",
+        version: "0.1.0",
+        edition: "2018",
+    };
+    let value = toml::Value::try_from(&package).unwrap();
+    let keys = value.as_table().unwrap().keys().collect::<Vec<_>>();
+    assert_eq!(keys, ["name", "version", "edition"]);
+    assert_eq!(
+        toml::to_string(&value).unwrap(),
+        "name = \"demo\"\nversion = \"0.1.0\"\nedition = \"2018\"\n"
+    );
+}